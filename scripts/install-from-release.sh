@@ -49,8 +49,13 @@ fi
 install -m 0755 "$binary_path" "$install_dir/yoink"
 echo "Installed yoink to ${install_dir}/yoink"
 
-config_path="${HOME}/.yoinkignore"
-if [[ ! -f "$config_path" ]]; then
+config_dir="${XDG_CONFIG_HOME:-${HOME}/.config}/yoink"
+config_path="${config_dir}/ignore"
+legacy_config_path="${HOME}/.yoinkignore"
+if [[ -f "$legacy_config_path" ]]; then
+  echo "Keeping legacy config at ${legacy_config_path} (run: yoink config migrate)"
+elif [[ ! -f "$config_path" ]]; then
+  mkdir -p "$config_dir"
   cat > "$config_path" <<'EOF'
 include_hidden=false
 include_mounts=false
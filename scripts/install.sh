@@ -13,10 +13,15 @@ cd "$workspace_root"
 echo "Installing yoink with cargo..."
 cargo install --path . --locked --force
 
-config_path="$HOME/.yoinkignore"
+config_dir="${XDG_CONFIG_HOME:-$HOME/.config}/yoink"
+config_path="$config_dir/ignore"
+legacy_config_path="$HOME/.yoinkignore"
 default_config="$workspace_root/.yoinkignore"
 
-if [[ ! -f "$config_path" ]]; then
+if [[ -f "$legacy_config_path" ]]; then
+  echo "Keeping legacy config at $legacy_config_path (run: yoink config migrate)"
+elif [[ ! -f "$config_path" ]]; then
+  mkdir -p "$config_dir"
   cp "$default_config" "$config_path"
   echo "Installed default config at $config_path"
 else
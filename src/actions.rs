@@ -1,23 +1,320 @@
+use crate::archive::split_member;
+use crate::lang::language_for_extension;
+use crate::report::longest_backtick_run;
 use anyhow::{Context, Result};
-use std::path::{Path, PathBuf};
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
 use std::process::Command;
 use which::which;
 
-pub fn resolve_target_dir(cwd: &Path, selected_rel_path: &str) -> PathBuf {
-    let selected = cwd.join(selected_rel_path);
-    match selected.parent() {
-        Some(parent) => parent.to_path_buf(),
-        None => cwd.to_path_buf(),
+/// A `--root` or `ROOT` argument as the canonical directory to search; a
+/// missing path or a file is an error before anything runs.
+pub fn resolve_search_root(root: &Path) -> Result<PathBuf> {
+    let canonical = fs::canonicalize(root)
+        .with_context(|| format!("search root {} does not exist", root.display()))?;
+    if !canonical.is_dir() {
+        anyhow::bail!("search root {} is not a directory", root.display());
     }
+    Ok(canonical)
 }
 
-pub fn open_in_editor(editor_cmd: &str, cwd: &Path, selected_rel_path: &str) -> Result<()> {
-    which(editor_cmd)
-        .with_context(|| format!("editor command not found in PATH: {editor_cmd}"))?;
+/// Several search roots as their deepest common directory and each root's path
+/// under it. A root inside another is dropped, since the outer one covers it;
+/// with one root left, that root is returned with no paths.
+pub fn resolve_search_roots(roots: &[&Path]) -> Result<(PathBuf, Vec<PathBuf>)> {
+    let mut resolved = roots
+        .iter()
+        .map(|root| resolve_search_root(root))
+        .collect::<Result<Vec<_>>>()?;
+    resolved.sort();
+    resolved.dedup();
+    let outer: Vec<PathBuf> = resolved
+        .iter()
+        .filter(|root| {
+            !resolved
+                .iter()
+                .any(|other| other != *root && root.starts_with(other))
+        })
+        .cloned()
+        .collect();
+    let Some(first) = outer.first() else {
+        anyhow::bail!("no search root given");
+    };
+    if outer.len() == 1 {
+        return Ok((first.clone(), Vec::new()));
+    }
+
+    let mut common = first.clone();
+    while !outer.iter().all(|root| root.starts_with(&common)) {
+        common.pop();
+    }
+    let labels = outer
+        .iter()
+        .map(|root| root.strip_prefix(&common).unwrap_or(root).to_path_buf())
+        .collect();
+    Ok((common, labels))
+}
+
+/// The directory to `cd` into for a selection: the canonical form of the selected
+/// path's parent, so a symlinked file or directory leads to the directory holding
+/// the link. With a non-empty `allowed_roots`, targets outside every root are rejected.
+pub fn resolve_target_dir(
+    cwd: &Path,
+    selected_rel_path: &Path,
+    allowed_roots: &[PathBuf],
+) -> Result<PathBuf> {
+    // An archive member's container is the archive's directory.
+    let selected = match split_member(selected_rel_path) {
+        Some((archive, _)) => fold_dots(&cwd.join(archive)),
+        None => fold_dots(&cwd.join(selected_rel_path)),
+    };
+    let target = match selected.parent() {
+        Some(parent) => normalize_path(parent),
+        None => normalize_path(&selected),
+    };
+
+    if !allowed_roots.is_empty()
+        && !allowed_roots
+            .iter()
+            .any(|root| target.starts_with(normalize_path(root)))
+    {
+        anyhow::bail!(
+            "refusing to cd outside the search root: {} resolves to {}",
+            selected_rel_path.display(),
+            target.display()
+        );
+    }
+
+    Ok(target)
+}
+
+/// Canonicalizes the longest existing prefix of `path` (resolving symlinks) and
+/// folds `.` and `..` in the rest lexically, so missing paths still normalize.
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let lexical = fold_dots(path);
+    let mut missing = Vec::new();
+    let mut existing = lexical.as_path();
+    loop {
+        if let Ok(canonical) = fs::canonicalize(existing) {
+            return missing
+                .iter()
+                .rev()
+                .fold(canonical, |path, name| path.join(name));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return lexical,
+        }
+    }
+}
+
+/// `path` with `.` and `..` folded lexically, touching nothing on disk.
+fn fold_dots(path: &Path) -> PathBuf {
+    let mut lexical = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !lexical.pop() {
+                    lexical.push(component);
+                }
+            }
+            component => lexical.push(component),
+        }
+    }
+    lexical
+}
+
+pub fn create_file_from_query(cwd: &Path, query: &str) -> Result<PathBuf> {
+    let name = query.trim();
+    if name.is_empty() {
+        anyhow::bail!("cannot create a file from an empty query");
+    }
+
+    let rel = Path::new(name);
+    if rel.is_absolute() || rel.components().any(|c| matches!(c, Component::ParentDir)) {
+        anyhow::bail!("refusing to create a file outside the search root: {name}");
+    }
+
+    let full = cwd.join(rel);
+    if let Some(parent) = full.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create parent directory: {}", parent.display()))?;
+    }
+
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&full)
+        .with_context(|| format!("failed to create file: {}", full.display()))?;
+
+    Ok(full)
+}
+
+/// Trash commands tried in order; each takes the path to trash as its last argument.
+const TRASH_COMMANDS: &[(&str, &[&str])] =
+    &[("gio", &["trash"]), ("trash-put", &[]), ("trash", &[])];
+
+/// Deletes (or trashes) an entry picked in `yoink empty`, re-checking that it
+/// is still empty so a file written since the listing is never lost.
+pub fn remove_empty_path(cwd: &Path, rel: &Path, trash: bool) -> Result<PathBuf> {
+    if rel.as_os_str().is_empty()
+        || rel.is_absolute()
+        || rel.components().any(|c| matches!(c, Component::ParentDir))
+    {
+        anyhow::bail!(
+            "refusing to remove a path outside the search root: {}",
+            rel.display()
+        );
+    }
+
+    let full = cwd.join(rel);
+    let metadata = fs::symlink_metadata(&full)
+        .with_context(|| format!("failed to stat {}", full.display()))?;
+
+    if metadata.is_dir() {
+        let mut children =
+            fs::read_dir(&full).with_context(|| format!("failed to read {}", full.display()))?;
+        if children.next().is_some() {
+            anyhow::bail!("directory is no longer empty: {}", full.display());
+        }
+    } else if !metadata.is_file() || metadata.len() != 0 {
+        anyhow::bail!("file is no longer empty: {}", full.display());
+    }
+
+    if trash {
+        move_to_trash(&full)?;
+    } else if metadata.is_dir() {
+        fs::remove_dir(&full).with_context(|| format!("failed to remove {}", full.display()))?;
+    } else {
+        fs::remove_file(&full).with_context(|| format!("failed to remove {}", full.display()))?;
+    }
+
+    Ok(full)
+}
+
+fn move_to_trash(full: &Path) -> Result<()> {
+    let Some((command, args)) = TRASH_COMMANDS
+        .iter()
+        .find(|(command, _)| which(command).is_ok())
+    else {
+        anyhow::bail!("no trash command found in PATH (tried gio, trash-put, trash)");
+    };
+
+    let status = Command::new(command)
+        .args(*args)
+        .arg(full)
+        .status()
+        .with_context(|| format!("failed to launch trash command: {command}"))?;
+
+    if !status.success() {
+        anyhow::bail!("trash command exited unsuccessfully: {command}");
+    }
+    Ok(())
+}
+
+pub fn read_line_range(full: &Path, line: usize, context: usize) -> Result<(usize, Vec<String>)> {
+    let content = fs::read(full).with_context(|| format!("failed to read {}", full.display()))?;
+    let content = String::from_utf8_lossy(&content);
+    let lines: Vec<&str> = content.lines().collect();
+
+    if line == 0 || line > lines.len() {
+        anyhow::bail!("line {line} is out of range for {}", full.display());
+    }
+
+    let start = line.saturating_sub(context).max(1);
+    let end = (line + context).min(lines.len());
+    let range = lines[start - 1..end]
+        .iter()
+        .map(|text| text.to_string())
+        .collect();
+
+    Ok((start, range))
+}
+
+pub fn fence_language(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_string_lossy();
+    language_for_extension(&ext).map(|language| language.label)
+}
+
+pub fn fenced_block(
+    path_display: &str,
+    language: Option<&str>,
+    start: usize,
+    lines: &[String],
+) -> String {
+    let end = start + lines.len().saturating_sub(1);
+    let info = match language {
+        Some(language) => format!("{language} {path_display}:{start}-{end}"),
+        None => format!("{path_display}:{start}-{end}"),
+    };
+
+    // The fence outruns every backtick run inside, so a ``` line can't close it.
+    let longest_run = lines
+        .iter()
+        .map(|line| longest_backtick_run(line))
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+
+    let mut block = format!("{fence}{info}\n");
+    for line in lines {
+        block.push_str(line);
+        block.push('\n');
+    }
+    block.push_str(&fence);
+    block.push('\n');
+    block
+}
+
+/// Editor argv for a file, jumping to `line` (and `column` where the editor supports it).
+pub fn editor_args(
+    editor_cmd: &str,
+    full: &Path,
+    line: Option<usize>,
+    column: Option<usize>,
+) -> Vec<OsString> {
+    let file = full.as_os_str().to_os_string();
+    let Some(line) = line else {
+        return vec![file];
+    };
+
+    let mut located = file.clone();
+    match column {
+        Some(column) => located.push(format!(":{line}:{column}")),
+        None => located.push(format!(":{line}")),
+    }
+
+    match (editor_cmd, column) {
+        ("vim" | "nvim", Some(column)) => {
+            vec![
+                OsString::from(format!("+call cursor({line},{column})")),
+                file,
+            ]
+        }
+        ("vim" | "nvim" | "vi", _) => vec![OsString::from(format!("+{line}")), file],
+        ("code", _) => vec![OsString::from("--goto"), located],
+        ("subl", _) => vec![located],
+        _ => vec![file],
+    }
+}
+
+pub fn open_in_editor(
+    editor_cmd: &str,
+    cwd: &Path,
+    selected_rel_path: &Path,
+    line: Option<usize>,
+    column: Option<usize>,
+) -> Result<()> {
+    which(editor_cmd).with_context(|| format!("editor command not found in PATH: {editor_cmd}"))?;
 
     let full = cwd.join(selected_rel_path);
     let status = Command::new(editor_cmd)
-        .arg(full)
+        .args(editor_args(editor_cmd, &full, line, column))
         .status()
         .with_context(|| format!("failed to launch editor command: {editor_cmd}"))?;
 
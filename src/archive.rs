@@ -0,0 +1,141 @@
+#[cfg(feature = "archives")]
+use anyhow::Context;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "archives")]
+use std::process::Command;
+
+/// Between an archive's path and a member's name in `archive::member` rows.
+pub const MEMBER_SEPARATOR: &str = "::";
+
+/// An archive `list_members` can read, told by its extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+}
+
+impl ArchiveKind {
+    /// `.zip`, or `.tar`, `.tar.gz` and `.tgz`, in any case.
+    pub fn of(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_ascii_lowercase();
+        if name.ends_with(".zip") {
+            Some(ArchiveKind::Zip)
+        } else if [".tar", ".tar.gz", ".tgz"]
+            .iter()
+            .any(|ext| name.ends_with(ext))
+        {
+            Some(ArchiveKind::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+/// The file members of the archive at `full`, from `unzip -Z1` or `tar -tf`
+/// (which reads gzip on its own).
+#[cfg(feature = "archives")]
+pub fn list_members(full: &Path) -> Result<Vec<String>> {
+    let kind = ArchiveKind::of(full)
+        .with_context(|| format!("not a zip or tar archive: {}", full.display()))?;
+    let listing = match kind {
+        ArchiveKind::Zip => tool_output(Command::new("unzip").arg("-Z1").arg(full), "unzip", full)?,
+        ArchiveKind::Tar => tool_output(Command::new("tar").arg("-tf").arg(full), "tar", full)?,
+    };
+    Ok(parse_listing(&listing))
+}
+
+/// Reads `unzip -Z1` or `tar -tf` output: a name per line, with directory
+/// entries (ending in `/`) left out. Names are kept as the archive spells
+/// them, `./` included, so `extract_member` can find them again.
+pub fn parse_listing(listing: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(listing)
+        .lines()
+        .map(|name| name.trim_end_matches('\r'))
+        .filter(|name| !name.is_empty() && !name.ends_with('/'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// The bytes of `member` in the archive at `full`, from `unzip -p` or `tar -xOf`.
+#[cfg(feature = "archives")]
+pub fn extract_member(full: &Path, member: &str) -> Result<Vec<u8>> {
+    let kind = ArchiveKind::of(full)
+        .with_context(|| format!("not a zip or tar archive: {}", full.display()))?;
+    match kind {
+        // unzip reads member names as wildcards; brackets match each one literally.
+        ArchiveKind::Zip => tool_output(
+            Command::new("unzip")
+                .arg("-p")
+                .arg(full)
+                .arg(unzip_literal(member)),
+            "unzip",
+            full,
+        ),
+        ArchiveKind::Tar => tool_output(
+            Command::new("tar").arg("-xOf").arg(full).arg(member),
+            "tar",
+            full,
+        ),
+    }
+}
+
+#[cfg(feature = "archives")]
+fn unzip_literal(member: &str) -> String {
+    let mut literal = String::with_capacity(member.len());
+    for ch in member.chars() {
+        match ch {
+            '*' | '?' | '[' => {
+                literal.push('[');
+                literal.push(ch);
+                literal.push(']');
+            }
+            ch => literal.push(ch),
+        }
+    }
+    literal
+}
+
+#[cfg(not(feature = "archives"))]
+pub fn extract_member(_full: &Path, _member: &str) -> Result<Vec<u8>> {
+    anyhow::bail!("built without archive support")
+}
+
+/// The row path for `member` of `archive`: `archive::member`.
+pub fn member_path(archive: &Path, member: &str) -> PathBuf {
+    let mut path = archive.as_os_str().to_os_string();
+    path.push(MEMBER_SEPARATOR);
+    path.push(member);
+    PathBuf::from(path)
+}
+
+/// Splits an `archive::member` row path back into the archive, relative like
+/// the row, and the member name; `None` for a path no archive leads. The
+/// archive is the first prefix before a `::` that names one, so an archive
+/// whose own name holds `::` still splits where `member_path` joined it.
+pub fn split_member(rel: &Path) -> Option<(PathBuf, String)> {
+    let text = rel.to_str()?;
+    text.match_indices(MEMBER_SEPARATOR).find_map(|(index, _)| {
+        let archive = Path::new(&text[..index]);
+        ArchiveKind::of(archive)?;
+        Some((
+            archive.to_path_buf(),
+            text[index + MEMBER_SEPARATOR.len()..].to_string(),
+        ))
+    })
+}
+
+#[cfg(feature = "archives")]
+fn tool_output(command: &mut Command, tool: &str, full: &Path) -> Result<Vec<u8>> {
+    let output = command
+        .output()
+        .with_context(|| format!("failed to run {tool}"))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "{tool} failed on {}: {}",
+            full.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
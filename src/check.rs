@@ -0,0 +1,190 @@
+use anyhow::{bail, Result};
+use std::fmt;
+use std::path::Path;
+
+use crate::search::{load_settings_for, resolve_settings, SettingOverrides, YoinkSettings};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The combination can't work; yoink refuses to start.
+    Error,
+    /// Part of the combination has no effect; yoink starts anyway.
+    Warning,
+}
+
+/// One broken rule, reported against the fully merged settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettingsIssue {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for SettingsIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{label}: {} [{}]", self.message, self.rule)
+    }
+}
+
+struct Rule {
+    name: &'static str,
+    severity: Severity,
+    check: fn(&YoinkSettings) -> Option<String>,
+}
+
+const RULES: &[Rule] = &[
+    Rule {
+        name: "types-all-excluded",
+        severity: Severity::Error,
+        check: types_all_excluded,
+    },
+    Rule {
+        name: "types-overlap",
+        severity: Severity::Warning,
+        check: types_overlap,
+    },
+    Rule {
+        name: "symlinks-without-mounts",
+        severity: Severity::Warning,
+        check: symlinks_without_mounts,
+    },
+    Rule {
+        name: "same-match-colors",
+        severity: Severity::Warning,
+        check: same_match_colors,
+    },
+    Rule {
+        name: "auto-refresh-without-watch",
+        severity: Severity::Warning,
+        check: auto_refresh_without_watch,
+    },
+    Rule {
+        name: "watch-without-watch-support",
+        severity: Severity::Warning,
+        check: watch_without_watch,
+    },
+    Rule {
+        name: "tracked-without-git",
+        severity: Severity::Warning,
+        check: tracked_without_git,
+    },
+    Rule {
+        name: "archives-without-archive-support",
+        severity: Severity::Warning,
+        check: archives_without_archives,
+    },
+];
+
+fn types_all_excluded(settings: &YoinkSettings) -> Option<String> {
+    let types = settings.file_types;
+    (!types.is_empty() && types.intersection(settings.file_types_not) == types).then(|| {
+        format!(
+            "every type in types ({}) is also in types_not, so no file can match",
+            types.names().join(",")
+        )
+    })
+}
+
+fn types_overlap(settings: &YoinkSettings) -> Option<String> {
+    let shared = settings.file_types.intersection(settings.file_types_not);
+    // All-shared is the error above; only a partial overlap is merely redundant.
+    (!shared.is_empty() && shared != settings.file_types).then(|| {
+        format!(
+            "{} listed in both types and types_not; types_not wins",
+            shared.names().join(",")
+        )
+    })
+}
+
+fn symlinks_without_mounts(settings: &YoinkSettings) -> Option<String> {
+    (settings.include_symlinks && !settings.include_mounts).then(|| {
+        "include_symlinks=true with include_mounts=false still skips links onto other filesystems"
+            .to_string()
+    })
+}
+
+fn same_match_colors(settings: &YoinkSettings) -> Option<String> {
+    (settings.path_match_color == settings.content_match_color).then(|| {
+        format!(
+            "path_match_color and content_match_color are both {}; path and content matches look alike",
+            settings.path_match_color
+        )
+    })
+}
+
+fn auto_refresh_without_watch(settings: &YoinkSettings) -> Option<String> {
+    (!cfg!(feature = "watch") && settings.auto_refresh_secs.is_some())
+        .then(|| "auto_refresh_secs is ignored: built without watch support".to_string())
+}
+
+fn tracked_without_git(settings: &YoinkSettings) -> Option<String> {
+    (!cfg!(feature = "git") && settings.tracked_only)
+        .then(|| "tracked_only is ignored: built without git support".to_string())
+}
+
+fn archives_without_archives(settings: &YoinkSettings) -> Option<String> {
+    (!cfg!(feature = "archives") && settings.archives)
+        .then(|| "archives is ignored: built without archive support".to_string())
+}
+
+fn watch_without_watch(settings: &YoinkSettings) -> Option<String> {
+    (!cfg!(feature = "watch") && settings.watch)
+        .then(|| "watch is ignored: built without watch support".to_string())
+}
+
+/// Every rule `settings` breaks, errors before warnings, in table order.
+pub fn check_settings(settings: &YoinkSettings) -> Vec<SettingsIssue> {
+    let mut issues: Vec<SettingsIssue> = RULES
+        .iter()
+        .filter_map(|rule| {
+            (rule.check)(settings).map(|message| SettingsIssue {
+                rule: rule.name,
+                severity: rule.severity,
+                message,
+            })
+        })
+        .collect();
+    issues.sort_by_key(|issue| issue.severity != Severity::Error);
+    issues
+}
+
+/// Prints warnings to stderr and fails on the first error; run before any
+/// subprocess starts, so a broken config never reaches rg or fzf.
+pub fn enforce(settings: &YoinkSettings) -> Result<()> {
+    let issues = check_settings(settings);
+    if let Some(error) = issues
+        .iter()
+        .find(|issue| issue.severity == Severity::Error)
+    {
+        bail!("conflicting settings: {} [{}]", error.message, error.rule);
+    }
+    for issue in issues {
+        eprintln!("yoink: {issue}");
+    }
+    Ok(())
+}
+
+/// `yoink config check`: lists every issue; fails when any is an error.
+pub fn run_check(cwd: &Path, overrides: SettingOverrides) -> Result<()> {
+    let settings = resolve_settings(load_settings_for(cwd)?, &[overrides]);
+    let issues = check_settings(&settings);
+    if issues.is_empty() {
+        println!("ok: no conflicting settings");
+        return Ok(());
+    }
+    for issue in &issues {
+        println!("{issue}");
+    }
+    let errors = issues
+        .iter()
+        .filter(|issue| issue.severity == Severity::Error)
+        .count();
+    if errors > 0 {
+        bail!("{errors} conflicting setting(s)");
+    }
+    Ok(())
+}
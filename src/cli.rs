@@ -1,27 +1,478 @@
+use crate::complete::{parse_completion_kind, parse_shell, CompletionKind, Shell};
+use crate::lang::FileTypeSet;
+use crate::report::{parse_report_format, ReportFormat};
+use crate::search::{
+    parse_duration_setting, parse_sort_mode_setting, SearchScope, SettingOverrides, SortMode,
+};
+use crate::ui::{parse_session_toggle, SessionToggle};
 use clap::{Parser, Subcommand};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 #[derive(Debug, Parser)]
-#[command(name = "yoink", version, about = "TUI regex search using rg + fzf + bat")]
+#[command(
+    name = "yoink",
+    version,
+    about = "TUI regex search using rg + fzf + bat",
+    args_conflicts_with_subcommands = true
+)]
 pub struct Cli {
     #[arg(value_name = "SEARCH")]
     pub query: Option<String>,
 
+    #[arg(
+        value_name = "ROOT",
+        conflicts_with = "root",
+        help = "Search these directories instead of the current one"
+    )]
+    pub root_args: Vec<PathBuf>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "DIR",
+        help = "Search DIR instead of the current directory; repeatable"
+    )]
+    pub root: Vec<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        conflicts_with = "query",
+        help = "Start with the pattern saved as search.NAME in the config"
+    )]
+    pub saved: Option<String>,
+
+    #[arg(long, help = "Search text piped on stdin instead of files")]
+    pub stdin_content: bool,
+
+    #[arg(
+        long,
+        global = true,
+        overrides_with = "no_hidden",
+        help = "Include hidden paths this session"
+    )]
+    pub hidden: bool,
+
+    #[arg(
+        long,
+        global = true,
+        overrides_with = "hidden",
+        help = "Exclude hidden paths this session"
+    )]
+    pub no_hidden: bool,
+
+    #[arg(
+        long,
+        global = true,
+        overrides_with = "no_follow",
+        help = "Follow symlinks this session"
+    )]
+    pub follow: bool,
+
+    #[arg(
+        long,
+        global = true,
+        overrides_with = "follow",
+        help = "Don't follow symlinks this session"
+    )]
+    pub no_follow: bool,
+
+    #[arg(
+        long,
+        global = true,
+        overrides_with = "no_mounts",
+        help = "Descend into other filesystems this session"
+    )]
+    pub mounts: bool,
+
+    #[arg(
+        long,
+        global = true,
+        overrides_with = "mounts",
+        help = "Stay on the root's filesystem this session"
+    )]
+    pub no_mounts: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "MODE",
+        value_parser = parse_sort_arg,
+        help = "Sort by depth, alphabetical, modified, matches or size, or stream unsorted",
+    )]
+    pub sort: Option<SortMode>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "N",
+        help = "Only walk N directory levels below the root"
+    )]
+    pub max_depth: Option<usize>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "DURATION",
+        value_parser = parse_duration_arg,
+        help = "Only list and search files modified within DURATION (30m, 24h, 7d)",
+    )]
+    pub changed_within: Option<Duration>,
+
+    #[arg(
+        long = "type",
+        short = 't',
+        global = true,
+        value_name = "TYPE",
+        value_parser = parse_file_types_arg,
+        help = "Only list files of this rg type (rust, py, js, ...); repeatable",
+    )]
+    pub types: Vec<FileTypeSet>,
+
+    #[arg(
+        long = "type-not",
+        short = 'T',
+        global = true,
+        value_name = "TYPE",
+        value_parser = parse_file_types_arg,
+        help = "Leave out files of this rg type; repeatable",
+    )]
+    pub types_not: Vec<FileTypeSet>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Don't exclude build output, caches and vendored trees"
+    )]
+    pub no_smart_defaults: bool,
+
+    #[arg(
+        long,
+        global = true,
+        conflicts_with = "content_only",
+        help = "Match file names only; skip rg's content search"
+    )]
+    pub paths_only: bool,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Match file contents only; names don't match a query"
+    )]
+    pub content_only: bool,
+
+    #[arg(
+        long,
+        short = 'w',
+        global = true,
+        help = "Match the query only as whole words"
+    )]
+    pub word: bool,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Match the query against file names only, not whole paths"
+    )]
+    pub basename: bool,
+
+    #[arg(
+        long,
+        short = 'U',
+        global = true,
+        help = "Let content matches span lines (rg -U --multiline-dotall)"
+    )]
+    pub multiline: bool,
+
+    #[arg(
+        long = "a11y",
+        global = true,
+        help = "Screen-reader friendly rows: no color, no emoji, words for glyphs"
+    )]
+    pub accessibility: bool,
+
+    #[arg(
+        long,
+        short = 'y',
+        global = true,
+        help = "Skip the large-tree confirmation prompt"
+    )]
+    pub yes: bool,
+
+    #[arg(long, global = true, help = "List and search only files git tracks")]
+    pub tracked: bool,
+
+    #[arg(
+        long,
+        value_name = "REF",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "HEAD",
+        help = "List and search only files changed since REF (default HEAD), untracked ones included",
+    )]
+    pub changed: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "COMMITISH",
+        conflicts_with = "changed",
+        help = "Search the tree at COMMITISH with git grep instead of the work tree"
+    )]
+    pub rev: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Reload the results when files under the root change"
+    )]
+    pub watch: bool,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Reread the config files on every reload instead of the launch snapshot"
+    )]
+    pub fresh_settings: bool,
+
     #[command(subcommand)]
     pub internal: Option<InternalCommand>,
 }
 
+impl Cli {
+    /// The `--root DIR`s or the positional `ROOT`s, as typed.
+    pub fn search_roots(&self) -> Vec<&Path> {
+        self.root
+            .iter()
+            .chain(&self.root_args)
+            .map(PathBuf::as_path)
+            .collect()
+    }
+
+    pub fn overrides(&self) -> SettingOverrides {
+        SettingOverrides {
+            include_hidden: match (self.hidden, self.no_hidden) {
+                (true, _) => Some(true),
+                (_, true) => Some(false),
+                _ => None,
+            },
+            include_symlinks: match (self.follow, self.no_follow) {
+                (true, _) => Some(true),
+                (_, true) => Some(false),
+                _ => None,
+            },
+            include_mounts: match (self.mounts, self.no_mounts) {
+                (true, _) => Some(true),
+                (_, true) => Some(false),
+                _ => None,
+            },
+            sort_mode: self.sort,
+            max_depth: self.max_depth,
+            modified_within: self.changed_within,
+            smart_defaults: self.no_smart_defaults.then_some(false),
+            accessibility: self.accessibility.then_some(true),
+            watch: self.watch.then_some(true),
+            tracked_only: self.tracked.then_some(true),
+            search_scope: match (self.paths_only, self.content_only) {
+                (true, _) => Some(SearchScope::Paths),
+                (_, true) => Some(SearchScope::Content),
+                _ => None,
+            },
+            word_boundaries: self.word.then_some(true),
+            match_basename_only: self.basename.then_some(true),
+            multiline: self.multiline.then_some(true),
+            file_types: union_file_types(&self.types),
+            file_types_not: union_file_types(&self.types_not),
+        }
+    }
+}
+
+fn parse_sort_arg(value: &str) -> Result<SortMode, String> {
+    parse_sort_mode_setting(value).ok_or_else(|| {
+        format!("expected depth, alphabetical, modified, matches, size or stream, got {value}")
+    })
+}
+
+fn parse_duration_arg(value: &str) -> Result<Duration, String> {
+    parse_duration_setting(value)
+        .ok_or_else(|| format!("expected a duration like 30m, 24h or 7d, got {value}"))
+}
+
+fn union_file_types(sets: &[FileTypeSet]) -> Option<FileTypeSet> {
+    sets.iter().copied().reduce(FileTypeSet::union)
+}
+
+fn parse_file_types_arg(value: &str) -> Result<FileTypeSet, String> {
+    FileTypeSet::parse(value)
+}
+
+/// A line or column from an fzf `{3}`/`{4}` placeholder, which is empty on
+/// rows without one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FieldNumber(pub Option<usize>);
+
+pub fn parse_field_number_arg(value: &str) -> Result<FieldNumber, String> {
+    if value.trim().is_empty() {
+        return Ok(FieldNumber(None));
+    }
+    value
+        .trim()
+        .parse()
+        .map(|number| FieldNumber(Some(number)))
+        .map_err(|_| format!("expected a number or nothing, got {value}"))
+}
+
+fn parse_report_format_arg(value: &str) -> Result<ReportFormat, String> {
+    parse_report_format(value).ok_or_else(|| format!("expected md or html, got {value}"))
+}
+
+fn parse_session_toggle_arg(value: &str) -> Result<SessionToggle, String> {
+    parse_session_toggle(value).ok_or_else(|| format!("expected hidden or sort, got {value}"))
+}
+
+fn parse_shell_arg(value: &str) -> Result<Shell, String> {
+    parse_shell(value).ok_or_else(|| format!("expected bash, zsh or fish, got {value}"))
+}
+
+fn parse_completion_kind_arg(value: &str) -> Result<CompletionKind, String> {
+    parse_completion_kind(value)
+        .ok_or_else(|| format!("expected search, key, type or root, got {value}"))
+}
+
 #[derive(Debug, Subcommand)]
 pub enum InternalCommand {
+    /// Manage the yoink config files
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+    /// Manage saved per-root session preferences
+    Prefs {
+        #[command(subcommand)]
+        action: PrefsCommand,
+    },
+    /// Find empty files and directories, with delete and trash bindings
+    Empty {
+        #[arg(value_name = "SEARCH")]
+        query: Option<String>,
+    },
+    /// Compare which paths two queries match: only A, only B, and both
+    Diff {
+        query_a: String,
+        query_b: String,
+        #[arg(long, help = "Emit the comparison as JSON")]
+        json: bool,
+        #[arg(long, help = "Only count content matches, ignoring path matches")]
+        content_only: bool,
+    },
+    /// Run a search and write its matches as a Markdown or HTML report
+    Report {
+        query: String,
+        #[arg(
+            long,
+            value_name = "FORMAT",
+            default_value = "md",
+            value_parser = parse_report_format_arg,
+            help = "md or html",
+        )]
+        format: ReportFormat,
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Write the report to FILE instead of stdout"
+        )]
+        out: Option<PathBuf>,
+    },
+    /// List the saved searches from the config's `search.NAME = PATTERN` lines
+    Saved,
+    /// Print a bash, zsh or fish completion script
+    Completions {
+        #[arg(value_parser = parse_shell_arg)]
+        shell: Shell,
+    },
+    #[command(name = "__complete", hide = true)]
+    Complete {
+        #[arg(value_parser = parse_completion_kind_arg)]
+        kind: CompletionKind,
+        #[arg(default_value = "", allow_hyphen_values = true)]
+        prefix: String,
+    },
     #[command(name = "__search", hide = true)]
     Search {
         #[arg(default_value = "")]
         query: String,
+        #[arg(long)]
+        buffer: Option<PathBuf>,
+        #[arg(long, conflicts_with = "buffer")]
+        empty: bool,
+    },
+    #[command(name = "__remove", hide = true)]
+    Remove {
+        path: String,
+        #[arg(long)]
+        trash: bool,
+    },
+    #[command(name = "__edit-config", hide = true)]
+    EditConfig,
+    #[command(name = "__pill", hide = true)]
+    Pill {
+        #[arg(default_value = "")]
+        path: String,
+        #[arg(long)]
+        dir: bool,
+        #[arg(long, conflicts_with = "dir")]
+        ext: bool,
+        #[arg(long, conflicts_with_all = ["dir", "ext"])]
+        pop: bool,
+    },
+    #[command(name = "__toggle", hide = true)]
+    Toggle {
+        #[arg(value_parser = parse_session_toggle_arg)]
+        toggle: SessionToggle,
+    },
+    #[command(name = "__header", hide = true)]
+    Header,
+    #[command(name = "__jump", hide = true)]
+    Jump { n: usize },
+    #[command(name = "__occurrence", hide = true)]
+    Occurrence {
+        path: String,
+        #[arg(default_value = "")]
+        query: String,
+        #[arg(default_value = "", value_parser = parse_field_number_arg)]
+        line: FieldNumber,
+        #[arg(long)]
+        prev: bool,
     },
     #[command(name = "__preview", hide = true)]
     Preview {
         path: String,
         #[arg(default_value = "")]
         query: String,
-        line: Option<usize>,
+        #[arg(default_value = "", value_parser = parse_field_number_arg)]
+        line: FieldNumber,
+        #[arg(default_value = "", value_parser = parse_field_number_arg)]
+        column: FieldNumber,
     },
 }
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Copy ~/.yoinkignore to $XDG_CONFIG_HOME/yoink/ignore
+    Migrate,
+    /// Print the active ignore and suppression patterns and where each came from
+    Show,
+    /// Run a search and report how many paths each ignore glob excluded
+    Stats {
+        #[arg(default_value = "")]
+        query: String,
+    },
+    /// Report conflicting or redundant settings in the merged configuration
+    Check,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PrefsCommand {
+    /// Forget saved preferences for ROOT (default: the current directory)
+    Clear { root: Option<PathBuf> },
+}
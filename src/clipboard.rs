@@ -0,0 +1,56 @@
+#[cfg(feature = "clipboard")]
+use anyhow::Context;
+use anyhow::Result;
+#[cfg(feature = "clipboard")]
+use std::fs::OpenOptions;
+#[cfg(feature = "clipboard")]
+use std::io::Write;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub fn base64_encode(input: &[u8]) -> String {
+    let mut out = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let b0 = chunk[0] as u32;
+        let b1 = chunk.get(1).copied().unwrap_or(0) as u32;
+        let b2 = chunk.get(2).copied().unwrap_or(0) as u32;
+        let triple = (b0 << 16) | (b1 << 8) | b2;
+
+        out.push(BASE64_ALPHABET[(triple >> 18) as usize & 0x3f] as char);
+        out.push(BASE64_ALPHABET[(triple >> 12) as usize & 0x3f] as char);
+        if chunk.len() > 1 {
+            out.push(BASE64_ALPHABET[(triple >> 6) as usize & 0x3f] as char);
+        } else {
+            out.push('=');
+        }
+        if chunk.len() > 2 {
+            out.push(BASE64_ALPHABET[triple as usize & 0x3f] as char);
+        } else {
+            out.push('=');
+        }
+    }
+    out
+}
+
+pub fn osc52_sequence(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64_encode(text.as_bytes()))
+}
+
+#[cfg(feature = "clipboard")]
+pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    // Writing to the tty keeps the escape out of stdout, which the shell helper captures.
+    let mut tty = OpenOptions::new()
+        .write(true)
+        .open("/dev/tty")
+        .context("failed to open /dev/tty for clipboard copy")?;
+    tty.write_all(osc52_sequence(text).as_bytes())
+        .context("failed to write OSC52 clipboard sequence")?;
+    tty.flush()?;
+    Ok(())
+}
+
+#[cfg(not(feature = "clipboard"))]
+pub fn copy_to_clipboard(_text: &str) -> Result<()> {
+    anyhow::bail!("built without clipboard support")
+}
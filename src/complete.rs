@@ -0,0 +1,228 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::history::{history_path, parse_history};
+use crate::lang::file_type_names;
+use crate::prefs::{prefs_path, PrefsStore};
+use crate::search::SETTING_KEYS;
+
+/// What `__complete` enumerates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+    /// Past queries from the history file, newest first.
+    Search,
+    /// Setting names for the config files.
+    Key,
+    /// rg file type names for `--type` / `--type-not`.
+    Type,
+    /// Roots with saved session preferences, for `prefs clear`.
+    Root,
+}
+
+pub fn parse_completion_kind(value: &str) -> Option<CompletionKind> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "search" | "searches" => Some(CompletionKind::Search),
+        "key" | "keys" => Some(CompletionKind::Key),
+        "type" | "types" => Some(CompletionKind::Type),
+        "root" | "roots" => Some(CompletionKind::Root),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+pub fn parse_shell(value: &str) -> Option<Shell> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "bash" => Some(Shell::Bash),
+        "zsh" => Some(Shell::Zsh),
+        "fish" => Some(Shell::Fish),
+        _ => None,
+    }
+}
+
+/// History queries, newest first and without repeats; a missing file is empty.
+pub fn history_queries(path: &Path) -> Vec<String> {
+    let content = fs::read_to_string(path).unwrap_or_default();
+    let mut seen = HashSet::new();
+    parse_history(&content, usize::MAX)
+        .into_iter()
+        .rev()
+        .filter(|query| seen.insert(query.clone()))
+        .collect()
+}
+
+/// Roots in the prefs file; a missing or unreadable file is empty.
+pub fn saved_roots(path: &Path) -> Vec<String> {
+    PrefsStore::load(path)
+        .unwrap_or_default()
+        .roots
+        .keys()
+        .map(|root| root.display().to_string())
+        .collect()
+}
+
+/// Every name of `kind`, read from the same stores the features use.
+pub fn candidates(kind: CompletionKind) -> Vec<String> {
+    match kind {
+        CompletionKind::Search => history_path()
+            .map(|path| history_queries(&path))
+            .unwrap_or_default(),
+        CompletionKind::Key => SETTING_KEYS.iter().map(|key| key.to_string()).collect(),
+        CompletionKind::Type => file_type_names().map(str::to_string).collect(),
+        CompletionKind::Root => prefs_path()
+            .map(|path| saved_roots(&path))
+            .unwrap_or_default(),
+    }
+}
+
+pub fn filter_prefix(names: Vec<String>, prefix: &str) -> Vec<String> {
+    names
+        .into_iter()
+        .filter(|name| name.starts_with(prefix))
+        .collect()
+}
+
+/// `yoink __complete KIND PREFIX`: matching names, one per line.
+pub fn run_complete(kind: CompletionKind, prefix: &str) {
+    for name in filter_prefix(candidates(kind), prefix) {
+        println!("{name}");
+    }
+}
+
+const SUBCOMMANDS: &[&str] = &[
+    "config",
+    "prefs",
+    "empty",
+    "diff",
+    "report",
+    "saved",
+    "completions",
+];
+const CONFIG_SUBCOMMANDS: &[&str] = &["migrate", "show", "stats", "check"];
+const FLAGS: &[&str] = &[
+    "--stdin-content",
+    "--hidden",
+    "--no-hidden",
+    "--follow",
+    "--no-follow",
+    "--mounts",
+    "--no-mounts",
+    "--sort",
+    "--max-depth",
+    "--changed-within",
+    "--type",
+    "--type-not",
+    "--no-smart-defaults",
+    "--paths-only",
+    "--content-only",
+    "--word",
+    "--saved",
+    "--root",
+    "--basename",
+    "--multiline",
+    "--a11y",
+    "--yes",
+    "--fresh-settings",
+    "--watch",
+    "--tracked",
+    "--changed",
+    "--rev",
+];
+const SORT_MODES: &str = "depth alphabetical modified matches size stream";
+
+/// A completion script for `shell`; search queries, types and prefs roots are
+/// completed by calling back into `yoink __complete`.
+pub fn completion_script(shell: Shell) -> String {
+    let subcommands = SUBCOMMANDS.join(" ");
+    let config = CONFIG_SUBCOMMANDS.join(" ");
+    let flags = FLAGS.join(" ");
+    match shell {
+        Shell::Bash => format!(
+            r#"_yoink() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}" prev="${{COMP_WORDS[COMP_CWORD-1]}}"
+    local IFS=$'\n'
+    case "$prev" in
+        --type|-t|--type-not|-T)
+            COMPREPLY=($(yoink __complete type -- "$cur" 2>/dev/null)); return ;;
+        --sort)
+            COMPREPLY=($(IFS=' ' compgen -W "{SORT_MODES}" -- "$cur")); return ;;
+    esac
+    if [[ "${{COMP_WORDS[1]}}" == prefs && "${{COMP_WORDS[2]}}" == clear && $COMP_CWORD -eq 3 ]]; then
+        COMPREPLY=($(yoink __complete root -- "$cur" 2>/dev/null))
+    elif [[ "${{COMP_WORDS[1]}}" == config && $COMP_CWORD -eq 2 ]]; then
+        COMPREPLY=($(IFS=' ' compgen -W "{config}" -- "$cur"))
+    elif [[ "$cur" == -* ]]; then
+        COMPREPLY=($(IFS=' ' compgen -W "{flags}" -- "$cur"))
+    elif [[ $COMP_CWORD -eq 1 ]]; then
+        COMPREPLY=($(IFS=' ' compgen -W "{subcommands}" -- "$cur") $(yoink __complete search -- "$cur" 2>/dev/null))
+    fi
+}}
+complete -o default -F _yoink yoink
+"#
+        ),
+        Shell::Zsh => format!(
+            r#"#compdef yoink
+
+_yoink() {{
+    local cur="${{words[CURRENT]}}"
+    local -a matches
+    case "${{words[CURRENT-1]}}" in
+        --type|-t|--type-not|-T)
+            matches=(${{(f)"$(yoink __complete type -- "$cur" 2>/dev/null)"}}) ;;
+        --sort)
+            matches=({SORT_MODES}) ;;
+        *)
+            if [[ "${{words[2]}}" == prefs && "${{words[3]}}" == clear && $CURRENT -eq 4 ]]; then
+                matches=(${{(f)"$(yoink __complete root -- "$cur" 2>/dev/null)"}})
+            elif [[ "${{words[2]}}" == config && $CURRENT -eq 3 ]]; then
+                matches=({config})
+            elif [[ "$cur" == -* ]]; then
+                matches=({flags})
+            elif [[ $CURRENT -eq 2 ]]; then
+                matches=({subcommands} ${{(f)"$(yoink __complete search -- "$cur" 2>/dev/null)"}})
+            else
+                _files; return
+            fi ;;
+    esac
+    compadd -a matches
+}}
+
+compdef _yoink yoink
+"#
+        ),
+        Shell::Fish => {
+            let mut script = String::from("complete -c yoink -f\n");
+            script.push_str(&format!(
+                "complete -c yoink -n '__fish_use_subcommand' -a '{subcommands}'\n"
+            ));
+            script.push_str(
+                "complete -c yoink -n '__fish_use_subcommand' -a '(yoink __complete search -- (commandline -ct) 2>/dev/null)'\n",
+            );
+            script.push_str(&format!(
+                "complete -c yoink -n '__fish_seen_subcommand_from config' -a '{config}'\n"
+            ));
+            script.push_str(
+                "complete -c yoink -n '__fish_seen_subcommand_from clear' -a '(yoink __complete root -- (commandline -ct) 2>/dev/null)'\n",
+            );
+            script.push_str(&format!("complete -c yoink -l sort -x -a '{SORT_MODES}'\n"));
+            for (short, long) in [("t", "type"), ("T", "type-not")] {
+                script.push_str(&format!(
+                    "complete -c yoink -s {short} -l {long} -x -a '(yoink __complete type -- (commandline -ct) 2>/dev/null)'\n"
+                ));
+            }
+            for flag in FLAGS {
+                let long = flag.trim_start_matches('-');
+                if !["sort", "type", "type-not"].contains(&long) {
+                    script.push_str(&format!("complete -c yoink -l {long}\n"));
+                }
+            }
+            script
+        }
+    }
+}
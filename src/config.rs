@@ -0,0 +1,421 @@
+use crate::prefs::state_dir;
+use crate::search::{
+    collect_ignore_stats, load_settings_for, load_settings_from, reload_session_config,
+    resolve_settings, IgnorePatternStat, SettingOverrides, YoinkSettings, SMART_DEFAULTS_VERSION,
+};
+use crate::session::Session;
+use anyhow::{Context, Result};
+use std::collections::hash_map::DefaultHasher;
+use std::env;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::UNIX_EPOCH;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConfigKind {
+    /// `key=value` settings only (`config.toml`).
+    Settings,
+    /// Settings plus ignore globs (`ignore`, `.yoinkignore`).
+    Ignore,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ConfigSource {
+    pub path: PathBuf,
+    pub kind: ConfigKind,
+}
+
+impl ConfigSource {
+    pub fn ignore(path: PathBuf) -> Self {
+        ConfigSource {
+            path,
+            kind: ConfigKind::Ignore,
+        }
+    }
+
+    pub fn settings(path: PathBuf) -> Self {
+        ConfigSource {
+            path,
+            kind: ConfigKind::Settings,
+        }
+    }
+}
+
+pub fn xdg_config_dir() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("yoink"))
+}
+
+pub fn legacy_config_path() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".yoinkignore"))
+}
+
+pub fn xdg_ignore_path() -> Option<PathBuf> {
+    xdg_config_dir().map(|dir| dir.join("ignore"))
+}
+
+pub fn xdg_settings_path() -> Option<PathBuf> {
+    xdg_config_dir().map(|dir| dir.join("config.toml"))
+}
+
+/// Name of the per-project ignore file looked up in the search root.
+pub const LOCAL_IGNORE_NAME: &str = ".yoinkignore";
+
+/// The global ignore file: `YOINKIGNORE_PATH`, then the XDG location, then `~/.yoinkignore`.
+pub fn yoinkignore_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("YOINKIGNORE_PATH") {
+        return Some(PathBuf::from(path));
+    }
+
+    if let Some(path) = xdg_ignore_path().filter(|path| path.exists()) {
+        return Some(path);
+    }
+
+    legacy_config_path()
+}
+
+/// Ignore files layered for a search rooted at `root`: the global one, then the
+/// project's own `.yoinkignore` in `root`, whose settings and globs apply last.
+pub fn yoinkignore_paths(root: Option<&Path>) -> Vec<PathBuf> {
+    let global = yoinkignore_path();
+    let local = root
+        .map(|root| root.join(LOCAL_IGNORE_NAME))
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            !global
+                .as_deref()
+                .is_some_and(|global| same_file(global, path))
+        });
+    global.into_iter().chain(local).collect()
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Every config file in the order it is applied; `root` adds the project's `.yoinkignore`.
+pub fn config_sources(root: Option<&Path>) -> Vec<ConfigSource> {
+    let mut sources = Vec::new();
+
+    if env::var_os("YOINKIGNORE_PATH").is_none() {
+        if let Some(path) = xdg_settings_path().filter(|path| path.exists()) {
+            sources.push(ConfigSource::settings(path));
+        }
+    }
+
+    sources.extend(
+        yoinkignore_paths(root)
+            .into_iter()
+            .map(ConfigSource::ignore),
+    );
+    sources
+}
+
+/// A config file as a session captured it; `content` is `None` when it didn't exist.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SnapshotFile {
+    pub source: ConfigSource,
+    /// Modification time in nanoseconds since the epoch.
+    pub modified: Option<u64>,
+    pub content: Option<String>,
+}
+
+/// The config files a session parses its settings from, captured at launch
+/// (or by `alt-i`) so every reload sees the same config.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigSnapshot {
+    pub files: Vec<SnapshotFile>,
+}
+
+pub fn modified_stamp(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()?;
+    let nanos = modified.duration_since(UNIX_EPOCH).ok()?.as_nanos();
+    u64::try_from(nanos).ok()
+}
+
+impl ConfigSnapshot {
+    pub fn capture(sources: &[ConfigSource]) -> Result<Self> {
+        let mut files = Vec::with_capacity(sources.len());
+        for source in sources {
+            let path = &source.path;
+            let (modified, content) = if path.exists() {
+                let content = fs::read_to_string(path)
+                    .with_context(|| format!("failed to read {}", path.display()))?;
+                (modified_stamp(path), Some(content))
+            } else {
+                (None, None)
+            };
+            files.push(SnapshotFile {
+                source: source.clone(),
+                modified,
+                content,
+            });
+        }
+        Ok(ConfigSnapshot { files })
+    }
+
+    /// Identifies this capture for the session's settings cache, which is
+    /// only reused while the snapshot it was parsed from is unchanged.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.files.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Whether the files behind `sources` moved on since the capture: a file
+    /// appeared, disappeared, or has a different mtime.
+    pub fn is_stale(&self, sources: &[ConfigSource]) -> bool {
+        self.files.len() != sources.len()
+            || self.files.iter().zip(sources).any(|(file, source)| {
+                file.source != *source || file.modified != modified_stamp(&source.path)
+            })
+    }
+}
+
+pub fn using_legacy_config() -> bool {
+    env::var_os("YOINKIGNORE_PATH").is_none()
+        && xdg_ignore_path().is_some_and(|path| !path.exists())
+        && legacy_config_path().is_some_and(|path| path.exists())
+}
+
+pub fn legacy_config_notice() -> Option<String> {
+    if !using_legacy_config() {
+        return None;
+    }
+
+    let legacy = legacy_config_path()?;
+    let target = xdg_ignore_path()?;
+    Some(format!(
+        "yoink: reading legacy config {}; run `yoink config migrate` to move it to {}",
+        legacy.display(),
+        target.display()
+    ))
+}
+
+/// Records in the state directory that the legacy notice was shown.
+pub fn legacy_notice_marker() -> Option<PathBuf> {
+    Some(state_dir()?.join("legacy-config-notice"))
+}
+
+/// `legacy_config_notice` the first time it applies, leaving a marker so later
+/// launches stay quiet. A marker that can't be written only means it's shown again.
+pub fn take_legacy_config_notice() -> Option<String> {
+    let notice = legacy_config_notice()?;
+    let marker = legacy_notice_marker();
+    if marker.as_ref().is_some_and(|marker| marker.exists()) {
+        return None;
+    }
+    if let Some(marker) = marker {
+        let _ = marker
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(&marker, ""));
+    }
+    Some(notice)
+}
+
+pub fn migrate_config_file(legacy: &Path, target: &Path) -> Result<()> {
+    if !legacy.exists() {
+        anyhow::bail!("no legacy config to migrate at {}", legacy.display());
+    }
+
+    if target.exists() {
+        anyhow::bail!(
+            "refusing to overwrite existing config at {}",
+            target.display()
+        );
+    }
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create config directory {}", parent.display()))?;
+    }
+
+    // A byte-for-byte copy keeps comments and ordering intact.
+    fs::copy(legacy, target).with_context(|| {
+        format!(
+            "failed to copy {} to {}",
+            legacy.display(),
+            target.display()
+        )
+    })?;
+
+    Ok(())
+}
+
+pub fn configs_equivalent(a: &Path, b: &Path) -> Result<bool> {
+    let a_settings = load_settings_from(&[ConfigSource::ignore(a.to_path_buf())])?;
+    let b_settings = load_settings_from(&[ConfigSource::ignore(b.to_path_buf())])?;
+    Ok(a_settings == b_settings)
+}
+
+pub fn run_migrate() -> Result<()> {
+    let legacy = legacy_config_path().context("HOME is not set; cannot locate ~/.yoinkignore")?;
+    let target = xdg_ignore_path().context("cannot determine the XDG config directory")?;
+
+    migrate_config_file(&legacy, &target)?;
+    println!("copied {} to {}", legacy.display(), target.display());
+
+    if !configs_equivalent(&legacy, &target)? {
+        anyhow::bail!(
+            "migrated config at {} does not parse identically; keeping {}",
+            target.display(),
+            legacy.display()
+        );
+    }
+
+    let stdin = io::stdin();
+    if !stdin.is_terminal() {
+        println!(
+            "both files parse identically; remove {} when ready",
+            legacy.display()
+        );
+        return Ok(());
+    }
+
+    print!(
+        "both files parse identically; delete {}? [y/N] ",
+        legacy.display()
+    );
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    stdin.lock().read_line(&mut answer)?;
+
+    if matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes") {
+        fs::remove_file(&legacy)
+            .with_context(|| format!("failed to delete {}", legacy.display()))?;
+        println!("deleted {}", legacy.display());
+    }
+
+    Ok(())
+}
+
+pub fn format_ignore_stats(stats: &[IgnorePatternStat]) -> String {
+    let width = stats
+        .iter()
+        .map(|stat| stat.pattern.len())
+        .max()
+        .unwrap_or(0);
+    let mut out = String::new();
+
+    for stat in stats {
+        let origin = stat.origin.label();
+        let flag = if stat.hits == 0 { "  (no hits)" } else { "" };
+        out.push_str(&format!(
+            "{:>6}  {:<width$}  {origin}{flag}\n",
+            stat.hits, stat.pattern
+        ));
+    }
+
+    out
+}
+
+pub fn run_stats(cwd: &Path, query: &str) -> Result<()> {
+    let stats = collect_ignore_stats(query, cwd)?;
+    print!("{}", format_ignore_stats(&stats));
+    Ok(())
+}
+
+/// Active exclude and suppression patterns, each labeled with where it came from.
+pub fn format_config_show(settings: &YoinkSettings) -> String {
+    let mut out = if settings.smart_defaults {
+        format!("smart_defaults = true (set v{SMART_DEFAULTS_VERSION})\n")
+    } else {
+        "smart_defaults = false\n".to_string()
+    };
+
+    let excludes: Vec<(&str, String)> = settings
+        .globset
+        .patterns()
+        .iter()
+        .zip(settings.globset.origins())
+        .map(|(pattern, origin)| (pattern.as_str(), origin.label()))
+        .collect();
+    let suppressed: Vec<(&str, String)> = settings
+        .suppress_globs
+        .iter()
+        // Only smart defaults fill the suppression list so far.
+        .map(|pattern| (pattern.as_str(), "smart default".to_string()))
+        .collect();
+
+    let width = excludes
+        .iter()
+        .chain(&suppressed)
+        .map(|(pattern, _)| pattern.len())
+        .max()
+        .unwrap_or(0);
+
+    for (title, rows) in [
+        ("excluded", &excludes),
+        ("content not searched", &suppressed),
+    ] {
+        out.push_str(&format!("\n{title}:\n"));
+        if rows.is_empty() {
+            out.push_str("  (none)\n");
+        }
+        for (pattern, origin) in rows {
+            out.push_str(&format!("  {pattern:<width$}  {origin}\n"));
+        }
+    }
+    out
+}
+
+pub fn run_show(cwd: &Path, overrides: SettingOverrides) -> Result<()> {
+    let settings = resolve_settings(load_settings_for(cwd)?, &[overrides]);
+    print!("{}", format_config_show(&settings));
+    Ok(())
+}
+
+/// `$VISUAL`, then `$EDITOR`, then `vi`, split into program and arguments.
+pub fn editor_command(visual: Option<&str>, editor: Option<&str>) -> Vec<String> {
+    [visual, editor]
+        .into_iter()
+        .flatten()
+        .map(|value| {
+            value
+                .split_whitespace()
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        })
+        .find(|words| !words.is_empty())
+        .unwrap_or_else(|| vec!["vi".to_string()])
+}
+
+/// `alt-i`: opens the ignore file in the editor, then recaptures the session's
+/// config snapshot so the reload that follows sees the edit.
+pub fn run_edit_config() -> Result<()> {
+    let path = yoinkignore_path().context("HOME is not set; cannot locate the ignore file")?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create config directory {}", parent.display()))?;
+    }
+
+    let visual = env::var("VISUAL").ok();
+    let editor = env::var("EDITOR").ok();
+    let command = editor_command(visual.as_deref(), editor.as_deref());
+    let status = Command::new(&command[0])
+        .args(&command[1..])
+        .arg(&path)
+        .status()
+        .with_context(|| format!("failed to launch editor command: {}", command[0]))?;
+    if !status.success() {
+        anyhow::bail!("editor command exited unsuccessfully: {}", command[0]);
+    }
+
+    if let Some(session) = Session::from_env() {
+        let root = env::current_dir().ok();
+        reload_session_config(&session, &config_sources(root.as_deref()))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,173 @@
+use crate::notify::{completion_message, notify_if_slow, SystemBackend};
+pub use crate::search::MatchKind;
+use crate::search::{build_candidates, load_settings_for, Candidate};
+use crate::session::json_string;
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffEntry {
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub a: Option<MatchKind>,
+    pub b: Option<MatchKind>,
+}
+
+/// Paths matched by only the first query, only the second, or both; each sorted by path.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchDiff {
+    pub only_a: Vec<DiffEntry>,
+    pub only_b: Vec<DiffEntry>,
+    pub both: Vec<DiffEntry>,
+}
+
+/// Splits two candidate lists by path. A path- or content-match on either side
+/// counts as a match; with `content_only`, path-only matches are left out first.
+pub fn diff_candidates(a: &[Candidate], b: &[Candidate], content_only: bool) -> SearchDiff {
+    let kind = |candidate: &Candidate| {
+        if content_only && !candidate.content_match {
+            return None;
+        }
+        MatchKind::of(candidate)
+    };
+
+    let mut merged: BTreeMap<PathBuf, DiffEntry> = BTreeMap::new();
+    for (candidate, is_a) in a
+        .iter()
+        .map(|candidate| (candidate, true))
+        .chain(b.iter().map(|candidate| (candidate, false)))
+    {
+        let Some(kind) = kind(candidate) else {
+            continue;
+        };
+        let entry = merged
+            .entry(candidate.path.clone())
+            .or_insert_with(|| DiffEntry {
+                path: candidate.path.clone(),
+                is_dir: candidate.is_dir,
+                a: None,
+                b: None,
+            });
+        if is_a {
+            entry.a = Some(kind);
+        } else {
+            entry.b = Some(kind);
+        }
+    }
+
+    let mut diff = SearchDiff::default();
+    for entry in merged.into_values() {
+        match (entry.a, entry.b) {
+            (Some(_), Some(_)) => diff.both.push(entry),
+            (Some(_), None) => diff.only_a.push(entry),
+            _ => diff.only_b.push(entry),
+        }
+    }
+    diff
+}
+
+fn entry_label(entry: &DiffEntry) -> String {
+    let path = entry.path.to_string_lossy();
+    let path = if entry.is_dir {
+        format!("{path}/")
+    } else {
+        path.into_owned()
+    };
+    match (entry.a, entry.b) {
+        (Some(a), Some(b)) => format!("{path}  (A: {}, B: {})", a.as_str(), b.as_str()),
+        (Some(kind), None) | (None, Some(kind)) => format!("{path}  ({})", kind.as_str()),
+        (None, None) => path,
+    }
+}
+
+pub fn format_diff(diff: &SearchDiff, query_a: &str, query_b: &str) -> String {
+    let mut out = String::new();
+    for (title, entries) in [
+        (format!("only A ({query_a})"), &diff.only_a),
+        (format!("only B ({query_b})"), &diff.only_b),
+        ("both".to_string(), &diff.both),
+    ] {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&format!("{title}: {}\n", entries.len()));
+        for entry in entries {
+            out.push_str(&format!("  {}\n", entry_label(entry)));
+        }
+    }
+    out
+}
+
+fn json_kind(kind: Option<MatchKind>) -> String {
+    match kind {
+        Some(kind) => json_string(kind.as_str()),
+        None => "null".to_string(),
+    }
+}
+
+pub fn format_diff_json(diff: &SearchDiff, query_a: &str, query_b: &str) -> String {
+    let section = |entries: &[DiffEntry]| {
+        let items: Vec<String> = entries
+            .iter()
+            .map(|entry| {
+                format!(
+                    "{{\"path\":{},\"is_dir\":{},\"a\":{},\"b\":{}}}",
+                    json_string(&entry.path.to_string_lossy()),
+                    entry.is_dir,
+                    json_kind(entry.a),
+                    json_kind(entry.b)
+                )
+            })
+            .collect();
+        format!("[{}]", items.join(","))
+    };
+
+    format!(
+        "{{\"query_a\":{},\"query_b\":{},\"counts\":{{\"only_a\":{},\"only_b\":{},\"both\":{}}},\"only_a\":{},\"only_b\":{},\"both\":{}}}\n",
+        json_string(query_a),
+        json_string(query_b),
+        diff.only_a.len(),
+        diff.only_b.len(),
+        diff.both.len(),
+        section(&diff.only_a),
+        section(&diff.only_b),
+        section(&diff.both)
+    )
+}
+
+pub fn run_diff(
+    cwd: &Path,
+    query_a: &str,
+    query_b: &str,
+    content_only: bool,
+    json: bool,
+) -> Result<()> {
+    let started = Instant::now();
+    let a = build_candidates(query_a, cwd)?;
+    let b = build_candidates(query_b, cwd)?;
+    let diff = diff_candidates(&a, &b, content_only);
+    let elapsed = started.elapsed();
+
+    if json {
+        print!("{}", format_diff_json(&diff, query_a, query_b));
+    } else {
+        print!("{}", format_diff(&diff, query_a, query_b));
+    }
+
+    notify_if_slow(
+        &mut SystemBackend,
+        load_settings_for(cwd)?.notify_after_secs,
+        elapsed,
+        || {
+            let results = diff.only_a.len() + diff.only_b.len() + diff.both.len();
+            completion_message(
+                &format!("diff of {query_a:?} and {query_b:?}"),
+                results,
+                elapsed,
+            )
+        },
+    );
+    Ok(())
+}
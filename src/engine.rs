@@ -0,0 +1,156 @@
+use crate::query::QueryPattern;
+use crate::search::{
+    is_hidden_path, mark_multiline_snippets, search_root_dev, send_file, text_matches,
+    text_occurrences, walk_entries, ContentScan, Deadline, FileSink, GlobMatcher, OccurrenceBudget,
+    OccurrenceScan, YoinkSettings, BINARY_SNIFF_BYTES, DEADLINE_CHECK_ENTRIES,
+};
+use anyhow::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The content pass without rg, for `internal_engine`: the walk's files, or
+/// `--changed`'s, already pruned for hidden paths, ignore globs and
+/// gitignores, searched with the query's own regex through `text_matches`
+/// and `text_occurrences`. Files past `max_filesize`, binaries and files that
+/// can't be read are skipped rather than failing the search. Each matching
+/// file also goes to `sink` as soon as it's searched.
+pub(crate) fn scan_content_internal(
+    pattern: &QueryPattern,
+    cwd: &Path,
+    settings: &YoinkSettings,
+    budget: &OccurrenceBudget,
+    deadline: &Deadline,
+    sink: Option<&FileSink>,
+) -> Result<ContentScan> {
+    let files = match &settings.changed_files {
+        Some(changed) => changed.sorted_files(),
+        None => {
+            let root_dev = search_root_dev(cwd, settings)?;
+            let entries = walk_entries(cwd, settings, root_dev, None, deadline);
+            let mut files = linked_files(cwd, settings, &entries);
+            files.extend(
+                entries
+                    .into_iter()
+                    .filter(|(_, is_dir)| !is_dir)
+                    .map(|(rel, _)| rel),
+            );
+            files
+        }
+    };
+
+    // Listed by path only, as rg's content passes leave them out with `-g !GLOB`.
+    let suppressed = GlobMatcher::new(&settings.suppress_globs)?;
+    let mut content = ContentScan::default();
+    let mut total = 0;
+    for (index, rel) in files.into_iter().enumerate() {
+        if index % DEADLINE_CHECK_ENTRIES == 0 && deadline.passed() {
+            break;
+        }
+        // Matched on their extracted text by the preprocess pass instead.
+        if settings.preprocessor_for(&rel).is_some() {
+            continue;
+        }
+        // A glob without a slash matches the file name at any depth, as in rg.
+        if suppressed.is_match(&rel)
+            || rel
+                .file_name()
+                .is_some_and(|name| suppressed.is_match(name))
+        {
+            continue;
+        }
+        let Some(text) = searchable_text(&cwd.join(&rel), settings) else {
+            continue;
+        };
+        if !text_matches(&text, pattern) {
+            continue;
+        }
+        let mut file = OccurrenceScan::default();
+        if total < budget.total {
+            let per_file = budget.per_file.min(budget.total - total);
+            let (occurrences, truncated) = text_occurrences(
+                &text,
+                pattern,
+                per_file,
+                settings.max_columns,
+                settings.icons,
+            );
+            content.scan.truncated |= truncated;
+            total += occurrences.len();
+            content
+                .scan
+                .occurrences
+                .insert(rel.clone(), occurrences.clone());
+            file.occurrences.insert(rel.clone(), occurrences);
+        } else {
+            content.scan.truncated = true;
+            file.occurrences.insert(rel.clone(), Vec::new());
+        }
+        if let Some(sink) = sink {
+            send_file(sink, file, pattern, settings.icons);
+        }
+        content.files.insert(rel);
+    }
+    mark_multiline_snippets(&mut content.scan, pattern, settings.icons);
+    Ok(content)
+}
+
+/// The files rg's `--follow` also reaches through symlinked directories whose
+/// targets lie inside the root. The walk lists those targets at their real
+/// paths only, so each of their files is listed again under the link, where
+/// the globs and hidden setting apply to it as they would in rg. A link into
+/// its own ancestry would loop and is left alone, as rg leaves it.
+fn linked_files(cwd: &Path, settings: &YoinkSettings, entries: &[(PathBuf, bool)]) -> Vec<PathBuf> {
+    if !settings.include_symlinks {
+        return Vec::new();
+    }
+    let Ok(root) = fs::canonicalize(cwd) else {
+        return Vec::new();
+    };
+    let mut linked = Vec::new();
+    for (link, _) in entries.iter().filter(|(_, is_dir)| *is_dir) {
+        let full = cwd.join(link);
+        if !fs::symlink_metadata(&full).is_ok_and(|metadata| metadata.file_type().is_symlink()) {
+            continue;
+        }
+        let Some(target) = fs::canonicalize(&full)
+            .ok()
+            .and_then(|target| target.strip_prefix(&root).ok().map(Path::to_path_buf))
+        else {
+            continue;
+        };
+        if link.starts_with(&target) {
+            continue;
+        }
+        linked.extend(
+            entries
+                .iter()
+                .filter(|(_, is_dir)| !is_dir)
+                .filter_map(|(rel, _)| rel.strip_prefix(&target).ok())
+                .map(|rest| link.join(rest))
+                .filter(|rel| {
+                    !settings.globset.is_match(rel)
+                        && (settings.include_hidden || !is_hidden_path(rel))
+                }),
+        );
+    }
+    linked
+}
+
+/// A file's text for `scan_content_internal`, or `None` to skip it: not a
+/// regular file, past `max_filesize`, unreadable, or binary by the same sniff
+/// as `is_binary_file`.
+pub fn searchable_text(full: &Path, settings: &YoinkSettings) -> Option<String> {
+    let metadata = fs::metadata(full).ok()?;
+    if !metadata.is_file()
+        || settings
+            .max_filesize
+            .is_some_and(|limit| metadata.len() > limit)
+    {
+        return None;
+    }
+    let bytes = fs::read(full).ok()?;
+    if bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0) {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
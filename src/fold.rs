@@ -0,0 +1,180 @@
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnicodeFold {
+    Off,
+    /// Unicode lowercase plus the full folds `to_lowercase` skips (`ß`, ligatures).
+    Case,
+    /// Case folding plus accent stripping, so `uber` finds `Über`.
+    Accents,
+}
+
+pub fn parse_unicode_fold_setting(value: &str) -> Option<UnicodeFold> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "false" | "off" => Some(UnicodeFold::Off),
+        "case" => Some(UnicodeFold::Case),
+        "true" | "accents" => Some(UnicodeFold::Accents),
+        _ => None,
+    }
+}
+
+/// Folded text plus a map from folded byte offsets back to the original string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoldedText {
+    pub text: String,
+    // (folded start, original start, original end), one per original character
+    // that produced output; dropped combining marks extend the previous span.
+    spans: Vec<(usize, usize, usize)>,
+}
+
+impl FoldedText {
+    /// Maps a byte range in `text` to the smallest whole-character range of the original.
+    pub fn original_range(&self, folded: Range<usize>) -> Range<usize> {
+        if self.spans.is_empty() {
+            return 0..0;
+        }
+
+        let span_at = |offset: usize| {
+            let index = self.spans.partition_point(|span| span.0 <= offset);
+            self.spans[index.saturating_sub(1)]
+        };
+
+        let start = span_at(folded.start).1;
+        if folded.end <= folded.start {
+            return start..start;
+        }
+        start..span_at(folded.end - 1).2
+    }
+}
+
+pub fn fold_text(text: &str, mode: UnicodeFold) -> FoldedText {
+    let mut folded = String::with_capacity(text.len());
+    let mut spans: Vec<(usize, usize, usize)> = Vec::new();
+
+    for (offset, ch) in text.char_indices() {
+        let folded_start = folded.len();
+        push_folded(ch, mode, &mut folded);
+        let original_end = offset + ch.len_utf8();
+
+        if folded.len() > folded_start {
+            spans.push((folded_start, offset, original_end));
+        } else if let Some(previous) = spans.last_mut() {
+            previous.2 = original_end;
+        }
+    }
+
+    FoldedText {
+        text: folded,
+        spans,
+    }
+}
+
+/// Folds the literal parts of a regex query, leaving escapes and group flags intact.
+pub fn fold_query(query: &str, mode: UnicodeFold) -> String {
+    if mode == UnicodeFold::Off {
+        return query.to_string();
+    }
+
+    let mut out = String::with_capacity(query.len());
+    let mut chars = query.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => {
+                out.push(ch);
+                let Some(escaped) = chars.next() else {
+                    break;
+                };
+                out.push(escaped);
+                // `\p{Greek}` names are case-sensitive.
+                if matches!(escaped, 'p' | 'P') && chars.peek() == Some(&'{') {
+                    for class_ch in chars.by_ref() {
+                        out.push(class_ch);
+                        if class_ch == '}' {
+                            break;
+                        }
+                    }
+                }
+            }
+            '(' if chars.peek() == Some(&'?') => {
+                out.push(ch);
+                for flag_ch in chars.by_ref() {
+                    out.push(flag_ch);
+                    if matches!(flag_ch, ')' | ':' | '<' | '>') {
+                        break;
+                    }
+                }
+            }
+            ch => push_folded(ch, mode, &mut out),
+        }
+    }
+
+    out
+}
+
+fn push_folded(ch: char, mode: UnicodeFold, out: &mut String) {
+    if mode == UnicodeFold::Off {
+        out.push(ch);
+        return;
+    }
+
+    for lower in ch.to_lowercase() {
+        if let Some(expansion) = full_case_fold(lower) {
+            out.push_str(expansion);
+            continue;
+        }
+
+        if mode == UnicodeFold::Accents {
+            if is_combining_mark(lower) {
+                continue;
+            }
+            out.push(strip_accent(lower));
+        } else {
+            out.push(lower);
+        }
+    }
+}
+
+fn full_case_fold(ch: char) -> Option<&'static str> {
+    match ch {
+        'ß' => Some("ss"),
+        'ﬀ' => Some("ff"),
+        'ﬁ' => Some("fi"),
+        'ﬂ' => Some("fl"),
+        'ﬃ' => Some("ffi"),
+        'ﬄ' => Some("ffl"),
+        'ﬅ' | 'ﬆ' => Some("st"),
+        _ => None,
+    }
+}
+
+fn is_combining_mark(ch: char) -> bool {
+    matches!(ch, '\u{0300}'..='\u{036F}')
+}
+
+/// Base letter of a precomposed Latin character, following its NFKD decomposition.
+/// Letters without a decomposition (`ø`, `æ`, `đ`, dotless `ı`) are left alone.
+fn strip_accent(ch: char) -> char {
+    match ch {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => 'a',
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => 'c',
+        'ď' => 'd',
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => 'e',
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => 'g',
+        'ĥ' => 'h',
+        'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' => 'i',
+        'ĵ' => 'j',
+        'ķ' => 'k',
+        'ĺ' | 'ļ' | 'ľ' => 'l',
+        'ñ' | 'ń' | 'ņ' | 'ň' => 'n',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ō' | 'ŏ' | 'ő' => 'o',
+        'ŕ' | 'ŗ' | 'ř' => 'r',
+        'ś' | 'ŝ' | 'ş' | 'š' => 's',
+        'ţ' | 'ť' => 't',
+        'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => 'u',
+        'ŵ' => 'w',
+        'ý' | 'ÿ' | 'ŷ' => 'y',
+        'ź' | 'ż' | 'ž' => 'z',
+        ch => ch,
+    }
+}
@@ -0,0 +1,291 @@
+#[cfg(feature = "git")]
+use crate::query::QueryPattern;
+#[cfg(feature = "git")]
+use crate::search::{column_window, Occurrence, LONG_LINE_NOTE, LONG_LINE_WINDOW};
+use crate::search::{path_from_bytes, YoinkSettings};
+#[cfg(feature = "git")]
+use crate::term::sanitize_control;
+#[cfg(feature = "git")]
+use anyhow::Context;
+use anyhow::Result;
+use std::collections::HashSet;
+#[cfg(feature = "git")]
+use std::fs;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "git")]
+use std::process::Command;
+
+/// Files git reports under a root, relative to that root, plus every
+/// directory holding one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GitFiles {
+    files: HashSet<PathBuf>,
+    dirs: HashSet<PathBuf>,
+}
+
+impl GitFiles {
+    /// What git tracks under `root`, from `git ls-files`; an error, naming the
+    /// setting, when `root` isn't in a git work tree.
+    #[cfg(feature = "git")]
+    pub fn tracked(root: &Path) -> Result<Self> {
+        let listing = git_output(root, &["ls-files", "-z"])
+            .context("tracked_only (--tracked) needs a git repository")?;
+        Ok(GitFiles::parse(&listing))
+    }
+
+    /// Files under `root` that differ from `rev`, in the index or the work
+    /// tree, and untracked files that aren't ignored. Files deleted since
+    /// `rev` are in the diff but not on disk, so they're left out.
+    #[cfg(feature = "git")]
+    pub fn changed(root: &Path, rev: &str) -> Result<Self> {
+        let context =
+            || format!("--changed={rev} needs a git repository where {rev} names a commit");
+        let mut listing = git_output(
+            root,
+            &["diff", "--name-only", "--relative", "-z", rev, "--"],
+        )
+        .with_context(context)?;
+        listing.extend(
+            git_output(root, &["ls-files", "--others", "--exclude-standard", "-z"])
+                .with_context(context)?,
+        );
+
+        let mut changed = GitFiles::default();
+        for file in GitFiles::parse(&listing).files {
+            // A dangling symlink is still there to list.
+            if root.join(&file).symlink_metadata().is_ok() {
+                changed.insert(file);
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Every file in `rev`'s tree under `root`, from `git ls-tree`, for `--rev`
+    /// to list in place of the work tree.
+    #[cfg(feature = "git")]
+    pub fn at_revision(root: &Path, rev: &str) -> Result<Self> {
+        let listing = git_output(root, &["ls-tree", "-r", "--name-only", "-z", rev, "--"])
+            .with_context(|| revision_context(rev))?;
+        Ok(GitFiles::parse(&listing))
+    }
+
+    /// Reads `git ls-files -z` or `git diff --name-only -z` output: paths
+    /// unquoted and NUL-terminated.
+    pub fn parse(listing: &[u8]) -> Self {
+        let mut parsed = GitFiles::default();
+        for raw in listing
+            .split(|byte| *byte == 0)
+            .filter(|raw| !raw.is_empty())
+        {
+            parsed.insert(path_from_bytes(raw.to_vec()));
+        }
+        parsed
+    }
+
+    fn insert(&mut self, file: PathBuf) {
+        self.dirs.extend(
+            file.ancestors()
+                .skip(1)
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .map(Path::to_path_buf),
+        );
+        self.files.insert(file);
+    }
+
+    /// A listed file, or a directory with a listed file somewhere below it.
+    pub fn contains(&self, rel: &Path, is_dir: bool) -> bool {
+        if is_dir {
+            self.dirs.contains(rel)
+        } else {
+            self.files.contains(rel)
+        }
+    }
+
+    /// The files in path order, for rg to search in place of walking `.`.
+    pub fn sorted_files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = self.files.iter().cloned().collect();
+        files.sort();
+        files
+    }
+
+    /// The files and the directories holding them, flagged `true`, in path order.
+    pub fn sorted_entries(&self) -> Vec<(PathBuf, bool)> {
+        let mut entries: Vec<(PathBuf, bool)> = self
+            .files
+            .iter()
+            .map(|file| (file.clone(), false))
+            .chain(self.dirs.iter().map(|dir| (dir.clone(), true)))
+            .collect();
+        entries.sort();
+        entries
+    }
+}
+
+#[cfg(feature = "git")]
+fn revision_context(rev: &str) -> String {
+    format!("--rev={rev} needs a git repository where {rev} names a commit")
+}
+
+/// `rel`'s content at `rev`, from `git show rev:./rel` run in `root`; a
+/// directory comes back as git's listing of its entries.
+#[cfg(feature = "git")]
+pub fn show_blob(root: &Path, rev: &str, rel: &Path) -> Result<Vec<u8>> {
+    let spec = format!("{rev}:./{}", rel.to_string_lossy());
+    git_output(root, &["show", &spec]).with_context(|| revision_context(rev))
+}
+
+/// Writes `rev`'s copy of `rel` to a read-only file under a temporary
+/// directory kept per revision and returns that directory, so editor actions
+/// can open `dir.join(rel)` without touching the work tree. The copy is left
+/// in place for editors that return before the file is closed.
+#[cfg(feature = "git")]
+pub fn extract_blob(root: &Path, rev: &str, rel: &Path) -> Result<PathBuf> {
+    let content = show_blob(root, rev, rel)?;
+    let label: String = rev
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || ch == '.' || ch == '-' {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let dir = std::env::temp_dir().join(format!("yoink-rev-{label}"));
+    let dest = dir.join(rel);
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    // An earlier copy is read-only, so it's replaced rather than written over.
+    let _ = fs::remove_file(&dest);
+    fs::write(&dest, content).with_context(|| format!("failed to write {}", dest.display()))?;
+    let mut permissions = fs::metadata(&dest)?.permissions();
+    permissions.set_readonly(true);
+    fs::set_permissions(&dest, permissions)
+        .with_context(|| format!("failed to protect {}", dest.display()))?;
+    Ok(dir)
+}
+
+/// `git grep` for `pattern` over `rev`'s blobs under `root`: one
+/// `rev:path NUL line NUL column NUL text` line per matching line, binary
+/// blobs skipped. No match is empty output, not an error.
+#[cfg(feature = "git")]
+pub fn grep_revision(root: &Path, rev: &str, pattern: &QueryPattern) -> Result<Vec<u8>> {
+    let output = Command::new("git")
+        .args(["grep", "-n", "--column", "-z", "-I", "--no-color"])
+        .args(pattern.git_grep_args())
+        .args([rev, "--"])
+        .current_dir(root)
+        .output()
+        .context("failed to run git")?;
+    match output.status.code() {
+        Some(0) => Ok(output.stdout),
+        Some(1) if output.stderr.is_empty() => Ok(Vec::new()),
+        _ => Err(anyhow::anyhow!(
+            "git grep failed in {}: {}",
+            root.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+        .with_context(|| revision_context(rev)),
+    }
+}
+
+/// Parses one line of `grep_revision` output into the path, relative to the
+/// root, and its `Occurrence`. Past `max_columns` bytes (0 for no limit) the
+/// snippet is the stretch of the line around the match, `marker` at a cut end.
+/// Without `-z` the fields would be split on `:`, which paths may contain, so
+/// lines without the NULs are rejected.
+#[cfg(feature = "git")]
+pub fn parse_grep_line(
+    raw: &[u8],
+    rev: &str,
+    max_columns: usize,
+    marker: &str,
+) -> Option<(PathBuf, Occurrence)> {
+    let mut fields = raw.splitn(4, |byte| *byte == 0);
+    let path = fields
+        .next()?
+        .strip_prefix(rev.as_bytes())?
+        .strip_prefix(b":")?;
+    let line = std::str::from_utf8(fields.next()?)
+        .ok()?
+        .parse::<usize>()
+        .ok()?;
+    let column = std::str::from_utf8(fields.next()?)
+        .ok()?
+        .parse::<usize>()
+        .ok()?;
+    let text = fields.next()?;
+    let long_line = max_columns > 0 && text.len() > max_columns;
+    let snippet = if long_line {
+        column_window(text, column, LONG_LINE_WINDOW, marker)
+            .unwrap_or_else(|| LONG_LINE_NOTE.to_string())
+    } else {
+        sanitize_control(String::from_utf8_lossy(text).trim())
+    };
+    Some((
+        path_from_bytes(path.to_vec()),
+        Occurrence {
+            line,
+            column,
+            snippet,
+            long_line,
+            context: Vec::new(),
+        },
+    ))
+}
+
+/// `git ARGS` run in `root`; a failing git is an error carrying its message.
+#[cfg(feature = "git")]
+fn git_output(root: &Path, args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(root)
+        .output()
+        .context("failed to run git")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed in {}: {}",
+            args.join(" "),
+            root.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+/// The tracked files under `root` when `tracked_only` is on.
+#[cfg(feature = "git")]
+pub fn tracked_files(root: &Path, settings: &YoinkSettings) -> Result<Option<GitFiles>> {
+    if !settings.tracked_only {
+        return Ok(None);
+    }
+    GitFiles::tracked(root).map(Some)
+}
+
+/// A build without the `git` feature ignores `tracked_only`, which `check` warns about.
+#[cfg(not(feature = "git"))]
+pub fn tracked_files(_root: &Path, _settings: &YoinkSettings) -> Result<Option<GitFiles>> {
+    Ok(None)
+}
+
+/// The files changed since `--changed`'s ref under `root`, when one is set.
+#[cfg(feature = "git")]
+pub fn changed_files(root: &Path, settings: &YoinkSettings) -> Result<Option<GitFiles>> {
+    match settings.changed_ref.as_deref() {
+        Some(rev) => GitFiles::changed(root, rev).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// A build without the `git` feature ignores `--changed`; `main` warns.
+#[cfg(not(feature = "git"))]
+pub fn changed_files(_root: &Path, _settings: &YoinkSettings) -> Result<Option<GitFiles>> {
+    Ok(None)
+}
+
+#[cfg(not(feature = "git"))]
+pub fn extract_blob(_root: &Path, _rev: &str, _rel: &Path) -> Result<PathBuf> {
+    anyhow::bail!("built without git support")
+}
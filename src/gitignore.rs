@@ -0,0 +1,266 @@
+use crate::config::LOCAL_IGNORE_NAME;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// One rule from an ignore file, parallel to its glob in `IgnoreFile::set`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct IgnoreRule {
+    /// `!pattern`: re-includes what an earlier rule ignored.
+    negated: bool,
+    /// `pattern/`: only matches directories.
+    dir_only: bool,
+}
+
+/// A parsed `.gitignore` (or `.git/info/exclude`), matched against paths
+/// relative to the directory holding it.
+#[derive(Debug, Clone)]
+pub struct IgnoreFile {
+    set: GlobSet,
+    rules: Vec<IgnoreRule>,
+}
+
+impl Default for IgnoreFile {
+    fn default() -> Self {
+        IgnoreFile {
+            set: GlobSet::empty(),
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl IgnoreFile {
+    /// Lines that don't form a valid glob are skipped, as git skips them.
+    pub fn parse(content: &str) -> IgnoreFile {
+        let mut builder = GlobSetBuilder::new();
+        let mut rules = Vec::new();
+        for line in content.lines() {
+            let Some((glob, rule)) = parse_line(line) else {
+                continue;
+            };
+            let Ok(glob) = GlobBuilder::new(&glob).literal_separator(true).build() else {
+                continue;
+            };
+            builder.add(glob);
+            rules.push(rule);
+        }
+        match builder.build() {
+            Ok(set) => IgnoreFile { set, rules },
+            Err(_) => IgnoreFile::default(),
+        }
+    }
+
+    /// `Some(true)` when the last matching rule ignores `rel`, `Some(false)` when
+    /// it is a negation, `None` when no rule applies.
+    pub fn matched(&self, rel: &Path, is_dir: bool) -> Option<bool> {
+        self.set
+            .matches(rel)
+            .into_iter()
+            .filter(|&index| is_dir || !self.rules[index].dir_only)
+            .max()
+            .map(|index| !self.rules[index].negated)
+    }
+}
+
+/// A line as a glob relative to the ignore file's directory, or `None` for
+/// blanks and comments.
+fn parse_line(line: &str) -> Option<(String, IgnoreRule)> {
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    // Trailing spaces are dropped unless the last one is escaped.
+    let mut pattern = line.trim_end_matches(' ').to_string();
+    if pattern.ends_with('\\') && pattern.len() < line.len() {
+        pattern.pop();
+        pattern.push(' ');
+    }
+
+    // `!` negates; `\!` and `\#` are literal.
+    let negated = pattern.starts_with('!');
+    if negated || pattern.starts_with("\\!") || pattern.starts_with("\\#") {
+        pattern.remove(0);
+    }
+
+    let dir_only = pattern.ends_with('/');
+    let pattern = pattern.trim_end_matches('/');
+    if pattern.is_empty() {
+        return None;
+    }
+
+    // A slash anywhere but the end anchors the pattern to the file's directory;
+    // otherwise it matches a name at any depth.
+    let glob = if pattern.contains('/') {
+        pattern.trim_start_matches('/').to_string()
+    } else {
+        format!("**/{pattern}")
+    };
+    Some((glob, IgnoreRule { negated, dir_only }))
+}
+
+/// The ignore files governing a walk: `.git/info/exclude` plus every
+/// `.gitignore` from the repository root down, loaded as directories are reached.
+#[derive(Debug, Clone)]
+pub struct GitIgnore {
+    repo_root: PathBuf,
+    /// The search root relative to `repo_root`.
+    prefix: PathBuf,
+    exclude: IgnoreFile,
+    files: HashMap<PathBuf, Option<IgnoreFile>>,
+}
+
+impl GitIgnore {
+    /// `None` outside a git repository; like rg, ignore files only count inside one.
+    pub fn discover(root: &Path) -> Option<GitIgnore> {
+        let repo_root = root.ancestors().find(|dir| dir.join(".git").exists())?;
+        let prefix = root.strip_prefix(repo_root).ok()?.to_path_buf();
+        let exclude = fs::read_to_string(repo_root.join(".git/info/exclude"))
+            .map(|content| IgnoreFile::parse(&content))
+            .unwrap_or_default();
+        Some(GitIgnore {
+            repo_root: repo_root.to_path_buf(),
+            prefix,
+            exclude,
+            files: HashMap::new(),
+        })
+    }
+
+    /// Whether `rel` (relative to the search root) is ignored. Deeper
+    /// `.gitignore` files override shallower ones, and all of them override
+    /// `info/exclude`; within one file the last matching rule wins.
+    pub fn is_ignored(&mut self, rel: &Path, is_dir: bool) -> bool {
+        let path = self.prefix.join(rel);
+        let mut ignored = self.exclude.matched(&path, is_dir).unwrap_or(false);
+
+        let mut dirs: Vec<PathBuf> = path.ancestors().skip(1).map(Path::to_path_buf).collect();
+        dirs.reverse();
+        for dir in dirs {
+            let Ok(relative) = path.strip_prefix(&dir) else {
+                continue;
+            };
+            if let Some(verdict) = self
+                .file_for(&dir)
+                .and_then(|file| file.matched(relative, is_dir))
+            {
+                ignored = verdict;
+            }
+        }
+        ignored
+    }
+
+    fn file_for(&mut self, dir: &Path) -> Option<&IgnoreFile> {
+        let repo_root = &self.repo_root;
+        self.files
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| {
+                fs::read_to_string(repo_root.join(dir).join(".gitignore"))
+                    .ok()
+                    .map(|content| IgnoreFile::parse(&content))
+            })
+            .as_ref()
+    }
+}
+
+/// `.yoinkignore` files in directories below the search root, each read
+/// gitignore-style against the paths under its own directory. The root's own
+/// file is config (see `config_sources`), so it is left to the settings.
+#[derive(Debug, Clone)]
+pub struct NestedIgnores {
+    root: PathBuf,
+    files: HashMap<PathBuf, Option<IgnoreFile>>,
+}
+
+impl NestedIgnores {
+    pub fn new(root: &Path) -> NestedIgnores {
+        NestedIgnores {
+            root: root.to_path_buf(),
+            files: HashMap::new(),
+        }
+    }
+
+    /// Whether `rel` (relative to the search root) is ignored by a file in one
+    /// of its ancestors below the root; deeper files override shallower ones.
+    pub fn is_ignored(&mut self, rel: &Path, is_dir: bool) -> bool {
+        let mut dirs: Vec<PathBuf> = rel
+            .ancestors()
+            .skip(1)
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .map(Path::to_path_buf)
+            .collect();
+        dirs.reverse();
+
+        let mut ignored = false;
+        for dir in dirs {
+            let Ok(relative) = rel.strip_prefix(&dir) else {
+                continue;
+            };
+            if let Some(verdict) = self
+                .file_for(&dir)
+                .and_then(|file| file.matched(relative, is_dir))
+            {
+                ignored = verdict;
+            }
+        }
+        ignored
+    }
+
+    /// `is_ignored` for a path found without walking down to it, such as an rg
+    /// content match: an ignored directory above it hides it too.
+    pub fn hides(&mut self, rel: &Path) -> bool {
+        let parents: Vec<PathBuf> = rel
+            .ancestors()
+            .skip(1)
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .map(Path::to_path_buf)
+            .collect();
+        parents.iter().rev().any(|dir| self.is_ignored(dir, true)) || self.is_ignored(rel, false)
+    }
+
+    fn file_for(&mut self, dir: &Path) -> Option<&IgnoreFile> {
+        let root = &self.root;
+        self.files
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| {
+                let path = root.join(dir).join(LOCAL_IGNORE_NAME);
+                let content = fs::read_to_string(&path).ok()?;
+                let (globs, settings) = split_nested_settings(&content);
+                if !settings.is_empty() {
+                    eprintln!(
+                        "yoink: settings only apply in the root's {LOCAL_IGNORE_NAME}; ignoring {} in {}",
+                        settings.join(", "),
+                        path.display()
+                    );
+                }
+                Some(IgnoreFile::parse(&globs))
+            })
+            .as_ref()
+    }
+}
+
+/// Splits a nested `.yoinkignore` into its glob lines and the keys of any
+/// `key = value` setting lines, which only the root's file may hold.
+pub fn split_nested_settings(content: &str) -> (String, Vec<String>) {
+    let mut globs = String::new();
+    let mut settings = Vec::new();
+    for line in content.lines() {
+        let key = line
+            .split_once('=')
+            .map(|(key, _)| key.trim())
+            .filter(|key| {
+                !key.is_empty()
+                    && key
+                        .chars()
+                        .all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '.')
+            });
+        match key {
+            Some(key) => settings.push(key.to_string()),
+            None => {
+                globs.push_str(line);
+                globs.push('\n');
+            }
+        }
+    }
+    (globs, settings)
+}
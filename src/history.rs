@@ -0,0 +1,210 @@
+use crate::prefs::state_dir;
+use anyhow::{Context, Result};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Entries kept in the history file, unless `history_size` says otherwise.
+pub const DEFAULT_HISTORY_SIZE: usize = 1000;
+/// First fzf release with `--history` and `--history-size`.
+pub const FZF_HISTORY_VERSION: FzfVersion = FzfVersion(0, 10, 0);
+
+/// `$XDG_STATE_HOME/yoink/history`, defaulting to `~/.local/state/yoink/history`.
+pub fn history_path() -> Option<PathBuf> {
+    Some(state_dir()?.join("history"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FzfVersion(pub u32, pub u32, pub u32);
+
+/// Reads `fzf --version` output such as `0.44.1 (brew)` or `0.20.0 (c9f16b6)`.
+pub fn parse_fzf_version(output: &str) -> Option<FzfVersion> {
+    let version = output.split_whitespace().next()?;
+    let mut parts = version.split('.').map(|part| {
+        let digits = part
+            .find(|ch: char| !ch.is_ascii_digit())
+            .map_or(part, |end| &part[..end]);
+        digits.parse::<u32>().ok()
+    });
+    let major = parts.next()??;
+    let minor = parts.next()??;
+    let patch = parts.next().flatten().unwrap_or(0);
+    Some(FzfVersion(major, minor, patch))
+}
+
+/// The installed fzf's version, or `None` when it can't be run or read.
+pub fn fzf_version() -> Option<FzfVersion> {
+    let output = Command::new("fzf").arg("--version").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_fzf_version(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// fzf flags that point its query history at `path`. Empty when fzf is too
+/// old (or its version unknown) or history is turned off with `size` 0.
+///
+/// fzf moves through history on Ctrl-P/Ctrl-N, but Ctrl-N creates a file in
+/// yoink, so Alt-N steps forward instead.
+pub fn history_args(version: Option<FzfVersion>, path: &Path, size: usize) -> Vec<String> {
+    if size == 0 || version.is_none_or(|version| version < FZF_HISTORY_VERSION) {
+        return Vec::new();
+    }
+    vec![
+        format!("--history={}", path.display()),
+        format!("--history-size={size}"),
+        "--bind".to_string(),
+        "ctrl-p:previous-history,alt-n:next-history".to_string(),
+    ]
+}
+
+/// The history file is fzf's own format: one query per line, oldest first.
+/// Blank lines are dropped and only the newest `size` entries are kept.
+pub fn parse_history(content: &str, size: usize) -> Vec<String> {
+    let entries: Vec<String> = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(str::to_string)
+        .collect();
+    let skip = entries.len().saturating_sub(size);
+    entries.into_iter().skip(skip).collect()
+}
+
+pub fn render_history(entries: &[String]) -> String {
+    entries.iter().map(|entry| format!("{entry}\n")).collect()
+}
+
+/// The newest query in the history file.
+pub fn last_query(path: &Path) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+    parse_history(&content, 1).pop()
+}
+
+/// Creates the history file owner-only if it is missing and prunes it to
+/// `size` entries, so fzf only ever appends to a file yoink owns.
+pub fn prepare_history(path: &Path, size: usize) -> Result<()> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    fs::create_dir_all(dir)
+        .with_context(|| format!("failed to create state directory {}", dir.display()))?;
+
+    let mut options = OpenOptions::new();
+    options.append(true).create(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+        .open(path)
+        .with_context(|| format!("failed to create history file {}", path.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+            .with_context(|| format!("failed to restrict permissions of {}", path.display()))?;
+    }
+
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        // Queries fzf saved from non-UTF-8 input aren't ours to rewrite.
+        Err(error) if error.kind() == ErrorKind::InvalidData => return Ok(()),
+        Err(error) => {
+            return Err(error).with_context(|| format!("failed to read {}", path.display()))
+        }
+    };
+    let pruned = render_history(&parse_history(&content, size));
+    if pruned == content {
+        return Ok(());
+    }
+
+    // The temp file is created 0600, so the rename keeps the file private.
+    let mut temp = tempfile::Builder::new()
+        .prefix(".history-")
+        .tempfile_in(dir)
+        .context("failed to create history temp file")?;
+    temp.write_all(pruned.as_bytes())
+        .context("failed to write history")?;
+    temp.persist(path)
+        .with_context(|| format!("failed to replace {}", path.display()))?;
+    Ok(())
+}
+
+/// Moves `query` to the newest end of the history file, dropping earlier copies
+/// and the oldest entries past `size`. The file is opened for append and held
+/// under an exclusive lock while it is rewritten, so concurrent sessions don't
+/// lose each other's queries.
+pub fn record_query(path: &Path, query: &str, size: usize) -> Result<()> {
+    if size == 0 || query.trim().is_empty() || query.contains('\n') {
+        return Ok(());
+    }
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    fs::create_dir_all(dir)
+        .with_context(|| format!("failed to create state directory {}", dir.display()))?;
+
+    let mut options = OpenOptions::new();
+    options.read(true).append(true).create(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("failed to open history file {}", path.display()))?;
+    file.lock()
+        .with_context(|| format!("failed to lock {}", path.display()))?;
+
+    let mut content = String::new();
+    match file.read_to_string(&mut content) {
+        Ok(_) => {}
+        // Queries fzf saved from non-UTF-8 input aren't ours to rewrite.
+        Err(error) if error.kind() == ErrorKind::InvalidData => return Ok(()),
+        Err(error) => {
+            return Err(error).with_context(|| format!("failed to read {}", path.display()))
+        }
+    }
+    let mut entries = parse_history(&content, usize::MAX);
+    entries.retain(|entry| entry != query);
+    entries.push(query.to_string());
+    let rendered = render_history(&entries[entries.len().saturating_sub(size)..]);
+
+    // Appending keeps the common case (a new query, under the cap) a single write.
+    let appended = rendered
+        .strip_prefix(content.as_str())
+        .filter(|_| content.is_empty() || content.ends_with('\n'));
+    match appended {
+        Some(line) => file.write_all(line.as_bytes()),
+        None => file
+            .set_len(0)
+            .and_then(|()| file.write_all(rendered.as_bytes())),
+    }
+    .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// Records the session's final query; a failure is only reported.
+pub fn session_record_query(query: &str, size: usize) {
+    let Some(path) = history_path() else {
+        return;
+    };
+    if let Err(error) = record_query(&path, query, size) {
+        eprintln!("yoink: failed to save query history: {error:#}");
+    }
+}
+
+/// Prepares the history file and returns the fzf flags that use it; any
+/// failure only costs the session its history.
+pub fn session_history_args(size: usize) -> Vec<String> {
+    if size == 0 {
+        return Vec::new();
+    }
+    let Some(path) = history_path() else {
+        return Vec::new();
+    };
+    let args = history_args(fzf_version(), &path, size);
+    if args.is_empty() {
+        return args;
+    }
+    match prepare_history(&path, size) {
+        Ok(()) => args,
+        Err(error) => {
+            eprintln!("yoink: query history disabled: {error:#}");
+            Vec::new()
+        }
+    }
+}
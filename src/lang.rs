@@ -0,0 +1,356 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+pub const BADGE_WIDTH: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Language {
+    pub label: &'static str,
+    pub color: u8,
+    pub extensions: &'static [&'static str],
+    pub interpreters: &'static [&'static str],
+}
+
+const LANGUAGES: &[Language] = &[
+    Language {
+        label: "rs",
+        color: 31,
+        extensions: &["rs"],
+        interpreters: &[],
+    },
+    Language {
+        label: "py",
+        color: 33,
+        extensions: &["py", "pyi"],
+        interpreters: &["python", "python2", "python3"],
+    },
+    Language {
+        label: "js",
+        color: 33,
+        extensions: &["js", "mjs", "cjs", "jsx"],
+        interpreters: &["node"],
+    },
+    Language {
+        label: "ts",
+        color: 34,
+        extensions: &["ts", "tsx"],
+        interpreters: &["deno", "ts-node"],
+    },
+    Language {
+        label: "md",
+        color: 37,
+        extensions: &["md", "markdown"],
+        interpreters: &[],
+    },
+    Language {
+        label: "toml",
+        color: 35,
+        extensions: &["toml"],
+        interpreters: &[],
+    },
+    Language {
+        label: "json",
+        color: 32,
+        extensions: &["json"],
+        interpreters: &[],
+    },
+    Language {
+        label: "yaml",
+        color: 36,
+        extensions: &["yaml", "yml"],
+        interpreters: &[],
+    },
+    Language {
+        label: "sh",
+        color: 32,
+        extensions: &["sh", "bash", "zsh"],
+        interpreters: &["sh", "bash", "zsh", "dash"],
+    },
+    Language {
+        label: "rb",
+        color: 31,
+        extensions: &["rb"],
+        interpreters: &["ruby"],
+    },
+    Language {
+        label: "pl",
+        color: 36,
+        extensions: &["pl", "pm"],
+        interpreters: &["perl"],
+    },
+    Language {
+        label: "go",
+        color: 36,
+        extensions: &["go"],
+        interpreters: &[],
+    },
+    Language {
+        label: "c",
+        color: 34,
+        extensions: &["c", "h"],
+        interpreters: &[],
+    },
+    Language {
+        label: "cpp",
+        color: 34,
+        extensions: &["cc", "cpp", "cxx", "hpp", "hh"],
+        interpreters: &[],
+    },
+    Language {
+        label: "java",
+        color: 31,
+        extensions: &["java"],
+        interpreters: &[],
+    },
+    Language {
+        label: "html",
+        color: 35,
+        extensions: &["html", "htm"],
+        interpreters: &[],
+    },
+    Language {
+        label: "css",
+        color: 35,
+        extensions: &["css", "scss"],
+        interpreters: &[],
+    },
+    Language {
+        label: "sql",
+        color: 33,
+        extensions: &["sql"],
+        interpreters: &[],
+    },
+];
+
+pub fn language_for_extension(ext: &str) -> Option<&'static Language> {
+    let ext = ext.to_ascii_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|language| language.extensions.contains(&ext.as_str()))
+}
+
+pub fn language_for_shebang(first_line: &str) -> Option<&'static Language> {
+    let rest = first_line.strip_prefix("#!")?;
+    let mut words = rest.split_whitespace();
+    let program = words.next()?;
+    let program_name = Path::new(program)
+        .file_name()?
+        .to_string_lossy()
+        .to_string();
+
+    let interpreter = if program_name == "env" {
+        words.find(|word| !word.starts_with('-'))?.to_string()
+    } else {
+        program_name
+    };
+
+    LANGUAGES
+        .iter()
+        .find(|language| language.interpreters.contains(&interpreter.as_str()))
+}
+
+pub fn detect_language(full_path: &Path) -> Option<&'static Language> {
+    if let Some(ext) = full_path.extension() {
+        return language_for_extension(&ext.to_string_lossy());
+    }
+
+    let file = File::open(full_path).ok()?;
+    let mut bytes = Vec::new();
+    BufReader::new(file)
+        .take(256)
+        .read_until(b'\n', &mut bytes)
+        .ok()?;
+    language_for_shebang(String::from_utf8_lossy(&bytes).trim_end())
+}
+
+pub fn language_badge(language: Option<&Language>) -> String {
+    match language {
+        Some(language) => format!(
+            "\x1b[2;{}m{:<width$}\x1b[0m",
+            language.color,
+            language.label,
+            width = BADGE_WIDTH
+        ),
+        None => " ".repeat(BADGE_WIDTH),
+    }
+}
+
+/// One of rg's built-in `--type` names, with the file patterns it covers: `*.ext`
+/// or an exact file name. rg keeps its own definitions for content search; these
+/// mirror them for the path walk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileType {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    pub patterns: &'static [&'static str],
+}
+
+const FILE_TYPES: &[FileType] = &[
+    FileType {
+        name: "rust",
+        aliases: &[],
+        patterns: &["*.rs"],
+    },
+    FileType {
+        name: "py",
+        aliases: &[],
+        patterns: &["*.py", "*.pyi"],
+    },
+    FileType {
+        name: "js",
+        aliases: &[],
+        patterns: &["*.js", "*.jsx", "*.mjs", "*.cjs", "*.vue"],
+    },
+    FileType {
+        name: "ts",
+        aliases: &[],
+        patterns: &["*.ts", "*.tsx", "*.cts", "*.mts"],
+    },
+    FileType {
+        name: "md",
+        aliases: &["markdown"],
+        patterns: &["*.md", "*.markdown", "*.mdx", "*.mkd", "*.mkdn"],
+    },
+    FileType {
+        name: "toml",
+        aliases: &[],
+        patterns: &["*.toml", "Cargo.lock"],
+    },
+    FileType {
+        name: "json",
+        aliases: &[],
+        patterns: &["*.json", "composer.lock"],
+    },
+    FileType {
+        name: "yaml",
+        aliases: &[],
+        patterns: &["*.yaml", "*.yml"],
+    },
+    FileType {
+        name: "sh",
+        aliases: &[],
+        patterns: &["*.sh", "*.bash", "*.zsh", ".bashrc", ".zshrc", ".profile"],
+    },
+    FileType {
+        name: "go",
+        aliases: &[],
+        patterns: &["*.go"],
+    },
+    FileType {
+        name: "c",
+        aliases: &[],
+        patterns: &["*.c", "*.h"],
+    },
+    FileType {
+        name: "cpp",
+        aliases: &[],
+        patterns: &["*.cc", "*.cpp", "*.cxx", "*.hpp", "*.hh", "*.hxx"],
+    },
+    FileType {
+        name: "java",
+        aliases: &[],
+        patterns: &["*.java"],
+    },
+    FileType {
+        name: "ruby",
+        aliases: &[],
+        patterns: &["*.rb", "Gemfile", "Rakefile"],
+    },
+    FileType {
+        name: "html",
+        aliases: &[],
+        patterns: &["*.html", "*.htm"],
+    },
+    FileType {
+        name: "css",
+        aliases: &[],
+        patterns: &["*.css", "*.scss"],
+    },
+    FileType {
+        name: "sql",
+        aliases: &[],
+        patterns: &["*.sql"],
+    },
+];
+
+impl FileType {
+    pub fn matches(&self, path: &Path) -> bool {
+        let Some(name) = path.file_name().map(|name| name.to_string_lossy()) else {
+            return false;
+        };
+        self.patterns
+            .iter()
+            .any(|pattern| match pattern.strip_prefix("*.") {
+                Some(ext) => path.extension().is_some_and(|actual| actual == ext),
+                None => name == *pattern,
+            })
+    }
+}
+
+pub fn file_type(name: &str) -> Option<&'static FileType> {
+    let name = name.trim().to_ascii_lowercase();
+    FILE_TYPES
+        .iter()
+        .find(|file_type| file_type.name == name || file_type.aliases.contains(&name.as_str()))
+}
+
+/// Every known rg type name, without aliases.
+pub fn file_type_names() -> impl Iterator<Item = &'static str> {
+    FILE_TYPES.iter().map(|file_type| file_type.name)
+}
+
+/// A set of `FILE_TYPES` entries, small enough to travel in `Copy` override layers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FileTypeSet(u32);
+
+impl FileTypeSet {
+    /// Reads a comma-separated list such as `rust,py`; an unknown name is an error.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let mut set = FileTypeSet::default();
+        for name in value
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            let index = FILE_TYPES
+                .iter()
+                .position(|known| Some(known) == file_type(name))
+                .ok_or_else(|| {
+                    let known: Vec<&str> = FILE_TYPES.iter().map(|known| known.name).collect();
+                    format!("unknown file type {name} (known: {})", known.join(", "))
+                })?;
+            set.0 |= 1 << index;
+        }
+        Ok(set)
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn union(self, other: FileTypeSet) -> FileTypeSet {
+        FileTypeSet(self.0 | other.0)
+    }
+
+    pub fn intersection(self, other: FileTypeSet) -> FileTypeSet {
+        FileTypeSet(self.0 & other.0)
+    }
+
+    pub fn types(self) -> impl Iterator<Item = &'static FileType> {
+        FILE_TYPES
+            .iter()
+            .enumerate()
+            .filter(move |(index, _)| self.0 & (1 << index) != 0)
+            .map(|(_, file_type)| file_type)
+    }
+
+    /// rg type names, as `--type` takes them.
+    pub fn names(self) -> Vec<&'static str> {
+        self.types().map(|file_type| file_type.name).collect()
+    }
+
+    pub fn matches(self, path: &Path) -> bool {
+        self.types().any(|file_type| file_type.matches(path))
+    }
+}
@@ -1,4 +1,30 @@
 pub mod actions;
+pub mod archive;
+pub mod check;
 pub mod cli;
+pub mod clipboard;
+pub mod complete;
+pub mod config;
+pub mod diff;
+pub mod engine;
+pub mod fold;
+pub mod git;
+pub mod gitignore;
+pub mod history;
+pub mod lang;
+pub mod notify;
+#[cfg(feature = "builtin-ui")]
+pub mod picker;
+pub mod pills;
+pub mod preflight;
+pub mod prefs;
+pub mod query;
+pub mod recent;
+#[cfg(feature = "watch")]
+pub mod refresh;
+pub mod report;
+pub mod rgjson;
 pub mod search;
+pub mod session;
+pub mod term;
 pub mod ui;
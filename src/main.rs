@@ -1,49 +1,473 @@
-mod actions;
-mod cli;
-mod search;
-mod ui;
-
 use anyhow::{Context, Result};
 use clap::Parser;
-use cli::{Cli, InternalCommand};
-use search::{build_search_entries, format_search_entries};
 use std::env;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 use which::which;
+use yoink::actions;
+use yoink::check;
+use yoink::cli::{Cli, ConfigCommand, InternalCommand, PrefsCommand};
+use yoink::complete;
+use yoink::config;
+use yoink::diff;
+use yoink::notify::{completion_message, notify_if_slow, SystemBackend, WATCH_CYCLE_ENV};
+use yoink::pills::pill_header;
+use yoink::preflight::{self, PreflightChoice};
+use yoink::prefs;
+use yoink::report;
+use yoink::search::{
+    build_buffer_entries, build_empty_entries, build_search_entries_with_stats, decode_path_field,
+    for_each_search_entry, format_saved_searches, format_search_entries,
+    format_search_entries_capped, jump_action, load_settings, load_settings_for, number_file_rows,
+    resolve_settings, root_gone_entry, root_gone_message, root_unavailable, saved_search,
+    stats_header, timing_header, FileRowNumbers, SearchEntry, SearchStats, SettingOverrides,
+    SortMode, YoinkSettings,
+};
+use yoink::session::Session;
+use yoink::term;
+use yoink::ui;
 
 fn ensure_dependency(binary: &str) -> Result<()> {
     which(binary).with_context(|| format!("required dependency not found in PATH: {binary}"))?;
     Ok(())
 }
 
+/// fzf, unless the built-in picker can stand in for it.
+fn ensure_fzf() -> Result<()> {
+    #[cfg(not(feature = "builtin-ui"))]
+    ensure_dependency("fzf")?;
+    Ok(())
+}
+
+/// rg, unless the root's settings search content with the internal engine.
+fn ensure_content_engine(cwd: &Path) -> Result<()> {
+    if load_settings_for(cwd)?.internal_engine() {
+        return Ok(());
+    }
+    ensure_dependency("rg")
+}
+
 fn run() -> Result<()> {
     let cli = Cli::parse();
-    let cwd = env::current_dir().context("failed to read current working directory")?;
+    let search_roots = cli.search_roots();
+    let (cwd, roots) = if search_roots.is_empty() {
+        let cwd = match env::current_dir() {
+            Ok(cwd) => cwd,
+            // A reload whose root was deleted mid-session can't read its cwd; the session still knows it.
+            Err(error) => Session::from_env()
+                .and_then(|session| session.root())
+                .ok_or(error)
+                .context("failed to read current working directory")?,
+        };
+        (cwd, Vec::new())
+    } else {
+        actions::resolve_search_roots(&search_roots)?
+    };
+    // Reloads and previews find the roots in the session, under its common root.
+    if !roots.is_empty() && (cli.internal.is_some() || cli.stdin_content) {
+        anyhow::bail!("several search roots are only supported by the interactive search");
+    }
+    if cli.changed.is_some() && (cli.internal.is_some() || cli.stdin_content) {
+        anyhow::bail!("--changed is only supported by the interactive search");
+    }
+    #[cfg(not(feature = "git"))]
+    if cli.changed.is_some() {
+        eprintln!("yoink: --changed is ignored: built without git support");
+    }
+    if cli.rev.is_some() && (cli.internal.is_some() || cli.stdin_content) {
+        anyhow::bail!("--rev is only supported by the interactive search");
+    }
+    #[cfg(not(feature = "git"))]
+    if cli.rev.is_some() {
+        eprintln!("yoink: --rev is ignored: built without git support");
+    }
+    let overrides = cli.overrides();
 
     match cli.internal {
-        Some(InternalCommand::Search { query }) => {
-            ensure_dependency("rg")?;
-            let entries = build_search_entries(&query, &cwd)?;
-            print!("{}", format_search_entries(&entries));
+        Some(InternalCommand::Config {
+            action: ConfigCommand::Migrate,
+        }) => {
+            return config::run_migrate();
+        }
+        Some(InternalCommand::Config {
+            action: ConfigCommand::Show,
+        }) => {
+            return config::run_show(&cwd, overrides);
+        }
+        Some(InternalCommand::Config {
+            action: ConfigCommand::Stats { query },
+        }) => {
+            if !query.is_empty() {
+                ensure_content_engine(&cwd)?;
+            }
+            return config::run_stats(&cwd, &query);
+        }
+        Some(InternalCommand::Config {
+            action: ConfigCommand::Check,
+        }) => {
+            return check::run_check(&cwd, overrides);
+        }
+        Some(InternalCommand::Saved) => {
+            print!("{}", format_saved_searches(&load_settings_for(&cwd)?));
+            return Ok(());
+        }
+        Some(InternalCommand::Completions { shell }) => {
+            print!("{}", complete::completion_script(shell));
             return Ok(());
         }
-        Some(InternalCommand::Preview { path, query, line }) => {
+        Some(InternalCommand::Complete { kind, prefix }) => {
+            complete::run_complete(kind, &prefix);
+            return Ok(());
+        }
+        Some(InternalCommand::Prefs {
+            action: PrefsCommand::Clear { root },
+        }) => {
+            return prefs::run_clear(&cwd, root.as_deref());
+        }
+        Some(InternalCommand::Diff {
+            query_a,
+            query_b,
+            json,
+            content_only,
+        }) => {
+            ensure_content_engine(&cwd)?;
+            validate_settings(&cwd, overrides)?;
+            return diff::run_diff(&cwd, &query_a, &query_b, content_only, json);
+        }
+        Some(InternalCommand::Report { query, format, out }) => {
+            ensure_content_engine(&cwd)?;
+            validate_settings(&cwd, overrides)?;
+            return report::run_report(&cwd, &query, format, out.as_deref());
+        }
+        Some(InternalCommand::Empty { query }) => {
+            ensure_fzf()?;
             ensure_dependency("bat")?;
-            ensure_dependency("rg")?;
-            return ui::run_preview(&cwd, &path, &query, line);
+
+            let Some(overrides) = preflight(&cwd, overrides, cli.yes)? else {
+                bail_exit();
+                return Ok(());
+            };
+            let exe = ui::current_exe()?;
+            let outcome = ui::run_fzf_session(
+                query.as_deref(),
+                &cwd,
+                &exe,
+                ui::SessionSource::Empty,
+                overrides,
+                cli.fresh_settings,
+                &ui::SessionScope::default(),
+            )?;
+            exit_for_outcome(outcome, &cwd);
+            return Ok(());
+        }
+        Some(InternalCommand::Search {
+            query, empty: true, ..
+        }) => {
+            if root_unavailable(&cwd) {
+                print!("{}", format_search_entries(&[root_gone_entry(&cwd)]));
+                return Ok(());
+            }
+            let settings = load_settings_for(&cwd)?;
+            let mut stats = SearchStats::default();
+            let mut entries = build_empty_entries(&query, &cwd, &mut stats)?;
+            record_file_rows(&mut entries, &settings)?;
+            let format_started = Instant::now();
+            let output =
+                format_search_entries_capped(&entries, settings.max_entry_bytes, &mut stats);
+            stats.format = format_started.elapsed();
+
+            if settings.show_timings {
+                println!("{}", timing_header(&stats));
+            }
+            print!("{output}");
+            return Ok(());
+        }
+        Some(InternalCommand::Remove { path, trash }) => {
+            let removed = actions::remove_empty_path(&cwd, &decode_path_field(&path), trash)?;
+            eprintln!("yoink: removed {}", removed.display());
+            return Ok(());
+        }
+        Some(InternalCommand::Search { query, buffer, .. }) => {
+            if let Some(buffer) = buffer {
+                let entries = build_buffer_entries(&query, &buffer)?;
+                let max_bytes = load_settings()?.max_entry_bytes;
+                print!(
+                    "{}",
+                    format_search_entries_capped(&entries, max_bytes, &mut SearchStats::default())
+                );
+                return Ok(());
+            }
+
+            if root_unavailable(&cwd) {
+                print!("{}", format_search_entries(&[root_gone_entry(&cwd)]));
+                return Ok(());
+            }
+            ensure_content_engine(&cwd)?;
+            let started = Instant::now();
+            let settings = load_settings_for(&cwd)?;
+            if settings.sort_mode == SortMode::Stream {
+                return stream_search(&query, &cwd, &settings, started);
+            }
+            let mut stats = SearchStats::default();
+            let mut entries = build_search_entries_with_stats(&query, &cwd, &mut stats)?;
+            record_file_rows(&mut entries, &settings)?;
+            let format_started = Instant::now();
+            let output =
+                format_search_entries_capped(&entries, settings.max_entry_bytes, &mut stats);
+            stats.format = format_started.elapsed();
+
+            // The session reserves a header line for each of these.
+            if settings.show_stats {
+                println!("{}", stats_header(&stats, started.elapsed()));
+            }
+            if settings.show_timings {
+                println!("{}", timing_header(&stats));
+            }
+            print!("{output}");
+
+            if env::var_os(WATCH_CYCLE_ENV).is_some() {
+                let elapsed = started.elapsed();
+                notify_if_slow(
+                    &mut SystemBackend,
+                    settings.notify_after_secs,
+                    elapsed,
+                    || {
+                        let files = entries.iter().filter(|entry| entry.kind.is_some()).count();
+                        completion_message(&format!("refresh of {query:?}"), files, elapsed)
+                    },
+                );
+            }
+            return Ok(());
+        }
+        Some(InternalCommand::EditConfig) => {
+            return config::run_edit_config();
+        }
+        Some(InternalCommand::Pill {
+            path,
+            dir,
+            ext,
+            pop,
+        }) => {
+            return ui::update_pills(&cwd, &decode_path_field(&path), dir, ext, pop);
+        }
+        Some(InternalCommand::Toggle { toggle }) => {
+            return ui::toggle_session(&cwd, toggle);
+        }
+        Some(InternalCommand::Header) => {
+            let pills = Session::from_env()
+                .map(|session| session.pills())
+                .unwrap_or_default();
+            let settings = load_settings_for(&cwd)?;
+            let header = ui::settings_header(&ui::files_header(settings.search_scope), &settings);
+            let header = ui::roots_header(&header, &settings.roots);
+            println!("{}", pill_header(&header, &pills));
+            return Ok(());
+        }
+        Some(InternalCommand::Jump { n }) => {
+            let rows = Session::from_env()
+                .map(|session| session.file_rows())
+                .unwrap_or_default();
+            print!("{}", jump_action(&rows, n));
+            return Ok(());
+        }
+        Some(InternalCommand::Occurrence {
+            path,
+            query,
+            line,
+            prev,
+        }) => {
+            ensure_content_engine(&cwd)?;
+            return ui::step_preview_occurrence(
+                &cwd,
+                &decode_path_field(&path),
+                &query,
+                line.0,
+                !prev,
+            );
+        }
+        Some(InternalCommand::Preview {
+            path,
+            query,
+            line,
+            column,
+        }) => {
+            if root_unavailable(&cwd) {
+                println!("{}", root_gone_message(&cwd));
+                return Ok(());
+            }
+            if term::session_color_mode() == term::ColorMode::Ansi {
+                ensure_dependency("bat")?;
+            }
+            ensure_content_engine(&cwd)?;
+            return ui::run_preview(&cwd, &decode_path_field(&path), &query, line.0, column.0);
         }
         None => {}
     }
 
-    ensure_dependency("fzf")?;
-    ensure_dependency("rg")?;
+    if let Some(notice) = config::take_legacy_config_notice() {
+        eprintln!("{notice}");
+    }
+
+    // A saved search is resolved before fzf starts, so an unknown name fails fast.
+    let query = match &cli.saved {
+        Some(name) => Some(saved_search(&load_settings_for(&cwd)?, name)?.to_string()),
+        None => cli.query.clone(),
+    };
+
+    if cli.stdin_content {
+        ensure_fzf()?;
+        ensure_dependency("bat")?;
+        validate_settings(&cwd, overrides)?;
+
+        let buffer = ui::buffer_stdin()?;
+        let exe = ui::current_exe()?;
+        let outcome = ui::run_fzf_session(
+            query.as_deref(),
+            &cwd,
+            &exe,
+            ui::SessionSource::Buffer(buffer.path()),
+            overrides,
+            cli.fresh_settings,
+            &ui::SessionScope::default(),
+        )?;
+        drop(buffer);
+        exit_for_outcome(outcome, &cwd);
+        return Ok(());
+    }
+
+    ensure_fzf()?;
+    ensure_content_engine(&cwd)?;
     ensure_dependency("bat")?;
 
+    // Several roots are sized one by one; their common root may hold much more.
+    let sized: Vec<PathBuf> = if roots.is_empty() {
+        vec![cwd.clone()]
+    } else {
+        roots.iter().map(|root| cwd.join(root)).collect()
+    };
+    let mut overrides = Some(overrides);
+    for root in &sized {
+        let Some(checked) = overrides else { break };
+        overrides = preflight(root, checked, cli.yes)?;
+    }
+    let Some(overrides) = overrides else {
+        bail_exit();
+        return Ok(());
+    };
     let exe = ui::current_exe()?;
-    ui::run_fzf_session(cli.query.as_deref(), &cwd, &exe)?;
+    let outcome = ui::run_fzf_session(
+        query.as_deref(),
+        &cwd,
+        &exe,
+        ui::SessionSource::Files,
+        overrides,
+        cli.fresh_settings,
+        &ui::SessionScope {
+            roots,
+            changed_ref: cli.changed.clone(),
+            rev: cli.rev.clone().filter(|_| cfg!(feature = "git")),
+        },
+    )?;
+    exit_for_outcome(outcome, &cwd);
+
+    Ok(())
+}
 
+/// `__search` under `sort_mode=stream`: each file's rows go to stdout, flushed,
+/// once the next file's start or the end shows the group is whole. There's no
+/// stats or timing header, as the totals come last.
+fn stream_search(
+    query: &str,
+    cwd: &Path,
+    settings: &YoinkSettings,
+    started: Instant,
+) -> Result<()> {
+    let mut out = BufWriter::new(io::stdout().lock());
+    let mut numbers = FileRowNumbers::default();
+    let mut files = 0;
+    let color_mode = settings.color_mode();
+    for_each_search_entry(query, cwd, &mut SearchStats::default(), |mut entry| {
+        if entry.kind.is_some() {
+            files += 1;
+            out.flush().context("failed to write search results")?;
+        }
+        if settings.number_file_rows {
+            numbers.number(&mut entry, color_mode);
+        }
+        let line = format_search_entries_capped(
+            std::slice::from_ref(&entry),
+            settings.max_entry_bytes,
+            &mut SearchStats::default(),
+        );
+        out.write_all(line.as_bytes())
+            .context("failed to write search results")
+    })?;
+    out.flush().context("failed to write search results")?;
+
+    if settings.number_file_rows {
+        if let Some(session) = Session::from_env() {
+            session.set_file_rows(numbers.rows)?;
+        }
+    }
+    if env::var_os(WATCH_CYCLE_ENV).is_some() {
+        let elapsed = started.elapsed();
+        notify_if_slow(
+            &mut SystemBackend,
+            settings.notify_after_secs,
+            elapsed,
+            || completion_message(&format!("refresh of {query:?}"), files, elapsed),
+        );
+    }
     Ok(())
 }
 
+/// Numbers the first file rows and hands their positions to the session for `__jump`.
+fn record_file_rows(entries: &mut [SearchEntry], settings: &YoinkSettings) -> Result<()> {
+    if !settings.number_file_rows {
+        return Ok(());
+    }
+    let rows = number_file_rows(entries, settings.color_mode());
+    if let Some(session) = Session::from_env() {
+        session.set_file_rows(rows)?;
+    }
+    Ok(())
+}
+
+/// Rejects conflicting settings before rg or fzf starts; warnings go to stderr.
+fn validate_settings(cwd: &Path, overrides: SettingOverrides) -> Result<()> {
+    check::enforce(&resolve_settings(load_settings_for(cwd)?, &[overrides]))
+}
+
+/// Validates settings, then runs the large-tree check; `None` means the user chose to abort.
+fn preflight(
+    cwd: &Path,
+    overrides: SettingOverrides,
+    assume_yes: bool,
+) -> Result<Option<SettingOverrides>> {
+    let settings = resolve_settings(load_settings_for(cwd)?, &[overrides]);
+    check::enforce(&settings)?;
+    Ok(
+        match preflight::confirm_large_tree(cwd, &settings, assume_yes)? {
+            PreflightChoice::Continue => Some(overrides),
+            PreflightChoice::Shallow => Some(SettingOverrides {
+                max_depth: Some(preflight::SHALLOW_DEPTH),
+                ..overrides
+            }),
+            PreflightChoice::Abort => None,
+        },
+    )
+}
+
+fn exit_for_outcome(outcome: ui::SessionOutcome, cwd: &Path) {
+    if outcome == ui::SessionOutcome::RootGone {
+        eprintln!("yoink: {}", root_gone_message(cwd));
+    }
+    if let Some(code) = ui::outcome_exit_code(outcome) {
+        std::process::exit(code);
+    }
+}
+
 fn main() {
     if let Err(error) = run() {
         eprintln!("yoink error: {error}");
@@ -0,0 +1,117 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+use which::which;
+
+/// Set on reloads fired by auto refresh rather than by typing, so a slow
+/// watch cycle can notify without every slow keystroke doing the same.
+pub const WATCH_CYCLE_ENV: &str = "YOINK_WATCH_CYCLE";
+
+const NOTIFY_TITLE: &str = "yoink";
+
+/// Where notifications go; tests swap in a recorder.
+pub trait NotifyBackend {
+    /// Whether `program` is on `PATH`.
+    fn has(&self, program: &str) -> bool;
+    /// Runs `program`, ignoring failures: a missed notification isn't worth an error.
+    fn run(&mut self, program: &str, args: &[String]);
+    fn bell(&mut self);
+}
+
+/// The real terminal and notifier binaries.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemBackend;
+
+impl NotifyBackend for SystemBackend {
+    fn has(&self, program: &str) -> bool {
+        which(program).is_ok()
+    }
+
+    fn run(&mut self, program: &str, args: &[String]) {
+        let _ = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    }
+
+    /// Straight to the terminal, so it rings even with stdout piped or under fzf.
+    fn bell(&mut self) {
+        if let Ok(mut tty) = OpenOptions::new().write(true).open("/dev/tty") {
+            let _ = tty.write_all(b"\x07");
+        }
+    }
+}
+
+/// Whether a run taking `elapsed` is past `notify_after_secs`.
+pub fn should_notify(elapsed: Duration, threshold_secs: Option<u64>) -> bool {
+    threshold_secs.is_some_and(|secs| elapsed >= Duration::from_secs(secs))
+}
+
+/// `report for "needle" finished in 42s: 17 results`.
+pub fn completion_message(label: &str, results: usize, elapsed: Duration) -> String {
+    let noun = if results == 1 { "result" } else { "results" };
+    format!(
+        "{label} finished in {}s: {results} {noun}",
+        elapsed.as_secs()
+    )
+}
+
+/// Desktop notifier invocations in order of preference for the platform.
+pub fn desktop_commands(macos: bool, message: &str) -> Vec<(&'static str, Vec<String>)> {
+    if macos {
+        let script = format!(
+            "display notification {} with title {}",
+            applescript_string(message),
+            applescript_string(NOTIFY_TITLE)
+        );
+        vec![
+            (
+                "terminal-notifier",
+                vec![
+                    "-title".to_string(),
+                    NOTIFY_TITLE.to_string(),
+                    "-message".to_string(),
+                    message.to_string(),
+                ],
+            ),
+            ("osascript", vec!["-e".to_string(), script]),
+        ]
+    } else {
+        vec![(
+            "notify-send",
+            vec![NOTIFY_TITLE.to_string(), message.to_string()],
+        )]
+    }
+}
+
+fn applescript_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Rings the bell and, when a notifier is installed, posts `message` to the desktop.
+pub fn notify(backend: &mut impl NotifyBackend, macos: bool, message: &str) {
+    backend.bell();
+    if let Some((program, args)) = desktop_commands(macos, message)
+        .into_iter()
+        .find(|(program, _)| backend.has(program))
+    {
+        backend.run(program, &args);
+    }
+}
+
+/// Notifies when `elapsed` reaches the threshold; `message` is only built then.
+pub fn notify_if_slow(
+    backend: &mut impl NotifyBackend,
+    threshold_secs: Option<u64>,
+    elapsed: Duration,
+    message: impl FnOnce() -> String,
+) -> bool {
+    if !should_notify(elapsed, threshold_secs) {
+        return false;
+    }
+    notify(backend, cfg!(target_os = "macos"), &message());
+    true
+}
@@ -0,0 +1,141 @@
+use crate::session::SESSION_ENV;
+use crate::term::strip_ansi;
+use crate::ui::shell_quote;
+use anyhow::{Context, Result};
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::Command;
+
+/// Entries the picker lists at once; a query matching more lists the first ones.
+pub const PICKER_ROWS: usize = 20;
+
+/// What a line typed at the picker's prompt asks for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PickerInput {
+    Quit,
+    /// A zero-based index into the listed entries.
+    Pick(usize),
+    Search(String),
+}
+
+/// Reads a line typed at the prompt: an empty line quits, a listed entry's
+/// number picks it, and anything else is the next query. A leading `/` makes
+/// the rest a query, so a number can be searched for too.
+pub fn parse_picker_input(input: &str, listed: usize) -> PickerInput {
+    let input = input.trim_end_matches(['\r', '\n']);
+    if input.trim().is_empty() {
+        return PickerInput::Quit;
+    }
+    if let Some(query) = input.strip_prefix('/') {
+        return PickerInput::Search(query.to_string());
+    }
+    match input.trim().parse::<usize>() {
+        Ok(number) if (1..=listed).contains(&number) => PickerInput::Pick(number - 1),
+        _ => PickerInput::Search(input.to_string()),
+    }
+}
+
+/// Splits a reload's output into its `header_lines` and the entries below them.
+pub fn picker_entries(listing: &str, header_lines: usize) -> (Vec<&str>, Vec<&str>) {
+    let mut lines = listing.lines().filter(|line| !line.is_empty());
+    let headers = lines.by_ref().take(header_lines).collect();
+    (headers, lines.collect())
+}
+
+/// The numbered listing of `entries`, each shown by its display field like
+/// fzf's `--with-nth 1`.
+pub fn format_picker_entries(entries: &[&str]) -> String {
+    let width = entries.len().to_string().len();
+    entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let display = entry.split('\t').next().unwrap_or_default();
+            format!("{:>width$}  {display}\n", index + 1)
+        })
+        .collect()
+}
+
+/// What fzf prints for Enter on `entry` under `--print-query --expect`.
+pub fn picker_output(query: &str, entry: &str) -> String {
+    format!("{query}\nenter\n{entry}\n")
+}
+
+/// Lists `list(query)` on `out` and reads the reply from `input` until an
+/// entry is picked, returning fzf's output for it; `None` when the user quits.
+pub fn pick(
+    query: &str,
+    header_lines: usize,
+    mut list: impl FnMut(&str) -> Result<String>,
+    input: &mut impl BufRead,
+    out: &mut impl Write,
+) -> Result<Option<String>> {
+    let mut query = query.to_string();
+    loop {
+        let listing = list(&query)?;
+        let (headers, entries) = picker_entries(&listing, header_lines);
+        let listed = &entries[..entries.len().min(PICKER_ROWS)];
+
+        writeln!(out)?;
+        for line in headers {
+            writeln!(out, "{line}")?;
+        }
+        write!(out, "{}", format_picker_entries(listed))?;
+        if entries.len() > listed.len() {
+            writeln!(out, "  … {} more", entries.len() - listed.len())?;
+        }
+        write!(
+            out,
+            "regex> {query}\nnumber to pick, text to search, Enter to quit: "
+        )?;
+        out.flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        match parse_picker_input(&line, listed.len()) {
+            PickerInput::Quit => return Ok(None),
+            PickerInput::Pick(index) => return Ok(Some(picker_output(&query, listed[index]))),
+            PickerInput::Search(next) => query = next,
+        }
+    }
+}
+
+/// Stands in for fzf on the terminal: each query runs `reload` with `{q}`
+/// filled in, as fzf's own reloads do, in `cwd` under the session `session_id`.
+/// Only Enter's action is offered, so fzf's key bindings aren't listed.
+pub fn run_picker(
+    reload: &str,
+    cwd: &Path,
+    session_id: &str,
+    header_lines: usize,
+    query: &str,
+) -> Result<Option<String>> {
+    let tty = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .context("the built-in picker needs a terminal")?;
+    let mut out = tty.try_clone()?;
+    let list = |query: &str| -> Result<String> {
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(reload.replace("{q}", &shell_quote(query)))
+            .env(SESSION_ENV, session_id)
+            .current_dir(cwd)
+            .output()
+            .context("failed to list entries for the built-in picker")?;
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+    let picked = pick(
+        query,
+        header_lines,
+        list,
+        &mut BufReader::new(tty),
+        &mut out,
+    )?;
+    // Like fzf under `--ansi`, the picked entry comes back without its colors.
+    Ok(picked.map(|output| strip_ansi(&output)))
+}
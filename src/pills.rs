@@ -0,0 +1,149 @@
+use anyhow::{Context, Result};
+use globset::{Glob, GlobMatcher};
+use std::path::{Component, Path, PathBuf};
+
+/// One narrowing step added during a session. Pills stack: a path has to pass
+/// every one of them, and `alt-backspace` pops the newest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterPill {
+    /// Only paths under this directory (relative to the root).
+    Dir(PathBuf),
+    /// Only files with this extension, without the dot.
+    Ext(String),
+    /// Only files matching this glob, as rg's `-g` reads it.
+    Glob(String),
+}
+
+impl FilterPill {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            FilterPill::Dir(_) => "dir",
+            FilterPill::Ext(_) => "ext",
+            FilterPill::Glob(_) => "glob",
+        }
+    }
+
+    pub fn value(&self) -> String {
+        match self {
+            FilterPill::Dir(dir) => dir.to_string_lossy().into_owned(),
+            FilterPill::Ext(ext) | FilterPill::Glob(ext) => ext.clone(),
+        }
+    }
+
+    pub fn from_parts(kind: &str, value: &str) -> Option<Self> {
+        if value.is_empty() {
+            return None;
+        }
+        match kind {
+            "dir" => Some(FilterPill::Dir(PathBuf::from(value))),
+            "ext" => Some(FilterPill::Ext(value.to_string())),
+            "glob" => Some(FilterPill::Glob(value.to_string())),
+            _ => None,
+        }
+    }
+
+    /// How the header shows the pill: `src/`, `*.rs` or the glob itself.
+    pub fn label(&self) -> String {
+        match self {
+            FilterPill::Dir(dir) => format!("{}/", dir.display()),
+            FilterPill::Ext(ext) => format!("*.{ext}"),
+            FilterPill::Glob(glob) => glob.clone(),
+        }
+    }
+
+    /// The include glob handed to rg, so content passes skip what the pill rules out.
+    pub fn rg_glob(&self) -> String {
+        match self {
+            FilterPill::Dir(dir) => format!("{}/**", dir.to_string_lossy()),
+            FilterPill::Ext(ext) => format!("*.{ext}"),
+            FilterPill::Glob(glob) => glob.clone(),
+        }
+    }
+}
+
+/// `alt-d`: the top-level directory holding `rel`, or `rel` itself when it is one.
+/// `None` for files directly under the root, which the root already covers.
+pub fn dir_pill_for(rel: &Path, is_dir: bool) -> Option<FilterPill> {
+    let mut components = rel
+        .components()
+        .filter(|component| matches!(component, Component::Normal(_)));
+    let first = components.next()?;
+    if components.next().is_none() && !is_dir {
+        return None;
+    }
+    Some(FilterPill::Dir(PathBuf::from(first.as_os_str())))
+}
+
+/// `alt-x`: the extension of `rel`, if it has one.
+pub fn ext_pill_for(rel: &Path) -> Option<FilterPill> {
+    let ext = rel.extension()?.to_string_lossy();
+    Some(FilterPill::Ext(ext.into_owned()))
+}
+
+/// The pills compiled once per search.
+#[derive(Debug, Clone, Default)]
+pub struct PillFilter {
+    pills: Vec<(FilterPill, Option<GlobMatcher>)>,
+}
+
+impl PillFilter {
+    pub fn new(pills: &[FilterPill]) -> Result<Self> {
+        let pills = pills
+            .iter()
+            .map(|pill| {
+                let matcher = match pill {
+                    FilterPill::Glob(glob) => Some(
+                        Glob::new(glob)
+                            .with_context(|| format!("invalid filter glob: {glob}"))?
+                            .compile_matcher(),
+                    ),
+                    _ => None,
+                };
+                Ok((pill.clone(), matcher))
+            })
+            .collect::<Result<_>>()?;
+        Ok(PillFilter { pills })
+    }
+
+    /// Whether `rel` passes every pill. A directory pill prunes everything
+    /// outside it; extension and glob pills only judge files, so the
+    /// directories leading to them stay listed.
+    pub fn allows(&self, rel: &Path, is_dir: bool) -> bool {
+        self.pills.iter().all(|(pill, matcher)| match pill {
+            FilterPill::Dir(dir) => rel.starts_with(dir),
+            FilterPill::Ext(ext) => {
+                is_dir || rel.extension().is_some_and(|actual| actual == ext.as_str())
+            }
+            FilterPill::Glob(_) => {
+                is_dir
+                    || matcher
+                        .as_ref()
+                        .is_some_and(|matcher| matcher.is_match(rel))
+            }
+        })
+    }
+}
+
+/// `-g` includes for rg. rg ORs its include globs, so this only narrows the
+/// search; `PillFilter::allows` applies the pills together on the results.
+pub fn rg_pill_args(pills: &[FilterPill]) -> Vec<String> {
+    pills
+        .iter()
+        .flat_map(|pill| ["-g".to_string(), pill.rg_glob()])
+        .collect()
+}
+
+/// The session header plus a line of active pills and the key that pops them.
+pub fn pill_header(base: &str, pills: &[FilterPill]) -> String {
+    if pills.is_empty() {
+        return base.to_string();
+    }
+    let labels: Vec<String> = pills
+        .iter()
+        .map(|pill| format!("[{}]", pill.label()))
+        .collect();
+    format!(
+        "{base}\nFilters: {}  |  Alt-Backspace: pop last",
+        labels.join(" ")
+    )
+}
@@ -0,0 +1,98 @@
+use crate::search::{count_entries_bounded, TreeSize, YoinkSettings};
+use anyhow::{Context, Result};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// How long the pre-count may walk before the tree is treated as large.
+pub const PREFLIGHT_BUDGET: Duration = Duration::from_secs(2);
+/// Depth offered as the "continue, but shallower" choice.
+pub const SHALLOW_DEPTH: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreflightChoice {
+    Continue,
+    Shallow,
+    Abort,
+}
+
+/// Reads an answer to the large-tree prompt; an empty line takes the default, continue.
+pub fn parse_preflight_choice(input: &str) -> Option<PreflightChoice> {
+    match input.trim().to_ascii_lowercase().as_str() {
+        "" | "c" | "continue" | "y" | "yes" => Some(PreflightChoice::Continue),
+        "d" | "depth" | "shallow" => Some(PreflightChoice::Shallow),
+        "a" | "abort" | "n" | "no" | "q" | "quit" => Some(PreflightChoice::Abort),
+        _ => None,
+    }
+}
+
+/// Whether a pre-count should stop the launch to ask; an unfinished count
+/// means the walk was too slow, which is as bad as too big.
+pub fn exceeds_threshold(size: TreeSize, threshold: usize) -> bool {
+    match size {
+        TreeSize::Exact(count) => threshold > 0 && count >= threshold,
+        TreeSize::AtLeast(_) => threshold > 0,
+    }
+}
+
+pub fn large_tree_message(root: &Path, size: TreeSize, threshold: usize) -> String {
+    let estimate = match size {
+        TreeSize::AtLeast(count) if count < threshold => format!(
+            "counting stopped after {}s at {count} entries",
+            PREFLIGHT_BUDGET.as_secs()
+        ),
+        TreeSize::AtLeast(count) => format!("at least {count} entries"),
+        TreeSize::Exact(count) => format!("{count} entries"),
+    };
+    format!(
+        "yoink: {} looks large ({estimate}; large_tree_threshold = {threshold}).\n\
+         [C]ontinue, continue with --max-[d]epth {SHALLOW_DEPTH}, or [a]bort? ",
+        root.display()
+    )
+}
+
+/// Counts the tree before an interactive launch and, past `large_tree_threshold`,
+/// asks on the terminal how to go on. Without a terminal to ask, it warns and continues.
+pub fn confirm_large_tree(
+    cwd: &Path,
+    settings: &YoinkSettings,
+    assume_yes: bool,
+) -> Result<PreflightChoice> {
+    let threshold = settings.large_tree_threshold;
+    if assume_yes || threshold == 0 {
+        return Ok(PreflightChoice::Continue);
+    }
+
+    let size = count_entries_bounded(cwd, settings, threshold, PREFLIGHT_BUDGET)?;
+    if !exceeds_threshold(size, threshold) {
+        return Ok(PreflightChoice::Continue);
+    }
+
+    let message = large_tree_message(cwd, size, threshold);
+    let stdin = io::stdin();
+    if !stdin.is_terminal() {
+        eprintln!("{}", message.lines().next().unwrap_or_default());
+        return Ok(PreflightChoice::Continue);
+    }
+
+    let mut stderr = io::stderr();
+    let mut input = stdin.lock();
+    loop {
+        write!(stderr, "{message}").context("failed to write large-tree prompt")?;
+        stderr
+            .flush()
+            .context("failed to write large-tree prompt")?;
+
+        let mut answer = String::new();
+        if input
+            .read_line(&mut answer)
+            .context("failed to read large-tree prompt answer")?
+            == 0
+        {
+            return Ok(PreflightChoice::Abort);
+        }
+        if let Some(choice) = parse_preflight_choice(&answer) {
+            return Ok(choice);
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use crate::search::{parse_sort_mode_setting, SettingOverrides};
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// `$XDG_STATE_HOME/yoink`, defaulting to `~/.local/state/yoink`.
+pub fn state_dir() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_STATE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".local/state"),
+    };
+    Some(base.join("yoink"))
+}
+
+/// `$XDG_STATE_HOME/yoink/prefs.toml`, defaulting to `~/.local/state/yoink/prefs.toml`.
+pub fn prefs_path() -> Option<PathBuf> {
+    Some(state_dir()?.join("prefs.toml"))
+}
+
+/// Prefs are keyed by canonical root so `.`, symlinks and trailing slashes agree.
+pub fn canonical_root(root: &Path) -> PathBuf {
+    fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf())
+}
+
+/// Saved per-root session overrides.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PrefsStore {
+    pub roots: BTreeMap<PathBuf, SettingOverrides>,
+}
+
+impl PrefsStore {
+    /// A missing file is an empty store; malformed lines are skipped.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(PrefsStore::default())
+            }
+            Err(error) => {
+                return Err(error).with_context(|| format!("failed to read {}", path.display()))
+            }
+        };
+        Ok(PrefsStore::parse(&content))
+    }
+
+    pub fn parse(content: &str) -> Self {
+        let mut store = PrefsStore::default();
+        let mut current: Option<PathBuf> = None;
+
+        for line in content.lines() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            if let Some(header) = trimmed.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+                current = unquote(header.trim()).map(PathBuf::from);
+                if let Some(root) = &current {
+                    store.roots.entry(root.clone()).or_default();
+                }
+                continue;
+            }
+
+            let (Some(root), Some((key, value))) = (&current, trimmed.split_once('=')) else {
+                continue;
+            };
+            let Some(prefs) = store.roots.get_mut(root) else {
+                continue;
+            };
+            let value = value.trim().trim_matches('"');
+            match key.trim() {
+                "include_hidden" => prefs.include_hidden = value.parse::<bool>().ok(),
+                "sort_mode" => prefs.sort_mode = parse_sort_mode_setting(value),
+                _ => {}
+            }
+        }
+
+        store.roots.retain(|_, prefs| !prefs.is_empty());
+        store
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::from("# Saved by yoink when persist_session_prefs = true.\n");
+        for (root, prefs) in &self.roots {
+            out.push_str(&format!("\n[{}]\n", quote(&root.to_string_lossy())));
+            if let Some(include_hidden) = prefs.include_hidden {
+                out.push_str(&format!("include_hidden = {include_hidden}\n"));
+            }
+            if let Some(sort_mode) = prefs.sort_mode {
+                out.push_str(&format!("sort_mode = \"{}\"\n", sort_mode.as_str()));
+            }
+        }
+        out
+    }
+
+    /// Writes via a sibling temp file and rename so concurrent exits never interleave.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create state directory {}", dir.display()))?;
+
+        let mut temp = tempfile::Builder::new()
+            .prefix(".prefs-")
+            .tempfile_in(dir)
+            .context("failed to create prefs temp file")?;
+        temp.write_all(self.render().as_bytes())
+            .context("failed to write prefs")?;
+        temp.persist(path)
+            .with_context(|| format!("failed to replace {}", path.display()))?;
+        Ok(())
+    }
+
+    pub fn get(&self, root: &Path) -> SettingOverrides {
+        self.roots.get(root).copied().unwrap_or_default()
+    }
+
+    pub fn set(&mut self, root: PathBuf, prefs: SettingOverrides) {
+        if prefs.is_empty() {
+            self.roots.remove(&root);
+        } else {
+            self.roots.insert(root, prefs);
+        }
+    }
+
+    pub fn clear(&mut self, root: &Path) -> bool {
+        self.roots.remove(root).is_some()
+    }
+
+    /// Drops roots that no longer exist on disk; returns how many were removed.
+    pub fn prune(&mut self) -> usize {
+        let before = self.roots.len();
+        self.roots.retain(|root, _| root.is_dir());
+        before - self.roots.len()
+    }
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn unquote(value: &str) -> Option<String> {
+    let inner = value.strip_prefix('"')?.strip_suffix('"')?;
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\\' {
+            out.push(chars.next()?);
+        } else {
+            out.push(ch);
+        }
+    }
+    Some(out)
+}
+
+pub fn load_root_prefs(root: &Path) -> SettingOverrides {
+    let Some(path) = prefs_path() else {
+        return SettingOverrides::default();
+    };
+    match PrefsStore::load(&path) {
+        Ok(store) => store.get(&canonical_root(root)),
+        Err(error) => {
+            eprintln!("yoink: ignoring saved prefs: {error}");
+            SettingOverrides::default()
+        }
+    }
+}
+
+/// What a session leaves in the prefs: the `saved` prefs it started from,
+/// updated with whatever its toggles changed since `launched`. Command-line
+/// flags are part of `launched`, so they last for that session only.
+pub fn session_prefs(
+    saved: SettingOverrides,
+    launched: SettingOverrides,
+    ended: SettingOverrides,
+) -> SettingOverrides {
+    saved.layered_under(ended.changed_since(launched))
+}
+
+/// Records `prefs` for `root` in the prefs file at `path`.
+pub fn save_root_prefs(path: &Path, root: &Path, prefs: SettingOverrides) -> Result<()> {
+    // Only the runtime toggles are remembered; launch flags apply to one session.
+    let prefs = SettingOverrides {
+        include_hidden: prefs.include_hidden,
+        sort_mode: prefs.sort_mode,
+        ..SettingOverrides::default()
+    };
+    let mut store = PrefsStore::load(path)?;
+    store.set(canonical_root(root), prefs);
+    store.prune();
+    store.save(path)
+}
+
+pub fn run_clear(cwd: &Path, root: Option<&Path>) -> Result<()> {
+    let path = prefs_path().context("HOME is not set; cannot locate the prefs file")?;
+    let root = canonical_root(&root.map_or_else(|| cwd.to_path_buf(), |root| cwd.join(root)));
+    let mut store = PrefsStore::load(&path)?;
+
+    if store.clear(&root) {
+        store.save(&path)?;
+        println!("cleared saved prefs for {}", root.display());
+    } else {
+        println!("no saved prefs for {}", root.display());
+    }
+    Ok(())
+}
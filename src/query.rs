@@ -0,0 +1,401 @@
+use anyhow::Result;
+use regex::{Regex, RegexBuilder};
+
+/// Compiled-program bounds for the Rust-side regex. Patterns past them still
+/// match (rg does the searching), but are too costly to highlight with.
+pub const REGEX_SIZE_LIMIT: usize = 1 << 20;
+pub const REGEX_DFA_SIZE_LIMIT: usize = 1 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegexFallback {
+    Literal,
+    Strict,
+}
+
+#[derive(Debug, Clone)]
+pub struct QueryPattern {
+    pub raw: String,
+    pub regex: Regex,
+    pub literal: bool,
+    /// False when the pattern only compiled past `REGEX_SIZE_LIMIT`.
+    pub highlight: bool,
+    /// Matches ignoring case, here and in rg (`-i`).
+    pub case_insensitive: bool,
+    /// Under `and_terms`, one pattern per whitespace-separated term, all of
+    /// which must match; `regex` is then their alternation, so occurrences and
+    /// highlighting cover any term. Empty for a single-term query.
+    pub terms: Vec<QueryPattern>,
+    /// Wrapped in `\b…\b` under `word_boundaries`; rg then matches whole words (`-w`).
+    pub word: bool,
+    /// Under `multiline`, rg may match across lines (`-U --multiline-dotall`).
+    pub multiline: bool,
+}
+
+/// Compiles within the limits, or without them when that's the only problem;
+/// the flag says whether the bounded compile succeeded.
+fn compile_bounded(pattern: &str, case_insensitive: bool) -> Result<(Regex, bool), regex::Error> {
+    let bounded = RegexBuilder::new(pattern)
+        .case_insensitive(case_insensitive)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .dfa_size_limit(REGEX_DFA_SIZE_LIMIT)
+        .build();
+    match bounded {
+        Ok(regex) => Ok((regex, true)),
+        Err(regex::Error::CompiledTooBig(_)) => RegexBuilder::new(pattern)
+            .case_insensitive(case_insensitive)
+            .build()
+            .map(|regex| (regex, false)),
+        Err(error) => Err(error),
+    }
+}
+
+impl QueryPattern {
+    pub fn resolve(query: &str, fallback: RegexFallback) -> Result<Self> {
+        QueryPattern::resolve_with_case(query, fallback, false)
+    }
+
+    pub fn resolve_with_case(
+        query: &str,
+        fallback: RegexFallback,
+        case_insensitive: bool,
+    ) -> Result<Self> {
+        match compile_bounded(query, case_insensitive) {
+            Ok((regex, highlight)) => Ok(QueryPattern {
+                raw: query.to_string(),
+                regex,
+                literal: false,
+                highlight,
+                case_insensitive,
+                terms: Vec::new(),
+                word: false,
+                multiline: false,
+            }),
+            Err(error) if fallback == RegexFallback::Literal => {
+                let (regex, highlight) =
+                    compile_bounded(&regex::escape(query), case_insensitive).map_err(|_| error)?;
+                Ok(QueryPattern {
+                    raw: query.to_string(),
+                    regex,
+                    literal: true,
+                    highlight,
+                    case_insensitive,
+                    terms: Vec::new(),
+                    word: false,
+                    multiline: false,
+                })
+            }
+            Err(error) => {
+                Err(anyhow::Error::new(error).context(format!("invalid regex query: {query}")))
+            }
+        }
+    }
+
+    /// `resolve_with_case` for each term of `split_terms(query)`; a query of one
+    /// term (or none) resolves as a whole, exactly as without `and_terms`.
+    pub fn resolve_terms(
+        query: &str,
+        fallback: RegexFallback,
+        case_insensitive: bool,
+    ) -> Result<Self> {
+        let split = split_terms(query);
+        if split.len() < 2 {
+            let single = split.first().map_or(query, String::as_str);
+            return QueryPattern::resolve_with_case(single, fallback, case_insensitive);
+        }
+        let terms = split
+            .iter()
+            .map(|term| QueryPattern::resolve_with_case(term, fallback, case_insensitive))
+            .collect::<Result<Vec<_>>>()?;
+        QueryPattern::any_of(terms, case_insensitive)
+    }
+
+    /// The alternation of `terms`, keeping each for `all_terms`.
+    fn any_of(terms: Vec<QueryPattern>, case_insensitive: bool) -> Result<Self> {
+        // Literal fallbacks are already escaped in their compiled source.
+        let any = terms
+            .iter()
+            .map(|term| format!("(?:{})", term.regex.as_str()))
+            .collect::<Vec<_>>()
+            .join("|");
+        let (regex, bounded) = compile_bounded(&any, case_insensitive)?;
+        Ok(QueryPattern {
+            raw: any,
+            regex,
+            literal: terms.iter().any(|term| term.literal),
+            highlight: bounded && terms.iter().all(|term| term.highlight),
+            case_insensitive,
+            terms,
+            word: false,
+            multiline: false,
+        })
+    }
+
+    /// Restricts matches to whole words: the compiled regex (escaped first for a
+    /// literal fallback) is wrapped in `\b…\b` and rg gets `-w`. The half
+    /// boundaries are rg's own, so `log(` still matches before a space. A regex
+    /// that already spells `\b` is left as typed, and AND terms are bounded one by one.
+    pub fn word_bounded(self) -> Result<Self> {
+        if !self.terms.is_empty() {
+            let case_insensitive = self.case_insensitive;
+            let terms = self
+                .terms
+                .into_iter()
+                .map(QueryPattern::word_bounded)
+                .collect::<Result<Vec<_>>>()?;
+            return QueryPattern::any_of(terms, case_insensitive);
+        }
+        if self.word || (!self.literal && self.raw.contains("\\b")) {
+            return Ok(self);
+        }
+        let bounded = format!(
+            "\\b{{start-half}}(?:{})\\b{{end-half}}",
+            self.regex.as_str()
+        );
+        let (regex, highlight) = compile_bounded(&bounded, self.case_insensitive)?;
+        Ok(QueryPattern {
+            regex,
+            highlight: highlight && self.highlight,
+            word: true,
+            ..self
+        })
+    }
+
+    /// Lets rg match across line ends, for this pattern and each AND term. The
+    /// Rust-side regex is unchanged: it only ever sees one line at a time.
+    pub fn with_multiline(mut self) -> Self {
+        self.multiline = true;
+        self.terms = self
+            .terms
+            .into_iter()
+            .map(QueryPattern::with_multiline)
+            .collect();
+        self
+    }
+
+    /// The patterns that must each match: the terms, or the whole query.
+    pub fn all_terms(&self) -> Vec<&QueryPattern> {
+        if self.terms.is_empty() {
+            vec![self]
+        } else {
+            self.terms.iter().collect()
+        }
+    }
+
+    /// The regex to highlight matches with, unless it was too big to bound.
+    pub fn highlight_regex(&self) -> Option<&Regex> {
+        self.highlight.then_some(&self.regex)
+    }
+
+    /// Pattern arguments for rg; literal fallbacks switch rg to fixed-string matching.
+    pub fn rg_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if self.case_insensitive {
+            args.push("-i".to_string());
+        }
+        // A multi-term alternation is always a regex, escaped where a term fell back.
+        if self.literal && self.terms.is_empty() {
+            args.push("-F".to_string());
+        }
+        if self.word {
+            args.push("-w".to_string());
+        }
+        if self.multiline {
+            args.push("-U".to_string());
+            args.push("--multiline-dotall".to_string());
+        }
+        args.push("-e".to_string());
+        args.push(self.raw.clone());
+        args
+    }
+
+    /// Pattern arguments for `git grep` under `--rev`, which takes POSIX
+    /// extended regexes. AND terms become `--all-match`, so a file must match
+    /// every term; a term that fell back to a literal is escaped instead of `-F`.
+    pub fn git_grep_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if self.case_insensitive {
+            args.push("-i".to_string());
+        }
+        if self.word {
+            args.push("-w".to_string());
+        }
+        if self.literal && self.terms.is_empty() {
+            args.push("-F".to_string());
+        } else {
+            args.push("-E".to_string());
+        }
+        if !self.terms.is_empty() {
+            args.push("--all-match".to_string());
+        }
+        for term in self.all_terms() {
+            args.push("-e".to_string());
+            if term.literal && !self.terms.is_empty() {
+                args.push(regex::escape(&term.raw));
+            } else {
+                args.push(term.raw.clone());
+            }
+        }
+        args
+    }
+}
+
+/// Splits a query on whitespace for `and_terms`. `"a quoted phrase"` is one
+/// term without its quotes, `\ ` keeps a space inside a term, and `\"` is a
+/// quote to match rather than one that groups.
+pub fn split_terms(query: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut chars = query.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' if chars.peek().is_some_and(|next| next.is_whitespace()) => {
+                current.extend(chars.next());
+            }
+            '\\' => {
+                current.push(ch);
+                current.extend(chars.next());
+            }
+            '"' => {
+                if !current.is_empty() {
+                    terms.push(std::mem::take(&mut current));
+                }
+                quoted = !quoted;
+            }
+            ch if ch.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    terms.push(std::mem::take(&mut current));
+                }
+            }
+            ch => current.push(ch),
+        }
+    }
+    if !current.is_empty() {
+        terms.push(current);
+    }
+    terms
+}
+
+/// Pulls `!term` exclusions out of a query: whitespace-separated tokens led by
+/// `!` are negative terms, and `\!` at the start of a token is a literal `!`.
+/// A query with neither comes back unchanged; otherwise the remaining tokens
+/// are rejoined with single spaces.
+pub fn split_negations(query: &str) -> (String, Vec<String>) {
+    let tokens: Vec<&str> = query.split_whitespace().collect();
+    if !tokens
+        .iter()
+        .any(|token| token.starts_with('!') || token.starts_with("\\!"))
+    {
+        return (query.to_string(), Vec::new());
+    }
+    let mut positive = Vec::new();
+    let mut negatives = Vec::new();
+    for token in tokens {
+        if let Some(escaped) = token.strip_prefix("\\!") {
+            positive.push(format!("!{escaped}"));
+        } else if let Some(negative) = token.strip_prefix('!').filter(|rest| !rest.is_empty()) {
+            negatives.push(negative.to_string());
+        } else {
+            positive.push(token.to_string());
+        }
+    }
+    (positive.join(" "), negatives)
+}
+
+/// How the query's case is matched; `Smart` ignores case unless the query has an uppercase letter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseMode {
+    Sensitive,
+    Insensitive,
+    Smart,
+}
+
+impl CaseMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CaseMode::Sensitive => "sensitive",
+            CaseMode::Insensitive => "insensitive",
+            CaseMode::Smart => "smart",
+        }
+    }
+
+    pub fn ignores_case(self, query: &str) -> bool {
+        match self {
+            CaseMode::Sensitive => false,
+            CaseMode::Insensitive => true,
+            CaseMode::Smart => !query_has_uppercase(query),
+        }
+    }
+}
+
+pub fn parse_case_mode_setting(value: &str) -> Option<CaseMode> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "sensitive" => Some(CaseMode::Sensitive),
+        "insensitive" => Some(CaseMode::Insensitive),
+        "smart" => Some(CaseMode::Smart),
+        _ => None,
+    }
+}
+
+/// Whether the query spells an uppercase letter itself. Regex syntax doesn't count:
+/// `\W`, `\S` and `\p{Lu}` are classes, `\x4A` names a code point, and `(?P<Name>…)`
+/// only labels a group.
+pub fn query_has_uppercase(query: &str) -> bool {
+    let chars: Vec<char> = query.chars().collect();
+    let mut index = 0;
+    while index < chars.len() {
+        match chars[index] {
+            '\\' => {
+                let escaped = chars.get(index + 1).copied();
+                index += 2;
+                let digits = match escaped {
+                    Some('x') => 2,
+                    Some('u') => 4,
+                    Some('U') => 8,
+                    Some('p' | 'P') => 1,
+                    _ => 0,
+                };
+                if digits > 0 && chars.get(index) == Some(&'{') {
+                    index = chars[index..]
+                        .iter()
+                        .position(|&ch| ch == '}')
+                        .map_or(chars.len(), |offset| index + offset + 1);
+                } else {
+                    index += digits;
+                }
+            }
+            '(' => match group_name_start(&chars[index..]) {
+                Some(offset) => {
+                    let start = index + offset;
+                    index = chars[start..]
+                        .iter()
+                        .position(|&ch| ch == '>')
+                        .map_or(chars.len(), |end| start + end + 1);
+                }
+                None => index += 1,
+            },
+            ch if ch.is_uppercase() => return true,
+            _ => index += 1,
+        }
+    }
+    false
+}
+
+/// Offset of the name in a `(?P<name>` or `(?<name>` group opener.
+fn group_name_start(chars: &[char]) -> Option<usize> {
+    match chars {
+        ['(', '?', 'P', '<', ..] => Some(4),
+        ['(', '?', '<', next, ..] if *next != '=' && *next != '!' => Some(3),
+        _ => None,
+    }
+}
+
+pub fn parse_regex_fallback_setting(value: &str) -> Option<RegexFallback> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "literal" => Some(RegexFallback::Literal),
+        "strict" => Some(RegexFallback::Strict),
+        _ => None,
+    }
+}
+
+pub const LITERAL_FALLBACK_NOTICE: &str = "(literal — invalid regex)";
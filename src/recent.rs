@@ -0,0 +1,79 @@
+use crate::prefs::{canonical_root, state_dir};
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+/// Selections kept in the log across all roots; older ones are pruned on write.
+pub const MAX_SELECTIONS: usize = 1000;
+/// Recent rows shown atop an empty-query session, unless `recent_count` says otherwise.
+pub const DEFAULT_RECENT_COUNT: usize = 5;
+
+/// `$XDG_STATE_HOME/yoink/selections`, defaulting to `~/.local/state/yoink/selections`.
+pub fn selections_path() -> Option<PathBuf> {
+    Some(state_dir()?.join("selections"))
+}
+
+/// One selection per line, `ROOT\tPATH`, oldest first; lines without a tab
+/// are skipped.
+pub fn parse_selections(content: &str) -> Vec<(PathBuf, PathBuf)> {
+    content
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .filter(|(root, rel)| !root.is_empty() && !rel.is_empty())
+        .map(|(root, rel)| (PathBuf::from(root), PathBuf::from(rel)))
+        .collect()
+}
+
+pub fn render_selections(entries: &[(PathBuf, PathBuf)]) -> String {
+    entries
+        .iter()
+        .map(|(root, rel)| format!("{}\t{}\n", root.display(), rel.display()))
+        .collect()
+}
+
+/// The `count` newest distinct selections under `root` that still exist,
+/// newest first. A missing or unreadable log is empty.
+pub fn recent_selections(path: &Path, root: &Path, count: usize) -> Vec<PathBuf> {
+    let content = fs::read_to_string(path).unwrap_or_default();
+    let root = canonical_root(root);
+    let mut seen = HashSet::new();
+    parse_selections(&content)
+        .into_iter()
+        .rev()
+        .filter(|(selected_root, _)| *selected_root == root)
+        .map(|(_, rel)| rel)
+        .filter(|rel| seen.insert(rel.clone()) && root.join(rel).exists())
+        .take(count)
+        .collect()
+}
+
+/// Appends `rel` under `root` to the log, keeping the newest `MAX_SELECTIONS`.
+/// The log is rewritten through an owner-only temp file, like the history.
+pub fn record_selection(path: &Path, root: &Path, rel: &Path) -> Result<()> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    fs::create_dir_all(dir)
+        .with_context(|| format!("failed to create state directory {}", dir.display()))?;
+
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(error) if error.kind() == ErrorKind::NotFound => String::new(),
+        Err(error) => {
+            return Err(error).with_context(|| format!("failed to read {}", path.display()))
+        }
+    };
+    let mut entries = parse_selections(&content);
+    entries.push((canonical_root(root), rel.to_path_buf()));
+    let skip = entries.len().saturating_sub(MAX_SELECTIONS);
+
+    let mut temp = tempfile::Builder::new()
+        .prefix(".selections-")
+        .tempfile_in(dir)
+        .context("failed to create selections temp file")?;
+    temp.write_all(render_selections(&entries[skip..]).as_bytes())
+        .context("failed to write selections")?;
+    temp.persist(path)
+        .with_context(|| format!("failed to replace {}", path.display()))?;
+    Ok(())
+}
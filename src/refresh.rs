@@ -0,0 +1,454 @@
+use crate::history::{fzf_version, FzfVersion};
+use crate::notify::WATCH_CYCLE_ENV;
+use crate::search::{tree_snapshot, watched_paths, TreeSnapshot, YoinkSettings};
+use crate::ui::shell_quote;
+use ::notify::event::Flag;
+use ::notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use anyhow::{Context, Result};
+use std::collections::hash_map::RandomState;
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::io::Write;
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime};
+use tempfile::NamedTempFile;
+
+pub const TYPING_DEBOUNCE: Duration = Duration::from_secs(2);
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// How often `--watch` checks for a settled change between file events.
+const WATCH_TICK: Duration = Duration::from_millis(50);
+/// Quiet time after the last seen change before `--watch` reloads, so a save
+/// that touches several files reloads once.
+pub const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+/// The shortest wait between `--watch` rescans, for small trees, when file
+/// events are unavailable.
+pub const WATCH_SCAN_MIN: Duration = Duration::from_secs(2);
+/// The longest wait between `--watch` rescans, however large the tree.
+pub const WATCH_SCAN_MAX: Duration = Duration::from_secs(60);
+/// Each rescan waits a second more for every this many entries in the tree.
+pub const WATCH_ENTRIES_PER_SECOND: usize = 10_000;
+/// A rescan waits at least this many times as long as the last one took, so
+/// walking the tree stays a small share of the session's I/O.
+pub const WATCH_SCAN_COST_FACTOR: u32 = 20;
+/// How soon after starting an exit with status 2 counts as a lost `--listen` port.
+pub const LISTEN_RETRY_WINDOW: Duration = Duration::from_secs(1);
+/// fzf requires this key in the `x-api-key` header of every `--listen` request.
+pub const FZF_API_KEY_ENV: &str = "FZF_API_KEY";
+/// The first fzf with `--listen`, which every pushed reload goes through.
+pub const FZF_LISTEN_VERSION: FzfVersion = FzfVersion(0, 36, 0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RefreshState {
+    pub last_refresh: SystemTime,
+    pub last_change: Option<SystemTime>,
+}
+
+impl RefreshState {
+    pub fn new(now: SystemTime) -> Self {
+        RefreshState {
+            last_refresh: now,
+            last_change: None,
+        }
+    }
+
+    pub fn should_refresh(&self, now: SystemTime, interval: Duration, debounce: Duration) -> bool {
+        let since_refresh = now.duration_since(self.last_refresh).unwrap_or_default();
+        if since_refresh < interval {
+            return false;
+        }
+
+        match self.last_change {
+            Some(changed) => now.duration_since(changed).unwrap_or_default() >= debounce,
+            None => true,
+        }
+    }
+
+    pub fn record_change(&mut self, changed: SystemTime) {
+        if self.last_change.is_none_or(|previous| changed > previous) {
+            self.last_change = Some(changed);
+        }
+    }
+
+    pub fn record_refresh(&mut self, now: SystemTime) {
+        self.last_refresh = now;
+    }
+}
+
+/// Holds back a reload until changes have stopped for `quiet`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchDebounce {
+    quiet: Duration,
+    last_change: Option<SystemTime>,
+}
+
+impl WatchDebounce {
+    pub fn new(quiet: Duration) -> Self {
+        WatchDebounce {
+            quiet,
+            last_change: None,
+        }
+    }
+
+    pub fn record_change(&mut self, changed: SystemTime) {
+        self.last_change = Some(changed);
+    }
+
+    /// Whether a reload is due; a due reload is consumed, so the next needs a new change.
+    pub fn take_due(&mut self, now: SystemTime) -> bool {
+        let due = self
+            .last_change
+            .is_some_and(|changed| now.duration_since(changed).unwrap_or_default() >= self.quiet);
+        if due {
+            self.last_change = None;
+        }
+        due
+    }
+}
+
+/// How long `--watch` waits before its next rescan, after one that listed
+/// `entries` in `took`, when the platform's file events are unavailable. The
+/// whole tree is walked and stat'ed each time, so larger and slower trees are
+/// polled less.
+pub fn watch_scan_interval(entries: usize, took: Duration) -> Duration {
+    let by_size = Duration::from_secs((entries / WATCH_ENTRIES_PER_SECOND) as u64);
+    let by_cost = took * WATCH_SCAN_COST_FACTOR;
+    (WATCH_SCAN_MIN + by_size).max(by_cost).min(WATCH_SCAN_MAX)
+}
+
+/// Paths added, removed or rewritten between two snapshots, sorted. Anything
+/// the walk leaves out, such as ignored directories, is in neither.
+pub fn changed_paths(before: &TreeSnapshot, after: &TreeSnapshot) -> Vec<PathBuf> {
+    let mut changed: Vec<PathBuf> = after
+        .iter()
+        .filter(|(path, stamp)| before.get(*path) != Some(stamp))
+        .map(|(path, _)| path.clone())
+        .chain(
+            before
+                .keys()
+                .filter(|path| !after.contains_key(*path))
+                .cloned(),
+        )
+        .collect();
+    changed.sort();
+    changed
+}
+
+/// A free local port for fzf's `--listen`. The probe listener is dropped
+/// before fzf binds the port, so another process can take it in between; fzf
+/// then exits with status 2 at startup, and `listen_failed` tells the caller
+/// to launch again on a fresh port.
+pub fn reserve_listen_port() -> Result<u16> {
+    let listener =
+        TcpListener::bind(("127.0.0.1", 0)).context("failed to reserve a port for fzf --listen")?;
+    Ok(listener.local_addr()?.port())
+}
+
+/// Whether an fzf that listened exited the way a lost port race does: status
+/// 2 within `LISTEN_RETRY_WINDOW` of starting.
+pub fn listen_failed(status: Option<i32>, ran_for: Duration) -> bool {
+    status == Some(2) && ran_for < LISTEN_RETRY_WINDOW
+}
+
+/// 128 bits for `FZF_API_KEY`, from two SipHash keys the standard library
+/// seeds with OS randomness.
+fn random_api_key() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    (0..2)
+        .map(|round| {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u128(nanos);
+            hasher.write_u32(std::process::id());
+            hasher.write_u8(round);
+            format!("{:016x}", hasher.finish())
+        })
+        .collect()
+}
+
+/// fzf's `--listen` port and the `FZF_API_KEY` it was started with; without
+/// the key any local process could POST `execute(...)` to the port.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FzfListen {
+    pub port: u16,
+    api_key: String,
+}
+
+impl FzfListen {
+    /// Adds `--listen` on a reserved port to `command` and hands fzf a fresh
+    /// API key through its environment.
+    pub fn attach(command: &mut Command) -> Result<Self> {
+        let listen = FzfListen {
+            port: reserve_listen_port()?,
+            api_key: random_api_key(),
+        };
+        command
+            .arg(format!("--listen=127.0.0.1:{}", listen.port))
+            .env(FZF_API_KEY_ENV, &listen.api_key);
+        Ok(listen)
+    }
+
+    fn post(&self, action: &str) -> Result<()> {
+        let port = self.port;
+        let mut stream = TcpStream::connect(("127.0.0.1", port))
+            .with_context(|| format!("failed to connect to fzf on port {port}"))?;
+        stream.write_all(reload_request(action, &self.api_key).as_bytes())?;
+        Ok(())
+    }
+}
+
+pub fn reload_request(action: &str, api_key: &str) -> String {
+    format!(
+        "POST / HTTP/1.1\r\nHost: 127.0.0.1\r\nx-api-key: {api_key}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        action.len(),
+        action
+    )
+}
+
+fn read_change_stamp(stamp: &Path) -> Option<SystemTime> {
+    fs::metadata(stamp)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+pub struct AutoRefresh {
+    listen: FzfListen,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl AutoRefresh {
+    pub fn spawn(listen: FzfListen, interval: Duration, stamp: PathBuf, action: String) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let initial_stamp = read_change_stamp(&stamp);
+        let thread_listen = listen.clone();
+
+        let handle = thread::spawn(move || {
+            let mut state = RefreshState::new(SystemTime::now());
+            while !thread_stop.load(Ordering::Relaxed) {
+                thread::sleep(POLL_INTERVAL);
+
+                if let Some(changed) = read_change_stamp(&stamp) {
+                    if Some(changed) != initial_stamp {
+                        state.record_change(changed);
+                    }
+                }
+
+                let now = SystemTime::now();
+                if state.should_refresh(now, interval, TYPING_DEBOUNCE) {
+                    // fzf may still be starting up or already gone; the next tick retries.
+                    let _ = thread_listen.post(&action);
+                    state.record_refresh(now);
+                }
+            }
+        });
+
+        AutoRefresh {
+            listen,
+            stop,
+            handle: Some(handle),
+        }
+    }
+
+    pub fn stop(mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Auto refresh wired into an fzf command: the poller plus the stamp file fzf
+/// touches on every keystroke.
+pub struct SessionRefresh {
+    refresher: AutoRefresh,
+    _stamp: NamedTempFile,
+}
+
+impl SessionRefresh {
+    /// Adds `--listen` and the keystroke stamp bind to `command`, then starts polling.
+    pub fn attach(command: &mut Command, interval: Duration, reload: &str) -> Result<Self> {
+        let stamp = tempfile::Builder::new()
+            .prefix("yoink-change-")
+            .tempfile()
+            .context("failed to create auto refresh stamp file")?;
+        let listen = FzfListen::attach(command)?;
+
+        command.arg("--bind").arg(format!(
+            "change:execute-silent(touch {})+reload:{reload}",
+            shell_quote(&stamp.path().to_string_lossy())
+        ));
+
+        let refresher = AutoRefresh::spawn(
+            listen,
+            interval,
+            stamp.path().to_path_buf(),
+            format!("track+reload:{WATCH_CYCLE_ENV}=1 {reload}"),
+        );
+        Ok(SessionRefresh {
+            refresher,
+            _stamp: stamp,
+        })
+    }
+
+    pub fn stop(self) {
+        self.refresher.stop();
+    }
+
+    pub fn listen(&self) -> &FzfListen {
+        &self.refresher.listen
+    }
+}
+
+/// Adds a file event's paths to `changed`, leaving out reads, which can't
+/// change the results. True when the platform dropped events (`Flag::Rescan`),
+/// so anything may have changed.
+fn event_paths(event: Event, changed: &mut Vec<PathBuf>) -> bool {
+    if matches!(event.kind, EventKind::Access(_)) {
+        return false;
+    }
+    let rescan = event.flag() == Some(Flag::Rescan);
+    changed.extend(event.paths);
+    rescan
+}
+
+/// Watches each root of the session recursively, sending its events to the
+/// returned receiver.
+fn watch_roots(
+    cwd: &Path,
+    settings: &YoinkSettings,
+) -> ::notify::Result<(RecommendedWatcher, Receiver<::notify::Result<Event>>)> {
+    let (sender, events) = mpsc::channel();
+    let mut watcher = ::notify::recommended_watcher(sender)?;
+    if settings.roots.is_empty() {
+        watcher.watch(cwd, RecursiveMode::Recursive)?;
+    }
+    for label in &settings.roots {
+        watcher.watch(&cwd.join(label), RecursiveMode::Recursive)?;
+    }
+    Ok((watcher, events))
+}
+
+/// `--watch`: listens for file events under the root and, once changes the
+/// walk would see settle for `WATCH_DEBOUNCE`, pushes a reload through fzf's
+/// `--listen` port. Where file events are unavailable it rescans the tree
+/// every `watch_scan_interval` instead.
+pub struct SessionWatch {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl SessionWatch {
+    /// Starts watching `cwd`, adding `--listen` to `command` unless `listen`
+    /// is already set up. `None` when fzf is older than `FZF_LISTEN_VERSION`
+    /// or its version is unknown; the results then refresh on keystrokes only.
+    pub fn attach(
+        command: &mut Command,
+        listen: Option<&FzfListen>,
+        cwd: &Path,
+        settings: &YoinkSettings,
+        reload: &str,
+    ) -> Result<Option<Self>> {
+        if fzf_version().is_none_or(|version| version < FZF_LISTEN_VERSION) {
+            eprintln!("yoink: --watch needs fzf 0.36 or newer; results refresh on keystrokes only");
+            return Ok(None);
+        }
+        let listen = match listen {
+            Some(listen) => listen.clone(),
+            None => FzfListen::attach(command)?,
+        };
+        // Such as past the inotify watch limit on a large tree.
+        let watcher = watch_roots(cwd, settings)
+            .inspect_err(|error| {
+                eprintln!(
+                    "yoink: --watch can't get file events ({error}); rescanning the tree instead"
+                )
+            })
+            .ok();
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let cwd = cwd.to_path_buf();
+        let settings = settings.clone();
+        let action = format!("track+reload:{WATCH_CYCLE_ENV}=1 {reload}");
+        let handle = thread::spawn(move || {
+            let mut debounce = WatchDebounce::new(WATCH_DEBOUNCE);
+            let post_when_due = |debounce: &mut WatchDebounce| {
+                if debounce.take_due(SystemTime::now()) {
+                    // fzf may already be gone; the session is ending then.
+                    let _ = listen.post(&action);
+                }
+            };
+            match watcher {
+                // The watcher stops when it's dropped at the end of the session.
+                Some((_watcher, events)) => {
+                    while !thread_stop.load(Ordering::Relaxed) {
+                        let mut changed = Vec::new();
+                        let mut rescan = false;
+                        match events.recv_timeout(WATCH_TICK) {
+                            Ok(Ok(event)) => rescan |= event_paths(event, &mut changed),
+                            Ok(Err(_)) | Err(RecvTimeoutError::Timeout) => {}
+                            Err(RecvTimeoutError::Disconnected) => break,
+                        }
+                        for event in events.try_iter().flatten() {
+                            rescan |= event_paths(event, &mut changed);
+                        }
+                        // Only what the walk lists reloads; an unreadable root is the reload's to report.
+                        if rescan
+                            || (!changed.is_empty()
+                                && watched_paths(&cwd, &settings, &changed)
+                                    .is_ok_and(|watched| !watched.is_empty()))
+                        {
+                            debounce.record_change(SystemTime::now());
+                        }
+                        post_when_due(&mut debounce);
+                    }
+                }
+                None => {
+                    let started = Instant::now();
+                    let mut snapshot = tree_snapshot(&cwd, &settings).unwrap_or_default();
+                    let mut interval = watch_scan_interval(snapshot.len(), started.elapsed());
+                    let mut scanned = SystemTime::now();
+                    while !thread_stop.load(Ordering::Relaxed) {
+                        thread::sleep(POLL_INTERVAL);
+
+                        let now = SystemTime::now();
+                        if now.duration_since(scanned).unwrap_or_default() >= interval {
+                            // A root gone mid-session is the reload's to report.
+                            let started = Instant::now();
+                            if let Ok(next) = tree_snapshot(&cwd, &settings) {
+                                if !changed_paths(&snapshot, &next).is_empty() {
+                                    debounce.record_change(now);
+                                }
+                                snapshot = next;
+                            }
+                            interval = watch_scan_interval(snapshot.len(), started.elapsed());
+                            scanned = SystemTime::now();
+                        }
+                        post_when_due(&mut debounce);
+                    }
+                }
+            }
+        });
+
+        Ok(Some(SessionWatch {
+            stop,
+            handle: Some(handle),
+        }))
+    }
+
+    pub fn stop(mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
@@ -0,0 +1,376 @@
+use crate::actions::fence_language;
+use crate::notify::{completion_message, notify_if_slow, SystemBackend};
+use crate::query::{split_negations, QueryPattern};
+use crate::search::{
+    alias_paths, build_candidates, load_settings_for, scan_occurrences, Occurrence, YoinkSettings,
+    LONG_LINE_NOTE,
+};
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+pub fn parse_report_format(value: &str) -> Option<ReportFormat> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "md" | "markdown" => Some(ReportFormat::Markdown),
+        "html" => Some(ReportFormat::Html),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportFile {
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub path_match: bool,
+    pub occurrences: Vec<Occurrence>,
+}
+
+/// Everything a report shows, gathered up front so rendering is pure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub query: String,
+    pub root: PathBuf,
+    /// `YYYY-MM-DD HH:MM:SS UTC`; see `format_timestamp`.
+    pub generated: String,
+    pub settings: String,
+    pub files: Vec<ReportFile>,
+    /// The occurrence budget was hit, so some matches are missing.
+    pub truncated: bool,
+}
+
+impl Report {
+    pub fn occurrence_count(&self) -> usize {
+        self.files.iter().map(|file| file.occurrences.len()).sum()
+    }
+}
+
+/// The settings that change what a search finds, as `key=value` pairs.
+pub fn settings_summary(settings: &YoinkSettings) -> String {
+    let max_depth = settings
+        .max_depth
+        .map_or_else(|| "none".to_string(), |depth| depth.to_string());
+    format!(
+        "include_hidden={}, sort_mode={}, max_depth={max_depth}, smart_defaults={}, excludes={}",
+        settings.include_hidden,
+        settings.sort_mode.as_str(),
+        settings.smart_defaults,
+        settings.globs.len()
+    )
+}
+
+/// Formats a time as UTC without pulling in a date crate.
+pub fn format_timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (days, rest) = (secs / 86_400, secs % 86_400);
+
+    // Civil-from-days, after Howard Hinnant's date algorithms.
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        rest / 3_600,
+        rest % 3_600 / 60,
+        rest % 60
+    )
+}
+
+pub fn build_report(query: &str, cwd: &Path, generated: String) -> Result<Report> {
+    let settings = load_settings_for(cwd)?;
+    let candidates = build_candidates(query, cwd)?;
+    let mut scan = scan_occurrences(query, cwd, &alias_paths(&candidates))?;
+
+    let files = candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            let mut occurrences = scan.occurrences.remove(&candidate.path).unwrap_or_default();
+            occurrences.sort_by_key(|occurrence| (occurrence.line, occurrence.column));
+            if !(candidate.path_match || candidate.content_match || !occurrences.is_empty()) {
+                return None;
+            }
+            Some(ReportFile {
+                path: candidate.path,
+                is_dir: candidate.is_dir,
+                path_match: candidate.path_match,
+                occurrences,
+            })
+        })
+        .collect();
+
+    Ok(Report {
+        query: query.to_string(),
+        root: cwd.to_path_buf(),
+        generated,
+        settings: settings_summary(&settings),
+        files,
+        truncated: scan.truncated,
+    })
+}
+
+pub(crate) fn longest_backtick_run(text: &str) -> usize {
+    text.split(|ch| ch != '`').map(str::len).max().unwrap_or(0)
+}
+
+/// An inline code span that survives backticks in `text`.
+fn md_code(text: &str) -> String {
+    let ticks = "`".repeat(longest_backtick_run(text) + 1);
+    let pad = if text.starts_with('`') || text.ends_with('`') {
+        " "
+    } else {
+        ""
+    };
+    format!("{ticks}{pad}{text}{pad}{ticks}")
+}
+
+fn md_cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+fn link_target(path: &Path) -> String {
+    path.to_string_lossy()
+        .replace('%', "%25")
+        .replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29")
+        .replace('#', "%23")
+}
+
+fn no_content_note(file: &ReportFile) -> &'static str {
+    if file.is_dir {
+        "Directory name matched."
+    } else if file.path_match {
+        "Path matched; no content matches."
+    } else {
+        "Content matched; occurrences were cut by the budget."
+    }
+}
+
+pub fn render_markdown(report: &Report) -> String {
+    let mut out = format!("# yoink report: {}\n\n", md_code(&report.query));
+    out.push_str("| | |\n|---|---|\n");
+    for (label, value) in [
+        ("Query", md_code(&report.query)),
+        ("Root", md_code(&report.root.to_string_lossy())),
+        ("Files", report.files.len().to_string()),
+        ("Occurrences", report.occurrence_count().to_string()),
+        ("Settings", report.settings.clone()),
+        ("Generated", report.generated.clone()),
+    ] {
+        out.push_str(&format!("| {label} | {} |\n", md_cell(&value)));
+    }
+    if report.truncated {
+        out.push_str("\n> Some occurrences were left out: the search hit its occurrence budget.\n");
+    }
+
+    for file in &report.files {
+        let path = file.path.to_string_lossy();
+        out.push_str(&format!("\n## {}\n\n", md_code(&path)));
+        if file.occurrences.is_empty() {
+            out.push_str(&format!("_{}_\n", no_content_note(file)));
+            continue;
+        }
+
+        let language = fence_language(&file.path).unwrap_or_default();
+        for occurrence in &file.occurrences {
+            if occurrence.long_line {
+                out.push_str(&format!(
+                    "[{path}:{line}]({target}#L{line})\n\n_{LONG_LINE_NOTE}_\n\n",
+                    path = path.replace('[', "\\[").replace(']', "\\]"),
+                    line = occurrence.line,
+                    target = link_target(&file.path),
+                ));
+                continue;
+            }
+            let fence = "`".repeat(longest_backtick_run(&occurrence.snippet).max(2) + 1);
+            out.push_str(&format!(
+                "[{path}:{line}]({target}#L{line})\n\n{fence}{language}\n{snippet}\n{fence}\n\n",
+                path = path.replace('[', "\\[").replace(']', "\\]"),
+                line = occurrence.line,
+                target = link_target(&file.path),
+                snippet = occurrence.snippet,
+            ));
+        }
+        out.truncate(out.trim_end().len());
+        out.push('\n');
+    }
+    out
+}
+
+pub fn html_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            ch => out.push(ch),
+        }
+    }
+    out
+}
+
+/// Escapes `text` and wraps each match of `regex` in `<mark>`, the HTML
+/// counterpart of `search::highlight_query_matches`.
+pub fn highlight_html(text: &str, regex: Option<&Regex>) -> String {
+    let Some(regex) = regex else {
+        return html_escape(text);
+    };
+    let mut out = String::new();
+    let mut last = 0;
+    for found in regex.find_iter(text).filter(|found| !found.is_empty()) {
+        out.push_str(&html_escape(&text[last..found.start()]));
+        out.push_str("<mark>");
+        out.push_str(&html_escape(found.as_str()));
+        out.push_str("</mark>");
+        last = found.end();
+    }
+    out.push_str(&html_escape(&text[last..]));
+    out
+}
+
+const HTML_STYLE: &str = "body{font-family:sans-serif;margin:2em}table{border-collapse:collapse}\
+td,th{border:1px solid #ccc;padding:4px 8px;text-align:left}\
+pre{background:#f6f8fa;padding:8px;overflow-x:auto}mark{background:#ffe066}";
+
+pub fn render_html(report: &Report, regex: Option<&Regex>) -> String {
+    let query = html_escape(&report.query);
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>yoink report: {query}</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>\n<h1>yoink report: <code>{query}</code></h1>\n<table>\n"
+    );
+    for (label, value) in [
+        ("Query", format!("<code>{query}</code>")),
+        (
+            "Root",
+            format!(
+                "<code>{}</code>",
+                html_escape(&report.root.to_string_lossy())
+            ),
+        ),
+        ("Files", report.files.len().to_string()),
+        ("Occurrences", report.occurrence_count().to_string()),
+        ("Settings", html_escape(&report.settings)),
+        ("Generated", html_escape(&report.generated)),
+    ] {
+        out.push_str(&format!("<tr><th>{label}</th><td>{value}</td></tr>\n"));
+    }
+    out.push_str("</table>\n");
+    if report.truncated {
+        out.push_str(
+            "<p><em>Some occurrences were left out: the search hit its occurrence budget.</em></p>\n",
+        );
+    }
+
+    if !report.files.is_empty() {
+        out.push_str("<ul>\n");
+        for (index, file) in report.files.iter().enumerate() {
+            out.push_str(&format!(
+                "<li><a href=\"#file-{index}\">{}</a> ({})</li>\n",
+                html_escape(&file.path.to_string_lossy()),
+                file.occurrences.len()
+            ));
+        }
+        out.push_str("</ul>\n");
+    }
+
+    for (index, file) in report.files.iter().enumerate() {
+        let path = html_escape(&file.path.to_string_lossy());
+        out.push_str(&format!(
+            "<h2 id=\"file-{index}\"><code>{path}</code></h2>\n"
+        ));
+        if file.occurrences.is_empty() {
+            out.push_str(&format!("<p><em>{}</em></p>\n", no_content_note(file)));
+            continue;
+        }
+
+        let class = fence_language(&file.path)
+            .map(|language| format!(" class=\"language-{}\"", html_escape(language)))
+            .unwrap_or_default();
+        for occurrence in &file.occurrences {
+            let line = occurrence.line;
+            let body = if occurrence.long_line {
+                format!("<p><em>{LONG_LINE_NOTE}</em></p>")
+            } else {
+                format!(
+                    "<pre><code{class}>{}</code></pre>",
+                    highlight_html(&occurrence.snippet, regex)
+                )
+            };
+            out.push_str(&format!(
+                "<p id=\"file-{index}-L{line}\"><a href=\"{}#L{line}\">{path}:{line}</a></p>\n{body}\n",
+                html_escape(&link_target(&file.path)),
+            ));
+        }
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+pub fn run_report(cwd: &Path, query: &str, format: ReportFormat, out: Option<&Path>) -> Result<()> {
+    let started = Instant::now();
+    let report = build_report(query, cwd, format_timestamp(SystemTime::now()))?;
+    let elapsed = started.elapsed();
+    let settings = load_settings_for(cwd)?;
+    let rendered = match format {
+        ReportFormat::Markdown => render_markdown(&report),
+        ReportFormat::Html => {
+            let (positive, _) = split_negations(query);
+            let pattern = if positive.trim().is_empty() {
+                None
+            } else {
+                Some(settings.query_pattern(&positive)?)
+            };
+            render_html(
+                &report,
+                pattern.as_ref().and_then(QueryPattern::highlight_regex),
+            )
+        }
+    };
+
+    match out {
+        Some(path) => {
+            fs::write(path, rendered)
+                .with_context(|| format!("failed to write report to {}", path.display()))?;
+            eprintln!(
+                "yoink: wrote {} files, {} occurrences to {}",
+                report.files.len(),
+                report.occurrence_count(),
+                path.display()
+            );
+        }
+        None => print!("{rendered}"),
+    }
+
+    notify_if_slow(
+        &mut SystemBackend,
+        settings.notify_after_secs,
+        elapsed,
+        || {
+            completion_message(
+                &format!("report for {query:?}"),
+                report.files.len(),
+                elapsed,
+            )
+        },
+    );
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use crate::search::path_from_bytes;
+use crate::session::{parse_json, JsonValue};
+use std::path::PathBuf;
+
+/// One line of `rg --json` output, keeping only what the search needs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RgEvent {
+    /// rg found a first match in `path`; files without matches get no events.
+    Begin {
+        path: PathBuf,
+    },
+    Match(RgMatch),
+    /// rg is done with `path`; every match in it came before.
+    End {
+        path: PathBuf,
+    },
+    /// `context`, `summary` and anything newer.
+    Other,
+}
+
+/// A `match` event: the lines holding a match (several under `-U`) and the
+/// byte ranges matched within them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RgMatch {
+    pub path: PathBuf,
+    pub lines: Vec<u8>,
+    /// 1-based number of the first line in `lines`.
+    pub line_number: usize,
+    pub submatches: Vec<(usize, usize)>,
+}
+
+impl RgMatch {
+    /// The first line of `lines`, without its line terminator.
+    pub fn first_line(&self) -> &[u8] {
+        let end = self
+            .lines
+            .iter()
+            .position(|byte| *byte == b'\n')
+            .unwrap_or(self.lines.len());
+        let line = &self.lines[..end];
+        line.strip_suffix(b"\r").unwrap_or(line)
+    }
+
+    /// 1-based byte column of the first submatch, as `rg --column` prints it.
+    pub fn column(&self) -> usize {
+        self.submatches.first().map_or(1, |(start, _)| start + 1)
+    }
+}
+
+/// Parses one event line. `None` for lines that aren't a whole JSON object,
+/// such as one cut short by a read cap.
+pub fn parse_event(line: &str) -> Option<RgEvent> {
+    let value = parse_json(line)?;
+    let data = value.get("data");
+    match value.get("type")?.as_str()? {
+        "begin" => Some(RgEvent::Begin {
+            path: data_path(data?.get("path")?)?,
+        }),
+        "end" => Some(RgEvent::End {
+            path: data_path(data?.get("path")?)?,
+        }),
+        "match" => {
+            let data = data?;
+            let submatches = match data.get("submatches") {
+                Some(JsonValue::Array(items)) => items
+                    .iter()
+                    .map(|item| {
+                        Some((item.get("start")?.as_usize()?, item.get("end")?.as_usize()?))
+                    })
+                    .collect::<Option<Vec<_>>>()?,
+                _ => Vec::new(),
+            };
+            Some(RgEvent::Match(RgMatch {
+                path: data_path(data.get("path")?)?,
+                lines: data_bytes(data.get("lines")?)?,
+                line_number: data.get("line_number")?.as_usize()?,
+                submatches,
+            }))
+        }
+        _ => Some(RgEvent::Other),
+    }
+}
+
+/// rg's `{"text": …}` or, for invalid UTF-8, `{"bytes": <base64>}`.
+fn data_bytes(value: &JsonValue) -> Option<Vec<u8>> {
+    if let Some(text) = value.get("text").and_then(JsonValue::as_str) {
+        return Some(text.as_bytes().to_vec());
+    }
+    decode_base64(value.get("bytes")?.as_str()?)
+}
+
+fn data_path(value: &JsonValue) -> Option<PathBuf> {
+    let path = path_from_bytes(data_bytes(value)?);
+    Some(path.strip_prefix("./").map(PathBuf::from).unwrap_or(path))
+}
+
+/// Standard base64 with padding, as rg writes it.
+pub fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let digit = |byte: u8| -> Option<u32> {
+        Some(match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        } as u32)
+    };
+    let trimmed = text.trim_end_matches('=');
+    let mut out = Vec::with_capacity(trimmed.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for byte in trimmed.bytes() {
+        buffer = (buffer << 6) | digit(byte)?;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(out)
+}
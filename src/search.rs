@@ -1,11 +1,45 @@
+#[cfg(feature = "archives")]
+use crate::archive::{list_members, member_path, ArchiveKind};
+use crate::config::{config_sources, ConfigKind, ConfigSnapshot, ConfigSource};
+use crate::engine::scan_content_internal;
+use crate::fold::{fold_query, fold_text, parse_unicode_fold_setting, UnicodeFold};
+use crate::git::{changed_files, tracked_files, GitFiles};
+#[cfg(feature = "git")]
+use crate::git::{grep_revision, parse_grep_line};
+use crate::gitignore::{GitIgnore, NestedIgnores};
+use crate::history::DEFAULT_HISTORY_SIZE;
+use crate::lang::{detect_language, language_badge, FileTypeSet};
+use crate::pills::{rg_pill_args, FilterPill, PillFilter};
+use crate::query::{
+    parse_case_mode_setting, parse_regex_fallback_setting, split_negations, CaseMode, QueryPattern,
+    RegexFallback, LITERAL_FALLBACK_NOTICE,
+};
+use crate::recent::{recent_selections, selections_path, DEFAULT_RECENT_COUNT};
+use crate::rgjson::{parse_event, RgEvent};
+use crate::session::{decode_json_object, json_string, JsonValue, Session};
+use crate::term::{
+    parse_match_color_setting, sanitize_control, session_color_mode, strip_ansi, ColorMode,
+    CONTENT_MATCH_COLOR, PATH_MATCH_COLOR,
+};
 use anyhow::{Context, Result};
-use globset::{Glob, GlobSet, GlobSetBuilder};
+use globset::{Candidate as GlobCandidate, Glob, GlobSet, GlobSetBuilder};
+use ignore::{WalkBuilder, WalkState};
 use regex::Regex;
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::env;
 use std::fs;
+use std::io::{self, BufRead, BufReader, Read};
+use std::ops::Range;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{ChildStdout, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 use walkdir::WalkDir;
 
 #[cfg(target_family = "unix")]
@@ -13,537 +47,6610 @@ use std::os::unix::fs::MetadataExt;
 
 const DEFAULT_IGNORE_GLOBS: &[&str] = &[".git/**", "node_modukes/**"];
 
+/// Bumped whenever the smart default sets below change.
+pub const SMART_DEFAULTS_VERSION: u32 = 1;
+/// Build output, caches and vendored trees excluded while `smart_defaults` is on.
+/// Names that are common source directories elsewhere stay anchored at the root.
+pub const SMART_DEFAULT_GLOBS: &[&str] = &[
+    "target/**",
+    "dist/**",
+    "build/**",
+    "vendor/**",
+    "**/__pycache__/**",
+    "**/.venv/**",
+    "**/.terraform/**",
+];
+/// Generated files that stay listed by path but are never searched for content.
+pub const SMART_SUPPRESS_GLOBS: &[&str] = &["*.lock", "package-lock.json", "pnpm-lock.yaml"];
+
+/// Share of the fzf window given to the preview pane (`--preview-window=right:65%`).
+pub const PREVIEW_WIDTH_PERCENT: usize = 65;
+const TEASER_SEPARATOR: &str = "  ·  ";
+const MIN_PATH_WIDTH: usize = 16;
+/// Columns past which `path_ellipsis` shortens a listed path.
+pub const PATH_DISPLAY_MAX_WIDTH: usize = 100;
+const MIN_TEASER_WIDTH: usize = 8;
+pub const DEFAULT_MAX_ENTRY_BYTES: usize = 4096;
+pub const SEARCH_TIMEOUT_NOTICE: &str =
+    "(search timed out) results are partial; narrow the query or raise search_timeout_ms";
+pub const TRUNCATED_RESULTS_NOTICE: &str = "(results truncated) too many matches; narrow the query";
+pub const HIGHLIGHT_OFF_NOTICE: &str = "(highlighting off) pattern too costly to color matches";
+/// A single highlight call slower than this turns highlighting off for the session.
+pub const HIGHLIGHT_TIME_LIMIT: Duration = Duration::from_millis(50);
+pub const DEFAULT_LARGE_TREE_THRESHOLD: usize = 200_000;
+pub const DEFAULT_MAX_SYMLINK_DEPTH: usize = 8;
+const TRUNCATION_SUFFIX: &str = "\x1b[0m…";
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum SortMode {
+pub enum SortMode {
     Depth,
     Alphabetical,
+    /// Most recently modified first; see `Candidate::modified`.
+    Modified,
+    /// Most content matches first; applied by `build_search_entries` once the
+    /// occurrences are counted, depth order until then.
+    Matches,
+    /// Directories, then files, each by `Candidate::size` in `size_order`.
+    Size,
+    /// Discovery order, unsorted, so `__search` can write each file's rows as
+    /// soon as they're built; see `for_each_search_entry`.
+    Stream,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Candidate {
-    pub path: PathBuf,
-    pub is_dir: bool,
-    pub path_match: bool,
-    pub content_match: bool,
+/// Which passes a query runs: the path walk, rg's content search, or both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchScope {
+    #[default]
+    Both,
+    /// Name matches only; rg never runs for content or occurrences.
+    Paths,
+    /// rg content matches only; the walk runs just to list an empty query.
+    Content,
 }
 
-#[derive(Debug)]
-struct YoinkSettings {
-    include_hidden: bool,
-    include_mounts: bool,
-    include_symlinks: bool,
-    sort_mode: SortMode,
-    globset: GlobSet,
-    globs: Vec<String>,
-}
+impl SearchScope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SearchScope::Both => "both",
+            SearchScope::Paths => "paths",
+            SearchScope::Content => "content",
+        }
+    }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct SearchEntry {
-    pub display: String,
-    pub path: PathBuf,
-    pub line: Option<usize>,
-}
+    /// Whether names are matched; an empty query always lists, whatever the scope.
+    pub fn searches_paths(self, query_empty: bool) -> bool {
+        self != SearchScope::Content || query_empty
+    }
 
-#[derive(Debug, Clone)]
-struct Occurrence {
-    line: usize,
-    column: usize,
-    snippet: String,
+    pub fn searches_content(self) -> bool {
+        self != SearchScope::Paths
+    }
 }
 
-fn is_hidden_path(rel: &Path) -> bool {
-    rel.components().any(|component| {
-        let name = component.as_os_str().to_string_lossy();
-        name.starts_with('.')
-    })
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SizeOrder {
+    #[default]
+    Ascending,
+    Descending,
 }
 
-fn parse_bool_setting(value: &str) -> Option<bool> {
-    match value.trim().to_ascii_lowercase().as_str() {
-        "true" | "1" | "yes" | "on" => Some(true),
-        "false" | "0" | "no" | "off" => Some(false),
-        _ => None,
-    }
+/// How `sort_candidates_with` orders within a `SortMode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SortOptions {
+    pub size_order: SizeOrder,
+    /// Directories before files: within each depth under `Depth`, ahead of
+    /// everything else under the other modes.
+    pub group_dirs_first: bool,
 }
 
-fn parse_sort_mode_setting(value: &str) -> Option<SortMode> {
-    match value.trim().to_ascii_lowercase().as_str() {
-        "depth" => Some(SortMode::Depth),
-        "alphabetical" => Some(SortMode::Alphabetical),
-        _ => None,
+impl SortOptions {
+    /// The grouping key: false (sorts first) for directories when grouping,
+    /// the same for every candidate otherwise.
+    fn file_group(&self, candidate: &Candidate) -> bool {
+        self.group_dirs_first && !candidate.is_dir
     }
 }
 
-fn yoinkignore_path() -> Option<PathBuf> {
-    if let Some(path) = env::var_os("YOINKIGNORE_PATH") {
-        return Some(PathBuf::from(path));
+impl SortMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SortMode::Depth => "depth",
+            SortMode::Alphabetical => "alphabetical",
+            SortMode::Modified => "modified",
+            SortMode::Matches => "matches",
+            SortMode::Size => "size",
+            SortMode::Stream => "stream",
+        }
     }
 
-    env::var_os("HOME").map(|home| PathBuf::from(home).join(".yoinkignore"))
+    /// The order `alt-s` switches to. `Stream` is left out of the cycle, as a
+    /// session lays out its header lines for one or the other at launch.
+    pub fn next(self) -> SortMode {
+        match self {
+            SortMode::Depth => SortMode::Alphabetical,
+            SortMode::Alphabetical => SortMode::Modified,
+            SortMode::Modified => SortMode::Matches,
+            SortMode::Matches => SortMode::Size,
+            SortMode::Size | SortMode::Stream => SortMode::Depth,
+        }
+    }
 }
 
-fn load_settings() -> Result<YoinkSettings> {
-    let mut include_hidden = false;
-    let mut include_mounts = false;
-    let mut include_symlinks = false;
-    let mut sort_mode = SortMode::Depth;
-    let mut globs: Vec<String> = DEFAULT_IGNORE_GLOBS
-        .iter()
-        .map(|pattern| pattern.to_string())
-        .collect();
-
-    if let Some(ignore_file) = yoinkignore_path() {
-        if ignore_file.exists() {
-            let content = fs::read_to_string(&ignore_file)
-                .with_context(|| format!("failed to read {}", ignore_file.display()))?;
-
-            for line in content.lines() {
-                let trimmed = line.trim();
-                if trimmed.is_empty() || trimmed.starts_with('#') {
-                    continue;
-                }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileLister {
+    Walkdir,
+    Rg,
+}
 
-                if let Some((raw_key, raw_value)) = trimmed.split_once('=') {
-                    let key = raw_key.trim().to_ascii_lowercase();
-                    let value = raw_value.trim();
-                    match key.as_str() {
-                        "include_hidden" => {
-                            include_hidden = parse_bool_setting(value).with_context(|| {
-                                format!("invalid include_hidden value in {}: {value}", ignore_file.display())
-                            })?;
-                            continue;
-                        }
-                        "include_mounts" => {
-                            include_mounts = parse_bool_setting(value).with_context(|| {
-                                format!("invalid include_mounts value in {}: {value}", ignore_file.display())
-                            })?;
-                            continue;
-                        }
-                        "include_symlinks" => {
-                            include_symlinks = parse_bool_setting(value).with_context(|| {
-                                format!("invalid include_symlinks value in {}: {value}", ignore_file.display())
-                            })?;
-                            continue;
-                        }
-                        "sort_mode" => {
-                            sort_mode = parse_sort_mode_setting(value).with_context(|| {
-                                format!("invalid sort_mode value in {}: {value}", ignore_file.display())
-                            })?;
-                            continue;
-                        }
-                        _ => {}
-                    }
-                }
+/// What searches file contents; see `YoinkSettings::internal_engine`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentEngine {
+    /// rg when it's installed, the internal scanner otherwise.
+    Auto,
+    Rg,
+    /// `scan_content_internal`: the query's regex run over each file in-process.
+    Internal,
+}
 
-                globs.push(trimmed.to_string());
-            }
+impl ContentEngine {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ContentEngine::Auto => "auto",
+            ContentEngine::Rg => "rg",
+            ContentEngine::Internal => "internal",
         }
     }
+}
 
-    let mut builder = GlobSetBuilder::new();
-    for pattern in &globs {
-        builder.add(
-            Glob::new(pattern)
-                .with_context(|| format!("invalid ~/.yoinkignore glob: {pattern}"))?,
-        );
-    }
-
-    let globset = builder.build().context("failed building ignore glob set")?;
-    Ok(YoinkSettings {
-        include_hidden,
-        include_mounts,
-        include_symlinks,
-        sort_mode,
-        globset,
-        globs,
-    })
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub path_match: bool,
+    pub content_match: bool,
+    /// Matched a `~pattern` demote glob: kept, but sorted last and dimmed.
+    pub demoted: bool,
+    /// Other paths to the same file (hard links, symlinks) folded into this one
+    /// under `include_symlinks` or `dedupe_inodes`; see `dedup_by_inode`.
+    pub aliases: Vec<PathBuf>,
+    /// A NUL in the first `BINARY_SNIFF_BYTES`; listed by path only, under `show_binaries`.
+    pub is_binary: bool,
+    /// Modification time, read only under `SortMode::Modified`; `None` when
+    /// unread or the stat failed.
+    pub modified: Option<SystemTime>,
+    /// Bytes for a file, direct children for a directory; read only under
+    /// `SortMode::Size`.
+    pub size: Option<u64>,
+    /// Listed by the `fuzzy_fallback` subsequence pass, not the query itself.
+    pub fuzzy_match: bool,
+    /// Content matches read for the file, within the occurrence budget; filled
+    /// in by `build_search_entries`, 0 until then and for path-only matches.
+    pub match_count: usize,
 }
 
-pub fn build_candidates(query: &str, cwd: &Path) -> Result<Vec<Candidate>> {
-    let mut map: HashMap<PathBuf, Candidate> = HashMap::new();
-    let settings = load_settings()?;
+/// Suffix on file rows listed by the `fuzzy_fallback` pass.
+pub const FUZZY_MARKER: &str = "~";
 
-    #[cfg(target_family = "unix")]
-    let root_dev = if settings.include_mounts {
-        None
-    } else {
-        Some(
-            fs::metadata(cwd)
-                .with_context(|| format!("failed to stat search root: {}", cwd.display()))?
-                .dev(),
-        )
-    };
+/// File-row icon for binaries, so they read as unpreviewable at a glance.
+pub const BINARY_ICON: &str = "💾";
 
-    let regex = if query.is_empty() {
-        None
-    } else {
-        Some(Regex::new(query).with_context(|| format!("invalid regex query: {query}"))?)
-    };
+/// A row's leading icon; without `icons`, a word a screen reader can say.
+pub fn path_icon(is_dir: bool, is_binary: bool, icons: bool) -> &'static str {
+    match (is_dir, is_binary, icons) {
+        (true, _, true) => "📁",
+        (true, _, false) => "DIR",
+        (false, true, true) => BINARY_ICON,
+        (false, true, false) => "BINARY",
+        (false, false, true) => "📄",
+        (false, false, false) => "FILE",
+    }
+}
 
-    let iter = WalkDir::new(cwd)
-        .follow_links(false)
-        .into_iter()
-        .filter_entry(|entry| {
-            let path = entry.path();
-            if path == cwd {
-                return true;
-            }
+/// How much of a file is read to decide whether it is binary, as rg and git do.
+pub const BINARY_SNIFF_BYTES: usize = 8 * 1024;
 
-            if !settings.include_symlinks && entry.path_is_symlink() {
-                return false;
-            }
+/// Whether `path` was modified at or after `cutoff`. A file whose mtime can't
+/// be read is kept: better listed than silently lost.
+fn modified_since(path: &Path, cutoff: Option<SystemTime>) -> bool {
+    let Some(cutoff) = cutoff else {
+        return true;
+    };
+    modified_time(path).is_none_or(|modified| modified >= cutoff)
+}
 
-            let rel = match path.strip_prefix(cwd) {
-                Ok(v) => v,
-                Err(_) => return false,
-            };
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
 
-            if (!settings.include_hidden && is_hidden_path(rel)) || settings.globset.is_match(rel) {
-                return false;
-            }
+/// The `(modified, size)` that `sort_mode` orders by, from a single stat (or
+/// directory read); both `None` for modes that don't need them.
+fn sort_keys(path: &Path, is_dir: bool, sort_mode: SortMode) -> (Option<SystemTime>, Option<u64>) {
+    match sort_mode {
+        SortMode::Modified => (modified_time(path), None),
+        SortMode::Size if is_dir => (
+            None,
+            fs::read_dir(path)
+                .ok()
+                .map(|entries| entries.count() as u64),
+        ),
+        SortMode::Size => (None, fs::metadata(path).ok().map(|metadata| metadata.len())),
+        _ => (None, None),
+    }
+}
 
-            #[cfg(target_family = "unix")]
-            {
-                if let Some(root_dev) = root_dev {
-                    if entry.file_type().is_dir() {
-                        if let Ok(metadata) = fs::metadata(path) {
-                            if metadata.dev() != root_dev {
-                                return false;
-                            }
-                        }
-                    }
-                }
-            }
+/// Whether `path` has a NUL byte in its first `BINARY_SNIFF_BYTES`; unreadable files aren't.
+pub fn is_binary_file(path: &Path) -> bool {
+    let Ok(file) = fs::File::open(path) else {
+        return false;
+    };
+    let mut head = Vec::with_capacity(BINARY_SNIFF_BYTES);
+    file.take(BINARY_SNIFF_BYTES as u64)
+        .read_to_end(&mut head)
+        .is_ok_and(|_| head.contains(&0))
+}
 
-            true
-        });
+/// How a query matched a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchKind {
+    Path,
+    Content,
+    Both,
+}
 
-    for entry in iter.filter_map(Result::ok) {
-        let path = entry.path();
-        if path == cwd {
-            continue;
+impl MatchKind {
+    pub fn of(candidate: &Candidate) -> Option<MatchKind> {
+        match (candidate.path_match, candidate.content_match) {
+            (true, true) => Some(MatchKind::Both),
+            (true, false) => Some(MatchKind::Path),
+            (false, true) => Some(MatchKind::Content),
+            (false, false) => None,
         }
+    }
 
-        let rel = match path.strip_prefix(cwd) {
-            Ok(v) => v.to_path_buf(),
-            Err(_) => continue,
-        };
-
-        let path_str = rel.to_string_lossy();
-        let file_name = rel
-            .file_name()
-            .map(|v| v.to_string_lossy())
-            .unwrap_or_else(|| path_str.clone());
-
-        let is_match = match &regex {
-            None => true,
-            Some(re) => re.is_match(&path_str) || re.is_match(&file_name),
-        };
-
-        if is_match {
-            map.entry(rel.clone())
-                .and_modify(|candidate| candidate.path_match = true)
-                .or_insert(Candidate {
-                    path: rel,
-                    is_dir: entry.file_type().is_dir(),
-                    path_match: true,
-                    content_match: false,
-                });
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MatchKind::Path => "path",
+            MatchKind::Content => "content",
+            MatchKind::Both => "path+content",
         }
     }
 
-    if !query.is_empty() {
-        let mut rg_command = Command::new("rg");
-        rg_command
-            .arg("-l")
-            .arg("--color=never")
-            .arg("--no-messages")
-            .arg("-e")
-            .arg(query);
-
-        if settings.include_hidden {
-            rg_command.arg("--hidden");
+    /// The short tag shown on file rows.
+    pub fn badge(self) -> &'static str {
+        match self {
+            MatchKind::Path => "[p]",
+            MatchKind::Content => "[c]",
+            MatchKind::Both => "[pc]",
         }
+    }
+}
 
-        if !settings.include_mounts {
-            rg_command.arg("--one-file-system");
-        }
+/// Where an ignore glob was declared; built-in defaults have no source file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GlobOrigin {
+    pub file: Option<PathBuf>,
+    pub line: usize,
+    /// Injected by `smart_defaults` rather than written anywhere.
+    pub smart_default: bool,
+}
 
-        if settings.include_symlinks {
-            rg_command.arg("--follow");
+impl GlobOrigin {
+    pub fn smart_default() -> Self {
+        GlobOrigin {
+            smart_default: true,
+            ..GlobOrigin::default()
         }
+    }
 
-        for pattern in &settings.globs {
-            rg_command.arg("-g").arg(format!("!{pattern}"));
+    /// `path:line`, `smart default` or `built-in`.
+    pub fn label(&self) -> String {
+        match &self.file {
+            Some(file) => format!("{}:{}", file.display(), self.line),
+            None if self.smart_default => "smart default".to_string(),
+            None => "built-in".to_string(),
         }
+    }
+}
 
-        let output = rg_command
-            .arg(".")
-            .current_dir(cwd)
-            .output()
-            .context("failed to execute rg for content matches")?;
-
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        for line in stdout.lines().filter(|line| !line.trim().is_empty()) {
-            let normalized = line.trim_start_matches("./");
-            let rel = PathBuf::from(normalized);
-
-            if (!settings.include_hidden && is_hidden_path(&rel)) || settings.globset.is_match(&rel)
-            {
-                continue;
-            }
-
-            let full = cwd.join(&rel);
-
-            #[cfg(target_family = "unix")]
-            {
-                if let Some(root_dev) = root_dev {
-                    if let Ok(metadata) = fs::metadata(&full) {
-                        if metadata.dev() != root_dev {
-                            continue;
-                        }
-                    }
-                }
-            }
+/// Compiled ignore globs; equality follows the source patterns. A `!pattern`
+/// re-includes what earlier patterns excluded, and the last match decides.
+#[derive(Debug, Clone, Default)]
+pub struct GlobMatcher {
+    patterns: Vec<String>,
+    origins: Vec<GlobOrigin>,
+    /// Parallel to `patterns`: which are `!` re-includes.
+    negated: Vec<bool>,
+    set: GlobSet,
+}
 
-            let is_dir = full.is_dir();
+/// Per-pattern exclusion counts, indexed like the matcher's patterns.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IgnoreHits {
+    pub counts: Vec<usize>,
+}
 
-            map.entry(rel.clone())
-                .and_modify(|candidate| candidate.content_match = true)
-                .or_insert(Candidate {
-                    path: rel,
-                    is_dir,
-                    path_match: false,
-                    content_match: true,
-                });
+impl IgnoreHits {
+    pub fn for_matcher(matcher: &GlobMatcher) -> Self {
+        IgnoreHits {
+            counts: vec![0; matcher.patterns.len()],
         }
     }
-
-    let mut list: Vec<Candidate> = map.into_values().collect();
-    sort_candidates(&mut list, settings.sort_mode);
-    Ok(list)
 }
 
-pub fn build_search_entries(query: &str, cwd: &Path) -> Result<Vec<SearchEntry>> {
-    let settings = load_settings()?;
-    let candidates = build_candidates(query, cwd)?;
-    let highlight_re = if query.trim().is_empty() {
-        None
-    } else {
-        Regex::new(query).ok()
-    };
-
-    let occurrence_map = if query.trim().is_empty() {
-        HashMap::new()
-    } else {
-        collect_occurrences(query, cwd, &settings)?
-    };
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IgnorePatternStat {
+    pub pattern: String,
+    pub origin: GlobOrigin,
+    pub hits: usize,
+}
 
-    let mut entries = Vec::new();
+impl GlobMatcher {
+    pub fn new(patterns: &[String]) -> Result<Self> {
+        Self::with_origins(patterns, &[])
+    }
 
-    for candidate in candidates {
-        let occurrences = occurrence_map.get(&candidate.path).cloned().unwrap_or_default();
-        let count = occurrences.len();
-
-        if candidate.path_match || count > 0 {
-            let icon = if candidate.is_dir { "📁" } else { "📄" };
-            let path_display = highlight_query_matches(
-                &candidate.path.to_string_lossy(),
-                highlight_re.as_ref(),
+    pub fn with_origins(patterns: &[String], origins: &[GlobOrigin]) -> Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        let mut negated = Vec::with_capacity(patterns.len());
+        for pattern in patterns {
+            let (glob, is_negated) = split_negation(pattern);
+            builder.add(
+                Glob::new(glob)
+                    .with_context(|| format!("invalid ~/.yoinkignore glob: {pattern}"))?,
             );
+            negated.push(is_negated);
+        }
 
-            let display = format!("{} {}", icon, path_display);
-
-            entries.push(SearchEntry {
-                display,
-                path: candidate.path.clone(),
-                line: None,
-            });
-
-            let line_width = occurrences
-                .iter()
-                .map(|occurrence| occurrence.line.to_string().len())
-                .max()
-                .unwrap_or(4)
-                .max(4);
+        let mut origins = origins.to_vec();
+        origins.resize(patterns.len(), GlobOrigin::default());
 
-            for (index, occurrence) in occurrences.into_iter().enumerate() {
-                let snippet = highlight_query_matches(&occurrence.snippet, highlight_re.as_ref());
-                let count_prefix = if index == 0 {
-                    format!("\x1b[33m{:>2}\x1b[0m", count)
-                } else {
-                    "  ".to_string()
-                };
+        Ok(GlobMatcher {
+            patterns: patterns.to_vec(),
+            origins,
+            negated,
+            set: builder.build().context("failed building ignore glob set")?,
+        })
+    }
 
-                entries.push(SearchEntry {
-                    display: format!(
-                        "{}   ↳ {:>width$}  {}",
-                        count_prefix,
-                        occurrence.line,
-                        truncate_snippet(&snippet, 140),
-                        width = line_width
-                    ),
-                    path: candidate.path.clone(),
-                    line: Some(occurrence.line),
-                });
-            }
+    /// Whether `path` ends up excluded once every pattern has had its say.
+    pub fn is_match<P: AsRef<Path>>(&self, path: P) -> bool {
+        if !self.has_negations() {
+            return self.set.is_match(path);
         }
+        self.excludes(&self.set.matches(path))
     }
 
-    Ok(entries)
-}
-
-pub fn format_search_entries(entries: &[SearchEntry]) -> String {
-    let mut out = String::new();
-    for entry in entries {
-        let line = entry.line.map(|v| v.to_string()).unwrap_or_default();
-        out.push_str(&entry.display.replace('\t', "    "));
-        out.push('\t');
-        out.push_str(&entry.path.to_string_lossy());
-        out.push('\t');
-        out.push_str(&line);
-        out.push('\n');
+    pub fn has_negations(&self) -> bool {
+        self.negated.contains(&true)
     }
-    out
-}
 
-fn path_depth(path: &Path) -> usize {
-    path.components().count()
-}
+    /// The last matching pattern decides; `matched` comes back from the set in order.
+    fn excludes(&self, matched: &[usize]) -> bool {
+        matched.last().is_some_and(|&index| !self.negated[index])
+    }
 
-fn truncate_snippet(snippet: &str, max_chars: usize) -> String {
-    if snippet.chars().count() <= max_chars {
-        return snippet.to_string();
+    /// Whether some `!pattern` could match a path below `dir`, so an excluded
+    /// directory still has to be walked for the files it re-includes.
+    pub fn may_reinclude_under(&self, dir: &Path) -> bool {
+        self.patterns
+            .iter()
+            .zip(&self.negated)
+            .filter(|(_, negated)| **negated)
+            .any(|(pattern, _)| pattern_may_descend(split_negation(pattern).0, dir))
     }
 
-    let mut out = String::new();
-    for (idx, ch) in snippet.chars().enumerate() {
-        if idx >= max_chars {
-            break;
-        }
-        out.push(ch);
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
     }
-    out.push('…');
-    out
-}
 
-fn highlight_query_matches(text: &str, re: Option<&Regex>) -> String {
-    let Some(re) = re else {
-        return text.to_string();
-    };
+    pub fn origins(&self) -> &[GlobOrigin] {
+        &self.origins
+    }
 
-    let mut out = String::new();
-    let mut last = 0usize;
+    /// Like `is_match`, but credits every matching pattern when `hits` is given.
+    /// The plain path stays on `is_match` so normal searches pay nothing for stats.
+    pub fn is_match_counted<P: AsRef<Path>>(&self, path: P, hits: Option<&mut IgnoreHits>) -> bool {
+        let Some(hits) = hits else {
+            return self.is_match(path);
+        };
 
-    for matched in re.find_iter(text) {
-        if matched.start() > last {
-            out.push_str(&text[last..matched.start()]);
+        let matched = self
+            .set
+            .matches_candidate(&GlobCandidate::new(path.as_ref()));
+        if !self.excludes(&matched) {
+            return false;
         }
-        out.push_str("\x1b[1;36m");
-        out.push_str(matched.as_str());
-        out.push_str("\x1b[0m");
-        last = matched.end();
+        for index in matched.into_iter().filter(|index| !self.negated[*index]) {
+            hits.counts[index] += 1;
+        }
+        true
     }
 
-    if last < text.len() {
-        out.push_str(&text[last..]);
+    pub fn pattern_stats(&self, hits: &IgnoreHits) -> Vec<IgnorePatternStat> {
+        self.patterns
+            .iter()
+            .zip(&self.origins)
+            .zip(&hits.counts)
+            .map(|((pattern, origin), hits)| IgnorePatternStat {
+                pattern: pattern.clone(),
+                origin: origin.clone(),
+                hits: *hits,
+            })
+            .collect()
     }
+}
 
-    out
+/// `!pattern` as the glob and whether it was negated.
+fn split_negation(pattern: &str) -> (&str, bool) {
+    match pattern.strip_prefix('!') {
+        Some(glob) => (glob, true),
+        None => (pattern, false),
+    }
 }
 
-fn sort_candidates(candidates: &mut [Candidate], sort_mode: SortMode) {
-    match sort_mode {
-        SortMode::Depth => {
-            candidates.sort_by_key(|candidate| {
-                (
-                    path_depth(&candidate.path),
-                    candidate.path.to_string_lossy().to_string(),
-                )
-            });
+/// Compares `glob` with `dir` one component at a time: it may match something
+/// below `dir` unless a literal component rules the directory out first.
+fn pattern_may_descend(glob: &str, dir: &Path) -> bool {
+    let mut parts = glob.trim_start_matches('/').split('/');
+    for component in dir.components() {
+        let Some(part) = parts.next() else {
+            return false;
+        };
+        if part.contains("**") {
+            return true;
         }
-        SortMode::Alphabetical => {
-            candidates.sort_by_key(|candidate| candidate.path.to_string_lossy().to_string());
+        let matches = Glob::new(part)
+            .map(|part| part.compile_matcher().is_match(component.as_os_str()))
+            .unwrap_or(true);
+        if !matches {
+            return false;
         }
     }
+    parts.next().is_some()
 }
 
-fn collect_occurrences(
-    query: &str,
-    cwd: &Path,
-    settings: &YoinkSettings,
-) -> Result<HashMap<PathBuf, Vec<Occurrence>>> {
-    let mut rg_command = Command::new("rg");
-    rg_command
-        .arg("-n")
-        .arg("--column")
-        .arg("--no-heading")
-        .arg("--color=never")
-        .arg("--no-messages")
-        .arg("-e")
-        .arg(query);
-
-    if settings.include_hidden {
-        rg_command.arg("--hidden");
+impl PartialEq for GlobMatcher {
+    fn eq(&self, other: &Self) -> bool {
+        self.patterns == other.patterns
     }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct YoinkSettings {
+    pub include_hidden: bool,
+    pub include_mounts: bool,
+    pub include_symlinks: bool,
+    /// Fold hard links and bind-mounted copies of a file into one candidate
+    /// without following symlinks; a no-op off unix.
+    pub dedupe_inodes: bool,
+    /// Symlinked directories the walk passes through on the way to any path
+    /// under `include_symlinks`; `0` lists them without entering.
+    pub max_symlink_depth: usize,
+    /// Let rg read `RIPGREP_CONFIG_PATH`; off passes `--no-config` to every rg run.
+    pub inherit_rg_config: bool,
+    /// Skip what `.gitignore` / `.git/info/exclude` ignore in the walk; off hands rg `--no-ignore`.
+    pub respect_gitignore: bool,
+    pub sort_mode: SortMode,
+    /// Direction of `sort_mode=size`.
+    pub size_order: SizeOrder,
+    /// List directories before files under any `sort_mode`.
+    pub group_dirs_first: bool,
+    pub search_scope: SearchScope,
+    /// Split the query on whitespace into terms that must all match.
+    pub and_terms: bool,
+    /// Match the query only as whole words; see `QueryPattern::word_bounded`.
+    pub word_boundaries: bool,
+    /// Test the path regex against file names only, not the whole relative path.
+    pub match_basename_only: bool,
+    /// Let content matches span lines; see `QueryPattern::with_multiline`.
+    pub multiline: bool,
+    /// When a query lists nothing, list paths holding its chars in order instead.
+    pub fuzzy_fallback: bool,
+    pub file_lister: FileLister,
+    pub engine: ContentEngine,
+    pub show_language: bool,
+    /// Emoji icons, `↳` and `…` in rows; off spells them as words (`DIR`, `line 42:`, `truncated`).
+    pub icons: bool,
+    /// No ANSI anywhere, whatever the terminal supports; see `term::ColorMode::Plain`.
+    pub no_color: bool,
+    /// Screen-reader preset over `icons`, `no_color`, `show_language`, `teaser`
+    /// and `number_file_rows`, applied by `resolve_settings`; also drops match markers.
+    pub accessibility: bool,
+    pub auto_refresh_secs: Option<u64>,
+    /// Reload when files under the root change; see `refresh::SessionWatch`.
+    pub watch: bool,
+    /// List and search only files git tracks; see `git::GitFiles::tracked`.
+    pub tracked_only: bool,
+    /// List zip and tar members whose names match, as `archive::member` rows;
+    /// see `archive::list_members`.
+    pub archives: bool,
+    /// Reports, diffs and watch cycles slower than this ring the bell and post a desktop notification.
+    pub notify_after_secs: Option<u64>,
+    pub yank_context: usize,
+    /// Lines either side of each listed occurrence shown as dimmed rows under it; 0 for none.
+    pub snippet_context: usize,
+    /// Recent selections in this root listed atop an empty-query session; 0 turns them off.
+    pub recent_count: usize,
+    /// Record final queries and recall them with Ctrl-P / Alt-N.
+    pub history: bool,
+    /// Queries kept in fzf's history file; 0 turns history off.
+    pub history_size: usize,
+    pub show_timings: bool,
+    /// Lead each reload with a `files · matches · time` summary line.
+    pub show_stats: bool,
+    pub invalid_regex_fallback: RegexFallback,
+    pub unicode_fold: UnicodeFold,
+    /// SGR foreground codes for matches in file names and in content snippets.
+    pub path_match_color: u8,
+    pub content_match_color: u8,
+    /// Whether rg gets `-i` and the path/highlight regex ignores case, per query.
+    pub case_mode: CaseMode,
+    pub persist_session_prefs: bool,
+    pub teaser: bool,
+    /// Shorten paths wider than `PATH_DISPLAY_MAX_WIDTH` from the middle; see `ellipsize_path`.
+    pub path_ellipsis: bool,
+    pub max_entry_bytes: usize,
+    /// Passed to rg as `--max-columns` for occurrences; 0 leaves lines uncut.
+    pub max_columns: usize,
+    /// Files larger than this many bytes are skipped by content search (rg `--max-filesize`).
+    pub max_filesize: Option<u64>,
+    /// Also leave files past `max_filesize` out of the path listing.
+    pub size_filter_paths: bool,
+    /// List binary files (NUL in their first 8 KiB) as path matches.
+    pub show_binaries: bool,
+    /// Workers for the directory walk and parallel rg processes for the occurrence
+    /// pass over many files (also rg's own `-j`); 0 uses every core.
+    pub threads: usize,
+    /// Walk depth limit below the search root, for the walk and every rg pass;
+    /// `max_depth` in config, or `--max-depth` for one session.
+    pub max_depth: Option<usize>,
+    /// Candidates listed before the walk and rg output are cut short; see `results_cap_entry`.
+    pub max_results: Option<usize>,
+    /// Wall-clock limit on listing candidates; past it the walk stops, rg is
+    /// killed, and what was found so far is listed under a warning.
+    pub search_timeout: Option<Duration>,
+    /// Occurrences read per file, below `DEFAULT_OCCURRENCE_BUDGET`'s own cap.
+    pub max_occurrences_per_file: Option<usize>,
+    /// Occurrence rows listed per file before an "and N more" row; `0` leaves
+    /// only the file row, with its count.
+    pub occurrence_display_limit: usize,
+    /// Only files modified this recently are listed or searched; `modified_within`
+    /// in config, or `--changed-within` for one session.
+    pub modified_within: Option<Duration>,
+    /// Entry count past which an interactive launch asks before continuing; 0 never asks.
+    pub large_tree_threshold: usize,
+    /// Recapture the session's config snapshot when a config file's mtime changes.
+    pub hot_reload_config: bool,
+    pub confine_cd_to_root: bool,
+    /// Prefix the first nine file rows with `[1]`…`[9]` for the `Alt-<digit>` jumps.
+    pub number_file_rows: bool,
+    /// Adds `SMART_DEFAULT_GLOBS` to the excludes and `SMART_SUPPRESS_GLOBS` to `suppress_globs`.
+    pub smart_defaults: bool,
+    /// rg `--type` / `--type-not` names; files outside them are left out, directories stay.
+    pub file_types: FileTypeSet,
+    pub file_types_not: FileTypeSet,
+    /// File name suffixes (`min.js`, `lock`) left out of listing and content
+    /// search alike, compared case-insensitively.
+    pub exclude_extensions: HashSet<String>,
+    /// Session filter pills; only set inside a session (`alt-d` / `alt-x`).
+    pub pills: Vec<FilterPill>,
+    /// Session roots under the search root, for several `--root`s; empty for one.
+    pub roots: Vec<PathBuf>,
+    /// `--changed`'s git ref; only set inside a session.
+    pub changed_ref: Option<String>,
+    /// The files changed since `changed_ref` under the root being searched,
+    /// looked up once per search by `with_changed_files`.
+    pub changed_files: Option<GitFiles>,
+    /// `--rev`'s commit, searched in place of the work tree; only set inside a session.
+    pub rev: Option<String>,
+    /// `search.NAME = pattern` lines, run with `--saved NAME`.
+    pub saved_searches: BTreeMap<String, String>,
+    /// `preprocess.EXT = COMMAND` lines: lowercase extension to the command
+    /// whose stdout is searched in place of the file; see `extract_text`.
+    pub preprocessors: BTreeMap<String, String>,
+    /// Files listed by path whose contents are never searched.
+    pub suppress_globs: Vec<String>,
+    pub globset: GlobMatcher,
+    pub globs: Vec<String>,
+    pub demote_globset: GlobMatcher,
+    pub demote_globs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    pub walk: Duration,
+    pub rg_list: Duration,
+    pub occurrences: Duration,
+    pub format: Duration,
+    /// Rows whose display column was cut to `max_entry_bytes`.
+    pub truncated_rows: usize,
+    /// Candidates were left out once `max_results` were listed.
+    pub results_truncated: bool,
+    /// `search_timeout_ms` ran out before the candidates were all listed.
+    pub timed_out: bool,
+    /// Listed files matched by name alone.
+    pub path_files: usize,
+    /// Listed files with content matches, whatever their name.
+    pub content_files: usize,
+    /// Occurrences found in `content_files`, up to the occurrence budget.
+    pub matches: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchEntry {
+    pub display: String,
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    /// Why a file row is listed; `None` on occurrence, buffer and notice rows.
+    pub kind: Option<MatchKind>,
+    /// A file row's content match count, the fifth field of its fzf line;
+    /// `None` on path-only matches and every other row.
+    pub match_count: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Occurrence {
+    pub line: usize,
+    pub column: usize,
+    pub snippet: String,
+    /// rg cut the line at `max_columns`; `snippet` holds the stretch of the line
+    /// around the match (see `window_long_lines`), or `LONG_LINE_NOTE`.
+    pub long_line: bool,
+    /// `(line, text)` for up to `snippet_context` lines either side of the
+    /// match, read only for listed occurrences; empty otherwise.
+    pub context: Vec<(usize, String)>,
+}
+
+pub(crate) fn is_hidden_path(rel: &Path) -> bool {
+    rel.components().any(|component| {
+        let name = component.as_os_str().to_string_lossy();
+        name.starts_with('.')
+    })
+}
+
+/// Reads a duration like `30m`, `24h`, `7d` or `2w`; a bare number is seconds.
+pub fn parse_duration_setting(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (digits, unit_secs) = match value.chars().last()?.to_ascii_lowercase() {
+        's' => (&value[..value.len() - 1], 1),
+        'm' => (&value[..value.len() - 1], 60),
+        'h' => (&value[..value.len() - 1], 60 * 60),
+        'd' => (&value[..value.len() - 1], 24 * 60 * 60),
+        'w' => (&value[..value.len() - 1], 7 * 24 * 60 * 60),
+        _ => (value, 1),
+    };
+    let secs = digits.trim().parse::<u64>().ok()?.checked_mul(unit_secs)?;
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// Reads a size like `500K`, `10M` or `1G` (binary units, as rg reads them) or plain bytes.
+pub fn parse_size_setting(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let (digits, multiplier) = match value.chars().last()?.to_ascii_uppercase() {
+        'K' => (&value[..value.len() - 1], 1 << 10),
+        'M' => (&value[..value.len() - 1], 1 << 20),
+        'G' => (&value[..value.len() - 1], 1 << 30),
+        _ => (value, 1),
+    };
+    digits.trim().parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+/// Reads `min.js, .map, LOCK` as lowercase suffixes without their leading dot.
+/// `None` when an entry holds a path separator or glob syntax.
+pub fn parse_extension_list(value: &str) -> Option<HashSet<String>> {
+    value
+        .split(',')
+        .map(|ext| ext.trim().trim_start_matches('.').to_ascii_lowercase())
+        .filter(|ext| !ext.is_empty())
+        .map(|ext| (!ext.contains(['/', '\\', '*', '?', '[', ']', '{', '}', '!'])).then_some(ext))
+        .collect()
+}
+
+fn parse_bool_setting(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" | "on" => Some(true),
+        "false" | "0" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+pub fn parse_sort_mode_setting(value: &str) -> Option<SortMode> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "depth" => Some(SortMode::Depth),
+        "alphabetical" => Some(SortMode::Alphabetical),
+        "modified" | "mtime" => Some(SortMode::Modified),
+        "matches" => Some(SortMode::Matches),
+        "size" => Some(SortMode::Size),
+        "stream" => Some(SortMode::Stream),
+        _ => None,
+    }
+}
+
+pub fn parse_search_scope_setting(value: &str) -> Option<SearchScope> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "both" => Some(SearchScope::Both),
+        "paths" => Some(SearchScope::Paths),
+        "content" => Some(SearchScope::Content),
+        _ => None,
+    }
+}
+
+pub fn parse_size_order_setting(value: &str) -> Option<SizeOrder> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "asc" | "ascending" => Some(SizeOrder::Ascending),
+        "desc" | "descending" => Some(SizeOrder::Descending),
+        _ => None,
+    }
+}
+
+fn parse_content_engine_setting(value: &str) -> Option<ContentEngine> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "auto" => Some(ContentEngine::Auto),
+        "rg" => Some(ContentEngine::Rg),
+        "internal" => Some(ContentEngine::Internal),
+        _ => None,
+    }
+}
+
+fn parse_file_lister_setting(value: &str) -> Option<FileLister> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "walkdir" | "internal" => Some(FileLister::Walkdir),
+        "rg" => Some(FileLister::Rg),
+        _ => None,
+    }
+}
+
+/// Every key `apply_setting` accepts, for completion.
+pub const SETTING_KEYS: &[&str] = &[
+    "accessibility",
+    "and_terms",
+    "archives",
+    "auto_refresh_secs",
+    "case_insensitive",
+    "case_mode",
+    "confine_cd_to_root",
+    "content_match_color",
+    "dedupe_inodes",
+    "engine",
+    "exclude_extensions",
+    "file_lister",
+    "fuzzy_fallback",
+    "group_dirs_first",
+    "history",
+    "history_size",
+    "hot_reload_config",
+    "icons",
+    "include_hidden",
+    "include_mounts",
+    "include_symlinks",
+    "inherit_rg_config",
+    "invalid_regex_fallback",
+    "large_tree_threshold",
+    "match_basename_only",
+    "max_columns",
+    "max_line_bytes",
+    "max_depth",
+    "max_entry_bytes",
+    "max_filesize",
+    "max_occurrences_per_file",
+    "max_results",
+    "max_symlink_depth",
+    "modified_within",
+    "multiline",
+    "no_color",
+    "notify_after_secs",
+    "number_file_rows",
+    "occurrence_display_limit",
+    "path_ellipsis",
+    "path_match_color",
+    "persist_session_prefs",
+    "recent_count",
+    "respect_gitignore",
+    "search_scope",
+    "search_timeout_ms",
+    "show_binaries",
+    "show_language",
+    "show_stats",
+    "show_timings",
+    "size_filter_paths",
+    "size_order",
+    "smart_defaults",
+    "snippet_context",
+    "sort_mode",
+    "teaser",
+    "threads",
+    "tracked_only",
+    "types",
+    "types_not",
+    "unicode_fold",
+    "walker",
+    "watch",
+    "word_boundaries",
+    "yank_context",
+];
+
+/// Applies a `key=value` setting line; returns false when the key is not a known setting.
+fn apply_setting(
+    settings: &mut YoinkSettings,
+    key: &str,
+    value: &str,
+    path: &Path,
+) -> Result<bool> {
+    match key {
+        "include_hidden" => {
+            settings.include_hidden = parse_bool_setting(value).with_context(|| {
+                format!(
+                    "invalid include_hidden value in {}: {value}",
+                    path.display()
+                )
+            })?;
+        }
+        "dedupe_inodes" => {
+            settings.dedupe_inodes = parse_bool_setting(value).with_context(|| {
+                format!("invalid dedupe_inodes value in {}: {value}", path.display())
+            })?;
+        }
+        "inherit_rg_config" => {
+            settings.inherit_rg_config = parse_bool_setting(value).with_context(|| {
+                format!(
+                    "invalid inherit_rg_config value in {}: {value}",
+                    path.display()
+                )
+            })?;
+        }
+        "include_mounts" => {
+            settings.include_mounts = parse_bool_setting(value).with_context(|| {
+                format!(
+                    "invalid include_mounts value in {}: {value}",
+                    path.display()
+                )
+            })?;
+        }
+        "respect_gitignore" => {
+            settings.respect_gitignore = parse_bool_setting(value).with_context(|| {
+                format!(
+                    "invalid respect_gitignore value in {}: {value}",
+                    path.display()
+                )
+            })?;
+        }
+        "include_symlinks" => {
+            settings.include_symlinks = parse_bool_setting(value).with_context(|| {
+                format!(
+                    "invalid include_symlinks value in {}: {value}",
+                    path.display()
+                )
+            })?;
+        }
+        "icons" => {
+            settings.icons = parse_bool_setting(value)
+                .with_context(|| format!("invalid icons value in {}: {value}", path.display()))?;
+        }
+        "no_color" => {
+            settings.no_color = parse_bool_setting(value).with_context(|| {
+                format!("invalid no_color value in {}: {value}", path.display())
+            })?;
+        }
+        "accessibility" => {
+            settings.accessibility = parse_bool_setting(value).with_context(|| {
+                format!("invalid accessibility value in {}: {value}", path.display())
+            })?;
+        }
+        "show_binaries" => {
+            settings.show_binaries = parse_bool_setting(value).with_context(|| {
+                format!("invalid show_binaries value in {}: {value}", path.display())
+            })?;
+        }
+        "show_language" => {
+            settings.show_language = parse_bool_setting(value).with_context(|| {
+                format!("invalid show_language value in {}: {value}", path.display())
+            })?;
+        }
+        "auto_refresh_secs" => {
+            let secs = value.parse::<u64>().ok().with_context(|| {
+                format!(
+                    "invalid auto_refresh_secs value in {}: {value}",
+                    path.display()
+                )
+            })?;
+            settings.auto_refresh_secs = (secs > 0).then_some(secs);
+        }
+        "watch" => {
+            settings.watch = parse_bool_setting(value)
+                .with_context(|| format!("invalid watch value in {}: {value}", path.display()))?;
+        }
+        "tracked_only" => {
+            settings.tracked_only = parse_bool_setting(value).with_context(|| {
+                format!("invalid tracked_only value in {}: {value}", path.display())
+            })?;
+        }
+        "archives" => {
+            settings.archives = parse_bool_setting(value).with_context(|| {
+                format!("invalid archives value in {}: {value}", path.display())
+            })?;
+        }
+        "notify_after_secs" => {
+            let secs = value.parse::<u64>().ok().with_context(|| {
+                format!(
+                    "invalid notify_after_secs value in {}: {value}",
+                    path.display()
+                )
+            })?;
+            settings.notify_after_secs = (secs > 0).then_some(secs);
+        }
+        "engine" => {
+            settings.engine = parse_content_engine_setting(value)
+                .with_context(|| format!("invalid engine value in {}: {value}", path.display()))?;
+        }
+        // `walker` names the same choice.
+        "file_lister" | "walker" => {
+            settings.file_lister = parse_file_lister_setting(value)
+                .with_context(|| format!("invalid {key} value in {}: {value}", path.display()))?;
+        }
+        "recent_count" => {
+            settings.recent_count = value.parse::<usize>().ok().with_context(|| {
+                format!("invalid recent_count value in {}: {value}", path.display())
+            })?;
+        }
+        "snippet_context" => {
+            settings.snippet_context = value.parse::<usize>().ok().with_context(|| {
+                format!(
+                    "invalid snippet_context value in {}: {value}",
+                    path.display()
+                )
+            })?;
+        }
+        "yank_context" => {
+            settings.yank_context = value.parse::<usize>().ok().with_context(|| {
+                format!("invalid yank_context value in {}: {value}", path.display())
+            })?;
+        }
+        "invalid_regex_fallback" => {
+            settings.invalid_regex_fallback =
+                parse_regex_fallback_setting(value).with_context(|| {
+                    format!(
+                        "invalid invalid_regex_fallback value in {}: {value}",
+                        path.display()
+                    )
+                })?;
+        }
+        "unicode_fold" => {
+            settings.unicode_fold = parse_unicode_fold_setting(value).with_context(|| {
+                format!("invalid unicode_fold value in {}: {value}", path.display())
+            })?;
+        }
+        "path_match_color" => {
+            settings.path_match_color = parse_match_color_setting(value).with_context(|| {
+                format!(
+                    "invalid path_match_color value in {}: {value}",
+                    path.display()
+                )
+            })?;
+        }
+        "content_match_color" => {
+            settings.content_match_color = parse_match_color_setting(value).with_context(|| {
+                format!(
+                    "invalid content_match_color value in {}: {value}",
+                    path.display()
+                )
+            })?;
+        }
+        "case_mode" => {
+            settings.case_mode = parse_case_mode_setting(value).with_context(|| {
+                format!("invalid case_mode value in {}: {value}", path.display())
+            })?;
+        }
+        // The older boolean spelling of `case_mode = insensitive`.
+        "case_insensitive" => {
+            let insensitive = parse_bool_setting(value).with_context(|| {
+                format!(
+                    "invalid case_insensitive value in {}: {value}",
+                    path.display()
+                )
+            })?;
+            settings.case_mode = if insensitive {
+                CaseMode::Insensitive
+            } else {
+                CaseMode::Sensitive
+            };
+        }
+        "persist_session_prefs" => {
+            settings.persist_session_prefs = parse_bool_setting(value).with_context(|| {
+                format!(
+                    "invalid persist_session_prefs value in {}: {value}",
+                    path.display()
+                )
+            })?;
+        }
+        "path_ellipsis" => {
+            settings.path_ellipsis = parse_bool_setting(value).with_context(|| {
+                format!("invalid path_ellipsis value in {}: {value}", path.display())
+            })?;
+        }
+        "teaser" => {
+            settings.teaser = parse_bool_setting(value)
+                .with_context(|| format!("invalid teaser value in {}: {value}", path.display()))?;
+        }
+        "max_entry_bytes" => {
+            settings.max_entry_bytes = value.parse::<usize>().ok().with_context(|| {
+                format!(
+                    "invalid max_entry_bytes value in {}: {value}",
+                    path.display()
+                )
+            })?;
+        }
+        "history" => {
+            settings.history = parse_bool_setting(value)
+                .with_context(|| format!("invalid history value in {}: {value}", path.display()))?;
+        }
+        "history_size" => {
+            settings.history_size = value.parse::<usize>().ok().with_context(|| {
+                format!("invalid history_size value in {}: {value}", path.display())
+            })?;
+        }
+        "modified_within" => {
+            settings.modified_within = Some(parse_duration_setting(value).with_context(|| {
+                format!(
+                    "invalid modified_within value in {}: {value}",
+                    path.display()
+                )
+            })?);
+        }
+        "max_depth" => {
+            let depth = value.parse::<usize>().ok().filter(|depth| *depth > 0);
+            settings.max_depth = Some(depth.with_context(|| {
+                format!("invalid max_depth value in {}: {value}", path.display())
+            })?);
+        }
+        "max_results" => {
+            let cap = value.parse::<usize>().ok().filter(|cap| *cap > 0);
+            settings.max_results = Some(cap.with_context(|| {
+                format!("invalid max_results value in {}: {value}", path.display())
+            })?);
+        }
+        "max_occurrences_per_file" => {
+            let cap = value.parse::<usize>().ok().filter(|cap| *cap > 0);
+            settings.max_occurrences_per_file = Some(cap.with_context(|| {
+                format!(
+                    "invalid max_occurrences_per_file value in {}: {value}",
+                    path.display()
+                )
+            })?);
+        }
+        "search_timeout_ms" => {
+            let millis = value.parse::<u64>().ok().with_context(|| {
+                format!(
+                    "invalid search_timeout_ms value in {}: {value}",
+                    path.display()
+                )
+            })?;
+            settings.search_timeout = (millis > 0).then(|| Duration::from_millis(millis));
+        }
+        "occurrence_display_limit" => {
+            settings.occurrence_display_limit = value.parse::<usize>().ok().with_context(|| {
+                format!(
+                    "invalid occurrence_display_limit value in {}: {value}",
+                    path.display()
+                )
+            })?;
+        }
+        "max_filesize" => {
+            let bytes = parse_size_setting(value).with_context(|| {
+                format!("invalid max_filesize value in {}: {value}", path.display())
+            })?;
+            settings.max_filesize = (bytes > 0).then_some(bytes);
+        }
+        "size_filter_paths" => {
+            settings.size_filter_paths = parse_bool_setting(value).with_context(|| {
+                format!(
+                    "invalid size_filter_paths value in {}: {value}",
+                    path.display()
+                )
+            })?;
+        }
+        "max_symlink_depth" => {
+            settings.max_symlink_depth = value.parse::<usize>().ok().with_context(|| {
+                format!(
+                    "invalid max_symlink_depth value in {}: {value}",
+                    path.display()
+                )
+            })?;
+        }
+        "threads" => {
+            settings.threads = value
+                .parse::<usize>()
+                .ok()
+                .with_context(|| format!("invalid threads value in {}: {value}", path.display()))?;
+        }
+        // `max_line_bytes` names the same limit by what it counts.
+        "max_columns" | "max_line_bytes" => {
+            settings.max_columns = value
+                .parse::<usize>()
+                .ok()
+                .with_context(|| format!("invalid {key} value in {}: {value}", path.display()))?;
+        }
+        "exclude_extensions" => {
+            settings.exclude_extensions = parse_extension_list(value).with_context(|| {
+                format!(
+                    "invalid exclude_extensions value in {}: {value}",
+                    path.display()
+                )
+            })?;
+        }
+        "types" => {
+            settings.file_types = FileTypeSet::parse(value)
+                .ok()
+                .with_context(|| format!("invalid types value in {}: {value}", path.display()))?;
+        }
+        "types_not" => {
+            settings.file_types_not = FileTypeSet::parse(value).ok().with_context(|| {
+                format!("invalid types_not value in {}: {value}", path.display())
+            })?;
+        }
+        "large_tree_threshold" => {
+            settings.large_tree_threshold = value.parse::<usize>().ok().with_context(|| {
+                format!(
+                    "invalid large_tree_threshold value in {}: {value}",
+                    path.display()
+                )
+            })?;
+        }
+        "hot_reload_config" => {
+            settings.hot_reload_config = parse_bool_setting(value).with_context(|| {
+                format!(
+                    "invalid hot_reload_config value in {}: {value}",
+                    path.display()
+                )
+            })?;
+        }
+        "confine_cd_to_root" => {
+            settings.confine_cd_to_root = parse_bool_setting(value).with_context(|| {
+                format!(
+                    "invalid confine_cd_to_root value in {}: {value}",
+                    path.display()
+                )
+            })?;
+        }
+        "number_file_rows" => {
+            settings.number_file_rows = parse_bool_setting(value).with_context(|| {
+                format!(
+                    "invalid number_file_rows value in {}: {value}",
+                    path.display()
+                )
+            })?;
+        }
+        "smart_defaults" => {
+            settings.smart_defaults = parse_bool_setting(value).with_context(|| {
+                format!(
+                    "invalid smart_defaults value in {}: {value}",
+                    path.display()
+                )
+            })?;
+        }
+        "show_stats" => {
+            settings.show_stats = parse_bool_setting(value).with_context(|| {
+                format!("invalid show_stats value in {}: {value}", path.display())
+            })?;
+        }
+        "show_timings" => {
+            settings.show_timings = parse_bool_setting(value).with_context(|| {
+                format!("invalid show_timings value in {}: {value}", path.display())
+            })?;
+        }
+        "sort_mode" => {
+            settings.sort_mode = parse_sort_mode_setting(value).with_context(|| {
+                format!("invalid sort_mode value in {}: {value}", path.display())
+            })?;
+        }
+        "and_terms" => {
+            settings.and_terms = parse_bool_setting(value).with_context(|| {
+                format!("invalid and_terms value in {}: {value}", path.display())
+            })?;
+        }
+        "fuzzy_fallback" => {
+            settings.fuzzy_fallback = parse_bool_setting(value).with_context(|| {
+                format!(
+                    "invalid fuzzy_fallback value in {}: {value}",
+                    path.display()
+                )
+            })?;
+        }
+        "multiline" => {
+            settings.multiline = parse_bool_setting(value).with_context(|| {
+                format!("invalid multiline value in {}: {value}", path.display())
+            })?;
+        }
+        "match_basename_only" => {
+            settings.match_basename_only = parse_bool_setting(value).with_context(|| {
+                format!(
+                    "invalid match_basename_only value in {}: {value}",
+                    path.display()
+                )
+            })?;
+        }
+        "word_boundaries" => {
+            settings.word_boundaries = parse_bool_setting(value).with_context(|| {
+                format!(
+                    "invalid word_boundaries value in {}: {value}",
+                    path.display()
+                )
+            })?;
+        }
+        "search_scope" => {
+            settings.search_scope = parse_search_scope_setting(value).with_context(|| {
+                format!("invalid search_scope value in {}: {value}", path.display())
+            })?;
+        }
+        "group_dirs_first" => {
+            settings.group_dirs_first = parse_bool_setting(value).with_context(|| {
+                format!(
+                    "invalid group_dirs_first value in {}: {value}",
+                    path.display()
+                )
+            })?;
+        }
+        "size_order" => {
+            settings.size_order = parse_size_order_setting(value).with_context(|| {
+                format!("invalid size_order value in {}: {value}", path.display())
+            })?;
+        }
+        key if key.starts_with(SAVED_SEARCH_PREFIX) => {
+            let name = &key[SAVED_SEARCH_PREFIX.len()..];
+            if name.is_empty() || value.is_empty() {
+                anyhow::bail!(
+                    "invalid saved search in {}: {key} = {value}",
+                    path.display()
+                );
+            }
+            settings
+                .saved_searches
+                .insert(name.to_string(), value.to_string());
+        }
+        key if key.starts_with(PREPROCESS_PREFIX) => {
+            let ext = key[PREPROCESS_PREFIX.len()..]
+                .trim_start_matches('.')
+                .to_ascii_lowercase();
+            if ext.is_empty() || value.is_empty() {
+                anyhow::bail!(
+                    "invalid preprocess command in {}: {key} = {value}",
+                    path.display()
+                );
+            }
+            settings.preprocessors.insert(ext, value.to_string());
+        }
+        _ => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+/// Config keys `search.NAME` name a saved search.
+const SAVED_SEARCH_PREFIX: &str = "search.";
+/// Config keys `preprocess.EXT` name the text extractor for files ending `.EXT`.
+const PREPROCESS_PREFIX: &str = "preprocess.";
+
+/// The pattern saved as `name`; an unknown name lists the ones there are.
+pub fn saved_search<'a>(settings: &'a YoinkSettings, name: &str) -> Result<&'a str> {
+    if let Some(pattern) = settings.saved_searches.get(&name.to_ascii_lowercase()) {
+        return Ok(pattern);
+    }
+    if settings.saved_searches.is_empty() {
+        anyhow::bail!("unknown saved search {name}: none are configured (add `search.{name} = PATTERN` to the config)");
+    }
+    let names: Vec<&str> = settings.saved_searches.keys().map(String::as_str).collect();
+    anyhow::bail!(
+        "unknown saved search {name}; available: {}",
+        names.join(", ")
+    )
+}
+
+/// `yoink saved`: one `name = pattern` line per saved search.
+pub fn format_saved_searches(settings: &YoinkSettings) -> String {
+    let width = settings
+        .saved_searches
+        .keys()
+        .map(String::len)
+        .max()
+        .unwrap_or(0);
+    settings
+        .saved_searches
+        .iter()
+        .map(|(name, pattern)| format!("{name:<width$} = {pattern}\n"))
+        .collect()
+}
+
+fn apply_config_file(
+    settings: &mut YoinkSettings,
+    origins: &mut Vec<GlobOrigin>,
+    source: &ConfigSource,
+    content: &str,
+) -> Result<()> {
+    let path = &source.path;
+    // The `[table]` a config.toml key sits under, as a `table.` key prefix.
+    let mut table = String::new();
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        // config.toml only carries settings, so a line that isn't one is an error.
+        if source.kind == ConfigKind::Settings {
+            let number = index + 1;
+            let uncommented = trimmed.split('#').next().unwrap_or_default().trim();
+            if let Some(name) = uncommented
+                .strip_prefix('[')
+                .and_then(|rest| rest.strip_suffix(']'))
+            {
+                table = format!("{}.", name.trim().to_ascii_lowercase());
+                continue;
+            }
+            let (raw_key, raw_value) = trimmed.split_once('=').with_context(|| {
+                format!(
+                    "expected key = value in {}:{number}: {trimmed}",
+                    path.display()
+                )
+            })?;
+            let key = format!("{table}{}", raw_key.trim().to_ascii_lowercase());
+            let value = toml_value(raw_value).with_context(|| {
+                format!(
+                    "unterminated value in {}:{number}: {trimmed}",
+                    path.display()
+                )
+            })?;
+            if !apply_setting(settings, &key, value, path)? {
+                anyhow::bail!("unknown setting in {}:{number}: {key}", path.display());
+            }
+            continue;
+        }
+
+        if let Some((raw_key, raw_value)) = trimmed.split_once('=') {
+            let key = raw_key.trim().to_ascii_lowercase();
+            let value = raw_value.trim().trim_matches('"');
+            if apply_setting(settings, &key, value, path)? {
+                continue;
+            }
+        }
+
+        // A pattern listed again, say by a project's `.yoinkignore`, takes the
+        // later file's meaning: excluded or demoted, never both or twice.
+        if let Some(at) = settings
+            .globs
+            .iter()
+            .position(|glob| glob == trimmed.trim_start_matches('~').trim())
+        {
+            settings.globs.remove(at);
+            origins.remove(at);
+        }
+
+        // `~pattern` demotes matches instead of excluding them.
+        if let Some(pattern) = trimmed.strip_prefix('~') {
+            let pattern = pattern.trim().to_string();
+            settings
+                .demote_globs
+                .retain(|existing| *existing != pattern);
+            settings.demote_globs.push(pattern);
+            continue;
+        }
+
+        settings.demote_globs.retain(|existing| existing != trimmed);
+        settings.globs.push(trimmed.to_string());
+        origins.push(GlobOrigin {
+            file: Some(path.clone()),
+            line: index + 1,
+            ..GlobOrigin::default()
+        });
+    }
+
+    Ok(())
+}
+
+/// A config.toml value: a quoted string's contents, taken as written, or bare
+/// text up to a trailing `# comment`. `None` when a quote isn't closed or
+/// something other than a comment follows it.
+fn toml_value(raw: &str) -> Option<&str> {
+    let raw = raw.trim();
+    let (value, rest) = match raw.chars().next() {
+        Some(quote @ ('"' | '\'')) => {
+            let mut escaped = false;
+            let (end, _) = raw.char_indices().skip(1).find(|&(_, ch)| {
+                let closes = ch == quote && !escaped;
+                escaped = quote == '"' && ch == '\\' && !escaped;
+                closes
+            })?;
+            (&raw[1..end], &raw[end + 1..])
+        }
+        _ => {
+            let end = raw
+                .char_indices()
+                .find(|&(at, ch)| ch == '#' && raw[..at].ends_with(char::is_whitespace))
+                .map_or(raw.len(), |(at, _)| at);
+            (raw[..end].trim_end(), "")
+        }
+    };
+    let rest = rest.trim();
+    (rest.is_empty() || rest.starts_with('#')).then_some(value)
+}
+
+impl Default for YoinkSettings {
+    fn default() -> Self {
+        YoinkSettings {
+            include_hidden: false,
+            include_mounts: false,
+            include_symlinks: false,
+            dedupe_inodes: false,
+            max_symlink_depth: DEFAULT_MAX_SYMLINK_DEPTH,
+            inherit_rg_config: false,
+            respect_gitignore: true,
+            sort_mode: SortMode::Depth,
+            size_order: SizeOrder::Ascending,
+            group_dirs_first: false,
+            search_scope: SearchScope::Both,
+            and_terms: false,
+            word_boundaries: false,
+            match_basename_only: false,
+            multiline: false,
+            fuzzy_fallback: false,
+            file_lister: FileLister::Walkdir,
+            engine: ContentEngine::Auto,
+            show_language: true,
+            icons: true,
+            no_color: false,
+            accessibility: false,
+            auto_refresh_secs: None,
+            watch: false,
+            tracked_only: false,
+            archives: false,
+            notify_after_secs: None,
+            yank_context: 5,
+            snippet_context: 0,
+            recent_count: DEFAULT_RECENT_COUNT,
+            history: true,
+            history_size: DEFAULT_HISTORY_SIZE,
+            show_timings: false,
+            show_stats: true,
+            invalid_regex_fallback: RegexFallback::Literal,
+            unicode_fold: UnicodeFold::Off,
+            case_mode: CaseMode::Sensitive,
+            path_match_color: PATH_MATCH_COLOR,
+            content_match_color: CONTENT_MATCH_COLOR,
+            persist_session_prefs: false,
+            teaser: false,
+            path_ellipsis: true,
+            max_entry_bytes: DEFAULT_MAX_ENTRY_BYTES,
+            max_columns: DEFAULT_MAX_COLUMNS,
+            max_filesize: None,
+            size_filter_paths: false,
+            show_binaries: false,
+            threads: 0,
+            max_depth: None,
+            max_results: None,
+            max_occurrences_per_file: None,
+            search_timeout: None,
+            occurrence_display_limit: DEFAULT_OCCURRENCE_DISPLAY_LIMIT,
+            modified_within: None,
+            large_tree_threshold: DEFAULT_LARGE_TREE_THRESHOLD,
+            hot_reload_config: false,
+            confine_cd_to_root: true,
+            number_file_rows: true,
+            smart_defaults: true,
+            file_types: FileTypeSet::default(),
+            file_types_not: FileTypeSet::default(),
+            exclude_extensions: HashSet::new(),
+            pills: Vec::new(),
+            roots: Vec::new(),
+            changed_ref: None,
+            changed_files: None,
+            rev: None,
+            saved_searches: BTreeMap::new(),
+            preprocessors: BTreeMap::new(),
+            suppress_globs: Vec::new(),
+            globset: GlobMatcher::default(),
+            globs: DEFAULT_IGNORE_GLOBS
+                .iter()
+                .map(|pattern| pattern.to_string())
+                .collect(),
+            demote_globset: GlobMatcher::default(),
+            demote_globs: Vec::new(),
+        }
+    }
+}
+
+/// Settings from config, with the running session's overrides (saved prefs,
+/// CLI flags and runtime toggles, already merged) layered on top.
+/// Settings from the global config files only; see `load_settings_for`.
+pub fn load_settings() -> Result<YoinkSettings> {
+    load_settings_with(None)
+}
+
+/// Settings for a search rooted at `root`, with its `.yoinkignore` merged over
+/// the global files. Inside a session the launch snapshot already holds it.
+pub fn load_settings_for(root: &Path) -> Result<YoinkSettings> {
+    load_settings_with(Some(root))
+}
+
+fn load_settings_with(root: Option<&Path>) -> Result<YoinkSettings> {
+    let sources = config_sources(root);
+    let Some(session) = Session::from_env() else {
+        return Ok(resolve_settings(load_settings_from(&sources)?, &[]));
+    };
+    let mut settings = load_session_settings(&session, &sources)?;
+    let state = session.load();
+    settings.pills = state.pills.clone();
+    settings.roots = state.roots.clone();
+    settings.changed_ref = state.changed_ref.clone();
+    settings.rev = state.rev.clone();
+    Ok(resolve_settings(settings, &[state.overrides()]))
+}
+
+/// Settings from the session's config snapshot, so edits mid-session don't
+/// change results between reloads. The snapshot is parsed once and cached
+/// beside the session state (see `encode_settings`); a missing cache, or one
+/// parsed from another snapshot, is rebuilt. With `hot_reload_config`, a
+/// snapshot whose files changed on disk is recaptured first; `alt-i` always
+/// recaptures. Under `--fresh-settings` every call reads the files instead.
+pub fn load_session_settings(session: &Session, sources: &[ConfigSource]) -> Result<YoinkSettings> {
+    let state = session.load();
+    if state.fresh_settings {
+        return load_settings_from(sources);
+    }
+    if let Some(snapshot) = state.config {
+        let fingerprint = snapshot.fingerprint();
+        let cached = session
+            .settings_cache()
+            .and_then(|content| decode_settings(&content, fingerprint));
+        let settings = match cached {
+            Some(settings) => settings,
+            None => {
+                let settings = load_settings_from_snapshot(&snapshot)?;
+                session.store_settings_cache(&encode_settings(&settings, fingerprint))?;
+                settings
+            }
+        };
+        if !(settings.hot_reload_config && snapshot.is_stale(sources)) {
+            return Ok(settings);
+        }
+    }
+    reload_session_config(session, sources)
+}
+
+/// Re-reads the config files into the session snapshot and its settings
+/// cache. A config that fails to parse leaves the previous snapshot in place.
+pub fn reload_session_config(session: &Session, sources: &[ConfigSource]) -> Result<YoinkSettings> {
+    let snapshot = ConfigSnapshot::capture(sources)?;
+    let settings = load_settings_from_snapshot(&snapshot)?;
+    session.set_config(Some(snapshot.clone()))?;
+    session.store_settings_cache(&encode_settings(&settings, snapshot.fingerprint()))?;
+    Ok(settings)
+}
+
+/// Bumped whenever the cache layout changes, so an older cache is rebuilt.
+const SETTINGS_CACHE_VERSION: u64 = 1;
+
+/// Every scalar setting as the `key = value` text `apply_setting` reads back;
+/// options left unset are skipped, keeping their defaults.
+fn setting_lines(settings: &YoinkSettings) -> Vec<(&'static str, String)> {
+    let or_zero = |value: Option<u64>| value.unwrap_or(0).to_string();
+    let mut lines = vec![
+        ("include_hidden", settings.include_hidden.to_string()),
+        ("include_mounts", settings.include_mounts.to_string()),
+        ("include_symlinks", settings.include_symlinks.to_string()),
+        ("dedupe_inodes", settings.dedupe_inodes.to_string()),
+        ("max_symlink_depth", settings.max_symlink_depth.to_string()),
+        ("inherit_rg_config", settings.inherit_rg_config.to_string()),
+        ("respect_gitignore", settings.respect_gitignore.to_string()),
+        ("sort_mode", settings.sort_mode.as_str().to_string()),
+        (
+            "size_order",
+            match settings.size_order {
+                SizeOrder::Ascending => "asc",
+                SizeOrder::Descending => "desc",
+            }
+            .to_string(),
+        ),
+        ("group_dirs_first", settings.group_dirs_first.to_string()),
+        ("search_scope", settings.search_scope.as_str().to_string()),
+        ("and_terms", settings.and_terms.to_string()),
+        ("word_boundaries", settings.word_boundaries.to_string()),
+        (
+            "match_basename_only",
+            settings.match_basename_only.to_string(),
+        ),
+        ("multiline", settings.multiline.to_string()),
+        ("fuzzy_fallback", settings.fuzzy_fallback.to_string()),
+        (
+            "file_lister",
+            match settings.file_lister {
+                FileLister::Walkdir => "walkdir",
+                FileLister::Rg => "rg",
+            }
+            .to_string(),
+        ),
+        ("engine", settings.engine.as_str().to_string()),
+        ("show_language", settings.show_language.to_string()),
+        ("icons", settings.icons.to_string()),
+        ("no_color", settings.no_color.to_string()),
+        ("accessibility", settings.accessibility.to_string()),
+        ("auto_refresh_secs", or_zero(settings.auto_refresh_secs)),
+        ("watch", settings.watch.to_string()),
+        ("tracked_only", settings.tracked_only.to_string()),
+        ("archives", settings.archives.to_string()),
+        ("notify_after_secs", or_zero(settings.notify_after_secs)),
+        ("yank_context", settings.yank_context.to_string()),
+        ("snippet_context", settings.snippet_context.to_string()),
+        ("recent_count", settings.recent_count.to_string()),
+        ("history", settings.history.to_string()),
+        ("history_size", settings.history_size.to_string()),
+        ("show_timings", settings.show_timings.to_string()),
+        ("show_stats", settings.show_stats.to_string()),
+        (
+            "invalid_regex_fallback",
+            match settings.invalid_regex_fallback {
+                RegexFallback::Literal => "literal",
+                RegexFallback::Strict => "strict",
+            }
+            .to_string(),
+        ),
+        (
+            "unicode_fold",
+            match settings.unicode_fold {
+                UnicodeFold::Off => "off",
+                UnicodeFold::Case => "case",
+                UnicodeFold::Accents => "accents",
+            }
+            .to_string(),
+        ),
+        ("path_match_color", settings.path_match_color.to_string()),
+        (
+            "content_match_color",
+            settings.content_match_color.to_string(),
+        ),
+        ("case_mode", settings.case_mode.as_str().to_string()),
+        (
+            "persist_session_prefs",
+            settings.persist_session_prefs.to_string(),
+        ),
+        ("teaser", settings.teaser.to_string()),
+        ("path_ellipsis", settings.path_ellipsis.to_string()),
+        ("max_entry_bytes", settings.max_entry_bytes.to_string()),
+        ("max_columns", settings.max_columns.to_string()),
+        ("max_filesize", or_zero(settings.max_filesize)),
+        ("size_filter_paths", settings.size_filter_paths.to_string()),
+        ("show_binaries", settings.show_binaries.to_string()),
+        ("threads", settings.threads.to_string()),
+        (
+            "search_timeout_ms",
+            or_zero(
+                settings
+                    .search_timeout
+                    .map(|timeout| timeout.as_millis() as u64),
+            ),
+        ),
+        (
+            "occurrence_display_limit",
+            settings.occurrence_display_limit.to_string(),
+        ),
+        (
+            "large_tree_threshold",
+            settings.large_tree_threshold.to_string(),
+        ),
+        ("hot_reload_config", settings.hot_reload_config.to_string()),
+        (
+            "confine_cd_to_root",
+            settings.confine_cd_to_root.to_string(),
+        ),
+        ("number_file_rows", settings.number_file_rows.to_string()),
+        ("smart_defaults", settings.smart_defaults.to_string()),
+    ];
+    if let Some(window) = settings.modified_within {
+        lines.push(("modified_within", format!("{}s", window.as_secs())));
+    }
+    for (key, value) in [
+        ("max_depth", settings.max_depth),
+        ("max_results", settings.max_results),
+        (
+            "max_occurrences_per_file",
+            settings.max_occurrences_per_file,
+        ),
+    ] {
+        if let Some(value) = value {
+            lines.push((key, value.to_string()));
+        }
+    }
+    for (key, types) in [
+        ("types", settings.file_types),
+        ("types_not", settings.file_types_not),
+    ] {
+        if !types.is_empty() {
+            lines.push((key, types.names().join(",")));
+        }
+    }
+    if !settings.exclude_extensions.is_empty() {
+        let mut extensions: Vec<&str> = settings
+            .exclude_extensions
+            .iter()
+            .map(String::as_str)
+            .collect();
+        extensions.sort_unstable();
+        lines.push(("exclude_extensions", extensions.join(",")));
+    }
+    lines
+}
+
+/// Settings parsed from the snapshot with `fingerprint`, for the session's
+/// settings cache. Scalars are stored as their config lines; globs keep their
+/// origins, so `config stats` reads the same from a cached load.
+pub fn encode_settings(settings: &YoinkSettings, fingerprint: u64) -> String {
+    let strings = |values: &[String]| -> String {
+        let values: Vec<String> = values.iter().map(|value| json_string(value)).collect();
+        format!("[{}]", values.join(","))
+    };
+    let saved = settings
+        .saved_searches
+        .iter()
+        .map(|(name, pattern)| (format!("{SAVED_SEARCH_PREFIX}{name}"), pattern.clone()));
+    let preprocess = settings
+        .preprocessors
+        .iter()
+        .map(|(ext, command)| (format!("{PREPROCESS_PREFIX}{ext}"), command.clone()));
+    let lines: Vec<String> = setting_lines(settings)
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .chain(saved)
+        .chain(preprocess)
+        .map(|(key, value)| format!("[{},{}]", json_string(&key), json_string(&value)))
+        .collect();
+    let globs: Vec<String> = settings
+        .globs
+        .iter()
+        .enumerate()
+        .map(|(index, pattern)| {
+            let origin = settings
+                .globset
+                .origins()
+                .get(index)
+                .cloned()
+                .unwrap_or_default();
+            let file = match &origin.file {
+                Some(file) => json_string(&file.to_string_lossy()),
+                None => "null".to_string(),
+            };
+            format!(
+                "[{},{file},{},{}]",
+                json_string(pattern),
+                origin.line,
+                origin.smart_default
+            )
+        })
+        .collect();
+    let pills: Vec<String> = settings
+        .pills
+        .iter()
+        .map(|pill| {
+            format!(
+                "[{},{}]",
+                json_string(pill.kind()),
+                json_string(&pill.value())
+            )
+        })
+        .collect();
+
+    format!(
+        "{{\"version\":{SETTINGS_CACHE_VERSION},\"fingerprint\":{fingerprint},\"settings\":[{}],\"globs\":[{}],\"demote_globs\":{},\"suppress_globs\":{},\"pills\":[{}]}}\n",
+        lines.join(","),
+        globs.join(","),
+        strings(&settings.demote_globs),
+        strings(&settings.suppress_globs),
+        pills.join(",")
+    )
+}
+
+/// Reads back `encode_settings`. `None` for anything else, an older layout,
+/// or settings parsed from a snapshot other than the one with `fingerprint`.
+pub fn decode_settings(content: &str, fingerprint: u64) -> Option<YoinkSettings> {
+    let strings = |items: Vec<JsonValue>| -> Option<Vec<String>> {
+        items
+            .into_iter()
+            .map(|item| match item {
+                JsonValue::String(value) => Some(value),
+                _ => None,
+            })
+            .collect()
+    };
+
+    let mut version = None;
+    let mut stamp = None;
+    let mut lines = Vec::new();
+    let mut globs = Vec::new();
+    let mut origins = Vec::new();
+    let mut demote_globs = Vec::new();
+    let mut suppress_globs = Vec::new();
+    let mut pills = Vec::new();
+    decode_json_object(content, |key, value| {
+        match (key, value) {
+            ("version", JsonValue::Number(number)) => version = Some(number),
+            ("fingerprint", JsonValue::Number(number)) => stamp = Some(number),
+            ("settings", JsonValue::Array(items)) => {
+                for item in items {
+                    let JsonValue::Array(pair) = item else {
+                        return None;
+                    };
+                    let mut pair = strings(pair)?.into_iter();
+                    let (Some(key), Some(value), None) = (pair.next(), pair.next(), pair.next())
+                    else {
+                        return None;
+                    };
+                    lines.push((key, value));
+                }
+            }
+            ("globs", JsonValue::Array(items)) => {
+                for item in items {
+                    let JsonValue::Array(fields) = item else {
+                        return None;
+                    };
+                    let mut fields = fields.into_iter();
+                    let (
+                        Some(JsonValue::String(pattern)),
+                        Some(file),
+                        Some(JsonValue::Number(line)),
+                        Some(JsonValue::Bool(smart_default)),
+                        None,
+                    ) = (
+                        fields.next(),
+                        fields.next(),
+                        fields.next(),
+                        fields.next(),
+                        fields.next(),
+                    )
+                    else {
+                        return None;
+                    };
+                    let file = match file {
+                        JsonValue::String(file) => Some(PathBuf::from(file)),
+                        JsonValue::Null => None,
+                        _ => return None,
+                    };
+                    globs.push(pattern);
+                    origins.push(GlobOrigin {
+                        file,
+                        line: usize::try_from(line).ok()?,
+                        smart_default,
+                    });
+                }
+            }
+            ("demote_globs", JsonValue::Array(items)) => demote_globs = strings(items)?,
+            ("suppress_globs", JsonValue::Array(items)) => suppress_globs = strings(items)?,
+            ("pills", JsonValue::Array(items)) => {
+                for item in items {
+                    let JsonValue::Array(parts) = item else {
+                        return None;
+                    };
+                    let mut parts = strings(parts)?.into_iter();
+                    let (Some(kind), Some(value), None) =
+                        (parts.next(), parts.next(), parts.next())
+                    else {
+                        return None;
+                    };
+                    pills.push(FilterPill::from_parts(&kind, &value)?);
+                }
+            }
+            _ => return None,
+        }
+        Some(())
+    })?;
+    if version != Some(SETTINGS_CACHE_VERSION) || stamp != Some(fingerprint) {
+        return None;
+    }
+
+    let mut settings = YoinkSettings::default();
+    let cache = Path::new("the settings cache");
+    for (key, value) in &lines {
+        if !apply_setting(&mut settings, key, value, cache).ok()? {
+            return None;
+        }
+    }
+    // The globs are stored whole, smart defaults already in place.
+    settings.globset = GlobMatcher::with_origins(&globs, &origins).ok()?;
+    settings.globs = globs;
+    settings.demote_globset = GlobMatcher::new(&demote_globs).ok()?;
+    settings.demote_globs = demote_globs;
+    settings.suppress_globs = suppress_globs;
+    settings.pills = pills;
+    Some(settings)
+}
+
+/// One layer of session-level options; `None` defers to the layer below.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SettingOverrides {
+    pub include_hidden: Option<bool>,
+    pub include_symlinks: Option<bool>,
+    pub include_mounts: Option<bool>,
+    pub sort_mode: Option<SortMode>,
+    pub max_depth: Option<usize>,
+    pub modified_within: Option<Duration>,
+    pub smart_defaults: Option<bool>,
+    pub accessibility: Option<bool>,
+    pub search_scope: Option<SearchScope>,
+    pub word_boundaries: Option<bool>,
+    pub match_basename_only: Option<bool>,
+    pub multiline: Option<bool>,
+    pub file_types: Option<FileTypeSet>,
+    pub file_types_not: Option<FileTypeSet>,
+    pub watch: Option<bool>,
+    pub tracked_only: Option<bool>,
+}
+
+impl SettingOverrides {
+    pub fn is_empty(&self) -> bool {
+        *self == SettingOverrides::default()
+    }
+
+    /// Combines two layers; values set in `upper` win.
+    pub fn layered_under(self, upper: SettingOverrides) -> SettingOverrides {
+        SettingOverrides {
+            include_hidden: upper.include_hidden.or(self.include_hidden),
+            include_symlinks: upper.include_symlinks.or(self.include_symlinks),
+            include_mounts: upper.include_mounts.or(self.include_mounts),
+            sort_mode: upper.sort_mode.or(self.sort_mode),
+            max_depth: upper.max_depth.or(self.max_depth),
+            modified_within: upper.modified_within.or(self.modified_within),
+            smart_defaults: upper.smart_defaults.or(self.smart_defaults),
+            accessibility: upper.accessibility.or(self.accessibility),
+            search_scope: upper.search_scope.or(self.search_scope),
+            word_boundaries: upper.word_boundaries.or(self.word_boundaries),
+            match_basename_only: upper.match_basename_only.or(self.match_basename_only),
+            multiline: upper.multiline.or(self.multiline),
+            file_types: upper.file_types.or(self.file_types),
+            file_types_not: upper.file_types_not.or(self.file_types_not),
+            watch: upper.watch.or(self.watch),
+            tracked_only: upper.tracked_only.or(self.tracked_only),
+        }
+    }
+
+    /// Only the values that differ from `before`, as a session's toggles
+    /// leave them against the overrides it was launched with.
+    pub fn changed_since(self, before: SettingOverrides) -> SettingOverrides {
+        fn changed<T: PartialEq>(now: Option<T>, before: Option<T>) -> Option<T> {
+            if now == before {
+                None
+            } else {
+                now
+            }
+        }
+        SettingOverrides {
+            include_hidden: changed(self.include_hidden, before.include_hidden),
+            include_symlinks: changed(self.include_symlinks, before.include_symlinks),
+            include_mounts: changed(self.include_mounts, before.include_mounts),
+            sort_mode: changed(self.sort_mode, before.sort_mode),
+            max_depth: changed(self.max_depth, before.max_depth),
+            modified_within: changed(self.modified_within, before.modified_within),
+            smart_defaults: changed(self.smart_defaults, before.smart_defaults),
+            accessibility: changed(self.accessibility, before.accessibility),
+            search_scope: changed(self.search_scope, before.search_scope),
+            word_boundaries: changed(self.word_boundaries, before.word_boundaries),
+            match_basename_only: changed(self.match_basename_only, before.match_basename_only),
+            multiline: changed(self.multiline, before.multiline),
+            file_types: changed(self.file_types, before.file_types),
+            file_types_not: changed(self.file_types_not, before.file_types_not),
+            watch: changed(self.watch, before.watch),
+            tracked_only: changed(self.tracked_only, before.tracked_only),
+        }
+    }
+}
+
+/// Merges override layers passed lowest precedence first: profile, saved prefs, CLI.
+pub fn merge_layers(layers: &[SettingOverrides]) -> SettingOverrides {
+    layers
+        .iter()
+        .fold(SettingOverrides::default(), |lower, upper| {
+            lower.layered_under(*upper)
+        })
+}
+
+/// Applies override layers (see `merge_layers`) on top of config and defaults.
+pub fn resolve_settings(mut settings: YoinkSettings, layers: &[SettingOverrides]) -> YoinkSettings {
+    let merged = merge_layers(layers);
+
+    if let Some(include_hidden) = merged.include_hidden {
+        settings.include_hidden = include_hidden;
+    }
+    if let Some(include_symlinks) = merged.include_symlinks {
+        settings.include_symlinks = include_symlinks;
+    }
+    if let Some(include_mounts) = merged.include_mounts {
+        settings.include_mounts = include_mounts;
+    }
+    if let Some(sort_mode) = merged.sort_mode {
+        settings.sort_mode = sort_mode;
+    }
+    if let Some(max_depth) = merged.max_depth {
+        settings.max_depth = Some(max_depth);
+    }
+    if let Some(window) = merged.modified_within {
+        settings.modified_within = Some(window);
+    }
+    if let Some(smart_defaults) = merged.smart_defaults {
+        if let Err(error) = settings.set_smart_defaults(smart_defaults) {
+            eprintln!("yoink: failed to toggle smart defaults: {error}");
+        }
+    }
+    if let Some(scope) = merged.search_scope {
+        settings.search_scope = scope;
+    }
+    if let Some(word_boundaries) = merged.word_boundaries {
+        settings.word_boundaries = word_boundaries;
+    }
+    if let Some(match_basename_only) = merged.match_basename_only {
+        settings.match_basename_only = match_basename_only;
+    }
+    if let Some(multiline) = merged.multiline {
+        settings.multiline = multiline;
+    }
+    if let Some(file_types) = merged.file_types {
+        settings.file_types = file_types;
+    }
+    if let Some(file_types_not) = merged.file_types_not {
+        settings.file_types_not = file_types_not;
+    }
+    if let Some(watch) = merged.watch {
+        settings.watch = watch;
+    }
+    if let Some(tracked_only) = merged.tracked_only {
+        settings.tracked_only = tracked_only;
+    }
+    if let Some(accessibility) = merged.accessibility {
+        settings.accessibility = accessibility;
+    }
+    if settings.accessibility {
+        settings.apply_accessibility_preset();
+    }
+    settings
+}
+
+pub fn load_settings_from(sources: &[ConfigSource]) -> Result<YoinkSettings> {
+    load_settings_from_snapshot(&ConfigSnapshot::capture(sources)?)
+}
+
+pub fn load_settings_from_snapshot(snapshot: &ConfigSnapshot) -> Result<YoinkSettings> {
+    let mut settings = YoinkSettings::default();
+    let mut origins = vec![GlobOrigin::default(); settings.globs.len()];
+    for file in &snapshot.files {
+        if let Some(content) = &file.content {
+            apply_config_file(&mut settings, &mut origins, &file.source, content)?;
+        }
+    }
+
+    if settings.smart_defaults {
+        insert_smart_defaults(&mut settings, &mut origins);
+    }
+    settings.globset = GlobMatcher::with_origins(&settings.globs, &origins)?;
+    settings.demote_globset = GlobMatcher::new(&settings.demote_globs)?;
+    Ok(settings)
+}
+
+/// Smart excludes go right after the built-ins, ahead of anything from config files.
+fn insert_smart_defaults(settings: &mut YoinkSettings, origins: &mut Vec<GlobOrigin>) {
+    let at = DEFAULT_IGNORE_GLOBS.len().min(settings.globs.len());
+    settings.globs.splice(
+        at..at,
+        SMART_DEFAULT_GLOBS
+            .iter()
+            .map(|pattern| pattern.to_string()),
+    );
+    origins.splice(
+        at..at,
+        SMART_DEFAULT_GLOBS
+            .iter()
+            .map(|_| GlobOrigin::smart_default()),
+    );
+    settings.suppress_globs.splice(
+        0..0,
+        SMART_SUPPRESS_GLOBS
+            .iter()
+            .map(|pattern| pattern.to_string()),
+    );
+}
+
+impl YoinkSettings {
+    /// Whether a file passes `types` and `types_not`; see `FileTypeSet`.
+    pub fn allows_file_type(&self, rel: &Path) -> bool {
+        (self.file_types.is_empty() || self.file_types.matches(rel))
+            && !self.file_types_not.matches(rel)
+    }
+
+    /// Whether content passes run `scan_content_internal` rather than rg: under
+    /// `engine = internal`, or `auto` where rg isn't installed.
+    pub fn internal_engine(&self) -> bool {
+        match self.engine {
+            ContentEngine::Auto => !rg_installed(),
+            ContentEngine::Rg => false,
+            ContentEngine::Internal => true,
+        }
+    }
+
+    /// The `preprocess.EXT` command for `rel`, by its extension in any case.
+    pub fn preprocessor_for(&self, rel: &Path) -> Option<&str> {
+        if self.preprocessors.is_empty() {
+            return None;
+        }
+        let ext = rel.extension()?.to_string_lossy().to_ascii_lowercase();
+        self.preprocessors.get(&ext).map(String::as_str)
+    }
+
+    /// Whether the file name ends in one of `exclude_extensions`, so both
+    /// `js` and `min.js` leave out `app.min.js`.
+    pub fn excludes_extension(&self, rel: &Path) -> bool {
+        if self.exclude_extensions.is_empty() {
+            return false;
+        }
+        let Some(name) = rel.file_name() else {
+            return false;
+        };
+        let name = name.to_string_lossy().to_ascii_lowercase();
+        self.exclude_extensions.iter().any(|ext| {
+            name.strip_suffix(ext.as_str())
+                .is_some_and(|stem| stem.ends_with('.'))
+        })
+    }
+
+    fn apply_accessibility_preset(&mut self) {
+        self.icons = false;
+        self.no_color = true;
+        self.show_language = false;
+        self.teaser = false;
+        self.number_file_rows = false;
+    }
+
+    /// The session's color mode, or plain whenever `no_color` is set.
+    pub fn color_mode(&self) -> ColorMode {
+        if self.no_color {
+            ColorMode::Plain
+        } else {
+            session_color_mode()
+        }
+    }
+
+    /// The oldest modification time `modified_within` lets through, as of now.
+    pub fn modified_cutoff(&self) -> Option<SystemTime> {
+        self.modified_within
+            .and_then(|window| SystemTime::now().checked_sub(window))
+    }
+
+    /// `threads`, with 0 resolved to the available cores.
+    pub fn occurrence_threads(&self) -> usize {
+        if self.threads > 0 {
+            return self.threads;
+        }
+        thread::available_parallelism().map_or(1, |cores| cores.get())
+    }
+
+    pub fn sort_options(&self) -> SortOptions {
+        SortOptions {
+            size_order: self.size_order,
+            group_dirs_first: self.group_dirs_first,
+        }
+    }
+
+    /// Whether `size_filter_paths` drops the file at `path` from the listing.
+    pub fn oversized_path(&self, path: &Path) -> bool {
+        match self.max_filesize {
+            Some(limit) if self.size_filter_paths => {
+                fs::metadata(path).is_ok_and(|metadata| metadata.len() > limit)
+            }
+            _ => false,
+        }
+    }
+
+    /// The query as every search pass compiles it, honoring `case_mode`.
+    pub fn query_pattern(&self, query: &str) -> Result<QueryPattern> {
+        self.compile_query(query, self.case_mode.ignores_case(query))
+    }
+
+    /// `DEFAULT_OCCURRENCE_BUDGET`, with `max_occurrences_per_file` lowering the per-file cap.
+    pub fn occurrence_budget(&self) -> OccurrenceBudget {
+        let default = DEFAULT_OCCURRENCE_BUDGET;
+        OccurrenceBudget {
+            per_file: self
+                .max_occurrences_per_file
+                .map_or(default.per_file, |cap| cap.min(default.per_file)),
+            ..default
+        }
+    }
+
+    /// The `!term` exclusions from `split_negations`, each cased like a query.
+    pub fn exclusion_patterns(&self, negatives: &[String]) -> Result<Vec<QueryPattern>> {
+        negatives
+            .iter()
+            .map(|term| self.query_pattern(term))
+            .collect()
+    }
+
+    /// Compiles `query` as one pattern, or as AND terms under `and_terms`,
+    /// word-bounded under `word_boundaries` and line-spanning under `multiline`.
+    fn compile_query(&self, query: &str, case_insensitive: bool) -> Result<QueryPattern> {
+        let pattern = if self.and_terms {
+            QueryPattern::resolve_terms(query, self.invalid_regex_fallback, case_insensitive)?
+        } else {
+            QueryPattern::resolve_with_case(query, self.invalid_regex_fallback, case_insensitive)?
+        };
+        let pattern = if self.word_boundaries {
+            pattern.word_bounded()?
+        } else {
+            pattern
+        };
+        Ok(if self.multiline {
+            pattern.with_multiline()
+        } else {
+            pattern
+        })
+    }
+
+    /// Turns smart defaults on or off after loading, recompiling the exclude set.
+    pub fn set_smart_defaults(&mut self, enabled: bool) -> Result<()> {
+        if enabled == self.smart_defaults {
+            return Ok(());
+        }
+
+        let mut origins = self.globset.origins().to_vec();
+        origins.resize(self.globs.len(), GlobOrigin::default());
+        let (globs, mut origins): (Vec<String>, Vec<GlobOrigin>) = self
+            .globs
+            .drain(..)
+            .zip(origins)
+            .filter(|(_, origin)| !origin.smart_default)
+            .unzip();
+        self.globs = globs;
+        self.suppress_globs
+            .retain(|pattern| !SMART_SUPPRESS_GLOBS.contains(&pattern.as_str()));
+
+        if enabled {
+            insert_smart_defaults(self, &mut origins);
+        }
+        self.globset = GlobMatcher::with_origins(&self.globs, &origins)?;
+        self.smart_defaults = enabled;
+        Ok(())
+    }
+}
+
+/// Excludes for content passes only: suppressed files are still listed by path.
+fn rg_suppress_args(settings: &YoinkSettings) -> Vec<String> {
+    settings
+        .suppress_globs
+        .iter()
+        .flat_map(|pattern| ["-g".to_string(), format!("!{pattern}")])
+        .collect()
+}
+
+pub fn rg_filter_args(settings: &YoinkSettings) -> Vec<String> {
+    let mut args = rg_toggle_args(settings);
+    let mut globs = Vec::new();
+    // Any plain `-g` turns rg's globs into a whitelist; a leading `*` keeps
+    // everything else in so a `!pattern` only re-includes what it names.
+    if settings.globset.has_negations() {
+        globs.push(("-g", "*".to_string()));
+    }
+    for pattern in &settings.globs {
+        globs.push((
+            "-g",
+            match split_negation(pattern) {
+                (glob, true) => glob.to_string(),
+                // rg never looks inside an excluded directory; excluding its
+                // contents instead leaves room for the re-included files.
+                (glob, false) if settings.globset.may_reinclude_under(Path::new(glob)) => {
+                    format!("!{glob}/**")
+                }
+                (glob, false) => format!("!{glob}"),
+            },
+        ));
+    }
+    let mut extensions: Vec<&String> = settings.exclude_extensions.iter().collect();
+    extensions.sort();
+    for ext in extensions {
+        globs.push(("--iglob", format!("!*.{ext}")));
+    }
+    // An inherited config already hands rg its own globs; don't repeat them.
+    let inherited = if settings.inherit_rg_config {
+        std::env::var_os("RIPGREP_CONFIG_PATH")
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|config| rg_config_globs(&config))
+            .unwrap_or_default()
+    } else {
+        HashSet::new()
+    };
+    for (flag, glob) in globs {
+        if !inherited.contains(&glob) {
+            args.push(flag.to_string());
+            args.push(glob);
+        }
+    }
+    args
+}
+
+/// `--no-config` unless `inherit_rg_config`, so a personal rg config can't make
+/// the content passes disagree with the path walk.
+pub fn rg_config_args(settings: &YoinkSettings) -> &'static [&'static str] {
+    if settings.inherit_rg_config {
+        &[]
+    } else {
+        &["--no-config"]
+    }
+}
+
+/// The `-g` / `--glob` / `--iglob` values in an rg config file: one argument per
+/// line, `#` comments, a flag's value either attached or on the next line.
+pub fn rg_config_globs(config: &str) -> HashSet<String> {
+    let mut globs = HashSet::new();
+    let mut lines = config
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'));
+    while let Some(line) = lines.next() {
+        if matches!(line, "-g" | "--glob" | "--iglob") {
+            if let Some(value) = lines.next() {
+                globs.insert(value.to_string());
+            }
+        } else if let Some(value) = line
+            .strip_prefix("--glob=")
+            .or_else(|| line.strip_prefix("--iglob="))
+            .or_else(|| line.strip_prefix("-g"))
+        {
+            globs.insert(value.to_string());
+        }
+    }
+    globs
+}
+
+/// `--type` / `--type-not` for the content passes. File listing leaves them out so
+/// directories holding only other types still show up.
+fn rg_type_args(settings: &YoinkSettings) -> Vec<String> {
+    let mut args = Vec::new();
+    for name in settings.file_types.names() {
+        args.push("--type".to_string());
+        args.push(name.to_string());
+    }
+    for name in settings.file_types_not.names() {
+        args.push("--type-not".to_string());
+        args.push(name.to_string());
+    }
+    args
+}
+
+/// Filter flags without the ignore globs, for passes that apply the globs themselves.
+fn rg_toggle_args(settings: &YoinkSettings) -> Vec<String> {
+    let mut args = Vec::new();
+
+    if settings.include_hidden {
+        args.push("--hidden".to_string());
+    }
+
+    if !settings.include_mounts {
+        args.push("--one-file-system".to_string());
+    }
+
+    if settings.include_symlinks {
+        args.push("--follow".to_string());
+    }
+
+    if !settings.respect_gitignore {
+        args.push("--no-ignore".to_string());
+    }
+
+    if let Some(max_depth) = settings.max_depth {
+        args.push("--max-depth".to_string());
+        args.push(max_depth.to_string());
+    }
+
+    if settings.threads > 0 {
+        args.push("-j".to_string());
+        args.push(settings.threads.to_string());
+    }
+
+    args
+}
+
+pub fn rg_files_args(settings: &YoinkSettings) -> Vec<String> {
+    let mut args = vec![
+        "--files".to_string(),
+        "-0".to_string(),
+        "--color=never".to_string(),
+        "--no-messages".to_string(),
+    ];
+    args.extend(rg_config_args(settings).iter().map(|arg| arg.to_string()));
+    args.extend(rg_filter_args(settings));
+    args
+}
+
+/// `(dev, inode)` of every directory a walk entered through a symlink, shared
+/// by the parallel walk's workers.
+type FollowedDirs = Arc<Mutex<HashSet<(u64, u64)>>>;
+
+/// The pruning shared by every walk: hidden paths, ignore globs, gitignored
+/// paths, nested `.yoinkignore` files, symlinks and other filesystems. Each
+/// parallel worker owns one, so the ignore file caches need no lock.
+struct WalkFilter<'a> {
+    cwd: &'a Path,
+    settings: &'a YoinkSettings,
+    root_dev: Option<u64>,
+    gitignore: Option<GitIgnore>,
+    nested: NestedIgnores,
+    /// The canonical root, to tell symlinks pointing back into the tree.
+    root: Option<PathBuf>,
+    followed: FollowedDirs,
+}
+
+impl<'a> WalkFilter<'a> {
+    fn new(
+        cwd: &'a Path,
+        settings: &'a YoinkSettings,
+        root_dev: Option<u64>,
+        followed: FollowedDirs,
+    ) -> WalkFilter<'a> {
+        #[cfg(not(target_family = "unix"))]
+        let _ = root_dev;
+
+        let gitignore = if settings.respect_gitignore {
+            GitIgnore::discover(cwd)
+        } else {
+            None
+        };
+        let root = if settings.include_symlinks {
+            fs::canonicalize(cwd).ok()
+        } else {
+            None
+        };
+        WalkFilter {
+            cwd,
+            settings,
+            root_dev,
+            gitignore,
+            nested: NestedIgnores::new(cwd),
+            root,
+            followed,
+        }
+    }
+
+    /// Whether the walk keeps `rel` at all, entering it when it's a directory.
+    fn admits(
+        &mut self,
+        rel: &Path,
+        is_dir: bool,
+        is_symlink: bool,
+        hits: Option<&mut IgnoreHits>,
+    ) -> bool {
+        let settings = self.settings;
+        if !settings.include_symlinks && is_symlink {
+            return false;
+        }
+
+        if (!settings.include_hidden && is_hidden_path(rel))
+            || self
+                .gitignore
+                .as_mut()
+                .is_some_and(|gitignore| gitignore.is_ignored(rel, is_dir))
+            || self.nested.is_ignored(rel, is_dir)
+        {
+            return false;
+        }
+        // An excluded directory is still entered when a `!pattern` may
+        // re-include something in it; it is left out of the listing below.
+        if settings.globset.is_match_counted(rel, hits) {
+            return is_dir && settings.globset.may_reinclude_under(rel);
+        }
+
+        #[cfg(target_family = "unix")]
+        {
+            if let Some(root_dev) = self.root_dev {
+                if is_dir {
+                    if let Ok(metadata) = fs::metadata(self.cwd.join(rel)) {
+                        if metadata.dev() != root_dev {
+                            return false;
+                        }
+                    }
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Whether the walk enters `rel`, an admitted symlink to a directory. A
+    /// target inside the root is walked through its real path already, and one
+    /// entered before (through another link, or a link back up a followed tree)
+    /// would repeat or loop, so neither is entered; nor is a link more than
+    /// `max_symlink_depth` links deep.
+    fn follows(&mut self, rel: &Path) -> bool {
+        let settings = self.settings;
+        if !settings.include_symlinks {
+            return false;
+        }
+        let links = rel
+            .ancestors()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .filter(|dir| {
+                fs::symlink_metadata(self.cwd.join(dir))
+                    .is_ok_and(|metadata| metadata.file_type().is_symlink())
+            })
+            .count();
+        if links > settings.max_symlink_depth {
+            return false;
+        }
+        let Ok(target) = fs::canonicalize(self.cwd.join(rel)) else {
+            return false;
+        };
+        if self
+            .root
+            .as_ref()
+            .is_some_and(|root| target.starts_with(root))
+        {
+            return false;
+        }
+
+        #[cfg(target_family = "unix")]
+        {
+            let Ok(metadata) = fs::metadata(&target) else {
+                return false;
+            };
+            self.followed
+                .lock()
+                .expect("followed dirs")
+                .insert((metadata.dev(), metadata.ino()))
+        }
+        // Without inodes there is nothing to break a cycle with.
+        #[cfg(not(target_family = "unix"))]
+        false
+    }
+
+    /// Whether the walk would list a change at `rel`: each directory above it
+    /// admitted and `rel` itself listed. A path that's gone is judged as a file.
+    fn watches(&mut self, rel: &Path) -> bool {
+        let depth = rel.components().count();
+        if depth == 0 || self.settings.max_depth.is_some_and(|max| depth > max) {
+            return false;
+        }
+        let mut prefix = PathBuf::new();
+        for (index, component) in rel.components().enumerate() {
+            prefix.push(component);
+            let full = self.cwd.join(&prefix);
+            let is_symlink =
+                fs::symlink_metadata(&full).is_ok_and(|metadata| metadata.file_type().is_symlink());
+            let last = index + 1 == depth;
+            let is_dir = !last || fs::metadata(&full).is_ok_and(|metadata| metadata.is_dir());
+            if !self.admits(&prefix, is_dir, is_symlink, None) {
+                return false;
+            }
+            if last {
+                return walk_lists(self.settings, &prefix, is_dir);
+            }
+        }
+        false
+    }
+}
+
+/// Whether an admitted walk entry is listed: a directory entered only for its
+/// re-included contents isn't.
+fn walk_lists(settings: &YoinkSettings, rel: &Path, is_dir: bool) -> bool {
+    let globset = &settings.globset;
+    !(globset.has_negations() && is_dir && globset.is_match(rel))
+}
+
+/// The walkdir pass shared by listing and counting: hidden paths, ignore globs,
+/// gitignored paths, symlinks, other filesystems and the depth limit are pruned
+/// as directories are entered. Under `include_symlinks` symlinked directories
+/// are listed as directories and entered as `WalkFilter::follows` allows.
+fn filtered_walk<'a>(
+    cwd: &'a Path,
+    settings: &'a YoinkSettings,
+    root_dev: Option<u64>,
+    mut hits: Option<&'a mut IgnoreHits>,
+) -> impl Iterator<Item = (PathBuf, bool)> + 'a {
+    let mut filter = WalkFilter::new(cwd, settings, root_dev, FollowedDirs::default());
+    let mut walker = WalkDir::new(cwd).follow_links(settings.include_symlinks);
+    if let Some(max_depth) = settings.max_depth {
+        walker = walker.max_depth(max_depth);
+    }
+
+    let mut entries = walker.into_iter();
+    std::iter::from_fn(move || loop {
+        let (path, is_dir, is_symlink, entered) = match entries.next()? {
+            Ok(entry) => {
+                let (is_dir, is_symlink) = (entry.file_type().is_dir(), entry.path_is_symlink());
+                (entry.into_path(), is_dir, is_symlink, true)
+            }
+            // A link to an ancestor, which walkdir reports instead of entering;
+            // it is still listed. Dangling links are skipped.
+            Err(error) if error.loop_ancestor().is_some() => match error.path() {
+                Some(path) => (path.to_path_buf(), true, true, false),
+                None => continue,
+            },
+            Err(_) => continue,
+        };
+        let Ok(rel) = path.strip_prefix(cwd) else {
+            continue;
+        };
+        if rel.as_os_str().is_empty() {
+            continue;
+        }
+        if !filter.admits(rel, is_dir, is_symlink, hits.as_deref_mut()) {
+            if is_dir && entered {
+                entries.skip_current_dir();
+            }
+            continue;
+        }
+        if is_dir && is_symlink && entered && !filter.follows(rel) {
+            entries.skip_current_dir();
+        }
+        if walk_lists(settings, rel, is_dir) {
+            return Some((rel.to_path_buf(), is_dir));
+        }
+    })
+}
+
+pub(crate) fn walk_entries(
+    cwd: &Path,
+    settings: &YoinkSettings,
+    root_dev: Option<u64>,
+    hits: Option<&mut IgnoreHits>,
+    deadline: &Deadline,
+) -> Vec<(PathBuf, bool)> {
+    // Crediting ignore globs needs the one `IgnoreHits`, so `config stats` walks serially.
+    let threads = settings.occurrence_threads();
+    if hits.is_none() && threads > 1 {
+        return parallel_walk_entries(cwd, settings, root_dev, threads, deadline);
+    }
+
+    let mut listed = Vec::new();
+    for (index, entry) in filtered_walk(cwd, settings, root_dev, hits).enumerate() {
+        if index % DEADLINE_CHECK_ENTRIES == 0 && deadline.passed() {
+            break;
+        }
+        listed.push(entry);
+    }
+
+    listed
+}
+
+/// `walk_entries` on `ignore`'s parallel walker over `threads` workers, each
+/// applying its own `WalkFilter` in place of the crate's standard filters;
+/// admitted entries come back over a channel. The listing is unordered, which
+/// the candidate sort makes up for.
+fn parallel_walk_entries(
+    cwd: &Path,
+    settings: &YoinkSettings,
+    root_dev: Option<u64>,
+    threads: usize,
+    deadline: &Deadline,
+) -> Vec<(PathBuf, bool)> {
+    let walker = WalkBuilder::new(cwd)
+        .standard_filters(false)
+        .follow_links(settings.include_symlinks)
+        .max_depth(settings.max_depth)
+        .threads(threads)
+        .build_parallel();
+    let stop = AtomicBool::new(false);
+    let (sender, receiver) = mpsc::channel::<(PathBuf, bool)>();
+    let followed = FollowedDirs::default();
+    let mut listed = Vec::new();
+
+    thread::scope(|scope| {
+        let stop = &stop;
+        scope.spawn(move || {
+            walker.run(|| {
+                let sender = sender.clone();
+                let mut filter = WalkFilter::new(cwd, settings, root_dev, Arc::clone(&followed));
+                Box::new(move |result| {
+                    if stop.load(Ordering::Relaxed) {
+                        return WalkState::Quit;
+                    }
+                    let (path, is_dir, is_symlink, entered) = match result {
+                        Ok(entry) => {
+                            let is_dir = entry.file_type().is_some_and(|kind| kind.is_dir());
+                            let is_symlink = entry.path_is_symlink();
+                            (entry.into_path(), is_dir, is_symlink, true)
+                        }
+                        // A link to an ancestor, listed but not entered, as in the
+                        // serial walk. Dangling links are skipped.
+                        Err(error) => match loop_link(&error) {
+                            Some(path) => (path.to_path_buf(), true, true, false),
+                            None => return WalkState::Continue,
+                        },
+                    };
+                    let Ok(rel) = path.strip_prefix(cwd) else {
+                        return WalkState::Continue;
+                    };
+                    if rel.as_os_str().is_empty() {
+                        return WalkState::Continue;
+                    }
+                    if !filter.admits(rel, is_dir, is_symlink, None) {
+                        return if is_dir {
+                            WalkState::Skip
+                        } else {
+                            WalkState::Continue
+                        };
+                    }
+                    let state = if is_dir && is_symlink && entered && !filter.follows(rel) {
+                        WalkState::Skip
+                    } else {
+                        WalkState::Continue
+                    };
+                    if walk_lists(settings, rel, is_dir)
+                        && sender.send((rel.to_path_buf(), is_dir)).is_err()
+                    {
+                        return WalkState::Quit;
+                    }
+                    state
+                })
+            });
+        });
+
+        // Dropping the receiver at the deadline turns every worker's next send into a quit.
+        for (index, entry) in receiver.into_iter().enumerate() {
+            if index % DEADLINE_CHECK_ENTRIES == 0 && deadline.passed() {
+                stop.store(true, Ordering::Relaxed);
+                break;
+            }
+            listed.push(entry);
+        }
+    });
+
+    listed
+}
+
+/// The link in a symlink loop the walker reports as an error instead of entering.
+fn loop_link(error: &ignore::Error) -> Option<&Path> {
+    match error {
+        ignore::Error::Loop { child, .. } => Some(child),
+        ignore::Error::WithPath { err, .. }
+        | ignore::Error::WithDepth { err, .. }
+        | ignore::Error::WithLineNumber { err, .. } => loop_link(err),
+        _ => None,
+    }
+}
+
+/// Walk entries between checks of the `search_timeout_ms` deadline.
+pub(crate) const DEADLINE_CHECK_ENTRIES: usize = 256;
+/// How often a deadlined rg child is polled for having exited.
+const DEADLINE_POLL: Duration = Duration::from_millis(5);
+
+/// `search_timeout_ms` as an instant the search checks against; remembers
+/// whether any check found it passed, so the results can say they're partial.
+pub(crate) struct Deadline {
+    at: Option<Instant>,
+    expired: AtomicBool,
+}
+
+impl Deadline {
+    fn after(timeout: Option<Duration>) -> Deadline {
+        Deadline {
+            at: timeout.map(|timeout| Instant::now() + timeout),
+            expired: AtomicBool::new(false),
+        }
+    }
+
+    pub(crate) fn passed(&self) -> bool {
+        let passed = self.at.is_some_and(|at| Instant::now() >= at);
+        if passed {
+            self.expired.store(true, Ordering::Relaxed);
+        }
+        passed
+    }
+
+    fn expired(&self) -> bool {
+        self.expired.load(Ordering::Relaxed)
+    }
+}
+
+/// Runs `command` to completion, or kills it once `deadline` passes, while
+/// `read` consumes its stdout on the side so a chatty child never blocks on a
+/// full pipe. Also says whether the child was killed.
+fn run_until<T: Send>(
+    command: &mut Command,
+    deadline: &Deadline,
+    read: impl FnOnce(ChildStdout) -> T + Send,
+) -> io::Result<(T, bool)> {
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let stdout = child.stdout.take().expect("piped stdout");
+    thread::scope(|scope| {
+        let reader = scope.spawn(move || read(stdout));
+        let mut killed = false;
+        if deadline.at.is_none() {
+            child.wait()?;
+        } else {
+            while child.try_wait()?.is_none() {
+                if deadline.passed() {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    killed = true;
+                    break;
+                }
+                thread::sleep(DEADLINE_POLL);
+            }
+        }
+        let out = reader
+            .join()
+            .map_err(|_| io::Error::other("rg output reader panicked"))?;
+        Ok((out, killed))
+    })
+}
+
+/// `run_until`, returning what the child printed before exiting. A killed
+/// child's output is cut after its last `terminator`, dropping a half-written record.
+fn stdout_until(command: &mut Command, deadline: &Deadline, terminator: u8) -> io::Result<Vec<u8>> {
+    let (mut out, killed) = run_until(command, deadline, |mut stdout| {
+        let mut out = Vec::new();
+        let _ = stdout.read_to_end(&mut out);
+        out
+    })?;
+    if killed {
+        out.truncate(
+            out.iter()
+                .rposition(|byte| *byte == terminator)
+                .map_or(0, |end| end + 1),
+        );
+    }
+    Ok(out)
+}
+
+/// An extractor still running after this long is killed; its file then
+/// matches by path only.
+pub const PREPROCESS_TIMEOUT: Duration = Duration::from_secs(5);
+/// Files larger than this aren't handed to an extractor.
+pub const PREPROCESS_MAX_FILE_BYTES: u64 = 64 * 1024 * 1024;
+/// Extracted text kept per file; the rest of the extractor's output is read and dropped.
+pub const PREPROCESS_MAX_TEXT_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Whether rg is on `PATH`, looked up once per process.
+fn rg_installed() -> bool {
+    static INSTALLED: OnceLock<bool> = OnceLock::new();
+    *INSTALLED.get_or_init(|| which::which("rg").is_ok())
+}
+
+/// Extractor programs found missing, each warned about once per process.
+static MISSING_EXTRACTORS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// The `preprocess.EXT` command line for `full`: the template split on
+/// whitespace, `{}` standing for the path, which is appended when the
+/// template has no `{}`.
+pub fn preprocess_args(template: &str, full: &Path) -> Vec<String> {
+    let path = full.to_string_lossy();
+    let mut args: Vec<String> = template
+        .split_whitespace()
+        .map(|word| word.replace("{}", &path))
+        .collect();
+    if !template.contains("{}") {
+        args.push(path.into_owned());
+    }
+    args
+}
+
+/// The text extractor `template` prints for `full`, cut at
+/// `PREPROCESS_MAX_TEXT_BYTES`. `None` when the file is over
+/// `PREPROCESS_MAX_FILE_BYTES`, the extractor outlives `PREPROCESS_TIMEOUT`,
+/// or it isn't installed, which warns on stderr once per program.
+pub fn extract_text(template: &str, full: &Path) -> Option<String> {
+    if fs::metadata(full).ok()?.len() > PREPROCESS_MAX_FILE_BYTES {
+        return None;
+    }
+    let args = preprocess_args(template, full);
+    let (program, rest) = args.split_first()?;
+    let mut command = Command::new(program);
+    command.args(rest).stdin(Stdio::null());
+    let read = |stdout: ChildStdout| {
+        let mut text = Vec::new();
+        let mut stdout = stdout;
+        let _ = (&mut stdout)
+            .take(PREPROCESS_MAX_TEXT_BYTES)
+            .read_to_end(&mut text);
+        // Drained, so an extractor past the cap doesn't block on a full pipe.
+        let _ = io::copy(&mut stdout, &mut io::sink());
+        text
+    };
+    match run_until(
+        &mut command,
+        &Deadline::after(Some(PREPROCESS_TIMEOUT)),
+        read,
+    ) {
+        Ok((text, false)) => Some(String::from_utf8_lossy(&text).into_owned()),
+        Ok((_, true)) => None,
+        Err(error) => {
+            if error.kind() == io::ErrorKind::NotFound {
+                let mut missing = MISSING_EXTRACTORS
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+                if !missing.contains(program) {
+                    eprintln!("yoink: preprocess command {program} not found; those files match by path only");
+                    missing.push(program.clone());
+                }
+            }
+            None
+        }
+    }
+}
+
+/// Whether extracted `text` matches `pattern`, every AND term somewhere in it.
+/// Like rg, only a multiline pattern matches across lines; the rest match one
+/// line at a time, so `^` and `$` anchor there.
+pub fn text_matches(text: &str, pattern: &QueryPattern) -> bool {
+    pattern.all_terms().iter().all(|term| {
+        if pattern.multiline {
+            term.regex.is_match(text)
+        } else {
+            text.lines().any(|line| term.regex.is_match(line))
+        }
+    })
+}
+
+/// Occurrences of `pattern` in extracted `text`, one per matching line at its
+/// first match, as rg would list them from a file; a multiline match counts
+/// on the line it starts on. Lines past `max_columns` bytes (0 for no limit)
+/// are windowed around the match. Also says whether `per_file` cut the list short.
+pub fn text_occurrences(
+    text: &str,
+    pattern: &QueryPattern,
+    per_file: usize,
+    max_columns: usize,
+    icons: bool,
+) -> (Vec<Occurrence>, bool) {
+    let marker = if icons { "…" } else { "..." };
+    let mut occurrences = Vec::new();
+    for (index, line, start) in first_match_per_line(text, pattern) {
+        if occurrences.len() >= per_file {
+            return (occurrences, true);
+        }
+        let column = start + 1;
+        let long_line = max_columns > 0 && line.len() > max_columns;
+        let snippet = if long_line {
+            column_window(line.as_bytes(), column, LONG_LINE_WINDOW, marker)
+                .unwrap_or_else(|| LONG_LINE_NOTE.to_string())
+        } else {
+            sanitize_control(line.trim())
+        };
+        occurrences.push(Occurrence {
+            line: index + 1,
+            column,
+            snippet,
+            long_line,
+            context: Vec::new(),
+        });
+    }
+    (occurrences, false)
+}
+
+/// Each line of `text` where a match of `pattern` starts, as its 0-based
+/// index, its text and the first match's byte offset in it.
+fn first_match_per_line<'t>(text: &'t str, pattern: &QueryPattern) -> Vec<(usize, &'t str, usize)> {
+    if !pattern.multiline {
+        return text
+            .lines()
+            .enumerate()
+            .filter_map(|(index, line)| Some((index, line, pattern.regex.find(line)?.start())))
+            .collect();
+    }
+    let mut starts: Vec<(usize, &str, usize)> = Vec::new();
+    for found in pattern.regex.find_iter(text) {
+        let line_start = text[..found.start()]
+            .rfind('\n')
+            .map_or(0, |newline| newline + 1);
+        let index = text[..line_start].matches('\n').count();
+        if starts.last().is_some_and(|(last, _, _)| *last == index) {
+            continue;
+        }
+        let line_end = text[line_start..]
+            .find('\n')
+            .map_or(text.len(), |newline| line_start + newline);
+        let line = text[line_start..line_end].trim_end_matches('\r');
+        starts.push((index, line, found.start() - line_start));
+    }
+    starts
+}
+
+/// Result of a bounded pre-count of the entries a session would list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeSize {
+    /// The whole tree was counted.
+    Exact(usize),
+    /// Counting stopped at the limit or ran out of time after this many entries.
+    AtLeast(usize),
+}
+
+/// Counts listable entries under `cwd`, giving up at `limit` entries or once
+/// `budget` has elapsed, whichever comes first.
+pub fn count_entries_bounded(
+    cwd: &Path,
+    settings: &YoinkSettings,
+    limit: usize,
+    budget: Duration,
+) -> Result<TreeSize> {
+    let root_dev = search_root_dev(cwd, settings)?;
+    let started = Instant::now();
+    let mut counted = 0;
+
+    for _ in filtered_walk(cwd, settings, root_dev, None) {
+        counted += 1;
+        if counted >= limit || started.elapsed() >= budget {
+            return Ok(TreeSize::AtLeast(counted));
+        }
+    }
+
+    Ok(TreeSize::Exact(counted))
+}
+
+/// Every listable path with its file's modification time and size. Directories
+/// carry neither: their own mtime moves when an ignored file inside changes.
+pub type TreeSnapshot = HashMap<PathBuf, Option<(SystemTime, u64)>>;
+
+/// What the session would walk right now, each root of several under its label;
+/// the ignore globs, gitignores, hidden and depth settings all apply.
+pub fn tree_snapshot(cwd: &Path, settings: &YoinkSettings) -> Result<TreeSnapshot> {
+    if settings.roots.is_empty() {
+        return root_snapshot(cwd, settings);
+    }
+    let mut snapshot = TreeSnapshot::new();
+    for (label, root_settings) in root_searches(settings) {
+        let found = root_snapshot(&cwd.join(&label), &root_settings)?;
+        snapshot.extend(
+            found
+                .into_iter()
+                .map(|(rel, stamp)| (label.join(rel), stamp)),
+        );
+    }
+    Ok(snapshot)
+}
+
+/// The changed `paths` `--watch` reloads for, relative to `cwd` and each root
+/// of several under its label: those the walk would list, through the same
+/// ignore globs, gitignores, hidden and depth settings as `tree_snapshot`.
+/// Paths outside the root are dropped.
+pub fn watched_paths(
+    cwd: &Path,
+    settings: &YoinkSettings,
+    paths: &[PathBuf],
+) -> Result<Vec<PathBuf>> {
+    if settings.roots.is_empty() {
+        return root_watched_paths(cwd, Path::new(""), settings, paths);
+    }
+    let mut watched = Vec::new();
+    for (label, root_settings) in root_searches(settings) {
+        watched.extend(root_watched_paths(
+            &cwd.join(&label),
+            &label,
+            &root_settings,
+            paths,
+        )?);
+    }
+    Ok(watched)
+}
+
+fn root_watched_paths(
+    cwd: &Path,
+    label: &Path,
+    settings: &YoinkSettings,
+    paths: &[PathBuf],
+) -> Result<Vec<PathBuf>> {
+    let root_dev = search_root_dev(cwd, settings)?;
+    // Some platforms report events under the root's real path.
+    let real_root = fs::canonicalize(cwd).ok();
+    let mut filter = WalkFilter::new(cwd, settings, root_dev, FollowedDirs::default());
+    Ok(paths
+        .iter()
+        .filter_map(|path| {
+            let rel = path.strip_prefix(cwd).ok().or_else(|| {
+                real_root
+                    .as_deref()
+                    .and_then(|root| path.strip_prefix(root).ok())
+            })?;
+            filter.watches(rel).then(|| label.join(rel))
+        })
+        .collect())
+}
+
+fn root_snapshot(cwd: &Path, settings: &YoinkSettings) -> Result<TreeSnapshot> {
+    let root_dev = search_root_dev(cwd, settings)?;
+    Ok(filtered_walk(cwd, settings, root_dev, None)
+        .map(|(rel, is_dir)| {
+            let stamp = if is_dir {
+                None
+            } else {
+                fs::metadata(cwd.join(&rel))
+                    .ok()
+                    .and_then(|metadata| Some((metadata.modified().ok()?, metadata.len())))
+            };
+            (rel, stamp)
+        })
+        .collect())
+}
+
+/// `rg --files` only emits files, so directories are synthesized from their
+/// parents, then a shallow pass over the admitted directories adds the ones
+/// holding no listed file. Both go through the walk's `WalkFilter`, so the two
+/// listers agree on what is pruned and on `config stats` counts.
+fn rg_file_entries(
+    cwd: &Path,
+    settings: &YoinkSettings,
+    root_dev: Option<u64>,
+    mut hits: Option<&mut IgnoreHits>,
+    deadline: &Deadline,
+) -> Result<Vec<(PathBuf, bool)>> {
+    let mut command = Command::new("rg");
+    if hits.is_some() {
+        // Let every path through so the globs below can be credited.
+        command
+            .args(["--files", "-0", "--color=never", "--no-messages"])
+            .args(rg_config_args(settings))
+            .args(rg_toggle_args(settings));
+    } else {
+        command.args(rg_files_args(settings));
+    }
+
+    let stdout = stdout_until(command.arg(".").current_dir(cwd), deadline, 0)
+        .context("failed to execute rg for file listing")?;
+
+    let mut filter = WalkFilter::new(cwd, settings, root_dev, FollowedDirs::default());
+    let mut listed = Vec::new();
+    // Whether each directory seen so far was admitted and, for a symlink, entered.
+    let mut dirs: HashMap<PathBuf, bool> = HashMap::new();
+
+    'files: for raw in stdout.split(|byte| *byte == 0) {
+        if raw.is_empty() {
+            continue;
+        }
+
+        let raw = path_from_bytes(raw.to_vec());
+        let rel = raw.strip_prefix("./").map(PathBuf::from).unwrap_or(raw);
+        // Outermost first, so a pruned directory hides its contents uncounted.
+        let ancestors: Vec<&Path> = rel
+            .ancestors()
+            .skip(1)
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .collect();
+        for dir in ancestors.into_iter().rev() {
+            let admitted = match dirs.get(dir) {
+                Some(admitted) => *admitted,
+                None => {
+                    // Without `--follow` rg never lists anything under a symlink.
+                    let is_symlink = settings.include_symlinks
+                        && fs::symlink_metadata(cwd.join(dir))
+                            .is_ok_and(|metadata| metadata.file_type().is_symlink());
+                    let admitted = filter.admits(dir, true, is_symlink, hits.as_deref_mut());
+                    if admitted && walk_lists(settings, dir, true) {
+                        listed.push((dir.to_path_buf(), true));
+                    }
+                    let entered = admitted && (!is_symlink || filter.follows(dir));
+                    dirs.insert(dir.to_path_buf(), entered);
+                    entered
+                }
+            };
+            if !admitted {
+                continue 'files;
+            }
+        }
+
+        if filter.admits(&rel, false, false, hits.as_deref_mut()) {
+            listed.push((rel, false));
+        }
+    }
+
+    // Directories with no listed file (empty, or holding only filtered files).
+    let mut pending: Vec<PathBuf> = std::iter::once(PathBuf::new())
+        .chain(
+            dirs.iter()
+                .filter(|(_, admitted)| **admitted)
+                .map(|(dir, _)| dir.clone()),
+        )
+        .collect();
+    while let Some(dir) = pending.pop() {
+        if settings
+            .max_depth
+            .is_some_and(|max_depth| dir.components().count() >= max_depth)
+        {
+            continue;
+        }
+        let Ok(children) = fs::read_dir(cwd.join(&dir)) else {
+            continue;
+        };
+        for child in children.flatten() {
+            let Ok(file_type) = child.file_type() else {
+                continue;
+            };
+            let is_symlink = file_type.is_symlink();
+            let is_dir = if is_symlink {
+                child.path().is_dir()
+            } else {
+                file_type.is_dir()
+            };
+            let rel = dir.join(child.file_name());
+            if !is_dir || dirs.contains_key(&rel) {
+                continue;
+            }
+            let admitted = filter.admits(&rel, true, is_symlink, hits.as_deref_mut());
+            if admitted && walk_lists(settings, &rel, true) {
+                listed.push((rel.clone(), true));
+            }
+            let entered = admitted && (!is_symlink || filter.follows(&rel));
+            dirs.insert(rel.clone(), entered);
+            if entered {
+                pending.push(rel);
+            }
+        }
+    }
+
+    Ok(listed)
+}
+
+pub fn build_candidates(query: &str, cwd: &Path) -> Result<Vec<Candidate>> {
+    build_candidates_with_stats(query, cwd, &mut SearchStats::default())
+}
+
+pub fn build_candidates_with_stats(
+    query: &str,
+    cwd: &Path,
+    stats: &mut SearchStats,
+) -> Result<Vec<Candidate>> {
+    let settings = load_settings_for(cwd)?;
+    let deadline = Deadline::after(settings.search_timeout);
+    if settings.roots.is_empty() {
+        return candidates_with_settings(query, cwd, &settings, stats, None, &deadline, None);
+    }
+    let mut list = Vec::new();
+    for (label, root_settings) in root_searches(&settings) {
+        let mut found = candidates_with_settings(
+            query,
+            &cwd.join(&label),
+            &root_settings,
+            stats,
+            None,
+            &deadline,
+            None,
+        )?;
+        label_candidates(&mut found, &label);
+        list.extend(found);
+    }
+    sort_candidates_with(&mut list, settings.sort_mode, settings.sort_options());
+    Ok(list)
+}
+
+/// `build_candidates` with the content matches already known, e.g. from an
+/// earlier `rg --json` scan, instead of listing them with `rg -l`.
+pub fn build_candidates_with_content(
+    query: &str,
+    cwd: &Path,
+    content: &HashSet<PathBuf>,
+) -> Result<Vec<Candidate>> {
+    let settings = load_settings_for(cwd)?;
+    let deadline = Deadline::after(settings.search_timeout);
+    candidates_with_settings(
+        query,
+        cwd,
+        &settings,
+        &mut SearchStats::default(),
+        None,
+        &deadline,
+        Some(content),
+    )
+}
+
+/// Runs a search and reports how many paths each ignore glob excluded.
+pub fn collect_ignore_stats(query: &str, cwd: &Path) -> Result<Vec<IgnorePatternStat>> {
+    let settings = load_settings_for(cwd)?;
+    let mut hits = IgnoreHits::for_matcher(&settings.globset);
+    candidates_with_settings(
+        query,
+        cwd,
+        &settings,
+        &mut SearchStats::default(),
+        Some(&mut hits),
+        &Deadline::after(settings.search_timeout),
+        None,
+    )?;
+    Ok(settings.globset.pattern_stats(&hits))
+}
+
+/// The checks every pass applies before a listed path becomes a candidate:
+/// file types, extensions, size, age, tracked files, filter pills and `!term`
+/// path exclusions, and for content matches the filters rg can't apply.
+struct CandidateFilter<'a> {
+    cwd: &'a Path,
+    settings: &'a YoinkSettings,
+    root_dev: Option<u64>,
+    exclusions: Vec<QueryPattern>,
+    pill_filter: PillFilter,
+    /// rg doesn't read nested `.yoinkignore` files, so its matches are checked here.
+    nested: NestedIgnores,
+    tracked: Option<GitFiles>,
+    cutoff: Option<SystemTime>,
+}
+
+impl<'a> CandidateFilter<'a> {
+    fn new(
+        cwd: &'a Path,
+        settings: &'a YoinkSettings,
+        negatives: &[String],
+        root_dev: Option<u64>,
+    ) -> Result<CandidateFilter<'a>> {
+        Ok(CandidateFilter {
+            cwd,
+            settings,
+            root_dev,
+            exclusions: settings.exclusion_patterns(negatives)?,
+            pill_filter: PillFilter::new(&settings.pills)?,
+            nested: NestedIgnores::new(cwd),
+            tracked: tracked_files(cwd, settings)?,
+            cutoff: settings.modified_cutoff(),
+        })
+    }
+
+    /// Neither pass can be handed the tracked list, so both are filtered
+    /// against it; rg already searched only the changed files, but the walk didn't.
+    fn is_tracked(&self, rel: &Path, is_dir: bool) -> bool {
+        [self.tracked.as_ref(), self.settings.changed_files.as_ref()]
+            .into_iter()
+            .flatten()
+            .all(|files| files.contains(rel, is_dir))
+    }
+
+    /// Whether a walked `rel` may be listed.
+    fn lists(&self, rel: &Path, is_dir: bool) -> bool {
+        let settings = self.settings;
+        let full = self.cwd.join(rel);
+        (is_dir
+            || (settings.allows_file_type(rel)
+                && !settings.excludes_extension(rel)
+                && !settings.oversized_path(&full)
+                && modified_since(&full, self.cutoff)))
+            && self.is_tracked(rel, is_dir)
+            && self.pill_filter.allows(rel, is_dir)
+            && !excluded_path(rel, &self.exclusions)
+    }
+
+    /// Whether a content match in `rel` is kept. A preprocessed file isn't:
+    /// it's matched on its extracted text instead.
+    fn keeps_content(&mut self, rel: &Path, hits: Option<&mut IgnoreHits>) -> bool {
+        let settings = self.settings;
+        !((!settings.include_hidden && is_hidden_path(rel))
+            || settings.globset.is_match_counted(rel, hits)
+            || !settings.allows_file_type(rel)
+            || settings.excludes_extension(rel)
+            || !modified_since(&self.cwd.join(rel), self.cutoff)
+            || !self.pill_filter.allows(rel, false)
+            || excluded_path(rel, &self.exclusions)
+            || self.nested.hides(rel)
+            || !self.is_tracked(rel, false)
+            || settings.preprocessor_for(rel).is_some())
+    }
+
+    /// Whether `rel` is on the search root's filesystem, when walks stay on it.
+    fn on_root_device(&self, rel: &Path) -> bool {
+        #[cfg(target_family = "unix")]
+        if let Some(root_dev) = self.root_dev {
+            if let Ok(metadata) = fs::metadata(self.cwd.join(rel)) {
+                return metadata.dev() == root_dev;
+            }
+        }
+        #[cfg(not(target_family = "unix"))]
+        let _ = rel;
+        true
+    }
+}
+
+fn candidates_with_settings(
+    query: &str,
+    cwd: &Path,
+    settings: &YoinkSettings,
+    stats: &mut SearchStats,
+    mut hits: Option<&mut IgnoreHits>,
+    deadline: &Deadline,
+    content: Option<&HashSet<PathBuf>>,
+) -> Result<Vec<Candidate>> {
+    let settings = &*with_changed_files(cwd, settings)?;
+    #[cfg(feature = "git")]
+    if let Some(rev) = settings.rev.as_deref() {
+        let (positive, _) = split_negations(query);
+        let pattern = if positive.trim().is_empty() {
+            None
+        } else {
+            Some(settings.query_pattern(&positive)?)
+        };
+        let budget = settings.occurrence_budget();
+        return revision_matches(query, pattern.as_ref(), rev, cwd, settings, stats, &budget)
+            .map(|(candidates, _)| candidates);
+    }
+    let mut map: HashMap<PathBuf, Candidate> = HashMap::new();
+    let root_dev = search_root_dev(cwd, settings)?;
+
+    let (positive, negatives) = split_negations(query);
+    let query = positive.as_str();
+    let mut filter = CandidateFilter::new(cwd, settings, &negatives, root_dev)?;
+    let pattern = if query.is_empty() {
+        None
+    } else {
+        Some(settings.query_pattern(query)?)
+    };
+    let path_pattern = folded_path_pattern(query, settings)?;
+    let regex = path_pattern.as_ref().or(pattern.as_ref());
+
+    let walk_started = Instant::now();
+    let listed = if !settings.search_scope.searches_paths(pattern.is_none()) {
+        Vec::new()
+    } else {
+        match settings.file_lister {
+            FileLister::Rg if !settings.internal_engine() => {
+                rg_file_entries(cwd, settings, root_dev, hits.as_deref_mut(), deadline)?
+            }
+            _ => walk_entries(cwd, settings, root_dev, hits.as_deref_mut(), deadline),
+        }
+    };
+
+    let at_cap = |map: &HashMap<PathBuf, Candidate>| {
+        settings.max_results.is_some_and(|cap| map.len() >= cap)
+    };
+    for (index, (rel, is_dir)) in listed.iter().enumerate() {
+        let is_dir = *is_dir;
+        if index % DEADLINE_CHECK_ENTRIES == 0 && deadline.passed() {
+            break;
+        }
+        if !filter.lists(rel, is_dir) {
+            continue;
+        }
+        if path_matches(
+            rel,
+            regex,
+            settings.unicode_fold,
+            settings.match_basename_only,
+        ) {
+            if at_cap(&map) {
+                stats.results_truncated = true;
+                break;
+            }
+            let is_binary = !is_dir
+                && settings.preprocessor_for(rel).is_none()
+                && is_binary_file(&cwd.join(rel));
+            if is_binary && !settings.show_binaries {
+                continue;
+            }
+            let (modified, size) = sort_keys(&cwd.join(rel), is_dir, settings.sort_mode);
+            map.entry(rel.clone())
+                .and_modify(|candidate| candidate.path_match = true)
+                .or_insert(Candidate {
+                    path: rel.clone(),
+                    is_dir,
+                    path_match: true,
+                    content_match: false,
+                    demoted: false,
+                    aliases: Vec::new(),
+                    is_binary,
+                    modified,
+                    size,
+                    fuzzy_match: false,
+                    match_count: 0,
+                });
+        }
+    }
+
+    // Members are listed by name only, and only for a query: an empty one
+    // would spill every archive into the list.
+    #[cfg(feature = "archives")]
+    if settings.archives && regex.is_some() {
+        'archives: for (rel, is_dir) in &listed {
+            if *is_dir || ArchiveKind::of(rel).is_none() || !filter.lists(rel, false) {
+                continue;
+            }
+            if deadline.passed() {
+                break;
+            }
+            // An archive unzip or tar can't read is still listed as a file.
+            let Ok(members) = list_members(&cwd.join(rel)) else {
+                continue;
+            };
+            for member in members {
+                let name = Path::new(&member);
+                if !path_matches(
+                    name,
+                    regex,
+                    settings.unicode_fold,
+                    settings.match_basename_only,
+                ) || excluded_path(name, &filter.exclusions)
+                {
+                    continue;
+                }
+                if at_cap(&map) {
+                    stats.results_truncated = true;
+                    break 'archives;
+                }
+                let path = member_path(rel, &member);
+                map.insert(
+                    path.clone(),
+                    Candidate {
+                        path,
+                        is_dir: false,
+                        path_match: true,
+                        content_match: false,
+                        demoted: false,
+                        aliases: Vec::new(),
+                        is_binary: false,
+                        modified: None,
+                        size: None,
+                        fuzzy_match: false,
+                        match_count: 0,
+                    },
+                );
+            }
+        }
+    }
+
+    stats.walk = walk_started.elapsed();
+
+    if let Some(pattern) = pattern
+        .as_ref()
+        .filter(|_| settings.search_scope.searches_content())
+    {
+        let rg_started = Instant::now();
+        let content_paths = match content {
+            Some(content) => {
+                let mut paths: Vec<PathBuf> = content.iter().cloned().collect();
+                paths.sort();
+                paths
+            }
+            None => content_paths(pattern, cwd, settings, hits.is_some(), deadline)?,
+        };
+        for rel in content_paths {
+            if !filter.keeps_content(&rel, hits.as_deref_mut()) {
+                continue;
+            }
+
+            if at_cap(&map) && !map.contains_key(&rel) {
+                stats.results_truncated = true;
+                continue;
+            }
+
+            if !filter.on_root_device(&rel) {
+                continue;
+            }
+            let full = cwd.join(&rel);
+            let is_dir = full.is_dir();
+            let (modified, size) = sort_keys(&full, is_dir, settings.sort_mode);
+
+            map.entry(rel.clone())
+                .and_modify(|candidate| candidate.content_match = true)
+                .or_insert(Candidate {
+                    path: rel,
+                    is_dir,
+                    path_match: false,
+                    content_match: true,
+                    demoted: false,
+                    aliases: Vec::new(),
+                    is_binary: false,
+                    modified,
+                    size,
+                    fuzzy_match: false,
+                    match_count: 0,
+                });
+        }
+
+        stats.rg_list = rg_started.elapsed();
+    }
+
+    // rg only sees the raw bytes of a preprocessed file, so its matches there
+    // were dropped above; the extracted text is matched here instead.
+    let preprocess_pattern = pattern
+        .as_ref()
+        .filter(|_| settings.search_scope.searches_content() && !settings.preprocessors.is_empty());
+    if let Some(pattern) = preprocess_pattern {
+        let walked;
+        let files = if listed.is_empty() {
+            walked = walk_entries(cwd, settings, root_dev, None, deadline);
+            &walked
+        } else {
+            &listed
+        };
+        for (rel, is_dir) in files {
+            if *is_dir
+                || map
+                    .get(rel)
+                    .is_some_and(|candidate| candidate.content_match)
+                || !filter.lists(rel, false)
+            {
+                continue;
+            }
+            let Some(template) = settings.preprocessor_for(rel) else {
+                continue;
+            };
+            if deadline.passed() {
+                break;
+            }
+            if !extract_text(template, &cwd.join(rel))
+                .is_some_and(|text| text_matches(&text, pattern))
+            {
+                continue;
+            }
+            if at_cap(&map) && !map.contains_key(rel) {
+                stats.results_truncated = true;
+                continue;
+            }
+            let (modified, size) = sort_keys(&cwd.join(rel), false, settings.sort_mode);
+            map.entry(rel.clone())
+                .and_modify(|candidate| candidate.content_match = true)
+                .or_insert(Candidate {
+                    path: rel.clone(),
+                    is_dir: false,
+                    path_match: false,
+                    content_match: true,
+                    demoted: false,
+                    aliases: Vec::new(),
+                    is_binary: false,
+                    modified,
+                    size,
+                    fuzzy_match: false,
+                    match_count: 0,
+                });
+        }
+    }
+
+    // One extra `rg -l` per `!term`, dropping files whose contents match it.
+    if settings.search_scope.searches_content() {
+        for exclusion in &filter.exclusions {
+            for rel in content_paths(exclusion, cwd, settings, false, deadline)? {
+                map.remove(&rel);
+            }
+        }
+    }
+
+    // Nothing matched: retry the listing as an fzf-style subsequence, path-only.
+    if map.is_empty() && settings.fuzzy_fallback && !query.is_empty() && !deadline.expired() {
+        let ignore_case = settings.case_mode.ignores_case(query);
+        for (rel, is_dir) in &listed {
+            let is_dir = *is_dir;
+            if !filter.lists(rel, is_dir)
+                || !fuzzy_subsequence(&rel.to_string_lossy(), query, ignore_case)
+            {
+                continue;
+            }
+            if at_cap(&map) {
+                stats.results_truncated = true;
+                break;
+            }
+            let is_binary = !is_dir
+                && settings.preprocessor_for(rel).is_none()
+                && is_binary_file(&cwd.join(rel));
+            if is_binary && !settings.show_binaries {
+                continue;
+            }
+            let (modified, size) = sort_keys(&cwd.join(rel), is_dir, settings.sort_mode);
+            map.insert(
+                rel.clone(),
+                Candidate {
+                    path: rel.clone(),
+                    is_dir,
+                    path_match: true,
+                    content_match: false,
+                    demoted: false,
+                    aliases: Vec::new(),
+                    is_binary,
+                    modified,
+                    size,
+                    fuzzy_match: true,
+                    match_count: 0,
+                },
+            );
+        }
+    }
+
+    stats.timed_out = deadline.expired();
+    let mut list: Vec<Candidate> = map.into_values().collect();
+    if settings.include_symlinks || settings.dedupe_inodes {
+        list = dedup_by_inode(list, cwd);
+    }
+    for candidate in &mut list {
+        candidate.demoted = settings.demote_globset.is_match(&candidate.path);
+    }
+    sort_candidates_with(&mut list, settings.sort_mode, settings.sort_options());
+    Ok(list)
+}
+
+/// Files whose contents match `pattern`, relative to `cwd`. With AND terms, rg
+/// runs once per term and only files every pass listed are kept. `toggles`
+/// passes every path (for `config stats`) instead of applying the filters.
+fn rg_content_paths(
+    pattern: &QueryPattern,
+    cwd: &Path,
+    settings: &YoinkSettings,
+    toggles: bool,
+    deadline: &Deadline,
+) -> Result<Vec<PathBuf>> {
+    let targets = rg_search_paths(settings);
+    if targets.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut matched: Option<Vec<PathBuf>> = None;
+    for term in pattern.all_terms() {
+        let mut rg_command = Command::new("rg");
+        rg_command
+            .arg("-l")
+            .arg("--color=never")
+            .arg("--no-messages")
+            .args(rg_config_args(settings))
+            .args(term.rg_args());
+        if toggles {
+            rg_command.args(rg_toggle_args(settings));
+        } else {
+            rg_command.args(rg_filter_args(settings));
+        }
+        rg_command
+            .args(rg_max_filesize_args(settings))
+            .args(rg_type_args(settings))
+            .args(rg_pill_args(&settings.pills))
+            .args(rg_suppress_args(settings));
+
+        let stdout = stdout_until(
+            rg_command.arg("--").args(&targets).current_dir(cwd),
+            deadline,
+            b'\n',
+        )
+        .context("failed to execute rg for content matches")?;
+
+        let found: Vec<PathBuf> = String::from_utf8_lossy(&stdout)
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| PathBuf::from(line.trim_start_matches("./")))
+            .collect();
+        let kept = match matched {
+            None => found,
+            Some(previous) => {
+                let found: HashSet<PathBuf> = found.into_iter().collect();
+                previous
+                    .into_iter()
+                    .filter(|path| found.contains(path))
+                    .collect()
+            }
+        };
+        if kept.is_empty() {
+            return Ok(kept);
+        }
+        matched = Some(kept);
+    }
+    Ok(matched.unwrap_or_default())
+}
+
+/// The device of the search root, when walks should stay on that filesystem.
+pub(crate) fn search_root_dev(cwd: &Path, settings: &YoinkSettings) -> Result<Option<u64>> {
+    #[cfg(target_family = "unix")]
+    {
+        if settings.include_mounts {
+            return Ok(None);
+        }
+        let metadata = fs::metadata(cwd)
+            .with_context(|| format!("failed to stat search root: {}", cwd.display()))?;
+        Ok(Some(metadata.dev()))
+    }
+    #[cfg(not(target_family = "unix"))]
+    {
+        let _ = (cwd, settings);
+        Ok(None)
+    }
+}
+
+/// Whether every non-space char of `query` appears in `path`, in order.
+pub fn fuzzy_subsequence(path: &str, query: &str, ignore_case: bool) -> bool {
+    let fold = |ch: char| {
+        if ignore_case {
+            ch.to_lowercase().next().unwrap_or(ch)
+        } else {
+            ch
+        }
+    };
+    let mut haystack = path.chars().map(fold);
+    query
+        .chars()
+        .filter(|ch| !ch.is_whitespace())
+        .map(fold)
+        .all(|wanted| haystack.any(|ch| ch == wanted))
+}
+
+/// Whether a `!term` matches the path or the file name.
+fn excluded_path(rel: &Path, exclusions: &[QueryPattern]) -> bool {
+    exclusions
+        .iter()
+        .any(|exclusion| path_matches(rel, Some(exclusion), UnicodeFold::Off, false))
+}
+
+/// Every term of `pattern` must match the path or the file name; only the file
+/// name under `basename_only`.
+fn path_matches(
+    rel: &Path,
+    pattern: Option<&QueryPattern>,
+    fold: UnicodeFold,
+    basename_only: bool,
+) -> bool {
+    let Some(pattern) = pattern else {
+        return true;
+    };
+    let path_str = rel.to_string_lossy();
+    let file_name = rel
+        .file_name()
+        .map(|v| v.to_string_lossy())
+        .unwrap_or_else(|| path_str.clone());
+    let (path_str, file_name) = match fold {
+        UnicodeFold::Off => (path_str.to_string(), file_name.to_string()),
+        mode => (
+            fold_text(&path_str, mode).text,
+            fold_text(&file_name, mode).text,
+        ),
+    };
+
+    pattern.all_terms().iter().all(|term| {
+        (!basename_only && term.regex.is_match(&path_str)) || term.regex.is_match(&file_name)
+    })
+}
+
+pub fn build_empty_candidates(query: &str, cwd: &Path) -> Result<Vec<Candidate>> {
+    let settings = load_settings_for(cwd)?;
+    empty_candidates_with_settings(query, cwd, &settings, &mut SearchStats::default())
+}
+
+/// Zero-byte files and directories with nothing left in them once ignores and
+/// hidden filtering apply, narrowed to paths matching `query`.
+fn empty_candidates_with_settings(
+    query: &str,
+    cwd: &Path,
+    settings: &YoinkSettings,
+    stats: &mut SearchStats,
+) -> Result<Vec<Candidate>> {
+    let root_dev = search_root_dev(cwd, settings)?;
+    let (positive, negatives) = split_negations(query);
+    let query = positive.as_str();
+    let exclusions = settings.exclusion_patterns(&negatives)?;
+    let pattern = if query.is_empty() {
+        None
+    } else {
+        Some(settings.query_pattern(query)?)
+    };
+    let path_pattern = folded_path_pattern(query, settings)?;
+    let regex = path_pattern.as_ref().or(pattern.as_ref());
+
+    // Always walkdir: `rg --files` never reports directories, so it can't see empty ones.
+    let walk_started = Instant::now();
+    let listed = walk_entries(cwd, settings, root_dev, None, &Deadline::after(None));
+
+    // A directory is empty when no listed entry names it as a parent; ignored
+    // children were pruned by the walk, so they don't count.
+    let parents: HashSet<&Path> = listed.iter().filter_map(|(rel, _)| rel.parent()).collect();
+
+    let mut list = Vec::new();
+    for (rel, is_dir) in &listed {
+        let empty = if *is_dir {
+            !parents.contains(rel.as_path())
+        } else {
+            fs::metadata(cwd.join(rel))
+                .is_ok_and(|metadata| metadata.is_file() && metadata.len() == 0)
+        };
+
+        if empty
+            && path_matches(
+                rel,
+                regex,
+                settings.unicode_fold,
+                settings.match_basename_only,
+            )
+            && !excluded_path(rel, &exclusions)
+        {
+            let (modified, size) = sort_keys(&cwd.join(rel), *is_dir, settings.sort_mode);
+            list.push(Candidate {
+                path: rel.clone(),
+                is_dir: *is_dir,
+                path_match: true,
+                content_match: false,
+                demoted: settings.demote_globset.is_match(rel),
+                aliases: Vec::new(),
+                is_binary: false,
+                modified,
+                size,
+                fuzzy_match: false,
+                match_count: 0,
+            });
+        }
+    }
+    stats.walk = walk_started.elapsed();
+
+    sort_candidates_with(&mut list, settings.sort_mode, settings.sort_options());
+    Ok(list)
+}
+
+/// Rows for `yoink empty`: one per empty file or directory, no occurrences.
+pub fn build_empty_entries(
+    query: &str,
+    cwd: &Path,
+    stats: &mut SearchStats,
+) -> Result<Vec<SearchEntry>> {
+    let settings = load_settings_for(cwd)?;
+    let candidates = empty_candidates_with_settings(query, cwd, &settings, stats)?;
+    let (query, _) = split_negations(query);
+    let query = query.as_str();
+    let pattern = if query.trim().is_empty() {
+        None
+    } else {
+        Some(settings.query_pattern(query)?)
+    };
+    let highlight_re = pattern
+        .as_ref()
+        .and_then(QueryPattern::highlight_regex)
+        .filter(|_| !settings.accessibility);
+    let path_pattern = folded_path_pattern(query, &settings)?.filter(|_| !settings.accessibility);
+    let mut highlighter =
+        HighlightBudget::for_patterns(HIGHLIGHT_TIME_LIMIT, pattern.iter().chain(&path_pattern));
+
+    let row_width = list_width();
+    let color_mode = settings.color_mode();
+    let mut entries = Vec::new();
+    if pattern.as_ref().is_some_and(|pattern| pattern.literal) {
+        entries.push(notice_entry_with(LITERAL_FALLBACK_NOTICE, settings.icons));
+    }
+
+    for candidate in candidates {
+        let icon = path_icon(candidate.is_dir, false, settings.icons);
+        let path_display =
+            highlighter.apply(
+                &shown_path(&candidate.path, &settings),
+                |path| match &path_pattern {
+                    Some(path_pattern) => highlight_folded_matches_in(
+                        path,
+                        &path_pattern.regex,
+                        settings.unicode_fold,
+                        color_mode,
+                        settings.path_match_color,
+                    ),
+                    None => highlight_query_matches_in(
+                        path,
+                        highlight_re,
+                        color_mode,
+                        settings.path_match_color,
+                    ),
+                },
+            );
+        let path_display = if candidate.demoted {
+            dim_display(&path_display)
+        } else {
+            path_display
+        };
+
+        entries.push(SearchEntry {
+            display: compose_file_row(Some(icon), None, &path_display, None, row_width),
+            path: candidate.path,
+            line: None,
+            column: None,
+            kind: None,
+            match_count: None,
+        });
+    }
+
+    highlighter.finish(&mut entries)?;
+    strip_entry_colors(&mut entries, color_mode);
+    Ok(entries)
+}
+
+/// The path-match pattern under `unicode_fold`; content matching keeps the raw query.
+fn folded_path_pattern(query: &str, settings: &YoinkSettings) -> Result<Option<QueryPattern>> {
+    if query.is_empty() || settings.unicode_fold == UnicodeFold::Off {
+        return Ok(None);
+    }
+
+    // Smart case looks at the query as typed; folding lowercases it.
+    settings
+        .compile_query(
+            &fold_query(query, settings.unicode_fold),
+            settings.case_mode.ignores_case(query),
+        )
+        .map(Some)
+}
+
+pub fn build_search_entries(query: &str, cwd: &Path) -> Result<Vec<SearchEntry>> {
+    build_search_entries_with_stats(query, cwd, &mut SearchStats::default())
+}
+
+/// The candidates for `query` under `cwd` and the occurrences of their content
+/// matches, aliases left out. With `on_file`, a one-pass content scan also
+/// hands it each content-matched candidate as soon as the scan finishes that
+/// file; see `stream_content`.
+fn collect_matches(
+    query: &str,
+    pattern: Option<&QueryPattern>,
+    cwd: &Path,
+    settings: &YoinkSettings,
+    stats: &mut SearchStats,
+    deadline: &Deadline,
+    on_file: Option<&mut dyn FnMut(Candidate, Vec<Occurrence>) -> Result<()>>,
+) -> Result<(Vec<Candidate>, OccurrenceScan)> {
+    let settings = &*with_changed_files(cwd, settings)?;
+    let content_pattern = pattern.filter(|_| settings.search_scope.searches_content());
+    let budget = settings.occurrence_budget();
+    #[cfg(feature = "git")]
+    if let Some(rev) = settings.rev.as_deref() {
+        return revision_matches(query, pattern, rev, cwd, settings, stats, &budget);
+    }
+
+    // One `rg --json` pass yields both the content matches and their
+    // occurrences. AND terms still list per term, then match lines separately.
+    // The internal engine handles both in its one pass.
+    let scan_started = Instant::now();
+    let json_scan = match content_pattern {
+        Some(pattern) if settings.internal_engine() || pattern.terms.is_empty() => {
+            Some(match on_file {
+                Some(on_file) => {
+                    stream_content(query, pattern, cwd, settings, &budget, deadline, on_file)?
+                }
+                None if settings.internal_engine() => {
+                    scan_content_internal(pattern, cwd, settings, &budget, deadline, None)?
+                }
+                None => scan_content(pattern, cwd, settings, &budget, deadline, None)?,
+            })
+        }
+        _ => None,
+    };
+    let scan_elapsed = scan_started.elapsed();
+    let candidates = candidates_with_settings(
+        query,
+        cwd,
+        settings,
+        stats,
+        None,
+        deadline,
+        json_scan.as_ref().map(|content| &content.files),
+    )?;
+    let aliases = alias_paths(&candidates);
+
+    let occurrences_started = Instant::now();
+    let content_files: Vec<PathBuf> = candidates
+        .iter()
+        .filter(|candidate| candidate.content_match && !candidate.is_dir)
+        .map(|candidate| candidate.path.clone())
+        .collect();
+    let threads = settings.occurrence_threads();
+    let mut scan = match (json_scan, content_pattern) {
+        (Some(content), _) => {
+            stats.rg_list = scan_elapsed;
+            let mut scan = content.scan;
+            for alias in &aliases {
+                scan.occurrences.remove(alias);
+            }
+            scan
+        }
+        (None, Some(pattern))
+            if threads > 1 && content_files.len() >= PARALLEL_OCCURRENCE_MIN_FILES =>
+        {
+            collect_occurrences_chunked(pattern, cwd, settings, &budget, &content_files, threads)?
+        }
+        (None, Some(pattern)) => collect_occurrences(pattern, cwd, settings, &budget, &aliases)?,
+        (None, None) => OccurrenceScan::default(),
+    };
+    // rg's occurrences in a preprocessed file are of its raw bytes; the extracted text's replace them.
+    if let Some(pattern) = content_pattern.filter(|_| !settings.preprocessors.is_empty()) {
+        scan.occurrences
+            .retain(|path, _| settings.preprocessor_for(path).is_none());
+        for candidate in candidates
+            .iter()
+            .filter(|candidate| candidate.content_match && !candidate.is_dir)
+        {
+            let Some(template) = settings.preprocessor_for(&candidate.path) else {
+                continue;
+            };
+            let Some(text) = extract_text(template, &cwd.join(&candidate.path)) else {
+                continue;
+            };
+            let (occurrences, truncated) = text_occurrences(
+                &text,
+                pattern,
+                budget.per_file,
+                settings.max_columns,
+                settings.icons,
+            );
+            scan.truncated |= truncated;
+            scan.occurrences.insert(candidate.path.clone(), occurrences);
+        }
+    }
+    stats.occurrences = occurrences_started.elapsed();
+    Ok((candidates, scan))
+}
+
+/// `collect_matches`'s content scan on its own thread, handing `on_file` each
+/// matched file as soon as the scan is done with it, as the candidate it will
+/// be in the finished list: the candidate checks are applied here, and the
+/// `!term` content exclusions are listed before the scan starts, so nothing
+/// handed over is taken back.
+fn stream_content(
+    query: &str,
+    pattern: &QueryPattern,
+    cwd: &Path,
+    settings: &YoinkSettings,
+    budget: &OccurrenceBudget,
+    deadline: &Deadline,
+    on_file: &mut dyn FnMut(Candidate, Vec<Occurrence>) -> Result<()>,
+) -> Result<ContentScan> {
+    let (positive, negatives) = split_negations(query);
+    let root_dev = search_root_dev(cwd, settings)?;
+    let mut filter = CandidateFilter::new(cwd, settings, &negatives, root_dev)?;
+    let mut excluded = HashSet::new();
+    for exclusion in &filter.exclusions {
+        excluded.extend(content_paths(exclusion, cwd, settings, false, deadline)?);
+    }
+    let path_pattern = folded_path_pattern(&positive, settings)?;
+    let regex = path_pattern.as_ref().unwrap_or(pattern);
+    let searches_paths = settings.search_scope.searches_paths(false);
+
+    thread::scope(|scope| {
+        let (sink, finished) = mpsc::sync_channel(STREAM_AHEAD_FILES);
+        let scan = scope.spawn(move || {
+            if settings.internal_engine() {
+                scan_content_internal(pattern, cwd, settings, budget, deadline, Some(&sink))
+            } else {
+                scan_content(pattern, cwd, settings, budget, deadline, Some(&sink))
+            }
+        });
+        let mut handed = Ok(());
+        for (rel, occurrences) in finished {
+            if excluded.contains(&rel)
+                || !filter.keeps_content(&rel, None)
+                || !filter.on_root_device(&rel)
+            {
+                continue;
+            }
+            // A path match as the walk would have listed it.
+            let path_match = searches_paths
+                && filter.lists(&rel, false)
+                && path_matches(
+                    &rel,
+                    Some(regex),
+                    settings.unicode_fold,
+                    settings.match_basename_only,
+                );
+            let is_binary = path_match
+                && settings.preprocessor_for(&rel).is_none()
+                && is_binary_file(&cwd.join(&rel));
+            let candidate = Candidate {
+                demoted: settings.demote_globset.is_match(&rel),
+                path: rel,
+                is_dir: false,
+                path_match: path_match && (!is_binary || settings.show_binaries),
+                content_match: true,
+                aliases: Vec::new(),
+                is_binary: is_binary && settings.show_binaries,
+                modified: None,
+                size: None,
+                fuzzy_match: false,
+                match_count: occurrences.len(),
+            };
+            handed = on_file(candidate, occurrences);
+            if handed.is_err() {
+                break;
+            }
+        }
+        let scan = scan
+            .join()
+            .map_err(|_| anyhow::anyhow!("the content scan panicked"))?;
+        handed.and(scan)
+    })
+}
+
+/// `collect_matches` under `--rev`: paths from `rev`'s tree, content from
+/// `git grep` over its blobs. Nothing is read from the work tree, so the
+/// filters that stat files (size, age, binaries, mounts, inodes) don't apply.
+#[cfg(feature = "git")]
+fn revision_matches(
+    query: &str,
+    pattern: Option<&QueryPattern>,
+    rev: &str,
+    cwd: &Path,
+    settings: &YoinkSettings,
+    stats: &mut SearchStats,
+    budget: &OccurrenceBudget,
+) -> Result<(Vec<Candidate>, OccurrenceScan)> {
+    let walk_started = Instant::now();
+    let (positive, negatives) = split_negations(query);
+    let exclusions = settings.exclusion_patterns(&negatives)?;
+    let path_pattern = folded_path_pattern(&positive, settings)?;
+    let regex = path_pattern.as_ref().or(pattern);
+    let content_pattern = pattern.filter(|_| settings.search_scope.searches_content());
+    let pill_filter = PillFilter::new(&settings.pills)?;
+    let tracked = tracked_files(cwd, settings)?;
+    let listable = |rel: &Path, is_dir: bool| {
+        (settings.include_hidden || !is_hidden_path(rel))
+            && !settings.globset.is_match(rel)
+            && (is_dir || (settings.allows_file_type(rel) && !settings.excludes_extension(rel)))
+            && tracked
+                .as_ref()
+                .is_none_or(|tracked| tracked.contains(rel, is_dir))
+            && pill_filter.allows(rel, is_dir)
+            && !excluded_path(rel, &exclusions)
+    };
+    let candidate = |rel: &Path, is_dir: bool| Candidate {
+        path: rel.to_path_buf(),
+        is_dir,
+        path_match: false,
+        content_match: false,
+        demoted: settings.demote_globset.is_match(rel),
+        aliases: Vec::new(),
+        is_binary: false,
+        modified: None,
+        size: None,
+        fuzzy_match: false,
+        match_count: 0,
+    };
+
+    let mut map: HashMap<PathBuf, Candidate> = HashMap::new();
+    if settings.search_scope.searches_paths(pattern.is_none()) {
+        for (rel, is_dir) in GitFiles::at_revision(cwd, rev)?.sorted_entries() {
+            if listable(&rel, is_dir)
+                && path_matches(
+                    &rel,
+                    regex,
+                    settings.unicode_fold,
+                    settings.match_basename_only,
+                )
+            {
+                map.insert(
+                    rel.clone(),
+                    Candidate {
+                        path_match: true,
+                        ..candidate(&rel, is_dir)
+                    },
+                );
+            }
+        }
+    }
+    stats.walk = walk_started.elapsed();
+
+    let grep_started = Instant::now();
+    let mut scan = OccurrenceScan::default();
+    if let Some(pattern) = content_pattern {
+        let output = grep_revision(cwd, rev, pattern)?;
+        let content = read_git_grep_occurrences(
+            &output[..],
+            rev,
+            budget,
+            settings.max_columns,
+            settings.icons,
+        )?;
+        for exclusion in &exclusions {
+            let excluded = read_git_grep_occurrences(
+                &grep_revision(cwd, rev, exclusion)?[..],
+                rev,
+                budget,
+                0,
+                false,
+            )?;
+            map.retain(|rel, _| !excluded.files.contains(rel));
+        }
+        for rel in content.files {
+            if listable(&rel, false) {
+                map.entry(rel.clone())
+                    .or_insert_with(|| candidate(&rel, false))
+                    .content_match = true;
+            }
+        }
+        scan = content.scan;
+        scan.occurrences.retain(|rel, _| map.contains_key(rel));
+    }
+    stats.rg_list = grep_started.elapsed();
+
+    let mut list: Vec<Candidate> = map.into_values().collect();
+    if let Some(cap) = settings.max_results.filter(|cap| list.len() > *cap) {
+        sort_candidates_with(&mut list, SortMode::Depth, settings.sort_options());
+        list.truncate(cap);
+        stats.results_truncated = true;
+    }
+    sort_candidates_with(&mut list, settings.sort_mode, settings.sort_options());
+    Ok((list, scan))
+}
+
+/// Each of the session's roots with the settings to search it by: directory
+/// pills are made relative to the root, and a root that a pill rules out is
+/// left out.
+fn root_searches(settings: &YoinkSettings) -> Vec<(PathBuf, YoinkSettings)> {
+    settings
+        .roots
+        .iter()
+        .filter_map(|label| {
+            let mut root_settings = settings.clone();
+            root_settings.roots = Vec::new();
+            root_settings.pills = Vec::new();
+            for pill in &settings.pills {
+                match pill {
+                    FilterPill::Dir(dir) if dir.starts_with(label) => {
+                        let inner = dir.strip_prefix(label).unwrap_or(dir);
+                        if !inner.as_os_str().is_empty() {
+                            root_settings
+                                .pills
+                                .push(FilterPill::Dir(inner.to_path_buf()));
+                        }
+                    }
+                    FilterPill::Dir(dir) if label.starts_with(dir) => {}
+                    FilterPill::Dir(_) => return None,
+                    pill => root_settings.pills.push(pill.clone()),
+                }
+            }
+            Some((label.clone(), root_settings))
+        })
+        .collect()
+}
+
+/// Puts a root's `label` in front of the paths found under it, so `cwd.join`
+/// on the session root still reaches each file.
+fn label_candidates(candidates: &mut [Candidate], label: &Path) {
+    for candidate in candidates {
+        candidate.path = label.join(&candidate.path);
+        for alias in &mut candidate.aliases {
+            *alias = label.join(&*alias);
+        }
+    }
+}
+
+pub fn build_search_entries_with_stats(
+    query: &str,
+    cwd: &Path,
+    stats: &mut SearchStats,
+) -> Result<Vec<SearchEntry>> {
+    let mut entries = Vec::new();
+    for_each_search_entry(query, cwd, stats, |entry| {
+        entries.push(entry);
+        Ok(())
+    })?;
+    Ok(entries)
+}
+
+/// Hands `emit` the rows for `query` in list order. The sorted modes build the
+/// whole list first; `SortMode::Stream` emits each file's rows, one run per
+/// file, as soon as they're built, and notes disabled highlighting last
+/// rather than first. Under a single root, without `max_results` or alias
+/// folding, that starts mid-scan: each content match goes out as the scan
+/// finishes its file, and the path matches follow once the scan is done.
+pub fn for_each_search_entry(
+    query: &str,
+    cwd: &Path,
+    stats: &mut SearchStats,
+    mut emit: impl FnMut(SearchEntry) -> Result<()>,
+) -> Result<()> {
+    let settings = load_settings_for(cwd)?;
+    // Exclusions are applied to the candidates; only the positive terms match lines.
+    let (positive, _) = split_negations(query);
+    let pattern = if positive.trim().is_empty() {
+        None
+    } else {
+        Some(settings.query_pattern(&positive)?)
+    };
+    let deadline = Deadline::after(settings.search_timeout);
+
+    let path_pattern =
+        folded_path_pattern(&positive, &settings)?.filter(|_| !settings.accessibility);
+    let mut rows = FileRows {
+        cwd,
+        settings: &settings,
+        highlight_re: pattern
+            .as_ref()
+            .and_then(QueryPattern::highlight_regex)
+            .filter(|_| !settings.accessibility),
+        path_pattern: path_pattern.as_ref(),
+        highlighter: HighlightBudget::for_patterns(
+            HIGHLIGHT_TIME_LIMIT,
+            pattern.iter().chain(&path_pattern),
+        ),
+        row_width: list_width(),
+        color_mode: settings.color_mode(),
+    };
+    let color_mode = rows.color_mode;
+    let streaming = settings.sort_mode == SortMode::Stream;
+    // Folding aliases and capping the list both need every match first.
+    let mid_scan = streaming
+        && settings.roots.is_empty()
+        && settings.max_results.is_none()
+        && !(settings.include_symlinks || settings.dedupe_inodes);
+    let mut streamed = false;
+    let mut emitted: HashSet<PathBuf> = HashSet::new();
+    let mut entries = Vec::new();
+    if pattern.as_ref().is_some_and(|pattern| pattern.literal) {
+        entries.push(notice_entry_with(LITERAL_FALLBACK_NOTICE, settings.icons));
+    }
+
+    let (mut candidates, scan) = if mid_scan {
+        // Only an empty query leads with recents, and it has no content scan.
+        let mut early_stats = SearchStats::default();
+        let mut on_file = |candidate: Candidate, occurrences: Vec<Occurrence>| {
+            rows.push(&candidate, occurrences, &mut early_stats, &mut entries);
+            streamed |= !entries.is_empty();
+            strip_entry_colors(&mut entries, color_mode);
+            emitted.insert(candidate.path);
+            entries.drain(..).try_for_each(&mut emit)
+        };
+        let found = collect_matches(
+            query,
+            pattern.as_ref(),
+            cwd,
+            &settings,
+            stats,
+            &deadline,
+            Some(&mut on_file),
+        )?;
+        stats.content_files += early_stats.content_files;
+        stats.path_files += early_stats.path_files;
+        stats.matches += early_stats.matches;
+        found
+    } else if settings.roots.is_empty() {
+        collect_matches(
+            query,
+            pattern.as_ref(),
+            cwd,
+            &settings,
+            stats,
+            &deadline,
+            None,
+        )?
+    } else {
+        let mut candidates = Vec::new();
+        let mut scan = OccurrenceScan::default();
+        for (label, root_settings) in root_searches(&settings) {
+            let (mut found, found_scan) = collect_matches(
+                query,
+                pattern.as_ref(),
+                &cwd.join(&label),
+                &root_settings,
+                stats,
+                &deadline,
+                None,
+            )?;
+            label_candidates(&mut found, &label);
+            candidates.extend(found);
+            scan.truncated |= found_scan.truncated;
+            scan.warnings.extend(found_scan.warnings);
+            scan.occurrences.extend(
+                found_scan
+                    .occurrences
+                    .into_iter()
+                    .map(|(path, occurrences)| (label.join(path), occurrences)),
+            );
+        }
+        sort_candidates_with(&mut candidates, settings.sort_mode, settings.sort_options());
+        (candidates, scan)
+    };
+    record_aliases(&candidates)?;
+    let occurrence_map = scan.occurrences;
+    for candidate in &mut candidates {
+        candidate.match_count = occurrence_map.get(&candidate.path).map_or(0, Vec::len);
+    }
+    if settings.sort_mode == SortMode::Matches {
+        let counts: HashMap<PathBuf, usize> = occurrence_map
+            .iter()
+            .map(|(path, occurrences)| (path.clone(), occurrences.len()))
+            .collect();
+        sort_by_match_count(&mut candidates, &counts, settings.sort_options());
+    }
+
+    if stats.timed_out {
+        entries.push(notice_entry_with(SEARCH_TIMEOUT_NOTICE, settings.icons));
+    }
+    if scan.truncated {
+        entries.push(notice_entry_with(TRUNCATED_RESULTS_NOTICE, settings.icons));
+    }
+    for warning in &scan.warnings {
+        entries.push(notice_entry_with(warning, settings.icons));
+    }
+    // Only an empty query leads with recents; the first keystroke reloads without them.
+    if query.trim().is_empty() && settings.recent_count > 0 {
+        if let Some(store) = selections_path() {
+            entries.extend(recent_entries(&store, cwd, &settings));
+        }
+    }
+
+    for candidate in candidates {
+        if emitted.contains(&candidate.path) {
+            continue;
+        }
+        let occurrences = occurrence_map
+            .get(&candidate.path)
+            .cloned()
+            .unwrap_or_default();
+        rows.push(&candidate, occurrences, stats, &mut entries);
+        if streaming {
+            streamed |= !entries.is_empty();
+            strip_entry_colors(&mut entries, color_mode);
+            entries.drain(..).try_for_each(&mut emit)?;
+        }
+    }
+    if let Some(cap) = settings.max_results.filter(|_| stats.results_truncated) {
+        entries.push(results_cap_entry(cap, settings.icons));
+    }
+
+    let highlighter = rows.highlighter;
+    if streaming {
+        if highlighter.is_disabled() && (streamed || !entries.is_empty()) {
+            entries.push(notice_entry(HIGHLIGHT_OFF_NOTICE));
+        }
+        highlighter.remember_trip()?;
+    } else {
+        highlighter.finish(&mut entries)?;
+    }
+    strip_entry_colors(&mut entries, color_mode);
+    entries.into_iter().try_for_each(emit)
+}
+
+/// Builds a candidate's rows for `for_each_search_entry`: its file row, then
+/// its occurrence and context rows.
+struct FileRows<'a> {
+    cwd: &'a Path,
+    settings: &'a YoinkSettings,
+    highlight_re: Option<&'a Regex>,
+    path_pattern: Option<&'a QueryPattern>,
+    highlighter: HighlightBudget,
+    row_width: usize,
+    color_mode: ColorMode,
+}
+
+impl FileRows<'_> {
+    /// Appends `candidate`'s rows to `entries` and counts it in `stats`.
+    /// Content matches stay listed even when the budget left them without
+    /// occurrences.
+    fn push(
+        &mut self,
+        candidate: &Candidate,
+        occurrences: Vec<Occurrence>,
+        stats: &mut SearchStats,
+        entries: &mut Vec<SearchEntry>,
+    ) {
+        let FileRows {
+            cwd,
+            settings,
+            highlight_re,
+            path_pattern,
+            ref mut highlighter,
+            row_width,
+            color_mode,
+        } = *self;
+        let count = candidate.match_count;
+        if candidate.path_match || candidate.content_match || count > 0 {
+            if candidate.content_match || count > 0 {
+                stats.content_files += 1;
+                stats.matches += count;
+            } else {
+                stats.path_files += 1;
+            }
+            let kind = MatchKind::of(candidate).or(Some(MatchKind::Content));
+            let icon = path_icon(candidate.is_dir, candidate.is_binary, settings.icons);
+            let path_display =
+                highlighter.apply(
+                    &shown_path(&candidate.path, settings),
+                    |path| match &path_pattern {
+                        Some(path_pattern) => highlight_folded_matches_in(
+                            path,
+                            &path_pattern.regex,
+                            settings.unicode_fold,
+                            color_mode,
+                            settings.path_match_color,
+                        ),
+                        None => highlight_query_matches_in(
+                            path,
+                            highlight_re,
+                            color_mode,
+                            settings.path_match_color,
+                        ),
+                    },
+                );
+
+            let badge = if !settings.show_language {
+                None
+            } else if candidate.is_dir {
+                Some(language_badge(None))
+            } else {
+                Some(language_badge(detect_language(&cwd.join(&candidate.path))))
+            };
+
+            let path_display = if candidate.fuzzy_match {
+                let marker = if settings.icons {
+                    FUZZY_MARKER
+                } else {
+                    "(fuzzy)"
+                };
+                format!("{} {marker}", dim_display(&path_display))
+            } else if candidate.demoted {
+                dim_display(&path_display)
+            } else {
+                path_display
+            };
+            let path_display = if candidate.aliases.is_empty() {
+                path_display
+            } else {
+                format!(
+                    "{path_display}{}",
+                    dim_display(&alias_suffix(candidate.aliases.len()))
+                )
+            };
+            let path_display = match kind.filter(|_| settings.icons) {
+                Some(kind) => format!("{} {path_display}", dim_display(kind.badge())),
+                None => path_display,
+            };
+            // The count sits on the file row, so it stays in view however many
+            // occurrence rows `occurrence_display_limit` shows; text rows spell it out.
+            let path_display = match count {
+                0 => path_display,
+                count if settings.icons => format!("{path_display} \x1b[33m({count})\x1b[0m"),
+                1 => format!("{path_display} (1 match)"),
+                count => format!("{path_display} ({count} matches)"),
+            };
+
+            let teaser = occurrences
+                .first()
+                .filter(|_| settings.teaser)
+                .map(|occurrence| teaser_text(&occurrence.snippet));
+            let display = compose_file_row(
+                Some(icon),
+                badge.as_deref(),
+                &path_display,
+                teaser.as_deref(),
+                row_width,
+            );
+
+            entries.push(SearchEntry {
+                display,
+                path: candidate.path.clone(),
+                line: None,
+                column: None,
+                kind,
+                match_count: (count > 0).then_some(count),
+            });
+
+            let line_width = occurrences
+                .iter()
+                .map(|occurrence| occurrence.line.to_string().len())
+                .max()
+                .unwrap_or(4)
+                .max(4);
+
+            let hidden = count.saturating_sub(settings.occurrence_display_limit);
+            let mut shown: Vec<Occurrence> = occurrences
+                .into_iter()
+                .take(settings.occurrence_display_limit)
+                .collect();
+            // Context comes from the work tree's text, which `--rev` doesn't
+            // search and a preprocessed file doesn't have.
+            if settings.snippet_context > 0
+                && settings.rev.is_none()
+                && settings.preprocessor_for(&candidate.path).is_none()
+            {
+                read_snippet_context(
+                    &cwd.join(&candidate.path),
+                    &mut shown,
+                    settings.snippet_context,
+                );
+            }
+            // Context rows run in line order: none twice, and none for a line
+            // that has its own occurrence row.
+            let match_lines: HashSet<usize> =
+                shown.iter().map(|occurrence| occurrence.line).collect();
+            let mut last_listed = 0;
+            for occurrence in shown {
+                let context_row = |context_line: usize, text: &str| {
+                    let text = truncate_snippet(text, 140, settings.icons);
+                    let display = if settings.icons {
+                        dim_display(&format!("     ↳ {context_line:>line_width$}  {text}"))
+                    } else {
+                        format!("  context line {context_line}: {text}")
+                    };
+                    // Selecting a context row jumps to the match it surrounds.
+                    SearchEntry {
+                        display,
+                        path: candidate.path.clone(),
+                        line: Some(occurrence.line),
+                        column: Some(occurrence.column),
+                        kind: None,
+                        match_count: None,
+                    }
+                };
+                let (before, after): (Vec<_>, Vec<_>) = occurrence
+                    .context
+                    .iter()
+                    .filter(|(context_line, _)| !match_lines.contains(context_line))
+                    .partition(|(context_line, _)| *context_line < occurrence.line);
+                for (context_line, text) in before {
+                    if *context_line > last_listed {
+                        entries.push(context_row(*context_line, text));
+                        last_listed = *context_line;
+                    }
+                }
+
+                let snippet = if occurrence.long_line && occurrence.snippet == LONG_LINE_NOTE {
+                    dim_display(&occurrence.snippet)
+                } else {
+                    highlighter.apply(&occurrence.snippet, |snippet| {
+                        highlight_query_matches_in(
+                            snippet,
+                            highlight_re,
+                            color_mode,
+                            settings.content_match_color,
+                        )
+                    })
+                };
+                let snippet = truncate_snippet(&snippet, 140, settings.icons);
+                let display = if settings.icons {
+                    format!(
+                        "     ↳ {:>width$}  {}",
+                        occurrence.line,
+                        snippet,
+                        width = line_width
+                    )
+                } else {
+                    format!("  line {}: {snippet}", occurrence.line)
+                };
+
+                entries.push(SearchEntry {
+                    display,
+                    path: candidate.path.clone(),
+                    line: Some(occurrence.line),
+                    column: Some(occurrence.column),
+                    kind: None,
+                    match_count: None,
+                });
+                last_listed = last_listed.max(occurrence.line);
+
+                for (context_line, text) in after {
+                    if *context_line > last_listed {
+                        entries.push(context_row(*context_line, text));
+                        last_listed = *context_line;
+                    }
+                }
+            }
+            if hidden > 0 && settings.occurrence_display_limit > 0 {
+                entries.push(more_matches_entry(&candidate.path, hidden, settings.icons));
+            }
+        }
+    }
+}
+
+/// Fills in `context` for `occurrences` (sorted by line) from one read of
+/// `full`, stopping after the last line any of them needs. An unreadable file
+/// leaves them without context.
+fn read_snippet_context(full: &Path, occurrences: &mut [Occurrence], context: usize) {
+    let Some(last) = occurrences
+        .last()
+        .map(|occurrence| occurrence.line + context)
+    else {
+        return;
+    };
+    let Ok(file) = fs::File::open(full) else {
+        return;
+    };
+    let mut lines = Vec::new();
+    for line in BufReader::new(file).split(b'\n').take(last) {
+        let Ok(line) = line else {
+            return;
+        };
+        lines.push(sanitize_control(String::from_utf8_lossy(&line).trim()));
+    }
+    for occurrence in occurrences {
+        let start = occurrence.line.saturating_sub(context).max(1);
+        let end = (occurrence.line + context).min(lines.len());
+        occurrence.context = (start..=end)
+            .filter(|line| *line != occurrence.line)
+            .map(|line| (line, lines[line - 1].clone()))
+            .collect();
+    }
+}
+
+/// Every occurrence of `query` under `cwd`, within `YoinkSettings::occurrence_budget`;
+/// for callers that render matches themselves rather than as list rows. Files
+/// under `aliases` (see `alias_paths`) are skipped.
+pub fn scan_occurrences(query: &str, cwd: &Path, aliases: &[PathBuf]) -> Result<OccurrenceScan> {
+    let (positive, _) = split_negations(query);
+    if positive.trim().is_empty() {
+        return Ok(OccurrenceScan::default());
+    }
+    let settings = load_settings_for(cwd)?;
+    let pattern = settings.query_pattern(&positive)?;
+    if settings.internal_engine() {
+        let deadline = Deadline::after(settings.search_timeout);
+        let mut scan = scan_content_internal(
+            &pattern,
+            cwd,
+            &settings,
+            &settings.occurrence_budget(),
+            &deadline,
+            None,
+        )?
+        .scan;
+        for alias in aliases {
+            scan.occurrences.remove(alias);
+        }
+        return Ok(scan);
+    }
+    collect_occurrences(
+        &pattern,
+        cwd,
+        &settings,
+        &settings.occurrence_budget(),
+        aliases,
+    )
+}
+
+/// Keeps the session's alias map in step with the latest reload, for the preview header.
+fn record_aliases(candidates: &[Candidate]) -> Result<()> {
+    let Some(session) = Session::from_env() else {
+        return Ok(());
+    };
+    let aliases: Vec<(PathBuf, Vec<PathBuf>)> = candidates
+        .iter()
+        .filter(|candidate| !candidate.aliases.is_empty())
+        .map(|candidate| (candidate.path.clone(), candidate.aliases.clone()))
+        .collect();
+    if session.load().aliases != aliases {
+        session.set_aliases(aliases)?;
+    }
+    Ok(())
+}
+
+/// Plain mode keeps the `>>match<<` markers but drops badges, dimming and counts' color.
+fn strip_entry_colors(entries: &mut [SearchEntry], color_mode: ColorMode) {
+    if color_mode == ColorMode::Plain {
+        for entry in entries {
+            entry.display = strip_ansi(&entry.display);
+        }
+    }
+}
+
+pub fn build_buffer_entries(query: &str, buffer: &Path) -> Result<Vec<SearchEntry>> {
+    let content = fs::read(buffer)
+        .with_context(|| format!("failed to read stdin buffer: {}", buffer.display()))?;
+    let content = String::from_utf8_lossy(&content);
+
+    let settings = load_settings()?;
+    let pattern = if query.trim().is_empty() {
+        None
+    } else {
+        Some(settings.query_pattern(query)?)
+    };
+    let regex = pattern.as_ref().map(|pattern| &pattern.regex);
+    let highlight_re = pattern
+        .as_ref()
+        .and_then(QueryPattern::highlight_regex)
+        .filter(|_| !settings.accessibility);
+    let mut highlighter = HighlightBudget::for_patterns(HIGHLIGHT_TIME_LIMIT, pattern.iter());
+
+    let line_width = content.lines().count().to_string().len().max(4);
+    let color_mode = settings.color_mode();
+    let mut entries = Vec::new();
+
+    for (index, raw_line) in content.lines().enumerate() {
+        let column = match regex {
+            None => None,
+            Some(re) => match re.find(raw_line) {
+                Some(found) => Some(found.start() + 1),
+                None => continue,
+            },
+        };
+
+        let line_num = index + 1;
+        let snippet = highlighter.apply(&sanitize_control(raw_line), |line| {
+            highlight_query_matches_in(line, highlight_re, color_mode, settings.content_match_color)
+        });
+        let snippet = truncate_snippet(&snippet, 140, settings.icons);
+        let display = if settings.icons {
+            format!("{:>width$}  {}", line_num, snippet, width = line_width)
+        } else {
+            format!("line {line_num}: {snippet}")
+        };
+        entries.push(SearchEntry {
+            display,
+            path: buffer.to_path_buf(),
+            line: Some(line_num),
+            column,
+            kind: None,
+            match_count: None,
+        });
+    }
+
+    highlighter.finish(&mut entries)?;
+    strip_entry_colors(&mut entries, color_mode);
+    Ok(entries)
+}
+
+pub fn buffer_line(buffer: &Path, line: usize) -> Result<Option<String>> {
+    let content = fs::read(buffer)
+        .with_context(|| format!("failed to read stdin buffer: {}", buffer.display()))?;
+    let content = String::from_utf8_lossy(&content);
+    Ok(line
+        .checked_sub(1)
+        .and_then(|index| content.lines().nth(index))
+        .map(|text| text.to_string()))
+}
+
+pub fn timing_header(stats: &SearchStats) -> String {
+    let mut header = format!(
+        "walk {}ms · rg {}ms · occ {}ms · fmt {}ms",
+        stats.walk.as_millis(),
+        stats.rg_list.as_millis(),
+        stats.occurrences.as_millis(),
+        stats.format.as_millis()
+    );
+    if stats.truncated_rows > 0 {
+        header.push_str(&format!(" · {} rows truncated", stats.truncated_rows));
+    }
+    header
+}
+
+/// `42 files (30 content, 12 path only) · 187 matches · 12ms`, the summary line
+/// `show_stats` puts above each reload.
+pub fn stats_header(stats: &SearchStats, elapsed: Duration) -> String {
+    let count = |count: usize, one: &str, many: &str| {
+        format!("{count} {}", if count == 1 { one } else { many })
+    };
+    format!(
+        "{} ({} content, {} path only) · {} · {}ms",
+        count(stats.content_files + stats.path_files, "file", "files"),
+        stats.content_files,
+        stats.path_files,
+        count(stats.matches, "match", "matches"),
+        elapsed.as_millis()
+    )
+}
+
+/// How many file rows get a number, one per `Alt-1`…`Alt-9` binding.
+pub const NUMBERED_FILE_ROWS: usize = 9;
+
+/// Prefixes the first nine file rows with a dim `[N]` and returns their 1-based
+/// positions in the list, which is what fzf's `pos()` action expects. A file's
+/// "and N more" row shares its path and isn't numbered again.
+pub fn number_file_rows(entries: &mut [SearchEntry], color_mode: ColorMode) -> Vec<usize> {
+    let mut numbers = FileRowNumbers::default();
+    for entry in entries.iter_mut() {
+        if numbers.rows.len() == NUMBERED_FILE_ROWS {
+            break;
+        }
+        numbers.number(entry, color_mode);
+    }
+    numbers.rows
+}
+
+/// `number_file_rows` one entry at a time, for rows written as they arrive.
+#[derive(Debug, Clone, Default)]
+pub struct FileRowNumbers {
+    /// 1-based list positions of the numbered rows so far.
+    pub rows: Vec<usize>,
+    seen: usize,
+    last_file: Option<PathBuf>,
+}
+
+impl FileRowNumbers {
+    /// Numbers `entry` if it's one of the first nine file rows; every entry
+    /// passed counts toward the positions.
+    pub fn number(&mut self, entry: &mut SearchEntry, color_mode: ColorMode) {
+        self.seen += 1;
+        if self.rows.len() == NUMBERED_FILE_ROWS
+            || entry.line.is_some()
+            || entry.path.as_os_str().is_empty()
+            || self.last_file.as_ref() == Some(&entry.path)
+        {
+            return;
+        }
+        self.last_file = Some(entry.path.clone());
+
+        let label = format!("[{}]", self.rows.len() + 1);
+        entry.display = match color_mode {
+            ColorMode::Ansi => format!("\x1b[2m{label}\x1b[0m {}", entry.display),
+            ColorMode::Plain => format!("{label} {}", entry.display),
+        };
+        self.rows.push(self.seen);
+    }
+}
+
+/// The fzf action for `Alt-<n>`: `pos(row)` of the nth numbered file, or nothing.
+pub fn jump_action(rows: &[usize], n: usize) -> String {
+    n.checked_sub(1)
+        .and_then(|index| rows.get(index))
+        .map(|row| format!("pos({row})"))
+        .unwrap_or_default()
+}
+
+/// Whether the search root can no longer be listed: deleted, unmounted or unreadable.
+pub fn root_unavailable(root: &Path) -> bool {
+    fs::read_dir(root).is_err()
+}
+
+pub fn root_gone_message(root: &Path) -> String {
+    format!("search root no longer exists: {}", root.display())
+}
+
+/// The single row a reload lists once its root is gone, in place of walk and rg errors.
+pub fn root_gone_entry(root: &Path) -> SearchEntry {
+    notice_entry(&root_gone_message(root))
+}
+
+pub const RECENT_SEPARATOR: &str = "── recent ──";
+/// The dim suffix that marks a recent row apart from the same path in the listing.
+pub const RECENT_MARKER: &str = " (recent)";
+
+/// The separator and one file row per recent selection in `cwd` from the log
+/// at `store`; nothing when there are none. Rows carry the real path, so
+/// selecting one behaves like selecting it in the listing.
+pub fn recent_entries(store: &Path, cwd: &Path, settings: &YoinkSettings) -> Vec<SearchEntry> {
+    let recent = recent_selections(store, cwd, settings.recent_count);
+    if recent.is_empty() {
+        return Vec::new();
+    }
+    let separator = if settings.icons {
+        RECENT_SEPARATOR
+    } else {
+        "Recent selections:"
+    };
+    let mut entries = vec![SearchEntry {
+        display: dim_display(separator),
+        path: PathBuf::new(),
+        line: None,
+        column: None,
+        kind: None,
+        match_count: None,
+    }];
+    for rel in recent {
+        let icon = path_icon(cwd.join(&rel).is_dir(), false, settings.icons);
+        let path_display = format!(
+            "{}{}",
+            sanitize_control(&rel.to_string_lossy()),
+            dim_display(RECENT_MARKER)
+        );
+        entries.push(SearchEntry {
+            display: compose_file_row(Some(icon), None, &path_display, None, 0),
+            path: rel,
+            line: None,
+            column: None,
+            kind: None,
+            match_count: None,
+        });
+    }
+    entries
+}
+
+/// Informational row with an empty path, so selecting it behaves like an empty selection.
+/// Closes a file's occurrence rows past `occurrence_display_limit`. It keeps the
+/// path without a line, so Enter acts on the file.
+pub fn more_matches_entry(path: &Path, hidden: usize, icons: bool) -> SearchEntry {
+    let noun = if hidden == 1 { "match" } else { "matches" };
+    let display = if icons {
+        format!(
+            "     ↳ {}",
+            dim_display(&format!("… and {hidden} more {noun}"))
+        )
+    } else {
+        format!("  and {hidden} more {noun}")
+    };
+    SearchEntry {
+        display,
+        path: path.to_path_buf(),
+        line: None,
+        column: None,
+        kind: None,
+        match_count: None,
+    }
+}
+
+/// The last row when `max_results` cut the candidates short; its empty path
+/// makes selecting it a no-op, like a notice.
+pub fn results_cap_entry(cap: usize, icons: bool) -> SearchEntry {
+    let marker = if icons { "…" } else { "NOTE:" };
+    SearchEntry {
+        display: dim_display(&format!(
+            "{marker} results truncated at {cap} (refine your query)"
+        )),
+        path: PathBuf::new(),
+        line: None,
+        column: None,
+        kind: None,
+        match_count: None,
+    }
+}
+
+pub fn notice_entry(message: &str) -> SearchEntry {
+    notice_entry_with(message, true)
+}
+
+/// `notice_entry`, led by `NOTE:` rather than `⚠` without `icons`.
+pub fn notice_entry_with(message: &str, icons: bool) -> SearchEntry {
+    let marker = if icons { "⚠" } else { "NOTE:" };
+    SearchEntry {
+        display: format!("\x1b[2;33m{marker} {message}\x1b[0m"),
+        path: PathBuf::new(),
+        line: None,
+        column: None,
+        kind: None,
+        match_count: None,
+    }
+}
+
+/// A path's bytes; its lossy UTF-8 where the platform has no byte view.
+pub fn path_bytes(path: &Path) -> Cow<'_, [u8]> {
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        Cow::Borrowed(path.as_os_str().as_bytes())
+    }
+    #[cfg(not(target_family = "unix"))]
+    match path.to_string_lossy() {
+        Cow::Borrowed(text) => Cow::Borrowed(text.as_bytes()),
+        Cow::Owned(text) => Cow::Owned(text.into_bytes()),
+    }
+}
+
+pub fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::ffi::OsStringExt;
+        PathBuf::from(std::ffi::OsString::from_vec(bytes))
+    }
+    #[cfg(not(target_family = "unix"))]
+    PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// The path field of an fzf line: the path as text, with `%`, control
+/// characters (a tab or newline would split the line) and any bytes that
+/// aren't UTF-8 written as `%XX`, so it decodes back to the exact path.
+pub fn encode_path_field(path: &Path) -> String {
+    let bytes = path_bytes(path);
+    let mut out = String::with_capacity(bytes.len());
+    for chunk in bytes.utf8_chunks() {
+        for ch in chunk.valid().chars() {
+            match ch {
+                '%' => out.push_str("%25"),
+                ch if ch.is_ascii_control() => out.push_str(&format!("%{:02X}", ch as u8)),
+                ch => out.push(ch),
+            }
+        }
+        for byte in chunk.invalid() {
+            out.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    out
+}
+
+/// Reverses `encode_path_field`; a `%` not followed by two hex digits stays as is.
+pub fn decode_path_field(field: &str) -> PathBuf {
+    let raw = field.as_bytes();
+    let mut bytes = Vec::with_capacity(raw.len());
+    let mut index = 0;
+    while index < raw.len() {
+        let escaped = (raw[index] == b'%')
+            .then(|| raw.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(byte) => {
+                bytes.push(byte);
+                index += 3;
+            }
+            None => {
+                bytes.push(raw[index]);
+                index += 1;
+            }
+        }
+    }
+    path_from_bytes(bytes)
+}
+
+pub fn format_search_entries(entries: &[SearchEntry]) -> String {
+    format_search_entries_capped(
+        entries,
+        DEFAULT_MAX_ENTRY_BYTES,
+        &mut SearchStats::default(),
+    )
+}
+
+/// Formats entries as `display\tpath\tline\tcolumn\tmatch_count` lines, each
+/// held to `max_bytes`; only the display column is cut, so the other fields
+/// always survive intact. Fields a row doesn't have are left empty.
+pub fn format_search_entries_capped(
+    entries: &[SearchEntry],
+    max_bytes: usize,
+    stats: &mut SearchStats,
+) -> String {
+    let mut out = String::new();
+    for entry in entries {
+        let path = encode_path_field(&entry.path);
+        let line = entry.line.map(|v| v.to_string()).unwrap_or_default();
+        let column = entry.column.map(|v| v.to_string()).unwrap_or_default();
+        let match_count = entry.match_count.map(|v| v.to_string()).unwrap_or_default();
+        let hidden_len = path.len() + line.len() + column.len() + match_count.len() + 5;
+
+        let display = entry
+            .display
+            .replace('\t', "    ")
+            .replace(['\n', '\r'], " ");
+        let display = match cap_display(&display, max_bytes.saturating_sub(hidden_len)) {
+            Some(capped) => {
+                stats.truncated_rows += 1;
+                capped
+            }
+            None => display,
+        };
+
+        out.push_str(&display);
+        out.push('\t');
+        out.push_str(&path);
+        out.push('\t');
+        out.push_str(&line);
+        out.push('\t');
+        out.push_str(&column);
+        out.push('\t');
+        out.push_str(&match_count);
+        out.push('\n');
+    }
+    out
+}
+
+/// Cuts `display` to at most `budget` bytes (ending in a reset and `…`), or
+/// returns `None` when it already fits. Escape sequences and UTF-8 characters
+/// are kept whole, so the cut never leaves a dangling partial sequence.
+pub fn cap_display(display: &str, budget: usize) -> Option<String> {
+    if display.len() <= budget {
+        return None;
+    }
+
+    let limit = budget.saturating_sub(TRUNCATION_SUFFIX.len());
+    let mut end = 0;
+    let mut rest = display;
+    while let Some(ch) = rest.chars().next() {
+        let token_len = if ch == '\x1b' {
+            escape_sequence_len(rest)
+        } else {
+            ch.len_utf8()
+        };
+        if end + token_len > limit {
+            break;
+        }
+        end += token_len;
+        rest = &rest[token_len..];
+    }
+
+    Some(format!("{}{TRUNCATION_SUFFIX}", &display[..end]))
+}
+
+/// Byte length of the escape sequence at the start of `text`: `ESC [ params final`
+/// for CSI, `ESC x` otherwise.
+fn escape_sequence_len(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    if bytes.get(1) != Some(&b'[') {
+        return text.chars().take(2).map(char::len_utf8).sum();
+    }
+
+    bytes[2..]
+        .iter()
+        .position(|byte| (0x40..=0x7e).contains(byte))
+        .map_or(text.len(), |offset| offset + 3)
+}
+
+/// Columns available to the result list beside the preview pane. fzf exports
+/// `FZF_COLUMNS` to reload commands; `COLUMNS` covers other callers.
+pub fn list_width() -> usize {
+    let columns = ["FZF_COLUMNS", "COLUMNS"]
+        .iter()
+        .find_map(|key| env::var(key).ok()?.trim().parse::<usize>().ok())
+        .unwrap_or(120);
+    columns * (100 - PREVIEW_WIDTH_PERCENT) / 100
+}
+
+/// Lays out `icon badge path  ·  teaser` within `width` columns. The path is
+/// never shortened: the teaser only gets the columns left after the prefix and
+/// at least `MIN_PATH_WIDTH` for the path, and is dropped when too little remains.
+pub fn compose_file_row(
+    icon: Option<&str>,
+    badge: Option<&str>,
+    path_display: &str,
+    teaser: Option<&str>,
+    width: usize,
+) -> String {
+    let mut row = String::new();
+    let mut used = 0;
+
+    if let Some(icon) = icon {
+        row.push_str(icon);
+        row.push(' ');
+        used += display_width(icon) + 1;
+    }
+    if let Some(badge) = badge {
+        row.push_str(badge);
+        row.push(' ');
+        used += display_width(badge) + 1;
+    }
+    row.push_str(path_display);
+    used += display_width(path_display).max(MIN_PATH_WIDTH);
+
+    if let Some(teaser) = teaser {
+        let budget = width.saturating_sub(used + display_width(TEASER_SEPARATOR));
+        if budget >= MIN_TEASER_WIDTH && !teaser.is_empty() {
+            row.push_str("\x1b[2m");
+            row.push_str(TEASER_SEPARATOR);
+            row.push_str(&truncate_to_width(teaser, budget));
+            row.push_str("\x1b[0m");
+        }
+    }
+
+    row
+}
+
+/// Dims a row segment, re-applying dim after any reset from match highlighting.
+pub fn dim_display(text: &str) -> String {
+    format!(
+        "\x1b[2m{}\x1b[0m",
+        text.replace("\x1b[0m", "\x1b[0m\x1b[2m")
+    )
+}
+
+/// A first-match snippet flattened to one plain line for the teaser column.
+fn teaser_text(snippet: &str) -> String {
+    snippet
+        .trim()
+        .chars()
+        .map(|ch| if ch == '\t' { ' ' } else { ch })
+        .filter(|ch| !ch.is_control())
+        .collect()
+}
+
+/// Terminal columns taken by `text`, ignoring ANSI SGR sequences and counting
+/// emoji and East Asian wide characters as two, per grapheme cluster.
+pub fn display_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut rest = text;
+    while !rest.is_empty() {
+        if rest.starts_with('\x1b') {
+            rest = &rest[escape_sequence_len(rest)..];
+            continue;
+        }
+        let end = rest.find('\x1b').unwrap_or(rest.len());
+        width += graphemes(&rest[..end])
+            .into_iter()
+            .map(grapheme_width)
+            .sum::<usize>();
+        rest = &rest[end..];
+    }
+    width
+}
+
+/// Splits `text` into extended grapheme clusters (UAX #29), the units a
+/// terminal draws and truncation never splits.
+pub fn graphemes(text: &str) -> Vec<&str> {
+    text.graphemes(true).collect()
+}
+
+/// Columns one grapheme cluster takes, emoji sequences and East Asian wide
+/// characters as two and combining marks as none.
+fn grapheme_width(cluster: &str) -> usize {
+    UnicodeWidthStr::width(cluster)
+}
+
+fn truncate_to_width(text: &str, max_width: usize) -> String {
+    truncate_with_marker(text, max_width, "…")
+}
+
+/// Cuts plain `text` to `max_width` columns, `marker` included, or returns it
+/// unchanged when it fits. Grapheme clusters are never split.
+fn truncate_with_marker(text: &str, max_width: usize, marker: &str) -> String {
+    if display_width(text) <= max_width {
+        return text.to_string();
+    }
+
+    let budget = max_width.saturating_sub(display_width(marker));
+    let mut out = String::new();
+    let mut used = 0;
+    for cluster in graphemes(text) {
+        let width = grapheme_width(cluster);
+        if used + width > budget {
+            break;
+        }
+        out.push_str(cluster);
+        used += width;
+    }
+    out.push_str(marker);
+    out
+}
+
+/// The path as a file row shows it, before highlighting: control characters in
+/// the name made visible, then shortened per `path_ellipsis`.
+fn shown_path(path: &Path, settings: &YoinkSettings) -> String {
+    let path = sanitize_control(&path.to_string_lossy());
+    if settings.path_ellipsis {
+        ellipsize_path(&path, PATH_DISPLAY_MAX_WIDTH)
+    } else {
+        path
+    }
+}
+
+/// Shortens a long relative path from the middle, keeping its first component
+/// and as many trailing ones as fit in `max_width` columns: `src/…/deep/file.rs`.
+/// A file name too long for that is cut at the end instead.
+pub fn ellipsize_path(path: &str, max_width: usize) -> String {
+    if display_width(path) <= max_width {
+        return path.to_string();
+    }
+    let Some((head, rest)) = path.split_once('/') else {
+        return truncate_to_width(path, max_width);
+    };
+
+    let prefix = format!("{head}/…");
+    let mut tail = String::new();
+    for component in rest.rsplit('/') {
+        let candidate = format!("/{component}{tail}");
+        if display_width(&prefix) + display_width(&candidate) > max_width {
+            break;
+        }
+        tail = candidate;
+    }
+    if tail.is_empty() {
+        return truncate_to_width(path, max_width);
+    }
+    format!("{prefix}{tail}")
+}
+
+fn path_depth(path: &Path) -> usize {
+    path.components().count()
+}
+
+/// Cuts `snippet` to `max_width` columns, marked with `…`, or ` (truncated)`
+/// without `icons`; the marker only appears when something was cut.
+pub fn truncate_snippet(snippet: &str, max_width: usize, icons: bool) -> String {
+    truncate_with_marker(snippet, max_width, if icons { "…" } else { " (truncated)" })
+}
+
+pub fn highlight_query_matches(text: &str, re: Option<&Regex>, color_mode: ColorMode) -> String {
+    highlight_query_matches_in(text, re, color_mode, CONTENT_MATCH_COLOR)
+}
+
+/// `highlight_query_matches` in a chosen color, e.g. `path_match_color` for file names.
+pub fn highlight_query_matches_in(
+    text: &str,
+    re: Option<&Regex>,
+    color_mode: ColorMode,
+    color: u8,
+) -> String {
+    let Some(re) = re else {
+        return text.to_string();
+    };
+
+    highlight_ranges(
+        text,
+        re.find_iter(text).map(|matched| matched.range()),
+        color_mode,
+        color,
+    )
+}
+
+/// Guards highlighting against costly patterns: off from the start when a pattern
+/// only compiled past the regex size limits or the session already turned it off,
+/// and off for the rest of the session once a single call takes longer than `limit`.
+#[derive(Debug, Clone)]
+pub struct HighlightBudget {
+    limit: Duration,
+    disabled: bool,
+    tripped: bool,
+}
+
+impl HighlightBudget {
+    pub fn new(limit: Duration, disabled: bool) -> Self {
+        HighlightBudget {
+            limit,
+            disabled,
+            tripped: false,
+        }
+    }
+
+    fn for_patterns<'a>(
+        limit: Duration,
+        patterns: impl IntoIterator<Item = &'a QueryPattern>,
+    ) -> Self {
+        let patterns: Vec<&QueryPattern> = patterns.into_iter().collect();
+        if patterns.is_empty() {
+            return HighlightBudget::new(limit, false);
+        }
+        let session_off = Session::from_env().is_some_and(|session| session.load().highlight_off);
+        let too_big = patterns.iter().any(|pattern| !pattern.highlight);
+        HighlightBudget::new(limit, session_off || too_big)
+    }
+
+    /// Runs `highlight` on `text`, or returns `text` unchanged once disabled.
+    pub fn apply(&mut self, text: &str, highlight: impl FnOnce(&str) -> String) -> String {
+        if self.disabled {
+            return text.to_string();
+        }
+        let started = Instant::now();
+        let highlighted = highlight(text);
+        if started.elapsed() > self.limit {
+            self.disabled = true;
+            self.tripped = true;
+        }
+        highlighted
+    }
+
+    pub fn is_disabled(&self) -> bool {
+        self.disabled
+    }
+
+    /// Whether this run was the one to turn highlighting off.
+    pub fn tripped(&self) -> bool {
+        self.tripped
+    }
+
+    /// Notes disabled highlighting at the top of the list and remembers a trip in the session.
+    fn finish(&self, entries: &mut Vec<SearchEntry>) -> Result<()> {
+        if self.disabled && !entries.is_empty() {
+            entries.insert(0, notice_entry(HIGHLIGHT_OFF_NOTICE));
+        }
+        self.remember_trip()
+    }
+
+    fn remember_trip(&self) -> Result<()> {
+        if self.tripped {
+            if let Some(session) = Session::from_env() {
+                session.set_highlight_off(true)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Matches run against the folded text; their offsets are mapped back so the
+/// original spelling is what gets displayed.
+pub fn highlight_folded_matches(
+    text: &str,
+    re: &Regex,
+    mode: UnicodeFold,
+    color_mode: ColorMode,
+) -> String {
+    highlight_folded_matches_in(text, re, mode, color_mode, CONTENT_MATCH_COLOR)
+}
+
+pub fn highlight_folded_matches_in(
+    text: &str,
+    re: &Regex,
+    mode: UnicodeFold,
+    color_mode: ColorMode,
+    color: u8,
+) -> String {
+    let folded = fold_text(text, mode);
+    let ranges: Vec<Range<usize>> = re
+        .find_iter(&folded.text)
+        .map(|matched| folded.original_range(matched.range()))
+        .collect();
+    highlight_ranges(text, ranges, color_mode, color)
+}
+
+fn highlight_ranges(
+    text: &str,
+    ranges: impl IntoIterator<Item = Range<usize>>,
+    color_mode: ColorMode,
+    color: u8,
+) -> String {
+    let match_start = color_mode.match_start_in(color);
+    let mut out = String::new();
+    let mut last = 0usize;
+
+    for range in ranges {
+        // Folded matches can map onto the same original character twice.
+        let start = range.start.max(last);
+        if range.end < start {
+            continue;
+        }
+        if start > last {
+            out.push_str(&text[last..start]);
+        }
+        out.push_str(&match_start);
+        out.push_str(&text[start..range.end]);
+        out.push_str(color_mode.match_end());
+        last = range.end;
+    }
+
+    if last < text.len() {
+        out.push_str(&text[last..]);
+    }
+
+    out
+}
+
+/// Folds paths that reach the same file into one candidate, keyed by device and
+/// inode, so a hard link or a followed symlink doesn't list the file twice. The
+/// shallowest path is kept and the others become its `aliases`; directories are
+/// left as they are.
+pub fn dedup_by_inode(list: Vec<Candidate>, cwd: &Path) -> Vec<Candidate> {
+    #[cfg(target_family = "unix")]
+    {
+        let mut kept: Vec<Candidate> = Vec::with_capacity(list.len());
+        let mut by_inode: HashMap<(u64, u64), usize> = HashMap::new();
+        for candidate in list {
+            let inode = fs::metadata(cwd.join(&candidate.path))
+                .ok()
+                .filter(|metadata| !candidate.is_dir && metadata.is_file())
+                .map(|metadata| (metadata.dev(), metadata.ino()));
+            let Some(inode) = inode else {
+                kept.push(candidate);
+                continue;
+            };
+            match by_inode.get(&inode) {
+                Some(&index) => merge_alias(&mut kept[index], candidate),
+                None => {
+                    by_inode.insert(inode, kept.len());
+                    kept.push(candidate);
+                }
+            }
+        }
+        kept
+    }
+    #[cfg(not(target_family = "unix"))]
+    {
+        let _ = cwd;
+        list
+    }
+}
 
-    if !settings.include_mounts {
-        rg_command.arg("--one-file-system");
+/// Keeps whichever of the two paths has fewer components (then sorts first) and
+/// records the other as an alias; a match on either counts for the file.
+#[cfg(target_family = "unix")]
+fn merge_alias(kept: &mut Candidate, mut other: Candidate) {
+    let rank = |candidate: &Candidate| (path_depth(&candidate.path), candidate.path.clone());
+    if rank(&other) < rank(kept) {
+        std::mem::swap(kept, &mut other);
     }
+    kept.path_match |= other.path_match;
+    kept.content_match |= other.content_match;
+    kept.aliases.push(other.path);
+    kept.aliases.append(&mut other.aliases);
+    kept.aliases.sort();
+}
 
-    if settings.include_symlinks {
-        rg_command.arg("--follow");
+/// ` (+1 alias)` / ` (+3 aliases)` after a file row's path; empty without aliases.
+pub fn alias_suffix(count: usize) -> String {
+    match count {
+        0 => String::new(),
+        1 => " (+1 alias)".to_string(),
+        count => format!(" (+{count} aliases)"),
     }
+}
 
-    for pattern in &settings.globs {
-        rg_command.arg("-g").arg(format!("!{pattern}"));
+/// Alias paths across `candidates`; their occurrences belong to the kept path.
+pub fn alias_paths(candidates: &[Candidate]) -> Vec<PathBuf> {
+    candidates
+        .iter()
+        .flat_map(|candidate| candidate.aliases.iter().cloned())
+        .collect()
+}
+
+/// Past this many aliases the occurrence pass drops them from rg's output
+/// instead of passing one exclude glob each.
+pub const MAX_ALIAS_EXCLUDES: usize = 256;
+
+/// Anchored `-g !/path` excludes so rg doesn't read a file again through an alias.
+pub fn rg_alias_excludes(aliases: &[PathBuf]) -> Vec<String> {
+    if aliases.len() > MAX_ALIAS_EXCLUDES {
+        return Vec::new();
+    }
+    aliases
+        .iter()
+        .flat_map(|alias| {
+            [
+                "-g".to_string(),
+                format!("!/{}", escape_glob(&alias.to_string_lossy())),
+            ]
+        })
+        .collect()
+}
+
+/// Makes glob metacharacters in a literal path match only themselves.
+fn escape_glob(path: &str) -> String {
+    let mut escaped = String::with_capacity(path.len());
+    for ch in path.chars() {
+        match ch {
+            '*' | '?' | '[' | ']' | '{' | '}' => {
+                escaped.push('[');
+                escaped.push(ch);
+                escaped.push(']');
+            }
+            '\\' => escaped.push_str("\\\\"),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// Demoted candidates sort after everything else whatever the sort mode.
+pub fn sort_candidates(candidates: &mut [Candidate], sort_mode: SortMode) {
+    sort_candidates_with(candidates, sort_mode, SortOptions::default());
+}
+
+pub fn sort_candidates_with(
+    candidates: &mut [Candidate],
+    sort_mode: SortMode,
+    options: SortOptions,
+) {
+    match sort_mode {
+        SortMode::Depth | SortMode::Matches => {
+            candidates.sort_by_key(|candidate| {
+                (
+                    candidate.demoted,
+                    path_depth(&candidate.path),
+                    options.file_group(candidate),
+                    candidate.path.to_string_lossy().to_string(),
+                )
+            });
+        }
+        SortMode::Alphabetical => {
+            candidates.sort_by_key(|candidate| {
+                (
+                    candidate.demoted,
+                    options.file_group(candidate),
+                    candidate.path.to_string_lossy().to_string(),
+                )
+            });
+        }
+        SortMode::Modified => {
+            // Newest first; an unreadable mtime sorts after every dated entry.
+            candidates.sort_by_key(|candidate| {
+                (
+                    candidate.demoted,
+                    options.file_group(candidate),
+                    candidate.modified.is_none(),
+                    Reverse(candidate.modified),
+                    candidate.path.to_string_lossy().to_string(),
+                )
+            });
+        }
+        SortMode::Size => {
+            // Directories first in either direction; an unknown size goes last in its group.
+            candidates.sort_by(|a, b| {
+                let group = |candidate: &Candidate| {
+                    (
+                        candidate.demoted,
+                        !candidate.is_dir,
+                        candidate.size.is_none(),
+                    )
+                };
+                let by_size = match options.size_order {
+                    SizeOrder::Ascending => a.size.cmp(&b.size),
+                    SizeOrder::Descending => b.size.cmp(&a.size),
+                };
+                group(a)
+                    .cmp(&group(b))
+                    .then(by_size)
+                    .then_with(|| a.path.to_string_lossy().cmp(&b.path.to_string_lossy()))
+            });
+        }
+        SortMode::Stream => {}
+    }
+}
+
+/// `SortMode::Matches`: content matches by descending occurrence count, then
+/// path-only matches; ties keep depth order. Demoted candidates still go last.
+pub fn sort_by_match_count(
+    candidates: &mut [Candidate],
+    counts: &HashMap<PathBuf, usize>,
+    options: SortOptions,
+) {
+    candidates.sort_by_key(|candidate| {
+        (
+            candidate.demoted,
+            options.file_group(candidate),
+            !candidate.content_match,
+            Reverse(counts.get(&candidate.path).copied().unwrap_or(0)),
+            path_depth(&candidate.path),
+            candidate.path.to_string_lossy().to_string(),
+        )
+    });
+}
+
+/// Caps on what one occurrence pass reads from rg, so a huge file with a common
+/// token can't balloon memory on every keystroke.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OccurrenceBudget {
+    pub per_file: usize,
+    pub total: usize,
+    /// Bytes kept of any one rg output line; the rest of the line is skipped unread.
+    pub line_bytes: usize,
+}
+
+/// Matched lines longer than this many bytes are cut by rg (`--max-columns`).
+pub const DEFAULT_MAX_COLUMNS: usize = 1000;
+/// Shown in place of the snippet of a line rg cut at `max_columns`.
+pub const LONG_LINE_NOTE: &str = "long line truncated by rg";
+/// Ends the snippet of a `multiline` match that continues past its first line.
+pub const MULTILINE_MARKER: &str = "⤸";
+
+/// Occurrence rows per file unless `occurrence_display_limit` says otherwise.
+pub const DEFAULT_OCCURRENCE_DISPLAY_LIMIT: usize = 20;
+
+pub const DEFAULT_OCCURRENCE_BUDGET: OccurrenceBudget = OccurrenceBudget {
+    per_file: 1_000,
+    total: 20_000,
+    line_bytes: 8 * 1024,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct OccurrenceScan {
+    pub occurrences: HashMap<PathBuf, Vec<Occurrence>>,
+    /// A budget was hit and some occurrences were left out.
+    pub truncated: bool,
+    /// Chunks of a parallel scan that failed; the rest were kept.
+    pub warnings: Vec<String>,
+}
+
+/// Content-match files from which the occurrence pass splits into parallel rg chunks.
+pub const PARALLEL_OCCURRENCE_MIN_FILES: usize = 256;
+/// argv bytes per chunk. rg has no option to read a file list, so chunks are
+/// capped the way xargs caps them, far below any platform's `ARG_MAX`.
+pub const CHUNK_ARG_BYTES: usize = 64 * 1024;
+
+/// rg flags shared by every occurrence pass, up to the paths to search.
+fn occurrence_command(
+    pattern: &QueryPattern,
+    settings: &YoinkSettings,
+    budget: &OccurrenceBudget,
+) -> Command {
+    let mut rg_command = Command::new("rg");
+    rg_command
+        .arg("-n")
+        .arg("--column")
+        .arg("--no-heading")
+        // A NUL after the path, so a colon in it (`C:\repo`) can't shift the fields.
+        .arg("--null")
+        // rg leaves the path out when handed a single file to search.
+        .arg("--with-filename")
+        .arg("--color=never")
+        .arg("--no-messages")
+        .args(rg_config_args(settings))
+        // One past the per-file budget, so the reader can tell a file was cut short.
+        .arg("--max-count")
+        .arg((budget.per_file + 1).to_string())
+        .args(rg_max_columns_args(settings))
+        .args(rg_max_filesize_args(settings))
+        .args(pattern.rg_args());
+    if pattern.multiline {
+        // One record per match, at its first line; the standard output repeats
+        // the line prelude for every line a match covers.
+        rg_command.arg("--vimgrep");
     }
+    rg_command
+}
 
-    let output = rg_command
-        .arg(".")
+/// Spawns `rg_command` and reads its occurrences. With `strict`, rg reporting an
+/// error without having printed any match is an `Err` too.
+fn run_occurrence_command(
+    mut rg_command: Command,
+    cwd: &Path,
+    budget: &OccurrenceBudget,
+    strict: bool,
+) -> Result<OccurrenceScan> {
+    let mut child = rg_command
         .current_dir(cwd)
-        .output()
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
         .context("failed to execute rg for detailed occurrences")?;
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut map: HashMap<PathBuf, Vec<Occurrence>> = HashMap::new();
+    let stdout = child
+        .stdout
+        .take()
+        .context("failed to read rg output for detailed occurrences")?;
+    let scan = read_occurrences(BufReader::new(stdout), budget);
 
-    for raw_line in stdout.lines().filter(|line| !line.trim().is_empty()) {
-        let mut parts = raw_line.splitn(4, ':');
-        let raw_path = match parts.next() {
-            Some(value) => value,
-            None => continue,
-        };
-        let raw_line_num = match parts.next() {
-            Some(value) => value,
-            None => continue,
+    // Past the budget there is nothing left worth reading; stop rg rather than drain it.
+    if scan.as_ref().map_or(true, |scan| scan.truncated) {
+        let _ = child.kill();
+        let _ = child.wait();
+        return scan;
+    }
+    let status = child.wait().context("failed to wait for rg")?;
+    let scan = scan?;
+    if strict && status.code().is_some_and(|code| code > 1) && scan.occurrences.is_empty() {
+        anyhow::bail!("rg exited with {status}");
+    }
+    Ok(scan)
+}
+
+/// What the content passes hand rg: `.`, or under `--changed` just the changed
+/// files, which may be none at all.
+fn rg_search_paths(settings: &YoinkSettings) -> Vec<PathBuf> {
+    match &settings.changed_files {
+        Some(changed) => changed.sorted_files(),
+        None => vec![PathBuf::from(".")],
+    }
+}
+
+/// `settings` with `changed_files` looked up for a search of `cwd`, so git
+/// runs once per root rather than once per rg pass.
+fn with_changed_files<'a>(
+    cwd: &Path,
+    settings: &'a YoinkSettings,
+) -> Result<Cow<'a, YoinkSettings>> {
+    if settings.changed_files.is_some() {
+        return Ok(Cow::Borrowed(settings));
+    }
+    Ok(match changed_files(cwd, settings)? {
+        Some(changed) => Cow::Owned(YoinkSettings {
+            changed_files: Some(changed),
+            ..settings.clone()
+        }),
+        None => Cow::Borrowed(settings),
+    })
+}
+
+/// `rg_content_paths`, or the files `scan_content_internal` matches under
+/// `internal_engine`, in path order.
+fn content_paths(
+    pattern: &QueryPattern,
+    cwd: &Path,
+    settings: &YoinkSettings,
+    toggles: bool,
+    deadline: &Deadline,
+) -> Result<Vec<PathBuf>> {
+    if !settings.internal_engine() {
+        return rg_content_paths(pattern, cwd, settings, toggles, deadline);
+    }
+    let listing_only = OccurrenceBudget {
+        per_file: 0,
+        total: 0,
+        line_bytes: 0,
+    };
+    let mut paths: Vec<PathBuf> =
+        scan_content_internal(pattern, cwd, settings, &listing_only, deadline, None)?
+            .files
+            .into_iter()
+            .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+/// Where a content scan hands over each file as soon as it's done with it, so
+/// `SortMode::Stream` can list the file while the scan goes on.
+pub(crate) type FileSink = mpsc::SyncSender<(PathBuf, Vec<Occurrence>)>;
+/// Files a streaming scan may finish ahead of the listing; past that it waits,
+/// as rg waits on a full pipe.
+const STREAM_AHEAD_FILES: usize = 64;
+
+/// Sends the file in `file` to `sink` with its multiline snippets marked, as
+/// the scan marks them all at its end. A closed sink means the listing
+/// stopped, which the scan finds out for itself soon enough.
+pub(crate) fn send_file(
+    sink: &FileSink,
+    mut file: OccurrenceScan,
+    pattern: &QueryPattern,
+    icons: bool,
+) {
+    mark_multiline_snippets(&mut file, pattern, icons);
+    for finished in file.occurrences {
+        let _ = sink.send(finished);
+    }
+}
+
+/// The content-match list and the occurrence pass as one `rg --json` run,
+/// instead of `rg -l` followed by `rg -n`. The JSON printer doesn't cut long
+/// lines, so `read_json_occurrences` applies `max_columns` itself.
+fn scan_content(
+    pattern: &QueryPattern,
+    cwd: &Path,
+    settings: &YoinkSettings,
+    budget: &OccurrenceBudget,
+    deadline: &Deadline,
+    sink: Option<&FileSink>,
+) -> Result<ContentScan> {
+    let targets = rg_search_paths(settings);
+    if targets.is_empty() {
+        return Ok(ContentScan::default());
+    }
+    let mut rg_command = Command::new("rg");
+    rg_command
+        .arg("--json")
+        .arg("--no-messages")
+        .args(rg_config_args(settings))
+        // One past the per-file budget, so the reader can tell a file was cut short.
+        .arg("--max-count")
+        .arg((budget.per_file + 1).to_string())
+        .args(pattern.rg_args())
+        .args(rg_filter_args(settings))
+        .args(rg_max_filesize_args(settings))
+        .args(rg_type_args(settings))
+        .args(rg_pill_args(&settings.pills))
+        .args(rg_suppress_args(settings))
+        .arg("--")
+        .args(&targets)
+        .current_dir(cwd);
+
+    // A killed rg leaves at most a half-written last event, which doesn't parse.
+    let (content, _) = run_until(&mut rg_command, deadline, |stdout| {
+        read_json_occurrences_with(
+            BufReader::new(stdout),
+            budget,
+            settings.max_columns,
+            |path, occurrences| {
+                let Some(sink) = sink else {
+                    return;
+                };
+                let mut file = OccurrenceScan::default();
+                file.occurrences
+                    .insert(path.to_path_buf(), occurrences.to_vec());
+                window_long_lines(&mut file, cwd, settings.icons);
+                send_file(sink, file, pattern, settings.icons);
+            },
+        )
+    })
+    .context("failed to execute rg for content matches")?;
+    let mut content = content?;
+    if !content.long_events.is_empty() {
+        let mut files: Vec<PathBuf> = content.long_events.iter().cloned().collect();
+        files.sort();
+        let mut rg_command = occurrence_command(pattern, settings, budget);
+        rg_command.arg("--").args(&files);
+        let reread = run_occurrence_command(rg_command, cwd, budget, false)?;
+        for file in &files {
+            content.scan.occurrences.remove(file);
+        }
+        content.scan = merge_occurrence_scans(
+            vec![Ok(std::mem::take(&mut content.scan)), Ok(reread)],
+            budget,
+        )?;
+    }
+    window_long_lines(&mut content.scan, cwd, settings.icons);
+    mark_multiline_snippets(&mut content.scan, pattern, settings.icons);
+    Ok(content)
+}
+
+fn collect_occurrences(
+    pattern: &QueryPattern,
+    cwd: &Path,
+    settings: &YoinkSettings,
+    budget: &OccurrenceBudget,
+    aliases: &[PathBuf],
+) -> Result<OccurrenceScan> {
+    let targets = rg_search_paths(settings);
+    if targets.is_empty() {
+        return Ok(OccurrenceScan::default());
+    }
+    let mut rg_command = occurrence_command(pattern, settings, budget);
+    rg_command
+        .args(rg_filter_args(settings))
+        .args(rg_type_args(settings))
+        .args(rg_pill_args(&settings.pills))
+        .args(rg_alias_excludes(aliases))
+        .args(rg_suppress_args(settings))
+        .arg("--")
+        .args(&targets);
+
+    let mut scan = run_occurrence_command(rg_command, cwd, budget, false)?;
+    for alias in aliases {
+        scan.occurrences.remove(alias);
+    }
+    window_long_lines(&mut scan, cwd, settings.icons);
+    mark_multiline_snippets(&mut scan, pattern, settings.icons);
+    Ok(scan)
+}
+
+/// Bytes of a cut line shown around its match.
+pub const LONG_LINE_WINDOW: usize = 120;
+
+/// Replaces `LONG_LINE_NOTE` on lines rg cut with the stretch of the line
+/// around the match, so the row shows the match rather than the line's start.
+fn window_long_lines(scan: &mut OccurrenceScan, cwd: &Path, icons: bool) {
+    let marker = if icons { "…" } else { "..." };
+    for (path, occurrences) in &mut scan.occurrences {
+        for occurrence in occurrences
+            .iter_mut()
+            .filter(|occurrence| occurrence.long_line)
+        {
+            let full = cwd.join(path);
+            if let Some(window) = line_window(
+                &full,
+                occurrence.line,
+                occurrence.column,
+                LONG_LINE_WINDOW,
+                marker,
+            ) {
+                occurrence.snippet = window;
+            }
+        }
+    }
+}
+
+/// Up to `width` bytes of line `line` centered on byte `column` (both 1-based),
+/// read without loading the rest of the line; `marker` shows at a cut end.
+/// `None` when the file has changed so the column is no longer on that line.
+pub fn line_window(
+    full: &Path,
+    line: usize,
+    column: usize,
+    width: usize,
+    marker: &str,
+) -> Option<String> {
+    let mut reader = BufReader::new(fs::File::open(full).ok()?);
+    for _ in 1..line {
+        if reader.skip_until(b'\n').ok()? == 0 {
+            return None;
+        }
+    }
+    column_window(reader, column, width, marker)
+}
+
+/// `line_window` for a reader already at the start of the line.
+pub fn column_window(
+    mut reader: impl BufRead,
+    column: usize,
+    width: usize,
+    marker: &str,
+) -> Option<String> {
+    let start = column.saturating_sub(1).saturating_sub(width / 2);
+    let mut remaining = start;
+    while remaining > 0 {
+        let available = reader.fill_buf().ok()?;
+        let skip = available.len().min(remaining);
+        if skip == 0 || available[..skip].contains(&b'\n') {
+            return None;
+        }
+        reader.consume(skip);
+        remaining -= skip;
+    }
+
+    let mut window = Vec::with_capacity(width + 1);
+    reader
+        .take(width as u64 + 1)
+        .read_to_end(&mut window)
+        .ok()?;
+    let cut_end = match window.iter().position(|byte| *byte == b'\n') {
+        Some(newline) => {
+            window.truncate(newline);
+            false
+        }
+        None if window.len() > width => {
+            window.truncate(width);
+            true
+        }
+        None => false,
+    };
+    // The cut may land inside a character; drop its halves rather than show `�`.
+    let text = String::from_utf8_lossy(&window);
+    let text = sanitize_control(text.trim_matches('\u{FFFD}').trim());
+    Some(format!(
+        "{}{text}{}",
+        if start > 0 { marker } else { "" },
+        if cut_end { marker } else { "" }
+    ))
+}
+
+/// Marks snippets whose match runs past their line: the snippet is only the
+/// match's first line, which doesn't match the pattern on its own.
+pub(crate) fn mark_multiline_snippets(
+    scan: &mut OccurrenceScan,
+    pattern: &QueryPattern,
+    icons: bool,
+) {
+    if !pattern.multiline {
+        return;
+    }
+    let marker = if icons {
+        MULTILINE_MARKER
+    } else {
+        "(continues)"
+    };
+    for occurrence in scan.occurrences.values_mut().flatten() {
+        if !occurrence.long_line && !pattern.regex.is_match(&occurrence.snippet) {
+            occurrence.snippet = format!("{} {marker}", occurrence.snippet);
+        }
+    }
+}
+
+/// The occurrence pass over a known list of content-match files, as up to
+/// `threads` rg processes over chunks of it. The files already passed every
+/// filter, so the chunks name them directly instead of walking `.`.
+fn collect_occurrences_chunked(
+    pattern: &QueryPattern,
+    cwd: &Path,
+    settings: &YoinkSettings,
+    budget: &OccurrenceBudget,
+    files: &[PathBuf],
+    threads: usize,
+) -> Result<OccurrenceScan> {
+    let chunks = chunk_paths(files, threads, CHUNK_ARG_BYTES);
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(chunks.len()));
+
+    thread::scope(|scope| {
+        for _ in 0..threads.min(chunks.len()) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(chunk) = chunks.get(index) else {
+                    break;
+                };
+                let mut rg_command = occurrence_command(pattern, settings, budget);
+                rg_command.arg("--").args(chunk);
+                let result = run_occurrence_command(rg_command, cwd, budget, true);
+                results.lock().expect("chunk results").push((index, result));
+            });
+        }
+    });
+
+    let mut results = results.into_inner().expect("chunk results");
+    results.sort_by_key(|(index, _)| *index);
+    let mut scan = merge_occurrence_scans(
+        results.into_iter().map(|(_, result)| result).collect(),
+        budget,
+    )?;
+    window_long_lines(&mut scan, cwd, settings.icons);
+    mark_multiline_snippets(&mut scan, pattern, settings.icons);
+    Ok(scan)
+}
+
+/// Splits `files` into at least `chunks` contiguous runs of near-equal length,
+/// splitting further so no run's paths take more than `max_arg_bytes` of argv.
+pub fn chunk_paths(files: &[PathBuf], chunks: usize, max_arg_bytes: usize) -> Vec<Vec<PathBuf>> {
+    if files.is_empty() {
+        return Vec::new();
+    }
+    let per_chunk = files.len().div_ceil(chunks.max(1));
+    let mut out = Vec::new();
+    for run in files.chunks(per_chunk) {
+        let mut current = Vec::new();
+        let mut bytes = 0;
+        for path in run {
+            // The path plus its terminating NUL.
+            let size = path.as_os_str().len() + 1;
+            if !current.is_empty() && bytes + size > max_arg_bytes {
+                out.push(std::mem::take(&mut current));
+                bytes = 0;
+            }
+            current.push(path.clone());
+            bytes += size;
+        }
+        out.push(current);
+    }
+    out
+}
+
+/// Merges per-chunk scans, re-applying the global budget in path order. Failed
+/// chunks become one warning; only when every chunk failed is it an error.
+pub fn merge_occurrence_scans(
+    results: Vec<Result<OccurrenceScan>>,
+    budget: &OccurrenceBudget,
+) -> Result<OccurrenceScan> {
+    let chunks = results.len();
+    let mut merged = OccurrenceScan::default();
+    let mut failures = Vec::new();
+    for result in results {
+        match result {
+            Ok(scan) => {
+                merged.truncated |= scan.truncated;
+                merged.warnings.extend(scan.warnings);
+                merged.occurrences.extend(scan.occurrences);
+            }
+            Err(error) => failures.push(error),
+        }
+    }
+
+    if let Some(first) = failures.first() {
+        if failures.len() == chunks {
+            return Err(failures.swap_remove(0)).context("every occurrence chunk failed");
+        }
+        merged.warnings.push(format!(
+            "(partial results) {} of {chunks} occurrence chunks failed: {first:#}",
+            failures.len()
+        ));
+    }
+
+    let mut paths: Vec<PathBuf> = merged.occurrences.keys().cloned().collect();
+    paths.sort();
+    let mut remaining = budget.total;
+    for path in paths {
+        let Some(occurrences) = merged.occurrences.get_mut(&path) else {
+            continue;
         };
-        let raw_column = match parts.next() {
-            Some(value) => value,
-            None => continue,
+        if occurrences.len() > remaining {
+            occurrences.truncate(remaining);
+            merged.truncated = true;
+        }
+        remaining -= occurrences.len();
+        if occurrences.is_empty() {
+            merged.occurrences.remove(&path);
+        }
+    }
+    Ok(merged)
+}
+
+/// Minified bundles put hundreds of kilobytes on one line; rg previews the
+/// first `max_columns` bytes of those rather than printing them whole.
+pub fn rg_max_columns_args(settings: &YoinkSettings) -> Vec<String> {
+    if settings.max_columns == 0 {
+        return Vec::new();
+    }
+    vec![
+        "--max-columns".to_string(),
+        settings.max_columns.to_string(),
+        "--max-columns-preview".to_string(),
+    ]
+}
+
+/// Data dumps make slow searches and useless snippets; rg skips files past `max_filesize`.
+pub fn rg_max_filesize_args(settings: &YoinkSettings) -> Vec<String> {
+    match settings.max_filesize {
+        Some(bytes) => vec!["--max-filesize".to_string(), bytes.to_string()],
+        None => Vec::new(),
+    }
+}
+
+/// Parses `rg -n --column --no-heading --null` output incrementally, stopping at the
+/// global budget and skipping files past the per-file one.
+pub fn read_occurrences(
+    mut reader: impl BufRead,
+    budget: &OccurrenceBudget,
+) -> Result<OccurrenceScan> {
+    let mut scan = OccurrenceScan::default();
+    let mut total = 0;
+    let mut line = Vec::new();
+
+    while read_capped_line(&mut reader, &mut line, budget.line_bytes)
+        .context("failed to read rg output for detailed occurrences")?
+    {
+        let Some((path, occurrence)) = parse_occurrence_line(&line) else {
+            continue;
         };
-        let raw_snippet = parts.next().unwrap_or_default();
 
-        let path = PathBuf::from(raw_path.trim_start_matches("./"));
-        let line_num = match raw_line_num.parse::<usize>() {
-            Ok(value) => value,
-            Err(_) => continue,
+        if total >= budget.total {
+            scan.truncated = true;
+            break;
+        }
+
+        let occurrences = scan.occurrences.entry(path).or_default();
+        if occurrences.len() >= budget.per_file {
+            scan.truncated = true;
+            continue;
+        }
+        occurrences.push(occurrence);
+        total += 1;
+    }
+
+    for occurrences in scan.occurrences.values_mut() {
+        occurrences.sort_by_key(|occurrence| (occurrence.line, occurrence.column));
+    }
+
+    Ok(scan)
+}
+
+/// Files with content matches, and their occurrences, from one `rg --json` pass.
+#[derive(Debug, Clone, Default)]
+pub struct ContentScan {
+    pub files: HashSet<PathBuf>,
+    pub scan: OccurrenceScan,
+    /// Files with a match event past `line_bytes`, which couldn't be parsed;
+    /// `scan_content` re-reads their occurrences with rg cutting the lines.
+    pub long_events: HashSet<PathBuf>,
+}
+
+/// Parses `rg --json` events: every `begin` names a content-match file, every
+/// `match` becomes an occurrence at its first submatch. Past the global budget
+/// only `begin` events are kept, so the file list stays whole. Lines past
+/// `max_columns` bytes (0 for no limit) get `LONG_LINE_NOTE`, as rg's own cut
+/// would; an event past `line_bytes` can't be parsed and is skipped, though its
+/// file still counts from its `begin`.
+pub fn read_json_occurrences(
+    reader: impl BufRead,
+    budget: &OccurrenceBudget,
+    max_columns: usize,
+) -> Result<ContentScan> {
+    read_json_occurrences_with(reader, budget, max_columns, |_, _| {})
+}
+
+/// `read_json_occurrences`, handing `on_file` each file and its occurrences
+/// at the file's `end` event, while rg goes on with the rest. A file with an
+/// event too long to parse isn't handed over, since its occurrences are
+/// incomplete until `scan_content` reads them again.
+pub fn read_json_occurrences_with(
+    mut reader: impl BufRead,
+    budget: &OccurrenceBudget,
+    max_columns: usize,
+    mut on_file: impl FnMut(&Path, &[Occurrence]),
+) -> Result<ContentScan> {
+    let mut content = ContentScan::default();
+    let mut total = 0;
+    let mut line = Vec::new();
+    let mut current: Option<PathBuf> = None;
+
+    while read_capped_line(&mut reader, &mut line, budget.line_bytes)
+        .context("failed to read rg output for content matches")?
+    {
+        let Some(event) = parse_event(&String::from_utf8_lossy(&line)) else {
+            if line.len() >= budget.line_bytes {
+                content.long_events.extend(current.clone());
+            }
+            continue;
         };
-        let column = match raw_column.parse::<usize>() {
-            Ok(value) => value,
-            Err(_) => continue,
+        let found = match event {
+            RgEvent::Begin { path } => {
+                current = Some(path.clone());
+                content.files.insert(path);
+                continue;
+            }
+            RgEvent::Match(found) => found,
+            RgEvent::End { path } => {
+                if !content.long_events.contains(&path) {
+                    let occurrences = content.scan.occurrences.get(&path);
+                    on_file(&path, occurrences.map_or(&[], Vec::as_slice));
+                }
+                continue;
+            }
+            RgEvent::Other => continue,
         };
 
-        map.entry(path).or_default().push(Occurrence {
-            line: line_num,
-            column,
-            snippet: raw_snippet.replace('\t', " ").trim().to_string(),
+        if total >= budget.total {
+            content.scan.truncated = true;
+            continue;
+        }
+        let occurrences = content
+            .scan
+            .occurrences
+            .entry(found.path.clone())
+            .or_default();
+        if occurrences.len() >= budget.per_file {
+            content.scan.truncated = true;
+            continue;
+        }
+
+        let first_line = found.first_line();
+        let long_line = max_columns > 0 && first_line.len() > max_columns;
+        let snippet = if long_line {
+            LONG_LINE_NOTE.to_string()
+        } else {
+            sanitize_control(String::from_utf8_lossy(first_line).trim())
+        };
+        occurrences.push(Occurrence {
+            line: found.line_number,
+            column: found.column(),
+            snippet,
+            long_line,
+            context: Vec::new(),
         });
+        total += 1;
     }
 
-    for occurrences in map.values_mut() {
+    for occurrences in content.scan.occurrences.values_mut() {
         occurrences.sort_by_key(|occurrence| (occurrence.line, occurrence.column));
     }
 
-    Ok(map)
+    Ok(content)
+}
+
+/// `read_occurrences` for `git::grep_revision` output: every matching file is
+/// listed, and occurrences are kept within the budget.
+#[cfg(feature = "git")]
+pub fn read_git_grep_occurrences(
+    mut reader: impl BufRead,
+    rev: &str,
+    budget: &OccurrenceBudget,
+    max_columns: usize,
+    icons: bool,
+) -> Result<ContentScan> {
+    let marker = if icons { "…" } else { "..." };
+    let mut content = ContentScan::default();
+    let mut total = 0;
+    let mut line = Vec::new();
+
+    while read_capped_line(&mut reader, &mut line, budget.line_bytes)
+        .context("failed to read git grep output")?
+    {
+        let Some((path, occurrence)) = parse_grep_line(&line, rev, max_columns, marker) else {
+            continue;
+        };
+        content.files.insert(path.clone());
+        let occurrences = content.scan.occurrences.entry(path).or_default();
+        if total >= budget.total || occurrences.len() >= budget.per_file {
+            content.scan.truncated = true;
+            continue;
+        }
+        occurrences.push(occurrence);
+        total += 1;
+    }
+    content
+        .scan
+        .occurrences
+        .retain(|_, occurrences| !occurrences.is_empty());
+    Ok(content)
+}
+
+/// Reads one line into `line` (without the newline), keeping at most `cap` bytes.
+/// Returns false at end of input.
+fn read_capped_line(reader: &mut impl BufRead, line: &mut Vec<u8>, cap: usize) -> io::Result<bool> {
+    line.clear();
+    let mut read_any = false;
+
+    loop {
+        let available = match reader.fill_buf() {
+            Ok(available) => available,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        };
+        if available.is_empty() {
+            return Ok(read_any);
+        }
+        read_any = true;
+
+        let newline = available.iter().position(|byte| *byte == b'\n');
+        let chunk = &available[..newline.unwrap_or(available.len())];
+        let room = cap.saturating_sub(line.len());
+        line.extend_from_slice(&chunk[..chunk.len().min(room)]);
+
+        match newline {
+            Some(index) => {
+                reader.consume(index + 1);
+                return Ok(true);
+            }
+            None => {
+                let consumed = available.len();
+                reader.consume(consumed);
+            }
+        }
+    }
+}
+
+/// Whether rg replaced or cut a line for `--max-columns`: `[Omitted long
+/// matching line]`, `[Omitted long line with 3 matches]`, or a preview ending
+/// in `[... omitted end of long line]` / `[... 2 more matches]`.
+fn is_rg_long_line(snippet: &str) -> bool {
+    if snippet == "[Omitted long matching line]"
+        || (snippet.starts_with("[Omitted long line with ") && snippet.ends_with(" matches]"))
+        || snippet.ends_with(" [... omitted end of long line]")
+    {
+        return true;
+    }
+    let Some(rest) = snippet
+        .strip_suffix(" more matches]")
+        .or_else(|| snippet.strip_suffix(" more match]"))
+    else {
+        return false;
+    };
+    rest.rsplit_once(" [... ").is_some_and(|(_, count)| {
+        !count.is_empty() && count.bytes().all(|byte| byte.is_ascii_digit())
+    })
+}
+
+/// Splits `path:line:column:snippet`, as rg prints it without `--null`, at the
+/// first `:line:column:` so that colons in the path (a drive letter,
+/// `a:b.txt`) stay part of it.
+fn split_occurrence_path(raw_line: &str) -> Option<(&str, &str)> {
+    let is_number =
+        |field: &str| !field.is_empty() && field.bytes().all(|byte| byte.is_ascii_digit());
+    raw_line.match_indices(':').find_map(|(index, _)| {
+        let fields = &raw_line[index + 1..];
+        let mut parts = fields.splitn(3, ':');
+        let numbered =
+            is_number(parts.next()?) && is_number(parts.next()?) && parts.next().is_some();
+        (index > 0 && numbered).then(|| (&raw_line[..index], fields))
+    })
+}
+
+fn parse_occurrence_line(raw_line: &[u8]) -> Option<(PathBuf, Occurrence)> {
+    // With `--null` the path ends at a NUL and keeps its exact bytes.
+    let (path, fields) = match raw_line.iter().position(|byte| *byte == 0) {
+        Some(nul) => (
+            path_from_bytes(raw_line[..nul].to_vec()),
+            String::from_utf8_lossy(&raw_line[nul + 1..]),
+        ),
+        None => {
+            let text = String::from_utf8_lossy(raw_line);
+            let (raw_path, fields) = split_occurrence_path(&text)?;
+            (PathBuf::from(raw_path), Cow::Owned(fields.to_string()))
+        }
+    };
+    let mut parts = fields.splitn(3, ':');
+    let line_num = parts.next()?.parse::<usize>().ok()?;
+    let column = parts.next()?.parse::<usize>().ok()?;
+    let raw_snippet = parts.next().unwrap_or_default();
+    let long_line = is_rg_long_line(raw_snippet);
+    let snippet = if long_line {
+        LONG_LINE_NOTE.to_string()
+    } else {
+        sanitize_control(raw_snippet.trim())
+    };
+
+    Some((
+        path.strip_prefix("./").map(PathBuf::from).unwrap_or(path),
+        Occurrence {
+            line: line_num,
+            column,
+            snippet,
+            long_line,
+            context: Vec::new(),
+        },
+    ))
 }
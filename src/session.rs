@@ -0,0 +1,1009 @@
+use crate::config::{ConfigKind, ConfigSnapshot, ConfigSource, SnapshotFile};
+use crate::lang::FileTypeSet;
+use crate::pills::FilterPill;
+use crate::search::{
+    parse_search_scope_setting, parse_sort_mode_setting, SearchScope, SettingOverrides, SortMode,
+};
+use anyhow::{Context, Result};
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Internal commands find their session's state file through this variable.
+pub const SESSION_ENV: &str = "YOINK_SESSION";
+pub const STALE_SESSION_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Per-session overrides layered over the loaded settings.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionState {
+    pub include_hidden: Option<bool>,
+    pub include_symlinks: Option<bool>,
+    pub include_mounts: Option<bool>,
+    pub sort_mode: Option<SortMode>,
+    pub max_depth: Option<usize>,
+    pub modified_within: Option<Duration>,
+    pub smart_defaults: Option<bool>,
+    pub accessibility: Option<bool>,
+    pub search_scope: Option<SearchScope>,
+    pub word_boundaries: Option<bool>,
+    pub match_basename_only: Option<bool>,
+    pub multiline: Option<bool>,
+    pub file_types: Option<FileTypeSet>,
+    pub file_types_not: Option<FileTypeSet>,
+    pub watch: Option<bool>,
+    pub tracked_only: Option<bool>,
+    pub root: Option<PathBuf>,
+    /// Several `--root`s, as paths under `root`; empty when searching `root` itself.
+    pub roots: Vec<PathBuf>,
+    /// `--changed`'s git ref: only files changed since it are listed.
+    pub changed_ref: Option<String>,
+    /// `--rev`'s commit: its tree is searched instead of the work tree.
+    pub rev: Option<String>,
+    /// 1-based list positions of the numbered file rows from the latest reload.
+    pub file_rows: Vec<usize>,
+    /// Chosen once at startup for dumb terminals; see `term::ColorMode::Plain`.
+    pub plain: bool,
+    /// Set once a highlight call blew `search::HIGHLIGHT_TIME_LIMIT`; matches stay uncolored.
+    pub highlight_off: bool,
+    /// Which match the preview is centered on, for the selection it was stepped on.
+    pub occurrence: Option<OccurrenceCursor>,
+    /// Config files as of launch or the last `alt-i`; see `search::load_session_settings`.
+    pub config: Option<ConfigSnapshot>,
+    /// Narrowing added with `alt-d` / `alt-x`, oldest first.
+    pub pills: Vec<FilterPill>,
+    /// Listed paths with the other paths to the same file, from the latest reload.
+    pub aliases: Vec<(PathBuf, Vec<PathBuf>)>,
+    /// Set by `--fresh-settings`: every reload reads the config files from disk,
+    /// bypassing both `config` and the settings cache.
+    pub fresh_settings: bool,
+}
+
+/// The preview's position among a file's matches. `path` and `line` record the
+/// row it belongs to, so moving to another row starts over at that row's match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OccurrenceCursor {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    pub index: usize,
+}
+
+impl OccurrenceCursor {
+    pub fn is_for(&self, path: &Path, line: Option<usize>) -> bool {
+        self.path == path && self.line == line
+    }
+}
+
+impl SessionState {
+    pub fn overrides(&self) -> SettingOverrides {
+        SettingOverrides {
+            include_hidden: self.include_hidden,
+            include_symlinks: self.include_symlinks,
+            include_mounts: self.include_mounts,
+            sort_mode: self.sort_mode,
+            max_depth: self.max_depth,
+            modified_within: self.modified_within,
+            smart_defaults: self.smart_defaults,
+            accessibility: self.accessibility,
+            search_scope: self.search_scope,
+            word_boundaries: self.word_boundaries,
+            match_basename_only: self.match_basename_only,
+            multiline: self.multiline,
+            file_types: self.file_types,
+            file_types_not: self.file_types_not,
+            watch: self.watch,
+            tracked_only: self.tracked_only,
+        }
+    }
+
+    pub fn set_overrides(&mut self, overrides: SettingOverrides) {
+        self.include_hidden = overrides.include_hidden;
+        self.include_symlinks = overrides.include_symlinks;
+        self.include_mounts = overrides.include_mounts;
+        self.sort_mode = overrides.sort_mode;
+        self.max_depth = overrides.max_depth;
+        self.modified_within = overrides.modified_within;
+        self.smart_defaults = overrides.smart_defaults;
+        self.accessibility = overrides.accessibility;
+        self.search_scope = overrides.search_scope;
+        self.word_boundaries = overrides.word_boundaries;
+        self.match_basename_only = overrides.match_basename_only;
+        self.multiline = overrides.multiline;
+        self.file_types = overrides.file_types;
+        self.file_types_not = overrides.file_types_not;
+        self.watch = overrides.watch;
+        self.tracked_only = overrides.tracked_only;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    dir: PathBuf,
+    id: String,
+}
+
+/// `$XDG_RUNTIME_DIR/yoink`, or a `yoink` directory under the system temp dir.
+pub fn session_dir() -> PathBuf {
+    match env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir).join("yoink"),
+        _ => env::temp_dir().join("yoink"),
+    }
+}
+
+pub fn new_session_id() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    format!("{}-{nanos:x}", process::id())
+}
+
+impl Session {
+    pub fn new(dir: PathBuf, id: String) -> Self {
+        Session { dir, id }
+    }
+
+    /// Creates a fresh session under `session_dir()`, clearing out stale ones first.
+    pub fn start() -> Result<Self> {
+        let dir = session_dir();
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create session directory {}", dir.display()))?;
+        if let Err(error) = collect_stale_sessions(&dir, STALE_SESSION_AGE, SystemTime::now()) {
+            eprintln!("yoink: failed to clean up old sessions: {error}");
+        }
+        Ok(Session::new(dir, new_session_id()))
+    }
+
+    pub fn from_env() -> Option<Self> {
+        let id = env::var(SESSION_ENV)
+            .ok()
+            .filter(|id| valid_session_id(id))?;
+        Some(Session::new(session_dir(), id))
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.dir.join(format!("{}.json", self.id))
+    }
+
+    /// Missing or unreadable state falls back to defaults.
+    pub fn load(&self) -> SessionState {
+        fs::read_to_string(self.path())
+            .ok()
+            .and_then(|content| decode_state(&content))
+            .unwrap_or_default()
+    }
+
+    /// Writes to a temp file in the same directory and renames it into place,
+    /// so readers never observe a half-written state file.
+    pub fn store(&self, state: &SessionState) -> Result<()> {
+        let _lock = self.lock()?;
+        self.write_state(state)
+    }
+
+    /// The state file is replaced on every write, so writers serialize on an
+    /// exclusive lock of a sidecar file that stays put. Released on drop.
+    fn lock(&self) -> Result<File> {
+        fs::create_dir_all(&self.dir).with_context(|| {
+            format!("failed to create session directory {}", self.dir.display())
+        })?;
+        let path = self.lock_path();
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        file.lock()
+            .with_context(|| format!("failed to lock {}", path.display()))?;
+        Ok(file)
+    }
+
+    fn lock_path(&self) -> PathBuf {
+        self.dir.join(format!("{}.lock", self.id))
+    }
+
+    fn write_state(&self, state: &SessionState) -> Result<()> {
+        let mut temp = tempfile::Builder::new()
+            .prefix(&format!(".{}-", self.id))
+            .suffix(".tmp")
+            .tempfile_in(&self.dir)
+            .context("failed to create session temp file")?;
+        temp.write_all(encode_state(state).as_bytes())
+            .context("failed to write session state")?;
+        temp.persist(self.path())
+            .with_context(|| format!("failed to replace {}", self.path().display()))?;
+        Ok(())
+    }
+
+    /// The parsed settings `search::encode_settings` cached for this session.
+    /// Named like the state file so `collect_stale_sessions` sweeps it too.
+    pub fn settings_path(&self) -> PathBuf {
+        self.dir.join(format!("{}.settings.json", self.id))
+    }
+
+    pub fn settings_cache(&self) -> Option<String> {
+        fs::read_to_string(self.settings_path()).ok()
+    }
+
+    /// Replaced atomically, like the state file.
+    pub fn store_settings_cache(&self, content: &str) -> Result<()> {
+        fs::create_dir_all(&self.dir).with_context(|| {
+            format!("failed to create session directory {}", self.dir.display())
+        })?;
+
+        let mut temp = tempfile::Builder::new()
+            .prefix(&format!(".{}-settings-", self.id))
+            .suffix(".tmp")
+            .tempfile_in(&self.dir)
+            .context("failed to create settings cache temp file")?;
+        temp.write_all(content.as_bytes())
+            .context("failed to write settings cache")?;
+        temp.persist(self.settings_path())
+            .with_context(|| format!("failed to replace {}", self.settings_path().display()))?;
+        Ok(())
+    }
+
+    pub fn remove(&self) {
+        let _ = fs::remove_file(self.path());
+        let _ = fs::remove_file(self.settings_path());
+        let _ = fs::remove_file(self.lock_path());
+    }
+
+    /// Loads, changes and stores the state under the lock, so concurrent
+    /// internal commands (a reload and an `alt-j`, say) don't drop each
+    /// other's changes.
+    fn update(&self, change: impl FnOnce(&mut SessionState)) -> Result<()> {
+        let _lock = self.lock()?;
+        let mut state = self.load();
+        change(&mut state);
+        self.write_state(&state)
+    }
+
+    pub fn include_hidden(&self) -> Option<bool> {
+        self.load().include_hidden
+    }
+
+    pub fn set_include_hidden(&self, value: Option<bool>) -> Result<()> {
+        self.update(|state| state.include_hidden = value)
+    }
+
+    pub fn sort_mode(&self) -> Option<SortMode> {
+        self.load().sort_mode
+    }
+
+    pub fn set_sort_mode(&self, value: Option<SortMode>) -> Result<()> {
+        self.update(|state| state.sort_mode = value)
+    }
+
+    pub fn root(&self) -> Option<PathBuf> {
+        self.load().root
+    }
+
+    pub fn file_rows(&self) -> Vec<usize> {
+        self.load().file_rows
+    }
+
+    pub fn set_file_rows(&self, rows: Vec<usize>) -> Result<()> {
+        self.update(|state| state.file_rows = rows)
+    }
+
+    pub fn set_highlight_off(&self, off: bool) -> Result<()> {
+        self.update(|state| state.highlight_off = off)
+    }
+
+    pub fn occurrence(&self) -> Option<OccurrenceCursor> {
+        self.load().occurrence
+    }
+
+    pub fn set_occurrence(&self, cursor: Option<OccurrenceCursor>) -> Result<()> {
+        self.update(|state| state.occurrence = cursor)
+    }
+
+    pub fn config(&self) -> Option<ConfigSnapshot> {
+        self.load().config
+    }
+
+    pub fn set_config(&self, snapshot: Option<ConfigSnapshot>) -> Result<()> {
+        self.update(|state| state.config = snapshot)
+    }
+
+    pub fn pills(&self) -> Vec<FilterPill> {
+        self.load().pills
+    }
+
+    /// Adds a pill unless the same one is already active.
+    pub fn push_pill(&self, pill: FilterPill) -> Result<()> {
+        self.update(|state| {
+            if !state.pills.contains(&pill) {
+                state.pills.push(pill);
+            }
+        })
+    }
+
+    pub fn pop_pill(&self) -> Result<Option<FilterPill>> {
+        let mut popped = None;
+        self.update(|state| popped = state.pills.pop())?;
+        Ok(popped)
+    }
+
+    /// The other paths to `path`, when the latest reload folded any into it.
+    pub fn aliases_of(&self, path: &Path) -> Vec<PathBuf> {
+        self.load()
+            .aliases
+            .into_iter()
+            .find(|(kept, _)| kept == path)
+            .map(|(_, aliases)| aliases)
+            .unwrap_or_default()
+    }
+
+    pub fn set_aliases(&self, aliases: Vec<(PathBuf, Vec<PathBuf>)>) -> Result<()> {
+        self.update(|state| state.aliases = aliases)
+    }
+}
+
+fn valid_session_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
+}
+
+/// Removes state files (and leftover temp and lock files) not modified within `max_age`.
+/// Locking never touches a lock file, so it goes only once its session's state file has.
+pub fn collect_stale_sessions(dir: &Path, max_age: Duration, now: SystemTime) -> Result<usize> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(error) => {
+            return Err(error)
+                .with_context(|| format!("failed to read session directory {}", dir.display()))
+        }
+    };
+
+    let mut removed = 0;
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if ![".json", ".tmp", ".lock"]
+            .iter()
+            .any(|suffix| name.ends_with(suffix))
+        {
+            continue;
+        }
+
+        let stale = |path: &Path| {
+            fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .is_ok_and(|modified| now.duration_since(modified).unwrap_or_default() > max_age)
+        };
+        if let Some(id) = name.strip_suffix(".lock") {
+            let state = dir.join(format!("{id}.json"));
+            if state.exists() && !stale(&state) {
+                continue;
+            }
+        }
+        if stale(&path) && fs::remove_file(&path).is_ok() {
+            removed += 1;
+        }
+    }
+
+    Ok(removed)
+}
+
+pub fn encode_state(state: &SessionState) -> String {
+    let flag = |value: Option<bool>| optional_json(value, |value| value.to_string());
+    let path = |path: &PathBuf| json_string(&path.to_string_lossy());
+    let fields = [
+        ("include_hidden", flag(state.include_hidden)),
+        ("include_symlinks", flag(state.include_symlinks)),
+        ("include_mounts", flag(state.include_mounts)),
+        (
+            "sort_mode",
+            optional_json(state.sort_mode, |mode| json_string(mode.as_str())),
+        ),
+        (
+            "max_depth",
+            optional_json(state.max_depth, |depth| depth.to_string()),
+        ),
+        (
+            "modified_within",
+            optional_json(state.modified_within, |window| window.as_secs().to_string()),
+        ),
+        ("smart_defaults", flag(state.smart_defaults)),
+        ("accessibility", flag(state.accessibility)),
+        (
+            "search_scope",
+            optional_json(state.search_scope, |scope| json_string(scope.as_str())),
+        ),
+        ("word_boundaries", flag(state.word_boundaries)),
+        ("match_basename_only", flag(state.match_basename_only)),
+        ("multiline", flag(state.multiline)),
+        ("file_types", encode_file_types(state.file_types)),
+        ("file_types_not", encode_file_types(state.file_types_not)),
+        ("watch", flag(state.watch)),
+        ("tracked_only", flag(state.tracked_only)),
+        ("root", optional_json(state.root.as_ref(), path)),
+        ("roots", json_array(state.roots.iter().map(path))),
+        (
+            "changed_ref",
+            optional_json(state.changed_ref.as_deref(), json_string),
+        ),
+        ("rev", optional_json(state.rev.as_deref(), json_string)),
+        (
+            "file_rows",
+            json_array(state.file_rows.iter().map(usize::to_string)),
+        ),
+        ("plain", state.plain.to_string()),
+        ("highlight_off", state.highlight_off.to_string()),
+        (
+            "occurrence",
+            optional_json(state.occurrence.as_ref(), |cursor| {
+                format!(
+                    "[{},{},{}]",
+                    path(&cursor.path),
+                    optional_json(cursor.line, |line| line.to_string()),
+                    cursor.index
+                )
+            }),
+        ),
+        (
+            "config",
+            optional_json(state.config.as_ref(), |snapshot| {
+                json_array(snapshot.files.iter().map(encode_snapshot_file))
+            }),
+        ),
+        (
+            "pills",
+            json_array(state.pills.iter().map(|pill| {
+                format!(
+                    "[{},{}]",
+                    json_string(pill.kind()),
+                    json_string(&pill.value())
+                )
+            })),
+        ),
+        (
+            "aliases",
+            json_array(state.aliases.iter().map(|(kept, aliases)| {
+                format!("[{},{}]", path(kept), json_array(aliases.iter().map(path)))
+            })),
+        ),
+        ("fresh_settings", state.fresh_settings.to_string()),
+    ];
+
+    let members: Vec<String> = fields
+        .into_iter()
+        .map(|(key, value)| format!("{}:{value}", json_string(key)))
+        .collect();
+    format!("{{{}}}\n", members.join(","))
+}
+
+/// Parses the subset of JSON `encode_state` writes; anything else yields `None`.
+pub fn decode_state(content: &str) -> Option<SessionState> {
+    let mut state = SessionState::default();
+    decode_json_object(content, |key, value| {
+        match (key, value) {
+            ("include_hidden", JsonValue::Bool(value)) => state.include_hidden = Some(value),
+            ("include_hidden", JsonValue::Null) => state.include_hidden = None,
+            ("include_symlinks", JsonValue::Bool(value)) => state.include_symlinks = Some(value),
+            ("include_symlinks", JsonValue::Null) => state.include_symlinks = None,
+            ("include_mounts", JsonValue::Bool(value)) => state.include_mounts = Some(value),
+            ("include_mounts", JsonValue::Null) => state.include_mounts = None,
+            ("sort_mode", JsonValue::String(mode)) => {
+                state.sort_mode = Some(parse_sort_mode_setting(&mode)?);
+            }
+            ("sort_mode", JsonValue::Null) => state.sort_mode = None,
+            ("max_depth", JsonValue::Number(depth)) => {
+                state.max_depth = Some(usize::try_from(depth).ok()?);
+            }
+            ("max_depth", JsonValue::Null) => state.max_depth = None,
+            ("modified_within", JsonValue::Number(secs)) => {
+                state.modified_within = Some(Duration::from_secs(secs));
+            }
+            ("modified_within", JsonValue::Null) => state.modified_within = None,
+            ("smart_defaults", JsonValue::Bool(value)) => state.smart_defaults = Some(value),
+            ("smart_defaults", JsonValue::Null) => state.smart_defaults = None,
+            ("accessibility", JsonValue::Bool(value)) => state.accessibility = Some(value),
+            ("accessibility", JsonValue::Null) => state.accessibility = None,
+            ("watch", JsonValue::Bool(value)) => state.watch = Some(value),
+            ("watch", JsonValue::Null) => state.watch = None,
+            ("tracked_only", JsonValue::Bool(value)) => state.tracked_only = Some(value),
+            ("tracked_only", JsonValue::Null) => state.tracked_only = None,
+            ("search_scope", JsonValue::String(scope)) => {
+                state.search_scope = Some(parse_search_scope_setting(&scope)?);
+            }
+            ("search_scope", JsonValue::Null) => state.search_scope = None,
+            ("word_boundaries", JsonValue::Bool(value)) => state.word_boundaries = Some(value),
+            ("word_boundaries", JsonValue::Null) => state.word_boundaries = None,
+            ("match_basename_only", JsonValue::Bool(value)) => {
+                state.match_basename_only = Some(value)
+            }
+            ("match_basename_only", JsonValue::Null) => state.match_basename_only = None,
+            ("multiline", JsonValue::Bool(value)) => state.multiline = Some(value),
+            ("multiline", JsonValue::Null) => state.multiline = None,
+            ("file_types", value) => state.file_types = decode_file_types(value)?,
+            ("file_types_not", value) => state.file_types_not = decode_file_types(value)?,
+            ("root", JsonValue::String(root)) => state.root = Some(PathBuf::from(root)),
+            ("root", JsonValue::Null) => state.root = None,
+            ("changed_ref", JsonValue::String(rev)) => state.changed_ref = Some(rev),
+            ("changed_ref", JsonValue::Null) => state.changed_ref = None,
+            ("rev", JsonValue::String(rev)) => state.rev = Some(rev),
+            ("rev", JsonValue::Null) => state.rev = None,
+            ("roots", JsonValue::Array(items)) => {
+                state.roots = items
+                    .into_iter()
+                    .map(|item| match item {
+                        JsonValue::String(path) => Some(PathBuf::from(path)),
+                        _ => None,
+                    })
+                    .collect::<Option<_>>()?;
+            }
+            ("file_rows", JsonValue::Array(items)) => {
+                state.file_rows = items
+                    .into_iter()
+                    .map(|item| match item {
+                        JsonValue::Number(row) => usize::try_from(row).ok(),
+                        _ => None,
+                    })
+                    .collect::<Option<_>>()?;
+            }
+            ("plain", JsonValue::Bool(plain)) => state.plain = plain,
+            ("highlight_off", JsonValue::Bool(off)) => state.highlight_off = off,
+            ("fresh_settings", JsonValue::Bool(fresh)) => state.fresh_settings = fresh,
+            ("occurrence", JsonValue::Array(items)) => {
+                state.occurrence = Some(decode_occurrence(items)?);
+            }
+            ("occurrence", JsonValue::Null) => state.occurrence = None,
+            ("config", JsonValue::Array(items)) => {
+                let files = items
+                    .into_iter()
+                    .map(|item| match item {
+                        JsonValue::Array(fields) => decode_snapshot_file(fields),
+                        _ => None,
+                    })
+                    .collect::<Option<_>>()?;
+                state.config = Some(ConfigSnapshot { files });
+            }
+            ("config", JsonValue::Null) => state.config = None,
+            ("pills", JsonValue::Array(items)) => {
+                state.pills = items
+                    .into_iter()
+                    .map(|item| match item {
+                        JsonValue::Array(parts) => decode_pill(parts),
+                        _ => None,
+                    })
+                    .collect::<Option<_>>()?;
+            }
+            ("aliases", JsonValue::Array(items)) => {
+                state.aliases = items
+                    .into_iter()
+                    .map(|item| match item {
+                        JsonValue::Array(parts) => decode_alias_entry(parts),
+                        _ => None,
+                    })
+                    .collect::<Option<_>>()?;
+            }
+            (
+                "include_hidden"
+                | "include_symlinks"
+                | "include_mounts"
+                | "sort_mode"
+                | "max_depth"
+                | "modified_within"
+                | "smart_defaults"
+                | "accessibility"
+                | "search_scope"
+                | "word_boundaries"
+                | "match_basename_only"
+                | "multiline"
+                | "watch"
+                | "tracked_only"
+                | "root"
+                | "roots"
+                | "changed_ref"
+                | "rev"
+                | "file_rows"
+                | "plain"
+                | "highlight_off"
+                | "occurrence"
+                | "config"
+                | "pills"
+                | "aliases"
+                | "fresh_settings",
+                _,
+            ) => return None,
+            // Unknown keys from newer builds are ignored.
+            _ => {}
+        }
+        Some(())
+    })?;
+    Some(state)
+}
+
+/// Parses a flat JSON object, handing each field to `field` in order; `None`
+/// when the text isn't one or `field` rejects a value.
+pub(crate) fn decode_json_object(
+    content: &str,
+    mut field: impl FnMut(&str, JsonValue) -> Option<()>,
+) -> Option<()> {
+    let mut parser = JsonParser {
+        chars: content.trim().chars().collect(),
+        pos: 0,
+    };
+
+    parser.expect('{')?;
+    if !parser.eat('}') {
+        loop {
+            let key = parser.string()?;
+            parser.expect(':')?;
+            let value = parser.value()?;
+            field(&key, value)?;
+
+            if parser.eat('}') {
+                break;
+            }
+            parser.expect(',')?;
+        }
+    }
+
+    parser.at_end().then_some(())
+}
+
+/// `[path, line or null, index]`, as `encode_state` writes a cursor.
+fn decode_occurrence(items: Vec<JsonValue>) -> Option<OccurrenceCursor> {
+    let mut items = items.into_iter();
+    let (Some(JsonValue::String(path)), Some(line), Some(JsonValue::Number(index)), None) =
+        (items.next(), items.next(), items.next(), items.next())
+    else {
+        return None;
+    };
+    let line = match line {
+        JsonValue::Number(line) => Some(usize::try_from(line).ok()?),
+        JsonValue::Null => None,
+        _ => return None,
+    };
+    Some(OccurrenceCursor {
+        path: PathBuf::from(path),
+        line,
+        index: usize::try_from(index).ok()?,
+    })
+}
+
+/// `[kind, value]`, as `encode_state` writes a pill.
+fn decode_pill(parts: Vec<JsonValue>) -> Option<FilterPill> {
+    let mut parts = parts.into_iter();
+    let (Some(JsonValue::String(kind)), Some(JsonValue::String(value)), None) =
+        (parts.next(), parts.next(), parts.next())
+    else {
+        return None;
+    };
+    FilterPill::from_parts(&kind, &value)
+}
+
+/// `[kept, [alias, ...]]`, as `encode_state` writes an alias entry.
+fn decode_alias_entry(parts: Vec<JsonValue>) -> Option<(PathBuf, Vec<PathBuf>)> {
+    let mut parts = parts.into_iter();
+    let (Some(JsonValue::String(kept)), Some(JsonValue::Array(aliases)), None) =
+        (parts.next(), parts.next(), parts.next())
+    else {
+        return None;
+    };
+    let aliases = aliases
+        .into_iter()
+        .map(|alias| match alias {
+            JsonValue::String(alias) => Some(PathBuf::from(alias)),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    Some((PathBuf::from(kept), aliases))
+}
+
+/// Type names as an array, or `null` when the layer leaves types alone.
+fn encode_file_types(types: Option<FileTypeSet>) -> String {
+    optional_json(types, |types| {
+        let names: Vec<String> = types.names().into_iter().map(json_string).collect();
+        format!("[{}]", names.join(","))
+    })
+}
+
+/// `Some(None)` for `null`; `None` when the value isn't a list of known type names.
+fn decode_file_types(value: JsonValue) -> Option<Option<FileTypeSet>> {
+    let JsonValue::Array(items) = value else {
+        return matches!(value, JsonValue::Null).then_some(None);
+    };
+    let names = items
+        .into_iter()
+        .map(|item| match item {
+            JsonValue::String(name) => Some(name),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+    FileTypeSet::parse(&names.join(",")).ok().map(Some)
+}
+
+fn optional_json<T>(value: Option<T>, encode: impl FnOnce(T) -> String) -> String {
+    value.map_or_else(|| "null".to_string(), encode)
+}
+
+/// `[a,b,...]` from already encoded items.
+fn json_array(items: impl Iterator<Item = String>) -> String {
+    format!("[{}]", items.collect::<Vec<_>>().join(","))
+}
+
+/// `[path, "settings" or "ignore", mtime or null, content or null]`.
+fn encode_snapshot_file(file: &SnapshotFile) -> String {
+    let kind = match file.source.kind {
+        ConfigKind::Settings => "settings",
+        ConfigKind::Ignore => "ignore",
+    };
+    format!(
+        "[{},{},{},{}]",
+        json_string(&file.source.path.to_string_lossy()),
+        json_string(kind),
+        optional_json(file.modified, |modified| modified.to_string()),
+        optional_json(file.content.as_deref(), json_string)
+    )
+}
+
+fn decode_snapshot_file(fields: Vec<JsonValue>) -> Option<SnapshotFile> {
+    let mut fields = fields.into_iter();
+    let (
+        Some(JsonValue::String(path)),
+        Some(JsonValue::String(kind)),
+        Some(modified),
+        Some(content),
+        None,
+    ) = (
+        fields.next(),
+        fields.next(),
+        fields.next(),
+        fields.next(),
+        fields.next(),
+    )
+    else {
+        return None;
+    };
+    let source = match kind.as_str() {
+        "settings" => ConfigSource::settings(PathBuf::from(path)),
+        "ignore" => ConfigSource::ignore(PathBuf::from(path)),
+        _ => return None,
+    };
+    let modified = match modified {
+        JsonValue::Number(modified) => Some(modified),
+        JsonValue::Null => None,
+        _ => return None,
+    };
+    let content = match content {
+        JsonValue::String(content) => Some(content),
+        JsonValue::Null => None,
+        _ => return None,
+    };
+    Some(SnapshotFile {
+        source,
+        modified,
+        content,
+    })
+}
+
+pub(crate) fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+    out
+}
+
+pub(crate) enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(u64),
+    /// A negative, fractional or exponent number, which no field yoink reads holds.
+    OtherNumber,
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    pub(crate) fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(fields) => fields
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_usize(&self) -> Option<usize> {
+        match self {
+            JsonValue::Number(number) => usize::try_from(*number).ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Parses one whole JSON value; `None` when there's more after it.
+pub(crate) fn parse_json(content: &str) -> Option<JsonValue> {
+    let mut parser = JsonParser {
+        chars: content.chars().collect(),
+        pos: 0,
+    };
+    let value = parser.value()?;
+    parser.at_end().then_some(value)
+}
+
+struct JsonParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl JsonParser {
+    fn skip_whitespace(&mut self) {
+        while self
+            .chars
+            .get(self.pos)
+            .is_some_and(|ch| ch.is_whitespace())
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        self.skip_whitespace();
+        if self.chars.get(self.pos) == Some(&expected) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Option<()> {
+        self.eat(expected).then_some(())
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.skip_whitespace();
+        self.pos == self.chars.len()
+    }
+
+    fn keyword(&mut self, word: &str) -> bool {
+        self.skip_whitespace();
+        let end = self.pos + word.chars().count();
+        if end <= self.chars.len() && self.chars[self.pos..end].iter().copied().eq(word.chars()) {
+            self.pos = end;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn string(&mut self) -> Option<String> {
+        self.expect('"')?;
+        let mut out = String::new();
+        loop {
+            let ch = *self.chars.get(self.pos)?;
+            self.pos += 1;
+            match ch {
+                '"' => return Some(out),
+                '\\' => {
+                    let escaped = *self.chars.get(self.pos)?;
+                    self.pos += 1;
+                    match escaped {
+                        '"' | '\\' | '/' => out.push(escaped),
+                        'n' => out.push('\n'),
+                        't' => out.push('\t'),
+                        'r' => out.push('\r'),
+                        'b' => out.push('\u{8}'),
+                        'f' => out.push('\u{c}'),
+                        'u' => {
+                            let mut code = self.hex4()?;
+                            // A surrogate pair spells one code point past the BMP.
+                            if (0xD800..0xDC00).contains(&code)
+                                && self.chars.get(self.pos..self.pos + 2) == Some(&['\\', 'u'])
+                            {
+                                self.pos += 2;
+                                let low = self.hex4()?;
+                                code =
+                                    0x10000 + ((code - 0xD800) << 10) + low.checked_sub(0xDC00)?;
+                            }
+                            out.push(char::from_u32(code)?);
+                        }
+                        _ => return None,
+                    }
+                }
+                ch => out.push(ch),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Option<u32> {
+        let hex: String = self.chars.get(self.pos..self.pos + 4)?.iter().collect();
+        self.pos += 4;
+        u32::from_str_radix(&hex, 16).ok()
+    }
+
+    fn value(&mut self) -> Option<JsonValue> {
+        if self.keyword("null") {
+            return Some(JsonValue::Null);
+        }
+        if self.keyword("true") {
+            return Some(JsonValue::Bool(true));
+        }
+        if self.keyword("false") {
+            return Some(JsonValue::Bool(false));
+        }
+        self.skip_whitespace();
+        if self
+            .chars
+            .get(self.pos)
+            .is_some_and(|ch| ch.is_ascii_digit() || *ch == '-')
+        {
+            let start = self.pos;
+            while self
+                .chars
+                .get(self.pos)
+                .is_some_and(|ch| ch.is_ascii_digit() || "+-.eE".contains(*ch))
+            {
+                self.pos += 1;
+            }
+            let number: String = self.chars[start..self.pos].iter().collect();
+            return match number.parse() {
+                Ok(number) => Some(JsonValue::Number(number)),
+                Err(_) => number.parse::<f64>().ok().map(|_| JsonValue::OtherNumber),
+            };
+        }
+        if self.eat('{') {
+            let mut fields = Vec::new();
+            if !self.eat('}') {
+                loop {
+                    let key = self.string()?;
+                    self.expect(':')?;
+                    fields.push((key, self.value()?));
+                    if self.eat('}') {
+                        break;
+                    }
+                    self.expect(',')?;
+                }
+            }
+            return Some(JsonValue::Object(fields));
+        }
+        if self.eat('[') {
+            let mut items = Vec::new();
+            if !self.eat(']') {
+                loop {
+                    items.push(self.value()?);
+                    if self.eat(']') {
+                        break;
+                    }
+                    self.expect(',')?;
+                }
+            }
+            return Some(JsonValue::Array(items));
+        }
+        self.string().map(JsonValue::String)
+    }
+}
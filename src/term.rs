@@ -0,0 +1,173 @@
+use crate::session::Session;
+use std::env;
+use std::iter::Peekable;
+use std::process::Command;
+use std::str::Chars;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    Ansi,
+    /// No escape codes anywhere; matches are wrapped in `>>` / `<<` instead.
+    Plain,
+}
+
+/// Default SGR colors for matches in file names and in content snippets.
+pub const PATH_MATCH_COLOR: u8 = 35;
+pub const CONTENT_MATCH_COLOR: u8 = 36;
+
+const COLOR_NAMES: &[&str] = &[
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// A color name (`magenta`) or its SGR foreground code (`35`); see `COLOR_NAMES`.
+pub fn parse_match_color_setting(value: &str) -> Option<u8> {
+    let value = value.trim().to_ascii_lowercase();
+    if let Some(index) = COLOR_NAMES.iter().position(|name| *name == value) {
+        return Some(30 + index as u8);
+    }
+    value
+        .parse::<u8>()
+        .ok()
+        .filter(|code| (30..=37).contains(code) || (90..=97).contains(code))
+}
+
+impl ColorMode {
+    pub fn match_start(self) -> &'static str {
+        match self {
+            ColorMode::Ansi => "\x1b[1;36m",
+            ColorMode::Plain => ">>",
+        }
+    }
+
+    /// `match_start` in another color; plain mode keeps its `>>` marker.
+    pub fn match_start_in(self, color: u8) -> String {
+        match self {
+            ColorMode::Ansi => format!("\x1b[1;{color}m"),
+            ColorMode::Plain => ">>".to_string(),
+        }
+    }
+
+    pub fn match_end(self) -> &'static str {
+        match self {
+            ColorMode::Ansi => "\x1b[0m",
+            ColorMode::Plain => "<<",
+        }
+    }
+}
+
+/// Terminals known to lack color even when terminfo is unavailable to ask.
+const MONOCHROME_TERMS: &[&str] = &[
+    "dumb", "unknown", "vt52", "vt100", "vt102", "vt220", "cons25",
+];
+
+/// Picks plain output for `TERM=dumb` and friends, `NO_COLOR`, or terminfo
+/// reporting fewer than 8 colors. `terminfo_colors` is `tput colors`, when known.
+pub fn detect_color_mode(
+    term: Option<&str>,
+    no_color: bool,
+    terminfo_colors: Option<i32>,
+) -> ColorMode {
+    if no_color {
+        return ColorMode::Plain;
+    }
+
+    let term = term.map(str::trim).unwrap_or_default();
+    if term.is_empty() || MONOCHROME_TERMS.contains(&term) {
+        return ColorMode::Plain;
+    }
+
+    match terminfo_colors {
+        Some(colors) if colors < 8 => ColorMode::Plain,
+        _ => ColorMode::Ansi,
+    }
+}
+
+/// Inspects the launching terminal; run once when the session starts.
+pub fn terminal_color_mode() -> ColorMode {
+    let term = env::var("TERM").ok();
+    let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    let terminfo_colors = Command::new("tput")
+        .arg("colors")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .trim()
+                .parse::<i32>()
+                .ok()
+        });
+    detect_color_mode(term.as_deref(), no_color, terminfo_colors)
+}
+
+/// The mode chosen by the running session; internal commands never re-detect,
+/// since fzf's child processes don't see the user's terminal.
+pub fn session_color_mode() -> ColorMode {
+    match Session::from_env() {
+        Some(session) if session.load().plain => ColorMode::Plain,
+        _ => ColorMode::Ansi,
+    }
+}
+
+/// File text or a file name made safe to print: escape sequences are dropped,
+/// tabs become spaces and other control characters show as their control
+/// pictures (`␇` for BEL), so hostile bytes can't recolor or garble the list.
+pub fn sanitize_control(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\x1b' => skip_escape(&mut chars),
+            '\t' => out.push(' '),
+            '\x00'..='\x1f' => out.extend(char::from_u32(0x2400 + ch as u32)),
+            '\x7f' => out.push('\u{2421}'),
+            '\u{80}'..='\u{9f}' => out.push('\u{FFFD}'),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+/// Consumes the rest of an escape sequence whose `ESC` was just read.
+fn skip_escape(chars: &mut Peekable<Chars>) {
+    match chars.next() {
+        // CSI: parameters up to a final byte in `@`..=`~`.
+        Some('[') => {
+            for ch in chars.by_ref() {
+                if ('@'..='~').contains(&ch) {
+                    break;
+                }
+            }
+        }
+        // OSC and the other string sequences run to BEL or `ESC \`.
+        Some(']' | 'P' | 'X' | '^' | '_') => {
+            while let Some(ch) = chars.next() {
+                if ch == '\x07' {
+                    break;
+                }
+                if ch == '\x1b' && chars.peek() == Some(&'\\') {
+                    chars.next();
+                    break;
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\x1b' {
+            for escape_ch in chars.by_ref() {
+                if escape_ch.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+            continue;
+        }
+        out.push(ch);
+    }
+    out
+}
@@ -1,16 +1,412 @@
-use crate::actions::{open_in_editor, resolve_target_dir};
+use crate::actions::{
+    create_file_from_query, fence_language, fenced_block, open_in_editor, read_line_range,
+    resolve_target_dir,
+};
+use crate::archive::{extract_member, split_member};
+#[cfg(feature = "archives")]
+use crate::archive::{list_members, ArchiveKind};
+use crate::clipboard::copy_to_clipboard;
+use crate::config::{config_sources, ConfigSnapshot};
+use crate::git::extract_blob;
+#[cfg(feature = "git")]
+use crate::git::show_blob;
+use crate::history::{session_history_args, session_record_query};
+#[cfg(feature = "builtin-ui")]
+use crate::picker::run_picker;
+use crate::pills::{dir_pill_for, ext_pill_for};
+use crate::prefs::{load_root_prefs, prefs_path, save_root_prefs, session_prefs};
+use crate::query::{split_negations, QueryPattern, RegexFallback};
+use crate::recent::{record_selection, selections_path};
+#[cfg(feature = "watch")]
+use crate::refresh::{listen_failed, SessionRefresh, SessionWatch};
+use crate::search::{
+    buffer_line, decode_path_field, encode_settings, extract_text, highlight_query_matches,
+    is_binary_file, load_settings, load_settings_for, load_settings_from_snapshot, merge_layers,
+    resolve_settings, rg_config_args, root_gone_message, root_unavailable, SearchScope,
+    SettingOverrides, SortMode, YoinkSettings, BINARY_SNIFF_BYTES, NUMBERED_FILE_ROWS,
+    PREVIEW_WIDTH_PERCENT,
+};
+use crate::session::{OccurrenceCursor, Session, SessionState, SESSION_ENV};
+use crate::term::{session_color_mode, strip_ansi, terminal_color_mode, ColorMode};
 use anyhow::{Context, Result};
+use regex::Regex;
+use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Output, Stdio};
+#[cfg(feature = "watch")]
+use std::time::{Duration, Instant};
+use tempfile::NamedTempFile;
+#[cfg(feature = "builtin-ui")]
+use which::which;
 
-pub fn run_fzf_session(initial_query: Option<&str>, cwd: &Path, exe_path: &Path) -> Result<()> {
-    let exe = exe_path.to_string_lossy();
-    let preview = format!("{} __preview {{2}} {{q}} {{3}}", exe);
-    let reload = format!("{} __search {{q}}", exe);
+pub fn buffer_stdin() -> Result<NamedTempFile> {
+    let mut stdin = io::stdin();
+    if stdin.is_terminal() {
+        anyhow::bail!("--stdin-content expects text piped on stdin");
+    }
+
+    let mut buffer = tempfile::Builder::new()
+        .prefix("yoink-stdin-")
+        .suffix(".txt")
+        .tempfile()
+        .context("failed to create stdin buffer file")?;
+    io::copy(&mut stdin, buffer.as_file_mut()).context("failed to buffer stdin")?;
+    Ok(buffer)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionOutcome {
+    Done,
+    NoSelection,
+    /// The search root was deleted or unmounted while fzf was open.
+    RootGone,
+}
+
+/// Exit code when the session ends because its root disappeared.
+pub const ROOT_GONE_EXIT_CODE: i32 = 3;
+
+/// The process exit code for `outcome`; `None` exits normally.
+pub fn outcome_exit_code(outcome: SessionOutcome) -> Option<i32> {
+    match outcome {
+        SessionOutcome::Done => None,
+        SessionOutcome::NoSelection => Some(1),
+        SessionOutcome::RootGone => Some(ROOT_GONE_EXIT_CODE),
+    }
+}
+
+/// `RootGone` once `root` can't be listed, checked after fzf closes so the
+/// selection isn't resolved against a directory that isn't there.
+pub fn root_gone_outcome(root: &Path) -> Option<SessionOutcome> {
+    root_unavailable(root).then_some(SessionOutcome::RootGone)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionAction {
+    Select,
+    OpenEditor(&'static str),
+    CreateFromQuery,
+    CopyPath,
+    CopyQuery,
+    YankRange,
+    YankHint,
+    EditorHint(&'static str),
+    NoSelection,
+}
+
+/// `alt-1`…`alt-9`, each asking `__jump` for the `pos()` of that numbered file row;
+/// the rows change with every reload, so they're looked up when the key is pressed.
+pub fn jump_binds(exe: &str) -> Vec<String> {
+    (1..=NUMBERED_FILE_ROWS)
+        .map(|n| format!("alt-{n}:transform:{exe} __jump {n}"))
+        .collect()
+}
+
+pub fn resolve_session_action(key: &str, has_selection: bool) -> SessionAction {
+    let editor = match key {
+        "ctrl-v" => Some("vim"),
+        "ctrl-o" => Some("code"),
+        "ctrl-s" => Some("subl"),
+        _ => None,
+    };
+
+    match (key, editor, has_selection) {
+        ("ctrl-n", _, _) => SessionAction::CreateFromQuery,
+        ("ctrl-y", _, true) => SessionAction::CopyPath,
+        ("ctrl-y", _, false) => SessionAction::CopyQuery,
+        ("alt-y", _, true) => SessionAction::YankRange,
+        ("alt-y", _, false) => SessionAction::YankHint,
+        (_, Some(editor), true) => SessionAction::OpenEditor(editor),
+        (_, Some(editor), false) => SessionAction::EditorHint(editor),
+        (_, None, true) => SessionAction::Select,
+        (_, None, false) => SessionAction::NoSelection,
+    }
+}
+
+/// What a session lists: files and matches under the root, lines of a stdin
+/// buffer, or the root's empty files and directories.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionSource<'a> {
+    Files,
+    Buffer(&'a Path),
+    Empty,
+}
+
+/// A fresh `Command` with `command`'s program, arguments, environment and
+/// working directory, for launching it again.
+#[cfg(feature = "watch")]
+fn copy_command(command: &Command) -> Command {
+    let mut copy = Command::new(command.get_program());
+    copy.args(command.get_args());
+    for (key, value) in command.get_envs() {
+        match value {
+            Some(value) => copy.env(key, value),
+            None => copy.env_remove(key),
+        };
+    }
+    if let Some(dir) = command.get_current_dir() {
+        copy.current_dir(dir);
+    }
+    copy
+}
+
+/// Quotes a value for the shell fzf runs its commands in; plain words pass through.
+pub fn shell_quote(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || "_-./:=@%+,".contains(ch));
+    if plain {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+/// fzf quotes `{q}` and `{N}` itself; the `--` keeps a query or path that starts
+/// with a dash from being read as one of our own flags. `exe` is already quoted.
+pub fn preview_command(exe: &str) -> String {
+    format!("{exe} __preview -- {{2}} {{q}} {{3}} {{4}}")
+}
+
+pub fn reload_command(exe: &str, source: SessionSource) -> String {
+    match source {
+        SessionSource::Buffer(buffer) => format!(
+            "{exe} __search --buffer {} -- {{q}}",
+            shell_quote(&buffer.to_string_lossy())
+        ),
+        SessionSource::Empty => format!("{exe} __search --empty -- {{q}}"),
+        SessionSource::Files => format!("{exe} __search -- {{q}}"),
+    }
+}
+
+pub const FILES_HEADER: &str = concat!(
+    "Enter: cd to container  |  Ctrl-V: vim  |  Ctrl-O: code  |  Ctrl-S: subl  |  ",
+    "Ctrl-N: new file  |  Ctrl-Y: copy  |  Alt-Y: yank lines  |  Alt-J/K: next/prev match  |  ",
+    "Alt-I: edit ignores  |  Alt-D/X: only this dir/ext  |  Alt-H: hidden  |  Alt-S: sort  |  ",
+    "!term: exclude"
+);
+
+/// `FILES_HEADER`, led by the search scope when it isn't the default.
+pub fn files_header(scope: SearchScope) -> String {
+    match scope {
+        SearchScope::Both => FILES_HEADER.to_string(),
+        SearchScope::Paths => format!("[paths only]  |  {FILES_HEADER}"),
+        SearchScope::Content => format!("[content only]  |  {FILES_HEADER}"),
+    }
+}
+
+/// `base` led by the walk toggles, sort order and git narrowing that differ from
+/// the defaults, so a session started with `--hidden` or `--sort size` says so.
+pub fn settings_header(base: &str, settings: &YoinkSettings) -> String {
+    let mut marks = Vec::new();
+    if settings.include_hidden {
+        marks.push("[hidden]".to_string());
+    }
+    if settings.include_symlinks {
+        marks.push("[follow]".to_string());
+    }
+    if settings.include_mounts {
+        marks.push("[mounts]".to_string());
+    }
+    if settings.sort_mode != SortMode::Depth {
+        marks.push(format!("[sort: {}]", settings.sort_mode.as_str()));
+    }
+    if settings.tracked_only {
+        marks.push("[tracked]".to_string());
+    }
+    if let Some(rev) = &settings.changed_ref {
+        marks.push(format!("[changed: {rev}]"));
+    }
+    if let Some(rev) = &settings.rev {
+        marks.push(format!("[rev: {rev}]"));
+    }
+    if marks.is_empty() {
+        return base.to_string();
+    }
+    format!("{}  |  {base}", marks.join(" "))
+}
+
+/// What a files session is narrowed to beyond its settings: several `--root`s,
+/// `--changed`'s git ref, and `--rev`'s commit.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionScope {
+    pub roots: Vec<PathBuf>,
+    pub changed_ref: Option<String>,
+    pub rev: Option<String>,
+}
+
+/// The header with a line naming each root when a session searches several.
+pub fn roots_header(base: &str, roots: &[PathBuf]) -> String {
+    if roots.is_empty() {
+        return base.to_string();
+    }
+    let labels: Vec<String> = roots
+        .iter()
+        .map(|root| format!("{}/", root.display()))
+        .collect();
+    format!("Roots: {}\n{base}", labels.join("  "))
+}
+
+/// `alt-d`, `alt-x` and `alt-backspace`: change the session's pills, show them in
+/// the header, then reload with them applied.
+pub fn pill_binds(exe: &str, reload: &str) -> Vec<String> {
+    [("alt-d", " --dir -- {2}"), ("alt-x", " --ext -- {2}"), ("alt-bspace", " --pop")]
+        .into_iter()
+        .map(|(key, args)| {
+            format!(
+                "{key}:execute-silent({exe} __pill{args})+transform-header({exe} __header)+reload:{reload}"
+            )
+        })
+        .collect()
+}
+
+/// Applies one pill change to the current session; rows that give no
+/// directory or extension to narrow by leave the pills as they are.
+pub fn update_pills(cwd: &Path, rel: &Path, dir: bool, ext: bool, pop: bool) -> Result<()> {
+    let Some(session) = Session::from_env() else {
+        return Ok(());
+    };
+    if pop {
+        session.pop_pill()?;
+        return Ok(());
+    }
+    let pill = if dir {
+        dir_pill_for(rel, cwd.join(rel).is_dir())
+    } else if ext {
+        ext_pill_for(rel)
+    } else {
+        None
+    };
+    if let Some(pill) = pill {
+        session.push_pill(pill)?;
+    }
+    Ok(())
+}
+
+/// What `__toggle` changes for the rest of a session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionToggle {
+    Hidden,
+    Sort,
+}
+
+impl SessionToggle {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SessionToggle::Hidden => "hidden",
+            SessionToggle::Sort => "sort",
+        }
+    }
+}
+
+pub fn parse_session_toggle(value: &str) -> Option<SessionToggle> {
+    match value {
+        "hidden" => Some(SessionToggle::Hidden),
+        "sort" => Some(SessionToggle::Sort),
+        _ => None,
+    }
+}
+
+/// `alt-h` flips hidden paths and `alt-s` steps to the next sort order for the
+/// rest of the session; like the pill binds, the header and list follow. A
+/// streamed session isn't `sortable`.
+pub fn toggle_binds(exe: &str, reload: &str, sortable: bool) -> Vec<String> {
+    [
+        ("alt-h", SessionToggle::Hidden, true),
+        ("alt-s", SessionToggle::Sort, sortable),
+    ]
+    .into_iter()
+    .filter(|(_, _, bound)| *bound)
+    .map(|(key, toggle, _)| {
+        format!(
+            "{key}:execute-silent({exe} __toggle {})+transform-header({exe} __header)+reload:{reload}",
+            toggle.as_str()
+        )
+    })
+    .collect()
+}
+
+/// Applies one `alt-h` or `alt-s` to the current session's overrides.
+pub fn toggle_session(cwd: &Path, toggle: SessionToggle) -> Result<()> {
+    let Some(session) = Session::from_env() else {
+        return Ok(());
+    };
+    let settings = load_settings_for(cwd)?;
+    match toggle {
+        SessionToggle::Hidden => session.set_include_hidden(Some(!settings.include_hidden)),
+        SessionToggle::Sort => session.set_sort_mode(Some(settings.sort_mode.next())),
+    }
+}
+
+/// Leaves what `session`'s toggles changed since `launched` in `cwd`'s saved
+/// prefs in the file at `prefs_path`, on top of the `saved` ones it started from.
+pub fn save_session_prefs(
+    prefs_path: &Path,
+    cwd: &Path,
+    saved: SettingOverrides,
+    launched: SettingOverrides,
+    session: &Session,
+) -> Result<()> {
+    save_root_prefs(
+        prefs_path,
+        cwd,
+        session_prefs(saved, launched, session.load().overrides()),
+    )
+}
+
+pub fn run_fzf_session(
+    initial_query: Option<&str>,
+    cwd: &Path,
+    exe_path: &Path,
+    source: SessionSource,
+    cli_overrides: SettingOverrides,
+    fresh_settings: bool,
+    scope: &SessionScope,
+) -> Result<SessionOutcome> {
+    let exe = shell_quote(&exe_path.to_string_lossy());
+    let preview = preview_command(&exe);
+    let buffer = match source {
+        SessionSource::Buffer(buffer) => Some(buffer),
+        _ => None,
+    };
+    let reload = reload_command(&exe, source);
+
+    let snapshot = ConfigSnapshot::capture(&config_sources(Some(cwd)))?;
+    let config = load_settings_from_snapshot(&snapshot)?;
+    let persist_prefs = config.persist_session_prefs;
+    let saved_prefs = if persist_prefs {
+        load_root_prefs(cwd)
+    } else {
+        SettingOverrides::default()
+    };
+    let launch_overrides = merge_layers(&[saved_prefs, cli_overrides]);
+
+    let mut launch_settings = resolve_settings(config.clone(), &[launch_overrides]);
+    launch_settings.changed_ref = scope.changed_ref.clone();
+    launch_settings.rev = scope.rev.clone();
+    let header = match source {
+        SessionSource::Buffer(_) => "Enter: print line  |  Ctrl-V: vim  |  Ctrl-O: code  |  Ctrl-S: subl  |  Ctrl-Y: copy  |  Alt-Y: yank lines  |  Alt-J/K: next/prev match".to_string(),
+        SessionSource::Empty => "Ctrl-D: DELETE  |  Ctrl-T: TRASH  |  Enter: cd to container  |  Ctrl-V: vim  |  Ctrl-O: code  |  Ctrl-Y: copy  |  Alt-I: edit ignores".to_string(),
+        SessionSource::Files => roots_header(
+            &settings_header(&files_header(launch_settings.search_scope), &launch_settings),
+            &scope.roots,
+        ),
+    };
+
+    // Detected once here; `__search` and `__preview` read the choice from the session.
+    let color_mode = if launch_settings.no_color {
+        ColorMode::Plain
+    } else {
+        terminal_color_mode()
+    };
 
     let mut command = Command::new("fzf");
+    command.arg(match color_mode {
+        ColorMode::Ansi => "--ansi",
+        ColorMode::Plain => "--no-color",
+    });
     command
-        .arg("--ansi")
         .arg("--delimiter")
         .arg("\t")
         .arg("--with-nth")
@@ -18,98 +414,439 @@ pub fn run_fzf_session(initial_query: Option<&str>, cwd: &Path, exe_path: &Path)
         .arg("--layout=reverse")
         .arg("--height=100%")
         .arg("--header")
-        .arg("Enter: cd to container  |  Ctrl-V: vim  |  Ctrl-O: code  |  Ctrl-S: subl")
-        .arg("--preview-window=right:65%:wrap")
+        .arg(header)
+        .arg(format!(
+            "--preview-window=right:{PREVIEW_WIDTH_PERCENT}%:wrap"
+        ))
         .arg("--preview")
         .arg(preview)
         .arg("--disabled")
         .arg("--print-query")
-        .arg("--expect=enter,ctrl-v,ctrl-o,ctrl-s")
+        .arg("--expect=enter,ctrl-v,ctrl-o,ctrl-s,ctrl-n,ctrl-y,alt-y")
         .arg("--bind")
         .arg(format!("start:reload:{reload}"))
-        .arg("--bind")
-        .arg(format!("change:reload:{reload}"))
         .arg("--prompt")
         .arg("regex> ")
         .current_dir(cwd);
 
+    let history_size = if config.history {
+        config.history_size
+    } else {
+        0
+    };
+    command.args(session_history_args(history_size));
+
+    // Reload and preview commands inherit the id, keeping concurrent sessions apart.
+    let session = Session::start()?;
+    let mut state = SessionState::default();
+    state.set_overrides(launch_overrides);
+    // Reloads fall back to this when their cwd was deleted under them.
+    state.root = Some(cwd.to_path_buf());
+    state.roots = scope.roots.clone();
+    state.changed_ref = scope.changed_ref.clone();
+    state.rev = scope.rev.clone();
+    state.plain = color_mode == ColorMode::Plain;
+    state.fresh_settings = fresh_settings;
+    let fingerprint = snapshot.fingerprint();
+    state.config = Some(snapshot);
+    session.store(&state)?;
+    // Parsed once here; every `__search` and `__preview` reads it back.
+    if !fresh_settings {
+        session.store_settings_cache(&encode_settings(&config, fingerprint))?;
+    }
+    command.env(SESSION_ENV, session.id());
+
+    if source != SessionSource::Empty {
+        for (key, flag) in [("alt-j", ""), ("alt-k", " --prev")] {
+            command.arg("--bind").arg(format!(
+                "{key}:execute-silent({exe} __occurrence{flag} -- {{2}} {{q}} {{3}})+refresh-preview"
+            ));
+        }
+    }
+
+    if source == SessionSource::Files {
+        for bind in pill_binds(&exe, &reload) {
+            command.arg("--bind").arg(bind);
+        }
+        let sortable = launch_settings.sort_mode != SortMode::Stream;
+        for bind in toggle_binds(&exe, &reload, sortable) {
+            command.arg("--bind").arg(bind);
+        }
+    }
+
+    if buffer.is_none() {
+        // The editor gets the terminal; the reload then runs on the recaptured config.
+        command.arg("--bind").arg(format!(
+            "alt-i:execute({exe} __edit-config)+reload:{reload}"
+        ));
+    }
+
+    if source == SessionSource::Empty {
+        // Remove in place and relist, so several entries can be cleaned up in one session.
+        for (key, flag) in [("ctrl-d", ""), ("ctrl-t", " --trash")] {
+            command.arg("--bind").arg(format!(
+                "{key}:execute-silent({exe} __remove{flag} -- {{2}})+reload:{reload}"
+            ));
+        }
+    }
+
     if let Some(query) = initial_query {
         command.arg("--query").arg(query);
     }
 
-    let output = command
-        .output()
-        .context("failed to execute fzf for interactive selection")?;
+    let settings = match buffer {
+        Some(_) => None,
+        None => Some(resolve_settings(config, &[state.overrides()])),
+    };
+    // `__search` prints these above the entries; fzf keeps them as the header.
+    // A streamed list starts before its totals are known, so it has neither.
+    let header_lines = settings
+        .as_ref()
+        .filter(|settings| settings.sort_mode != SortMode::Stream)
+        .map_or(0, |settings| {
+            usize::from(settings.show_timings)
+                + usize::from(settings.show_stats && source == SessionSource::Files)
+        });
+    if header_lines > 0 {
+        command.arg(format!("--header-lines={header_lines}"));
+    }
 
-    if !output.status.success() {
-        return Ok(());
+    if settings
+        .as_ref()
+        .is_some_and(|settings| settings.number_file_rows)
+    {
+        for bind in jump_binds(&exe) {
+            command.arg("--bind").arg(bind);
+        }
+    }
+
+    // Without fzf on PATH, the built-in picker stands in for it.
+    #[cfg(feature = "builtin-ui")]
+    let picked = which("fzf").is_err().then(|| {
+        run_picker(
+            &reload,
+            cwd,
+            session.id(),
+            header_lines,
+            initial_query.unwrap_or_default(),
+        )
+    });
+    #[cfg(not(feature = "builtin-ui"))]
+    let picked: Option<Result<Option<String>>> = None;
+    let output = match picked {
+        // Quitting the picker is fzf's abort, which exits with 130.
+        Some(picked) => picked.map(|picked| match picked {
+            Some(stdout) => (Some(0), stdout),
+            None => (Some(130), String::new()),
+        }),
+        None => run_fzf(command, cwd, settings.as_ref(), &reload).map(|output| {
+            (
+                output.status.code(),
+                String::from_utf8_lossy(&output.stdout).into_owned(),
+            )
+        }),
+    };
+    if persist_prefs {
+        let saved = prefs_path()
+            .context("HOME is not set; cannot locate the prefs file")
+            .and_then(|path| {
+                save_session_prefs(&path, cwd, saved_prefs, launch_overrides, &session)
+            });
+        if let Err(error) = saved {
+            eprintln!("yoink: failed to save session prefs: {error}");
+        }
+    }
+    session.remove();
+    let (code, stdout) = output?;
+    if let Some(outcome) = root_gone_outcome(cwd) {
+        return Ok(outcome);
+    }
+
+    // fzf exits with 1 when nothing matched but still reports the query and key.
+    if !matches!(code, Some(0) | Some(1)) {
+        return Ok(SessionOutcome::Done);
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
     let mut lines = stdout.lines();
 
-    let _query_line = lines.next().unwrap_or_default();
+    let query = lines.next().unwrap_or_default();
+    session_record_query(query, history_size);
     let key = lines.next().unwrap_or("enter");
     let selected_line = lines.next().unwrap_or_default();
 
-    if selected_line.is_empty() {
-        return Ok(());
-    }
-
-    let (selected_rel_path, _selected_line_num) = parse_selected_line(selected_line);
+    let (selected_rel_path, selected_line_num, selected_column) =
+        parse_selected_line(selected_line);
+    let has_selection = !selected_rel_path.as_os_str().is_empty();
+    let selected_rel_path = selected_rel_path.as_path();
 
-    if selected_rel_path.is_empty() {
-        return Ok(());
+    let action = resolve_session_action(key, has_selection);
+    if buffer.is_none() && matches!(action, SessionAction::Select | SessionAction::OpenEditor(_)) {
+        remember_selection(cwd, selected_rel_path);
     }
 
-    match key {
-        "ctrl-v" => {
-            if let Err(error) = open_in_editor("vim", cwd, selected_rel_path) {
-                eprintln!("yoink editor error: {error}");
+    match action {
+        SessionAction::OpenEditor(editor) => {
+            let opened =
+                files_root(cwd, scope.rev.as_deref(), selected_rel_path).and_then(|root| {
+                    open_in_editor(
+                        editor,
+                        &root,
+                        selected_rel_path,
+                        selected_line_num,
+                        selected_column,
+                    )
+                });
+            if let Err(error) = opened {
+                eprintln!("yoink editor error: {error:#}");
+            }
+        }
+        SessionAction::EditorHint(editor) => {
+            eprintln!("yoink: no result selected to open in {editor}");
+        }
+        SessionAction::CreateFromQuery => {
+            if buffer.is_some() {
+                eprintln!("yoink: cannot create files while searching stdin");
+            } else {
+                match create_file_from_query(cwd, query) {
+                    Ok(created) => eprintln!("yoink: created {}", created.display()),
+                    Err(error) => eprintln!("yoink create error: {error}"),
+                }
+            }
+        }
+        SessionAction::CopyPath => {
+            if let Err(error) = copy_to_clipboard(&selected_rel_path.to_string_lossy()) {
+                eprintln!("yoink clipboard error: {error}");
+            }
+        }
+        SessionAction::CopyQuery => {
+            if let Err(error) = copy_to_clipboard(query) {
+                eprintln!("yoink clipboard error: {error}");
+            }
+        }
+        SessionAction::YankRange => match selected_line_num {
+            Some(line_num) => {
+                let context = load_settings_for(cwd)?.yank_context;
+                let yanked = files_root(cwd, scope.rev.as_deref(), selected_rel_path)
+                    .and_then(|root| yank_line_range(&root, selected_rel_path, line_num, context));
+                if let Err(error) = yanked {
+                    eprintln!("yoink clipboard error: {error}");
+                }
+            }
+            None => eprintln!("yoink: alt-y needs an occurrence row with a line number"),
+        },
+        SessionAction::YankHint => {
+            eprintln!("yoink: no occurrence selected to yank");
+        }
+        SessionAction::NoSelection => return Ok(SessionOutcome::NoSelection),
+        SessionAction::Select if buffer.is_some() => {
+            if let Some(line_num) = selected_line_num {
+                if let Some(text) = buffer_line(selected_rel_path, line_num)? {
+                    println!("{text}");
+                }
             }
-            Ok(())
         }
-        "ctrl-o" => {
-            if let Err(error) = open_in_editor("code", cwd, selected_rel_path) {
-                eprintln!("yoink editor error: {error}");
+        SessionAction::Select => {
+            let allowed_roots = if load_settings_for(cwd)?.confine_cd_to_root {
+                vec![cwd.to_path_buf()]
+            } else {
+                Vec::new()
+            };
+            match resolve_target_dir(cwd, selected_rel_path, &allowed_roots) {
+                Ok(target) => println!("{}", target.display()),
+                Err(error) => {
+                    eprintln!("yoink: {error}");
+                    return Ok(SessionOutcome::NoSelection);
+                }
             }
-            Ok(())
         }
-        "ctrl-s" => {
-            if let Err(error) = open_in_editor("subl", cwd, selected_rel_path) {
-                eprintln!("yoink editor error: {error}");
+    }
+
+    Ok(SessionOutcome::Done)
+}
+
+/// Runs the session's fzf; under `watch` the settings' auto refresh and
+/// `--watch` drive it over `--listen`, and a launch that lost its reserved port
+/// is retried once.
+fn run_fzf(
+    command: Command,
+    cwd: &Path,
+    settings: Option<&YoinkSettings>,
+    reload: &str,
+) -> Result<Output> {
+    let auto_refresh_secs = settings.and_then(|settings| settings.auto_refresh_secs);
+    #[cfg(feature = "watch")]
+    let mut listen_retries = 1;
+    #[cfg(feature = "watch")]
+    loop {
+        // Each launch listens on a freshly reserved port; see `reserve_listen_port`.
+        let mut command = copy_command(&command);
+        let auto_refresh = match auto_refresh_secs {
+            Some(secs) => Some(SessionRefresh::attach(
+                &mut command,
+                Duration::from_secs(secs),
+                reload,
+            )?),
+            None => {
+                command.arg("--bind").arg(format!("change:reload:{reload}"));
+                None
             }
-            Ok(())
+        };
+        let watch = match settings.filter(|settings| settings.watch) {
+            Some(settings) => SessionWatch::attach(
+                &mut command,
+                auto_refresh.as_ref().map(SessionRefresh::listen),
+                cwd,
+                settings,
+                reload,
+            )?,
+            None => None,
+        };
+        let listened = auto_refresh.is_some() || watch.is_some();
+
+        let started = Instant::now();
+        let output = command
+            .output()
+            .context("failed to execute fzf for interactive selection");
+
+        if let Some(auto_refresh) = auto_refresh {
+            auto_refresh.stop();
+        }
+        if let Some(watch) = watch {
+            watch.stop();
         }
-        _ => {
-            let target = resolve_target_dir(cwd, selected_rel_path);
-            println!("{}", target.display());
-            Ok(())
+        let lost_port = output
+            .as_ref()
+            .is_ok_and(|output| listen_failed(output.status.code(), started.elapsed()));
+        if !(listened && lost_port) || listen_retries == 0 {
+            return output;
         }
+        listen_retries -= 1;
+    }
+    #[cfg(not(feature = "watch"))]
+    {
+        // `check::enforce` has already warned about a set auto_refresh_secs or watch.
+        let _ = (auto_refresh_secs, cwd);
+        let mut command = command;
+        command.arg("--bind").arg(format!("change:reload:{reload}"));
+        command
+            .output()
+            .context("failed to execute fzf for interactive selection")
+    }
+}
+
+/// Logs a selection for the recent rows; a failure costs only the log entry.
+fn remember_selection(cwd: &Path, selected_rel_path: &Path) {
+    let Some(store) = selections_path() else {
+        return;
+    };
+    if let Err(error) = record_selection(&store, cwd, selected_rel_path) {
+        eprintln!("yoink: selection not remembered: {error:#}");
     }
 }
 
-fn parse_selected_line(selected_line: &str) -> (&str, Option<usize>) {
-    let mut parts = selected_line.splitn(3, '\t');
+/// Where the selection's file is read from: the root, or under `--rev` a
+/// read-only copy of the blob, since the work tree may differ or lack the file.
+fn files_root(cwd: &Path, rev: Option<&str>, rel: &Path) -> Result<PathBuf> {
+    match rev {
+        Some(rev) => extract_blob(cwd, rev, rel),
+        None => Ok(cwd.to_path_buf()),
+    }
+}
+
+fn yank_line_range(cwd: &Path, rel: &Path, line: usize, context: usize) -> Result<()> {
+    let (start, lines) = read_line_range(&cwd.join(rel), line, context)?;
+    let block = fenced_block(&rel.to_string_lossy(), fence_language(rel), start, &lines);
+    copy_to_clipboard(&block)
+}
+
+/// Splits an fzf line into path, line, and column; older three-field lines have
+/// no column. The trailing match count is for scripts reading `__search`.
+pub fn parse_selected_line(selected_line: &str) -> (PathBuf, Option<usize>, Option<usize>) {
+    let mut parts = selected_line.splitn(5, '\t');
     let _display = parts.next().unwrap_or_default();
     let path = parts.next().unwrap_or_default();
-    let line = parts.next().and_then(|raw| {
-        if raw.trim().is_empty() {
-            None
-        } else {
-            raw.trim().parse::<usize>().ok()
-        }
-    });
+    let parse_number = |raw: &str| raw.trim().parse::<usize>().ok();
+    let line = parts.next().and_then(parse_number);
+    let column = parts.next().and_then(parse_number);
 
-    (path, line)
+    (decode_path_field(path), line, column)
+}
+
+/// A `file`-style line for a binary preview: `lib/app.so: ELF binary, 14.2 KiB`.
+pub fn binary_summary(name: &str, head: &[u8], len: u64) -> String {
+    const MAGIC: &[(&[u8], &str)] = &[
+        (b"\x89PNG", "PNG image"),
+        (b"\xFF\xD8\xFF", "JPEG image"),
+        (b"GIF8", "GIF image"),
+        (b"%PDF", "PDF document"),
+        (b"\x7FELF", "ELF binary"),
+        (b"\xCF\xFA\xED\xFE", "Mach-O binary"),
+        (b"\xCE\xFA\xED\xFE", "Mach-O binary"),
+        (b"\xCA\xFE\xBA\xBE", "Mach-O universal binary"),
+        (b"MZ", "PE/Windows executable"),
+        (b"PK\x03\x04", "Zip archive"),
+        (b"\x1F\x8B", "gzip compressed data"),
+        (b"\0asm", "WebAssembly module"),
+        (b"SQLite format 3\0", "SQLite database"),
+    ];
+    let kind = MAGIC
+        .iter()
+        .find(|(magic, _)| head.starts_with(magic))
+        .map_or("binary data", |(_, kind)| kind);
+    format!("{name}: {kind}, {}", human_size(len))
+}
+
+fn human_size(len: u64) -> String {
+    const UNITS: [&str; 3] = ["KiB", "MiB", "GiB"];
+    if len < 1024 {
+        return format!("{len} B");
+    }
+    let mut size = len as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
 }
 
 pub fn run_preview(
     cwd: &Path,
-    selected_rel_path: &str,
+    selected_rel_path: &Path,
     query: &str,
     selected_line: Option<usize>,
+    selected_column: Option<usize>,
 ) -> Result<()> {
+    if root_unavailable(cwd) {
+        println!("{}", root_gone_message(cwd));
+        return Ok(());
+    }
+
+    let settings = load_settings()?;
+    #[cfg(feature = "git")]
+    if let Some(rev) = settings.rev.as_deref() {
+        let content = show_blob(cwd, rev, selected_rel_path)?;
+        return preview_bytes(
+            selected_rel_path,
+            &content,
+            query,
+            selected_line,
+            &settings,
+            true,
+        );
+    }
+    if let Some((archive, member)) = split_member(selected_rel_path).filter(|_| settings.archives) {
+        let content = extract_member(&cwd.join(archive), &member)?;
+        return preview_bytes(
+            Path::new(&member),
+            &content,
+            query,
+            selected_line,
+            &settings,
+            true,
+        );
+    }
+
     let full = cwd.join(selected_rel_path);
     if full.is_dir() {
         Command::new("ls")
@@ -120,58 +857,84 @@ pub fn run_preview(
         return Ok(());
     }
 
-    let mut bat = Command::new("bat");
-    bat.arg("--style=numbers")
-        .arg("--color=always");
-
-    if let Some(line_num) = selected_line {
-        let context = 30usize;
-        let start = if line_num > context { line_num - context } else { 1 };
-        let end = line_num + context;
-        bat.arg("--highlight-line")
-            .arg(line_num.to_string())
-            .arg("--line-range")
-            .arg(format!("{start}:{end}"));
-    } else if !query.trim().is_empty() {
-        let rg_output = Command::new("rg")
-            .arg("-n")
-            .arg("-m")
-            .arg("1")
-            .arg("--color=never")
-            .arg("--no-messages")
-            .arg("-e")
-            .arg(query)
-            .arg(&full)
-            .output()
-            .context("failed to execute rg for preview line detection")?;
-
-        if rg_output.status.success() {
-            let stdout = String::from_utf8_lossy(&rg_output.stdout);
-            let first_line = stdout.lines().next().unwrap_or_default();
-            let mut parts = first_line.splitn(2, ':');
-            if let Some(line_str) = parts.next() {
-                if let Ok(line_num) = line_str.parse::<usize>() {
-                    let context = 30usize;
-                    let start = if line_num > context { line_num - context } else { 1 };
-                    let end = line_num + context;
-                    bat.arg("--highlight-line")
-                        .arg(line_num.to_string())
-                        .arg("--line-range")
-                        .arg(format!("{start}:{end}"));
-                } else {
-                    bat.arg("--line-range=:300");
-                }
-            } else {
-                bat.arg("--line-range=:300");
+    let aliases = Session::from_env()
+        .map(|session| session.aliases_of(selected_rel_path))
+        .unwrap_or_default();
+    if let Some(header) = alias_header(&aliases) {
+        println!("{header}");
+    }
+
+    // The extracted text is shown as plain text; the extension would name the binary format.
+    if let Some(template) = settings.preprocessor_for(selected_rel_path) {
+        if let Some(text) = extract_text(template, &full) {
+            return preview_bytes(
+                selected_rel_path,
+                text.as_bytes(),
+                query,
+                selected_line,
+                &settings,
+                false,
+            );
+        }
+    }
+
+    // bat would dump raw bytes; describe the file instead.
+    if is_binary_file(&full) {
+        let len = fs::metadata(&full)
+            .map(|metadata| metadata.len())
+            .unwrap_or(0);
+        let mut head = Vec::new();
+        if let Ok(file) = fs::File::open(&full) {
+            let _ = file.take(16).read_to_end(&mut head);
+        }
+        println!(
+            "{}",
+            binary_summary(&selected_rel_path.to_string_lossy(), &head, len)
+        );
+        #[cfg(feature = "archives")]
+        if settings.archives && ArchiveKind::of(&full).is_some() {
+            for member in list_members(&full)? {
+                println!("  {member}");
             }
-        } else {
-            bat.arg("--line-range=:300");
         }
-    } else {
-        bat.arg("--line-range=:300");
+        return Ok(());
+    }
+
+    let pattern = preview_pattern(query, &settings)?;
+    let matches = match_lines(&full, pattern.as_ref(), &settings)?;
+    let cursor = Session::from_env().and_then(|session| session.occurrence());
+    let focus_index = occurrence_index(cursor.as_ref(), selected_rel_path, selected_line, &matches);
+    let focus_line = match focus_index {
+        Some(index) => Some(matches[index]),
+        None => selected_line,
+    };
+    if let Some(index) = focus_index {
+        let header = occurrence_header(
+            index,
+            matches.len(),
+            matches.len() >= PREVIEW_MAX_OCCURRENCES,
+        );
+        // The row's column only holds while the preview is on the row's own match.
+        match (focus_line, selected_column) {
+            (Some(line), Some(column)) if focus_line == selected_line => {
+                println!("{header} at {line}:{column}")
+            }
+            _ => println!("{header}"),
+        }
+    }
+
+    if session_color_mode() == ColorMode::Plain {
+        let content = fs::read(&full)
+            .with_context(|| format!("failed to read {} for preview", full.display()))?;
+        let regex = pattern.as_ref().and_then(QueryPattern::highlight_regex);
+        print!(
+            "{}",
+            render_plain_preview(&String::from_utf8_lossy(&content), focus_line, regex)
+        );
+        return Ok(());
     }
 
-    let status = bat
+    let status = bat_preview(focus_line)
         .arg(&full)
         .status()
         .context("failed to preview file with bat")?;
@@ -179,7 +942,7 @@ pub fn run_preview(
     if !status.success() {
         Command::new("sed")
             .arg("-n")
-            .arg("1,300p")
+            .arg(format!("1,{PREVIEW_MAX_LINES}p"))
             .arg(&full)
             .status()
             .context("failed to preview file with sed fallback")?;
@@ -188,6 +951,314 @@ pub fn run_preview(
     Ok(())
 }
 
+/// bat centered on `focus_line`, or the file's start; the caller adds the input.
+fn bat_preview(focus_line: Option<usize>) -> Command {
+    let mut bat = Command::new("bat");
+    bat.arg("--style=numbers").arg("--color=always");
+
+    match focus_line {
+        Some(line_num) => {
+            let (start, end) = preview_window(line_num);
+            bat.arg("--highlight-line")
+                .arg(line_num.to_string())
+                .arg("--line-range")
+                .arg(format!("{start}:{end}"));
+        }
+        None => {
+            bat.arg(format!("--line-range=:{PREVIEW_MAX_LINES}"));
+        }
+    }
+    bat
+}
+
+/// The preview of text that isn't a file on disk: a blob under `--rev`, an
+/// archive member, or a preprocessor's output. `content` is piped into bat,
+/// with `--file-name` under `by_name` so the syntax is still picked by
+/// `name`. The focus is the row's line, or the first match when the row has none.
+fn preview_bytes(
+    name: &Path,
+    content: &[u8],
+    query: &str,
+    selected_line: Option<usize>,
+    settings: &YoinkSettings,
+    by_name: bool,
+) -> Result<()> {
+    let head = &content[..content.len().min(BINARY_SNIFF_BYTES)];
+    if head.contains(&0) {
+        let summary = binary_summary(
+            &name.to_string_lossy(),
+            &content[..content.len().min(16)],
+            content.len() as u64,
+        );
+        println!("{summary}");
+        return Ok(());
+    }
+
+    let text = String::from_utf8_lossy(content);
+    let pattern = preview_pattern(query, settings)?;
+    let regex = pattern.as_ref().and_then(QueryPattern::highlight_regex);
+    let focus_line = selected_line.or_else(|| {
+        let regex = regex?;
+        text.lines()
+            .position(|line| regex.is_match(line))
+            .map(|index| index + 1)
+    });
+
+    if session_color_mode() == ColorMode::Plain {
+        print!("{}", render_plain_preview(&text, focus_line, regex));
+        return Ok(());
+    }
+
+    let mut bat = bat_preview(focus_line);
+    if by_name {
+        bat.arg("--file-name").arg(name);
+    }
+    let bat = bat.stdin(Stdio::piped()).spawn();
+    let Ok(mut bat) = bat else {
+        print!(
+            "{}",
+            text.lines()
+                .take(PREVIEW_MAX_LINES)
+                .map(|line| format!("{line}\n"))
+                .collect::<String>()
+        );
+        return Ok(());
+    };
+    if let Some(mut stdin) = bat.stdin.take() {
+        // bat stops reading once past its line range; the rest isn't needed.
+        let _ = stdin.write_all(content);
+    }
+    bat.wait().context("failed to preview with bat")?;
+    Ok(())
+}
+
+const PREVIEW_CONTEXT: usize = 30;
+const PREVIEW_MAX_LINES: usize = 300;
+/// Matches the preview pages through per file; past this the header shows `N+`.
+const PREVIEW_MAX_OCCURRENCES: usize = 1000;
+
+fn preview_window(line_num: usize) -> (usize, usize) {
+    (
+        line_num.saturating_sub(PREVIEW_CONTEXT).max(1),
+        line_num + PREVIEW_CONTEXT,
+    )
+}
+
+/// The query as the preview matches it: like the list, but always falling back
+/// to a literal search rather than failing on an invalid regex.
+fn preview_pattern(query: &str, settings: &YoinkSettings) -> Result<Option<QueryPattern>> {
+    let (positive, _) = split_negations(query);
+    if positive.is_empty() {
+        return Ok(None);
+    }
+    let ignore_case = settings.case_mode.ignores_case(&positive);
+    let pattern = if settings.and_terms {
+        QueryPattern::resolve_terms(&positive, RegexFallback::Literal, ignore_case)
+    } else {
+        QueryPattern::resolve_with_case(&positive, RegexFallback::Literal, ignore_case)
+    };
+    let pattern = match pattern {
+        Ok(pattern) if settings.word_boundaries => pattern.word_bounded(),
+        other => other,
+    };
+    let pattern = pattern.map(|pattern| {
+        if settings.multiline {
+            pattern.with_multiline()
+        } else {
+            pattern
+        }
+    });
+    Ok(pattern.ok())
+}
+
+/// Line numbers of the file's matching lines, in order, up to `PREVIEW_MAX_OCCURRENCES`.
+fn match_lines(
+    full: &Path,
+    pattern: Option<&QueryPattern>,
+    settings: &YoinkSettings,
+) -> Result<Vec<usize>> {
+    let Some(pattern) = pattern.filter(|pattern| !pattern.raw.trim().is_empty()) else {
+        return Ok(Vec::new());
+    };
+    if settings.internal_engine() {
+        // Unreadable or binary files simply have no lines to center on.
+        let Ok(bytes) = fs::read(full) else {
+            return Ok(Vec::new());
+        };
+        let text = String::from_utf8_lossy(&bytes);
+        return Ok(text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| pattern.regex.is_match(line))
+            .map(|(index, _)| index + 1)
+            .take(PREVIEW_MAX_OCCURRENCES)
+            .collect());
+    }
+
+    let mut rg_command = Command::new("rg");
+    rg_command
+        .arg("-n")
+        .arg("--max-count")
+        .arg(PREVIEW_MAX_OCCURRENCES.to_string())
+        .arg("--color=never")
+        .arg("--no-messages")
+        // Every line starts at its number, whatever colons the path holds.
+        .arg("--no-filename")
+        .args(rg_config_args(settings))
+        .args(pattern.rg_args());
+    if pattern.multiline {
+        // Only each match's starting line, so the preview centers there.
+        rg_command.arg("--vimgrep");
+    }
+    let rg_output = rg_command
+        .arg("--")
+        .arg(full)
+        .output()
+        .context("failed to execute rg for preview line detection")?;
+
+    if !rg_output.status.success() {
+        return Ok(Vec::new());
+    }
+
+    let stdout = String::from_utf8_lossy(&rg_output.stdout);
+    Ok(stdout
+        .lines()
+        .filter_map(|line| line.split(':').next()?.parse::<usize>().ok())
+        .collect())
+}
+
+/// Which of `matches` the preview centers on: the stepped-to index when `cursor`
+/// belongs to this row, otherwise the row's own line, or the first match for file rows.
+/// `None` when there is nothing to page through or the row's line isn't a match.
+pub fn occurrence_index(
+    cursor: Option<&OccurrenceCursor>,
+    path: &Path,
+    line: Option<usize>,
+    matches: &[usize],
+) -> Option<usize> {
+    if matches.is_empty() {
+        return None;
+    }
+    match (cursor, line) {
+        (Some(cursor), _) if cursor.is_for(path, line) => Some(cursor.index.min(matches.len() - 1)),
+        (_, Some(line)) => matches.iter().position(|&candidate| candidate == line),
+        (_, None) => Some(0),
+    }
+}
+
+/// Moves to the next (or previous) match, wrapping at either end; the cursor
+/// starts over whenever the selection differs from the one it was stepped on.
+pub fn step_occurrence(
+    cursor: Option<&OccurrenceCursor>,
+    path: &Path,
+    line: Option<usize>,
+    matches: &[usize],
+    forward: bool,
+) -> Option<OccurrenceCursor> {
+    if matches.is_empty() {
+        return None;
+    }
+    let total = matches.len();
+    let index = match occurrence_index(cursor, path, line, matches) {
+        Some(current) if forward => (current + 1) % total,
+        Some(current) => (current + total - 1) % total,
+        None if forward => 0,
+        None => total - 1,
+    };
+    Some(OccurrenceCursor {
+        path: path.to_path_buf(),
+        line,
+        index,
+    })
+}
+
+/// `also at: lib/a.rs, vendor/a.rs` for a file reachable by other paths.
+pub fn alias_header(aliases: &[PathBuf]) -> Option<String> {
+    if aliases.is_empty() {
+        return None;
+    }
+    let paths: Vec<String> = aliases
+        .iter()
+        .map(|alias| alias.display().to_string())
+        .collect();
+    Some(format!("also at: {}", paths.join(", ")))
+}
+
+/// `match 7/60`; `capped` marks a count cut off at the per-file limit.
+pub fn occurrence_header(index: usize, total: usize, capped: bool) -> String {
+    format!(
+        "match {}/{total}{}",
+        index + 1,
+        if capped { "+" } else { "" }
+    )
+}
+
+/// Backs `alt-j`/`alt-k`: steps the session's cursor for the selected row; the
+/// bind then refreshes the preview, which reads the cursor back.
+pub fn step_preview_occurrence(
+    cwd: &Path,
+    selected_rel_path: &Path,
+    query: &str,
+    selected_line: Option<usize>,
+    forward: bool,
+) -> Result<()> {
+    let Some(session) = Session::from_env() else {
+        return Ok(());
+    };
+    let full = cwd.join(selected_rel_path);
+    if full.is_dir() {
+        return Ok(());
+    }
+
+    let settings = load_settings()?;
+    let matches = match_lines(
+        &full,
+        preview_pattern(query, &settings)?.as_ref(),
+        &settings,
+    )?;
+    let cursor = step_occurrence(
+        session.occurrence().as_ref(),
+        selected_rel_path,
+        selected_line,
+        &matches,
+        forward,
+    );
+    session.set_occurrence(cursor)
+}
+
+/// Escape-free preview for dumb terminals: numbered lines around `focus_line`
+/// (or the first 300), the focus line marked with `>` and matches with `>>`/`<<`.
+pub fn render_plain_preview(
+    content: &str,
+    focus_line: Option<usize>,
+    regex: Option<&Regex>,
+) -> String {
+    let (start, end) = match focus_line {
+        Some(line_num) => preview_window(line_num),
+        None => (1, PREVIEW_MAX_LINES),
+    };
+
+    let mut out = String::new();
+    for (index, line) in content.lines().enumerate() {
+        let line_num = index + 1;
+        if line_num < start {
+            continue;
+        }
+        if line_num > end {
+            break;
+        }
+
+        let marker = if Some(line_num) == focus_line {
+            '>'
+        } else {
+            ' '
+        };
+        let text = highlight_query_matches(&strip_ansi(line), regex, ColorMode::Plain);
+        out.push_str(&format!("{marker}{line_num:>5}  {text}\n"));
+    }
+    out
+}
+
 pub fn current_exe() -> Result<PathBuf> {
     std::env::current_exe().context("failed to resolve current executable path")
 }
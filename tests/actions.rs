@@ -1,17 +1,310 @@
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
 
-use yoink::actions::resolve_target_dir;
+use tempfile::tempdir;
+
+use yoink::actions::{
+    create_file_from_query, editor_args, fence_language, fenced_block, read_line_range,
+    remove_empty_path, resolve_search_root, resolve_search_roots, resolve_target_dir,
+};
+use yoink::search::build_candidates;
 
 #[test]
 fn resolve_target_dir_for_file() {
     let cwd = Path::new("/tmp/work");
-    let target = resolve_target_dir(cwd, "src/main.rs");
+    let target =
+        resolve_target_dir(cwd, Path::new("src/main.rs"), &[cwd.to_path_buf()]).expect("target");
     assert_eq!(target, Path::new("/tmp/work/src"));
 }
 
+#[test]
+fn resolve_target_dir_for_archive_member() {
+    let cwd = Path::new("/tmp/work");
+    let target = resolve_target_dir(
+        cwd,
+        Path::new("dist/assets.zip::images/logo.svg"),
+        &[cwd.to_path_buf()],
+    )
+    .expect("target");
+    assert_eq!(target, Path::new("/tmp/work/dist"));
+}
+
 #[test]
 fn resolve_target_dir_for_directory() {
     let cwd = Path::new("/tmp/work");
-    let target = resolve_target_dir(cwd, "src");
+    let target = resolve_target_dir(cwd, Path::new("src"), &[cwd.to_path_buf()]).expect("target");
     assert_eq!(target, Path::new("/tmp/work"));
 }
+
+#[test]
+fn resolve_target_dir_folds_dot_segments() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path().canonicalize().expect("canonical root");
+    fs::create_dir_all(root.join("src/nested")).expect("mkdir");
+    let roots = [root.clone()];
+
+    let target =
+        resolve_target_dir(&root, Path::new("src/nested/../lib.rs"), &roots).expect("target");
+    assert_eq!(target, root.join("src"));
+    let target =
+        resolve_target_dir(&root, Path::new("./src/./missing/x.rs"), &roots).expect("target");
+    assert_eq!(target, root.join("src/missing"));
+}
+
+#[cfg(unix)]
+#[test]
+fn resolve_target_dir_canonicalizes_symlinked_parents() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path().canonicalize().expect("canonical root");
+    fs::create_dir_all(root.join("real/inner")).expect("mkdir");
+    std::os::unix::fs::symlink(root.join("real"), root.join("link")).expect("symlink");
+    let roots = [root.clone()];
+
+    let target =
+        resolve_target_dir(&root, Path::new("link/inner/file.txt"), &roots).expect("target");
+    assert_eq!(target, root.join("real/inner"));
+
+    // A symlink leading out of the root is caught after resolution.
+    let outside = tempdir().expect("outside");
+    fs::create_dir(outside.path().join("sub")).expect("mkdir outside");
+    std::os::unix::fs::symlink(outside.path(), root.join("escape")).expect("symlink");
+    assert!(resolve_target_dir(&root, Path::new("escape/sub/file.txt"), &roots).is_err());
+    assert!(resolve_target_dir(&root, Path::new("escape/sub/file.txt"), &[]).is_ok());
+}
+
+#[cfg(unix)]
+#[test]
+fn resolve_target_dir_goes_to_the_directory_holding_a_symlinked_entry() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path().canonicalize().expect("canonical root");
+    fs::create_dir(root.join("docs")).expect("mkdir");
+    let outside = tempdir().expect("outside");
+    fs::create_dir(outside.path().join("out")).expect("mkdir outside");
+    fs::write(outside.path().join("out/t.txt"), "t\n").expect("write outside");
+    std::os::unix::fs::symlink(outside.path().join("out/t.txt"), root.join("docs/link.txt"))
+        .expect("symlink file");
+    std::os::unix::fs::symlink(outside.path().join("out"), root.join("linked_dir"))
+        .expect("symlink dir");
+    let roots = [root.clone()];
+
+    let target = resolve_target_dir(&root, Path::new("docs/link.txt"), &roots).expect("file");
+    assert_eq!(target, root.join("docs"));
+    let target = resolve_target_dir(&root, Path::new("linked_dir"), &roots).expect("dir");
+    assert_eq!(target, root);
+}
+
+#[test]
+fn resolve_target_dir_rejects_paths_outside_the_roots() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path().join("project");
+    fs::create_dir(&root).expect("mkdir");
+    let roots = [root.clone()];
+
+    assert!(resolve_target_dir(&root, Path::new("../other/file.txt"), &roots).is_err());
+    assert!(resolve_target_dir(&root, Path::new("src/../../file.txt"), &roots).is_err());
+    assert!(resolve_target_dir(&root, Path::new("/etc/passwd"), &roots).is_err());
+
+    // Any root in the set is enough, and an empty set opts out.
+    let wider = [root.clone(), dir.path().to_path_buf()];
+    assert!(resolve_target_dir(&root, Path::new("../other/file.txt"), &wider).is_ok());
+    let unchecked: [PathBuf; 0] = [];
+    assert!(resolve_target_dir(&root, Path::new("../other/file.txt"), &unchecked).is_ok());
+}
+
+#[test]
+fn editor_args_jump_to_line() {
+    let full = Path::new("/tmp/buffer.txt");
+    assert_eq!(
+        editor_args("vim", full, Some(12), None),
+        vec!["+12", "/tmp/buffer.txt"]
+    );
+    assert_eq!(
+        editor_args("code", full, Some(12), None),
+        vec!["--goto", "/tmp/buffer.txt:12"]
+    );
+    assert_eq!(
+        editor_args("subl", full, Some(12), None),
+        vec!["/tmp/buffer.txt:12"]
+    );
+    assert_eq!(
+        editor_args("vim", full, None, None),
+        vec!["/tmp/buffer.txt"]
+    );
+}
+
+#[test]
+fn editor_args_jump_to_column_where_supported() {
+    let full = Path::new("/tmp/work/src/lib.rs");
+    assert_eq!(
+        editor_args("vim", full, Some(7), Some(3)),
+        vec!["+call cursor(7,3)", "/tmp/work/src/lib.rs"]
+    );
+    assert_eq!(
+        editor_args("nvim", full, Some(7), Some(3)),
+        vec!["+call cursor(7,3)", "/tmp/work/src/lib.rs"]
+    );
+    assert_eq!(
+        editor_args("code", full, Some(7), Some(3)),
+        vec!["--goto", "/tmp/work/src/lib.rs:7:3"]
+    );
+    assert_eq!(
+        editor_args("subl", full, Some(7), Some(3)),
+        vec!["/tmp/work/src/lib.rs:7:3"]
+    );
+    assert_eq!(
+        editor_args("vi", full, Some(7), Some(3)),
+        vec!["+7", "/tmp/work/src/lib.rs"]
+    );
+    assert_eq!(
+        editor_args("nano", full, Some(7), Some(3)),
+        vec!["/tmp/work/src/lib.rs"]
+    );
+}
+
+#[test]
+fn creates_file_named_after_query() {
+    let dir = tempdir().expect("tempdir");
+    let created = create_file_from_query(dir.path(), "notes/todo.md").expect("create file");
+    assert_eq!(created, dir.path().join("notes/todo.md"));
+    assert!(created.is_file());
+
+    assert!(create_file_from_query(dir.path(), "notes/todo.md").is_err());
+    assert!(create_file_from_query(dir.path(), "  ").is_err());
+    assert!(create_file_from_query(dir.path(), "../escape.txt").is_err());
+}
+
+#[test]
+fn reads_line_range_clamped_to_file_bounds() {
+    let dir = tempdir().expect("tempdir");
+    let file = dir.path().join("lines.txt");
+    let content: String = (1..=20).map(|n| format!("line {n}\n")).collect();
+    fs::write(&file, content).expect("write lines");
+
+    let (start, lines) = read_line_range(&file, 10, 2).expect("middle range");
+    assert_eq!(start, 8);
+    assert_eq!(
+        lines,
+        vec!["line 8", "line 9", "line 10", "line 11", "line 12"]
+    );
+
+    let (start, lines) = read_line_range(&file, 2, 5).expect("top range");
+    assert_eq!(start, 1);
+    assert_eq!(lines.len(), 7);
+
+    let (start, lines) = read_line_range(&file, 19, 5).expect("bottom range");
+    assert_eq!(start, 14);
+    assert_eq!(lines.last().map(String::as_str), Some("line 20"));
+
+    assert!(read_line_range(&file, 21, 5).is_err());
+}
+
+#[test]
+fn builds_fenced_block_with_path_and_range() {
+    let lines = vec!["fn main() {".to_string(), "}".to_string()];
+    let block = fenced_block("src/main.rs", Some("rs"), 10, &lines);
+    assert_eq!(block, "```rs src/main.rs:10-11\nfn main() {\n}\n```\n");
+
+    let untagged = fenced_block("LICENSE", None, 3, &lines[..1]);
+    assert_eq!(untagged, "```LICENSE:3-3\nfn main() {\n```\n");
+}
+
+#[test]
+fn fenced_block_outruns_backtick_fences_in_the_range() {
+    let lines = vec![
+        "Run it:".to_string(),
+        "```sh".to_string(),
+        "make ````all````".to_string(),
+        "```".to_string(),
+    ];
+    let block = fenced_block("README.md", Some("md"), 4, &lines);
+    assert_eq!(
+        block,
+        "`````md README.md:4-7\nRun it:\n```sh\nmake ````all````\n```\n`````\n"
+    );
+}
+
+#[test]
+fn picks_fence_language_from_extension() {
+    assert_eq!(fence_language(Path::new("src/ui.rs")), Some("rs"));
+    assert_eq!(fence_language(Path::new("scripts/install.sh")), Some("sh"));
+    assert_eq!(fence_language(Path::new("Makefile")), None);
+}
+
+#[test]
+fn removes_only_entries_that_are_still_empty() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::write(root.join("blank.txt"), "").expect("write blank");
+    fs::write(root.join("refilled.txt"), "new data\n").expect("write refilled");
+    fs::create_dir(root.join("hollow")).expect("mkdir hollow");
+    fs::create_dir(root.join("busy")).expect("mkdir busy");
+    fs::write(root.join("busy/file"), "").expect("write busy child");
+
+    remove_empty_path(root, Path::new("blank.txt"), false).expect("remove blank");
+    remove_empty_path(root, Path::new("hollow"), false).expect("remove hollow");
+    assert!(!root.join("blank.txt").exists());
+    assert!(!root.join("hollow").exists());
+
+    assert!(remove_empty_path(root, Path::new("refilled.txt"), false).is_err());
+    assert!(remove_empty_path(root, Path::new("busy"), false).is_err());
+    assert!(remove_empty_path(root, Path::new("../outside"), false).is_err());
+    assert!(remove_empty_path(root, Path::new(""), false).is_err());
+    assert!(root.join("refilled.txt").exists());
+    assert!(root.join("busy/file").exists());
+}
+
+#[test]
+fn search_root_is_canonical_and_lists_paths_relative_to_it() {
+    let dir = tempdir().expect("tempdir");
+    let project = dir.path().join("projects/foo");
+    fs::create_dir_all(project.join("src")).expect("create dirs");
+    fs::write(project.join("src/needle.rs"), "fn main() {}\n").expect("write file");
+
+    let root = resolve_search_root(&dir.path().join("projects/../projects/foo")).expect("root");
+    assert_eq!(root, fs::canonicalize(&project).expect("canonical"));
+    assert_ne!(std::env::current_dir().expect("cwd"), root);
+
+    let candidates = build_candidates("needle", &root).expect("candidates");
+    let paths: Vec<&Path> = candidates
+        .iter()
+        .map(|candidate| candidate.path.as_path())
+        .collect();
+    assert_eq!(paths, [Path::new("src/needle.rs")]);
+
+    let missing = resolve_search_root(&dir.path().join("missing")).expect_err("missing");
+    assert!(missing.to_string().contains("does not exist"), "{missing}");
+    let file = resolve_search_root(&project.join("src/needle.rs")).expect_err("file");
+    assert!(file.to_string().contains("is not a directory"), "{file}");
+}
+
+#[test]
+fn search_roots_share_their_common_directory_and_drop_nested_ones() {
+    let dir = tempdir().expect("tempdir");
+    let work = fs::canonicalize(dir.path())
+        .expect("canonical")
+        .join("work");
+    for sub in ["api/src", "web/frontend", "web/frontend/inner"] {
+        fs::create_dir_all(work.join(sub)).expect("create dirs");
+    }
+
+    let (common, labels) = resolve_search_roots(&[
+        &work.join("web/frontend"),
+        &work.join("api"),
+        &work.join("web/frontend/inner"),
+        &work.join("api/../api"),
+    ])
+    .expect("roots");
+    assert_eq!(common, work);
+    assert_eq!(
+        labels,
+        [PathBuf::from("api"), PathBuf::from("web/frontend")]
+    );
+
+    // One root left after dropping the nested one searches it directly.
+    let (common, labels) =
+        resolve_search_roots(&[&work.join("api"), &work.join("api/src")]).expect("nested");
+    assert_eq!(common, work.join("api"));
+    assert!(labels.is_empty());
+
+    assert!(resolve_search_roots(&[&work.join("api"), &work.join("missing")]).is_err());
+}
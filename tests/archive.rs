@@ -0,0 +1,163 @@
+#[cfg(feature = "archives")]
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "archives")]
+use tempfile::tempdir;
+
+#[cfg(feature = "archives")]
+use yoink::archive::{extract_member, list_members};
+use yoink::archive::{member_path, parse_listing, split_member, ArchiveKind};
+
+/// A zip of stored (uncompressed) entries; a name ending in `/` is a directory.
+#[cfg(feature = "archives")]
+fn write_zip(path: &Path, entries: &[(&str, &[u8])]) {
+    fn crc32(data: &[u8]) -> u32 {
+        let mut crc = !0u32;
+        for byte in data {
+            crc ^= u32::from(*byte);
+            for _ in 0..8 {
+                crc = if crc & 1 == 1 {
+                    (crc >> 1) ^ 0xEDB8_8320
+                } else {
+                    crc >> 1
+                };
+            }
+        }
+        !crc
+    }
+
+    let mut zip = Vec::new();
+    let mut central = Vec::new();
+    for (name, data) in entries {
+        let offset = zip.len() as u32;
+        let crc = crc32(data);
+        let size = data.len() as u32;
+        let name_len = name.len() as u16;
+        zip.extend(0x0403_4b50u32.to_le_bytes());
+        zip.extend(
+            [20u16, 0, 0, 0, 0]
+                .iter()
+                .flat_map(|field| field.to_le_bytes()),
+        );
+        zip.extend(crc.to_le_bytes());
+        zip.extend(size.to_le_bytes());
+        zip.extend(size.to_le_bytes());
+        zip.extend(name_len.to_le_bytes());
+        zip.extend(0u16.to_le_bytes());
+        zip.extend(name.as_bytes());
+        zip.extend(*data);
+
+        central.extend(0x0201_4b50u32.to_le_bytes());
+        central.extend(
+            [20u16, 20, 0, 0, 0, 0]
+                .iter()
+                .flat_map(|field| field.to_le_bytes()),
+        );
+        central.extend(crc.to_le_bytes());
+        central.extend(size.to_le_bytes());
+        central.extend(size.to_le_bytes());
+        central.extend(name_len.to_le_bytes());
+        central.extend([0u16, 0, 0, 0].iter().flat_map(|field| field.to_le_bytes()));
+        central.extend(0u32.to_le_bytes());
+        central.extend(offset.to_le_bytes());
+        central.extend(name.as_bytes());
+    }
+    let central_offset = zip.len() as u32;
+    let count = entries.len() as u16;
+    zip.extend(&central);
+    zip.extend(0x0605_4b50u32.to_le_bytes());
+    zip.extend(
+        [0u16, 0, count, count]
+            .iter()
+            .flat_map(|field| field.to_le_bytes()),
+    );
+    zip.extend((central.len() as u32).to_le_bytes());
+    zip.extend(central_offset.to_le_bytes());
+    zip.extend(0u16.to_le_bytes());
+    fs::write(path, zip).expect("write zip");
+}
+
+#[test]
+fn tells_archives_by_extension() {
+    assert_eq!(
+        ArchiveKind::of(Path::new("dist/assets.ZIP")),
+        Some(ArchiveKind::Zip)
+    );
+    assert_eq!(
+        ArchiveKind::of(Path::new("backup.tar")),
+        Some(ArchiveKind::Tar)
+    );
+    assert_eq!(
+        ArchiveKind::of(Path::new("backup.tar.gz")),
+        Some(ArchiveKind::Tar)
+    );
+    assert_eq!(
+        ArchiveKind::of(Path::new("backup.tgz")),
+        Some(ArchiveKind::Tar)
+    );
+    assert_eq!(ArchiveKind::of(Path::new("notes.gz")), None);
+    assert_eq!(ArchiveKind::of(Path::new("zip")), None);
+}
+
+#[test]
+fn member_paths_split_back_at_the_archive() {
+    let path = member_path(Path::new("dist/assets.zip"), "images/logo.svg");
+    assert_eq!(path, Path::new("dist/assets.zip::images/logo.svg"));
+    assert_eq!(
+        split_member(&path),
+        Some((
+            PathBuf::from("dist/assets.zip"),
+            "images/logo.svg".to_string()
+        ))
+    );
+    let odd = member_path(Path::new("a::b.tar"), "c::d.txt");
+    assert_eq!(
+        split_member(&odd),
+        Some((PathBuf::from("a::b.tar"), "c::d.txt".to_string()))
+    );
+    assert_eq!(split_member(Path::new("src/lib.rs")), None);
+    assert_eq!(
+        split_member(Path::new("notes::draft.md")),
+        None,
+        "no archive before the separator"
+    );
+}
+
+#[test]
+fn listings_leave_out_directory_entries() {
+    assert_eq!(
+        parse_listing(b"images/\nimages/logo.svg\r\n./README.md\n\n"),
+        ["images/logo.svg", "./README.md"]
+    );
+}
+
+#[cfg(feature = "archives")]
+#[test]
+fn lists_and_extracts_zip_members() {
+    let dir = tempdir().expect("tempdir");
+    let zip = dir.path().join("assets.zip");
+    write_zip(
+        &zip,
+        &[
+            ("images/", b""),
+            ("images/logo.svg", b"<svg/>\n"),
+            ("docs/[draft]*.md", b"# draft\n"),
+        ],
+    );
+
+    assert_eq!(
+        list_members(&zip).expect("members"),
+        ["images/logo.svg", "docs/[draft]*.md"]
+    );
+    assert_eq!(
+        extract_member(&zip, "images/logo.svg").expect("logo"),
+        b"<svg/>\n"
+    );
+    assert_eq!(
+        extract_member(&zip, "docs/[draft]*.md").expect("draft"),
+        b"# draft\n"
+    );
+    assert!(extract_member(&zip, "missing.txt").is_err());
+    assert!(list_members(&dir.path().join("plain.txt")).is_err());
+}
@@ -0,0 +1,154 @@
+use yoink::check::{check_settings, Severity};
+use yoink::lang::FileTypeSet;
+use yoink::search::YoinkSettings;
+
+fn rules(settings: &YoinkSettings) -> Vec<(&'static str, Severity)> {
+    check_settings(settings)
+        .into_iter()
+        .map(|issue| (issue.rule, issue.severity))
+        .collect()
+}
+
+#[test]
+fn default_settings_have_no_issues() {
+    assert!(check_settings(&YoinkSettings::default()).is_empty());
+}
+
+#[test]
+fn types_all_excluded_is_an_error() {
+    let settings = YoinkSettings {
+        file_types: FileTypeSet::parse("rust").unwrap(),
+        file_types_not: FileTypeSet::parse("rust,py").unwrap(),
+        ..YoinkSettings::default()
+    };
+    assert_eq!(
+        rules(&settings),
+        vec![("types-all-excluded", Severity::Error)]
+    );
+    let issue = &check_settings(&settings)[0];
+    assert!(issue.message.contains("rust"), "{}", issue.message);
+}
+
+#[test]
+fn partial_types_overlap_is_a_warning() {
+    let settings = YoinkSettings {
+        file_types: FileTypeSet::parse("rust,py").unwrap(),
+        file_types_not: FileTypeSet::parse("py").unwrap(),
+        ..YoinkSettings::default()
+    };
+    assert_eq!(rules(&settings), vec![("types-overlap", Severity::Warning)]);
+}
+
+#[test]
+fn types_not_alone_is_fine() {
+    let settings = YoinkSettings {
+        file_types_not: FileTypeSet::parse("py").unwrap(),
+        ..YoinkSettings::default()
+    };
+    assert!(check_settings(&settings).is_empty());
+}
+
+#[test]
+fn symlinks_without_mounts_is_a_warning() {
+    let settings = YoinkSettings {
+        include_symlinks: true,
+        ..YoinkSettings::default()
+    };
+    assert_eq!(
+        rules(&settings),
+        vec![("symlinks-without-mounts", Severity::Warning)]
+    );
+
+    let settings = YoinkSettings {
+        include_symlinks: true,
+        include_mounts: true,
+        ..YoinkSettings::default()
+    };
+    assert!(check_settings(&settings).is_empty());
+}
+
+#[test]
+fn same_match_colors_is_a_warning() {
+    let defaults = YoinkSettings::default();
+    let settings = YoinkSettings {
+        content_match_color: defaults.path_match_color,
+        ..defaults
+    };
+    assert_eq!(
+        rules(&settings),
+        vec![("same-match-colors", Severity::Warning)]
+    );
+}
+
+#[test]
+fn auto_refresh_warns_only_without_watch_support() {
+    let settings = YoinkSettings {
+        auto_refresh_secs: Some(2),
+        ..YoinkSettings::default()
+    };
+    let expected = if cfg!(feature = "watch") {
+        Vec::new()
+    } else {
+        vec![("auto-refresh-without-watch", Severity::Warning)]
+    };
+    assert_eq!(rules(&settings), expected);
+}
+
+#[test]
+fn watch_warns_only_without_watch_support() {
+    let settings = YoinkSettings {
+        watch: true,
+        ..YoinkSettings::default()
+    };
+    let expected = if cfg!(feature = "watch") {
+        Vec::new()
+    } else {
+        vec![("watch-without-watch-support", Severity::Warning)]
+    };
+    assert_eq!(rules(&settings), expected);
+}
+
+#[test]
+fn tracked_only_warns_only_without_git_support() {
+    let settings = YoinkSettings {
+        tracked_only: true,
+        ..YoinkSettings::default()
+    };
+    let expected = if cfg!(feature = "git") {
+        Vec::new()
+    } else {
+        vec![("tracked-without-git", Severity::Warning)]
+    };
+    assert_eq!(rules(&settings), expected);
+}
+
+#[test]
+fn archives_warns_only_without_archive_support() {
+    let settings = YoinkSettings {
+        archives: true,
+        ..YoinkSettings::default()
+    };
+    let expected = if cfg!(feature = "archives") {
+        Vec::new()
+    } else {
+        vec![("archives-without-archive-support", Severity::Warning)]
+    };
+    assert_eq!(rules(&settings), expected);
+}
+
+#[test]
+fn errors_sort_before_warnings() {
+    let settings = YoinkSettings {
+        include_symlinks: true,
+        file_types: FileTypeSet::parse("py").unwrap(),
+        file_types_not: FileTypeSet::parse("py").unwrap(),
+        ..YoinkSettings::default()
+    };
+    assert_eq!(
+        rules(&settings),
+        vec![
+            ("types-all-excluded", Severity::Error),
+            ("symlinks-without-mounts", Severity::Warning),
+        ]
+    );
+}
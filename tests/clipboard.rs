@@ -0,0 +1,15 @@
+use yoink::clipboard::{base64_encode, osc52_sequence};
+
+#[test]
+fn encodes_base64_with_padding() {
+    assert_eq!(base64_encode(b""), "");
+    assert_eq!(base64_encode(b"f"), "Zg==");
+    assert_eq!(base64_encode(b"fo"), "Zm8=");
+    assert_eq!(base64_encode(b"foo"), "Zm9v");
+    assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+}
+
+#[test]
+fn wraps_text_in_osc52() {
+    assert_eq!(osc52_sequence("hi"), "\x1b]52;c;aGk=\x07");
+}
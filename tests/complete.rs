@@ -0,0 +1,114 @@
+use std::fs;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use tempfile::tempdir;
+use yoink::complete::{
+    candidates, completion_script, filter_prefix, history_queries, parse_completion_kind,
+    saved_roots, CompletionKind, Shell,
+};
+
+fn env_lock() -> &'static Mutex<()> {
+    static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+    LOCK.get_or_init(|| Mutex::new(()))
+}
+
+fn with_state_home(test_fn: impl FnOnce(&Path)) {
+    let _guard = env_lock().lock().expect("env lock");
+    let state = tempdir().expect("temp state");
+    std::env::set_var("XDG_STATE_HOME", state.path());
+    test_fn(&state.path().join("yoink"));
+    std::env::remove_var("XDG_STATE_HOME");
+}
+
+fn complete(kind: CompletionKind, prefix: &str) -> Vec<String> {
+    filter_prefix(candidates(kind), prefix)
+}
+
+#[test]
+fn parses_kind_names() {
+    assert_eq!(
+        parse_completion_kind("search"),
+        Some(CompletionKind::Search)
+    );
+    assert_eq!(parse_completion_kind("types"), Some(CompletionKind::Type));
+    assert_eq!(parse_completion_kind("Root"), Some(CompletionKind::Root));
+    assert_eq!(parse_completion_kind("key"), Some(CompletionKind::Key));
+    assert_eq!(parse_completion_kind("profile"), None);
+}
+
+#[test]
+fn searches_come_from_history_newest_first_without_repeats() {
+    with_state_home(|dir| {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join("history"), "foo bar\nfoo\n\nbaz\nfoo bar\n").unwrap();
+
+        assert_eq!(
+            complete(CompletionKind::Search, ""),
+            vec!["foo bar", "baz", "foo"]
+        );
+        assert_eq!(
+            complete(CompletionKind::Search, "foo"),
+            vec!["foo bar", "foo"]
+        );
+        assert!(complete(CompletionKind::Search, "q").is_empty());
+    });
+}
+
+#[test]
+fn missing_stores_complete_nothing() {
+    with_state_home(|dir| {
+        assert!(complete(CompletionKind::Search, "").is_empty());
+        assert!(complete(CompletionKind::Root, "").is_empty());
+        assert!(history_queries(&dir.join("history")).is_empty());
+        assert!(saved_roots(&dir.join("prefs.toml")).is_empty());
+    });
+}
+
+#[test]
+fn roots_come_from_saved_prefs() {
+    with_state_home(|dir| {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(
+            dir.join("prefs.toml"),
+            "[\"/work/app\"]\ninclude_hidden = true\n\n[\"/home/me/notes\"]\nsort_mode = \"alphabetical\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            complete(CompletionKind::Root, ""),
+            vec!["/home/me/notes", "/work/app"]
+        );
+        assert_eq!(complete(CompletionKind::Root, "/work"), vec!["/work/app"]);
+    });
+}
+
+#[test]
+fn keys_are_setting_names_filtered_by_prefix() {
+    let keys = complete(CompletionKind::Key, "max_d");
+    assert_eq!(keys, vec!["max_depth"]);
+    assert!(candidates(CompletionKind::Key).contains(&"respect_gitignore".to_string()));
+}
+
+#[test]
+fn types_are_rg_type_names_filtered_by_prefix() {
+    let types = complete(CompletionKind::Type, "r");
+    assert!(types.contains(&"rust".to_string()), "{types:?}");
+    assert!(types.iter().all(|name| name.starts_with('r')));
+    assert!(candidates(CompletionKind::Type).contains(&"py".to_string()));
+}
+
+#[test]
+fn every_script_calls_back_into_complete() {
+    for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+        let script = completion_script(shell);
+        for kind in ["search", "type", "root"] {
+            assert!(
+                script.contains(&format!("yoink __complete {kind} --")),
+                "{shell:?} script lacks the {kind} hook:\n{script}"
+            );
+        }
+    }
+    assert!(completion_script(Shell::Bash).contains("complete -o default -F _yoink yoink"));
+    assert!(completion_script(Shell::Zsh).starts_with("#compdef yoink"));
+}
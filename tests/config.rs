@@ -0,0 +1,275 @@
+use std::fs;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use tempfile::tempdir;
+use yoink::check::run_check;
+use yoink::config::{
+    config_sources, configs_equivalent, editor_command, format_config_show, format_ignore_stats,
+    legacy_config_notice, legacy_notice_marker, migrate_config_file, take_legacy_config_notice,
+    yoinkignore_path, yoinkignore_paths, ConfigKind, ConfigSource,
+};
+use yoink::search::{
+    load_settings, load_settings_from, GlobOrigin, IgnorePatternStat, SettingOverrides, SortMode,
+};
+
+fn env_lock() -> &'static Mutex<()> {
+    static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+    LOCK.get_or_init(|| Mutex::new(()))
+}
+
+fn with_home(test_fn: impl FnOnce(&Path, &Path)) {
+    let _guard = env_lock().lock().expect("env lock");
+    let home = tempdir().expect("temp home");
+    let xdg = home.path().join("xdg");
+
+    std::env::remove_var("YOINKIGNORE_PATH");
+    std::env::set_var("HOME", home.path());
+    std::env::set_var("XDG_CONFIG_HOME", &xdg);
+    test_fn(home.path(), &xdg);
+    std::env::remove_var("XDG_CONFIG_HOME");
+}
+
+#[test]
+fn prefers_xdg_ignore_over_legacy_file() {
+    with_home(|home, xdg| {
+        fs::write(home.join(".yoinkignore"), "sort_mode=alphabetical\n").expect("write legacy");
+        assert_eq!(yoinkignore_path(), Some(home.join(".yoinkignore")));
+        assert!(legacy_config_notice().is_some());
+
+        fs::create_dir_all(xdg.join("yoink")).expect("mkdir xdg");
+        fs::write(xdg.join("yoink/ignore"), "sort_mode=depth\n").expect("write xdg");
+        assert_eq!(yoinkignore_path(), Some(xdg.join("yoink/ignore")));
+        assert!(legacy_config_notice().is_none());
+        assert_eq!(
+            load_settings().expect("settings").sort_mode,
+            SortMode::Depth
+        );
+    });
+}
+
+#[test]
+fn legacy_notice_is_shown_once() {
+    with_home(|home, xdg| {
+        std::env::set_var("XDG_STATE_HOME", home.join("state"));
+        assert_eq!(take_legacy_config_notice(), None);
+
+        fs::write(home.join(".yoinkignore"), "").expect("write legacy");
+        let notice = take_legacy_config_notice().expect("first launch");
+        assert!(notice.contains("yoink config migrate"), "{notice}");
+        assert_eq!(
+            legacy_notice_marker(),
+            Some(home.join("state/yoink/legacy-config-notice"))
+        );
+        assert!(home.join("state/yoink/legacy-config-notice").exists());
+        assert_eq!(take_legacy_config_notice(), None);
+        // The notice itself still applies; only its printing is once.
+        assert!(legacy_config_notice().is_some());
+        assert!(!xdg.join("yoink/ignore").exists());
+        std::env::remove_var("XDG_STATE_HOME");
+    });
+}
+
+#[test]
+fn env_override_wins_over_everything() {
+    with_home(|home, xdg| {
+        fs::create_dir_all(xdg.join("yoink")).expect("mkdir xdg");
+        fs::write(xdg.join("yoink/ignore"), "").expect("write xdg");
+        fs::write(xdg.join("yoink/config.toml"), "show_timings = true\n").expect("write toml");
+        let custom = home.join("custom");
+        fs::write(&custom, "").expect("write custom");
+
+        std::env::set_var("YOINKIGNORE_PATH", &custom);
+        assert_eq!(yoinkignore_path(), Some(custom.clone()));
+        assert_eq!(config_sources(None), vec![ConfigSource::ignore(custom)]);
+        std::env::remove_var("YOINKIGNORE_PATH");
+    });
+}
+
+#[test]
+fn reads_settings_from_config_toml_before_ignore_file() {
+    with_home(|_, xdg| {
+        fs::create_dir_all(xdg.join("yoink")).expect("mkdir xdg");
+        fs::write(
+            xdg.join("yoink/config.toml"),
+            "# settings\nshow_timings = true\nsort_mode = \"alphabetical\"\n[section]\n",
+        )
+        .expect("write toml");
+        fs::write(xdg.join("yoink/ignore"), "sort_mode=depth\nbuild/**\n").expect("write ignore");
+
+        let sources = config_sources(None);
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[0].kind, ConfigKind::Settings);
+        assert_eq!(sources[1].kind, ConfigKind::Ignore);
+
+        let settings = load_settings().expect("settings");
+        assert!(settings.show_timings);
+        assert_eq!(settings.sort_mode, SortMode::Depth);
+        assert!(settings.globs.contains(&"build/**".to_string()));
+        assert!(!settings.globs.iter().any(|glob| glob.contains("section")));
+    });
+}
+
+#[test]
+fn config_toml_reads_tables_quotes_and_comments_and_rejects_unknown_keys() {
+    with_home(|home, xdg| {
+        fs::create_dir_all(xdg.join("yoink")).expect("mkdir xdg");
+        let toml = xdg.join("yoink/config.toml");
+        fs::write(
+            &toml,
+            "show_timings = true # for now\nsort_mode = 'alphabetical'\n\n[search]\n\
+             todo = \"TODO # kept\" # dropped\n[preprocess] # extractors\npdf = pdftotext {} -  # text\n",
+        )
+        .expect("write toml");
+
+        let settings = load_settings().expect("settings");
+        assert!(settings.show_timings);
+        assert_eq!(settings.sort_mode, SortMode::Alphabetical);
+        assert_eq!(settings.saved_searches["todo"], "TODO # kept");
+        assert_eq!(settings.preprocessors["pdf"], "pdftotext {} -");
+
+        for (content, message) in [
+            (
+                "show_timings = true\nsort_mdoe = depth\n",
+                "config.toml:2: sort_mdoe",
+            ),
+            (
+                "[display]\nshow_timings = true\n",
+                "config.toml:2: display.show_timings",
+            ),
+            ("build/**\n", "expected key = value in"),
+            ("sort_mode = 'depth\n", "unterminated value in"),
+        ] {
+            fs::write(&toml, content).expect("write toml");
+            let error = format!("{:#}", load_settings().expect_err(content));
+            assert!(error.contains(message), "{error}");
+            assert!(run_check(home, SettingOverrides::default()).is_err());
+        }
+    });
+}
+
+#[test]
+fn migrates_legacy_file_preserving_comments() {
+    with_home(|home, xdg| {
+        let legacy = home.join(".yoinkignore");
+        let content = "# my settings\ninclude_hidden=true\n\n# junk dirs\nbuild/**\n";
+        fs::write(&legacy, content).expect("write legacy");
+        let target = xdg.join("yoink/ignore");
+
+        migrate_config_file(&legacy, &target).expect("migrate");
+        assert_eq!(fs::read_to_string(&target).expect("read target"), content);
+        assert!(configs_equivalent(&legacy, &target).expect("equivalence"));
+        assert!(migrate_config_file(&legacy, &target).is_err());
+    });
+}
+
+#[test]
+fn detects_configs_that_parse_differently() {
+    with_home(|home, _| {
+        let a = home.join("a");
+        let b = home.join("b");
+        fs::write(&a, "include_hidden=true\nbuild/**\n").expect("write a");
+        fs::write(&b, "# same but reordered\nbuild/**\ninclude_hidden = yes\n").expect("write b");
+        assert!(configs_equivalent(&a, &b).expect("equivalent"));
+
+        fs::write(&b, "build/**\n").expect("rewrite b");
+        assert!(!configs_equivalent(&a, &b).expect("different"));
+    });
+}
+
+#[test]
+fn formats_ignore_stats_and_flags_unused_patterns() {
+    let stats = vec![
+        IgnorePatternStat {
+            pattern: ".git/**".to_string(),
+            origin: GlobOrigin::default(),
+            hits: 3,
+        },
+        IgnorePatternStat {
+            pattern: "*.bak".to_string(),
+            origin: GlobOrigin {
+                file: Some(Path::new("/home/me/.yoinkignore").to_path_buf()),
+                line: 7,
+                ..GlobOrigin::default()
+            },
+            hits: 0,
+        },
+    ];
+
+    assert_eq!(
+        format_ignore_stats(&stats),
+        "     3  .git/**  built-in\n     0  *.bak    /home/me/.yoinkignore:7  (no hits)\n"
+    );
+}
+
+#[test]
+fn config_show_labels_smart_defaults() {
+    let dir = tempdir().expect("tempdir");
+    let ignore = dir.path().join("ignore");
+    fs::write(&ignore, "*.bak\n").expect("write ignore");
+
+    let settings = load_settings_from(&[ConfigSource::ignore(ignore.clone())]).expect("settings");
+    let shown = format_config_show(&settings);
+    assert!(shown.starts_with("smart_defaults = true (set v1)\n"));
+    assert!(shown.contains("\n  .git/**            built-in\n"));
+    assert!(shown.contains("\n  target/**          smart default\n"));
+    assert!(shown.contains(&format!("\n  *.bak              {}:1\n", ignore.display())));
+    assert!(shown.contains("content not searched:\n  *.lock             smart default\n"));
+
+    fs::write(&ignore, "smart_defaults=false\n").expect("write ignore");
+    let settings = load_settings_from(&[ConfigSource::ignore(ignore)]).expect("settings");
+    let shown = format_config_show(&settings);
+    assert!(shown.starts_with("smart_defaults = false\n"));
+    assert!(!shown.contains("smart default"));
+    assert!(shown.ends_with("content not searched:\n  (none)\n"));
+}
+
+#[test]
+fn editor_command_prefers_visual_then_editor_then_vi() {
+    assert_eq!(
+        editor_command(Some("code -w"), Some("nano")),
+        vec!["code", "-w"]
+    );
+    assert_eq!(editor_command(Some("  "), Some("nano")), vec!["nano"]);
+    assert_eq!(editor_command(None, Some("hx")), vec!["hx"]);
+    assert_eq!(editor_command(None, None), vec!["vi"]);
+}
+
+#[test]
+fn project_ignore_file_follows_the_global_one() {
+    with_home(|home, xdg| {
+        fs::create_dir_all(xdg.join("yoink")).expect("mkdir xdg");
+        fs::write(xdg.join("yoink/ignore"), "").expect("write xdg");
+        let project = home.join("project");
+        fs::create_dir_all(&project).expect("mkdir project");
+
+        // No local file yet: only the global one is considered.
+        assert_eq!(
+            yoinkignore_paths(Some(&project)),
+            vec![xdg.join("yoink/ignore")]
+        );
+
+        fs::write(project.join(".yoinkignore"), "gen/**\n").expect("write local");
+        assert_eq!(
+            yoinkignore_paths(Some(&project)),
+            vec![xdg.join("yoink/ignore"), project.join(".yoinkignore")]
+        );
+        assert_eq!(
+            config_sources(Some(&project)).last(),
+            Some(&ConfigSource::ignore(project.join(".yoinkignore")))
+        );
+        assert_eq!(yoinkignore_paths(None), vec![xdg.join("yoink/ignore")]);
+    });
+}
+
+#[test]
+fn legacy_global_file_is_not_read_twice_from_home() {
+    with_home(|home, _| {
+        fs::write(home.join(".yoinkignore"), "build/**\n").expect("write legacy");
+
+        assert_eq!(
+            yoinkignore_paths(Some(home)),
+            vec![home.join(".yoinkignore")]
+        );
+    });
+}
@@ -0,0 +1,128 @@
+use std::fs;
+use std::path::PathBuf;
+
+use tempfile::tempdir;
+use yoink::diff::{diff_candidates, format_diff, format_diff_json, DiffEntry, MatchKind};
+use yoink::search::{build_candidates, Candidate};
+
+fn candidate(path: &str, path_match: bool, content_match: bool) -> Candidate {
+    Candidate {
+        path: PathBuf::from(path),
+        is_dir: false,
+        path_match,
+        content_match,
+        demoted: false,
+        aliases: Vec::new(),
+        is_binary: false,
+        modified: None,
+        size: None,
+        fuzzy_match: false,
+        match_count: 0,
+    }
+}
+
+fn paths(entries: &[DiffEntry]) -> Vec<String> {
+    entries
+        .iter()
+        .map(|entry| entry.path.to_string_lossy().to_string())
+        .collect()
+}
+
+#[test]
+fn splits_paths_into_only_a_only_b_and_both() {
+    let a = vec![
+        candidate("legacy.rs", false, true),
+        candidate("shared.rs", false, true),
+        candidate("old_api.md", true, false),
+    ];
+    let b = vec![
+        candidate("shared.rs", false, true),
+        candidate("migrated.rs", false, true),
+        candidate("old_api.md", false, true),
+    ];
+
+    let diff = diff_candidates(&a, &b, false);
+    assert_eq!(paths(&diff.only_a), vec!["legacy.rs"]);
+    assert_eq!(paths(&diff.only_b), vec!["migrated.rs"]);
+    assert_eq!(paths(&diff.both), vec!["old_api.md", "shared.rs"]);
+    assert_eq!(diff.both[0].a, Some(MatchKind::Path));
+    assert_eq!(diff.both[0].b, Some(MatchKind::Content));
+}
+
+#[test]
+fn content_only_drops_path_matches_before_comparing() {
+    let a = vec![
+        candidate("old_api.md", true, false),
+        candidate("mixed.rs", true, true),
+    ];
+    let b = vec![
+        candidate("old_api.md", false, true),
+        candidate("mixed.rs", false, true),
+    ];
+
+    let diff = diff_candidates(&a, &b, true);
+    assert!(diff.only_a.is_empty());
+    assert_eq!(paths(&diff.only_b), vec!["old_api.md"]);
+    assert_eq!(paths(&diff.both), vec!["mixed.rs"]);
+    assert_eq!(diff.both[0].a, Some(MatchKind::Both));
+}
+
+#[test]
+fn formats_sections_with_counts() {
+    let a = vec![
+        candidate("legacy.rs", false, true),
+        candidate("both.rs", true, true),
+    ];
+    let b = vec![candidate("both.rs", false, true)];
+    let diff = diff_candidates(&a, &b, false);
+
+    assert_eq!(
+        format_diff(&diff, "old_api", "new_api"),
+        "only A (old_api): 1\n  legacy.rs  (content)\n\n\
+         only B (new_api): 0\n\n\
+         both: 1\n  both.rs  (A: path+content, B: content)\n"
+    );
+}
+
+#[test]
+fn formats_json_sections() {
+    let a = vec![candidate("src/\"q\".rs", false, true)];
+    let diff = diff_candidates(&a, &[], false);
+
+    assert_eq!(
+        format_diff_json(&diff, "old", "new"),
+        "{\"query_a\":\"old\",\"query_b\":\"new\",\"counts\":{\"only_a\":1,\"only_b\":0,\"both\":0},\
+         \"only_a\":[{\"path\":\"src/\\\"q\\\".rs\",\"is_dir\":false,\"a\":\"content\",\"b\":null}],\
+         \"only_b\":[],\"both\":[]}\n"
+    );
+}
+
+#[test]
+fn diffs_two_searches_over_a_fixture() {
+    let home = tempdir().expect("temp home");
+    let config_path = home.path().join(".yoinkignore");
+    fs::write(&config_path, ".git/**\n").expect("write config");
+    std::env::set_var("YOINKIGNORE_PATH", &config_path);
+
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::write(root.join("legacy.rs"), "old_api();\n").expect("write");
+    fs::write(root.join("migrated.rs"), "new_api();\n").expect("write");
+    fs::write(root.join("halfway.rs"), "old_api();\nnew_api();\n").expect("write");
+    fs::write(root.join("old_api_notes.md"), "see new_api\n").expect("write");
+    fs::write(root.join("unrelated.txt"), "nothing\n").expect("write");
+
+    let a = build_candidates("old_api", root).expect("search a");
+    let b = build_candidates("new_api", root).expect("search b");
+
+    let diff = diff_candidates(&a, &b, false);
+    assert_eq!(paths(&diff.only_a), vec!["legacy.rs"]);
+    assert_eq!(paths(&diff.only_b), vec!["migrated.rs"]);
+    assert_eq!(paths(&diff.both), vec!["halfway.rs", "old_api_notes.md"]);
+
+    let diff = diff_candidates(&a, &b, true);
+    assert_eq!(paths(&diff.only_b), vec!["migrated.rs", "old_api_notes.md"]);
+    assert_eq!(paths(&diff.both), vec!["halfway.rs"]);
+
+    std::env::remove_var("YOINKIGNORE_PATH");
+}
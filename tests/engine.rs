@@ -0,0 +1,264 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use tempfile::tempdir;
+use which::which;
+use yoink::search::{build_candidates, build_search_entries, load_settings_for, ContentEngine};
+
+fn env_lock() -> &'static Mutex<()> {
+    static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+    LOCK.get_or_init(|| Mutex::new(()))
+}
+
+fn with_system_config(config_content: &str, test_fn: impl FnOnce(&Path)) {
+    let _guard = env_lock().lock().expect("env lock");
+    let temp_home = tempdir().expect("temp home");
+    let config_path = temp_home.path().join(".yoinkignore");
+    fs::write(&config_path, config_content).expect("write config");
+
+    std::env::set_var("YOINKIGNORE_PATH", &config_path);
+    test_fn(temp_home.path());
+    std::env::remove_var("YOINKIGNORE_PATH");
+}
+
+type CandidateRow = (PathBuf, bool, bool, bool);
+type EntryRow = (PathBuf, Option<usize>, Option<usize>);
+
+/// Candidates and occurrence rows for `query` under `config` plus `engine`.
+fn search_with(
+    engine: &str,
+    config: &str,
+    query: &str,
+    root: &Path,
+) -> (Vec<CandidateRow>, Vec<EntryRow>) {
+    let mut rows = None;
+    with_system_config(&format!("{config}engine = {engine}\n"), |_| {
+        let candidates = build_candidates(query, root)
+            .expect("build candidates")
+            .into_iter()
+            .map(|candidate| {
+                (
+                    candidate.path,
+                    candidate.is_dir,
+                    candidate.path_match,
+                    candidate.content_match,
+                )
+            })
+            .collect();
+        let entries = build_search_entries(query, root)
+            .expect("build search entries")
+            .into_iter()
+            .map(|entry| (entry.path, entry.line, entry.column))
+            .collect();
+        rows = Some((candidates, entries));
+    });
+    rows.expect("search ran")
+}
+
+/// Runs `query` with rg and with the internal engine, asserts both list the
+/// same rows, and returns the internal engine's content-matched paths.
+fn assert_parity(config: &str, query: &str, root: &Path) -> Vec<String> {
+    let (internal_candidates, internal_entries) = search_with("internal", config, query, root);
+    // Without rg the internal engine is still held to each test's expected paths.
+    if which("rg").is_ok() {
+        let (rg_candidates, rg_entries) = search_with("rg", config, query, root);
+        assert_eq!(
+            internal_candidates, rg_candidates,
+            "candidates for {query:?}"
+        );
+        assert_eq!(internal_entries, rg_entries, "entries for {query:?}");
+    }
+    internal_candidates
+        .into_iter()
+        .filter(|(_, _, _, content_match)| *content_match)
+        .map(|(path, ..)| path.to_string_lossy().to_string())
+        .collect()
+}
+
+fn write_tree(root: &Path) {
+    fs::create_dir_all(root.join("src/nested")).expect("mkdir");
+    fs::write(
+        root.join("src/lib.rs"),
+        "fn eject_reasons() {}\n// EjectReasons here\n",
+    )
+    .expect("write");
+    fs::write(
+        root.join("src/nested/mod.rs"),
+        "let reasons = eject();\nlet more = reasons;\n",
+    )
+    .expect("write");
+    fs::write(
+        root.join("notes.txt"),
+        "ejected\nnothing else\neject_reasons again\n",
+    )
+    .expect("write");
+    fs::write(root.join("plain.md"), "no match in this one\n").expect("write");
+}
+
+#[test]
+fn parses_engine_setting() {
+    with_system_config("engine = internal\n", |_| {
+        let dir = tempdir().expect("tempdir");
+        let settings = load_settings_for(dir.path()).expect("settings");
+        assert_eq!(settings.engine, ContentEngine::Internal);
+        assert!(settings.internal_engine());
+    });
+    with_system_config("engine = rg\n", |_| {
+        let dir = tempdir().expect("tempdir");
+        assert!(!load_settings_for(dir.path())
+            .expect("settings")
+            .internal_engine());
+    });
+    with_system_config("engine = grep\n", |_| {
+        let dir = tempdir().expect("tempdir");
+        let error = load_settings_for(dir.path()).expect_err("bad engine");
+        assert!(
+            format!("{error:#}").contains("invalid engine value"),
+            "{error:#}"
+        );
+    });
+}
+
+#[test]
+fn matches_rg_on_plain_and_regex_queries() {
+    let dir = tempdir().expect("tempdir");
+    write_tree(dir.path());
+
+    let mut paths = assert_parity("", "eject_reasons", dir.path());
+    paths.sort();
+    assert_eq!(paths, ["notes.txt", "src/lib.rs"]);
+    assert_eq!(
+        assert_parity("", "^let more", dir.path()),
+        ["src/nested/mod.rs"]
+    );
+    assert_parity("", "eject\\w*", dir.path());
+}
+
+#[test]
+fn matches_rg_on_case_and_word_settings() {
+    let dir = tempdir().expect("tempdir");
+    write_tree(dir.path());
+
+    let mut paths = assert_parity("case_mode = insensitive\n", "ejectreasons", dir.path());
+    paths.sort();
+    assert_eq!(paths, ["src/lib.rs"]);
+    assert_parity("case_mode = smart\n", "eject", dir.path());
+    assert_parity("case_mode = smart\n", "Eject", dir.path());
+    assert_eq!(
+        assert_parity("word_boundaries = true\n", "eject", dir.path()),
+        ["src/nested/mod.rs"]
+    );
+}
+
+#[test]
+fn matches_rg_on_and_terms() {
+    let dir = tempdir().expect("tempdir");
+    write_tree(dir.path());
+
+    assert_eq!(
+        assert_parity("and_terms = true\n", "ejected again", dir.path()),
+        ["notes.txt"]
+    );
+}
+
+#[test]
+fn honors_hidden_and_glob_excludes() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::create_dir_all(root.join(".hidden")).expect("mkdir");
+    fs::create_dir_all(root.join("skipped")).expect("mkdir");
+    fs::write(root.join(".hidden/secret.txt"), "needle\n").expect("write");
+    fs::write(root.join("skipped/file.txt"), "needle\n").expect("write");
+    fs::write(root.join("kept.txt"), "needle\n").expect("write");
+
+    assert_eq!(assert_parity("skipped/**\n", "needle", root), ["kept.txt"]);
+    let mut paths = assert_parity("skipped/**\ninclude_hidden = true\n", "needle", root);
+    paths.sort();
+    assert_eq!(paths, [".hidden/secret.txt", "kept.txt"]);
+}
+
+#[test]
+fn skips_binary_and_oversized_files() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::write(root.join("blob.bin"), b"needle\0\x01\x02").expect("write");
+    fs::write(
+        root.join("large.txt"),
+        format!("needle\n{}", "x".repeat(4096)),
+    )
+    .expect("write");
+    fs::write(root.join("small.txt"), "needle\n").expect("write");
+
+    let (candidates, _) = search_with("internal", "", "needle", root);
+    let content: Vec<_> = candidates
+        .iter()
+        .filter(|row| row.3)
+        .map(|row| row.0.clone())
+        .collect();
+    assert!(!content.contains(&PathBuf::from("blob.bin")), "{content:?}");
+    assert!(content.contains(&PathBuf::from("large.txt")), "{content:?}");
+
+    assert_eq!(
+        assert_parity("max_filesize = 1K\n", "needle", root),
+        ["small.txt"]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn skips_unreadable_files() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    let locked = root.join("locked.txt");
+    fs::write(&locked, "needle\n").expect("write");
+    fs::write(root.join("open.txt"), "needle\n").expect("write");
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).expect("chmod");
+    // Root reads through any mode, so there is nothing to skip.
+    let unreadable = fs::read(&locked).is_err();
+
+    let (candidates, entries) = search_with("internal", "", "needle", root);
+    let content: Vec<_> = candidates
+        .iter()
+        .filter(|row| row.3)
+        .map(|row| row.0.clone())
+        .collect();
+    assert!(content.contains(&PathBuf::from("open.txt")));
+    assert_eq!(content.contains(&PathBuf::from("locked.txt")), !unreadable);
+    assert!(entries
+        .iter()
+        .any(|(path, line, _)| path == Path::new("open.txt") && *line == Some(1)));
+
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o644)).expect("chmod back");
+}
+
+#[cfg(unix)]
+#[test]
+fn follows_in_root_directory_links_like_rg() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::create_dir_all(root.join("src/nested")).expect("mkdir");
+    fs::write(root.join("src/nested/needle.rs"), "fn needle() {}\n").expect("write");
+    std::os::unix::fs::symlink("nested", root.join("src/mirror")).expect("symlink");
+
+    assert_parity("include_symlinks = true\n", "needle", root);
+    with_system_config("include_symlinks = true\nengine = internal\n", |_| {
+        let candidates = build_candidates("needle", root).expect("build candidates");
+        let kept = candidates
+            .iter()
+            .find(|candidate| candidate.content_match)
+            .expect("content match");
+        let mut paths = kept.aliases.clone();
+        paths.push(kept.path.clone());
+        paths.sort();
+        assert_eq!(
+            paths,
+            [
+                PathBuf::from("src/mirror/needle.rs"),
+                PathBuf::from("src/nested/needle.rs")
+            ]
+        );
+    });
+}
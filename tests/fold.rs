@@ -0,0 +1,114 @@
+use regex::Regex;
+use yoink::fold::{fold_query, fold_text, parse_unicode_fold_setting, UnicodeFold};
+use yoink::search::highlight_folded_matches;
+use yoink::term::ColorMode;
+
+#[test]
+fn parses_unicode_fold_values() {
+    assert_eq!(
+        parse_unicode_fold_setting("true"),
+        Some(UnicodeFold::Accents)
+    );
+    assert_eq!(
+        parse_unicode_fold_setting("accents"),
+        Some(UnicodeFold::Accents)
+    );
+    assert_eq!(parse_unicode_fold_setting("Case"), Some(UnicodeFold::Case));
+    assert_eq!(parse_unicode_fold_setting("false"), Some(UnicodeFold::Off));
+    assert_eq!(parse_unicode_fold_setting("nfkd"), None);
+}
+
+#[test]
+fn folds_umlauts_in_precomposed_and_decomposed_form() {
+    assert_eq!(
+        fold_text("Über_notes.md", UnicodeFold::Accents).text,
+        "uber_notes.md"
+    );
+    assert_eq!(fold_text("U\u{308}ber", UnicodeFold::Accents).text, "uber");
+    assert_eq!(fold_text("Über", UnicodeFold::Case).text, "über");
+    assert_eq!(fold_text("Über", UnicodeFold::Off).text, "Über");
+}
+
+#[test]
+fn expands_sharp_s_and_ligatures() {
+    assert_eq!(fold_text("Straße", UnicodeFold::Case).text, "strasse");
+    assert_eq!(fold_text("STRAẞE", UnicodeFold::Case).text, "strasse");
+    assert_eq!(fold_text("ﬁle_ﬂow", UnicodeFold::Accents).text, "file_flow");
+    // Letters with no decomposition stay distinct.
+    assert_eq!(fold_text("Æsir_Øl", UnicodeFold::Accents).text, "æsir_øl");
+}
+
+#[test]
+fn handles_turkish_dotted_and_dotless_i() {
+    // `İ` lowercases to `i` + combining dot, which only accent stripping removes.
+    assert_eq!(
+        fold_text("İstanbul", UnicodeFold::Case).text,
+        "i\u{307}stanbul"
+    );
+    assert_eq!(fold_text("İstanbul", UnicodeFold::Accents).text, "istanbul");
+    // Dotless `ı` is its own letter, and `I` folds to ASCII `i` without a locale.
+    assert_eq!(fold_text("ılık", UnicodeFold::Accents).text, "ılık");
+    assert_eq!(fold_text("IŞIK", UnicodeFold::Accents).text, "isik");
+}
+
+#[test]
+fn maps_folded_ranges_back_to_original_characters() {
+    let folded = fold_text("Straße/İndex", UnicodeFold::Accents);
+    assert_eq!(folded.text, "strasse/index");
+
+    // "ss" comes from the two-byte `ß`.
+    let ss = folded.text.find("ss").expect("ss");
+    assert_eq!(folded.original_range(ss..ss + 2), 4..6);
+    // Half of an expansion still maps to the whole source character.
+    assert_eq!(folded.original_range(ss..ss + 1), 4..6);
+    // `İ` is two bytes in the source and one in the folded text.
+    let index = folded.text.find("index").expect("index");
+    assert_eq!(folded.original_range(index..index + 5), 8..14);
+    assert_eq!(folded.original_range(index..index), 8..8);
+}
+
+#[test]
+fn decomposed_marks_stay_with_their_base_letter() {
+    let original = "cafe\u{301}.txt";
+    let folded = fold_text(original, UnicodeFold::Accents);
+    assert_eq!(folded.text, "cafe.txt");
+    assert_eq!(folded.original_range(0..4), 0..6);
+}
+
+#[test]
+fn folds_query_literals_but_not_regex_syntax() {
+    assert_eq!(fold_query("Über", UnicodeFold::Accents), "uber");
+    assert_eq!(fold_query(r"\WÜber\D", UnicodeFold::Accents), r"\Wuber\D");
+    assert_eq!(fold_query(r"\p{Lu}É", UnicodeFold::Case), r"\p{Lu}é");
+    assert_eq!(fold_query("(?U)ÄB+", UnicodeFold::Accents), "(?U)ab+");
+    assert_eq!(fold_query("Über", UnicodeFold::Off), "Über");
+}
+
+#[test]
+fn highlights_original_spelling_of_folded_matches() {
+    let re = Regex::new("uber").expect("regex");
+    assert_eq!(
+        highlight_folded_matches(
+            "docs/Über_notes.md",
+            &re,
+            UnicodeFold::Accents,
+            ColorMode::Ansi
+        ),
+        "docs/\x1b[1;36mÜber\x1b[0m_notes.md"
+    );
+
+    let re = Regex::new("strasse").expect("regex");
+    assert_eq!(
+        highlight_folded_matches("Straße.txt", &re, UnicodeFold::Case, ColorMode::Ansi),
+        "\x1b[1;36mStraße\x1b[0m.txt"
+    );
+}
+
+#[test]
+fn plain_mode_marks_folded_matches_with_markers() {
+    let re = Regex::new("uber").expect("regex");
+    assert_eq!(
+        highlight_folded_matches("Über.md", &re, UnicodeFold::Accents, ColorMode::Plain),
+        ">>Über<<.md"
+    );
+}
@@ -0,0 +1,94 @@
+#![cfg(feature = "git")]
+
+use std::path::Path;
+
+use yoink::git::{parse_grep_line, GitFiles};
+use yoink::search::{read_git_grep_occurrences, OccurrenceBudget};
+
+#[test]
+fn parses_ls_files_output_with_parent_directories() {
+    let tracked = GitFiles::parse(b"src/a b.rs\0src/deep/mod.rs\0README.md\0");
+    assert!(tracked.contains(Path::new("src/a b.rs"), false));
+    assert!(tracked.contains(Path::new("README.md"), false));
+    assert!(tracked.contains(Path::new("src"), true));
+    assert!(tracked.contains(Path::new("src/deep"), true));
+    assert!(
+        !tracked.contains(Path::new("src"), false),
+        "a directory isn't a tracked file"
+    );
+    assert!(!tracked.contains(Path::new("src/new.rs"), false));
+    assert!(!tracked.contains(Path::new("docs"), true));
+}
+
+#[test]
+fn lists_changed_files_in_path_order() {
+    let changed = GitFiles::parse(b"src/b.rs\0a.rs\0src/a.rs\0");
+    assert_eq!(
+        changed.sorted_files(),
+        [
+            Path::new("a.rs"),
+            Path::new("src/a.rs"),
+            Path::new("src/b.rs")
+        ]
+    );
+    assert!(GitFiles::parse(b"").sorted_files().is_empty());
+}
+
+#[test]
+fn parses_git_grep_lines_into_occurrences() {
+    let (path, occurrence) = parse_grep_line(
+        b"v1.2:src/odd:name.rs\x003\x005\x00    let needle = 1;",
+        "v1.2",
+        0,
+        "...",
+    )
+    .expect("parsed");
+    assert_eq!(path, Path::new("src/odd:name.rs"));
+    assert_eq!((occurrence.line, occurrence.column), (3, 5));
+    assert_eq!(occurrence.snippet, "let needle = 1;");
+    assert!(!occurrence.long_line);
+
+    assert!(
+        parse_grep_line(b"HEAD:a.rs:1:1:needle", "HEAD", 0, "...").is_none(),
+        "fields need -z"
+    );
+    assert!(parse_grep_line(b"main:a.rs\x001\x001\x00needle", "HEAD", 0, "...").is_none());
+    assert!(parse_grep_line(b"HEAD:a.rs\x00one\x001\x00needle", "HEAD", 0, "...").is_none());
+}
+
+#[test]
+fn windows_long_git_grep_lines_around_the_match() {
+    let line = format!(
+        "HEAD:min.js\x001\x00301\x00{}needle{}",
+        "a".repeat(300),
+        "b".repeat(300)
+    );
+    let (_, occurrence) = parse_grep_line(line.as_bytes(), "HEAD", 100, "...").expect("parsed");
+    assert!(occurrence.long_line);
+    assert!(
+        occurrence.snippet.starts_with("...") && occurrence.snippet.ends_with("..."),
+        "{}",
+        occurrence.snippet
+    );
+    assert!(
+        occurrence.snippet.contains("needle"),
+        "{}",
+        occurrence.snippet
+    );
+    assert!(occurrence.snippet.len() < 200, "{}", occurrence.snippet);
+}
+
+#[test]
+fn git_grep_occurrences_keep_every_file_within_the_budget() {
+    let output = b"HEAD:a.rs\x001\x001\x00needle\nHEAD:a.rs\x002\x001\x00needle\nHEAD:b.rs\x007\x003\x00a needle\n";
+    let budget = OccurrenceBudget {
+        per_file: 1,
+        total: 10,
+        line_bytes: 1024,
+    };
+    let content = read_git_grep_occurrences(&output[..], "HEAD", &budget, 0, false).expect("read");
+    assert_eq!(content.files.len(), 2);
+    assert!(content.scan.truncated);
+    assert_eq!(content.scan.occurrences[Path::new("a.rs")].len(), 1);
+    assert_eq!(content.scan.occurrences[Path::new("b.rs")][0].line, 7);
+}
@@ -0,0 +1,104 @@
+use std::fs;
+use std::path::Path;
+
+use tempfile::tempdir;
+use yoink::gitignore::{split_nested_settings, GitIgnore, IgnoreFile, NestedIgnores};
+
+#[test]
+fn ignore_file_follows_gitignore_pattern_rules() {
+    let file = IgnoreFile::parse(
+        "# build output\n\ntarget/\n*.log\n!keep.log\n/dist\ndocs/*.tmp\n\\#literal\n",
+    );
+
+    assert_eq!(file.matched(Path::new("target"), true), Some(true));
+    assert_eq!(
+        file.matched(Path::new("crates/core/target"), true),
+        Some(true)
+    );
+    // `target/` only names directories.
+    assert_eq!(file.matched(Path::new("target"), false), None);
+    assert_eq!(file.matched(Path::new("logs/run.log"), false), Some(true));
+    assert_eq!(file.matched(Path::new("logs/keep.log"), false), Some(false));
+    // A leading slash anchors to the file's directory.
+    assert_eq!(file.matched(Path::new("dist"), true), Some(true));
+    assert_eq!(file.matched(Path::new("web/dist"), true), None);
+    // So does a slash in the middle, and `*` stops at separators.
+    assert_eq!(file.matched(Path::new("docs/a.tmp"), false), Some(true));
+    assert_eq!(file.matched(Path::new("docs/deep/a.tmp"), false), None);
+    assert_eq!(file.matched(Path::new("#literal"), false), Some(true));
+    assert_eq!(file.matched(Path::new("src/main.rs"), false), None);
+}
+
+#[test]
+fn trailing_spaces_are_trimmed_unless_escaped() {
+    let file = IgnoreFile::parse("notes.txt   \nodd\\ \n");
+
+    assert_eq!(file.matched(Path::new("notes.txt"), false), Some(true));
+    assert_eq!(file.matched(Path::new("odd "), false), Some(true));
+    assert_eq!(file.matched(Path::new("odd"), false), None);
+}
+
+#[test]
+fn nested_gitignores_override_their_parents() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::create_dir_all(root.join(".git/info")).expect("create .git");
+    fs::create_dir_all(root.join("app/sub")).expect("create app");
+    fs::write(root.join(".git/info/exclude"), "*.bak\n").expect("write exclude");
+    fs::write(root.join(".gitignore"), "*.log\n").expect("write root gitignore");
+    fs::write(root.join("app/.gitignore"), "!keep.log\nlocal/\n").expect("write nested gitignore");
+
+    let mut gitignore = GitIgnore::discover(root).expect("inside a repo");
+    assert!(gitignore.is_ignored(Path::new("run.log"), false));
+    assert!(gitignore.is_ignored(Path::new("app/sub/run.log"), false));
+    assert!(!gitignore.is_ignored(Path::new("app/keep.log"), false));
+    assert!(gitignore.is_ignored(Path::new("keep.log"), false));
+    assert!(gitignore.is_ignored(Path::new("app/sub/local"), true));
+    assert!(!gitignore.is_ignored(Path::new("local"), true));
+    assert!(gitignore.is_ignored(Path::new("old.bak"), false));
+
+    // Rules are still read from the repository root when the walk starts below it.
+    let mut from_app = GitIgnore::discover(&root.join("app")).expect("inside a repo");
+    assert!(from_app.is_ignored(Path::new("sub/run.log"), false));
+    assert!(!from_app.is_ignored(Path::new("keep.log"), false));
+}
+
+#[test]
+fn no_repository_means_no_gitignore() {
+    let dir = tempdir().expect("tempdir");
+    fs::write(dir.path().join(".gitignore"), "*\n").expect("write gitignore");
+
+    assert!(GitIgnore::discover(dir.path()).is_none());
+}
+
+#[test]
+fn nested_yoinkignores_govern_their_own_subtree() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::create_dir_all(root.join("team/deep")).expect("create dirs");
+    fs::write(root.join(".yoinkignore"), "*.rs\n").expect("write root file");
+    fs::write(
+        root.join("team/.yoinkignore"),
+        "/build\n*.snap\nmax_depth = 1\n",
+    )
+    .expect("write team file");
+    fs::write(root.join("team/deep/.yoinkignore"), "!keep.snap\n").expect("write deep file");
+
+    let mut nested = NestedIgnores::new(root);
+    // The root's file is config, not a nested ignore.
+    assert!(!nested.is_ignored(Path::new("main.rs"), false));
+    assert!(nested.is_ignored(Path::new("team/build"), true));
+    assert!(!nested.is_ignored(Path::new("team/deep/build"), true));
+    assert!(nested.is_ignored(Path::new("team/a.snap"), false));
+    assert!(nested.is_ignored(Path::new("team/deep/a.snap"), false));
+    assert!(!nested.is_ignored(Path::new("team/deep/keep.snap"), false));
+    assert!(!nested.is_ignored(Path::new("a.snap"), false));
+    // A match rg found under an ignored directory is hidden with it.
+    assert!(nested.hides(Path::new("team/build/out.txt")));
+    assert!(!nested.hides(Path::new("team/builder/out.txt")));
+
+    let (globs, settings) =
+        split_nested_settings("/build\nmax_depth = 1\nsearch.todo = a=b\nweird=name\n");
+    assert_eq!(globs, "/build\n");
+    assert_eq!(settings, ["max_depth", "search.todo", "weird"]);
+}
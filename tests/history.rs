@@ -0,0 +1,131 @@
+use std::fs;
+use std::path::Path;
+
+use tempfile::tempdir;
+use yoink::history::{
+    history_args, last_query, parse_fzf_version, parse_history, prepare_history, record_query,
+    render_history, FzfVersion,
+};
+
+#[test]
+fn parses_fzf_version_output() {
+    assert_eq!(
+        parse_fzf_version("0.44.1 (brew)\n"),
+        Some(FzfVersion(0, 44, 1))
+    );
+    assert_eq!(
+        parse_fzf_version("0.20.0 (c9f16b6)"),
+        Some(FzfVersion(0, 20, 0))
+    );
+    assert_eq!(parse_fzf_version("0.9.13"), Some(FzfVersion(0, 9, 13)));
+    assert_eq!(parse_fzf_version("0.10"), Some(FzfVersion(0, 10, 0)));
+    assert_eq!(
+        parse_fzf_version("0.54.0-devel"),
+        Some(FzfVersion(0, 54, 0))
+    );
+    assert_eq!(parse_fzf_version(""), None);
+    assert_eq!(parse_fzf_version("fzf: command not found"), None);
+}
+
+#[test]
+fn history_flags_need_a_supporting_fzf() {
+    let path = Path::new("/state/yoink/history");
+    let args = history_args(Some(FzfVersion(0, 44, 1)), path, 500);
+    assert_eq!(
+        args,
+        vec![
+            "--history=/state/yoink/history",
+            "--history-size=500",
+            "--bind",
+            "ctrl-p:previous-history,alt-n:next-history",
+        ]
+    );
+    assert_eq!(history_args(Some(FzfVersion(0, 10, 0)), path, 500).len(), 4);
+
+    assert!(history_args(Some(FzfVersion(0, 9, 13)), path, 500).is_empty());
+    assert!(history_args(None, path, 500).is_empty());
+    assert!(history_args(Some(FzfVersion(0, 44, 1)), path, 0).is_empty());
+}
+
+#[test]
+fn store_reads_and_writes_fzfs_line_format() {
+    // What fzf appends after three accepted queries, including a blank one.
+    let written_by_fzf = "fn main\n\nTODO|FIXME\n\\bfoo\\(\n";
+    let entries = parse_history(written_by_fzf, 1000);
+    assert_eq!(entries, vec!["fn main", "TODO|FIXME", "\\bfoo\\("]);
+    assert_eq!(render_history(&entries), "fn main\nTODO|FIXME\n\\bfoo\\(\n");
+
+    assert_eq!(
+        parse_history(written_by_fzf, 2),
+        vec!["TODO|FIXME", "\\bfoo\\("]
+    );
+    assert!(parse_history("", 10).is_empty());
+}
+
+#[test]
+fn prepare_creates_private_file_and_prunes_oldest_entries() {
+    let dir = tempdir().expect("tempdir");
+    let path = dir.path().join("state/yoink/history");
+
+    prepare_history(&path, 3).expect("create");
+    assert_eq!(fs::read_to_string(&path).expect("read"), "");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&path).expect("metadata").permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    fs::write(&path, "one\ntwo\n\nthree\nfour\nfive\n").expect("write");
+    prepare_history(&path, 3).expect("prune");
+    assert_eq!(
+        fs::read_to_string(&path).expect("read"),
+        "three\nfour\nfive\n"
+    );
+    assert_eq!(last_query(&path).as_deref(), Some("five"));
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&path).expect("metadata").permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    assert_eq!(last_query(&dir.path().join("missing")), None);
+}
+
+#[test]
+fn record_query_creates_the_directory_dedupes_and_caps() {
+    let dir = tempdir().expect("tempdir");
+    let path = dir.path().join("state/yoink/history");
+
+    record_query(&path, "fn main", 3).expect("create");
+    assert_eq!(fs::read_to_string(&path).expect("read"), "fn main\n");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&path).expect("metadata").permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    record_query(&path, "TODO", 3).expect("append");
+    record_query(&path, "fn main", 3).expect("dedupe");
+    assert_eq!(fs::read_to_string(&path).expect("read"), "TODO\nfn main\n");
+
+    // fzf has already appended the accepted query; recording it again is a no-op.
+    fs::write(&path, "TODO\nfn main\nneedle\n").expect("write");
+    record_query(&path, "needle", 3).expect("already last");
+    assert_eq!(
+        fs::read_to_string(&path).expect("read"),
+        "TODO\nfn main\nneedle\n"
+    );
+
+    record_query(&path, "one", 3).expect("cap");
+    record_query(&path, "", 3).expect("blank");
+    assert_eq!(
+        fs::read_to_string(&path).expect("read"),
+        "fn main\nneedle\none\n"
+    );
+
+    record_query(&path, "ignored", 0).expect("off");
+    assert_eq!(last_query(&path).as_deref(), Some("one"));
+}
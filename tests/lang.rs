@@ -0,0 +1,150 @@
+use std::fs;
+
+use std::path::Path;
+use tempfile::tempdir;
+
+use yoink::lang::{
+    detect_language, file_type, language_badge, language_for_extension, language_for_shebang,
+    FileTypeSet,
+};
+use yoink::search::compose_file_row;
+
+fn strip_ansi(text: &str) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\x1b' {
+            for next in chars.by_ref() {
+                if next == 'm' {
+                    break;
+                }
+            }
+            continue;
+        }
+        out.push(ch);
+    }
+    out
+}
+
+fn path_column(row: &str, path: &str) -> usize {
+    let plain = strip_ansi(row);
+    let byte_idx = plain.find(path).expect("path in row");
+    plain[..byte_idx].chars().count()
+}
+
+#[test]
+fn maps_extensions_to_labels() {
+    assert_eq!(language_for_extension("rs").map(|l| l.label), Some("rs"));
+    assert_eq!(language_for_extension("PY").map(|l| l.label), Some("py"));
+    assert_eq!(language_for_extension("tsx").map(|l| l.label), Some("ts"));
+    assert_eq!(language_for_extension("md").map(|l| l.label), Some("md"));
+    assert!(language_for_extension("unknownext").is_none());
+}
+
+#[test]
+fn sniffs_shebang_interpreters() {
+    assert_eq!(
+        language_for_shebang("#!/usr/bin/env python3").map(|l| l.label),
+        Some("py")
+    );
+    assert_eq!(
+        language_for_shebang("#!/bin/bash -e").map(|l| l.label),
+        Some("sh")
+    );
+    assert_eq!(
+        language_for_shebang("#!/usr/bin/env -S node --flag").map(|l| l.label),
+        Some("js")
+    );
+    assert!(language_for_shebang("print('no shebang')").is_none());
+}
+
+#[test]
+fn detects_extensionless_scripts_by_shebang() {
+    let dir = tempdir().expect("tempdir");
+    let script = dir.path().join("deploy");
+    fs::write(&script, "#!/usr/bin/env python3\nprint('hi')\n").expect("write script");
+    let plain = dir.path().join("NOTES");
+    fs::write(&plain, "just text\n").expect("write notes");
+
+    assert_eq!(detect_language(&script).map(|l| l.label), Some("py"));
+    assert!(detect_language(&plain).is_none());
+}
+
+#[test]
+fn badges_keep_path_column_aligned() {
+    let rust_row = compose_file_row(
+        Some("📄"),
+        Some(&language_badge(language_for_extension("rs"))),
+        "src/lib.rs",
+        None,
+        80,
+    );
+    let toml_row = compose_file_row(
+        Some("📄"),
+        Some(&language_badge(language_for_extension("toml"))),
+        "Cargo.toml",
+        None,
+        80,
+    );
+    let unknown_row =
+        compose_file_row(Some("📄"), Some(&language_badge(None)), "LICENSE", None, 80);
+
+    let column = path_column(&rust_row, "src/lib.rs");
+    assert_eq!(path_column(&toml_row, "Cargo.toml"), column);
+    assert_eq!(path_column(&unknown_row, "LICENSE"), column);
+}
+
+#[test]
+fn rows_align_without_icons_or_badges() {
+    let no_icon_rows = [
+        compose_file_row(
+            None,
+            Some(&language_badge(language_for_extension("py"))),
+            "a.py",
+            None,
+            80,
+        ),
+        compose_file_row(None, Some(&language_badge(None)), "b", None, 80),
+    ];
+    assert_eq!(path_column(&no_icon_rows[0], "a.py"), 5);
+    assert_eq!(path_column(&no_icon_rows[1], "b"), 5);
+
+    let no_badge_row = compose_file_row(Some("📄"), None, "a.py", None, 80);
+    assert_eq!(strip_ansi(&no_badge_row), "📄 a.py");
+
+    let bare_row = compose_file_row(None, None, "a.py", None, 80);
+    assert_eq!(bare_row, "a.py");
+}
+
+#[test]
+fn badge_is_dim_colored() {
+    let badge = language_badge(language_for_extension("rs"));
+    assert!(badge.starts_with("\x1b[2;"));
+    assert!(badge.ends_with("\x1b[0m"));
+    assert_eq!(strip_ansi(&badge), "rs  ");
+}
+
+#[test]
+fn file_types_use_rg_names_and_patterns() {
+    let rust = file_type("rust").expect("rust");
+    assert!(rust.matches(Path::new("src/main.rs")));
+    assert!(!rust.matches(Path::new("src/main.py")));
+    assert_eq!(
+        file_type("Markdown").map(|file_type| file_type.name),
+        Some("md")
+    );
+    assert!(file_type("toml")
+        .expect("toml")
+        .matches(Path::new("Cargo.lock")));
+    assert!(file_type("rs").is_none());
+
+    let set = FileTypeSet::parse("py, ts,,yaml").expect("parse");
+    assert_eq!(set.names(), vec!["py", "ts", "yaml"]);
+    assert!(set.matches(Path::new("ci/deploy.yml")));
+    assert!(!set.matches(Path::new("README.md")));
+    assert!(FileTypeSet::parse("").expect("empty").is_empty());
+
+    let error = FileTypeSet::parse("rust,cobol").expect_err("unknown");
+    assert!(error.contains("unknown file type cobol"));
+    assert!(error.contains("rust, py, js"));
+}
@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+use yoink::notify::{
+    completion_message, desktop_commands, notify, notify_if_slow, should_notify, NotifyBackend,
+};
+
+/// Records what would have run; `installed` stands in for `PATH`.
+#[derive(Default)]
+struct Recorder {
+    installed: Vec<&'static str>,
+    bells: usize,
+    runs: Vec<(String, Vec<String>)>,
+}
+
+impl NotifyBackend for Recorder {
+    fn has(&self, program: &str) -> bool {
+        self.installed.contains(&program)
+    }
+
+    fn run(&mut self, program: &str, args: &[String]) {
+        self.runs.push((program.to_string(), args.to_vec()));
+    }
+
+    fn bell(&mut self) {
+        self.bells += 1;
+    }
+}
+
+#[test]
+fn only_runs_past_the_threshold_notify() {
+    assert!(!should_notify(Duration::from_secs(600), None));
+    assert!(!should_notify(Duration::from_millis(29_999), Some(30)));
+    assert!(should_notify(Duration::from_secs(30), Some(30)));
+
+    let mut recorder = Recorder::default();
+    let fast = notify_if_slow(&mut recorder, Some(30), Duration::from_secs(2), || {
+        panic!("message built for a fast run")
+    });
+    assert!(!fast);
+    assert_eq!(recorder.bells, 0);
+
+    assert!(notify_if_slow(
+        &mut recorder,
+        Some(30),
+        Duration::from_secs(31),
+        || "done".to_string()
+    ));
+    assert_eq!(recorder.bells, 1);
+}
+
+#[test]
+fn completion_message_names_the_run_and_its_count() {
+    assert_eq!(
+        completion_message("report for \"needle\"", 17, Duration::from_millis(42_700)),
+        "report for \"needle\" finished in 42s: 17 results"
+    );
+    assert_eq!(
+        completion_message("refresh of \"x\"", 1, Duration::from_secs(3)),
+        "refresh of \"x\" finished in 3s: 1 result"
+    );
+}
+
+#[test]
+fn picks_the_first_installed_notifier() {
+    let mut recorder = Recorder {
+        installed: vec!["osascript"],
+        ..Recorder::default()
+    };
+    notify(&mut recorder, true, "say \"hi\"");
+    assert_eq!(recorder.bells, 1);
+    assert_eq!(
+        recorder.runs,
+        [(
+            "osascript".to_string(),
+            vec![
+                "-e".to_string(),
+                "display notification \"say \\\"hi\\\"\" with title \"yoink\"".to_string()
+            ]
+        )]
+    );
+
+    let programs: Vec<&str> = desktop_commands(true, "m")
+        .into_iter()
+        .map(|(program, _)| program)
+        .collect();
+    assert_eq!(programs, ["terminal-notifier", "osascript"]);
+
+    let mut linux = Recorder {
+        installed: vec!["notify-send"],
+        ..Recorder::default()
+    };
+    notify(&mut linux, false, "done");
+    assert_eq!(
+        linux.runs,
+        [(
+            "notify-send".to_string(),
+            vec!["yoink".to_string(), "done".to_string()]
+        )]
+    );
+}
+
+#[test]
+fn without_a_notifier_only_the_bell_rings() {
+    let mut recorder = Recorder::default();
+    notify(&mut recorder, false, "done");
+
+    assert_eq!(recorder.bells, 1);
+    assert!(recorder.runs.is_empty());
+}
@@ -0,0 +1,79 @@
+#![cfg(feature = "builtin-ui")]
+
+use std::io::Cursor;
+
+use yoink::picker::{
+    format_picker_entries, parse_picker_input, pick, picker_entries, PickerInput, PICKER_ROWS,
+};
+
+#[test]
+fn picker_input_picks_searches_or_quits() {
+    assert_eq!(parse_picker_input("\n", 3), PickerInput::Quit);
+    assert_eq!(parse_picker_input("2\n", 3), PickerInput::Pick(1));
+    assert_eq!(
+        parse_picker_input("4\n", 3),
+        PickerInput::Search("4".to_string())
+    );
+    assert_eq!(
+        parse_picker_input("/2\n", 3),
+        PickerInput::Search("2".to_string())
+    );
+    assert_eq!(
+        parse_picker_input("fn main\n", 3),
+        PickerInput::Search("fn main".to_string())
+    );
+}
+
+#[test]
+fn picker_lists_entries_below_their_header_lines_by_display_field() {
+    let (headers, entries) = picker_entries("3 files\nsrc/a.rs\ta.rs\t1\t1\nb.rs\tb.rs\n", 1);
+    assert_eq!(headers, ["3 files"]);
+    assert_eq!(entries, ["src/a.rs\ta.rs\t1\t1", "b.rs\tb.rs"]);
+    assert_eq!(format_picker_entries(&entries), "1  src/a.rs\n2  b.rs\n");
+}
+
+#[test]
+fn pick_reruns_the_listing_per_query_and_answers_like_fzf() {
+    let mut queries = Vec::new();
+    let mut input = Cursor::new("main\n2\n");
+    let mut out = Vec::new();
+    let picked = pick(
+        "",
+        0,
+        |query| {
+            queries.push(query.to_string());
+            Ok("lib.rs\tsrc/lib.rs\nmain.rs\tsrc/main.rs\t3\t1\n".to_string())
+        },
+        &mut input,
+        &mut out,
+    )
+    .unwrap();
+
+    assert_eq!(queries, ["", "main"]);
+    assert_eq!(
+        picked.as_deref(),
+        Some("main\nenter\nmain.rs\tsrc/main.rs\t3\t1\n")
+    );
+    assert!(String::from_utf8(out).unwrap().contains("2  main.rs\n"));
+}
+
+#[test]
+fn pick_lists_only_the_first_rows_and_quits_on_an_empty_line() {
+    let listing: String = (0..PICKER_ROWS + 5)
+        .map(|n| format!("{n}.rs\t{n}.rs\n"))
+        .collect();
+    let mut out = Vec::new();
+    let picked = pick(
+        "",
+        0,
+        |_| Ok(listing.clone()),
+        &mut Cursor::new("\n"),
+        &mut out,
+    )
+    .unwrap();
+
+    assert_eq!(picked, None);
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains(&format!("{PICKER_ROWS}  {}.rs", PICKER_ROWS - 1)));
+    assert!(out.contains("… 5 more"));
+}
@@ -0,0 +1,79 @@
+use std::path::{Path, PathBuf};
+
+use yoink::pills::{dir_pill_for, ext_pill_for, pill_header, rg_pill_args, FilterPill, PillFilter};
+
+#[test]
+fn pills_come_from_the_selected_row() {
+    assert_eq!(
+        dir_pill_for(Path::new("crates/core/src/lib.rs"), false),
+        Some(FilterPill::Dir(PathBuf::from("crates")))
+    );
+    assert_eq!(
+        dir_pill_for(Path::new("./docs"), true),
+        Some(FilterPill::Dir(PathBuf::from("docs")))
+    );
+    // A file at the root has no narrower directory than the root itself.
+    assert_eq!(dir_pill_for(Path::new("Cargo.toml"), false), None);
+    assert_eq!(dir_pill_for(Path::new(""), false), None);
+
+    assert_eq!(
+        ext_pill_for(Path::new("src/ui.rs")),
+        Some(FilterPill::Ext("rs".to_string()))
+    );
+    assert_eq!(ext_pill_for(Path::new("Makefile")), None);
+}
+
+#[test]
+fn stacked_pills_must_all_pass() {
+    let filter = PillFilter::new(&[
+        FilterPill::Dir(PathBuf::from("src")),
+        FilterPill::Ext("rs".to_string()),
+    ])
+    .expect("filter");
+
+    assert!(filter.allows(Path::new("src/main.rs"), false));
+    assert!(filter.allows(Path::new("src/search"), true));
+    assert!(filter.allows(Path::new("src"), true));
+    assert!(!filter.allows(Path::new("src/notes.md"), false));
+    assert!(!filter.allows(Path::new("tests/ui.rs"), false));
+    assert!(!filter.allows(Path::new("tests"), true));
+    // `src` is a path component, not a string prefix.
+    assert!(!filter.allows(Path::new("srcgen/a.rs"), false));
+
+    let globbed = PillFilter::new(&[FilterPill::Glob("**/*_test.go".to_string())]).expect("glob");
+    assert!(globbed.allows(Path::new("pkg/api/handler_test.go"), false));
+    assert!(!globbed.allows(Path::new("pkg/api/handler.go"), false));
+    assert!(globbed.allows(Path::new("pkg"), true));
+
+    assert!(PillFilter::new(&[FilterPill::Glob("a{b".to_string())]).is_err());
+    assert!(PillFilter::new(&[])
+        .expect("empty")
+        .allows(Path::new("any"), false));
+}
+
+#[test]
+fn pills_become_rg_include_globs() {
+    let pills = [
+        FilterPill::Dir(PathBuf::from("web app")),
+        FilterPill::Ext("tsx".to_string()),
+        FilterPill::Glob("!*.snap".to_string()),
+    ];
+    assert_eq!(
+        rg_pill_args(&pills),
+        vec!["-g", "web app/**", "-g", "*.tsx", "-g", "!*.snap"]
+    );
+    assert!(rg_pill_args(&[]).is_empty());
+}
+
+#[test]
+fn header_lists_active_pills_in_order() {
+    assert_eq!(pill_header("Enter: cd", &[]), "Enter: cd");
+    let pills = [
+        FilterPill::Dir(PathBuf::from("src")),
+        FilterPill::Ext("rs".to_string()),
+    ];
+    assert_eq!(
+        pill_header("Enter: cd", &pills),
+        "Enter: cd\nFilters: [src/] [*.rs]  |  Alt-Backspace: pop last"
+    );
+}
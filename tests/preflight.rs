@@ -0,0 +1,120 @@
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use tempfile::tempdir;
+use yoink::preflight::{
+    exceeds_threshold, large_tree_message, parse_preflight_choice, PreflightChoice,
+};
+use yoink::search::{count_entries_bounded, GlobMatcher, TreeSize, YoinkSettings};
+
+fn write_tree(root: &Path) {
+    fs::create_dir_all(root.join("a/b/c/d")).expect("mkdir nested");
+    fs::write(root.join("a/one.txt"), "1").expect("write");
+    fs::write(root.join("a/b/c/d/deep.txt"), "4").expect("write");
+    fs::create_dir(root.join("build")).expect("mkdir build");
+    for index in 0..5 {
+        fs::write(root.join(format!("build/out{index}.o")), "").expect("write build");
+    }
+    fs::write(root.join(".env"), "").expect("write hidden");
+}
+
+fn settings_ignoring(globs: &[&str]) -> YoinkSettings {
+    let globs: Vec<String> = globs.iter().map(|glob| glob.to_string()).collect();
+    YoinkSettings {
+        globset: GlobMatcher::new(&globs).expect("globs"),
+        globs,
+        ..YoinkSettings::default()
+    }
+}
+
+#[test]
+fn counts_the_whole_tree_under_the_limit() {
+    let dir = tempdir().expect("tempdir");
+    write_tree(dir.path());
+
+    let size = count_entries_bounded(
+        dir.path(),
+        &YoinkSettings::default(),
+        1_000,
+        Duration::from_secs(60),
+    )
+    .expect("count");
+    // a, a/one.txt, a/b, a/b/c, a/b/c/d, deep.txt, build and its 5 files.
+    assert_eq!(size, TreeSize::Exact(12));
+}
+
+#[test]
+fn count_honors_ignores_and_depth() {
+    let dir = tempdir().expect("tempdir");
+    write_tree(dir.path());
+
+    let settings = settings_ignoring(&["build/**", "build"]);
+    let size = count_entries_bounded(dir.path(), &settings, 1_000, Duration::from_secs(60))
+        .expect("count");
+    assert_eq!(size, TreeSize::Exact(6));
+
+    let shallow = YoinkSettings {
+        max_depth: Some(2),
+        ..settings
+    };
+    let size =
+        count_entries_bounded(dir.path(), &shallow, 1_000, Duration::from_secs(60)).expect("count");
+    // a, a/one.txt, a/b
+    assert_eq!(size, TreeSize::Exact(3));
+}
+
+#[test]
+fn count_stops_at_the_limit_or_the_budget() {
+    let dir = tempdir().expect("tempdir");
+    write_tree(dir.path());
+
+    let settings = YoinkSettings::default();
+    assert_eq!(
+        count_entries_bounded(dir.path(), &settings, 4, Duration::from_secs(60)).expect("count"),
+        TreeSize::AtLeast(4)
+    );
+    assert_eq!(
+        count_entries_bounded(dir.path(), &settings, 1_000, Duration::ZERO).expect("count"),
+        TreeSize::AtLeast(1)
+    );
+}
+
+#[test]
+fn only_large_or_slow_trees_need_confirmation() {
+    assert!(!exceeds_threshold(TreeSize::Exact(10), 200_000));
+    assert!(exceeds_threshold(TreeSize::AtLeast(200_000), 200_000));
+    assert!(exceeds_threshold(TreeSize::AtLeast(50), 200_000));
+    assert!(!exceeds_threshold(TreeSize::AtLeast(200_000), 0));
+}
+
+#[test]
+fn parses_prompt_answers() {
+    assert_eq!(
+        parse_preflight_choice("\n"),
+        Some(PreflightChoice::Continue)
+    );
+    assert_eq!(parse_preflight_choice("C"), Some(PreflightChoice::Continue));
+    assert_eq!(
+        parse_preflight_choice(" d \n"),
+        Some(PreflightChoice::Shallow)
+    );
+    assert_eq!(
+        parse_preflight_choice("depth"),
+        Some(PreflightChoice::Shallow)
+    );
+    assert_eq!(parse_preflight_choice("a"), Some(PreflightChoice::Abort));
+    assert_eq!(parse_preflight_choice("quit"), Some(PreflightChoice::Abort));
+    assert_eq!(parse_preflight_choice("maybe"), None);
+}
+
+#[test]
+fn message_distinguishes_limit_from_timeout() {
+    let root = Path::new("/big");
+    let capped = large_tree_message(root, TreeSize::AtLeast(200_000), 200_000);
+    assert!(capped.contains("at least 200000 entries"));
+    assert!(capped.contains("--max-[d]epth 3"));
+
+    let slow = large_tree_message(root, TreeSize::AtLeast(1_234), 200_000);
+    assert!(slow.contains("counting stopped after 2s at 1234 entries"));
+}
@@ -0,0 +1,357 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use clap::Parser;
+use tempfile::tempdir;
+use yoink::cli::Cli;
+use yoink::lang::FileTypeSet;
+use yoink::prefs::{canonical_root, session_prefs, PrefsStore};
+use yoink::search::{
+    merge_layers, resolve_settings, SearchScope, SettingOverrides, SortMode, YoinkSettings,
+};
+use yoink::session::{new_session_id, Session, SessionState, SESSION_ENV};
+use yoink::ui::save_session_prefs;
+
+fn hidden(value: bool) -> SettingOverrides {
+    SettingOverrides {
+        include_hidden: Some(value),
+        ..SettingOverrides::default()
+    }
+}
+
+fn sorted(mode: SortMode) -> SettingOverrides {
+    SettingOverrides {
+        sort_mode: Some(mode),
+        ..SettingOverrides::default()
+    }
+}
+
+#[test]
+fn defaults_and_config_apply_when_no_layer_sets_a_value() {
+    let config = YoinkSettings {
+        include_hidden: true,
+        ..YoinkSettings::default()
+    };
+
+    let resolved = resolve_settings(config, &[SettingOverrides::default()]);
+    assert!(resolved.include_hidden);
+    assert_eq!(resolved.sort_mode, SortMode::Depth);
+}
+
+#[test]
+fn later_layers_take_precedence() {
+    let profile = SettingOverrides {
+        include_hidden: Some(true),
+        sort_mode: Some(SortMode::Alphabetical),
+        ..SettingOverrides::default()
+    };
+    let prefs = hidden(false);
+    let cli = sorted(SortMode::Depth);
+
+    // CLI > saved prefs > profile > config.
+    let config = YoinkSettings::default();
+    let resolved = resolve_settings(config.clone(), &[profile, prefs, cli]);
+    assert!(!resolved.include_hidden);
+    assert_eq!(resolved.sort_mode, SortMode::Depth);
+
+    let resolved = resolve_settings(config.clone(), &[profile, prefs]);
+    assert!(!resolved.include_hidden);
+    assert_eq!(resolved.sort_mode, SortMode::Alphabetical);
+
+    let resolved = resolve_settings(config, &[profile]);
+    assert!(resolved.include_hidden);
+}
+
+#[test]
+fn merged_layers_only_fill_unset_values() {
+    let merged = merge_layers(&[hidden(true), sorted(SortMode::Alphabetical), hidden(false)]);
+    assert_eq!(
+        merged,
+        SettingOverrides {
+            include_hidden: Some(false),
+            sort_mode: Some(SortMode::Alphabetical),
+            ..SettingOverrides::default()
+        }
+    );
+    assert!(merge_layers(&[]).is_empty());
+}
+
+#[test]
+fn only_runtime_toggles_reach_the_saved_prefs() {
+    let saved = sorted(SortMode::Modified);
+    let cli = Cli::parse_from(["yoink", "--hidden", "--word", "--paths-only"]).overrides();
+    let launched = merge_layers(&[saved, cli]);
+
+    // A session that toggled nothing leaves the prefs as they were.
+    assert_eq!(session_prefs(saved, launched, launched), saved);
+
+    // Toggling sort mid-session is remembered; the launch flags are not.
+    let ended = launched.layered_under(sorted(SortMode::Alphabetical));
+    assert_eq!(
+        session_prefs(saved, launched, ended),
+        sorted(SortMode::Alphabetical)
+    );
+
+    // Flipping a launch flag back is a toggle too.
+    let ended = launched.layered_under(hidden(false));
+    assert_eq!(
+        session_prefs(saved, launched, ended),
+        saved.layered_under(hidden(false))
+    );
+}
+
+#[test]
+fn store_round_trips_through_disk() {
+    let dir = tempdir().expect("tempdir");
+    let path = dir.path().join("state/yoink/prefs.toml");
+
+    let mut store = PrefsStore::default();
+    store.set(
+        PathBuf::from("/work/with \"quotes\""),
+        SettingOverrides {
+            include_hidden: Some(true),
+            sort_mode: Some(SortMode::Alphabetical),
+            ..SettingOverrides::default()
+        },
+    );
+    store.set(PathBuf::from("/work/other"), sorted(SortMode::Depth));
+    store.save(&path).expect("save");
+
+    let loaded = PrefsStore::load(&path).expect("load");
+    assert_eq!(loaded, store);
+    assert_eq!(
+        loaded.get(Path::new("/work/other")),
+        sorted(SortMode::Depth)
+    );
+    assert!(loaded.get(Path::new("/missing")).is_empty());
+}
+
+#[test]
+fn missing_file_loads_empty_and_bad_lines_are_skipped() {
+    let dir = tempdir().expect("tempdir");
+    assert_eq!(
+        PrefsStore::load(&dir.path().join("absent.toml")).expect("load"),
+        PrefsStore::default()
+    );
+
+    let store = PrefsStore::parse(
+        "include_hidden = true\n[\"/a\"]\nsort_mode = \"sideways\"\ninclude_hidden = maybe\n[\"/b\"]\ninclude_hidden = false\n[unquoted]\nsort_mode = depth\n",
+    );
+    assert_eq!(store.roots.len(), 1);
+    assert_eq!(store.get(Path::new("/b")), hidden(false));
+}
+
+#[test]
+fn setting_empty_prefs_and_clear_remove_roots() {
+    let mut store = PrefsStore::default();
+    store.set(PathBuf::from("/a"), hidden(true));
+    store.set(PathBuf::from("/b"), hidden(true));
+
+    store.set(PathBuf::from("/a"), SettingOverrides::default());
+    assert!(!store.roots.contains_key(Path::new("/a")));
+
+    assert!(store.clear(Path::new("/b")));
+    assert!(!store.clear(Path::new("/b")));
+    assert!(store.roots.is_empty());
+}
+
+#[test]
+fn prune_drops_roots_that_no_longer_exist() {
+    let dir = tempdir().expect("tempdir");
+    let kept = dir.path().join("kept");
+    fs::create_dir(&kept).expect("mkdir");
+
+    let mut store = PrefsStore::default();
+    store.set(canonical_root(&kept), hidden(true));
+    store.set(dir.path().join("gone"), hidden(true));
+
+    assert_eq!(store.prune(), 1);
+    assert_eq!(store.get(&canonical_root(&kept)), hidden(true));
+}
+
+#[test]
+fn canonical_root_normalizes_relative_spellings() {
+    let dir = tempdir().expect("tempdir");
+    let nested = dir.path().join("nested");
+    fs::create_dir(&nested).expect("mkdir");
+
+    assert_eq!(
+        canonical_root(&nested.join("..").join("nested")),
+        canonical_root(&nested)
+    );
+}
+
+#[test]
+fn cli_flags_become_the_top_layer() {
+    let cli = Cli::parse_from(["yoink", "--hidden", "--no-hidden", "--sort", "alphabetical"]);
+    assert_eq!(
+        cli.overrides(),
+        SettingOverrides {
+            include_hidden: Some(false),
+            sort_mode: Some(SortMode::Alphabetical),
+            ..SettingOverrides::default()
+        }
+    );
+    assert!(Cli::parse_from(["yoink"]).overrides().is_empty());
+    assert_eq!(
+        Cli::parse_from(["yoink", "--max-depth", "3"])
+            .overrides()
+            .max_depth,
+        Some(3)
+    );
+    assert!(Cli::try_parse_from(["yoink", "--sort", "shuffle"]).is_err());
+    assert_eq!(
+        Cli::parse_from(["yoink", "--paths-only"])
+            .overrides()
+            .search_scope,
+        Some(SearchScope::Paths)
+    );
+    assert_eq!(
+        Cli::parse_from(["yoink", "--content-only"])
+            .overrides()
+            .search_scope,
+        Some(SearchScope::Content)
+    );
+    assert!(Cli::try_parse_from(["yoink", "--paths-only", "--content-only"]).is_err());
+}
+
+#[test]
+fn walk_flags_beat_the_config_file() {
+    let config = YoinkSettings {
+        include_hidden: true,
+        include_symlinks: true,
+        include_mounts: false,
+        sort_mode: SortMode::Size,
+        ..YoinkSettings::default()
+    };
+    let cli = Cli::parse_from([
+        "yoink",
+        "--no-hidden",
+        "--no-follow",
+        "--mounts",
+        "--sort",
+        "alphabetical",
+    ]);
+    let resolved = resolve_settings(config.clone(), &[hidden(true), cli.overrides()]);
+    assert!(!resolved.include_hidden);
+    assert!(!resolved.include_symlinks);
+    assert!(resolved.include_mounts);
+    assert_eq!(resolved.sort_mode, SortMode::Alphabetical);
+
+    // The last of a pair wins; without either the config stands.
+    let cli = Cli::parse_from([
+        "yoink",
+        "--no-follow",
+        "--follow",
+        "--mounts",
+        "--no-mounts",
+    ]);
+    assert_eq!(cli.overrides().include_symlinks, Some(true));
+    assert_eq!(cli.overrides().include_mounts, Some(false));
+    let resolved = resolve_settings(config, &[Cli::parse_from(["yoink"]).overrides()]);
+    assert!(resolved.include_symlinks);
+    assert_eq!(resolved.sort_mode, SortMode::Size);
+}
+
+#[test]
+fn type_flags_repeat_and_combine() {
+    let overrides =
+        Cli::parse_from(["yoink", "-t", "rust", "--type", "md,toml", "-T", "json"]).overrides();
+    assert_eq!(
+        overrides.file_types.map(FileTypeSet::names),
+        Some(vec!["rust", "md", "toml"])
+    );
+    assert_eq!(
+        overrides.file_types_not.map(FileTypeSet::names),
+        Some(vec!["json"])
+    );
+    assert_eq!(
+        Cli::parse_from(["yoink", "needle"]).overrides().file_types,
+        None
+    );
+    assert!(Cli::try_parse_from(["yoink", "-t", "cobol"]).is_err());
+}
+
+#[test]
+fn runtime_toggles_are_saved_as_the_roots_prefs() {
+    let home = tempdir().expect("home");
+    let root = tempdir().expect("root");
+    let ignore = home.path().join(".yoinkignore");
+    fs::write(&ignore, "").expect("write config");
+    let prefs_path = home.path().join("state/yoink/prefs.toml");
+    let root_prefs = || {
+        PrefsStore::load(&prefs_path)
+            .expect("load prefs")
+            .get(&canonical_root(root.path()))
+    };
+
+    let saved = sorted(SortMode::Modified);
+    // `--no-hidden` on top of the saved sort order.
+    let launched = merge_layers(&[saved, hidden(false)]);
+    let session = Session::new(home.path().join("runtime/yoink"), new_session_id());
+    let mut state = SessionState::default();
+    state.set_overrides(launched);
+    session.store(&state).expect("store session");
+
+    // A session that toggled nothing leaves its launch flag out of the prefs.
+    save_session_prefs(&prefs_path, root.path(), saved, launched, &session)
+        .expect("save untouched");
+    assert_eq!(root_prefs(), saved);
+
+    // `alt-h`, then `alt-s` twice, as fzf runs them.
+    for args in [
+        ["__toggle", "hidden"],
+        ["__toggle", "sort"],
+        ["__toggle", "sort"],
+    ] {
+        let output = Command::new(env!("CARGO_BIN_EXE_yoink"))
+            .args(args)
+            .current_dir(root.path())
+            .env("HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path().join("config"))
+            .env("YOINKIGNORE_PATH", &ignore)
+            .env("XDG_RUNTIME_DIR", home.path().join("runtime"))
+            .env(SESSION_ENV, session.id())
+            .output()
+            .expect("run yoink");
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    assert_eq!(session.include_hidden(), Some(true));
+    assert_eq!(session.sort_mode(), Some(SortMode::Size));
+
+    save_session_prefs(&prefs_path, root.path(), saved, launched, &session).expect("save toggled");
+    assert_eq!(
+        root_prefs(),
+        SettingOverrides {
+            include_hidden: Some(true),
+            sort_mode: Some(SortMode::Size),
+            ..SettingOverrides::default()
+        }
+    );
+}
+
+#[test]
+fn sort_toggle_cycles_the_sorted_orders() {
+    let mut mode = SortMode::Depth;
+    let mut seen = Vec::new();
+    for _ in 0..5 {
+        mode = mode.next();
+        seen.push(mode);
+    }
+    assert_eq!(
+        seen,
+        [
+            SortMode::Alphabetical,
+            SortMode::Modified,
+            SortMode::Matches,
+            SortMode::Size,
+            SortMode::Depth,
+        ]
+    );
+    assert_eq!(SortMode::Stream.next(), SortMode::Depth);
+}
@@ -0,0 +1,172 @@
+use yoink::query::{
+    parse_case_mode_setting, query_has_uppercase, split_negations, split_terms, CaseMode,
+    QueryPattern, RegexFallback,
+};
+
+#[test]
+fn valid_regex_is_used_as_is() {
+    let pattern = QueryPattern::resolve("foo.*bar", RegexFallback::Literal).expect("resolve");
+    assert!(!pattern.literal);
+    assert!(pattern.regex.is_match("foo and bar"));
+    assert_eq!(pattern.rg_args(), vec!["-e", "foo.*bar"]);
+}
+
+#[test]
+fn invalid_regex_falls_back_to_literal() {
+    let pattern = QueryPattern::resolve("foo(", RegexFallback::Literal).expect("resolve");
+    assert!(pattern.literal);
+    assert!(pattern.regex.is_match("call foo(x)"));
+    assert!(!pattern.regex.is_match("call foo x"));
+    assert_eq!(pattern.rg_args(), vec!["-F", "-e", "foo("]);
+}
+
+#[test]
+fn strict_mode_keeps_regex_errors() {
+    let error = QueryPattern::resolve("foo(", RegexFallback::Strict).expect_err("strict error");
+    assert!(error.to_string().contains("invalid regex query: foo("));
+}
+
+#[test]
+fn oversized_patterns_still_match_but_skip_highlighting() {
+    // Unicode `\w` repeated fifty times compiles far past the bounded size limit.
+    let pattern = QueryPattern::resolve(r"\w{1,50}", RegexFallback::Literal).expect("resolve");
+    assert!(!pattern.literal);
+    assert!(!pattern.highlight);
+    assert!(pattern.highlight_regex().is_none());
+    assert!(pattern.regex.is_match("ünïcode"));
+
+    let small = QueryPattern::resolve("(a|b|c){1,10}", RegexFallback::Strict).expect("resolve");
+    assert!(small.highlight);
+    assert!(small.highlight_regex().is_some());
+}
+
+#[test]
+fn case_insensitive_patterns_pass_the_flag_to_rg() {
+    let pattern = QueryPattern::resolve_with_case("ejectreasons", RegexFallback::Literal, true)
+        .expect("resolve");
+    assert!(pattern.regex.is_match("ejectReasonsList.csv"));
+    assert_eq!(pattern.rg_args(), vec!["-i", "-e", "ejectreasons"]);
+
+    let literal =
+        QueryPattern::resolve_with_case("Foo(", RegexFallback::Literal, true).expect("resolve");
+    assert!(literal.regex.is_match("call foo(x)"));
+    assert_eq!(literal.rg_args(), vec!["-i", "-F", "-e", "Foo("]);
+}
+
+#[test]
+fn classifies_queries_for_smart_case() {
+    assert!(!query_has_uppercase("ejectreasons"));
+    assert!(query_has_uppercase("ejectReasons"));
+    assert!(query_has_uppercase("ÜBER"));
+    // Escaped classes, code points and group names aren't typed letters.
+    assert!(!query_has_uppercase(r"\Wfoo\S+\D"));
+    assert!(!query_has_uppercase(r"\p{Lu}x\pL\PN"));
+    assert!(!query_has_uppercase(r"\x4A\x{1F600}\u00C9"));
+    assert!(!query_has_uppercase(r"(?P<Name>abc)(?<Other>d)"));
+    assert!(query_has_uppercase(r"(?<=Foo)bar"));
+    assert!(query_has_uppercase(r"\WFoo"));
+    assert!(!query_has_uppercase("trailing\\"));
+}
+
+#[test]
+fn case_modes_decide_per_query() {
+    assert_eq!(parse_case_mode_setting("Smart"), Some(CaseMode::Smart));
+    assert_eq!(
+        parse_case_mode_setting("insensitive"),
+        Some(CaseMode::Insensitive)
+    );
+    assert_eq!(
+        parse_case_mode_setting("sensitive"),
+        Some(CaseMode::Sensitive)
+    );
+    assert_eq!(parse_case_mode_setting("loose"), None);
+
+    assert!(CaseMode::Smart.ignores_case("needle"));
+    assert!(!CaseMode::Smart.ignores_case("Needle"));
+    assert!(CaseMode::Smart.ignores_case(r"\Wneedle"));
+    assert!(CaseMode::Insensitive.ignores_case("Needle"));
+    assert!(!CaseMode::Sensitive.ignores_case("needle"));
+}
+
+#[test]
+fn splits_terms_on_whitespace_keeping_quoted_phrases_whole() {
+    assert_eq!(split_terms("foo  bar"), ["foo", "bar"]);
+    assert_eq!(
+        split_terms("\"exact phrase\" other"),
+        ["exact phrase", "other"]
+    );
+    assert_eq!(split_terms("one\\ term"), ["one term"]);
+    assert_eq!(split_terms("\\d+ say\\\"hi"), ["\\d+", "say\\\"hi"]);
+    assert!(split_terms("   ").is_empty());
+}
+
+#[test]
+fn splits_negative_terms_out_of_the_query() {
+    assert_eq!(
+        split_negations("handler  !test"),
+        ("handler".to_string(), vec!["test".to_string()])
+    );
+    assert_eq!(
+        split_negations("!a !b"),
+        (String::new(), vec!["a".to_string(), "b".to_string()])
+    );
+    assert_eq!(
+        split_negations("\\!important"),
+        ("!important".to_string(), Vec::new())
+    );
+    // A lone `!` and a `!` inside a term are matched as typed.
+    assert_eq!(
+        split_negations("a ! b!c"),
+        ("a ! b!c".to_string(), Vec::new())
+    );
+}
+
+#[test]
+fn and_terms_match_any_term_but_keep_each_for_intersection() {
+    let pattern =
+        QueryPattern::resolve_terms("foo bar(", RegexFallback::Literal, false).expect("resolve");
+    assert_eq!(pattern.terms.len(), 2);
+    assert!(pattern.terms[1].literal);
+    // The alternation finds either term, for occurrences and highlighting.
+    assert!(pattern.regex.is_match("only foo"));
+    assert!(pattern.regex.is_match("call bar(x)"));
+    assert!(!pattern.rg_args().contains(&"-F".to_string()));
+    assert_eq!(pattern.all_terms().len(), 2);
+
+    let single =
+        QueryPattern::resolve_terms("\"foo bar\"", RegexFallback::Literal, false).expect("resolve");
+    assert!(single.terms.is_empty());
+    assert_eq!(single.raw, "foo bar");
+    assert_eq!(single.all_terms().len(), 1);
+}
+
+#[test]
+fn word_bounded_wraps_escaped_literals_once_and_passes_w_to_rg() {
+    let literal = QueryPattern::resolve("log(", RegexFallback::Literal)
+        .and_then(QueryPattern::word_bounded)
+        .expect("bounded");
+    assert!(literal.word);
+    assert!(literal.regex.is_match("call log("));
+    assert!(!literal.regex.is_match("catalog("));
+    assert!(literal.rg_args().contains(&"-F".to_string()));
+    assert!(literal.rg_args().contains(&"-w".to_string()));
+
+    // A query already spelling `\b` keeps its own boundaries.
+    let typed = QueryPattern::resolve("\\blog", RegexFallback::Literal)
+        .and_then(QueryPattern::word_bounded)
+        .expect("bounded");
+    assert!(!typed.word);
+    assert_eq!(typed.regex.as_str(), "\\blog");
+    assert!(typed.regex.is_match("login"));
+}
+
+#[test]
+fn multiline_patterns_ask_rg_to_span_lines_for_every_term() {
+    let pattern = QueryPattern::resolve_terms("fn new", RegexFallback::Literal, false)
+        .expect("resolve")
+        .with_multiline();
+    let args = pattern.rg_args();
+    assert!(args.contains(&"-U".to_string()));
+    assert!(args.contains(&"--multiline-dotall".to_string()));
+    assert!(pattern.terms.iter().all(|term| term.multiline));
+}
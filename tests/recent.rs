@@ -0,0 +1,79 @@
+use std::fs;
+use std::path::PathBuf;
+
+use tempfile::tempdir;
+use yoink::recent::{parse_selections, recent_selections, record_selection, MAX_SELECTIONS};
+
+#[test]
+fn parses_root_and_path_per_line() {
+    let entries = parse_selections("/work\tsrc/main.rs\nno tab here\n\t\n/work\tREADME.md\n");
+    assert_eq!(
+        entries,
+        vec![
+            (PathBuf::from("/work"), PathBuf::from("src/main.rs")),
+            (PathBuf::from("/work"), PathBuf::from("README.md")),
+        ]
+    );
+}
+
+#[test]
+fn recent_selections_are_newest_first_per_root_and_still_present() {
+    let state = tempdir().expect("state");
+    let store = state.path().join("yoink/selections");
+    let root = tempdir().expect("root");
+    let other = tempdir().expect("other root");
+    for name in ["a.txt", "b.txt", "c.txt"] {
+        fs::write(root.path().join(name), "x").expect("write file");
+    }
+    fs::write(other.path().join("elsewhere.txt"), "x").expect("write other");
+
+    for name in ["a.txt", "b.txt", "gone.txt", "a.txt", "c.txt"] {
+        record_selection(&store, root.path(), PathBuf::from(name).as_path()).expect("record");
+    }
+    record_selection(
+        &store,
+        other.path(),
+        PathBuf::from("elsewhere.txt").as_path(),
+    )
+    .expect("record other");
+
+    assert_eq!(
+        recent_selections(&store, root.path(), 5),
+        vec![
+            PathBuf::from("c.txt"),
+            PathBuf::from("a.txt"),
+            PathBuf::from("b.txt")
+        ]
+    );
+    assert_eq!(recent_selections(&store, root.path(), 2).len(), 2);
+    assert_eq!(
+        recent_selections(&store, other.path(), 5),
+        vec![PathBuf::from("elsewhere.txt")]
+    );
+    assert!(recent_selections(&state.path().join("missing"), root.path(), 5).is_empty());
+}
+
+#[test]
+fn record_selection_prunes_to_the_newest_entries() {
+    let state = tempdir().expect("state");
+    let store = state.path().join("selections");
+    let root = tempdir().expect("root");
+    for index in 0..MAX_SELECTIONS + 10 {
+        let line = format!("{}\tfile{index}.txt\n", root.path().display());
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&store)
+            .and_then(|mut file| std::io::Write::write_all(&mut file, line.as_bytes()))
+            .expect("append");
+    }
+    record_selection(&store, root.path(), PathBuf::from("last.txt").as_path()).expect("record");
+
+    let entries = parse_selections(&fs::read_to_string(&store).expect("read store"));
+    assert_eq!(entries.len(), MAX_SELECTIONS);
+    assert_eq!(
+        entries.last().map(|(_, rel)| rel.clone()),
+        Some(PathBuf::from("last.txt"))
+    );
+    assert_eq!(entries[0].1, PathBuf::from("file11.txt"));
+}
@@ -0,0 +1,188 @@
+#![cfg(feature = "watch")]
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use tempfile::tempdir;
+use yoink::config::ConfigSource;
+use yoink::refresh::{
+    changed_paths, listen_failed, reload_request, watch_scan_interval, FzfListen, RefreshState,
+    WatchDebounce, FZF_API_KEY_ENV, WATCH_DEBOUNCE, WATCH_SCAN_MAX, WATCH_SCAN_MIN,
+};
+use yoink::search::{load_settings_from, tree_snapshot, watched_paths};
+
+const INTERVAL: Duration = Duration::from_secs(10);
+const DEBOUNCE: Duration = Duration::from_secs(2);
+
+fn at(secs: u64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000 + secs)
+}
+
+#[test]
+fn waits_for_interval_before_refreshing() {
+    let state = RefreshState::new(at(0));
+    assert!(!state.should_refresh(at(5), INTERVAL, DEBOUNCE));
+    assert!(state.should_refresh(at(10), INTERVAL, DEBOUNCE));
+}
+
+#[test]
+fn suppresses_refresh_while_typing() {
+    let mut state = RefreshState::new(at(0));
+    state.record_change(at(11));
+    assert!(!state.should_refresh(at(12), INTERVAL, DEBOUNCE));
+    assert!(state.should_refresh(at(13), INTERVAL, DEBOUNCE));
+}
+
+#[test]
+fn refresh_resets_the_interval() {
+    let mut state = RefreshState::new(at(0));
+    assert!(state.should_refresh(at(10), INTERVAL, DEBOUNCE));
+    state.record_refresh(at(10));
+    assert!(!state.should_refresh(at(15), INTERVAL, DEBOUNCE));
+    assert!(state.should_refresh(at(20), INTERVAL, DEBOUNCE));
+}
+
+#[test]
+fn ignores_stale_change_stamps() {
+    let mut state = RefreshState::new(at(0));
+    state.record_change(at(30));
+    state.record_change(at(20));
+    assert_eq!(state.last_change, Some(at(30)));
+}
+
+#[test]
+fn builds_listen_post_request() {
+    let request = reload_request("reload:yoink __search {q}", "0123abcd");
+    assert!(request.starts_with("POST / HTTP/1.1\r\n"));
+    assert!(request.contains("\r\nx-api-key: 0123abcd\r\n"));
+    assert!(request.contains("Content-Length: 25\r\n"));
+    assert!(request.ends_with("\r\n\r\nreload:yoink __search {q}"));
+}
+
+#[test]
+fn retries_only_a_quick_status_two_exit() {
+    assert!(listen_failed(Some(2), Duration::from_millis(50)));
+    assert!(
+        !listen_failed(Some(2), Duration::from_secs(5)),
+        "an error after the session ran"
+    );
+    assert!(!listen_failed(Some(1), Duration::from_millis(50)));
+    assert!(!listen_failed(Some(130), Duration::from_millis(50)));
+}
+
+#[test]
+fn listen_hands_fzf_an_api_key() {
+    let mut command = std::process::Command::new("fzf");
+    let listen = FzfListen::attach(&mut command).expect("listen");
+    let args: Vec<_> = command
+        .get_args()
+        .map(|arg| arg.to_string_lossy().to_string())
+        .collect();
+    assert_eq!(args, [format!("--listen=127.0.0.1:{}", listen.port)]);
+    let key = command
+        .get_envs()
+        .find(|(name, _)| *name == FZF_API_KEY_ENV)
+        .and_then(|(_, value)| value)
+        .expect("api key env");
+    assert_eq!(key.len(), 32);
+
+    let mut other = std::process::Command::new("fzf");
+    FzfListen::attach(&mut other).expect("listen");
+    let other_key = other
+        .get_envs()
+        .find(|(name, _)| *name == FZF_API_KEY_ENV)
+        .and_then(|(_, value)| value);
+    assert_ne!(other_key, Some(key), "each session gets its own key");
+}
+
+#[test]
+fn watch_rescans_large_or_slow_trees_less_often() {
+    assert_eq!(
+        watch_scan_interval(200, Duration::from_millis(5)),
+        WATCH_SCAN_MIN
+    );
+    assert_eq!(
+        watch_scan_interval(50_000, Duration::from_millis(50)),
+        WATCH_SCAN_MIN + Duration::from_secs(5)
+    );
+    // A scan that takes half a second waits ten before the next.
+    assert_eq!(
+        watch_scan_interval(1_000, Duration::from_millis(500)),
+        Duration::from_secs(10)
+    );
+    assert_eq!(
+        watch_scan_interval(5_000_000, Duration::from_secs(30)),
+        WATCH_SCAN_MAX
+    );
+}
+
+#[test]
+fn watch_reloads_once_changes_settle() {
+    let at_ms = |ms: u64| SystemTime::UNIX_EPOCH + Duration::from_millis(1_000_000 + ms);
+    let mut debounce = WatchDebounce::new(WATCH_DEBOUNCE);
+    assert!(!debounce.take_due(at_ms(0)), "nothing changed yet");
+
+    debounce.record_change(at_ms(0));
+    assert!(!debounce.take_due(at_ms(200)));
+    // A change inside the quiet window starts it over.
+    debounce.record_change(at_ms(250));
+    assert!(!debounce.take_due(at_ms(500)));
+    assert!(debounce.take_due(at_ms(550)));
+    assert!(!debounce.take_due(at_ms(900)), "one reload per burst");
+}
+
+#[test]
+fn watch_sees_changes_only_outside_ignored_paths() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path().join("project");
+    fs::create_dir_all(root.join("src")).expect("create src");
+    fs::create_dir_all(root.join("target/debug")).expect("create target");
+    fs::write(root.join("src/main.rs"), "fn main() {}\n").expect("write main");
+    let ignore = dir.path().join("ignore");
+    fs::write(&ignore, "*.log\n").expect("write ignore");
+    let settings = load_settings_from(&[ConfigSource::ignore(ignore)]).expect("settings");
+
+    let before = tree_snapshot(&root, &settings).expect("snapshot");
+    // Build output and an ignored log beside a watched file: no reload.
+    fs::write(root.join("target/debug/app"), "binary").expect("write build output");
+    fs::write(root.join("src/trace.log"), "noise").expect("write log");
+    let after = tree_snapshot(&root, &settings).expect("snapshot");
+    assert_eq!(changed_paths(&before, &after), Vec::<PathBuf>::new());
+
+    fs::write(root.join("src/lib.rs"), "pub fn lib() {}\n").expect("write lib");
+    fs::write(root.join("src/main.rs"), "fn main() { lib(); }\n").expect("rewrite main");
+    fs::remove_file(root.join("src/trace.log")).expect("remove log");
+    let later = tree_snapshot(&root, &settings).expect("snapshot");
+    assert_eq!(
+        changed_paths(&after, &later),
+        [PathBuf::from("src/lib.rs"), PathBuf::from("src/main.rs")]
+    );
+}
+
+#[test]
+fn watch_events_count_only_paths_the_walk_lists() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path().join("project");
+    fs::create_dir_all(root.join("src")).expect("create src");
+    fs::create_dir_all(root.join("target/debug")).expect("create target");
+    fs::create_dir_all(root.join(".git")).expect("create .git");
+    fs::write(root.join("src/lib.rs"), "pub fn lib() {}\n").expect("write lib");
+    let ignore = dir.path().join("ignore");
+    fs::write(&ignore, "*.log\n").expect("write ignore");
+    let settings = load_settings_from(&[ConfigSource::ignore(ignore.clone())]).expect("settings");
+
+    let events = [
+        root.join("src/lib.rs"),
+        // Removed before the event was read: still a change to report.
+        root.join("src/gone.rs"),
+        root.join("src/trace.log"),
+        root.join("target/debug/app"),
+        root.join(".git/index"),
+        ignore,
+    ];
+    assert_eq!(
+        watched_paths(&root, &settings, &events).expect("watched"),
+        [PathBuf::from("src/lib.rs"), PathBuf::from("src/gone.rs")]
+    );
+}
@@ -0,0 +1,170 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use regex::Regex;
+use tempfile::tempdir;
+use yoink::report::{
+    build_report, format_timestamp, highlight_html, html_escape, parse_report_format, render_html,
+    render_markdown, Report, ReportFile, ReportFormat,
+};
+use yoink::search::Occurrence;
+
+fn occurrence(line: usize, snippet: &str) -> Occurrence {
+    Occurrence {
+        line,
+        column: 1,
+        snippet: snippet.to_string(),
+        long_line: false,
+        context: Vec::new(),
+    }
+}
+
+fn sample_report() -> Report {
+    Report {
+        query: "unwrap".to_string(),
+        root: PathBuf::from("/work/app"),
+        generated: "2026-03-01 09:30:00 UTC".to_string(),
+        settings:
+            "include_hidden=false, sort_mode=depth, max_depth=none, smart_defaults=true, excludes=2"
+                .to_string(),
+        files: vec![
+            ReportFile {
+                path: PathBuf::from("src/main.rs"),
+                is_dir: false,
+                path_match: false,
+                occurrences: vec![
+                    occurrence(12, "let config = load().unwrap();"),
+                    occurrence(40, "let name = `unwrap` | args;"),
+                ],
+            },
+            ReportFile {
+                path: PathBuf::from("docs/unwrap notes.md"),
+                is_dir: false,
+                path_match: true,
+                occurrences: Vec::new(),
+            },
+        ],
+        truncated: false,
+    }
+}
+
+#[test]
+fn parses_report_formats() {
+    assert_eq!(parse_report_format("md"), Some(ReportFormat::Markdown));
+    assert_eq!(
+        parse_report_format("Markdown"),
+        Some(ReportFormat::Markdown)
+    );
+    assert_eq!(parse_report_format("html"), Some(ReportFormat::Html));
+    assert_eq!(parse_report_format("pdf"), None);
+}
+
+#[test]
+fn markdown_report_matches_golden_file() {
+    assert_eq!(
+        render_markdown(&sample_report()),
+        include_str!("golden/report.md")
+    );
+}
+
+#[test]
+fn markdown_report_notes_truncation() {
+    let report = Report {
+        truncated: true,
+        files: Vec::new(),
+        ..sample_report()
+    };
+    let rendered = render_markdown(&report);
+    assert!(rendered.contains("| Files | 0 |"));
+    assert!(rendered.contains("> Some occurrences were left out"));
+}
+
+#[test]
+fn escapes_html_special_characters() {
+    assert_eq!(
+        html_escape("a < b && c > \"d\" 'e'"),
+        "a &lt; b &amp;&amp; c &gt; &quot;d&quot; &#39;e&#39;"
+    );
+
+    let regex = Regex::new("<T>").expect("regex");
+    assert_eq!(
+        highlight_html("Vec<T> & Box<T>", Some(&regex)),
+        "Vec<mark>&lt;T&gt;</mark> &amp; Box<mark>&lt;T&gt;</mark>"
+    );
+    assert_eq!(highlight_html("<b>", None), "&lt;b&gt;");
+}
+
+#[test]
+fn html_report_escapes_snippets_paths_and_query() {
+    let report = Report {
+        query: "<script>".to_string(),
+        files: vec![ReportFile {
+            path: PathBuf::from("web/a&b.html"),
+            is_dir: false,
+            path_match: false,
+            occurrences: vec![occurrence(3, "<script>alert(1 && 2)</script>")],
+        }],
+        ..sample_report()
+    };
+    let regex = Regex::new("<script>").expect("regex");
+    let html = render_html(&report, Some(&regex));
+
+    assert!(!html.contains("<script>"));
+    assert!(html.contains("<title>yoink report: &lt;script&gt;</title>"));
+    assert!(html.contains("<a href=\"#file-0\">web/a&amp;b.html</a> (1)"));
+    assert!(html.contains("<a href=\"web/a&amp;b.html#L3\">web/a&amp;b.html:3</a>"));
+    assert!(html.contains(
+        "<pre><code class=\"language-html\"><mark>&lt;script&gt;</mark>alert(1 &amp;&amp; 2)&lt;/script&gt;</code></pre>"
+    ));
+    assert!(html.contains("<tr><th>Occurrences</th><td>1</td></tr>"));
+}
+
+#[test]
+fn formats_timestamps_in_utc() {
+    assert_eq!(format_timestamp(UNIX_EPOCH), "1970-01-01 00:00:00 UTC");
+    assert_eq!(
+        format_timestamp(UNIX_EPOCH + Duration::from_secs(951_782_400 + 3_661)),
+        "2000-02-29 01:01:01 UTC"
+    );
+    assert_eq!(
+        format_timestamp(UNIX_EPOCH + Duration::from_secs(1_792_108_800)),
+        "2026-10-16 00:00:00 UTC"
+    );
+}
+
+#[test]
+fn builds_report_from_a_search() {
+    let config_dir = tempdir().expect("config dir");
+    let config_path = config_dir.path().join(".yoinkignore");
+    fs::write(&config_path, "").expect("write config");
+    std::env::set_var("YOINKIGNORE_PATH", &config_path);
+
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::write(root.join("lib.rs"), "fn a() {}\nx.unwrap();\ny.unwrap();\n").expect("write");
+    fs::write(root.join("unwrap_guide.md"), "nothing here\n").expect("write");
+    fs::write(root.join("other.txt"), "nothing\n").expect("write");
+
+    let report = build_report("unwrap", root, "now".to_string()).expect("report");
+    let mut paths: Vec<_> = report.files.iter().map(|file| file.path.clone()).collect();
+    paths.sort();
+    assert_eq!(
+        paths,
+        vec![PathBuf::from("lib.rs"), PathBuf::from("unwrap_guide.md")]
+    );
+    assert_eq!(report.occurrence_count(), 2);
+    let lib = report
+        .files
+        .iter()
+        .find(|file| file.path == Path::new("lib.rs"))
+        .expect("lib.rs");
+    let lines: Vec<usize> = lib
+        .occurrences
+        .iter()
+        .map(|occurrence| occurrence.line)
+        .collect();
+    assert_eq!(lines, vec![2, 3]);
+
+    std::env::remove_var("YOINKIGNORE_PATH");
+}
@@ -0,0 +1,89 @@
+use std::path::PathBuf;
+
+use yoink::rgjson::{decode_base64, parse_event, RgEvent, RgMatch};
+
+#[test]
+fn match_events_keep_byte_offsets_of_multibyte_submatches() {
+    let line = r#"{"type":"match","data":{"path":{"text":"./src/héllo.rs"},"lines":{"text":"let s = \"héllo 🦀\";\n"},"line_number":7,"absolute_offset":120,"submatches":[{"match":{"text":"🦀"},"start":16,"end":20}]}}"#;
+    let Some(RgEvent::Match(found)) = parse_event(line) else {
+        panic!("expected a match event");
+    };
+    assert_eq!(found.path, PathBuf::from("src/héllo.rs"));
+    assert_eq!(found.line_number, 7);
+    assert_eq!(found.submatches, vec![(16, 20)]);
+    assert_eq!(&found.first_line()[16..20], "🦀".as_bytes());
+    assert_eq!(found.column(), 17);
+}
+
+#[test]
+fn escaped_text_and_surrogate_pairs_decode_to_utf8() {
+    let line = r#"{"type":"match","data":{"path":{"text":"a\/b.txt"},"lines":{"text":"tab\there 🦀 é\r\nnext\n"},"line_number":1,"submatches":[]}}"#;
+    let Some(RgEvent::Match(found)) = parse_event(line) else {
+        panic!("expected a match event");
+    };
+    assert_eq!(found.path, PathBuf::from("a/b.txt"));
+    assert_eq!(found.first_line(), "tab\there 🦀 é".as_bytes());
+    assert_eq!(found.column(), 1);
+
+    let escaped = r#"{"type":"match","data":{"path":{"text":"c.rs"},"lines":{"text":"\ud83e\udd80\b\f\u00e9\n"},"line_number":2,"submatches":[{"match":{"text":"\ud83e\udd80"},"start":0,"end":4}],"score":-1.5e2}}"#;
+    let Some(RgEvent::Match(found)) = parse_event(escaped) else {
+        panic!("expected a match event");
+    };
+    assert_eq!(found.first_line(), "🦀\u{8}\u{c}é".as_bytes());
+    assert_eq!(found.submatches, vec![(0, 4)]);
+    // A high surrogate without its low half isn't a character.
+    assert_eq!(
+        parse_event(r#"{"type":"begin","data":{"path":{"text":"\ud83e.rs"}}}"#),
+        None
+    );
+}
+
+#[test]
+fn invalid_utf8_arrives_as_base64_bytes() {
+    assert_eq!(
+        decode_base64("eP8gaMOpbGxvCg==").expect("base64"),
+        b"x\xff h\xc3\xa9llo\n"
+    );
+    assert_eq!(decode_base64("YQ").expect("unpadded"), b"a");
+    assert!(decode_base64("not base64!").is_none());
+
+    let line = r#"{"type":"begin","data":{"path":{"bytes":"Li9sYXRpbjEtZmlsZS3pLnR4dA=="}}}"#;
+    let Some(RgEvent::Begin { path }) = parse_event(line) else {
+        panic!("expected a begin event");
+    };
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        assert_eq!(path.as_os_str().as_bytes(), b"latin1-file-\xe9.txt");
+    }
+    #[cfg(not(target_family = "unix"))]
+    assert!(path.to_string_lossy().starts_with("latin1-file-"));
+}
+
+#[test]
+fn end_events_name_their_file_and_cut_lines_do_not_parse() {
+    let end = r#"{"type":"end","data":{"path":{"text":"a.rs"},"binary_offset":null,"stats":{"elapsed":{"secs":0,"nanos":1e3}}}}"#;
+    assert_eq!(
+        parse_event(end),
+        Some(RgEvent::End {
+            path: PathBuf::from("a.rs")
+        })
+    );
+    assert_eq!(
+        parse_event(r#"{"type":"summary","data":{}}"#),
+        Some(RgEvent::Other)
+    );
+
+    let cut = r#"{"type":"match","data":{"path":{"text":"a.rs"},"lines":{"text":"xxxx"#;
+    assert_eq!(parse_event(cut), None);
+    assert_eq!(parse_event(""), None);
+    assert_eq!(parse_event(r#"{"type":"begin"} trailing"#), None);
+
+    let spanning = RgMatch {
+        path: PathBuf::from("a.rs"),
+        lines: b"one\ntwo\n".to_vec(),
+        line_number: 3,
+        submatches: Vec::new(),
+    };
+    assert_eq!(spanning.first_line(), b"one");
+}
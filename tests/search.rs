@@ -1,9 +1,41 @@
+use clap::Parser;
 use std::fs;
-use std::path::Path;
-use std::sync::{Mutex, OnceLock};
+use std::path::{Path, PathBuf};
+#[cfg(any(feature = "git", feature = "archives"))]
+use std::process::Command;
+use std::sync::{Mutex, OnceLock, PoisonError};
 
+use std::time::{Duration, SystemTime};
 use tempfile::tempdir;
-use yoink::search::build_candidates;
+use yoink::cli::Cli;
+use yoink::config::{ConfigSnapshot, ConfigSource, SnapshotFile};
+use yoink::pills::FilterPill;
+use yoink::query::CaseMode;
+use yoink::recent::record_selection;
+use yoink::search::{
+    alias_suffix, buffer_line, build_buffer_entries, build_candidates,
+    build_candidates_with_content, build_candidates_with_stats, build_empty_candidates,
+    build_empty_entries, build_search_entries, build_search_entries_with_stats, cap_display,
+    chunk_paths, collect_ignore_stats, compose_file_row, decode_path_field, decode_settings,
+    dim_display, display_width, ellipsize_path, encode_path_field, encode_settings,
+    for_each_search_entry, format_saved_searches, format_search_entries,
+    format_search_entries_capped, graphemes, highlight_query_matches, is_binary_file, jump_action,
+    line_window, load_session_settings, load_settings, load_settings_for,
+    load_settings_from_snapshot, merge_occurrence_scans, notice_entry, number_file_rows,
+    parse_duration_setting, parse_extension_list, parse_size_setting, path_icon, preprocess_args,
+    read_json_occurrences, read_occurrences, recent_entries, reload_session_config,
+    resolve_settings, rg_alias_excludes, rg_config_globs, rg_files_args, rg_filter_args,
+    rg_max_columns_args, rg_max_filesize_args, saved_search, sort_by_match_count, sort_candidates,
+    sort_candidates_with, stats_header, timing_header, truncate_snippet, Candidate, GlobMatcher,
+    HighlightBudget, IgnoreHits, IgnorePatternStat, MatchKind, Occurrence, OccurrenceBudget,
+    OccurrenceScan, SearchEntry, SearchScope, SearchStats, SettingOverrides, SortMode, SortOptions,
+    YoinkSettings, BINARY_ICON, DEFAULT_OCCURRENCE_BUDGET, FUZZY_MARKER, HIGHLIGHT_OFF_NOTICE,
+    LONG_LINE_NOTE, PARALLEL_OCCURRENCE_MIN_FILES, PATH_DISPLAY_MAX_WIDTH, RECENT_SEPARATOR,
+    SEARCH_TIMEOUT_NOTICE, SMART_DEFAULT_GLOBS,
+};
+use yoink::session::{Session, SessionState};
+use yoink::term::ColorMode;
+use yoink::ui::parse_selected_line;
 
 fn env_lock() -> &'static Mutex<()> {
     static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
@@ -11,7 +43,8 @@ fn env_lock() -> &'static Mutex<()> {
 }
 
 fn with_system_config(config_content: &str, test_fn: impl FnOnce(&Path)) {
-    let _guard = env_lock().lock().expect("env lock");
+    // Every test sets its own YOINKIGNORE_PATH, so one failure needn't fail the rest.
+    let _guard = env_lock().lock().unwrap_or_else(PoisonError::into_inner);
     let temp_home = tempdir().expect("temp home");
     let config_path = temp_home.path().join(".yoinkignore");
     fs::write(&config_path, config_content).expect("write config");
@@ -21,6 +54,15 @@ fn with_system_config(config_content: &str, test_fn: impl FnOnce(&Path)) {
     std::env::remove_var("YOINKIGNORE_PATH");
 }
 
+/// `with_system_config` plus a temporary `XDG_STATE_HOME` for the selection log.
+fn with_state_and_config(config_content: &str, test_fn: impl FnOnce(&Path)) {
+    with_system_config(config_content, |home| {
+        std::env::set_var("XDG_STATE_HOME", home.join("state"));
+        test_fn(&home.join("state/yoink/selections"));
+        std::env::remove_var("XDG_STATE_HOME");
+    });
+}
+
 #[test]
 fn merges_path_and_content_matches() {
     with_system_config(".git/**\nnode_modukes/**\n", |_| {
@@ -108,7 +150,8 @@ fn applies_builtin_default_ignores() {
         fs::create_dir(root.join(".git")).expect("mkdir git");
         fs::write(root.join(".git/ignored.txt"), "ejectReasons\n").expect("write git ignored");
         fs::create_dir(root.join("node_modukes")).expect("mkdir node_modukes");
-        fs::write(root.join("node_modukes/ignored.txt"), "ejectReasons\n").expect("write node_modukes ignored");
+        fs::write(root.join("node_modukes/ignored.txt"), "ejectReasons\n")
+            .expect("write node_modukes ignored");
         fs::write(root.join("kept.txt"), "ejectReasons\n").expect("write kept");
 
         let candidates = build_candidates("ejectReasons", root).expect("build candidates");
@@ -208,3 +251,4715 @@ fn sorts_alphabetically_when_configured() {
         assert!(a_deep_idx < a_root_idx);
     });
 }
+
+#[test]
+fn builds_entries_from_stdin_buffer_without_walking() {
+    let dir = tempdir().expect("tempdir");
+    let buffer = dir.path().join("stdin.txt");
+    fs::write(
+        &buffer,
+        "INFO boot\nERROR disk full\nINFO retry\nERROR\tnet down\n",
+    )
+    .expect("write buffer");
+
+    let entries = build_buffer_entries("ERROR", &buffer).expect("buffer entries");
+    let lines: Vec<Option<usize>> = entries.iter().map(|entry| entry.line).collect();
+    assert_eq!(lines, vec![Some(2), Some(4)]);
+    assert!(entries.iter().all(|entry| entry.path == buffer));
+    assert!(entries.iter().all(|entry| !entry.display.contains('\t')));
+
+    let all = build_buffer_entries("", &buffer).expect("all buffer entries");
+    assert_eq!(all.len(), 4);
+}
+
+#[test]
+fn highlight_budget_turns_off_after_a_slow_call() {
+    let mut budget = HighlightBudget::new(Duration::ZERO, false);
+    let first = budget.apply("abc", |text| {
+        std::thread::sleep(Duration::from_millis(1));
+        format!("<{text}>")
+    });
+    assert_eq!(first, "<abc>");
+    assert!(budget.is_disabled() && budget.tripped());
+    assert_eq!(budget.apply("def", |text| format!("<{text}>")), "def");
+
+    let mut off = HighlightBudget::new(Duration::from_secs(60), true);
+    assert_eq!(off.apply("abc", |text| format!("<{text}>")), "abc");
+    assert!(!off.tripped());
+}
+
+#[test]
+fn oversized_patterns_list_uncolored_rows_with_a_notice() {
+    let dir = tempdir().expect("tempdir");
+    let buffer = dir.path().join("stdin.txt");
+    fs::write(
+        &buffer,
+        "alpha beta
+\n--\n\ngamma
+",
+    )
+    .expect("write buffer");
+
+    let entries = build_buffer_entries(r"\w{1,50}", &buffer).expect("buffer entries");
+    assert_eq!(entries[0], notice_entry(HIGHLIGHT_OFF_NOTICE));
+    let lines: Vec<Option<usize>> = entries[1..].iter().map(|entry| entry.line).collect();
+    assert_eq!(lines, vec![Some(1), Some(5)]);
+    assert!(entries[1].display.ends_with("alpha beta"));
+    assert!(!entries
+        .iter()
+        .any(|entry| entry.display.contains("\x1b[1;36m")));
+}
+
+#[test]
+fn reads_selected_line_back_from_stdin_buffer() {
+    let dir = tempdir().expect("tempdir");
+    let buffer = dir.path().join("stdin.txt");
+    fs::write(&buffer, "first\nsecond\n").expect("write buffer");
+
+    assert_eq!(
+        buffer_line(&buffer, 2).expect("line"),
+        Some("second".to_string())
+    );
+    assert_eq!(buffer_line(&buffer, 3).expect("line"), None);
+    assert_eq!(buffer_line(&buffer, 0).expect("line"), None);
+}
+
+fn candidate_set(candidates: Vec<Candidate>) -> Vec<(String, bool, bool, bool)> {
+    let mut set: Vec<(String, bool, bool, bool)> = candidates
+        .into_iter()
+        .map(|candidate| {
+            (
+                candidate.path.to_string_lossy().to_string(),
+                candidate.is_dir,
+                candidate.path_match,
+                candidate.content_match,
+            )
+        })
+        .collect();
+    set.sort();
+    set
+}
+
+fn write_lister_fixture(root: &Path) {
+    fs::create_dir_all(root.join("src/nested")).expect("mkdir src/nested");
+    fs::create_dir_all(root.join("ejectReasons_dir/inner")).expect("mkdir ejectReasons_dir");
+    fs::create_dir_all(root.join(".hidden")).expect("mkdir hidden");
+    fs::create_dir_all(root.join("skipped")).expect("mkdir skipped");
+    fs::write(root.join("src/main.rs"), "fn main() {}\n").expect("write main");
+    fs::write(root.join("src/nested/ejectReasons.rs"), "x\n").expect("write nested");
+    fs::write(
+        root.join("ejectReasons_dir/inner/data.txt"),
+        "ejectReasons\n",
+    )
+    .expect("write data");
+    fs::write(root.join(".hidden/secret.txt"), "ejectReasons\n").expect("write hidden");
+    fs::write(root.join("skipped/file.txt"), "ejectReasons\n").expect("write skipped");
+    fs::write(root.join("top.txt"), "nothing\n").expect("write top");
+}
+
+#[test]
+fn rg_file_lister_matches_walkdir_lister() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    write_lister_fixture(root);
+
+    for query in ["", "ejectReasons", "nested"] {
+        let mut walkdir_set = Vec::new();
+        with_system_config("skipped\n", |_| {
+            walkdir_set = candidate_set(build_candidates(query, root).expect("walkdir candidates"));
+        });
+
+        let mut rg_set = Vec::new();
+        with_system_config("file_lister=rg\nskipped\n", |_| {
+            rg_set = candidate_set(build_candidates(query, root).expect("rg candidates"));
+        });
+
+        assert_eq!(walkdir_set, rg_set, "query {query:?}");
+    }
+}
+
+#[test]
+fn rg_walker_lists_empty_directories_and_prunes_like_the_internal_walk() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    write_lister_fixture(root);
+    fs::create_dir_all(root.join("empty/deeper")).expect("create empty dirs");
+    fs::create_dir_all(root.join("out/sub")).expect("create out");
+    fs::write(root.join("out/keep.txt"), "x\n").expect("write keep");
+    fs::write(root.join("out/sub/drop.txt"), "x\n").expect("write drop");
+    fs::create_dir_all(root.join("logs")).expect("create logs");
+    fs::write(root.join("logs/app.log"), "x\n").expect("write log");
+
+    let config = "skipped\nout/**\n!out/keep.txt\n*.log\n";
+    let mut internal = Vec::new();
+    let mut internal_stats = Vec::new();
+    with_system_config(&format!("walker=internal\n{config}"), |_| {
+        internal = candidate_set(build_candidates("", root).expect("internal candidates"));
+        internal_stats = collect_ignore_stats("", root).expect("internal stats");
+    });
+    let mut rg = Vec::new();
+    let mut rg_stats = Vec::new();
+    with_system_config(&format!("walker=rg\n{config}"), |_| {
+        rg = candidate_set(build_candidates("", root).expect("rg candidates"));
+        rg_stats = collect_ignore_stats("", root).expect("rg stats");
+    });
+
+    assert_eq!(internal, rg);
+    let listed: Vec<&str> = rg.iter().map(|(path, ..)| path.as_str()).collect();
+    assert!(listed.contains(&"empty/deeper"));
+    assert!(listed.contains(&"logs"));
+    assert!(listed.contains(&"out/keep.txt"));
+    assert!(!listed.iter().any(|path| path.starts_with("skipped")));
+
+    let hits = |stats: &[IgnorePatternStat]| {
+        stats
+            .iter()
+            .map(|stat| (stat.pattern.clone(), stat.hits))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(hits(&internal_stats), hits(&rg_stats));
+
+    with_system_config("walker=ripgrep\n", |_| {
+        let error = load_settings().expect_err("invalid walker");
+        assert!(format!("{error:#}").contains("invalid walker value in"));
+    });
+}
+
+#[test]
+fn rg_file_lister_synthesizes_directories_once() {
+    with_system_config("file_lister = \"rg\"\n", |_| {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        write_lister_fixture(root);
+
+        let candidates = build_candidates("inner", root).expect("build candidates");
+        let inner: Vec<&Candidate> = candidates
+            .iter()
+            .filter(|candidate| candidate.path == Path::new("ejectReasons_dir/inner"))
+            .collect();
+
+        assert_eq!(inner.len(), 1);
+        assert!(inner[0].is_dir);
+    });
+}
+
+#[test]
+fn rg_files_args_follow_settings() {
+    with_system_config(
+        "include_hidden=true\ninclude_symlinks=true\nbuild/**\n",
+        |_| {
+            let settings = load_settings().expect("settings");
+            let args = rg_files_args(&settings);
+
+            assert_eq!(&args[..2], ["--files", "-0"]);
+            assert!(args.contains(&"--hidden".to_string()));
+            assert!(args.contains(&"--follow".to_string()));
+            assert!(args.contains(&"--one-file-system".to_string()));
+            assert!(args.windows(2).any(|pair| pair == ["-g", "!build/**"]));
+        },
+    );
+
+    with_system_config("include_mounts=true\n", |_| {
+        let settings = load_settings().expect("settings");
+        let args = rg_files_args(&settings);
+
+        assert!(!args.contains(&"--hidden".to_string()));
+        assert!(!args.contains(&"--follow".to_string()));
+        assert!(!args.contains(&"--one-file-system".to_string()));
+    });
+}
+
+#[test]
+fn invalid_regex_falls_back_to_literal_across_passes() {
+    with_system_config("", |_| {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+
+        fs::write(root.join("call_site.txt"), "value = foo(42)\n").expect("write call site");
+        fs::write(root.join("foo(notes).md"), "unrelated\n").expect("write notes");
+        fs::write(root.join("other.txt"), "foo bar\n").expect("write other");
+
+        let candidates = build_candidates("foo(", root).expect("literal candidates");
+        let paths: Vec<String> = candidates
+            .iter()
+            .map(|candidate| candidate.path.to_string_lossy().to_string())
+            .collect();
+        assert!(paths.contains(&"call_site.txt".to_string()));
+        assert!(paths.contains(&"foo(notes).md".to_string()));
+        assert!(!paths.contains(&"other.txt".to_string()));
+
+        let entries = build_search_entries("foo(", root).expect("literal entries");
+        assert!(entries[0].display.contains("(literal — invalid regex)"));
+        assert!(entries[0].path.as_os_str().is_empty());
+        assert!(entries
+            .iter()
+            .any(|entry| entry.line == Some(1) && entry.display.contains("\x1b[1;36mfoo(\x1b[0m")));
+    });
+}
+
+#[test]
+fn strict_regex_fallback_reports_errors() {
+    with_system_config("invalid_regex_fallback=strict\n", |_| {
+        let dir = tempdir().expect("tempdir");
+        let error = build_candidates("foo(", dir.path()).expect_err("strict error");
+        assert!(error.to_string().contains("invalid regex query"));
+    });
+}
+
+#[test]
+fn formats_timing_header_from_stats() {
+    let stats = SearchStats {
+        walk: Duration::from_millis(40),
+        rg_list: Duration::from_millis(310),
+        occurrences: Duration::from_micros(95_400),
+        format: Duration::from_millis(12),
+        truncated_rows: 0,
+        results_truncated: false,
+        timed_out: false,
+        ..SearchStats::default()
+    };
+
+    assert_eq!(
+        timing_header(&stats),
+        "walk 40ms · rg 310ms · occ 95ms · fmt 12ms"
+    );
+    assert_eq!(
+        timing_header(&SearchStats {
+            truncated_rows: 3,
+            ..stats
+        }),
+        "walk 40ms · rg 310ms · occ 95ms · fmt 12ms · 3 rows truncated"
+    );
+    assert_eq!(
+        timing_header(&SearchStats::default()),
+        "walk 0ms · rg 0ms · occ 0ms · fmt 0ms"
+    );
+}
+
+#[test]
+fn credits_every_overlapping_ignore_pattern() {
+    let patterns: Vec<String> = ["target/**", "*.log", "target/*.log", "docs/**"]
+        .iter()
+        .map(|pattern| pattern.to_string())
+        .collect();
+    let matcher = GlobMatcher::new(&patterns).expect("matcher");
+    let mut hits = IgnoreHits::for_matcher(&matcher);
+
+    assert!(matcher.is_match_counted("target/build.log", Some(&mut hits)));
+    assert!(matcher.is_match_counted("target/out.bin", Some(&mut hits)));
+    assert!(matcher.is_match_counted("app.log", Some(&mut hits)));
+    assert!(!matcher.is_match_counted("src/main.rs", Some(&mut hits)));
+    assert!(matcher.is_match_counted("app.log", None));
+
+    assert_eq!(hits.counts, vec![2, 2, 1, 0]);
+}
+
+#[test]
+fn negated_patterns_re_include_and_last_match_wins() {
+    let patterns: Vec<String> = ["out/**", "!out/keep.txt", "*.log", "!*.log", "debug.log"]
+        .iter()
+        .map(|pattern| pattern.to_string())
+        .collect();
+    let matcher = GlobMatcher::new(&patterns).expect("matcher");
+    let mut hits = IgnoreHits::for_matcher(&matcher);
+
+    assert!(matcher.has_negations());
+    assert!(matcher.is_match("out/other.txt"));
+    assert!(!matcher.is_match("out/keep.txt"));
+    assert!(!matcher.is_match("app.log"));
+    assert!(matcher.is_match("debug.log"));
+
+    // Only exclusions are credited; a re-included path counts for nobody.
+    assert!(!matcher.is_match_counted("out/keep.txt", Some(&mut hits)));
+    assert!(matcher.is_match_counted("out/other.txt", Some(&mut hits)));
+    assert!(matcher.is_match_counted("debug.log", Some(&mut hits)));
+    assert_eq!(hits.counts, vec![1, 0, 1, 0, 1]);
+
+    assert!(matcher.may_reinclude_under(Path::new("out")));
+    assert!(!matcher.may_reinclude_under(Path::new("out/nested")));
+    assert!(!matcher.may_reinclude_under(Path::new("docs")));
+    let anywhere =
+        GlobMatcher::new(&["vendor/**".to_string(), "!**/LICENSE".to_string()]).expect("matcher");
+    assert!(anywhere.may_reinclude_under(Path::new("vendor/a/b")));
+}
+
+#[test]
+fn negation_re_includes_a_file_inside_an_excluded_directory() {
+    with_system_config("out\nout/**\n!out/keep.txt\n", |_| {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        fs::create_dir_all(root.join("out/nested")).expect("create out");
+        fs::write(root.join("out/keep.txt"), "needle\n").expect("write keep");
+        fs::write(root.join("out/other.txt"), "needle\n").expect("write other");
+        fs::write(root.join("out/nested/keep.txt"), "needle\n").expect("write nested");
+        fs::write(root.join("top.txt"), "needle\n").expect("write top");
+
+        let paths = |query: &str| -> Vec<String> {
+            let mut paths: Vec<String> = build_candidates(query, root)
+                .expect("candidates")
+                .into_iter()
+                .map(|candidate| candidate.path.to_string_lossy().to_string())
+                .collect();
+            paths.sort();
+            paths
+        };
+        // By path: the walk enters `out` for the re-included file but doesn't list it.
+        assert_eq!(paths("keep"), ["out/keep.txt"]);
+        let listed = paths("");
+        assert_eq!(
+            listed
+                .iter()
+                .filter(|path| path.starts_with("out"))
+                .collect::<Vec<_>>(),
+            ["out/keep.txt"]
+        );
+        // By content: rg is told about the negation too.
+        assert_eq!(paths("needle"), ["out/keep.txt", "top.txt"]);
+
+        let args = rg_files_args(&load_settings().expect("settings"));
+        let globs: Vec<&str> = args
+            .windows(2)
+            .filter(|pair| pair[0] == "-g")
+            .map(|pair| pair[1].as_str())
+            .collect();
+        let at = |glob: &str| {
+            globs
+                .iter()
+                .position(|candidate| *candidate == glob)
+                .expect(glob)
+        };
+        assert!(at("*") < at("!out/**"));
+        assert!(at("!out/**") < at("out/keep.txt"));
+    });
+}
+
+#[test]
+fn reports_ignore_hits_with_their_source_lines() {
+    with_system_config(
+        "# comment\nskipped\nsort_mode=depth\n*.txt\nmissing/**\n",
+        |home| {
+            let dir = tempdir().expect("tempdir");
+            let root = dir.path();
+            write_lister_fixture(root);
+
+            let stats = collect_ignore_stats("", root).expect("ignore stats");
+            let summary: Vec<(&str, usize, Option<usize>)> = stats
+                .iter()
+                .map(|stat| {
+                    let line = stat.origin.file.as_ref().map(|_| stat.origin.line);
+                    (stat.pattern.as_str(), stat.hits, line)
+                })
+                .collect();
+
+            // `skipped` prunes the directory before its file is seen, so `*.txt` only
+            // picks up the two visible text files.
+            assert_eq!(
+                summary,
+                vec![
+                    (".git/**", 0, None),
+                    ("node_modukes/**", 0, None),
+                    ("target/**", 0, None),
+                    ("dist/**", 0, None),
+                    ("build/**", 0, None),
+                    ("vendor/**", 0, None),
+                    ("**/__pycache__/**", 0, None),
+                    ("**/.venv/**", 0, None),
+                    ("**/.terraform/**", 0, None),
+                    ("skipped", 1, Some(2)),
+                    ("*.txt", 2, Some(4)),
+                    ("missing/**", 0, Some(5)),
+                ]
+            );
+            assert_eq!(stats[2].origin.label(), "smart default");
+            assert_eq!(
+                stats[9].origin.file.as_deref(),
+                Some(home.join(".yoinkignore").as_path())
+            );
+        },
+    );
+}
+
+#[test]
+fn unicode_fold_matches_accented_paths_without_rewriting_them() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::write(root.join("Über_notes.md"), "plain\n").expect("write umlaut");
+    fs::write(root.join("other.md"), "plain\n").expect("write other");
+
+    with_system_config("", |_| {
+        let candidates = build_candidates("uber", root).expect("unfolded candidates");
+        assert!(candidates.is_empty());
+    });
+
+    with_system_config("unicode_fold = true\n", |_| {
+        let candidates = build_candidates("uber", root).expect("folded candidates");
+        let paths: Vec<_> = candidates
+            .iter()
+            .map(|candidate| candidate.path.clone())
+            .collect();
+        assert_eq!(paths, vec![Path::new("Über_notes.md").to_path_buf()]);
+
+        let entries = build_search_entries("uber", root).expect("entries");
+        assert_eq!(entries[0].path, Path::new("Über_notes.md"));
+        assert!(entries[0]
+            .display
+            .contains("\x1b[1;35mÜber\x1b[0m_notes.md"));
+    });
+}
+
+#[cfg(target_family = "unix")]
+#[test]
+fn non_utf8_paths_survive_the_fzf_line_round_trip() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let temp = tempdir().expect("tempdir");
+    let root = temp.path();
+    let name = Path::new(OsStr::from_bytes(b"caf\xe9 100%.txt"));
+    fs::write(root.join(name), "needle\n").expect("write file");
+
+    assert_eq!(encode_path_field(name), "caf%E9 100%25.txt");
+    assert_eq!(decode_path_field(&encode_path_field(name)), name);
+    assert_eq!(decode_path_field("50% off"), Path::new("50% off"));
+
+    with_system_config("", |_| {
+        let entries = build_search_entries("needle", root).expect("entries");
+        let lines: Vec<usize> = entries
+            .iter()
+            .filter(|entry| entry.path == name)
+            .filter_map(|entry| entry.line)
+            .collect();
+        assert_eq!(lines, vec![1]);
+
+        let formatted = format_search_entries(&entries);
+        let selected: Vec<PathBuf> = formatted
+            .lines()
+            .map(|line| parse_selected_line(line).0)
+            .filter(|path| path == name)
+            .collect();
+        assert_eq!(selected.len(), 2);
+        assert!(root.join(&selected[0]).is_file());
+    });
+}
+
+fn strip_ansi(text: &str) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\x1b' {
+            for escape_ch in chars.by_ref() {
+                if escape_ch == 'm' {
+                    break;
+                }
+            }
+            continue;
+        }
+        out.push(ch);
+    }
+    out
+}
+
+#[test]
+fn measures_display_width_without_escapes() {
+    assert_eq!(display_width("src/ui.rs"), 9);
+    assert_eq!(display_width("\x1b[1;36msrc\x1b[0m/ui.rs"), 9);
+    assert_eq!(display_width("📄 a"), 4);
+    assert_eq!(display_width("日本"), 4);
+    assert_eq!(display_width("e\u{301}"), 1);
+}
+
+#[test]
+fn graphemes_keep_marks_and_emoji_sequences_whole() {
+    let family = "👩\u{200D}👩\u{200D}👧";
+    assert_eq!(graphemes(&format!("a{family}b")), ["a", family, "b"]);
+    assert_eq!(display_width(family), 2);
+    assert_eq!(graphemes("e\u{301}\u{302}x"), ["e\u{301}\u{302}", "x"]);
+    assert_eq!(graphemes("👍🏽!"), ["👍🏽", "!"]);
+    // Flags are regional-indicator pairs; a third indicator starts a new flag.
+    assert_eq!(graphemes("🇯🇵🇫🇷🇩"), ["🇯🇵", "🇫🇷", "🇩"]);
+    assert_eq!(display_width("🇯🇵"), 2);
+    assert_eq!(display_width("\u{2764}\u{FE0F}"), 2);
+
+    // Conjoining jamo make one wide syllable; only the leading consonant has width.
+    let jamo = "\u{1100}\u{1161}\u{11A8}";
+    assert_eq!(graphemes(&format!("{jamo}a")), [jamo, "a"]);
+    assert_eq!(display_width(jamo), 2);
+    // Marks outside the Latin combining blocks: Devanagari, Hebrew, Thai.
+    assert_eq!(graphemes("कं"), ["कं"]);
+    assert_eq!(display_width("कं"), 1);
+    assert_eq!(display_width("\u{5E9}\u{5C1}"), 1);
+    assert_eq!(display_width("\u{E01}\u{E34}\u{E48}"), 1);
+    assert_eq!(display_width("a\u{200B}b\u{2060}"), 2);
+    assert_eq!(display_width("\u{FF21}\u{3000}"), 4);
+}
+
+#[test]
+fn truncate_snippet_fits_a_column_budget() {
+    // Ten wide characters take twenty columns: nine fit with the marker in twenty.
+    let cjk = "漢".repeat(12);
+    assert_eq!(
+        truncate_snippet(&cjk, 20, true),
+        format!("{}…", "漢".repeat(9))
+    );
+    assert_eq!(
+        truncate_snippet(&"漢".repeat(10), 20, true),
+        "漢".repeat(10)
+    );
+
+    let family = "👩\u{200D}👩\u{200D}👧";
+    let cut = truncate_snippet(&format!("ab{family}cd"), 4, true);
+    assert_eq!(cut, "ab…");
+    assert!(display_width(&cut) <= 4);
+
+    let accents = "e\u{301}".repeat(8);
+    assert_eq!(
+        truncate_snippet(&accents, 5, true),
+        format!("{}…", "e\u{301}".repeat(4))
+    );
+    assert_eq!(truncate_snippet(&accents, 8, false), accents);
+    assert_eq!(
+        truncate_snippet("abcdefghijklmnop", 14, false),
+        "ab (truncated)"
+    );
+
+    let hangul = "\u{1100}\u{1161}\u{11A8}".repeat(4);
+    assert_eq!(
+        truncate_snippet(&hangul, 5, true),
+        format!("{}…", "\u{1100}\u{1161}\u{11A8}".repeat(2))
+    );
+}
+
+#[test]
+fn long_paths_are_ellipsized_in_the_middle() {
+    assert_eq!(ellipsize_path("src/ui.rs", 20), "src/ui.rs");
+    assert_eq!(
+        ellipsize_path("src/a/b/c/deep/file.rs", 18),
+        "src/…/deep/file.rs"
+    );
+    assert_eq!(
+        ellipsize_path("src/a/b/c/deep/file.rs", 13),
+        "src/…/file.rs"
+    );
+    assert_eq!(
+        ellipsize_path("src/a/very_long_file_name.rs", 12),
+        "src/a/very_…"
+    );
+    assert_eq!(ellipsize_path("漢字/a/b/漢字.rs", 14), "漢字/…/漢字.rs");
+
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    let deep: PathBuf = (0..12).map(|index| format!("segment{index:02}")).collect();
+    fs::create_dir_all(root.join(&deep)).expect("create dirs");
+    fs::write(root.join(deep.join("needle.rs")), "x\n").expect("write file");
+    with_system_config("", |_| {
+        let rows: Vec<String> = displays(&build_search_entries("needle", root).expect("entries"))
+            .iter()
+            .map(|row| strip_ansi(row))
+            .collect();
+        let row = rows
+            .iter()
+            .find(|row| row.contains("needle.rs"))
+            .expect("file row");
+        assert!(row.contains("segment00/…/"), "{row}");
+        assert!(display_width(row) <= PATH_DISPLAY_MAX_WIDTH + 4, "{row}");
+    });
+    with_system_config("path_ellipsis = false\n", |_| {
+        let rows: Vec<String> = displays(&build_search_entries("needle", root).expect("entries"))
+            .iter()
+            .map(|row| strip_ansi(row))
+            .collect();
+        assert!(
+            rows.iter()
+                .any(|row| row.contains("segment11/needle.rs") && row.contains("segment05")),
+            "{rows:#?}"
+        );
+    });
+}
+
+#[test]
+fn teaser_fills_the_remaining_width() {
+    let row = compose_file_row(
+        Some("📄"),
+        None,
+        "src/ui.rs",
+        Some("let preview = format!(\"{} __preview {{2}}\", exe);"),
+        40,
+    );
+
+    // icon (2) + space, the path reserves 16 columns, then the 5-column separator.
+    assert_eq!(strip_ansi(&row), "📄 src/ui.rs  ·  let preview = f…");
+    assert!(row.contains("\x1b[2m  ·  "));
+    assert!(row.ends_with("\x1b[0m"));
+    assert!(display_width(&row) <= 40);
+}
+
+#[test]
+fn teaser_never_squeezes_the_path() {
+    let long_path = "src/some/deeply/nested/module/with/a/long/name.rs";
+    let row = compose_file_row(Some("📁"), None, long_path, Some("fn main() {}"), 60);
+    assert_eq!(strip_ansi(&row), format!("📁 {long_path}"));
+
+    let highlighted = "\x1b[1;36mui\x1b[0m.rs";
+    let row = compose_file_row(None, Some("rs  "), highlighted, Some("short teaser"), 40);
+    assert!(row.starts_with(&format!("rs   {highlighted}")));
+    assert_eq!(strip_ansi(&row), "rs   ui.rs  ·  short teaser");
+}
+
+#[test]
+fn teaser_shows_first_match_when_enabled() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::write(
+        root.join("notes.txt"),
+        "intro\n\tneedle first\nneedle second\n",
+    )
+    .expect("write");
+
+    with_system_config("teaser = true\nshow_language = false\n", |_| {
+        std::env::set_var("FZF_COLUMNS", "200");
+        let entries = build_search_entries("needle", root).expect("entries");
+        std::env::remove_var("FZF_COLUMNS");
+
+        assert_eq!(
+            strip_ansi(&entries[0].display),
+            "📄 [c] notes.txt (2)  ·  needle first"
+        );
+    });
+
+    with_system_config("show_language = false\n", |_| {
+        let entries = build_search_entries("needle", root).expect("entries");
+        assert_eq!(strip_ansi(&entries[0].display), "📄 [c] notes.txt (2)");
+    });
+}
+
+#[test]
+fn plain_sessions_render_entries_without_escapes() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::write(root.join("main.rs"), "fn needle() {}\n").expect("write");
+
+    with_system_config("", |home| {
+        let runtime = home.join("runtime");
+        let session = Session::new(runtime.join("yoink"), "plain-test".to_string());
+        session
+            .store(&SessionState {
+                plain: true,
+                ..SessionState::default()
+            })
+            .expect("store session");
+        std::env::set_var("XDG_RUNTIME_DIR", &runtime);
+        std::env::set_var("YOINK_SESSION", session.id());
+
+        let entries = build_search_entries("needle", root);
+
+        std::env::remove_var("YOINK_SESSION");
+        std::env::remove_var("XDG_RUNTIME_DIR");
+        let entries = entries.expect("entries");
+
+        assert_eq!(entries[0].display, "📄 rs   [c] main.rs (1)");
+        assert_eq!(entries[1].display, "     ↳    1  fn >>needle<<() {}");
+    });
+}
+
+#[test]
+fn parses_demote_patterns_separately_from_ignores() {
+    with_system_config("skipped/**\n~tests/**\n  ~ examples/**\n", |_| {
+        let settings = load_settings().expect("settings");
+        assert_eq!(settings.demote_globs, vec!["tests/**", "examples/**"]);
+        assert!(settings.globs.contains(&"skipped/**".to_string()));
+        assert!(!settings.globs.iter().any(|glob| glob.starts_with('~')));
+        assert!(settings.demote_globset.is_match("tests/unit.rs"));
+        assert!(!settings.globset.is_match("tests/unit.rs"));
+    });
+}
+
+fn candidate(path: &str, demoted: bool) -> Candidate {
+    Candidate {
+        path: Path::new(path).to_path_buf(),
+        is_dir: false,
+        path_match: true,
+        content_match: false,
+        demoted,
+        aliases: Vec::new(),
+        is_binary: false,
+        modified: None,
+        size: None,
+        fuzzy_match: false,
+        match_count: 0,
+    }
+}
+
+#[test]
+fn demoted_candidates_sort_last_in_every_mode() {
+    let paths = |candidates: &[Candidate]| -> Vec<String> {
+        candidates
+            .iter()
+            .map(|candidate| candidate.path.to_string_lossy().to_string())
+            .collect()
+    };
+
+    let mut candidates = vec![
+        candidate("a.rs", true),
+        candidate("src/deep/z.rs", false),
+        candidate("b.rs", false),
+        candidate("tests/a.rs", true),
+    ];
+
+    sort_candidates(&mut candidates, SortMode::Depth);
+    assert_eq!(
+        paths(&candidates),
+        ["b.rs", "src/deep/z.rs", "a.rs", "tests/a.rs"]
+    );
+
+    sort_candidates(&mut candidates, SortMode::Alphabetical);
+    assert_eq!(
+        paths(&candidates),
+        ["b.rs", "src/deep/z.rs", "a.rs", "tests/a.rs"]
+    );
+}
+
+#[test]
+fn sorts_by_modification_time_newest_first() {
+    with_system_config("sort_mode=modified\n", |_| {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        for (name, days) in [
+            ("week.txt", 7),
+            ("today.txt", 0),
+            ("month.txt", 30),
+            ("also_today.txt", 0),
+        ] {
+            fs::write(root.join(name), "x\n").expect("write file");
+            age_file(&root.join(name), days);
+        }
+
+        let paths: Vec<String> = build_candidates("txt", root)
+            .expect("candidates")
+            .into_iter()
+            .map(|candidate| candidate.path.to_string_lossy().to_string())
+            .collect();
+        assert_eq!(paths[2..], ["week.txt", "month.txt"]);
+        assert!(paths[..2].contains(&"today.txt".to_string()));
+        assert!(paths[..2].contains(&"also_today.txt".to_string()));
+    });
+}
+
+#[test]
+fn modified_sort_breaks_ties_by_path_and_puts_unknown_last() {
+    let now = SystemTime::now();
+    let dated = |path: &str, modified: Option<SystemTime>| Candidate {
+        modified,
+        ..candidate(path, false)
+    };
+    let mut candidates = vec![
+        dated("unknown.rs", None),
+        dated("b.rs", Some(now)),
+        dated("old.rs", Some(now - Duration::from_secs(60))),
+        dated("a.rs", Some(now)),
+        Candidate {
+            demoted: true,
+            ..dated("demoted.rs", Some(now + Duration::from_secs(60)))
+        },
+    ];
+    sort_candidates(&mut candidates, SortMode::Modified);
+    let paths: Vec<String> = candidates
+        .iter()
+        .map(|candidate| candidate.path.to_string_lossy().to_string())
+        .collect();
+    assert_eq!(
+        paths,
+        ["a.rs", "b.rs", "old.rs", "unknown.rs", "demoted.rs"]
+    );
+}
+
+#[test]
+fn matches_sort_ranks_files_by_occurrence_count() {
+    with_system_config("sort_mode=matches\n", |_| {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        fs::create_dir(root.join("deep")).expect("mkdir");
+        fs::write(root.join("one.txt"), "needle\n").expect("write one");
+        fs::write(root.join("deep/five.txt"), "needle\n".repeat(5)).expect("write five");
+        fs::write(root.join("needle.txt"), "nothing here\n").expect("write path-only");
+
+        let files: Vec<PathBuf> = build_search_entries("needle", root)
+            .expect("entries")
+            .into_iter()
+            .filter(|entry| entry.kind.is_some())
+            .map(|entry| entry.path)
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                PathBuf::from("deep/five.txt"),
+                PathBuf::from("one.txt"),
+                PathBuf::from("needle.txt")
+            ]
+        );
+    });
+}
+
+#[test]
+fn matches_sort_falls_back_to_depth_on_ties() {
+    let mut candidates = vec![
+        Candidate {
+            content_match: true,
+            ..candidate("src/deep/b.rs", false)
+        },
+        Candidate {
+            content_match: true,
+            ..candidate("a.rs", false)
+        },
+        Candidate {
+            content_match: true,
+            ..candidate("src/c.rs", false)
+        },
+    ];
+    let counts = [("src/deep/b.rs", 2), ("a.rs", 2), ("src/c.rs", 2)]
+        .into_iter()
+        .map(|(path, count)| (PathBuf::from(path), count))
+        .collect();
+    sort_by_match_count(&mut candidates, &counts, SortOptions::default());
+    let paths: Vec<String> = candidates
+        .iter()
+        .map(|candidate| candidate.path.to_string_lossy().to_string())
+        .collect();
+    assert_eq!(paths, ["a.rs", "src/c.rs", "src/deep/b.rs"]);
+}
+
+fn size_sorted_paths(config: &str) -> Vec<String> {
+    let mut paths = Vec::new();
+    with_system_config(config, |_| {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        fs::write(root.join("b_medium.log"), "x".repeat(200)).expect("write medium");
+        fs::write(root.join("a_large.log"), "x".repeat(3000)).expect("write large");
+        fs::write(root.join("c_small.log"), "x").expect("write small");
+        fs::create_dir(root.join("one_log")).expect("mkdir one");
+        fs::write(root.join("one_log/keep"), "").expect("write keep");
+        fs::create_dir(root.join("two_log")).expect("mkdir two");
+        fs::write(root.join("two_log/a"), "").expect("write a");
+        fs::write(root.join("two_log/b"), "").expect("write b");
+
+        paths = build_candidates("log$", root)
+            .expect("candidates")
+            .into_iter()
+            .map(|candidate| candidate.path.to_string_lossy().to_string())
+            .collect();
+    });
+    paths
+}
+
+#[test]
+fn size_sort_lists_directories_first_then_files_by_size() {
+    assert_eq!(
+        size_sorted_paths("sort_mode=size\n"),
+        [
+            "one_log",
+            "two_log",
+            "c_small.log",
+            "b_medium.log",
+            "a_large.log"
+        ]
+    );
+    assert_eq!(
+        size_sorted_paths("sort_mode=size\nsize_order=desc\n"),
+        [
+            "two_log",
+            "one_log",
+            "a_large.log",
+            "b_medium.log",
+            "c_small.log"
+        ]
+    );
+}
+
+#[test]
+fn invalid_size_order_names_the_value() {
+    with_system_config("size_order=biggest\n", |_| {
+        let message = format!("{:#}", load_settings().expect_err("invalid value"));
+        assert!(message.contains("invalid size_order value in"), "{message}");
+        assert!(message.contains("biggest"), "{message}");
+    });
+}
+
+fn grouped_paths(config: &str) -> Vec<String> {
+    let mut paths = Vec::new();
+    with_system_config(config, |_| {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        // Both at depth 2; the file sorts first by name.
+        fs::create_dir_all(root.join("app/widget_b")).expect("mkdir dir");
+        fs::write(root.join("app/widget_a.txt"), "x\n").expect("write file");
+
+        paths = build_candidates("widget", root)
+            .expect("candidates")
+            .into_iter()
+            .map(|candidate| candidate.path.to_string_lossy().to_string())
+            .collect();
+    });
+    paths
+}
+
+#[test]
+fn group_dirs_first_lists_directories_before_files_at_the_same_depth() {
+    assert_eq!(grouped_paths(""), ["app/widget_a.txt", "app/widget_b"]);
+    assert_eq!(
+        grouped_paths("group_dirs_first=true\n"),
+        ["app/widget_b", "app/widget_a.txt"]
+    );
+    assert_eq!(
+        grouped_paths("group_dirs_first=true\nsort_mode=alphabetical\n"),
+        ["app/widget_b", "app/widget_a.txt"]
+    );
+}
+
+#[test]
+fn group_dirs_first_composes_with_every_sort_mode() {
+    let options = SortOptions {
+        group_dirs_first: true,
+        ..SortOptions::default()
+    };
+    let dir = |path: &str| Candidate {
+        is_dir: true,
+        ..candidate(path, false)
+    };
+    for mode in [
+        SortMode::Depth,
+        SortMode::Alphabetical,
+        SortMode::Modified,
+        SortMode::Matches,
+        SortMode::Size,
+    ] {
+        let mut candidates = vec![candidate("a.rs", false), dir("z"), candidate("b.rs", false)];
+        sort_candidates_with(&mut candidates, mode, options);
+        assert!(candidates[0].is_dir, "{mode:?}");
+    }
+    let mut candidates = vec![candidate("deep/a.rs", false), dir("z")];
+    sort_candidates_with(&mut candidates, SortMode::Depth, options);
+    assert_eq!(
+        candidates[0].path,
+        Path::new("z"),
+        "a shallower directory still leads"
+    );
+}
+
+#[test]
+fn dims_demoted_rows_across_highlight_resets() {
+    assert_eq!(dim_display("tests/a.rs"), "\x1b[2mtests/a.rs\x1b[0m");
+    assert_eq!(
+        dim_display("tests/\x1b[1;36mneedle\x1b[0m.rs"),
+        "\x1b[2mtests/\x1b[1;36mneedle\x1b[0m\x1b[2m.rs\x1b[0m"
+    );
+}
+
+#[test]
+fn demoted_paths_stay_in_results_but_exclusion_wins() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::create_dir_all(root.join("tests")).expect("mkdir tests");
+    fs::create_dir_all(root.join("examples")).expect("mkdir examples");
+    fs::write(root.join("tests/needle.rs"), "x\n").expect("write test");
+    fs::write(root.join("examples/needle.rs"), "x\n").expect("write example");
+    fs::write(root.join("needle_main.rs"), "x\n").expect("write main");
+
+    with_system_config(
+        "~tests/**\n~examples/**\nexamples/**\nshow_language=false\n",
+        |_| {
+            let candidates = build_candidates("needle", root).expect("candidates");
+            let summary: Vec<(String, bool)> = candidates
+                .iter()
+                .map(|candidate| {
+                    (
+                        candidate.path.to_string_lossy().to_string(),
+                        candidate.demoted,
+                    )
+                })
+                .collect();
+            assert_eq!(
+                summary,
+                vec![
+                    ("needle_main.rs".to_string(), false),
+                    ("tests/needle.rs".to_string(), true),
+                ]
+            );
+
+            let entries = build_search_entries("needle", root).expect("entries");
+            let demoted_row = entries
+                .iter()
+                .find(|entry| entry.path == Path::new("tests/needle.rs"))
+                .expect("demoted row");
+            assert!(demoted_row.display.starts_with("📄 \x1b[2m"));
+        },
+    );
+}
+
+#[test]
+fn cap_display_leaves_short_rows_alone() {
+    assert_eq!(cap_display("📄 src/lib.rs", 64), None);
+    assert_eq!(cap_display("exactly", 7), None);
+}
+
+#[test]
+fn cap_display_never_splits_escapes_or_characters() {
+    let highlighted = "\x1b[1;36mneedle\x1b[0m ".repeat(200);
+    for budget in 0..80 {
+        let capped = cap_display(&highlighted, budget).expect("capped");
+        assert!(capped.len() <= budget.max(7), "budget {budget}: {capped:?}");
+        assert!(capped.ends_with("\x1b[0m…"));
+
+        // Every escape in the output is complete.
+        let body = capped.trim_end_matches("\x1b[0m…");
+        for piece in body.split('\x1b').skip(1) {
+            assert!(
+                piece.starts_with("[1;36m") || piece.starts_with("[0m"),
+                "budget {budget}: {capped:?}"
+            );
+        }
+    }
+
+    let wide = "ü".repeat(100);
+    let capped = cap_display(&wide, 20).expect("capped");
+    assert_eq!(capped, format!("{}\x1b[0m…", "ü".repeat(6)));
+}
+
+#[test]
+fn capped_format_keeps_hidden_columns_and_counts_rows() {
+    let long_display = format!("📄 {}", "\x1b[1;36mx\x1b[0m".repeat(1000));
+    let entries = vec![
+        SearchEntry {
+            display: long_display,
+            path: Path::new("src/long.rs").to_path_buf(),
+            line: Some(12),
+            column: Some(3),
+            kind: None,
+            match_count: None,
+        },
+        SearchEntry {
+            display: "📄 short.rs".to_string(),
+            path: Path::new("short.rs").to_path_buf(),
+            line: None,
+            column: None,
+            kind: None,
+            match_count: None,
+        },
+    ];
+
+    let mut stats = SearchStats::default();
+    let formatted = format_search_entries_capped(&entries, 256, &mut stats);
+    let lines: Vec<&str> = formatted.lines().collect();
+
+    assert_eq!(stats.truncated_rows, 1);
+    assert!(lines[0].len() < 256);
+    assert!(lines[0].ends_with("\x1b[0m…\tsrc/long.rs\t12\t3\t"));
+    assert_eq!(lines[1], "📄 short.rs\tshort.rs\t\t\t");
+}
+
+#[test]
+fn selects_empty_files_and_directories_after_ignores() {
+    with_system_config(".git/**\n*.log\n", |_| {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+
+        fs::write(root.join("blank.txt"), "").expect("write blank");
+        fs::write(root.join("full.txt"), "data\n").expect("write full");
+        fs::create_dir(root.join("hollow")).expect("mkdir hollow");
+        fs::create_dir_all(root.join("outer/inner")).expect("mkdir nested");
+        fs::create_dir(root.join("logs")).expect("mkdir logs");
+        fs::write(root.join("logs/run.log"), "ignored\n").expect("write log");
+        fs::create_dir(root.join("dotted")).expect("mkdir dotted");
+        fs::write(root.join("dotted/.keep"), "").expect("write hidden");
+        fs::create_dir(root.join("src")).expect("mkdir src");
+        fs::write(root.join("src/empty.rs"), "").expect("write empty source");
+
+        let candidates = build_empty_candidates("", root).expect("empty candidates");
+        let mut found: Vec<(String, bool)> = candidates
+            .iter()
+            .map(|candidate| {
+                (
+                    candidate.path.to_string_lossy().to_string(),
+                    candidate.is_dir,
+                )
+            })
+            .collect();
+        found.sort();
+
+        // `outer` holds an (empty) directory, so only `outer/inner` qualifies;
+        // `src` holds an empty file, so it is not empty itself.
+        assert_eq!(
+            found,
+            vec![
+                ("blank.txt".to_string(), false),
+                ("dotted".to_string(), true),
+                ("hollow".to_string(), true),
+                ("logs".to_string(), true),
+                ("outer/inner".to_string(), true),
+                ("src/empty.rs".to_string(), false),
+            ]
+        );
+
+        let filtered = build_empty_candidates("^lo", root).expect("filtered candidates");
+        let paths: Vec<String> = filtered
+            .iter()
+            .map(|candidate| candidate.path.to_string_lossy().to_string())
+            .collect();
+        assert_eq!(paths, vec!["logs"]);
+    });
+}
+
+const SMALL_BUDGET: OccurrenceBudget = OccurrenceBudget {
+    per_file: 3,
+    total: 10,
+    line_bytes: 64,
+};
+
+#[test]
+fn occurrence_reader_stops_at_the_total_budget_on_a_pipe() {
+    let (reader, mut writer) = std::io::pipe().expect("pipe");
+
+    // Far more output than any pipe buffer holds: the writer only finishes if the
+    // reader drains everything, and fails with a broken pipe once it stops.
+    let feeder = std::thread::spawn(move || -> std::io::Result<usize> {
+        use std::io::Write;
+        let mut written = 0;
+        for index in 0..200_000 {
+            let line = format!(
+                "./dump{}.sql:{}:1:INSERT INTO t VALUES ({index});\n",
+                index % 50,
+                index + 1
+            );
+            writer.write_all(line.as_bytes())?;
+            written += line.len();
+        }
+        Ok(written)
+    });
+
+    let scan = read_occurrences(std::io::BufReader::new(reader), &SMALL_BUDGET).expect("scan");
+    let total: usize = scan.occurrences.values().map(Vec::len).sum();
+    assert!(scan.truncated);
+    assert_eq!(total, 10);
+    assert!(scan.occurrences.contains_key(Path::new("dump0.sql")));
+
+    // The reader is gone, so the writer never gets to push all ~8 MB.
+    assert!(feeder.join().expect("feeder thread").is_err());
+}
+
+#[test]
+fn occurrence_reader_caps_each_file_and_each_line() {
+    let mut output = String::new();
+    for line in 1..=5 {
+        output.push_str(&format!("big.sql:{line}:1:token\n"));
+    }
+    output.push_str(&format!("wide.sql:1:1:{}\n", "x".repeat(4 * 1024 * 1024)));
+    output.push_str("small.rs:2:5:let token = 1;\n");
+
+    let scan = read_occurrences(output.as_bytes(), &SMALL_BUDGET).expect("scan");
+    assert!(scan.truncated);
+
+    let lines: Vec<usize> = scan.occurrences[Path::new("big.sql")]
+        .iter()
+        .map(|occurrence| occurrence.line)
+        .collect();
+    assert_eq!(lines, vec![1, 2, 3]);
+
+    // 64 bytes of line, minus the "wide.sql:1:1:" prefix.
+    let wide = &scan.occurrences[Path::new("wide.sql")][0];
+    assert_eq!(wide.snippet.len(), 64 - "wide.sql:1:1:".len());
+
+    let small = &scan.occurrences[Path::new("small.rs")][0];
+    assert_eq!(
+        (small.line, small.column, small.snippet.as_str()),
+        (2, 5, "let token = 1;")
+    );
+}
+
+#[test]
+fn occurrence_reader_marks_lines_rg_cut_at_max_columns() {
+    // Captured from `rg -n --column --no-heading --max-columns 40 --max-columns-preview`
+    // over a minified bundle, plus rg's forms without a preview.
+    let output = concat!(
+        "./dist/app.min.js:1:18:!function(e){var t={};function n(r){if(t[r]) [... 3 more matches]\n",
+        "./dist/app.min.js:2:5:var needle=function(){return 1},a=functi [... omitted end of long line]\n",
+        "./dist/vendor.js:7:1:[Omitted long line with 12 matches]\n",
+        "./dist/vendor.js:9:3:[Omitted long matching line]\n",
+        "./src/main.js:4:7:const more = \"[... 2 more matches]\";\n",
+    );
+    let scan = read_occurrences(output.as_bytes(), &DEFAULT_OCCURRENCE_BUDGET).expect("scan");
+
+    let cut: Vec<(usize, bool, &str)> = scan.occurrences[Path::new("dist/app.min.js")]
+        .iter()
+        .chain(&scan.occurrences[Path::new("dist/vendor.js")])
+        .map(|occurrence| {
+            (
+                occurrence.line,
+                occurrence.long_line,
+                occurrence.snippet.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        cut,
+        vec![
+            (1, true, LONG_LINE_NOTE),
+            (2, true, LONG_LINE_NOTE),
+            (7, true, LONG_LINE_NOTE),
+            (9, true, LONG_LINE_NOTE),
+        ]
+    );
+
+    // Marker-like text inside a normal line stays a snippet.
+    let normal = &scan.occurrences[Path::new("src/main.js")][0];
+    assert!(!normal.long_line);
+    assert_eq!(normal.snippet, "const more = \"[... 2 more matches]\";");
+}
+
+#[test]
+fn occurrence_reader_keeps_colons_in_paths() {
+    let output = concat!(
+        "C:\\repo\\src\\main.rs\x0012:3:let x = a::b;\n",
+        "./notes/10:30 standup.md\x004:1:agenda: 2:30\n",
+        // Without `--null`, the first `:line:column:` ends the path.
+        "C:\\repo\\lib.rs:7:9:map: HashMap<u8, u8>\n",
+        "logs/a:b.txt:2:5:x:1:2:y\n",
+    );
+    let scan = read_occurrences(output.as_bytes(), &DEFAULT_OCCURRENCE_BUDGET).expect("scan");
+
+    let found = |path: &str| {
+        let occurrence = &scan.occurrences[Path::new(path)][0];
+        (
+            occurrence.line,
+            occurrence.column,
+            occurrence.snippet.clone(),
+        )
+    };
+    assert_eq!(scan.occurrences.len(), 4);
+    assert_eq!(
+        found("C:\\repo\\src\\main.rs"),
+        (12, 3, "let x = a::b;".to_string())
+    );
+    assert_eq!(
+        found("notes/10:30 standup.md"),
+        (4, 1, "agenda: 2:30".to_string())
+    );
+    assert_eq!(
+        found("C:\\repo\\lib.rs"),
+        (7, 9, "map: HashMap<u8, u8>".to_string())
+    );
+    assert_eq!(found("logs/a:b.txt"), (2, 5, "x:1:2:y".to_string()));
+}
+
+#[test]
+fn occurrence_snippets_and_hostile_paths_are_sanitized() {
+    let output = "src/log.txt\x003:1:\x1b[31mERROR\x1b[0m bell\x07 here\n";
+    let scan = read_occurrences(output.as_bytes(), &DEFAULT_OCCURRENCE_BUDGET).expect("scan");
+    assert_eq!(
+        scan.occurrences[Path::new("src/log.txt")][0].snippet,
+        "ERROR bell\u{2407} here"
+    );
+
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::write(
+        root.join("evil\x1b[2Jname.txt"),
+        "needle \x1b[41mred\x1b[0m\x07\n",
+    )
+    .expect("write file");
+    with_system_config("", |_| {
+        let rows = displays(&build_search_entries("needle", root).expect("entries"));
+        let plain: Vec<String> = rows.iter().map(|row| strip_ansi(row)).collect();
+        assert!(
+            plain.iter().any(|row| row.contains("evilname.txt")),
+            "{plain:#?}"
+        );
+        assert!(
+            plain.iter().any(|row| row.contains("needle red\u{2407}")),
+            "{plain:#?}"
+        );
+        // Only our own SGR sequences remain; the file's `ESC [41m` and `ESC [2J` are gone.
+        assert!(
+            rows.iter()
+                .all(|row| !row.contains("\x1b[41m") && !row.contains("\x1b[2J")),
+            "{rows:#?}"
+        );
+    });
+}
+
+#[test]
+fn max_columns_setting_reaches_rg() {
+    assert_eq!(
+        rg_max_columns_args(&YoinkSettings::default()),
+        vec!["--max-columns", "1000", "--max-columns-preview"]
+    );
+
+    with_system_config("max_columns=0\n", |_| {
+        let settings = load_settings().expect("settings");
+        assert_eq!(settings.max_columns, 0);
+        assert!(rg_max_columns_args(&settings).is_empty());
+    });
+
+    with_system_config("max_columns=wide\n", |_| {
+        let error = load_settings().expect_err("invalid value");
+        assert!(format!("{error:#}").contains("invalid max_columns value in"));
+    });
+
+    with_system_config("max_columns=40\n", |_| {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        let bundle = format!("var needle=1;{}\n", "x".repeat(200));
+        fs::write(root.join("app.min.js"), bundle).expect("write bundle");
+        fs::write(root.join("short.js"), "let needle = 2;\n").expect("write short");
+
+        let entries = build_search_entries("needle", root).expect("entries");
+        let occurrence_rows: Vec<&str> = entries
+            .iter()
+            .filter(|entry| entry.line.is_some())
+            .map(|entry| entry.display.as_str())
+            .collect();
+        assert_eq!(occurrence_rows.len(), 2);
+        // The cut line shows from its start, where the match is.
+        assert!(occurrence_rows
+            .iter()
+            .any(|row| strip_ansi(row).contains("var needle=1;xxx") && row.ends_with('…')));
+        assert!(occurrence_rows.iter().any(|row| row.contains("let")));
+    });
+}
+
+#[test]
+fn occurrence_reader_within_budget_is_not_truncated() {
+    let output = "b.rs:9:1:two\n\nnot an occurrence\nb.rs:3:2:one\n";
+    let scan = read_occurrences(output.as_bytes(), &SMALL_BUDGET).expect("scan");
+    assert!(!scan.truncated);
+
+    let lines: Vec<usize> = scan.occurrences[Path::new("b.rs")]
+        .iter()
+        .map(|occurrence| occurrence.line)
+        .collect();
+    assert_eq!(lines, vec![3, 9]);
+}
+
+fn json_match(path: &str, line_number: usize, lines: &str, start: usize, end: usize) -> String {
+    format!(
+        r#"{{"type":"match","data":{{"path":{{"text":"{path}"}},"lines":{{"text":"{lines}"}},"line_number":{line_number},"absolute_offset":0,"submatches":[{{"match":{{"text":"x"}},"start":{start},"end":{end}}}]}}}}"#
+    )
+}
+
+#[test]
+fn json_reader_lists_every_begin_and_budgets_the_matches() {
+    let mut output = String::new();
+    output.push_str(r#"{"type":"begin","data":{"path":{"text":"./big.sql"}}}"#);
+    output.push('\n');
+    for line in 1..=5 {
+        output.push_str(&json_match("./big.sql", line, "token\\n", 0, 5));
+        output.push('\n');
+    }
+    output.push_str(
+        r#"{"type":"end","data":{"path":{"text":"./big.sql"},"binary_offset":null,"stats":{}}}"#,
+    );
+    output.push('\n');
+    output.push_str(r#"{"type":"begin","data":{"path":{"text":"./ünï.rs"}}}"#);
+    output.push('\n');
+    // The submatch is "🦀", four bytes in after "é = ".
+    output.push_str(&json_match("./ünï.rs", 2, "\\té = 🦀;\\n", 6, 10));
+    output.push('\n');
+    // Cut at `line_bytes`: unparseable, but the file's begin already counted.
+    output.push_str(r#"{"type":"begin","data":{"path":{"text":"./wide.sql"}}}"#);
+    output.push('\n');
+    output.push_str(&json_match("./wide.sql", 1, &"x".repeat(200), 0, 1));
+    output.push('\n');
+    output.push_str(r#"{"type":"begin","data":{"path":{"bytes":"Li9sYXRpbjEtZmlsZS3pLnR4dA=="}}}"#);
+    output.push('\n');
+    output.push_str(
+        r#"{"type":"match","data":{"path":{"bytes":"Li9sYXRpbjEtZmlsZS3pLnR4dA=="},"lines":{"bytes":"eP8gdG9rZW4K"},"line_number":4,"submatches":[{"match":{"text":"token"},"start":3,"end":8}]}}"#,
+    );
+    output.push('\n');
+
+    let budget = OccurrenceBudget {
+        per_file: 3,
+        total: 10,
+        line_bytes: 200,
+    };
+    let content = read_json_occurrences(output.as_bytes(), &budget, 0).expect("scan");
+    let mut files: Vec<String> = content
+        .files
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    files.sort();
+    assert_eq!(
+        files,
+        vec!["big.sql", "latin1-file-\u{FFFD}.txt", "wide.sql", "ünï.rs"]
+    );
+    assert!(content.scan.truncated);
+
+    let lines: Vec<usize> = content.scan.occurrences[Path::new("big.sql")]
+        .iter()
+        .map(|occurrence| occurrence.line)
+        .collect();
+    assert_eq!(lines, vec![1, 2, 3]);
+
+    let crab = &content.scan.occurrences[Path::new("ünï.rs")][0];
+    assert_eq!(
+        (crab.line, crab.column, crab.snippet.as_str()),
+        (2, 7, "é = 🦀;")
+    );
+    assert!(!content.scan.occurrences.contains_key(Path::new("wide.sql")));
+
+    let latin1 = content
+        .scan
+        .occurrences
+        .iter()
+        .find(|(path, _)| path.to_string_lossy().starts_with("latin1"))
+        .map(|(_, occurrences)| &occurrences[0])
+        .expect("latin1 occurrence");
+    assert_eq!(
+        (latin1.line, latin1.column, latin1.snippet.as_str()),
+        (4, 4, "x\u{FFFD} token")
+    );
+
+    // Past the total budget, begins still come through.
+    let tight = OccurrenceBudget {
+        per_file: 3,
+        total: 1,
+        line_bytes: 200,
+    };
+    let content = read_json_occurrences(output.as_bytes(), &tight, 3).expect("scan");
+    assert_eq!(content.files.len(), 4);
+    let total: usize = content.scan.occurrences.values().map(Vec::len).sum();
+    assert_eq!(total, 1);
+    // Five bytes of "token" is past `max_columns = 3`.
+    assert!(content.scan.occurrences[Path::new("big.sql")][0].long_line);
+}
+
+#[test]
+fn candidates_accept_a_precomputed_content_match_set() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::write(root.join("listed.rs"), "nothing to see\n").expect("write listed");
+    fs::write(root.join("needle.rs"), "plain\n").expect("write needle");
+    fs::write(root.join("hidden.rs"), "needle\n").expect("write hidden");
+
+    // The set is taken as given: hidden.rs holds the text but isn't in it.
+    let content = [PathBuf::from("listed.rs")].into_iter().collect();
+    let candidates = build_candidates_with_content("needle", root, &content).expect("candidates");
+    let found: Vec<(String, bool, bool)> = candidates
+        .iter()
+        .map(|candidate| {
+            (
+                candidate.path.to_string_lossy().to_string(),
+                candidate.path_match,
+                candidate.content_match,
+            )
+        })
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("listed.rs".to_string(), false, true),
+            ("needle.rs".to_string(), true, false)
+        ]
+    );
+}
+
+#[test]
+fn one_json_pass_lists_content_matches_with_their_occurrences() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::write(
+        root.join("a.rs"),
+        "fn main() {\n    let crab = \"🦀\";\n}\n",
+    )
+    .expect("write a");
+    fs::write(root.join("b.rs"), "nothing\n").expect("write b");
+
+    let entries = build_search_entries("crab", root).expect("entries");
+    let rows: Vec<(String, Option<usize>, Option<usize>)> = entries
+        .iter()
+        .map(|entry| {
+            (
+                entry.path.to_string_lossy().to_string(),
+                entry.line,
+                entry.column,
+            )
+        })
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            ("a.rs".to_string(), None, None),
+            ("a.rs".to_string(), Some(2), Some(9))
+        ]
+    );
+}
+
+fn file_entry(path: &str) -> SearchEntry {
+    SearchEntry {
+        display: format!("📄 {path}"),
+        path: path.into(),
+        line: None,
+        column: None,
+        kind: None,
+        match_count: None,
+    }
+}
+
+fn occurrence_entry(path: &str, line: usize) -> SearchEntry {
+    SearchEntry {
+        display: format!("   ↳ {line}"),
+        path: path.into(),
+        line: Some(line),
+        column: Some(1),
+        kind: None,
+        match_count: None,
+    }
+}
+
+#[test]
+fn numbers_only_the_first_nine_file_rows() {
+    let mut entries = vec![
+        notice_entry("literal"),
+        file_entry("a.rs"),
+        occurrence_entry("a.rs", 3),
+    ];
+    entries.extend((0..10).map(|index| file_entry(&format!("f{index}.rs"))));
+
+    let rows = number_file_rows(&mut entries, ColorMode::Plain);
+    // Positions are 1-based and skip the notice and occurrence rows.
+    assert_eq!(rows, vec![2, 4, 5, 6, 7, 8, 9, 10, 11]);
+    assert_eq!(entries[1].display, "[1] 📄 a.rs");
+    assert_eq!(entries[2].display, "   ↳ 3");
+    assert_eq!(entries[10].display, "[9] 📄 f7.rs");
+    assert_eq!(entries[11].display, "📄 f8.rs");
+
+    let mut ansi = vec![file_entry("a.rs")];
+    number_file_rows(&mut ansi, ColorMode::Ansi);
+    assert_eq!(ansi[0].display, "\x1b[2m[1]\x1b[0m 📄 a.rs");
+}
+
+#[test]
+fn numbering_is_stable_across_reloads() {
+    with_system_config("", |_| {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        for name in ["b.rs", "a.rs", "c.txt"] {
+            fs::write(root.join(name), "needle\n").expect("write");
+        }
+
+        let mut first = build_search_entries("needle", root).expect("first reload");
+        let mut second = build_search_entries("needle", root).expect("second reload");
+        let first_rows = number_file_rows(&mut first, ColorMode::Plain);
+        let second_rows = number_file_rows(&mut second, ColorMode::Plain);
+
+        assert_eq!(first_rows, vec![1, 3, 5]);
+        assert_eq!(first_rows, second_rows);
+        assert_eq!(first, second);
+    });
+}
+
+#[test]
+fn jump_actions_target_numbered_rows() {
+    let rows = vec![2, 5, 9];
+    assert_eq!(jump_action(&rows, 1), "pos(2)");
+    assert_eq!(jump_action(&rows, 3), "pos(9)");
+    assert_eq!(jump_action(&rows, 4), "");
+    assert_eq!(jump_action(&rows, 0), "");
+}
+
+fn write_smart_defaults_fixture(root: &Path) {
+    for file in [
+        "src/main.rs",
+        "target/debug/app.d",
+        "dist/bundle.js",
+        "build/out.o",
+        "vendor/github.com/lib/lib.go",
+        "pkg/__pycache__/mod.cpython-312.pyc",
+        ".venv/lib/site.py",
+        "infra/.terraform/providers.json",
+        "Cargo.lock",
+    ] {
+        let path = root.join(file);
+        fs::create_dir_all(path.parent().expect("parent")).expect("mkdir");
+        fs::write(path, "needle\n").expect("write");
+    }
+}
+
+#[test]
+fn smart_defaults_exclude_every_listed_layout() {
+    with_system_config("include_hidden=true\n", |_| {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        write_smart_defaults_fixture(root);
+
+        let stats = collect_ignore_stats("", root).expect("ignore stats");
+        for pattern in SMART_DEFAULT_GLOBS {
+            let stat = stats
+                .iter()
+                .find(|stat| stat.pattern == *pattern)
+                .expect("smart default is active");
+            assert!(stat.hits > 0, "{pattern} excluded nothing");
+            assert_eq!(stat.origin.label(), "smart default");
+        }
+
+        let paths: Vec<String> = build_candidates("needle", root)
+            .expect("candidates")
+            .into_iter()
+            .filter(|candidate| !candidate.is_dir)
+            .map(|candidate| candidate.path.to_string_lossy().to_string())
+            .collect();
+        assert_eq!(paths, vec!["src/main.rs"]);
+    });
+}
+
+#[test]
+fn smart_defaults_list_lockfiles_without_searching_them() {
+    with_system_config("", |_| {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        write_smart_defaults_fixture(root);
+
+        let candidates = build_candidates("Cargo|needle", root).expect("candidates");
+        let lock = candidates
+            .iter()
+            .find(|candidate| candidate.path == Path::new("Cargo.lock"))
+            .expect("lockfile listed by path");
+        assert!(lock.path_match);
+        assert!(!lock.content_match);
+    });
+}
+
+#[test]
+fn smart_defaults_can_be_turned_off() {
+    with_system_config("smart_defaults=false\n", |_| {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        write_smart_defaults_fixture(root);
+
+        let settings = load_settings().expect("settings");
+        assert!(!settings.globs.iter().any(|glob| glob == "target/**"));
+        assert!(settings.suppress_globs.is_empty());
+
+        let candidates = build_candidates("needle", root).expect("candidates");
+        for path in ["target/debug/app.d", "build/out.o", "Cargo.lock"] {
+            assert!(
+                candidates
+                    .iter()
+                    .any(|candidate| candidate.path == Path::new(path) && candidate.content_match),
+                "{path} should be searched"
+            );
+        }
+    });
+}
+
+#[test]
+fn smart_defaults_override_toggles_both_ways() {
+    with_system_config("build/**\n", |_| {
+        let settings = load_settings().expect("settings");
+        let off = resolve_settings(
+            settings.clone(),
+            &[SettingOverrides {
+                smart_defaults: Some(false),
+                ..SettingOverrides::default()
+            }],
+        );
+        assert!(!off.smart_defaults);
+        assert_eq!(off.globs, vec![".git/**", "node_modukes/**", "build/**"]);
+        assert!(off.globset.is_match("build/x") && !off.globset.is_match("target/x"));
+        assert_eq!(off.globset.origins()[2].line, 1);
+
+        let mut on = off.clone();
+        on.set_smart_defaults(true).expect("toggle on");
+        assert_eq!(on, settings);
+        assert!(on.globset.is_match("target/x"));
+    });
+}
+
+#[test]
+fn adversarial_queries_are_searched_for_not_run() {
+    with_system_config("", |home| {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        let marker = home.join("pwned");
+        let substitution = format!("$(touch {})", marker.display());
+        let backticks = format!("`touch {}`", marker.display());
+
+        fs::write(root.join("flags.txt"), "rm -rf --version\n").expect("write flags");
+        fs::write(
+            root.join("shell.txt"),
+            format!("{substitution}\n{backticks}\n"),
+        )
+        .expect("write shell");
+
+        // `$(…)` is a valid regex (an end anchor then a group), so it matches nothing.
+        for (query, expected) in [
+            ("--version", Some("flags.txt")),
+            ("-rf", Some("flags.txt")),
+            (backticks.as_str(), Some("shell.txt")),
+            (substitution.as_str(), None),
+        ] {
+            let matched: Vec<_> = build_candidates(query, root)
+                .expect("candidates")
+                .into_iter()
+                .filter(|candidate| candidate.content_match)
+                .map(|candidate| candidate.path)
+                .collect();
+            assert_eq!(
+                matched,
+                expected.map(PathBuf::from).into_iter().collect::<Vec<_>>(),
+                "{query:?}"
+            );
+            build_search_entries(query, root).expect("entries");
+        }
+        assert!(!marker.exists());
+    });
+}
+
+#[test]
+fn case_insensitive_setting_matches_paths_contents_and_highlights() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::write(root.join("ejectReasonsList.csv"), "header\n").expect("write csv");
+    fs::write(root.join("example.py"), "print('EjectReasons')\n").expect("write py");
+
+    with_system_config("", |_| {
+        let candidates = build_candidates("ejectreasons", root).expect("candidates");
+        assert!(candidates.is_empty());
+    });
+
+    with_system_config("case_insensitive=true\n", |_| {
+        assert_eq!(
+            load_settings().expect("settings").case_mode,
+            CaseMode::Insensitive
+        );
+
+        let candidates = build_candidates("ejectreasons", root).expect("candidates");
+        assert!(candidates.iter().any(|candidate| candidate.path
+            == Path::new("ejectReasonsList.csv")
+            && candidate.path_match));
+        assert!(candidates
+            .iter()
+            .any(|candidate| candidate.path == Path::new("example.py") && candidate.content_match));
+
+        let entries = build_search_entries("ejectreasons", root).expect("entries");
+        let occurrence = entries
+            .iter()
+            .find(|entry| entry.path == Path::new("example.py") && entry.line == Some(1))
+            .expect("occurrence row");
+        assert!(occurrence.display.contains("\x1b[1;36mEjectReasons\x1b[0m"));
+        let file_row = entries
+            .iter()
+            .find(|entry| entry.path == Path::new("ejectReasonsList.csv"))
+            .expect("file row");
+        assert!(file_row.display.contains("\x1b[1;35mejectReasons\x1b[0m"));
+    });
+
+    with_system_config("case_insensitive=maybe\n", |_| {
+        let error = load_settings().expect_err("invalid value");
+        assert!(format!("{error:#}").contains("invalid case_insensitive value in"));
+    });
+}
+
+#[test]
+fn file_rows_badge_and_color_each_match_kind() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::write(root.join("needle.txt"), "nothing here\n").expect("write path match");
+    fs::write(root.join("haystack.txt"), "a needle inside\n").expect("write content match");
+    fs::write(root.join("needle_notes.txt"), "needle again\n").expect("write both");
+
+    let row = |entries: &[SearchEntry], path: &str| {
+        entries
+            .iter()
+            .find(|entry| entry.path == Path::new(path) && entry.line.is_none())
+            .cloned()
+            .expect("file row")
+    };
+
+    with_system_config("show_language=false\n", |_| {
+        let entries = build_search_entries("needle", root).expect("entries");
+
+        let path_only = row(&entries, "needle.txt");
+        assert_eq!(path_only.kind, Some(MatchKind::Path));
+        assert_eq!(
+            path_only.display,
+            "📄 \x1b[2m[p]\x1b[0m \x1b[1;35mneedle\x1b[0m.txt"
+        );
+
+        let content_only = row(&entries, "haystack.txt");
+        assert_eq!(content_only.kind, Some(MatchKind::Content));
+        assert_eq!(
+            content_only.display,
+            "📄 \x1b[2m[c]\x1b[0m haystack.txt \x1b[33m(1)\x1b[0m"
+        );
+        assert_eq!(content_only.match_count, Some(1));
+        // Path-only matches carry no count.
+        assert_eq!(path_only.match_count, None);
+
+        let both = row(&entries, "needle_notes.txt");
+        assert_eq!(both.kind, Some(MatchKind::Both));
+        assert!(both
+            .display
+            .starts_with("📄 \x1b[2m[pc]\x1b[0m \x1b[1;35mneedle\x1b[0m"));
+
+        let snippet = entries
+            .iter()
+            .find(|entry| entry.path == Path::new("haystack.txt") && entry.line == Some(1))
+            .expect("occurrence row");
+        assert_eq!(snippet.kind, None);
+        assert!(snippet.display.contains("a \x1b[1;36mneedle\x1b[0m inside"));
+    });
+
+    with_system_config(
+        "show_language=false\npath_match_color=green\ncontent_match_color=93\n",
+        |_| {
+            let entries = build_search_entries("needle", root).expect("entries");
+            assert!(row(&entries, "needle.txt")
+                .display
+                .contains("\x1b[1;32mneedle"));
+            assert!(entries
+                .iter()
+                .any(|entry| entry.line.is_some() && entry.display.contains("\x1b[1;93mneedle")));
+        },
+    );
+
+    with_system_config("path_match_color=mauve\n", |_| {
+        let error = load_settings().expect_err("invalid color");
+        assert!(format!("{error:#}").contains("invalid path_match_color value in"));
+    });
+}
+
+#[test]
+fn smart_case_ignores_case_only_for_lowercase_queries() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::write(root.join("ejectReasonsList.csv"), "header\n").expect("write csv");
+    fs::write(
+        root.join("example.py"),
+        "print('EjectReasons')\nprint('ejectreasons')\n",
+    )
+    .expect("write py");
+
+    with_system_config("case_mode=smart\n", |_| {
+        let lower = build_candidates("ejectreasons", root).expect("lowercase");
+        assert!(lower.iter().any(
+            |candidate| candidate.path == Path::new("ejectReasonsList.csv") && candidate.path_match
+        ));
+        let entries = build_search_entries("ejectreasons", root).expect("entries");
+        let lines: Vec<usize> = entries
+            .iter()
+            .filter(|entry| entry.path == Path::new("example.py"))
+            .filter_map(|entry| entry.line)
+            .collect();
+        assert_eq!(lines, vec![1, 2]);
+
+        let mixed = build_candidates("EjectReasons", root).expect("mixed case");
+        assert!(!mixed.iter().any(|candidate| candidate.path_match));
+        let entries = build_search_entries("EjectReasons", root).expect("entries");
+        let lines: Vec<usize> = entries.iter().filter_map(|entry| entry.line).collect();
+        assert_eq!(lines, vec![1]);
+
+        // `\W` is a class, not an uppercase letter, so this still ignores case.
+        let entries = build_search_entries(r"\Wejectreasons\W", root).expect("entries");
+        let lines: Vec<usize> = entries.iter().filter_map(|entry| entry.line).collect();
+        assert_eq!(lines, vec![1, 2]);
+    });
+
+    with_system_config("case_mode=loud\n", |_| {
+        let error = load_settings().expect_err("invalid value");
+        assert!(format!("{error:#}").contains("invalid case_mode value in"));
+    });
+}
+
+#[test]
+fn session_config_snapshot_is_only_recaptured_on_hot_reload_or_request() {
+    let dir = tempdir().expect("tempdir");
+    let ignore = dir.path().join("ignore");
+    let sources = vec![ConfigSource::ignore(ignore.clone())];
+    let session = Session::new(dir.path().join("sessions"), "snap".to_string());
+    let launched = SystemTime::now() - Duration::from_secs(60);
+    let write = |content: &str, modified: SystemTime| {
+        fs::write(&ignore, content).expect("write ignore");
+        fs::File::options()
+            .write(true)
+            .open(&ignore)
+            .and_then(|file| file.set_modified(modified))
+            .expect("set mtime");
+    };
+
+    write("logs/**\n", launched);
+    let settings = load_session_settings(&session, &sources).expect("first load");
+    assert!(settings.globs.contains(&"logs/**".to_string()));
+    assert!(session.config().is_some());
+
+    // Without hot reload the snapshot wins, even after the file changed.
+    write("logs/**\nfixtures/**\n", launched + Duration::from_secs(10));
+    let settings = load_session_settings(&session, &sources).expect("snapshot load");
+    assert!(!settings.globs.contains(&"fixtures/**".to_string()));
+
+    // `alt-i` recaptures explicitly.
+    let settings = reload_session_config(&session, &sources).expect("reload");
+    assert!(settings.globs.contains(&"fixtures/**".to_string()));
+
+    // With hot reload, an mtime change invalidates the snapshot on the next search.
+    write(
+        "hot_reload_config = true\n",
+        launched + Duration::from_secs(20),
+    );
+    reload_session_config(&session, &sources).expect("reload");
+    let before = session.config();
+    write(
+        "hot_reload_config = true\nscratch/**\n",
+        launched + Duration::from_secs(20),
+    );
+    let settings = load_session_settings(&session, &sources).expect("same mtime");
+    assert!(!settings.globs.contains(&"scratch/**".to_string()));
+    assert_eq!(session.config(), before);
+
+    write(
+        "hot_reload_config = true\nscratch/**\n",
+        launched + Duration::from_secs(30),
+    );
+    let settings = load_session_settings(&session, &sources).expect("hot reload");
+    assert!(settings.globs.contains(&"scratch/**".to_string()));
+    assert_ne!(session.config(), before);
+
+    // A broken edit is reported and leaves the last good snapshot in place.
+    let good = session.config();
+    write(
+        "hot_reload_config = maybe\n",
+        launched + Duration::from_secs(40),
+    );
+    assert!(load_session_settings(&session, &sources).is_err());
+    assert_eq!(session.config(), good);
+}
+
+/// Every setting away from its default, plus globs from two files, a demotion
+/// and the smart-default globs and suppressions.
+const EVERY_SETTING: &str = "include_hidden = true\ninclude_mounts = true\ninclude_symlinks = true\n\
+dedupe_inodes = true\nmax_symlink_depth = 2\ninherit_rg_config = true\nsnippet_context = 2\nrespect_gitignore = false\nsort_mode = size\nsize_order = desc\ngroup_dirs_first = true\n\
+search_scope = content\nand_terms = true\nword_boundaries = true\nmatch_basename_only = true\nmultiline = true\n\
+fuzzy_fallback = true\nwalker = rg\nengine = internal\nshow_language = false\nicons = false\nno_color = true\n\
+accessibility = true\nauto_refresh_secs = 5\nwatch = true\ntracked_only = true\narchives = true\nnotify_after_secs = 9\nyank_context = 7\n\
+recent_count = 2\nhistory = false\nhistory_size = 11\nshow_timings = true\nshow_stats = false\ninvalid_regex_fallback = strict\n\
+unicode_fold = accents\npath_match_color = red\ncontent_match_color = 93\ncase_mode = smart\n\
+persist_session_prefs = true\nteaser = true\npath_ellipsis = false\nmax_entry_bytes = 512\nmax_columns = 40\n\
+max_filesize = 10M\nsize_filter_paths = true\nshow_binaries = true\nthreads = 3\n\
+max_depth = 4\nmax_results = 50\nsearch_timeout_ms = 1500\nmax_occurrences_per_file = 6\n\
+occurrence_display_limit = 0\nmodified_within = 2d\nlarge_tree_threshold = 10\n\
+hot_reload_config = true\nconfine_cd_to_root = true\nnumber_file_rows = false\n\
+smart_defaults = true\ntypes = rust,md\ntypes_not = json\n\
+exclude_extensions = .MIN.js, map\nsearch.todo = (TODO|FIXME|HACK)\nsearch.Eq = a=b\npreprocess.PDF = pdftotext {} -\n";
+
+#[test]
+fn saved_searches_parse_from_the_config_and_resolve_by_name() {
+    let snapshot = ConfigSnapshot {
+        files: vec![SnapshotFile {
+            source: ConfigSource::settings(PathBuf::from("/cfg/config.toml")),
+            modified: Some(1),
+            content: Some(
+                "search.todo = (TODO|FIXME|HACK)\nsearch.Assign = \"let \\w+ = \"\n".to_string(),
+            ),
+        }],
+    };
+    let settings = load_settings_from_snapshot(&snapshot).expect("settings");
+    assert_eq!(
+        saved_search(&settings, "todo").expect("todo"),
+        "(TODO|FIXME|HACK)"
+    );
+    assert_eq!(
+        saved_search(&settings, "ASSIGN").expect("assign"),
+        "let \\w+ = "
+    );
+    assert_eq!(
+        format_saved_searches(&settings),
+        "assign = let \\w+ = \ntodo   = (TODO|FIXME|HACK)\n"
+    );
+
+    let error = saved_search(&settings, "fixme")
+        .expect_err("unknown")
+        .to_string();
+    assert_eq!(error, "unknown saved search fixme; available: assign, todo");
+    let plain = load_settings_from_snapshot(&ConfigSnapshot::default()).expect("defaults");
+    assert!(saved_search(&plain, "todo")
+        .expect_err("none")
+        .to_string()
+        .contains("none are configured"));
+
+    let bad = ConfigSnapshot {
+        files: vec![SnapshotFile {
+            source: ConfigSource::settings(PathBuf::from("/cfg/config.toml")),
+            modified: Some(1),
+            content: Some("search. = x\n".to_string()),
+        }],
+    };
+    assert!(load_settings_from_snapshot(&bad).is_err());
+}
+
+#[test]
+fn settings_cache_round_trips_every_field() {
+    let snapshot = ConfigSnapshot {
+        files: vec![
+            SnapshotFile {
+                source: ConfigSource::settings(PathBuf::from("/cfg/config.toml")),
+                modified: Some(1),
+                content: Some(EVERY_SETTING.to_string()),
+            },
+            SnapshotFile {
+                source: ConfigSource::ignore(PathBuf::from("/home/me/.yoinkignore")),
+                modified: Some(2),
+                content: Some("logs/**\n~docs/\"quoted\"/**\n*.tmp\n".to_string()),
+            },
+            SnapshotFile {
+                source: ConfigSource::ignore(PathBuf::from("/work/.yoinkignore")),
+                modified: None,
+                content: Some("# project\n!logs/keep.log\nünïcode/**\n".to_string()),
+            },
+        ],
+    };
+    let mut settings = load_settings_from_snapshot(&snapshot).expect("settings");
+    settings.pills = vec![
+        FilterPill::Ext("rs".to_string()),
+        FilterPill::Dir(PathBuf::from("crates/core")),
+    ];
+    let fingerprint = snapshot.fingerprint();
+    assert_eq!(
+        settings.preprocessor_for(Path::new("docs/a.pdf")),
+        Some("pdftotext {} -")
+    );
+
+    let decoded =
+        decode_settings(&encode_settings(&settings, fingerprint), fingerprint).expect("decoded");
+    assert_eq!(decoded, settings);
+    assert_eq!(decoded.globset.origins(), settings.globset.origins());
+    assert!(decoded.globset.is_match("logs/app.log"));
+    assert!(!decoded.globset.is_match("logs/keep.log"));
+    assert!(decoded.demote_globset.is_match("docs/\"quoted\"/a.md"));
+    // Every scalar in `EVERY_SETTING` is off its default, so a dropped field would show.
+    let plain = load_settings_from_snapshot(&ConfigSnapshot::default()).expect("defaults");
+    assert_ne!(decoded, plain);
+    assert_eq!(
+        decode_settings(&encode_settings(&plain, 7), 7),
+        Some(plain.clone())
+    );
+
+    // Parsed from another snapshot, or not a cache at all: load afresh.
+    assert_eq!(decode_settings(&encode_settings(&plain, 7), 8), None);
+    assert_eq!(decode_settings("{\"version\":1}", 7), None);
+    assert_eq!(decode_settings("not json", 7), None);
+    let unknown_key = encode_settings(&plain, 7).replace("[\"icons\"", "[\"no_such_setting\"");
+    assert_eq!(decode_settings(&unknown_key, 7), None);
+}
+
+#[test]
+fn session_reloads_read_the_cached_settings_until_the_snapshot_changes() {
+    let dir = tempdir().expect("tempdir");
+    let ignore = dir.path().join("ignore");
+    let sources = vec![ConfigSource::ignore(ignore.clone())];
+    let session = Session::new(dir.path().join("sessions"), "cache".to_string());
+    fs::write(&ignore, "logs/**\n").expect("write ignore");
+
+    let settings = load_session_settings(&session, &sources).expect("first load");
+    let cache = session.settings_cache().expect("cache written");
+    let fingerprint = session.config().expect("snapshot").fingerprint();
+    assert_eq!(decode_settings(&cache, fingerprint), Some(settings));
+
+    // Reloads take the cache as written rather than reparsing the snapshot.
+    session
+        .store_settings_cache(&cache.replace("[\"teaser\",\"false\"]", "[\"teaser\",\"true\"]"))
+        .expect("edit cache");
+    assert!(
+        load_session_settings(&session, &sources)
+            .expect("cached load")
+            .teaser
+    );
+
+    // A cache parsed from another snapshot is rebuilt from the current one.
+    reload_session_config(&session, &sources).expect("reload");
+    let mut state = session.load();
+    state.config = Some(ConfigSnapshot::capture(&sources).expect("capture"));
+    state.config.as_mut().expect("snapshot").files[0].content = Some("fixtures/**\n".to_string());
+    session.store(&state).expect("store");
+    let settings = load_session_settings(&session, &sources).expect("rebuilt");
+    assert!(settings.globs.contains(&"fixtures/**".to_string()));
+    assert!(!settings.teaser);
+
+    // `--fresh-settings` reads the file on every call, snapshot or not.
+    let mut state = session.load();
+    state.fresh_settings = true;
+    session.store(&state).expect("store");
+    fs::write(&ignore, "scratch/**\n").expect("rewrite ignore");
+    let settings = load_session_settings(&session, &sources).expect("fresh load");
+    assert!(settings.globs.contains(&"scratch/**".to_string()));
+
+    session.remove();
+    assert!(session.settings_cache().is_none());
+}
+
+#[test]
+fn type_filters_keep_matching_files_and_path_matched_directories() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::create_dir(root.join("needle_docs")).expect("mkdir");
+    fs::write(root.join("needle_docs/guide.md"), "no match here\n").expect("write md");
+    fs::write(root.join("foo.py"), "needle = 1\n").expect("write py");
+    fs::write(root.join("bar.rs"), "let needle = 1;\n").expect("write rs");
+    fs::write(root.join("needle.toml"), "x = 1\n").expect("write toml");
+
+    let paths = |candidates: Vec<Candidate>| -> Vec<String> {
+        let mut paths: Vec<String> = candidates
+            .into_iter()
+            .map(|candidate| candidate.path.to_string_lossy().into_owned())
+            .collect();
+        paths.sort();
+        paths
+    };
+
+    with_system_config("types=rust\n", |_| {
+        let candidates = build_candidates("needle", root).expect("candidates");
+        assert_eq!(paths(candidates), vec!["bar.rs", "needle_docs"]);
+
+        let entries = build_search_entries("needle", root).expect("entries");
+        assert!(entries
+            .iter()
+            .all(|entry| entry.path != Path::new("foo.py")));
+        assert!(entries
+            .iter()
+            .any(|entry| entry.path == Path::new("bar.rs") && entry.line == Some(1)));
+    });
+
+    with_system_config("types_not=py,toml\n", |_| {
+        let candidates = build_candidates("needle", root).expect("candidates");
+        assert_eq!(
+            paths(candidates),
+            vec!["bar.rs", "needle_docs", "needle_docs/guide.md"]
+        );
+    });
+
+    with_system_config("types=cobol\n", |_| {
+        let error = load_settings().expect_err("invalid value");
+        assert!(format!("{error:#}").contains("invalid types value in"));
+    });
+}
+
+#[test]
+fn session_pills_narrow_paths_and_contents() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::create_dir_all(root.join("src/needle")).expect("mkdir src");
+    fs::create_dir(root.join("docs")).expect("mkdir docs");
+    fs::write(root.join("src/lib.rs"), "fn needle() {}\n").expect("write rs");
+    fs::write(root.join("src/notes.md"), "needle notes\n").expect("write src md");
+    fs::write(root.join("docs/needle.md"), "needle\n").expect("write docs md");
+    fs::write(root.join("build.rs"), "// needle\n").expect("write root rs");
+
+    with_system_config("", |home| {
+        let runtime = home.join("runtime");
+        let session = Session::new(runtime.join("yoink"), "pills-test".to_string());
+        session
+            .store(&SessionState::default())
+            .expect("store session");
+        session
+            .push_pill(FilterPill::Dir(PathBuf::from("src")))
+            .expect("dir pill");
+        std::env::set_var("XDG_RUNTIME_DIR", &runtime);
+        std::env::set_var("YOINK_SESSION", session.id());
+
+        let by_dir = build_candidates("needle", root);
+        session
+            .push_pill(FilterPill::Ext("rs".to_string()))
+            .expect("ext pill");
+        let by_both = build_candidates("needle", root);
+        session.pop_pill().expect("pop");
+        session.pop_pill().expect("pop");
+        let unfiltered = build_candidates("needle", root);
+
+        std::env::remove_var("YOINK_SESSION");
+        std::env::remove_var("XDG_RUNTIME_DIR");
+
+        let paths = |candidates: Vec<Candidate>| -> Vec<String> {
+            let mut paths: Vec<String> = candidates
+                .into_iter()
+                .map(|candidate| candidate.path.to_string_lossy().into_owned())
+                .collect();
+            paths.sort();
+            paths
+        };
+        assert_eq!(
+            paths(by_dir.expect("by dir")),
+            vec!["src/lib.rs", "src/needle", "src/notes.md"]
+        );
+        assert_eq!(
+            paths(by_both.expect("by both")),
+            vec!["src/lib.rs", "src/needle"]
+        );
+        assert_eq!(
+            paths(unfiltered.expect("unfiltered")),
+            vec![
+                "build.rs",
+                "docs/needle.md",
+                "src/lib.rs",
+                "src/needle",
+                "src/notes.md"
+            ]
+        );
+    });
+}
+
+#[test]
+fn max_depth_setting_bounds_path_and_content_matches() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::create_dir_all(root.join("a/b")).expect("mkdir");
+    fs::write(root.join("needle_top.txt"), "needle\n").expect("write depth 1");
+    fs::write(root.join("a/needle_mid.txt"), "needle\n").expect("write depth 2");
+    fs::write(root.join("a/b/needle_deep.txt"), "needle\n").expect("write depth 3");
+    fs::write(root.join("a/b/plain.txt"), "needle\n").expect("write depth 3 content");
+
+    with_system_config("max_depth=2\n", |_| {
+        assert_eq!(load_settings().expect("settings").max_depth, Some(2));
+
+        let mut paths: Vec<(String, bool, bool)> = build_candidates("needle", root)
+            .expect("candidates")
+            .into_iter()
+            .map(|candidate| {
+                (
+                    candidate.path.to_string_lossy().into_owned(),
+                    candidate.path_match,
+                    candidate.content_match,
+                )
+            })
+            .collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                ("a/needle_mid.txt".to_string(), true, true),
+                ("needle_top.txt".to_string(), true, true),
+            ]
+        );
+
+        let entries = build_search_entries("needle", root).expect("entries");
+        assert!(entries.iter().all(|entry| !entry.path.starts_with("a/b")));
+    });
+
+    // Depth counts from the search root, wherever that sits on disk.
+    with_system_config("max_depth=1\n", |_| {
+        let nested = build_candidates("needle", &root.join("a")).expect("nested root");
+        let paths: Vec<_> = nested
+            .iter()
+            .map(|candidate| candidate.path.clone())
+            .collect();
+        assert_eq!(paths, vec![PathBuf::from("needle_mid.txt")]);
+    });
+
+    for bad in ["0", "-1", "deep"] {
+        with_system_config(&format!("max_depth={bad}\n"), |home| {
+            let error = format!("{:#}", load_settings().expect_err("invalid value"));
+            assert!(error.contains("invalid max_depth value in"), "{error}");
+            assert!(
+                error.contains(&home.join(".yoinkignore").display().to_string()),
+                "{error}"
+            );
+            assert!(error.ends_with(&format!(": {bad}")), "{error}");
+        });
+    }
+}
+
+#[test]
+fn include_symlinks_folds_hard_links_and_symlinks_into_one_candidate() {
+    with_system_config("include_symlinks=true\n", |home| {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        fs::create_dir_all(root.join("src/nested")).expect("create src");
+        fs::write(root.join("src/nested/needle.rs"), "fn needle() {}\n").expect("write file");
+        fs::hard_link(
+            root.join("src/nested/needle.rs"),
+            root.join("needle_link.rs"),
+        )
+        .expect("hard link");
+        std::os::unix::fs::symlink("nested", root.join("src/mirror")).expect("symlink dir");
+        fs::write(root.join("other.rs"), "fn needle_other() {}\n").expect("write other");
+
+        let runtime = home.join("runtime");
+        let session = Session::new(runtime.join("yoink"), "alias-test".to_string());
+        session
+            .store(&SessionState::default())
+            .expect("store session");
+        std::env::set_var("XDG_RUNTIME_DIR", &runtime);
+        std::env::set_var("YOINK_SESSION", session.id());
+
+        let candidates = build_candidates("needle", root);
+        let entries = build_search_entries("needle", root);
+        let recorded = session.aliases_of(Path::new("needle_link.rs"));
+
+        std::env::remove_var("YOINK_SESSION");
+        std::env::remove_var("XDG_RUNTIME_DIR");
+        let candidates = candidates.expect("candidates");
+        let entries = entries.expect("entries");
+
+        // The root-level hard link is the shallowest path, so it is the one kept.
+        let kept: Vec<&Candidate> = candidates
+            .iter()
+            .filter(|candidate| candidate.path == Path::new("needle_link.rs"))
+            .collect();
+        assert_eq!(kept.len(), 1);
+        assert_eq!(
+            kept[0].aliases,
+            vec![
+                PathBuf::from("src/mirror/needle.rs"),
+                PathBuf::from("src/nested/needle.rs")
+            ]
+        );
+        assert!(kept[0].path_match && kept[0].content_match);
+        assert!(!candidates
+            .iter()
+            .any(|candidate| candidate.path.ends_with("needle.rs")));
+        assert!(candidates
+            .iter()
+            .any(|candidate| candidate.path == Path::new("other.rs")));
+        assert_eq!(recorded, kept[0].aliases);
+
+        let row = entries
+            .iter()
+            .find(|entry| entry.path == Path::new("needle_link.rs") && entry.line.is_none())
+            .expect("kept row");
+        assert!(row.display.contains(&alias_suffix(2)));
+        // Occurrences are read once, through the kept path only.
+        let occurrences: Vec<&SearchEntry> = entries
+            .iter()
+            .filter(|entry| entry.line.is_some())
+            .collect();
+        assert_eq!(occurrences.len(), 2);
+        assert!(!entries
+            .iter()
+            .any(|entry| entry.path.ends_with("needle.rs")));
+    });
+}
+
+#[cfg(target_family = "unix")]
+#[test]
+fn include_symlinks_follows_links_out_of_the_root_once_and_breaks_loops() {
+    use std::os::unix::fs::symlink;
+
+    let outside = tempdir().expect("outside");
+    fs::create_dir(outside.path().join("deep")).expect("mkdir deep");
+    fs::write(outside.path().join("data.txt"), "needle\n").expect("write data");
+    symlink(outside.path(), outside.path().join("deep/back")).expect("link back up");
+
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::create_dir(root.join("src")).expect("mkdir src");
+    fs::write(root.join("src/lib.rs"), "needle\n").expect("write lib");
+    symlink(".", root.join("src/self")).expect("self link");
+    symlink("loop", root.join("loop")).expect("looping link");
+    symlink(outside.path(), root.join("ext")).expect("ext link");
+    symlink(outside.path(), root.join("ext_again")).expect("second ext link");
+
+    for lister in ["threads=1\n", "threads=4\n", "walker=rg\n"] {
+        with_system_config(&format!("include_symlinks=true\n{lister}"), |_| {
+            let candidates = build_candidates("", root).expect("candidates");
+            let paths: Vec<&Path> = candidates
+                .iter()
+                .map(|candidate| candidate.path.as_path())
+                .collect();
+
+            // Links back into the tree or up a followed one are listed, not entered.
+            assert!(
+                paths.contains(&Path::new("src/self")),
+                "{lister:?}: {paths:?}"
+            );
+            assert!(
+                !paths
+                    .iter()
+                    .any(|path| path.starts_with("src/self") && *path != Path::new("src/self")),
+                "{lister:?}"
+            );
+            assert!(!paths.contains(&Path::new("loop")), "{lister:?}");
+            assert_eq!(
+                paths
+                    .iter()
+                    .filter(|path| path.ends_with("data.txt"))
+                    .count(),
+                1,
+                "{lister:?}: {paths:?}"
+            );
+            assert!(
+                !paths.iter().any(|path| path.ends_with("back/data.txt")),
+                "{lister:?}"
+            );
+
+            let found = build_candidates("needle", root).expect("content candidates");
+            assert_eq!(
+                found
+                    .iter()
+                    .filter(|candidate| candidate.path.ends_with("data.txt"))
+                    .count(),
+                1
+            );
+        });
+    }
+
+    with_system_config("include_symlinks=true\nmax_symlink_depth=0\n", |_| {
+        let paths: Vec<PathBuf> = build_candidates("", root)
+            .expect("candidates")
+            .into_iter()
+            .map(|candidate| candidate.path)
+            .collect();
+        assert!(paths.contains(&PathBuf::from("ext")));
+        assert!(!paths.iter().any(|path| path.ends_with("data.txt")));
+    });
+    with_system_config("max_symlink_depth=deep\n", |_| {
+        let error = load_settings().expect_err("invalid value");
+        assert!(format!("{error:#}").contains("invalid max_symlink_depth value in"));
+    });
+}
+
+#[test]
+fn symlinked_files_stay_separate_without_include_symlinks() {
+    with_system_config("", |_| {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        fs::write(root.join("needle.rs"), "fn needle() {}\n").expect("write file");
+        fs::hard_link(root.join("needle.rs"), root.join("needle_copy.rs")).expect("hard link");
+
+        let candidates = build_candidates("needle", root).expect("candidates");
+        assert_eq!(candidates.len(), 2);
+        assert!(candidates
+            .iter()
+            .all(|candidate| candidate.aliases.is_empty()));
+    });
+}
+
+#[cfg(target_family = "unix")]
+#[test]
+fn dedupe_inodes_keeps_the_shallowest_hard_link_without_following_symlinks() {
+    with_system_config("dedupe_inodes=true\n", |_| {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        fs::create_dir_all(root.join("src/deep")).expect("create dirs");
+        fs::write(root.join("src/deep/needle.rs"), "fn needle() {}\n").expect("write file");
+        fs::hard_link(root.join("src/deep/needle.rs"), root.join("needle_copy.rs"))
+            .expect("hard link");
+        std::os::unix::fs::symlink(root.join("src"), root.join("src_link")).expect("symlink");
+
+        let candidates = build_candidates("needle", root).expect("candidates");
+        let files: Vec<&Candidate> = candidates
+            .iter()
+            .filter(|candidate| !candidate.is_dir)
+            .collect();
+        assert_eq!(files.len(), 1, "{candidates:?}");
+        assert_eq!(files[0].path, PathBuf::from("needle_copy.rs"));
+        assert_eq!(files[0].aliases, [PathBuf::from("src/deep/needle.rs")]);
+        assert!(files[0].content_match);
+    });
+}
+
+#[test]
+fn alias_suffix_and_excludes_describe_aliases() {
+    assert_eq!(alias_suffix(0), "");
+    assert_eq!(alias_suffix(1), " (+1 alias)");
+    assert_eq!(alias_suffix(3), " (+3 aliases)");
+
+    let args = rg_alias_excludes(&[PathBuf::from("src/a*b.rs"), PathBuf::from("lib/{x}.rs")]);
+    assert_eq!(args, ["-g", "!/src/a[*]b.rs", "-g", "!/lib/[{]x[}].rs"]);
+
+    let many: Vec<PathBuf> = (0..300)
+        .map(|index| PathBuf::from(format!("f{index}")))
+        .collect();
+    assert!(rg_alias_excludes(&many).is_empty());
+}
+
+fn write_repo_fixture(root: &Path) {
+    fs::create_dir_all(root.join(".git/info")).expect("create .git");
+    fs::create_dir_all(root.join("out/debug")).expect("create out");
+    fs::create_dir_all(root.join("web/bundle")).expect("create bundle");
+    fs::create_dir_all(root.join("web/src")).expect("create web src");
+    fs::write(root.join(".gitignore"), "out/\n*.log\n").expect("write gitignore");
+    fs::write(root.join("web/.gitignore"), "bundle/\n!widget_keep.log\n")
+        .expect("write nested gitignore");
+    fs::write(root.join("out/debug/widget.rs"), "").expect("write out file");
+    fs::write(root.join("web/bundle/widget.js"), "").expect("write bundle file");
+    fs::write(root.join("web/src/widget.js"), "").expect("write source");
+    fs::write(root.join("widget.log"), "").expect("write log");
+    fs::write(root.join("web/widget_keep.log"), "").expect("write kept log");
+}
+
+#[test]
+fn respect_gitignore_prunes_ignored_paths_from_the_walk() {
+    let paths = |candidates: Vec<Candidate>| -> Vec<String> {
+        let mut paths: Vec<String> = candidates
+            .into_iter()
+            .map(|candidate| candidate.path.to_string_lossy().to_string())
+            .collect();
+        paths.sort();
+        paths
+    };
+
+    with_system_config("", |_| {
+        let dir = tempdir().expect("tempdir");
+        write_repo_fixture(dir.path());
+
+        let candidates = build_candidates("widget", dir.path()).expect("candidates");
+        assert_eq!(
+            paths(candidates),
+            ["web/src/widget.js", "web/widget_keep.log"]
+        );
+        assert!(!rg_files_args(&load_settings().expect("settings"))
+            .contains(&"--no-ignore".to_string()));
+    });
+
+    with_system_config("respect_gitignore=false\n", |_| {
+        let dir = tempdir().expect("tempdir");
+        write_repo_fixture(dir.path());
+
+        let candidates = build_candidates("widget", dir.path()).expect("candidates");
+        assert_eq!(
+            paths(candidates),
+            [
+                "out/debug/widget.rs",
+                "web/bundle/widget.js",
+                "web/src/widget.js",
+                "web/widget_keep.log",
+                "widget.log",
+            ]
+        );
+        assert!(
+            rg_files_args(&load_settings().expect("settings")).contains(&"--no-ignore".to_string())
+        );
+    });
+
+    with_system_config("respect_gitignore=sometimes\n", |_| {
+        let error = load_settings().expect_err("invalid value");
+        assert!(format!("{error:#}").contains("invalid respect_gitignore value in"));
+    });
+}
+
+#[test]
+fn notify_after_secs_setting_parses_seconds_and_zero_as_off() {
+    with_system_config("notify_after_secs=30\n", |_| {
+        assert_eq!(
+            load_settings().expect("settings").notify_after_secs,
+            Some(30)
+        );
+    });
+    with_system_config("notify_after_secs=0\n", |_| {
+        assert_eq!(load_settings().expect("settings").notify_after_secs, None);
+    });
+    with_system_config("notify_after_secs=soon\n", |_| {
+        let error = load_settings().expect_err("invalid value");
+        assert!(format!("{error:#}").contains("invalid notify_after_secs value in"));
+    });
+}
+
+#[test]
+fn project_yoinkignore_merges_over_the_global_file() {
+    with_system_config(
+        "include_hidden=false\nsort_mode=alphabetical\nshared/**\nfixtures/**\n",
+        |_| {
+            let dir = tempdir().expect("tempdir");
+            let root = dir.path();
+            fs::write(
+                root.join(".yoinkignore"),
+                "include_hidden=true\ngenerated/**\nshared/**\n~fixtures/**\n",
+            )
+            .expect("write local");
+            fs::create_dir_all(root.join("generated")).expect("create generated");
+            fs::create_dir_all(root.join("fixtures")).expect("create fixtures");
+            fs::write(root.join("generated/needle.rs"), "").expect("write generated");
+            fs::write(root.join("fixtures/needle.rs"), "").expect("write fixture");
+            fs::write(root.join(".needle.rs"), "").expect("write hidden");
+
+            let settings = load_settings_for(root).expect("settings");
+            // The local file wins conflicting keys; the rest of the global one stays.
+            assert!(settings.include_hidden);
+            assert_eq!(settings.sort_mode, SortMode::Alphabetical);
+            assert!(!load_settings().expect("global settings").include_hidden);
+
+            // Globs from both files, once each; a local `~` turns a global exclude into a demote.
+            assert_eq!(
+                settings
+                    .globs
+                    .iter()
+                    .filter(|glob| *glob == "shared/**")
+                    .count(),
+                1
+            );
+            assert!(settings.globs.contains(&"generated/**".to_string()));
+            assert!(!settings.globs.contains(&"fixtures/**".to_string()));
+            assert_eq!(settings.demote_globs, vec!["fixtures/**"]);
+
+            let args = rg_files_args(&settings);
+            assert!(args.windows(2).any(|pair| pair == ["-g", "!generated/**"]));
+            assert!(args.windows(2).any(|pair| pair == ["-g", "!shared/**"]));
+
+            let candidates = build_candidates("needle", root).expect("candidates");
+            let paths: Vec<String> = candidates
+                .iter()
+                .map(|candidate| candidate.path.to_string_lossy().to_string())
+                .collect();
+            assert_eq!(paths, [".needle.rs", "fixtures/needle.rs"]);
+            assert!(candidates[1].demoted);
+        },
+    );
+}
+
+#[test]
+fn parses_human_sizes() {
+    assert_eq!(parse_size_setting("512"), Some(512));
+    assert_eq!(parse_size_setting("500K"), Some(500 * 1024));
+    assert_eq!(parse_size_setting("10m"), Some(10 * 1024 * 1024));
+    assert_eq!(parse_size_setting(" 1G "), Some(1024 * 1024 * 1024));
+    assert_eq!(parse_size_setting("10MB"), None);
+    assert_eq!(parse_size_setting("big"), None);
+    assert_eq!(parse_size_setting(""), None);
+}
+
+#[test]
+fn max_filesize_setting_reaches_rg() {
+    assert!(rg_max_filesize_args(&YoinkSettings::default()).is_empty());
+
+    with_system_config("max_filesize=10M\n", |_| {
+        let settings = load_settings().expect("settings");
+        assert_eq!(settings.max_filesize, Some(10 * 1024 * 1024));
+        assert_eq!(
+            rg_max_filesize_args(&settings),
+            vec!["--max-filesize", "10485760"]
+        );
+    });
+
+    with_system_config("max_filesize=0\n", |_| {
+        assert_eq!(load_settings().expect("settings").max_filesize, None);
+    });
+
+    with_system_config("max_filesize=huge\n", |home| {
+        let error = load_settings().expect_err("invalid value");
+        let message = format!("{error:#}");
+        assert!(
+            message.contains("invalid max_filesize value in"),
+            "{message}"
+        );
+        assert!(
+            message.contains(&home.join(".yoinkignore").display().to_string()),
+            "{message}"
+        );
+    });
+}
+
+#[test]
+fn max_filesize_skips_content_matches_in_huge_files() {
+    with_system_config("max_filesize=1K\n", |_| {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        fs::write(
+            root.join("dump.sql"),
+            format!("needle\n{}\n", "x".repeat(4096)),
+        )
+        .expect("write dump");
+        fs::write(root.join("small.txt"), "needle\n").expect("write small");
+
+        let candidates = build_candidates("needle", root).expect("candidates");
+        let content: Vec<&Path> = candidates
+            .iter()
+            .filter(|candidate| candidate.content_match)
+            .map(|candidate| candidate.path.as_path())
+            .collect();
+        assert_eq!(content, vec![Path::new("small.txt")]);
+
+        let entries = build_search_entries("needle", root).expect("entries");
+        assert!(entries
+            .iter()
+            .filter(|entry| entry.line.is_some())
+            .all(|entry| !entry.display.contains("dump.sql")));
+        // Without size_filter_paths the dump is still listed by path.
+        assert!(build_candidates("", root)
+            .expect("listing")
+            .iter()
+            .any(|candidate| candidate.path == Path::new("dump.sql")));
+    });
+}
+
+#[test]
+fn size_filter_paths_drops_huge_files_from_the_listing() {
+    with_system_config("max_filesize=1K\nsize_filter_paths=true\n", |_| {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        fs::write(root.join("needle.dump"), "x".repeat(4096)).expect("write dump");
+        fs::write(root.join("needle.txt"), "small").expect("write small");
+
+        let paths: Vec<PathBuf> = build_candidates("needle", root)
+            .expect("candidates")
+            .into_iter()
+            .map(|candidate| candidate.path)
+            .collect();
+        assert_eq!(paths, vec![PathBuf::from("needle.txt")]);
+    });
+}
+
+#[test]
+fn parse_duration_setting_reads_units() {
+    assert_eq!(
+        parse_duration_setting("30m"),
+        Some(Duration::from_secs(30 * 60))
+    );
+    assert_eq!(
+        parse_duration_setting("24h"),
+        Some(Duration::from_secs(24 * 3600))
+    );
+    assert_eq!(
+        parse_duration_setting("7d"),
+        Some(Duration::from_secs(7 * 86400))
+    );
+    assert_eq!(
+        parse_duration_setting("2W"),
+        Some(Duration::from_secs(14 * 86400))
+    );
+    assert_eq!(parse_duration_setting("90"), Some(Duration::from_secs(90)));
+    assert_eq!(parse_duration_setting("0d"), None);
+    assert_eq!(parse_duration_setting("soon"), None);
+    assert_eq!(parse_duration_setting(""), None);
+}
+
+fn age_file(path: &Path, days: u64) {
+    fs::File::options()
+        .write(true)
+        .open(path)
+        .expect("open for mtime")
+        .set_modified(SystemTime::now() - Duration::from_secs(days * 86400))
+        .expect("set mtime");
+}
+
+#[test]
+fn modified_within_drops_old_files_from_paths_and_content() {
+    with_system_config(
+        "modified_within=7d
+",
+        |_| {
+            let dir = tempdir().expect("tempdir");
+            let root = dir.path();
+            fs::create_dir(root.join("notes")).expect("mkdir");
+            fs::write(
+                root.join("notes/needle_old.txt"),
+                "needle
+",
+            )
+            .expect("write old");
+            fs::write(
+                root.join("notes/needle_new.txt"),
+                "needle
+",
+            )
+            .expect("write new");
+            age_file(&root.join("notes/needle_old.txt"), 10);
+
+            let candidates = build_candidates("needle", root).expect("candidates");
+            assert!(candidates
+                .iter()
+                .all(|candidate| candidate.path != Path::new("notes/needle_old.txt")));
+            assert!(candidates.iter().any(|candidate| candidate.path
+                == Path::new("notes/needle_new.txt")
+                && candidate.content_match));
+            // Directories aren't dated; only files are filtered.
+            assert!(build_candidates("", root)
+                .expect("listing")
+                .iter()
+                .any(|candidate| candidate.path == Path::new("notes")));
+        },
+    );
+}
+
+#[test]
+fn modified_within_override_and_invalid_values() {
+    let overrides = SettingOverrides {
+        modified_within: Some(Duration::from_secs(3600)),
+        ..SettingOverrides::default()
+    };
+    let settings = resolve_settings(YoinkSettings::default(), &[overrides]);
+    assert_eq!(settings.modified_within, Some(Duration::from_secs(3600)));
+    assert!(settings.modified_cutoff().is_some());
+    assert!(YoinkSettings::default().modified_cutoff().is_none());
+
+    with_system_config("modified_within=lately\n", |home| {
+        let error = load_settings().expect_err("invalid value");
+        let message = format!("{error:#}");
+        assert!(
+            message.contains("invalid modified_within value in"),
+            "{message}"
+        );
+        assert!(message.contains("lately"), "{message}");
+        assert!(
+            message.contains(&home.join(".yoinkignore").display().to_string()),
+            "{message}"
+        );
+    });
+}
+
+#[test]
+fn empty_query_leads_with_recent_selections() {
+    with_state_and_config("", |store| {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        fs::write(root.join("alpha.txt"), "x\n").expect("write alpha");
+        fs::write(root.join("beta.txt"), "x\n").expect("write beta");
+        record_selection(store, root, Path::new("alpha.txt")).expect("record alpha");
+        record_selection(store, root, Path::new("removed.txt")).expect("record removed");
+        record_selection(store, root, Path::new("beta.txt")).expect("record beta");
+
+        let entries = build_search_entries("", root).expect("entries");
+        assert!(entries[0].display.contains(RECENT_SEPARATOR));
+        assert!(entries[0].path.as_os_str().is_empty());
+        let recent: Vec<&Path> = entries[1..3]
+            .iter()
+            .map(|entry| entry.path.as_path())
+            .collect();
+        assert_eq!(recent, vec![Path::new("beta.txt"), Path::new("alpha.txt")]);
+        assert!(entries[1].display.contains("recent"));
+        // The normal listing still follows, paths included again.
+        assert!(entries[3..]
+            .iter()
+            .any(|entry| entry.path == Path::new("beta.txt") && entry.kind.is_some()));
+
+        let typed = build_search_entries("alpha", root).expect("entries");
+        assert!(typed
+            .iter()
+            .all(|entry| !entry.display.contains(RECENT_SEPARATOR)));
+    });
+}
+
+#[test]
+fn recent_count_caps_or_disables_recent_rows() {
+    with_state_and_config("recent_count=1\n", |store| {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        fs::write(root.join("alpha.txt"), "x\n").expect("write alpha");
+        fs::write(root.join("beta.txt"), "x\n").expect("write beta");
+        record_selection(store, root, Path::new("alpha.txt")).expect("record alpha");
+        record_selection(store, root, Path::new("beta.txt")).expect("record beta");
+
+        let settings = load_settings_for(root).expect("settings");
+        let entries = recent_entries(store, root, &settings);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].path, Path::new("beta.txt"));
+    });
+
+    with_state_and_config("recent_count=0\n", |store| {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        fs::write(root.join("alpha.txt"), "x\n").expect("write alpha");
+        record_selection(store, root, Path::new("alpha.txt")).expect("record alpha");
+        let entries = build_search_entries("", root).expect("entries");
+        assert!(entries
+            .iter()
+            .all(|entry| !entry.display.contains(RECENT_SEPARATOR)));
+    });
+}
+
+#[test]
+fn paths_scope_skips_content_matches_and_occurrences() {
+    let run = |config: &str, overrides: SettingOverrides| {
+        let mut files = Vec::new();
+        with_system_config(config, |_| {
+            let dir = tempdir().expect("tempdir");
+            let root = dir.path();
+            fs::write(root.join("notes.txt"), "needle in here\n").expect("write content match");
+            fs::write(root.join("needle.md"), "needle too\n").expect("write name match");
+
+            let session = Session::start().expect("session");
+            let mut state = SessionState::default();
+            state.set_overrides(overrides);
+            session.store(&state).expect("store");
+            std::env::set_var("YOINK_SESSION", session.id());
+            let entries = build_search_entries("needle", root).expect("entries");
+            std::env::remove_var("YOINK_SESSION");
+            session.remove();
+
+            assert!(
+                entries.iter().all(|entry| entry.line.is_none()),
+                "no occurrence rows in paths scope"
+            );
+            files = entries.into_iter().map(|entry| entry.path).collect();
+        });
+        files
+    };
+
+    let paths_only = SettingOverrides {
+        search_scope: Some(SearchScope::Paths),
+        ..SettingOverrides::default()
+    };
+    assert_eq!(run("", paths_only), vec![PathBuf::from("needle.md")]);
+    assert_eq!(
+        run("search_scope=paths\n", SettingOverrides::default()),
+        vec![PathBuf::from("needle.md")]
+    );
+}
+
+#[test]
+fn nested_yoinkignore_excludes_its_subtree_from_paths_and_content() {
+    with_system_config("", |_| {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        for sub in ["team/generated", "team/src", "other/generated"] {
+            fs::create_dir_all(root.join(sub)).expect("create dirs");
+        }
+        fs::write(
+            root.join("team/.yoinkignore"),
+            "generated/\n*.snap\ninclude_hidden = true\n",
+        )
+        .expect("write ignore");
+        for file in [
+            "team/generated/needle.rs",
+            "team/src/needle.snap",
+            "team/src/keep.rs",
+            "other/generated/needle.rs",
+        ] {
+            fs::write(root.join(file), "let needle = 1;\n").expect("write file");
+        }
+
+        let mut paths: Vec<PathBuf> = build_candidates("needle", root)
+            .expect("candidates")
+            .into_iter()
+            .map(|candidate| candidate.path)
+            .collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            [
+                PathBuf::from("other/generated/needle.rs"),
+                PathBuf::from("team/src/keep.rs")
+            ]
+        );
+
+        // Settings in a nested file are not applied.
+        let listed = build_candidates(".yoinkignore", root).expect("listed");
+        assert!(listed.is_empty(), "{listed:?}");
+    });
+}
+
+#[test]
+fn rg_config_file_is_ignored_unless_inherited() {
+    with_system_config("build/**\n", |home| {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        fs::write(root.join("notes.txt"), "needle\n").expect("write notes");
+        fs::write(root.join("main.rs"), "needle\n").expect("write main");
+        let rg_config = home.join("ripgreprc");
+        fs::write(&rg_config, "# rust only\n--type\nrust\n-g\n!build/**\n")
+            .expect("write rg config");
+        std::env::set_var("RIPGREP_CONFIG_PATH", &rg_config);
+
+        let paths = |candidates: Vec<Candidate>| -> Vec<PathBuf> {
+            let mut paths: Vec<PathBuf> = candidates
+                .into_iter()
+                .map(|candidate| candidate.path)
+                .collect();
+            paths.sort();
+            paths
+        };
+        let default = build_candidates("needle", root).expect("default candidates");
+        assert_eq!(
+            paths(default),
+            [PathBuf::from("main.rs"), PathBuf::from("notes.txt")]
+        );
+        let settings = load_settings().expect("settings");
+        assert!(rg_files_args(&settings).contains(&"--no-config".to_string()));
+        assert!(rg_filter_args(&settings).contains(&"!build/**".to_string()));
+
+        fs::write(
+            home.join(".yoinkignore"),
+            "build/**\ninherit_rg_config = true\n",
+        )
+        .expect("rewrite config");
+        let inherited = build_candidates("needle", root).expect("inherited candidates");
+        let settings = load_settings().expect("settings");
+        if !settings.internal_engine() {
+            // Only rg reads the config file; the internal engine searches every type.
+            assert_eq!(paths(inherited), [PathBuf::from("main.rs")]);
+        }
+        assert!(!rg_files_args(&settings).contains(&"--no-config".to_string()));
+        // The config already excludes build/**; yoink doesn't pass it again.
+        assert!(!rg_filter_args(&settings).contains(&"!build/**".to_string()));
+
+        std::env::remove_var("RIPGREP_CONFIG_PATH");
+    });
+
+    let globs = rg_config_globs(
+        "--smart-case\n-g\n!a/**\n--glob=!b\n  --iglob=*.c  \n-g!d\n# -g\n--type=rust\n",
+    );
+    let mut globs: Vec<String> = globs.into_iter().collect();
+    globs.sort();
+    assert_eq!(globs, ["!a/**", "!b", "!d", "*.c"]);
+}
+
+#[test]
+fn exclude_extensions_match_whole_suffixes_in_any_case() {
+    let run = |config: &str| {
+        let mut paths = Vec::new();
+        with_system_config(config, |_| {
+            let dir = tempdir().expect("tempdir");
+            let root = dir.path();
+            for name in [
+                "app.min.js",
+                "app.js",
+                "LEGACY.JS",
+                "notmin.js.txt",
+                "style.map",
+            ] {
+                fs::write(root.join(name), "needle\n").expect("write file");
+            }
+            paths = build_candidates("needle", root)
+                .expect("candidates")
+                .into_iter()
+                .map(|candidate| candidate.path)
+                .collect();
+            paths.sort();
+        });
+        paths
+    };
+
+    assert_eq!(
+        run("exclude_extensions = min.js, .map\n"),
+        [
+            PathBuf::from("LEGACY.JS"),
+            PathBuf::from("app.js"),
+            PathBuf::from("notmin.js.txt")
+        ]
+    );
+    assert_eq!(
+        run("exclude_extensions = .JS\n"),
+        [PathBuf::from("notmin.js.txt"), PathBuf::from("style.map")]
+    );
+    assert_eq!(
+        parse_extension_list(" .Min.JS,,lock ").expect("list"),
+        ["min.js".to_string(), "lock".to_string()]
+            .into_iter()
+            .collect()
+    );
+    assert_eq!(parse_extension_list("*.js"), None);
+
+    let settings = YoinkSettings {
+        exclude_extensions: parse_extension_list("map,min.js").expect("list"),
+        ..YoinkSettings::default()
+    };
+    let args = rg_filter_args(&settings);
+    assert!(args.ends_with(&[
+        "--iglob".to_string(),
+        "!*.map".to_string(),
+        "--iglob".to_string(),
+        "!*.min.js".to_string()
+    ]));
+}
+
+#[test]
+fn several_roots_list_labeled_paths_from_each_root_only() {
+    with_system_config("", |_| {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        fs::create_dir_all(root.join("api/src")).expect("create api");
+        fs::create_dir_all(root.join("web/frontend")).expect("create frontend");
+        fs::create_dir_all(root.join("other")).expect("create other");
+        fs::write(root.join("api/src/handler.rs"), "let needle = 1;\n").expect("write api");
+        fs::write(root.join("web/frontend/needle.ts"), "export {}\n").expect("write frontend");
+        fs::write(root.join("other/needle.txt"), "needle\n").expect("write other");
+
+        let session = Session::start().expect("session");
+        let mut state = SessionState {
+            roots: vec![PathBuf::from("api"), PathBuf::from("web/frontend")],
+            ..SessionState::default()
+        };
+        session.store(&state).expect("store");
+        std::env::set_var("YOINK_SESSION", session.id());
+
+        let mut paths: Vec<PathBuf> = build_candidates("needle", root)
+            .expect("candidates")
+            .into_iter()
+            .map(|candidate| candidate.path)
+            .collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            [
+                PathBuf::from("api/src/handler.rs"),
+                PathBuf::from("web/frontend/needle.ts")
+            ]
+        );
+
+        let entries = build_search_entries("needle", root).expect("entries");
+        let occurrence = entries
+            .iter()
+            .find(|entry| entry.line.is_some())
+            .expect("occurrence row");
+        assert_eq!(occurrence.path, PathBuf::from("api/src/handler.rs"));
+        assert_eq!(occurrence.line, Some(1));
+        assert!(entries.iter().all(|entry| !entry.path.starts_with("other")));
+
+        // A directory pill from the labeled list narrows to that root.
+        state.pills = vec![FilterPill::Dir(PathBuf::from("web"))];
+        session.store(&state).expect("store pill");
+        let narrowed: Vec<PathBuf> = build_candidates("needle", root)
+            .expect("narrowed")
+            .into_iter()
+            .map(|candidate| candidate.path)
+            .collect();
+        std::env::remove_var("YOINK_SESSION");
+        session.remove();
+        assert_eq!(narrowed, [PathBuf::from("web/frontend/needle.ts")]);
+    });
+}
+
+#[test]
+fn basename_flag_matches_names_only_through_the_session() {
+    let run = |config: &str, overrides: SettingOverrides| {
+        let mut paths = Vec::new();
+        with_system_config(config, |_| {
+            let dir = tempdir().expect("tempdir");
+            let root = dir.path();
+            fs::create_dir_all(root.join("src/deep")).expect("create dirs");
+            fs::write(root.join("src/deep/file.txt"), "plain\n").expect("write file");
+            fs::write(root.join("src/deep/srcmap.txt"), "plain\n").expect("write name match");
+
+            let session = Session::start().expect("session");
+            let mut state = SessionState::default();
+            state.set_overrides(overrides);
+            session.store(&state).expect("store");
+            std::env::set_var("YOINK_SESSION", session.id());
+            let candidates = build_candidates("src", root).expect("candidates");
+            std::env::remove_var("YOINK_SESSION");
+            session.remove();
+
+            paths = candidates
+                .into_iter()
+                .map(|candidate| candidate.path)
+                .collect();
+            paths.sort();
+        });
+        paths
+    };
+
+    let basename = Cli::parse_from(["yoink", "--basename"]).overrides();
+    assert_eq!(basename.match_basename_only, Some(true));
+    let expected = vec![PathBuf::from("src"), PathBuf::from("src/deep/srcmap.txt")];
+    assert_eq!(run("", basename), expected);
+    assert_eq!(
+        run("match_basename_only=true\n", SettingOverrides::default()),
+        expected
+    );
+    assert!(run("", SettingOverrides::default()).contains(&PathBuf::from("src/deep/file.txt")));
+}
+
+#[test]
+fn content_scope_drops_name_matches_but_lists_an_empty_query() {
+    with_system_config("search_scope=content\n", |_| {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        fs::write(root.join("ejectReasons.txt"), "unrelated words\n").expect("write name match");
+        fs::write(root.join("handler.rs"), "let ejectReasons = 1;\n").expect("write content match");
+
+        let paths: Vec<PathBuf> = build_search_entries("ejectReasons", root)
+            .expect("entries")
+            .into_iter()
+            .filter(|entry| entry.kind.is_some())
+            .map(|entry| entry.path)
+            .collect();
+        assert_eq!(paths, vec![PathBuf::from("handler.rs")]);
+
+        let listing = build_candidates("", root).expect("listing");
+        assert!(listing
+            .iter()
+            .any(|candidate| candidate.path == Path::new("ejectReasons.txt")));
+        assert!(listing
+            .iter()
+            .any(|candidate| candidate.path == Path::new("handler.rs")));
+    });
+}
+
+#[test]
+fn and_terms_require_every_term_in_path_or_content() {
+    with_system_config("and_terms=true\n", |_| {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        fs::write(root.join("both.txt"), "alpha\nbeta\n").expect("write both");
+        fs::write(root.join("only_alpha.txt"), "alpha\n").expect("write one");
+        fs::write(root.join("alpha_beta.md"), "nothing\n").expect("write name match");
+        fs::write(root.join("phrase.txt"), "say alpha beta\n").expect("write phrase");
+
+        let paths = |query: &str| -> Vec<String> {
+            build_candidates(query, root)
+                .expect("candidates")
+                .into_iter()
+                .map(|candidate| candidate.path.to_string_lossy().to_string())
+                .collect()
+        };
+        let mut found = paths("alpha beta");
+        found.sort();
+        assert_eq!(found, ["alpha_beta.md", "both.txt", "phrase.txt"]);
+        assert_eq!(paths("\"alpha beta\""), ["phrase.txt"]);
+
+        // Occurrences cover either term, so both lines of both.txt are listed.
+        let lines: Vec<usize> = build_search_entries("alpha beta", root)
+            .expect("entries")
+            .into_iter()
+            .filter(|entry| entry.path == Path::new("both.txt"))
+            .filter_map(|entry| entry.line)
+            .collect();
+        assert_eq!(lines, [1, 2]);
+    });
+}
+
+#[test]
+fn negative_terms_drop_files_matching_in_path_or_content() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::write(root.join("handler.rs"), "fn handler() {}\n").expect("write handler");
+    fs::write(root.join("handler_test.rs"), "fn handler_case() {}\n").expect("write test");
+    fs::write(root.join("mocked.rs"), "fn handler() { mock() }\n").expect("write mocked");
+    fs::write(root.join("rule.css"), "color: red !important;\n").expect("write css");
+
+    let paths = |query: &str| -> Vec<String> {
+        let mut found: Vec<String> = build_candidates(query, root)
+            .expect("candidates")
+            .into_iter()
+            .map(|candidate| candidate.path.to_string_lossy().to_string())
+            .collect();
+        found.sort();
+        found
+    };
+    assert_eq!(paths("handler !test"), ["handler.rs", "mocked.rs"]);
+    assert_eq!(paths("handler !mock"), ["handler.rs", "handler_test.rs"]);
+    // Only negatives: the whole listing, less the exclusions.
+    assert_eq!(paths("!handler !mock"), ["rule.css"]);
+    // `\!` searches for a literal `!`.
+    assert_eq!(paths("\\!important"), ["rule.css"]);
+}
+
+#[test]
+fn word_boundaries_skip_matches_inside_longer_words() {
+    with_system_config("word_boundaries=true\n", |_| {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        fs::write(root.join("login.rs"), "fn login() {}\n").expect("write login");
+        fs::write(root.join("catalog.txt"), "items\n").expect("write catalog");
+        fs::write(root.join("writer.rs"), "log(message);\n").expect("write writer");
+
+        let found: Vec<String> = build_candidates("log", root)
+            .expect("candidates")
+            .into_iter()
+            .map(|candidate| candidate.path.to_string_lossy().to_string())
+            .collect();
+        assert_eq!(found, ["writer.rs"]);
+
+        let pattern = load_settings()
+            .expect("settings")
+            .query_pattern("log")
+            .expect("pattern");
+        assert_eq!(
+            highlight_query_matches(
+                "login(); log(x)",
+                pattern.highlight_regex(),
+                ColorMode::Plain
+            ),
+            "login(); >>log<<(x)"
+        );
+    });
+}
+
+#[test]
+fn multiline_matches_span_lines_and_report_their_first_line() {
+    let query = "fn new\\([^)]*\\)\\s*->";
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::write(
+        root.join("point.rs"),
+        "impl Point {\n    fn new(x: i32,\n           y: i32) -> Self {\n        Point { x, y }\n    }\n}\n",
+    )
+    .expect("write point");
+
+    with_system_config("", |_| {
+        assert!(build_candidates(query, root)
+            .expect("candidates")
+            .is_empty());
+    });
+    with_system_config("multiline=true\n", |_| {
+        let found: Vec<PathBuf> = build_candidates(query, root)
+            .expect("candidates")
+            .into_iter()
+            .map(|candidate| candidate.path)
+            .collect();
+        assert_eq!(found, [PathBuf::from("point.rs")]);
+
+        let occurrences: Vec<(usize, String)> = build_search_entries(query, root)
+            .expect("entries")
+            .into_iter()
+            .filter_map(|entry| Some((entry.line?, entry.display)))
+            .collect();
+        assert_eq!(occurrences.len(), 1, "{occurrences:?}");
+        assert_eq!(occurrences[0].0, 2);
+        assert!(
+            occurrences[0].1.contains("fn new(x: i32, \u{2938}"),
+            "{:?}",
+            occurrences[0].1
+        );
+    });
+}
+
+#[test]
+fn fuzzy_fallback_lists_subsequence_paths_only_when_enabled() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::create_dir(root.join("src")).expect("mkdir src");
+    fs::write(root.join("src/user_service.rs"), "pub struct Service;\n").expect("write service");
+    fs::write(root.join("src/main.rs"), "fn main() {}\n").expect("write main");
+
+    with_system_config("", |_| {
+        assert!(build_candidates("usrsvc", root)
+            .expect("candidates")
+            .is_empty());
+    });
+    with_system_config("fuzzy_fallback=true\n", |_| {
+        let candidates = build_candidates("usrsvc", root).expect("candidates");
+        let found: Vec<(PathBuf, bool)> = candidates
+            .into_iter()
+            .map(|candidate| (candidate.path, candidate.fuzzy_match))
+            .collect();
+        assert_eq!(found, [(PathBuf::from("src/user_service.rs"), true)]);
+
+        let row = build_search_entries("usrsvc", root)
+            .expect("entries")
+            .into_iter()
+            .find(|entry| entry.path == Path::new("src/user_service.rs"))
+            .expect("fuzzy row");
+        assert!(
+            row.display
+                .contains(&format!("\x1b[2msrc/user_service.rs\x1b[0m {FUZZY_MARKER}")),
+            "{:?}",
+            row.display
+        );
+
+        // A query that matches keeps the fallback out of it.
+        let exact = build_candidates("main", root).expect("candidates");
+        assert!(exact.iter().all(|candidate| !candidate.fuzzy_match));
+    });
+}
+
+#[test]
+fn max_results_caps_candidates_and_appends_a_sentinel_row() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    for index in 0..6 {
+        fs::write(root.join(format!("a{index}.txt")), "a\n").expect("write file");
+    }
+
+    with_system_config("max_results=4\n", |_| {
+        let mut stats = SearchStats::default();
+        let candidates = build_candidates_with_stats("a", root, &mut stats).expect("candidates");
+        assert_eq!(candidates.len(), 4);
+        assert!(stats.results_truncated);
+
+        let entries = build_search_entries("a", root).expect("entries");
+        let last = entries.last().expect("sentinel row");
+        assert_eq!(last.path, PathBuf::new());
+        assert_eq!(last.line, None);
+        assert!(
+            last.display
+                .contains("… results truncated at 4 (refine your query)"),
+            "{:?}",
+            last.display
+        );
+    });
+    with_system_config("max_results=6\n", |_| {
+        let mut stats = SearchStats::default();
+        assert_eq!(
+            build_candidates_with_stats("a", root, &mut stats)
+                .expect("candidates")
+                .len(),
+            6
+        );
+        assert!(!stats.results_truncated);
+    });
+}
+
+#[test]
+fn max_occurrences_per_file_caps_rows_per_file() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::write(root.join("many.txt"), "hit\n".repeat(10)).expect("write file");
+
+    with_system_config("max_occurrences_per_file=3\n", |_| {
+        assert_eq!(
+            load_settings()
+                .expect("settings")
+                .occurrence_budget()
+                .per_file,
+            3
+        );
+        let lines: Vec<usize> = build_search_entries("hit", root)
+            .expect("entries")
+            .into_iter()
+            .filter(|entry| entry.path == Path::new("many.txt"))
+            .filter_map(|entry| entry.line)
+            .collect();
+        assert_eq!(lines, [1, 2, 3]);
+    });
+    with_system_config("max_results=0\n", |_| {
+        let message = format!("{:#}", load_settings().expect_err("invalid value"));
+        assert!(
+            message.contains("invalid max_results value in"),
+            "{message}"
+        );
+    });
+}
+
+#[test]
+fn occurrence_display_limit_ends_a_file_with_an_and_more_row() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::write(root.join("lock.txt"), "hit\n".repeat(50)).expect("write file");
+
+    with_system_config("occurrence_display_limit=5\n", |_| {
+        let mut entries = build_search_entries("hit", root).expect("entries");
+        let rows: Vec<&SearchEntry> = entries
+            .iter()
+            .filter(|entry| entry.path == Path::new("lock.txt"))
+            .collect();
+        assert_eq!(rows.len(), 1 + 5 + 1);
+        let lines: Vec<usize> = rows.iter().filter_map(|entry| entry.line).collect();
+        assert_eq!(lines, [1, 2, 3, 4, 5]);
+        // The file row carries the true total, and is the only row with a count.
+        assert!(
+            rows[0].display.contains("lock.txt \x1b[33m(50)\x1b[0m"),
+            "{:?}",
+            rows[0].display
+        );
+        assert_eq!(rows[0].match_count, Some(50));
+        assert!(
+            rows[1].display.starts_with("     ↳ "),
+            "{:?}",
+            rows[1].display
+        );
+        assert!(rows[1..].iter().all(|row| row.match_count.is_none()));
+        let more = rows.last().expect("more row");
+        assert_eq!(more.line, None);
+        assert!(
+            more.display.contains("↳ \x1b[2m… and 45 more matches"),
+            "{:?}",
+            more.display
+        );
+
+        // Only the file row gets a number.
+        assert_eq!(number_file_rows(&mut entries, ColorMode::Plain).len(), 1);
+    });
+    with_system_config("occurrence_display_limit=0\n", |_| {
+        let entries = build_search_entries("hit", root).expect("entries");
+        let rows: Vec<&SearchEntry> = entries
+            .iter()
+            .filter(|entry| entry.path == Path::new("lock.txt"))
+            .collect();
+        assert_eq!(rows.len(), 1);
+        assert!(
+            rows[0].display.contains("lock.txt \x1b[33m(50)\x1b[0m"),
+            "{:?}",
+            rows[0].display
+        );
+    });
+}
+
+#[test]
+fn snippet_context_lists_dimmed_rows_that_jump_to_their_match() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::write(
+        root.join("notes.txt"),
+        "one\nneedle two\nthree\nfour\nfive\nneedle six\nneedle seven\n\tlast\n",
+    )
+    .expect("write file");
+
+    with_system_config("snippet_context=1\n", |_| {
+        let entries = build_search_entries("needle", root).expect("entries");
+        let rows: Vec<(Option<usize>, String)> = entries
+            .iter()
+            .filter(|entry| entry.path == Path::new("notes.txt"))
+            .skip(1)
+            .map(|entry| (entry.line, strip_ansi(&entry.display)))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (Some(2), "     ↳    1  one".to_string()),
+                (Some(2), "     ↳    2  needle two".to_string()),
+                (Some(2), "     ↳    3  three".to_string()),
+                // Line 6's context would be line 7, which has its own row.
+                (Some(6), "     ↳    5  five".to_string()),
+                (Some(6), "     ↳    6  needle six".to_string()),
+                (Some(7), "     ↳    7  needle seven".to_string()),
+                (Some(7), "     ↳    8  last".to_string()),
+            ]
+        );
+        let context = entries
+            .iter()
+            .find(|entry| strip_ansi(&entry.display).ends_with(" one"))
+            .expect("context row");
+        assert!(
+            context.display.starts_with("\x1b[2m"),
+            "{:?}",
+            context.display
+        );
+        assert_eq!(context.column, Some(1));
+    });
+
+    with_system_config("snippet_context=1\nicons=false\n", |_| {
+        let entries = build_search_entries("needle", root).expect("entries");
+        assert!(entries
+            .iter()
+            .any(|entry| entry.display == "  context line 5: five"));
+    });
+
+    // The default lists only the matching lines.
+    with_system_config("", |_| {
+        let entries = build_search_entries("needle", root).expect("entries");
+        assert_eq!(
+            entries.iter().filter(|entry| entry.line.is_some()).count(),
+            3
+        );
+    });
+
+    // A long context line is cut like a snippet.
+    fs::write(
+        root.join("wide.txt"),
+        format!("{}\nneedle\n", "x".repeat(500)),
+    )
+    .expect("write wide");
+    with_system_config("snippet_context=1\n", |_| {
+        let entries = build_search_entries("needle", root).expect("entries");
+        let wide = entries
+            .iter()
+            .find(|entry| {
+                entry.path == Path::new("wide.txt") && strip_ansi(&entry.display).contains("xxx")
+            })
+            .expect("wide context row");
+        assert!(strip_ansi(&wide.display).chars().count() < 200);
+    });
+    with_system_config("snippet_context=some\n", |_| {
+        let error = load_settings().expect_err("invalid value");
+        assert!(format!("{error:#}").contains("invalid snippet_context value in"));
+    });
+}
+
+#[test]
+fn search_timeout_returns_partial_results_under_a_warning() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    for index in 0..5000 {
+        fs::write(root.join(format!("f{index:04}.txt")), "f\n").expect("write file");
+    }
+
+    with_system_config("search_timeout_ms=1\n", |_| {
+        let mut stats = SearchStats::default();
+        let candidates =
+            build_candidates_with_stats("f", root, &mut stats).expect("partial, not an error");
+        assert!(stats.timed_out);
+        assert!(candidates.len() < 5000, "{}", candidates.len());
+
+        let entries = build_search_entries("f", root).expect("entries");
+        assert!(
+            entries[0].display.contains(SEARCH_TIMEOUT_NOTICE),
+            "{:?}",
+            entries[0].display
+        );
+    });
+    with_system_config("", |_| {
+        let mut stats = SearchStats::default();
+        assert_eq!(
+            build_candidates_with_stats("f", root, &mut stats)
+                .expect("candidates")
+                .len(),
+            5000
+        );
+        assert!(!stats.timed_out);
+    });
+}
+
+#[test]
+fn search_scope_setting_parses_and_names_bad_values() {
+    with_system_config("search_scope=both\n", |_| {
+        assert_eq!(
+            load_settings().expect("settings").search_scope,
+            SearchScope::Both
+        );
+    });
+    with_system_config("search_scope=everything\n", |_| {
+        let message = format!("{:#}", load_settings().expect_err("invalid value"));
+        assert!(
+            message.contains("invalid search_scope value in"),
+            "{message}"
+        );
+        assert!(message.contains("everything"), "{message}");
+    });
+}
+
+fn occurrence_at(line: usize) -> Occurrence {
+    Occurrence {
+        line,
+        column: 1,
+        snippet: format!("line {line}"),
+        long_line: false,
+        context: Vec::new(),
+    }
+}
+
+fn scan_of(files: &[(&str, usize)]) -> OccurrenceScan {
+    OccurrenceScan {
+        occurrences: files
+            .iter()
+            .map(|(path, count)| {
+                (
+                    PathBuf::from(path),
+                    (1..=*count).map(occurrence_at).collect(),
+                )
+            })
+            .collect(),
+        ..OccurrenceScan::default()
+    }
+}
+
+#[test]
+fn chunker_splits_contiguously_and_respects_the_argv_budget() {
+    let files: Vec<PathBuf> = (0..10).map(|n| PathBuf::from(format!("f{n}.rs"))).collect();
+
+    let chunks = chunk_paths(&files, 3, usize::MAX);
+    assert_eq!(
+        chunks.iter().map(Vec::len).collect::<Vec<_>>(),
+        vec![4, 4, 2]
+    );
+    assert_eq!(chunks.concat(), files);
+
+    // Each path is 5 bytes plus a NUL, so 13 bytes fit two paths.
+    let chunks = chunk_paths(&files, 2, 13);
+    assert!(chunks.iter().all(|chunk| chunk.len() <= 2), "{chunks:?}");
+    assert_eq!(chunks.concat(), files);
+
+    let long = vec![PathBuf::from("x".repeat(40))];
+    assert_eq!(chunk_paths(&long, 4, 13), vec![long.clone()]);
+    assert!(chunk_paths(&[], 4, 13).is_empty());
+}
+
+#[test]
+fn merger_unions_chunks_and_reapplies_the_total_budget() {
+    let budget = OccurrenceBudget {
+        total: 5,
+        ..DEFAULT_OCCURRENCE_BUDGET
+    };
+    let merged = merge_occurrence_scans(
+        vec![
+            Ok(scan_of(&[("b.rs", 2)])),
+            Ok(scan_of(&[("a.rs", 2), ("c.rs", 3)])),
+        ],
+        &budget,
+    )
+    .expect("merged");
+
+    assert!(merged.truncated);
+    assert!(merged.warnings.is_empty());
+    assert_eq!(merged.occurrences[Path::new("a.rs")].len(), 2);
+    assert_eq!(merged.occurrences[Path::new("b.rs")].len(), 2);
+    assert_eq!(merged.occurrences[Path::new("c.rs")].len(), 1);
+
+    let merged = merge_occurrence_scans(
+        vec![Ok(scan_of(&[("a.rs", 1)]))],
+        &DEFAULT_OCCURRENCE_BUDGET,
+    )
+    .expect("merged");
+    assert!(!merged.truncated);
+}
+
+#[test]
+fn merger_keeps_surviving_chunks_when_one_fails() {
+    let merged = merge_occurrence_scans(
+        vec![
+            Ok(scan_of(&[("a.rs", 1)])),
+            Err(anyhow::anyhow!("rg exited with exit status: 2")),
+            Ok(scan_of(&[("c.rs", 1)])),
+        ],
+        &DEFAULT_OCCURRENCE_BUDGET,
+    )
+    .expect("partial results");
+    assert_eq!(merged.occurrences.len(), 2);
+    assert_eq!(merged.warnings.len(), 1);
+    assert!(
+        merged.warnings[0].contains("1 of 3 occurrence chunks failed"),
+        "{:?}",
+        merged.warnings
+    );
+
+    let error = merge_occurrence_scans(
+        vec![
+            Err(anyhow::anyhow!("spawn failed")),
+            Err(anyhow::anyhow!("spawn failed")),
+        ],
+        &DEFAULT_OCCURRENCE_BUDGET,
+    )
+    .expect_err("every chunk failed");
+    assert!(format!("{error:#}").contains("every occurrence chunk failed"));
+}
+
+#[test]
+fn parallel_walk_lists_the_same_candidates_as_the_serial_walk() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    for rel in [
+        "src/lib.rs",
+        "src/deep/a/b/c.rs",
+        "src/deep/a/d.txt",
+        "docs/guide.md",
+        ".hidden/secret.rs",
+        "build/out.o",
+        "build/keep.txt",
+        "target/debug/bin",
+    ] {
+        let path = root.join(rel);
+        fs::create_dir_all(path.parent().expect("parent")).expect("mkdir");
+        fs::write(&path, "x\n").expect("write file");
+    }
+    fs::create_dir(root.join("empty")).expect("mkdir empty");
+    #[cfg(target_family = "unix")]
+    std::os::unix::fs::symlink("src", root.join("src_link")).expect("symlink dir");
+
+    let listing = |config: &str| -> Vec<(PathBuf, bool)> {
+        let mut listed = Vec::new();
+        with_system_config(config, |_| {
+            listed = build_candidates("", root)
+                .expect("candidates")
+                .into_iter()
+                .map(|candidate| (candidate.path, candidate.is_dir))
+                .collect();
+        });
+        listed
+    };
+    for extra in [
+        "",
+        "include_hidden=true\ninclude_symlinks=true\n",
+        "max_depth=2\n",
+    ] {
+        let rules = format!("{extra}build/**\n!build/keep.txt\ntarget/\n");
+        let serial = listing(&format!("threads=1\n{rules}"));
+        let parallel = listing(&format!("threads=4\n{rules}"));
+        assert!(!serial.is_empty());
+        assert_eq!(serial, parallel, "{extra:?}");
+    }
+}
+
+#[test]
+fn parallel_walk_with_many_workers_matches_the_serial_walk_and_stops_at_the_deadline() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    for branch in 0..12 {
+        let mut sub = root.join(format!("b{branch}"));
+        for level in 0..6 {
+            sub = sub.join(format!("l{level}"));
+            fs::create_dir_all(&sub).expect("mkdir");
+            for file in 0..8 {
+                fs::write(sub.join(format!("f{file}.txt")), "x\n").expect("write file");
+            }
+        }
+    }
+    #[cfg(target_family = "unix")]
+    std::os::unix::fs::symlink("..", root.join("b0/l0/up")).expect("symlink loop");
+
+    let listing = |config: &str| -> (Vec<(PathBuf, bool)>, bool) {
+        let mut listed = Vec::new();
+        let mut timed_out = false;
+        with_system_config(config, |_| {
+            let mut stats = SearchStats::default();
+            listed = build_candidates_with_stats("", root, &mut stats)
+                .expect("candidates")
+                .into_iter()
+                .map(|candidate| (candidate.path, candidate.is_dir))
+                .collect();
+            timed_out = stats.timed_out;
+        });
+        (listed, timed_out)
+    };
+    for extra in ["", "include_symlinks=true\n", "max_depth=4\n"] {
+        let (serial, _) = listing(&format!("threads=1\n{extra}"));
+        assert!(serial.len() > 12 * 8, "{extra:?}");
+        for _ in 0..5 {
+            assert_eq!(
+                listing(&format!("threads=32\n{extra}")).0,
+                serial,
+                "{extra:?}"
+            );
+        }
+    }
+
+    let started = std::time::Instant::now();
+    for _ in 0..5 {
+        let (listed, timed_out) = listing("threads=32\nsearch_timeout_ms=1\n");
+        if timed_out {
+            assert!(listed.len() < 12 * 6 * 9, "{}", listed.len());
+        }
+    }
+    assert!(started.elapsed() < std::time::Duration::from_secs(30));
+}
+
+#[test]
+fn threads_setting_parses_and_rejects_garbage() {
+    with_system_config("threads=3\n", |_| {
+        let settings = load_settings().expect("settings");
+        assert_eq!(settings.threads, 3);
+        assert_eq!(settings.occurrence_threads(), 3);
+    });
+    with_system_config("threads=many\n", |_| {
+        let error = load_settings().expect_err("invalid value");
+        assert!(format!("{error:#}").contains("invalid threads value in"));
+    });
+    assert!(YoinkSettings::default().occurrence_threads() >= 1);
+}
+
+#[test]
+fn chunked_occurrences_match_the_single_process_path() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    for n in 0..PARALLEL_OCCURRENCE_MIN_FILES + 20 {
+        let sub = root.join(format!("pkg{}", n % 7));
+        fs::create_dir_all(&sub).expect("mkdir");
+        fs::write(
+            sub.join(format!("file{n}.txt")),
+            format!("one needle\nnothing\nneedle {n}\n"),
+        )
+        .expect("write");
+    }
+    fs::write(root.join("needle_notes.md"), "no match inside\n").expect("write path match");
+
+    let entries = |threads: usize| {
+        let mut displays = Vec::new();
+        with_system_config(&format!("threads={threads}\n"), |_| {
+            displays = build_search_entries("needle", root)
+                .expect("entries")
+                .into_iter()
+                .map(|entry| (entry.display, entry.path, entry.line, entry.column))
+                .collect();
+        });
+        displays
+    };
+
+    let single = entries(1);
+    assert!(single.len() > (PARALLEL_OCCURRENCE_MIN_FILES + 20) * 3);
+    assert_eq!(entries(4), single);
+}
+
+/// A tiny PNG header: valid magic, then the NUL bytes every real PNG has.
+const PNG_FIXTURE: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x01";
+
+#[test]
+fn binary_sniff_looks_for_a_nul_in_the_head() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::write(root.join("logo.png"), PNG_FIXTURE).expect("write png");
+    fs::write(root.join("notes.txt"), "plain text\n").expect("write text");
+    let mut late = vec![b'a'; 9000];
+    late.push(0);
+    fs::write(root.join("late.dat"), late).expect("write late nul");
+
+    assert!(is_binary_file(&root.join("logo.png")));
+    assert!(!is_binary_file(&root.join("notes.txt")));
+    assert!(!is_binary_file(&root.join("late.dat")));
+    assert!(!is_binary_file(&root.join("missing.bin")));
+}
+
+#[test]
+fn binaries_are_hidden_unless_show_binaries() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::write(root.join("logo.png"), PNG_FIXTURE).expect("write png");
+    fs::write(root.join("logo.svg"), "<svg/>\n").expect("write svg");
+
+    with_system_config("", |_| {
+        let paths: Vec<PathBuf> = build_candidates("logo", root)
+            .expect("candidates")
+            .into_iter()
+            .map(|candidate| candidate.path)
+            .collect();
+        assert_eq!(paths, vec![PathBuf::from("logo.svg")]);
+    });
+
+    with_system_config("show_binaries=true\n", |_| {
+        let candidates = build_candidates("logo", root).expect("candidates");
+        let png = candidates
+            .iter()
+            .find(|candidate| candidate.path == Path::new("logo.png"))
+            .expect("png listed");
+        assert!(png.is_binary);
+        assert!(candidates
+            .iter()
+            .any(|candidate| candidate.path == Path::new("logo.svg") && !candidate.is_binary));
+
+        let entries = build_search_entries("logo", root).expect("entries");
+        let row = |name: &str| {
+            entries
+                .iter()
+                .find(|entry| entry.path == Path::new(name))
+                .map(|entry| entry.display.clone())
+                .expect("row")
+        };
+        assert!(row("logo.png").contains(BINARY_ICON));
+        assert!(!row("logo.svg").contains(BINARY_ICON));
+    });
+}
+
+fn displays(entries: &[SearchEntry]) -> Vec<String> {
+    entries.iter().map(|entry| entry.display.clone()).collect()
+}
+
+fn assert_screen_reader_friendly(rows: &[String]) {
+    for row in rows {
+        assert!(!row.contains('\x1b'), "ANSI in {row:?}");
+        for glyph in [
+            "📁",
+            "📄",
+            BINARY_ICON,
+            "↳",
+            "…",
+            "⚠",
+            ">>",
+            "<<",
+            "[p",
+            "[c]",
+        ] {
+            assert!(!row.contains(glyph), "{glyph} in {row:?}");
+        }
+    }
+}
+
+#[test]
+fn accessibility_preset_overrides_icon_and_color_settings() {
+    with_system_config(
+        "accessibility=true\nshow_language=true\nteaser=true\n",
+        |_| {
+            let settings = load_settings().expect("settings");
+            assert!(!settings.icons);
+            assert!(settings.no_color);
+            assert!(!settings.show_language);
+            assert!(!settings.teaser);
+            assert!(!settings.number_file_rows);
+            assert_eq!(settings.color_mode(), ColorMode::Plain);
+        },
+    );
+
+    let settings = resolve_settings(
+        YoinkSettings::default(),
+        &[SettingOverrides {
+            accessibility: Some(true),
+            ..SettingOverrides::default()
+        }],
+    );
+    assert!(settings.accessibility && !settings.icons && settings.no_color);
+
+    // The underlying settings work on their own too.
+    with_system_config("icons=false\n", |_| {
+        let settings = load_settings().expect("settings");
+        assert!(!settings.icons && !settings.no_color && settings.show_language);
+    });
+    assert_eq!(path_icon(true, false, false), "DIR");
+    assert_eq!(path_icon(false, true, false), "BINARY");
+    assert_eq!(path_icon(false, false, true), "📄");
+}
+
+#[test]
+fn accessibility_renders_file_dir_and_occurrence_rows_as_text() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::create_dir_all(root.join("src/handlers")).expect("mkdir");
+    fs::write(
+        root.join("src/ui.rs"),
+        "let handlers = 1;\nfn x() {}\nhandlers();\nhandlers.push(2);\n",
+    )
+    .expect("write ui");
+    fs::write(
+        root.join("src/long.rs"),
+        format!("// handlers {}\n", "y".repeat(200)),
+    )
+    .expect("write long");
+    fs::write(root.join("handlers.png"), PNG_FIXTURE).expect("write png");
+
+    with_system_config(
+        "accessibility=true\nshow_binaries=true\nmax_columns=0\n",
+        |_| {
+            let rows = displays(&build_search_entries("handlers", root).expect("entries"));
+            assert_screen_reader_friendly(&rows);
+
+            assert!(rows.contains(&"DIR src/handlers".to_string()), "{rows:#?}");
+            assert!(
+                rows.contains(&"BINARY handlers.png".to_string()),
+                "{rows:#?}"
+            );
+            assert!(
+                rows.contains(&"FILE src/ui.rs (3 matches)".to_string()),
+                "{rows:#?}"
+            );
+            assert!(
+                rows.contains(&"  line 1: let handlers = 1;".to_string()),
+                "{rows:#?}"
+            );
+            assert!(
+                rows.contains(&"  line 4: handlers.push(2);".to_string()),
+                "{rows:#?}"
+            );
+            assert!(
+                rows.contains(&"FILE src/long.rs (1 match)".to_string()),
+                "{rows:#?}"
+            );
+            assert!(
+                rows.iter()
+                    .any(|row| row.starts_with("  line 1: // handlers y")
+                        && row.ends_with("y (truncated)")),
+                "{rows:#?}"
+            );
+        },
+    );
+}
+
+#[test]
+fn matches_on_very_long_lines_show_the_text_around_the_match() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    // A 10KB minified line with the match in the middle: past rg's
+    // `max_columns` and past the JSON reader's line cap alike.
+    let line = format!("{}needle(){}\n", "a".repeat(5000), "b".repeat(5000));
+    fs::write(root.join("bundle.min.js"), format!("first\n{line}{line}")).expect("write bundle");
+
+    with_system_config("", |_| {
+        let entries = build_search_entries("needle", root).expect("entries");
+        let rows: Vec<String> = displays(&entries)
+            .iter()
+            .map(|row| strip_ansi(row))
+            .collect();
+        assert!(
+            rows.iter().any(|row| row.contains("bundle.min.js (2)")),
+            "{rows:#?}"
+        );
+        let windows: Vec<&String> = rows.iter().filter(|row| row.contains("↳")).collect();
+        assert_eq!(windows.len(), 2, "{rows:#?}");
+        for row in windows {
+            assert!(
+                row.contains("…aaa") && row.contains("needle()bbb") && row.ends_with('…'),
+                "{row}"
+            );
+            assert!(display_width(row) <= 160, "{row}");
+        }
+        assert_eq!(
+            entries
+                .iter()
+                .filter_map(|entry| entry.column)
+                .collect::<Vec<_>>(),
+            [5001, 5001]
+        );
+    });
+
+    let full = root.join("bundle.min.js");
+    let window = line_window(&full, 2, 5001, 20, "…").expect("window");
+    assert_eq!(window, format!("…{}needle()bb…", "a".repeat(10)));
+    assert_eq!(line_window(&full, 1, 1, 20, "…").as_deref(), Some("first"));
+    // A column past the end of its line means the file changed.
+    assert_eq!(line_window(&full, 1, 400, 20, "…"), None);
+
+    // Cuts never leave half a character behind.
+    fs::write(
+        root.join("wide.txt"),
+        format!("{}needle{}\n", "é".repeat(50), "é".repeat(50)),
+    )
+    .expect("write wide");
+    let window = line_window(&root.join("wide.txt"), 1, 101, 21, "…").expect("wide window");
+    assert!(!window.contains('\u{FFFD}'), "{window}");
+    assert!(window.contains("needle"), "{window}");
+}
+
+#[test]
+fn stats_count_content_and_path_only_files_apart() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::create_dir(root.join("docs")).expect("create docs");
+    fs::write(root.join("a.rs"), "let needle = 1;\nneedle();\n").expect("write a");
+    fs::write(root.join("b.txt"), "a needle\n").expect("write b");
+    fs::write(root.join("needle.rs"), "fn needle() {}\n").expect("write needle.rs");
+    fs::write(root.join("docs/needle_notes.md"), "nothing here\n").expect("write notes");
+    fs::write(root.join("other.rs"), "haystack\n").expect("write other");
+
+    with_system_config("", |_| {
+        let mut stats = SearchStats::default();
+        build_search_entries_with_stats("needle", root, &mut stats).expect("entries");
+        assert_eq!(
+            (stats.content_files, stats.path_files, stats.matches),
+            (3, 1, 4)
+        );
+        assert_eq!(
+            stats_header(&stats, Duration::from_millis(12)),
+            "4 files (3 content, 1 path only) · 4 matches · 12ms"
+        );
+    });
+
+    let one = SearchStats {
+        content_files: 1,
+        matches: 1,
+        ..SearchStats::default()
+    };
+    assert_eq!(
+        stats_header(&one, Duration::ZERO),
+        "1 file (1 content, 0 path only) · 1 match · 0ms"
+    );
+}
+
+#[cfg(feature = "git")]
+fn git(root: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(args)
+        .current_dir(root)
+        .status()
+        .expect("run git");
+    assert!(status.success(), "git {args:?}");
+}
+
+#[cfg(feature = "git")]
+#[test]
+fn tracked_only_leaves_out_untracked_files() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::create_dir(root.join("src")).expect("create src");
+    fs::create_dir(root.join("scratch")).expect("create scratch");
+    fs::write(root.join("src/lib.rs"), "pub fn needle() {}\n").expect("write lib");
+    fs::write(root.join("needle.md"), "notes\n").expect("write notes");
+    fs::write(root.join("new.rs"), "let needle = 1;\n").expect("write new");
+    fs::write(root.join("scratch/needle.txt"), "needle\n").expect("write scratch");
+    git(root, &["init", "-q"]);
+    git(root, &["add", "src/lib.rs", "needle.md"]);
+
+    let paths = |query: &str| {
+        let mut paths: Vec<PathBuf> = build_candidates(query, root)
+            .expect("candidates")
+            .into_iter()
+            .map(|candidate| candidate.path)
+            .collect();
+        paths.sort();
+        paths
+    };
+    with_system_config("", |_| {
+        assert!(paths("needle").contains(&PathBuf::from("new.rs")));
+    });
+    with_system_config("tracked_only = true\n", |_| {
+        assert_eq!(
+            paths("needle"),
+            [PathBuf::from("needle.md"), PathBuf::from("src/lib.rs")]
+        );
+        // Directories show only when they hold a tracked file.
+        assert_eq!(
+            paths(""),
+            [
+                PathBuf::from("needle.md"),
+                PathBuf::from("src"),
+                PathBuf::from("src/lib.rs")
+            ]
+        );
+        // A subdirectory of the repository lists its own tracked files.
+        let in_src: Vec<PathBuf> = build_candidates("", &root.join("src"))
+            .expect("candidates in src")
+            .into_iter()
+            .map(|candidate| candidate.path)
+            .collect();
+        assert_eq!(in_src, [PathBuf::from("lib.rs")]);
+    });
+
+    let plain = tempdir().expect("plain dir");
+    fs::write(plain.path().join("a.txt"), "needle\n").expect("write a");
+    with_system_config("tracked_only = true\n", |_| {
+        let error = build_candidates("needle", plain.path()).expect_err("not a repository");
+        let message = format!("{error:#}");
+        assert!(
+            message.contains("--tracked") && message.contains("git repository"),
+            "{message}"
+        );
+    });
+}
+
+#[cfg(feature = "git")]
+#[test]
+fn changed_lists_files_changed_since_the_ref() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::create_dir(root.join("src")).expect("create src");
+    fs::write(root.join("committed.rs"), "let needle = 1;\n").expect("write committed");
+    fs::write(root.join("src/modified.rs"), "fn other() {}\n").expect("write modified");
+    fs::write(root.join("deleted.rs"), "needle\n").expect("write deleted");
+    fs::write(root.join(".gitignore"), "*.log\n").expect("write gitignore");
+    git(root, &["init", "-q"]);
+    git(root, &["add", "."]);
+    git(
+        root,
+        &[
+            "-c",
+            "user.name=yoink",
+            "-c",
+            "user.email=yoink@example.com",
+            "commit",
+            "-qm",
+            "base",
+        ],
+    );
+    fs::write(root.join("src/modified.rs"), "fn needle() {}\n").expect("modify");
+    fs::remove_file(root.join("deleted.rs")).expect("delete");
+    fs::write(root.join("new_needle.txt"), "fresh\n").expect("write untracked");
+    fs::write(root.join("needle.log"), "needle\n").expect("write ignored");
+
+    with_system_config("", |_| {
+        let session = Session::start().expect("session");
+        let mut state = SessionState {
+            changed_ref: Some("HEAD".to_string()),
+            ..SessionState::default()
+        };
+        session.store(&state).expect("store");
+        std::env::set_var("YOINK_SESSION", session.id());
+
+        let mut paths: Vec<PathBuf> = build_candidates("needle", root)
+            .expect("candidates")
+            .into_iter()
+            .map(|candidate| candidate.path)
+            .collect();
+        paths.sort();
+        let entries = build_search_entries("needle", root).expect("entries");
+
+        state.changed_ref = Some("no-such-ref".to_string());
+        session.store(&state).expect("store bad ref");
+        let error = build_candidates("needle", root).expect_err("bad ref");
+        std::env::remove_var("YOINK_SESSION");
+        session.remove();
+
+        assert_eq!(
+            paths,
+            [
+                PathBuf::from("new_needle.txt"),
+                PathBuf::from("src/modified.rs")
+            ]
+        );
+        let occurrence = entries
+            .iter()
+            .find(|entry| entry.line.is_some())
+            .expect("occurrence row");
+        assert_eq!(
+            (occurrence.path.as_path(), occurrence.line),
+            (Path::new("src/modified.rs"), Some(1))
+        );
+        assert!(
+            entries
+                .iter()
+                .all(|entry| entry.path != Path::new("committed.rs")),
+            "{entries:#?}"
+        );
+        let message = format!("{error:#}");
+        assert!(message.contains("--changed=no-such-ref"), "{message}");
+    });
+}
+
+#[test]
+fn preprocess_args_put_the_path_in_place_of_braces() {
+    let path = Path::new("docs/a report.pdf");
+    assert_eq!(
+        preprocess_args("pdftotext -layout {} -", path),
+        ["pdftotext", "-layout", "docs/a report.pdf", "-"]
+    );
+    assert_eq!(
+        preprocess_args("docx2txt", path),
+        ["docx2txt", "docs/a report.pdf"]
+    );
+}
+
+#[cfg(target_family = "unix")]
+#[test]
+fn preprocessed_files_match_on_their_extracted_text() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    let tools = tempdir().expect("tools");
+    let extractor = tools.path().join("extract");
+    fs::write(
+        &extractor,
+        "#!/bin/sh\nprintf 'Quarterly report\\n   the needle is on page two\\n'\n",
+    )
+    .expect("write extractor");
+    fs::set_permissions(&extractor, fs::Permissions::from_mode(0o755)).expect("chmod extractor");
+    fs::write(root.join("report.pdf"), b"%PDF-1.7\0\x01binary").expect("write pdf");
+    fs::write(root.join("raw.docx"), b"PK\0\x03 needle in the raw bytes").expect("write docx");
+    fs::write(root.join("notes.txt"), "no match\n").expect("write notes");
+    let config = format!(
+        "preprocess.pdf = {} {{}}\npreprocess.DOCX = no-such-extractor-for-yoink {{}} -\n",
+        extractor.display()
+    );
+
+    with_system_config(&config, |_| {
+        let mut paths: Vec<PathBuf> = build_candidates("needle", root)
+            .expect("candidates")
+            .into_iter()
+            .map(|candidate| candidate.path)
+            .collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            [PathBuf::from("report.pdf")],
+            "raw.docx's extractor is missing"
+        );
+
+        let entries = build_search_entries("needle", root).expect("entries");
+        let occurrence = entries
+            .iter()
+            .find(|entry| entry.line.is_some())
+            .expect("occurrence row");
+        assert_eq!(
+            (
+                occurrence.path.as_path(),
+                occurrence.line,
+                occurrence.column
+            ),
+            (Path::new("report.pdf"), Some(2), Some(8))
+        );
+        assert!(
+            occurrence.display.contains("is on page two"),
+            "{}",
+            occurrence.display
+        );
+
+        let by_name = build_candidates("raw", root).expect("path match");
+        assert!(by_name
+            .iter()
+            .any(|candidate| candidate.path == Path::new("raw.docx")));
+    });
+}
+
+#[cfg(target_family = "unix")]
+#[test]
+fn preprocessed_text_anchors_match_each_line() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    let tools = tempdir().expect("tools");
+    let extractor = tools.path().join("extract");
+    fs::write(
+        &extractor,
+        "#!/bin/sh\nprintf 'Quarterly report\\nneedle on page two\\n'\n",
+    )
+    .expect("write extractor");
+    fs::set_permissions(&extractor, fs::Permissions::from_mode(0o755)).expect("chmod extractor");
+    fs::write(root.join("report.pdf"), b"%PDF-1.7\0\x01binary").expect("write pdf");
+    let config = format!("preprocess.pdf = {} {{}}\n", extractor.display());
+
+    with_system_config(&config, |_| {
+        let content_matched = |query: &str| {
+            build_candidates(query, root)
+                .expect("candidates")
+                .iter()
+                .any(|candidate| candidate.content_match)
+        };
+        // Against the whole text these only match at its start and end.
+        assert!(content_matched("^needle"));
+        assert!(content_matched("report$"));
+        assert!(
+            !content_matched("report.needle"),
+            "a single-line pattern stops at the newline"
+        );
+    });
+}
+
+#[cfg(feature = "archives")]
+#[test]
+fn archives_list_members_whose_names_match() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    let staging = tempdir().expect("staging");
+    fs::create_dir(staging.path().join("images")).expect("create images");
+    fs::write(staging.path().join("images/logo.svg"), "<svg/>\n").expect("write logo");
+    fs::write(staging.path().join("notes.txt"), "logo\n").expect("write notes");
+    fs::create_dir(root.join("bundles")).expect("create bundles");
+    let status = Command::new("tar")
+        .arg("-czf")
+        .arg(root.join("bundles/assets.tar.gz"))
+        .args(["images", "notes.txt"])
+        .current_dir(staging.path())
+        .status()
+        .expect("run tar");
+    assert!(status.success());
+    fs::write(root.join("logo.rs"), "fn main() {}\n").expect("write logo.rs");
+
+    let paths = |config: &str| {
+        let mut paths = Vec::new();
+        with_system_config(config, |_| {
+            paths = build_candidates("logo", root)
+                .expect("candidates")
+                .into_iter()
+                .map(|candidate| candidate.path)
+                .collect();
+        });
+        paths.sort();
+        paths
+    };
+    assert_eq!(
+        paths("search_scope = paths\narchives = true\n"),
+        [
+            PathBuf::from("bundles/assets.tar.gz::images/logo.svg"),
+            PathBuf::from("logo.rs")
+        ]
+    );
+    assert_eq!(paths("search_scope = paths\n"), [PathBuf::from("logo.rs")]);
+    with_system_config("archives = true\n", |_| {
+        let entries = build_search_entries("logo", root).expect("entries");
+        let member = Path::new("bundles/assets.tar.gz::images/logo.svg");
+        assert!(
+            entries.iter().any(|entry| entry.path == member),
+            "{entries:#?}"
+        );
+    });
+}
+
+#[cfg(feature = "git")]
+#[test]
+fn rev_searches_the_commit_instead_of_the_work_tree() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::create_dir(root.join("docs")).expect("create docs");
+    fs::write(root.join("lib.rs"), "fn main() {}\n    let needle = 1;\n").expect("write lib");
+    fs::write(root.join("docs/needle.md"), "nothing here\n").expect("write docs");
+    git(root, &["init", "-q"]);
+    git(root, &["add", "."]);
+    git(
+        root,
+        &[
+            "-c",
+            "user.name=yoink",
+            "-c",
+            "user.email=yoink@example.com",
+            "commit",
+            "-qm",
+            "base",
+        ],
+    );
+    fs::write(root.join("lib.rs"), "fn main() {}\n").expect("edit lib");
+    fs::remove_file(root.join("docs/needle.md")).expect("delete docs");
+    fs::write(root.join("fresh.rs"), "needle\n").expect("write untracked");
+
+    with_system_config("", |_| {
+        let session = Session::start().expect("session");
+        let mut state = SessionState {
+            rev: Some("HEAD".to_string()),
+            ..SessionState::default()
+        };
+        session.store(&state).expect("store");
+        std::env::set_var("YOINK_SESSION", session.id());
+
+        let mut paths: Vec<PathBuf> = build_candidates("needle", root)
+            .expect("candidates")
+            .into_iter()
+            .map(|candidate| candidate.path)
+            .collect();
+        paths.sort();
+        let entries = build_search_entries("needle", root).expect("entries");
+
+        state.rev = Some("no-such-rev".to_string());
+        session.store(&state).expect("store bad rev");
+        let error = build_candidates("needle", root).expect_err("bad rev");
+        std::env::remove_var("YOINK_SESSION");
+        session.remove();
+
+        assert_eq!(
+            paths,
+            [PathBuf::from("docs/needle.md"), PathBuf::from("lib.rs")]
+        );
+        let occurrence = entries
+            .iter()
+            .find(|entry| entry.line.is_some())
+            .expect("occurrence row");
+        assert_eq!(
+            (
+                occurrence.path.as_path(),
+                occurrence.line,
+                occurrence.column
+            ),
+            (Path::new("lib.rs"), Some(2), Some(9))
+        );
+        assert!(
+            entries
+                .iter()
+                .all(|entry| entry.path != Path::new("fresh.rs")),
+            "{entries:#?}"
+        );
+        let message = format!("{error:#}");
+        assert!(message.contains("--rev=no-such-rev"), "{message}");
+    });
+}
+
+#[test]
+fn streamed_entries_match_the_buffered_list() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::create_dir(root.join("src")).expect("create src");
+    fs::write(root.join("a.rs"), "let needle = 1;\nneedle();\n").expect("write a");
+    fs::write(root.join("src/b.rs"), "one needle\n").expect("write b");
+    fs::write(root.join("src/needle.txt"), "nothing\n").expect("write needle.txt");
+    fs::write(root.join("other.rs"), "haystack\n").expect("write other");
+
+    let collect = |query: &str| {
+        let mut entries = Vec::new();
+        for_each_search_entry(query, root, &mut SearchStats::default(), |entry| {
+            entries.push(entry);
+            Ok(())
+        })
+        .expect("streamed entries");
+        entries
+    };
+    let by_display = |mut entries: Vec<SearchEntry>| {
+        entries.sort_by(|a, b| a.display.cmp(&b.display));
+        entries
+    };
+
+    with_system_config("snippet_context=1\n", |_| {
+        let buffered = build_search_entries("needle", root).expect("entries");
+        assert_eq!(collect("needle"), buffered);
+        assert_eq!(
+            collect(""),
+            build_search_entries("", root).expect("empty entries")
+        );
+    });
+
+    with_system_config("snippet_context=1\nsort_mode=stream\n", |_| {
+        let streamed = collect("needle");
+        let buffered = build_search_entries("needle", root).expect("entries");
+        assert_eq!(by_display(streamed.clone()), by_display(buffered));
+
+        // Each file's rows still come as one run, led by its file row.
+        let mut finished: Vec<&Path> = Vec::new();
+        for (index, entry) in streamed.iter().enumerate() {
+            if index > 0 && streamed[index - 1].path != entry.path {
+                finished.push(&streamed[index - 1].path);
+                assert!(entry.kind.is_some(), "{streamed:#?}");
+            }
+            assert!(!finished.contains(&entry.path.as_path()), "{streamed:#?}");
+        }
+    });
+}
+
+#[test]
+fn streaming_emits_content_matches_before_the_scan_finishes() {
+    const FILES: usize = 1000;
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    let write_all = |text: &str| {
+        for n in 0..FILES {
+            fs::write(root.join(format!("f{n:04}.txt")), text).expect("write file");
+        }
+    };
+
+    // The first row rewrites every file, so only files the scan had already
+    // read by then can still be listed.
+    let file_rows = |config: &str| {
+        write_all("needle\n");
+        let mut files = 0;
+        with_system_config(config, |_| {
+            let mut first = true;
+            for_each_search_entry("needle", root, &mut SearchStats::default(), |entry| {
+                if std::mem::take(&mut first) {
+                    write_all("haystack\n");
+                }
+                files += usize::from(entry.kind.is_some());
+                Ok(())
+            })
+            .expect("entries");
+        });
+        files
+    };
+
+    assert_eq!(file_rows(""), FILES);
+    let streamed = file_rows("sort_mode=stream\n");
+    assert!((1..FILES / 2).contains(&streamed), "{streamed}");
+}
+
+#[test]
+fn accessibility_renders_long_line_and_notice_rows_as_text() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::write(
+        root.join("bundle.js"),
+        format!("foo( {}\n", "x".repeat(200)),
+    )
+    .expect("write bundle");
+
+    with_system_config("accessibility=true\nmax_columns=40\n", |_| {
+        let rows = displays(&build_search_entries("foo(", root).expect("entries"));
+        assert_screen_reader_friendly(&rows);
+
+        assert!(rows[0].starts_with("NOTE: "), "{rows:#?}");
+        assert!(
+            rows.contains(&"FILE bundle.js (1 match)".to_string()),
+            "{rows:#?}"
+        );
+        assert!(
+            rows.iter()
+                .any(|row| row.starts_with("  line 1: foo( xxx") && row.ends_with("x...")),
+            "{rows:#?}"
+        );
+    });
+}
+
+#[test]
+fn accessibility_renders_empty_and_buffer_rows_as_text() {
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::create_dir_all(root.join("hollow")).expect("mkdir");
+    fs::write(root.join("blank.txt"), "").expect("write blank");
+    let buffer = root.join("buffer.txt");
+    fs::write(&buffer, "ok\nERROR disk full\n").expect("write buffer");
+
+    with_system_config("accessibility=true\n", |_| {
+        let rows =
+            displays(&build_empty_entries("", root, &mut SearchStats::default()).expect("empty"));
+        assert_screen_reader_friendly(&rows);
+        assert!(rows.contains(&"DIR hollow".to_string()), "{rows:#?}");
+        assert!(rows.contains(&"FILE blank.txt".to_string()), "{rows:#?}");
+
+        let rows = displays(&build_buffer_entries("ERROR", &buffer).expect("buffer"));
+        assert_screen_reader_friendly(&rows);
+        assert_eq!(rows, vec!["line 2: ERROR disk full".to_string()]);
+    });
+}
+
+#[cfg(target_family = "unix")]
+#[test]
+fn internal_engine_anchors_each_line_and_skips_unreadable_files() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempdir().expect("tempdir");
+    let root = dir.path();
+    fs::write(root.join("notes.txt"), "first line\nlet more = needle;\n").expect("write notes");
+    let locked = root.join("locked.txt");
+    fs::write(&locked, "let more = needle;\n").expect("write locked");
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).expect("chmod");
+    // Root reads through any mode, and then there is nothing to skip.
+    let unreadable = fs::read(&locked).is_err();
+
+    with_system_config("engine = internal\n", |_| {
+        let content: Vec<PathBuf> = build_candidates("^let more", root)
+            .expect("candidates")
+            .into_iter()
+            .filter(|candidate| candidate.content_match)
+            .map(|candidate| candidate.path)
+            .collect();
+        assert!(content.contains(&PathBuf::from("notes.txt")), "{content:?}");
+        assert_eq!(
+            content.contains(&PathBuf::from("locked.txt")),
+            !unreadable,
+            "{content:?}"
+        );
+
+        let lines: Vec<(PathBuf, Option<usize>)> = build_search_entries("^let more", root)
+            .expect("entries")
+            .into_iter()
+            .filter(|entry| entry.path == Path::new("notes.txt"))
+            .map(|entry| (entry.path, entry.line))
+            .collect();
+        assert!(
+            lines.contains(&(PathBuf::from("notes.txt"), Some(2))),
+            "{lines:?}"
+        );
+    });
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o644)).expect("chmod back");
+}
@@ -0,0 +1,293 @@
+use std::fs::{self, File};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use tempfile::tempdir;
+use yoink::config::{ConfigSnapshot, ConfigSource, SnapshotFile};
+use yoink::lang::FileTypeSet;
+use yoink::pills::FilterPill;
+use yoink::search::{SearchScope, SortMode};
+use yoink::session::{
+    collect_stale_sessions, decode_state, encode_state, OccurrenceCursor, Session, SessionState,
+    STALE_SESSION_AGE,
+};
+
+#[test]
+fn round_trips_state_through_json() {
+    let state = SessionState {
+        include_hidden: Some(true),
+        sort_mode: Some(SortMode::Alphabetical),
+        max_depth: Some(3),
+        modified_within: Some(Duration::from_secs(86400)),
+        smart_defaults: Some(false),
+        accessibility: Some(true),
+        search_scope: Some(SearchScope::Paths),
+        word_boundaries: Some(true),
+        match_basename_only: Some(true),
+        include_symlinks: Some(false),
+        include_mounts: Some(true),
+        multiline: None,
+        file_types: Some(FileTypeSet::parse("rust,md").expect("types")),
+        file_types_not: None,
+        watch: Some(true),
+        tracked_only: Some(false),
+        root: Some(PathBuf::from("/work/with \"quotes\"\\and\ttabs")),
+        roots: vec![PathBuf::from("api"), PathBuf::from("web/frontend")],
+        changed_ref: Some("origin/main~2".to_string()),
+        rev: Some("v1.2.0".to_string()),
+        file_rows: vec![1, 4, 12],
+        plain: true,
+        highlight_off: true,
+        occurrence: Some(OccurrenceCursor {
+            path: PathBuf::from("src/ui.rs"),
+            line: None,
+            index: 6,
+        }),
+        config: Some(ConfigSnapshot {
+            files: vec![
+                SnapshotFile {
+                    source: ConfigSource::settings(PathBuf::from("/cfg/config.toml")),
+                    modified: Some(1_760_000_000_123_456_789),
+                    content: Some("case_mode = \"smart\"\r\n# \u{1}\n".to_string()),
+                },
+                SnapshotFile {
+                    source: ConfigSource::ignore(PathBuf::from("/home/me/.yoinkignore")),
+                    modified: None,
+                    content: None,
+                },
+            ],
+        }),
+        pills: vec![
+            FilterPill::Dir(PathBuf::from("crates/core")),
+            FilterPill::Ext("rs".to_string()),
+            FilterPill::Glob("**/*_test.rs".to_string()),
+        ],
+        aliases: vec![(
+            PathBuf::from("lib.rs"),
+            vec![
+                PathBuf::from("vendor/lib.rs"),
+                PathBuf::from("link/\"odd\".rs"),
+            ],
+        )],
+        fresh_settings: true,
+    };
+
+    assert_eq!(decode_state(&encode_state(&state)), Some(state));
+    assert_eq!(
+        decode_state(&encode_state(&SessionState::default())),
+        Some(SessionState::default())
+    );
+}
+
+#[test]
+fn typed_setters_persist_across_handles() {
+    let dir = tempdir().expect("tempdir");
+    let session = Session::new(dir.path().to_path_buf(), "one".to_string());
+    session
+        .store(&SessionState {
+            root: Some(PathBuf::from("/work")),
+            ..SessionState::default()
+        })
+        .expect("store root");
+
+    assert_eq!(session.include_hidden(), None);
+    session.set_include_hidden(Some(false)).expect("set hidden");
+    session
+        .set_sort_mode(Some(SortMode::Alphabetical))
+        .expect("set sort");
+
+    let reopened = Session::new(dir.path().to_path_buf(), "one".to_string());
+    assert_eq!(reopened.include_hidden(), Some(false));
+    assert_eq!(reopened.sort_mode(), Some(SortMode::Alphabetical));
+    assert_eq!(
+        reopened.root(),
+        Some(PathBuf::from("/work")),
+        "setters keep the other fields"
+    );
+}
+
+#[test]
+fn partial_writes_fall_back_to_defaults_and_are_replaced_atomically() {
+    let dir = tempdir().expect("tempdir");
+    let session = Session::new(dir.path().to_path_buf(), "crashy".to_string());
+
+    // A writer that died mid-write leaves a truncated file and a stray temp file.
+    let full = encode_state(&SessionState {
+        include_hidden: Some(true),
+        ..SessionState::default()
+    });
+    fs::write(session.path(), &full[..full.len() / 2]).expect("write partial");
+    fs::write(dir.path().join(".crashy-abc.tmp"), "{\"include_hidden\":tr").expect("write temp");
+
+    assert_eq!(session.load(), SessionState::default());
+
+    session.set_include_hidden(Some(true)).expect("store");
+    assert_eq!(session.include_hidden(), Some(true));
+
+    let mut leftovers: Vec<String> = fs::read_dir(dir.path())
+        .expect("read dir")
+        .map(|entry| {
+            entry
+                .expect("entry")
+                .file_name()
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect();
+    leftovers.sort();
+    assert_eq!(
+        leftovers,
+        vec![".crashy-abc.tmp", "crashy.json", "crashy.lock"]
+    );
+}
+
+#[test]
+fn missing_and_corrupt_files_load_defaults() {
+    let dir = tempdir().expect("tempdir");
+    let session = Session::new(dir.path().to_path_buf(), "gone".to_string());
+    assert_eq!(session.load(), SessionState::default());
+
+    for corrupt in [
+        "",
+        "not json",
+        "{\"root\":true}",
+        "{\"roots\":[\"a\",]}",
+        "{} trailing",
+        "{\"sort_mode\":\"random\"}",
+        "{\"file_rows\":[\"1\"]}",
+        "{\"roots\":[1]}",
+        "{\"occurrence\":[\"a\",null]}",
+        "{\"occurrence\":[\"a\",\"1\",0]}",
+        "{\"config\":[[\"/c\",\"theme\",null,null]]}",
+        "{\"file_types\":[\"cobol\"]}",
+        "{\"pills\":[[\"tag\",\"x\"]]}",
+        "{\"pills\":[[\"dir\",\"\"]]}",
+        "{\"file_types_not\":\"rust\"}",
+        "{\"config\":[[\"/c\",\"ignore\",\"1\",null]]}",
+    ] {
+        fs::write(session.path(), corrupt).expect("write corrupt");
+        assert_eq!(
+            session.load(),
+            SessionState::default(),
+            "content {corrupt:?}"
+        );
+    }
+}
+
+#[test]
+fn concurrent_sessions_do_not_share_state() {
+    let dir = tempdir().expect("tempdir");
+    let left = Session::new(dir.path().to_path_buf(), "left".to_string());
+    let right = Session::new(dir.path().to_path_buf(), "right".to_string());
+
+    left.set_include_hidden(Some(true)).expect("left hidden");
+    right
+        .store(&SessionState {
+            root: Some(PathBuf::from("/elsewhere")),
+            ..SessionState::default()
+        })
+        .expect("right root");
+
+    assert_eq!(left.include_hidden(), Some(true));
+    assert_eq!(left.root(), None);
+    assert_eq!(right.include_hidden(), None);
+    assert_eq!(right.root(), Some(PathBuf::from("/elsewhere")));
+
+    left.remove();
+    assert_eq!(left.load(), SessionState::default());
+    assert_eq!(right.root(), Some(PathBuf::from("/elsewhere")));
+}
+
+#[test]
+fn concurrent_updates_to_one_session_keep_both_changes() {
+    let dir = tempdir().expect("tempdir");
+    let session = Session::new(dir.path().to_path_buf(), "shared".to_string());
+
+    // A reload recording its rows while alt-x adds pills, many times over.
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            for rows in 0..100 {
+                session.set_file_rows(vec![rows]).expect("set rows");
+            }
+        });
+        scope.spawn(|| {
+            for index in 0..100 {
+                session
+                    .push_pill(FilterPill::Ext(format!("e{index}")))
+                    .expect("push pill");
+            }
+        });
+    });
+
+    let state = session.load();
+    assert_eq!(state.file_rows, vec![99]);
+    assert_eq!(state.pills.len(), 100, "no pill was lost to a racing write");
+}
+
+#[test]
+fn collects_only_stale_session_files() {
+    let dir = tempdir().expect("tempdir");
+    let now = SystemTime::now();
+    let old = now - STALE_SESSION_AGE - Duration::from_secs(60);
+
+    // A live session's lock keeps its creation mtime; its state file is what ages.
+    for name in [
+        "old.json",
+        ".old-x.tmp",
+        "old.lock",
+        "fresh.lock",
+        "keep.txt",
+    ] {
+        let file = File::create(dir.path().join(name)).expect("create");
+        file.set_modified(old).expect("set mtime");
+    }
+    File::create(dir.path().join("fresh.json")).expect("create fresh");
+
+    let removed = collect_stale_sessions(dir.path(), STALE_SESSION_AGE, now).expect("gc");
+    assert_eq!(removed, 3);
+    assert!(!dir.path().join("old.json").exists());
+    assert!(!dir.path().join(".old-x.tmp").exists());
+    assert!(!dir.path().join("old.lock").exists());
+    assert!(dir.path().join("keep.txt").exists());
+    assert!(dir.path().join("fresh.json").exists());
+    assert!(dir.path().join("fresh.lock").exists());
+
+    let missing = dir.path().join("missing");
+    assert_eq!(
+        collect_stale_sessions(&missing, STALE_SESSION_AGE, now).expect("gc missing"),
+        0
+    );
+}
+
+#[test]
+fn pills_stack_without_duplicates_and_pop_newest_first() {
+    let dir = tempdir().expect("tempdir");
+    let session = Session::new(dir.path().to_path_buf(), "pills".to_string());
+
+    session
+        .push_pill(FilterPill::Dir(PathBuf::from("src")))
+        .expect("push dir");
+    session
+        .push_pill(FilterPill::Ext("rs".to_string()))
+        .expect("push ext");
+    session
+        .push_pill(FilterPill::Dir(PathBuf::from("src")))
+        .expect("push again");
+    assert_eq!(
+        session.pills(),
+        vec![
+            FilterPill::Dir(PathBuf::from("src")),
+            FilterPill::Ext("rs".to_string())
+        ]
+    );
+
+    assert_eq!(
+        session.pop_pill().expect("pop"),
+        Some(FilterPill::Ext("rs".to_string()))
+    );
+    assert_eq!(
+        session.pop_pill().expect("pop"),
+        Some(FilterPill::Dir(PathBuf::from("src")))
+    );
+    assert_eq!(session.pop_pill().expect("pop"), None);
+}
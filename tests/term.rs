@@ -0,0 +1,119 @@
+use regex::Regex;
+use yoink::search::{highlight_query_matches, highlight_query_matches_in};
+use yoink::term::{
+    detect_color_mode, parse_match_color_setting, sanitize_control, strip_ansi, ColorMode,
+};
+
+#[test]
+fn dumb_and_unset_terms_are_plain() {
+    assert_eq!(
+        detect_color_mode(Some("dumb"), false, None),
+        ColorMode::Plain
+    );
+    assert_eq!(
+        detect_color_mode(Some("vt100"), false, None),
+        ColorMode::Plain
+    );
+    assert_eq!(detect_color_mode(Some(""), false, None), ColorMode::Plain);
+    assert_eq!(detect_color_mode(None, false, Some(256)), ColorMode::Plain);
+}
+
+#[test]
+fn terminfo_color_count_decides_for_other_terms() {
+    assert_eq!(
+        detect_color_mode(Some("xterm-256color"), false, Some(256)),
+        ColorMode::Ansi
+    );
+    assert_eq!(
+        detect_color_mode(Some("xterm"), false, Some(8)),
+        ColorMode::Ansi
+    );
+    assert_eq!(
+        detect_color_mode(Some("ansi-mono"), false, Some(-1)),
+        ColorMode::Plain
+    );
+    assert_eq!(
+        detect_color_mode(Some("screen"), false, Some(2)),
+        ColorMode::Plain
+    );
+    // Without terminfo, trust anything not known to be monochrome.
+    assert_eq!(
+        detect_color_mode(Some("alacritty"), false, None),
+        ColorMode::Ansi
+    );
+}
+
+#[test]
+fn no_color_forces_plain() {
+    assert_eq!(
+        detect_color_mode(Some("xterm-256color"), true, Some(256)),
+        ColorMode::Plain
+    );
+}
+
+#[test]
+fn marker_highlighter_wraps_matches_without_escapes() {
+    let re = Regex::new("needle").expect("regex");
+    assert_eq!(
+        highlight_query_matches("a needle, another needle", Some(&re), ColorMode::Plain),
+        "a >>needle<<, another >>needle<<"
+    );
+    assert_eq!(
+        highlight_query_matches("a needle", Some(&re), ColorMode::Ansi),
+        "a \x1b[1;36mneedle\x1b[0m"
+    );
+    assert_eq!(
+        highlight_query_matches("a needle", None, ColorMode::Plain),
+        "a needle"
+    );
+}
+
+#[test]
+fn strips_sgr_sequences() {
+    assert_eq!(
+        strip_ansi("\x1b[2;34mrs  \x1b[0m src/\x1b[1;36mui\x1b[0m.rs"),
+        "rs   src/ui.rs"
+    );
+    assert_eq!(strip_ansi("plain"), "plain");
+}
+
+#[test]
+fn parses_match_colors_by_name_or_code() {
+    assert_eq!(parse_match_color_setting("magenta"), Some(35));
+    assert_eq!(parse_match_color_setting(" Cyan "), Some(36));
+    assert_eq!(parse_match_color_setting("92"), Some(92));
+    assert_eq!(parse_match_color_setting("38"), None);
+    assert_eq!(parse_match_color_setting("mauve"), None);
+}
+
+#[test]
+fn colored_highlights_keep_plain_markers() {
+    let re = Regex::new("b").expect("regex");
+    assert_eq!(
+        highlight_query_matches_in("abc", Some(&re), ColorMode::Ansi, 35),
+        "a\x1b[1;35mb\x1b[0mc"
+    );
+    assert_eq!(
+        highlight_query_matches_in("abc", Some(&re), ColorMode::Plain, 35),
+        "a>>b<<c"
+    );
+}
+
+#[test]
+fn sanitize_control_drops_escapes_and_shows_other_controls() {
+    assert_eq!(
+        sanitize_control("\x1b[31mred\x1b[0m alert\x07!"),
+        "red alert\u{2407}!"
+    );
+    assert_eq!(sanitize_control("a\tb\x00c\x7fd"), "a b\u{2400}c\u{2421}d");
+    // OSC titles and hyperlinks end at BEL or `ESC \`.
+    assert_eq!(
+        sanitize_control("\x1b]0;pwned\x07ok \x1b]8;;http://x\x1b\\link"),
+        "ok link"
+    );
+    assert_eq!(
+        sanitize_control("carriage\rreturn\u{9b}"),
+        "carriage\u{240d}return\u{fffd}"
+    );
+    assert_eq!(sanitize_control("plain ünïcode"), "plain ünïcode");
+}
@@ -0,0 +1,658 @@
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use tempfile::tempdir;
+
+use clap::Parser;
+use regex::Regex;
+use yoink::cli::{Cli, ConfigCommand, FieldNumber, InternalCommand};
+use yoink::search::{
+    format_search_entries, root_gone_entry, root_gone_message, SearchEntry, SearchScope, SortMode,
+    YoinkSettings,
+};
+use yoink::session::{new_session_id, OccurrenceCursor, Session, SessionState, SESSION_ENV};
+use yoink::ui::{
+    alias_header, binary_summary, files_header, jump_binds, occurrence_header, occurrence_index,
+    outcome_exit_code, parse_selected_line, pill_binds, preview_command, reload_command,
+    render_plain_preview, resolve_session_action, root_gone_outcome, roots_header, settings_header,
+    shell_quote, step_occurrence, toggle_binds, SessionAction, SessionOutcome, SessionSource,
+    FILES_HEADER, ROOT_GONE_EXIT_CODE,
+};
+
+#[test]
+fn selection_keys_act_on_the_selected_entry() {
+    assert_eq!(resolve_session_action("enter", true), SessionAction::Select);
+    assert_eq!(
+        resolve_session_action("ctrl-v", true),
+        SessionAction::OpenEditor("vim")
+    );
+    assert_eq!(
+        resolve_session_action("ctrl-o", true),
+        SessionAction::OpenEditor("code")
+    );
+    assert_eq!(
+        resolve_session_action("ctrl-s", true),
+        SessionAction::OpenEditor("subl")
+    );
+    assert_eq!(
+        resolve_session_action("ctrl-y", true),
+        SessionAction::CopyPath
+    );
+}
+
+#[test]
+fn empty_selection_keys_fall_back_to_query_actions() {
+    assert_eq!(
+        resolve_session_action("enter", false),
+        SessionAction::NoSelection
+    );
+    assert_eq!(
+        resolve_session_action("ctrl-v", false),
+        SessionAction::EditorHint("vim")
+    );
+    assert_eq!(
+        resolve_session_action("ctrl-o", false),
+        SessionAction::EditorHint("code")
+    );
+    assert_eq!(
+        resolve_session_action("ctrl-s", false),
+        SessionAction::EditorHint("subl")
+    );
+    assert_eq!(
+        resolve_session_action("ctrl-y", false),
+        SessionAction::CopyQuery
+    );
+}
+
+#[test]
+fn create_uses_the_query_regardless_of_selection() {
+    assert_eq!(
+        resolve_session_action("ctrl-n", false),
+        SessionAction::CreateFromQuery
+    );
+    assert_eq!(
+        resolve_session_action("ctrl-n", true),
+        SessionAction::CreateFromQuery
+    );
+}
+
+#[test]
+fn alt_y_yanks_only_with_a_selection() {
+    assert_eq!(
+        resolve_session_action("alt-y", true),
+        SessionAction::YankRange
+    );
+    assert_eq!(
+        resolve_session_action("alt-y", false),
+        SessionAction::YankHint
+    );
+}
+
+#[test]
+fn entries_round_trip_through_the_line_protocol() {
+    let entries = vec![
+        SearchEntry {
+            display: "📄 src/lib.rs".to_string(),
+            path: PathBuf::from("src/lib.rs"),
+            line: None,
+            column: None,
+            kind: None,
+            match_count: None,
+        },
+        SearchEntry {
+            display: " 2   ↳   12  let\tx = 1;".to_string(),
+            path: PathBuf::from("src/lib.rs"),
+            line: Some(12),
+            column: Some(9),
+            kind: None,
+            match_count: None,
+        },
+    ];
+
+    let formatted = format_search_entries(&entries);
+    let lines: Vec<&str> = formatted.lines().collect();
+    assert_eq!(
+        parse_selected_line(lines[0]),
+        (PathBuf::from("src/lib.rs"), None, None)
+    );
+    assert_eq!(
+        parse_selected_line(lines[1]),
+        (PathBuf::from("src/lib.rs"), Some(12), Some(9))
+    );
+}
+
+#[test]
+fn tabs_and_newlines_in_paths_keep_the_line_protocol_intact() {
+    let names = ["weird\tname.txt", "two\nlines.txt", "cr\r.txt"];
+    let entries: Vec<SearchEntry> = names
+        .iter()
+        .map(|name| SearchEntry {
+            display: format!("{name}\t[c]"),
+            path: PathBuf::from(name),
+            line: Some(3),
+            column: Some(1),
+            kind: None,
+            match_count: None,
+        })
+        .collect();
+
+    let formatted = format_search_entries(&entries);
+    let lines: Vec<&str> = formatted.lines().collect();
+    assert_eq!(lines.len(), names.len());
+    assert_eq!(lines[0].split('\t').nth(1), Some("weird%09name.txt"));
+    for (line, name) in lines.iter().zip(names) {
+        assert_eq!(
+            parse_selected_line(line),
+            (PathBuf::from(name), Some(3), Some(1))
+        );
+    }
+    // The display keeps turning tabs into spaces.
+    assert!(lines[0].starts_with("weird    name.txt    [c]\t"));
+}
+
+#[test]
+fn preview_reads_the_line_and_column_fields_or_their_absence() {
+    let preview = |args: &[&str]| match Cli::try_parse_from(args).expect("parse").internal {
+        Some(InternalCommand::Preview { line, column, .. }) => (line, column),
+        other => panic!("not a preview: {other:?}"),
+    };
+    // File rows leave `{3}` and `{4}` empty, and fzf passes them as `''`.
+    assert_eq!(
+        preview(&["yoink", "__preview", "--", "src/lib.rs", "q", "", ""]),
+        (FieldNumber(None), FieldNumber(None))
+    );
+    assert_eq!(
+        preview(&["yoink", "__preview", "--", "src/lib.rs", "q", "12", "9"]),
+        (FieldNumber(Some(12)), FieldNumber(Some(9)))
+    );
+    // Three-field lines have no `{4}` at all.
+    assert_eq!(
+        preview(&["yoink", "__preview", "--", "src/lib.rs", "q", "12"]),
+        (FieldNumber(Some(12)), FieldNumber(None))
+    );
+    assert!(
+        Cli::try_parse_from(["yoink", "__preview", "--", "src/lib.rs", "q", "twelve"]).is_err()
+    );
+}
+
+#[test]
+fn roots_come_from_trailing_arguments_or_a_repeated_global_flag() {
+    let roots = |args: &[&str]| {
+        let cli = Cli::try_parse_from(args).expect("parse");
+        cli.search_roots()
+            .into_iter()
+            .map(PathBuf::from)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        roots(&["yoink", "needle", "../other"]),
+        [PathBuf::from("../other")]
+    );
+    assert_eq!(
+        roots(&["yoink", "needle", "api", "web"]),
+        [PathBuf::from("api"), PathBuf::from("web")]
+    );
+    assert_eq!(
+        roots(&["yoink", "--root", "api", "--root", "web", "needle"]),
+        [PathBuf::from("api"), PathBuf::from("web")]
+    );
+    assert_eq!(
+        roots(&["yoink", "__search", "--root", "/work", "--", "q"]),
+        [PathBuf::from("/work")]
+    );
+    assert_eq!(
+        roots(&["yoink", "__preview", "--root", "/work", "--", "a.rs", "q"]),
+        [PathBuf::from("/work")]
+    );
+    assert!(roots(&["yoink", "needle"]).is_empty());
+    assert!(Cli::try_parse_from(["yoink", "needle", "a", "--root", "b"]).is_err());
+}
+
+/// The query of a search `args` parse to, failing if they ran a command instead.
+fn searched(args: &[&str]) -> Option<String> {
+    let cli = Cli::try_parse_from(args).expect("parse");
+    assert!(cli.internal.is_none(), "{args:?} ran {:?}", cli.internal);
+    cli.query
+}
+
+#[test]
+fn command_words_search_after_dashes_or_a_flag() {
+    let cli = Cli::try_parse_from(["yoink", "config", "show", "--hidden"]).expect("parse");
+    assert!(matches!(
+        cli.internal,
+        Some(InternalCommand::Config {
+            action: ConfigCommand::Show
+        })
+    ));
+    assert_eq!(cli.overrides().include_hidden, Some(true));
+    assert!(Cli::try_parse_from(["yoink", "config"]).is_err());
+
+    assert_eq!(
+        searched(&["yoink", "--", "config"]).as_deref(),
+        Some("config")
+    );
+    assert_eq!(
+        searched(&["yoink", "--hidden", "config"]).as_deref(),
+        Some("config")
+    );
+    for word in ["prefs", "completions"] {
+        assert_eq!(searched(&["yoink", "--", word]).as_deref(), Some(word));
+    }
+
+    let cli = Cli::try_parse_from(["yoink", "diff", "a", "b"]).expect("parse");
+    assert!(matches!(cli.internal, Some(InternalCommand::Diff { .. })));
+    assert_eq!(searched(&["yoink", "--", "diff"]).as_deref(), Some("diff"));
+    let cli = Cli::try_parse_from(["yoink", "--", "diff", "src"]).expect("parse");
+    assert_eq!(cli.query.as_deref(), Some("diff"));
+    assert_eq!(cli.search_roots(), [Path::new("src")]);
+
+    let cli = Cli::try_parse_from(["yoink", "report", "TODO"]).expect("parse");
+    assert!(matches!(cli.internal, Some(InternalCommand::Report { .. })));
+    assert_eq!(
+        searched(&["yoink", "--sort", "size", "report"]).as_deref(),
+        Some("report")
+    );
+    // A root named like a command is still a root.
+    let cli = Cli::try_parse_from(["yoink", "TODO", "report"]).expect("parse");
+    assert!(cli.internal.is_none());
+    assert_eq!(cli.search_roots(), [Path::new("report")]);
+
+    // `empty` takes the session flags after its name.
+    let cli = Cli::try_parse_from(["yoink", "empty", "--hidden", "--yes", "--fresh-settings"])
+        .expect("parse");
+    assert!(matches!(
+        cli.internal,
+        Some(InternalCommand::Empty { query: None })
+    ));
+    assert_eq!(cli.overrides().include_hidden, Some(true));
+    assert!(cli.yes && cli.fresh_settings);
+    assert_eq!(
+        searched(&["yoink", "--", "empty"]).as_deref(),
+        Some("empty")
+    );
+
+    let cli = Cli::try_parse_from(["yoink", "saved"]).expect("parse");
+    assert!(matches!(cli.internal, Some(InternalCommand::Saved)));
+    assert_eq!(
+        searched(&["yoink", "--", "saved"]).as_deref(),
+        Some("saved")
+    );
+    let cli = Cli::try_parse_from(["yoink", "needle", "saved"]).expect("parse");
+    assert!(cli.internal.is_none());
+    assert_eq!(cli.search_roots(), [Path::new("saved")]);
+    // After the query, a command word is a root.
+    let cli = Cli::try_parse_from(["yoink", "needle", "config"]).expect("parse");
+    assert!(cli.internal.is_none());
+    assert_eq!(cli.search_roots(), [Path::new("config")]);
+}
+
+#[test]
+fn roots_header_names_each_root_above_the_keys() {
+    assert_eq!(roots_header(FILES_HEADER, &[]), FILES_HEADER);
+    let header = roots_header(
+        FILES_HEADER,
+        &[PathBuf::from("api"), PathBuf::from("web/frontend")],
+    );
+    assert_eq!(
+        header,
+        format!("Roots: api/  web/frontend/\n{FILES_HEADER}")
+    );
+}
+
+#[test]
+fn parses_legacy_three_field_lines() {
+    assert_eq!(
+        parse_selected_line("display\tsrc/main.rs\t42"),
+        (PathBuf::from("src/main.rs"), Some(42), None)
+    );
+    assert_eq!(
+        parse_selected_line("display\tsrc\t"),
+        (PathBuf::from("src"), None, None)
+    );
+    assert_eq!(parse_selected_line(""), (PathBuf::from(""), None, None));
+}
+
+#[test]
+fn plain_preview_marks_focus_line_and_matches() {
+    let content: String = (1..=100).map(|n| format!("line {n}\n")).collect();
+    let content = content.replace("line 50\n", "line 50 \x1b[31mneedle\x1b[0m\n");
+    let re = Regex::new("needle").expect("regex");
+
+    let preview = render_plain_preview(&content, Some(50), Some(&re));
+    let lines: Vec<&str> = preview.lines().collect();
+    assert_eq!(lines.len(), 61);
+    assert_eq!(lines[0], "    20  line 20");
+    assert_eq!(lines[30], ">   50  line 50 >>needle<<");
+    assert_eq!(lines[60], "    80  line 80");
+    assert!(!preview.contains('\x1b'));
+}
+
+#[test]
+fn plain_preview_without_focus_shows_the_top_of_the_file() {
+    let content: String = (1..=400).map(|n| format!("{n}\n")).collect();
+    let preview = render_plain_preview(&content, None, None);
+    assert_eq!(preview.lines().count(), 300);
+    assert_eq!(preview.lines().next(), Some("     1  1"));
+}
+
+#[test]
+fn jump_binds_ask_for_the_row_when_pressed() {
+    let binds = jump_binds("/usr/bin/yoink");
+    assert_eq!(binds.len(), 9);
+    assert_eq!(binds[0], "alt-1:transform:/usr/bin/yoink __jump 1");
+    assert_eq!(binds[8], "alt-9:transform:/usr/bin/yoink __jump 9");
+}
+
+#[test]
+fn toggle_binds_leave_out_the_sort_key_when_streaming() {
+    let binds = toggle_binds("/usr/bin/yoink", "/usr/bin/yoink __search -- {q}", true);
+    assert_eq!(
+        binds,
+        vec![
+            "alt-h:execute-silent(/usr/bin/yoink __toggle hidden)+transform-header(/usr/bin/yoink __header)+reload:/usr/bin/yoink __search -- {q}",
+            "alt-s:execute-silent(/usr/bin/yoink __toggle sort)+transform-header(/usr/bin/yoink __header)+reload:/usr/bin/yoink __search -- {q}",
+        ]
+    );
+    let streamed = toggle_binds("/usr/bin/yoink", "/usr/bin/yoink __search -- {q}", false);
+    assert_eq!(streamed.len(), 1);
+    assert!(streamed[0].starts_with("alt-h:"));
+}
+
+#[test]
+fn pill_binds_update_the_header_before_reloading() {
+    let binds = pill_binds("/usr/bin/yoink", "/usr/bin/yoink __search -- {q}");
+    assert_eq!(
+        binds,
+        vec![
+            "alt-d:execute-silent(/usr/bin/yoink __pill --dir -- {2})+transform-header(/usr/bin/yoink __header)+reload:/usr/bin/yoink __search -- {q}",
+            "alt-x:execute-silent(/usr/bin/yoink __pill --ext -- {2})+transform-header(/usr/bin/yoink __header)+reload:/usr/bin/yoink __search -- {q}",
+            "alt-bspace:execute-silent(/usr/bin/yoink __pill --pop)+transform-header(/usr/bin/yoink __header)+reload:/usr/bin/yoink __search -- {q}",
+        ]
+    );
+}
+
+#[test]
+fn occurrence_cursor_steps_and_wraps_within_a_file() {
+    let path = Path::new("src/lib.rs");
+    let matches = [3, 10, 42];
+
+    assert_eq!(occurrence_index(None, path, None, &matches), Some(0));
+    let next = step_occurrence(None, path, None, &matches, true).expect("cursor");
+    assert_eq!(next.index, 1);
+    let next = step_occurrence(Some(&next), path, None, &matches, true).expect("cursor");
+    let wrapped = step_occurrence(Some(&next), path, None, &matches, true).expect("cursor");
+    assert_eq!(wrapped.index, 0);
+    let back = step_occurrence(Some(&wrapped), path, None, &matches, false).expect("cursor");
+    assert_eq!(back.index, 2);
+    assert_eq!(occurrence_index(Some(&back), path, None, &matches), Some(2));
+
+    assert_eq!(step_occurrence(None, path, None, &[], true), None);
+    assert_eq!(occurrence_index(None, path, None, &[]), None);
+}
+
+#[test]
+fn occurrence_cursor_resets_when_the_selection_changes() {
+    let matches = [3, 10, 42];
+    let cursor = OccurrenceCursor {
+        path: PathBuf::from("src/lib.rs"),
+        line: None,
+        index: 2,
+    };
+
+    // Another file, or a line row of the same file, starts from its own match.
+    assert_eq!(
+        occurrence_index(Some(&cursor), Path::new("src/main.rs"), None, &matches),
+        Some(0)
+    );
+    assert_eq!(
+        occurrence_index(Some(&cursor), Path::new("src/lib.rs"), Some(10), &matches),
+        Some(1)
+    );
+    let stepped = step_occurrence(
+        Some(&cursor),
+        Path::new("src/lib.rs"),
+        Some(10),
+        &matches,
+        true,
+    )
+    .expect("cursor");
+    assert_eq!(stepped.index, 2);
+    assert_eq!(stepped.line, Some(10));
+
+    // A row whose line isn't among the matches keeps its own focus.
+    assert_eq!(
+        occurrence_index(None, Path::new("src/lib.rs"), Some(7), &matches),
+        None
+    );
+
+    // A stale index from a longer match list is clamped.
+    assert_eq!(
+        occurrence_index(Some(&cursor), Path::new("src/lib.rs"), None, &[5]),
+        Some(0)
+    );
+}
+
+#[test]
+fn occurrence_header_counts_from_one() {
+    assert_eq!(occurrence_header(6, 60, false), "match 7/60");
+    assert_eq!(occurrence_header(0, 1000, true), "match 1/1000+");
+}
+
+#[test]
+fn alias_header_lists_other_paths() {
+    assert_eq!(alias_header(&[]), None);
+    assert_eq!(
+        alias_header(&[PathBuf::from("src/a.rs"), PathBuf::from("vendor/a.rs")]).as_deref(),
+        Some("also at: src/a.rs, vendor/a.rs")
+    );
+}
+
+#[test]
+fn shell_quote_leaves_plain_words_and_quotes_the_rest() {
+    assert_eq!(shell_quote("/usr/bin/yoink"), "/usr/bin/yoink");
+    assert_eq!(shell_quote("/opt/my tools/yoink"), "'/opt/my tools/yoink'");
+    assert_eq!(shell_quote("it's"), "'it'\\''s'");
+    assert_eq!(shell_quote("$(id)"), "'$(id)'");
+    assert_eq!(shell_quote(""), "''");
+}
+
+#[test]
+fn templates_end_our_flags_before_fzf_placeholders() {
+    assert_eq!(
+        preview_command("yoink"),
+        "yoink __preview -- {2} {q} {3} {4}"
+    );
+    assert_eq!(
+        reload_command("yoink", SessionSource::Files),
+        "yoink __search -- {q}"
+    );
+    assert_eq!(
+        reload_command("yoink", SessionSource::Empty),
+        "yoink __search --empty -- {q}"
+    );
+    assert_eq!(
+        reload_command("yoink", SessionSource::Buffer(Path::new("/tmp/a b.txt"))),
+        "yoink __search --buffer '/tmp/a b.txt' -- {q}"
+    );
+}
+
+/// Expands a template the way fzf does, single-quoting each placeholder.
+fn fzf_expand(template: &str, query: &str) -> String {
+    let quote = |value: &str| format!("'{}'", value.replace('\'', "'\\''"));
+    template
+        .replace("{q}", &quote(query))
+        .replace("{2}", &quote("-rf.txt"))
+        .replace("{3}", &quote(""))
+        .replace("{4}", &quote(""))
+}
+
+#[test]
+fn adversarial_queries_reach_yoink_as_one_argument() {
+    let dir = tempdir().expect("tempdir");
+    let bin_dir = dir.path().join("my bin");
+    fs::create_dir(&bin_dir).expect("mkdir");
+    let exe = bin_dir.join("yoink");
+    fs::write(
+        &exe,
+        "#!/bin/sh\nfor arg in \"$@\"; do printf '%s\\n' \"$arg\"; done\n",
+    )
+    .expect("write stub");
+    fs::set_permissions(&exe, fs::Permissions::from_mode(0o755)).expect("chmod");
+    let marker = dir.path().join("pwned");
+    let exe = shell_quote(&exe.to_string_lossy());
+
+    for query in [
+        format!("$(touch {})", marker.display()),
+        format!("`touch {}`", marker.display()),
+        format!("'; touch {}; '", marker.display()),
+        "--version".to_string(),
+    ] {
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(fzf_expand(
+                &reload_command(&exe, SessionSource::Files),
+                &query,
+            ))
+            .output()
+            .expect("run reload");
+        let args = String::from_utf8_lossy(&output.stdout).into_owned();
+        assert_eq!(args, format!("__search\n--\n{query}\n"));
+
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(fzf_expand(&preview_command(&exe), &query))
+            .output()
+            .expect("run preview");
+        let args = String::from_utf8_lossy(&output.stdout).into_owned();
+        assert_eq!(args, format!("__preview\n--\n-rf.txt\n{query}\n\n\n"));
+    }
+    assert!(!marker.exists());
+}
+
+#[test]
+fn binary_summary_names_the_format_and_size() {
+    assert_eq!(
+        binary_summary("logo.png", b"\x89PNG\r\n\x1a\n\0\0", 2048),
+        "logo.png: PNG image, 2.0 KiB"
+    );
+    assert_eq!(
+        binary_summary("lib/app.so", b"\x7FELF\x02\x01", 3 * 1024 * 1024 / 2),
+        "lib/app.so: ELF binary, 1.5 MiB"
+    );
+    assert_eq!(
+        binary_summary("blob.bin", b"\0\x01\x02", 12),
+        "blob.bin: binary data, 12 B"
+    );
+}
+
+#[test]
+fn session_outcomes_map_to_exit_codes() {
+    assert_eq!(outcome_exit_code(SessionOutcome::Done), None);
+    assert_eq!(outcome_exit_code(SessionOutcome::NoSelection), Some(1));
+    assert_eq!(
+        outcome_exit_code(SessionOutcome::RootGone),
+        Some(ROOT_GONE_EXIT_CODE)
+    );
+}
+
+/// Runs `yoink ARGS` from inside `root` after deleting it, as an fzf reload
+/// would once the directory disappeared mid-session.
+fn run_in_removed_root(root: &Path, runtime: &Path, session_id: &str, args: &[&str]) -> String {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg("cd \"$1\" && rmdir \"$1\" && shift && exec \"$@\"")
+        .arg("sh")
+        .arg(root)
+        .arg(env!("CARGO_BIN_EXE_yoink"))
+        .args(args)
+        .env("XDG_RUNTIME_DIR", runtime)
+        .env(SESSION_ENV, session_id)
+        .output()
+        .expect("run yoink");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).expect("utf8 output")
+}
+
+#[test]
+fn reloads_in_a_removed_root_list_one_notice_row() {
+    let runtime = tempdir().expect("runtime dir");
+    let parent = tempdir().expect("parent");
+    let root = parent.path().join("project");
+    fs::create_dir(&root).expect("create root");
+    assert_eq!(root_gone_outcome(&root), None);
+
+    let session = Session::new(runtime.path().join("yoink"), new_session_id());
+    let state = SessionState {
+        root: Some(root.clone()),
+        ..SessionState::default()
+    };
+    session.store(&state).expect("store session");
+
+    let listing = run_in_removed_root(
+        &root,
+        runtime.path(),
+        session.id(),
+        &["__search", "--", "main"],
+    );
+    assert_eq!(listing, format_search_entries(&[root_gone_entry(&root)]));
+    assert!(listing.contains(&format!("search root no longer exists: {}", root.display())));
+    assert_eq!(listing.lines().count(), 1);
+    assert!(listing.split('\t').nth(1).is_some_and(str::is_empty));
+
+    fs::create_dir(&root).expect("recreate root");
+    let preview = run_in_removed_root(
+        &root,
+        runtime.path(),
+        session.id(),
+        &["__preview", "--", "main.rs", "main"],
+    );
+    assert_eq!(preview.trim_end(), root_gone_message(&root));
+
+    assert_eq!(root_gone_outcome(&root), Some(SessionOutcome::RootGone));
+    session.remove();
+}
+
+#[test]
+fn files_header_names_a_narrowed_scope() {
+    assert_eq!(files_header(SearchScope::Both), FILES_HEADER);
+    assert!(files_header(SearchScope::Paths).starts_with("[paths only]"));
+    assert!(files_header(SearchScope::Paths).ends_with(FILES_HEADER));
+    assert!(files_header(SearchScope::Content).starts_with("[content only]"));
+}
+
+#[test]
+fn settings_header_marks_non_default_walk_settings() {
+    let mut settings = YoinkSettings::default();
+    assert_eq!(settings_header(FILES_HEADER, &settings), FILES_HEADER);
+
+    settings.include_hidden = true;
+    settings.include_mounts = true;
+    settings.sort_mode = SortMode::Modified;
+    assert_eq!(
+        settings_header(FILES_HEADER, &settings),
+        format!("[hidden] [mounts] [sort: modified]  |  {FILES_HEADER}")
+    );
+    settings.include_symlinks = true;
+    assert!(settings_header("base", &settings).starts_with("[hidden] [follow] [mounts]"));
+
+    let git = YoinkSettings {
+        tracked_only: true,
+        changed_ref: Some("main".to_string()),
+        ..YoinkSettings::default()
+    };
+    assert_eq!(
+        settings_header("base", &git),
+        "[tracked] [changed: main]  |  base"
+    );
+    let rev = YoinkSettings {
+        rev: Some("v1.2.0".to_string()),
+        ..YoinkSettings::default()
+    };
+    assert_eq!(settings_header("base", &rev), "[rev: v1.2.0]  |  base");
+}